@@ -1,3 +1,5 @@
+use eyre::Result;
+
 /// How to process retired entries
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum RetiredOptions {
@@ -21,3 +23,18 @@ impl RetiredOptions {
         }
     }
 }
+
+/// Read the contents of a source file,
+/// which can be either a local path or a URL.
+pub fn read_source(src: &str) -> Result<String> {
+    if src.starts_with("http:") || src.starts_with("https:") {
+        // read from URL
+        println!("Downloading {} ...", src);
+        let resp = ureq::get(src).call()?;
+        Ok(resp.into_string()?)
+    } else {
+        // read from File
+        println!("Reading from file {}", src);
+        Ok(std::fs::read_to_string(src)?)
+    }
+}
@@ -7,6 +7,7 @@
 //!
 //! - **`data-element`** or **`tags`**: DICOM data element dictionary
 //! - **`uid`** or **`uids`**: DICOM unique identifiers dictionary
+//! - **`modules`** or **`iods`**: DICOM module and IOD definitions
 //!
 //! It will automatically retrieve dictionary specifications
 //! from a credible source and output the result as a Rust code file
@@ -18,6 +19,7 @@
 use clap::{Parser, Subcommand};
 
 mod common;
+mod modules;
 mod tags;
 mod uids;
 
@@ -34,6 +36,8 @@ enum BuilderSubcommand {
     DataElement(tags::DataElementApp),
     #[clap(name("uids"))]
     Uid(uids::UidApp),
+    #[clap(name("modules"))]
+    Modules(modules::ModulesApp),
 }
 
 fn main() {
@@ -44,6 +48,9 @@ fn main() {
         App {
             command: BuilderSubcommand::Uid(app),
         } => uids::run(app),
+        App {
+            command: BuilderSubcommand::Modules(app),
+        } => modules::run(app),
     }
     .unwrap()
 }
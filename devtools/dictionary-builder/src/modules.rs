@@ -0,0 +1,794 @@
+//! Dictionary builder for module and IOD definitions.
+//!
+//! Collects the composite IODs described in [PS3.3 annex A][1],
+//! the attributes of the modules which make them up,
+//! the SOP classes of each IOD from [PS3.4 table B.5-1][2],
+//! and the value multiplicity of each attribute from [PS3.6 table 6-1][3].
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/chapter_A.html
+//! [2]: https://dicom.nema.org/medical/dicom/current/output/chtml/part04/sect_B.5.html#table_B.5-1
+//! [3]: https://dicom.nema.org/medical/dicom/current/output/chtml/part06/chapter_6.html#table_6-1
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{create_dir_all, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use clap::Parser;
+use eyre::{Context, ContextCompat, Result};
+use heck::ToShoutySnakeCase;
+use regex::Regex;
+use sxd_document::dom::{Document, Element};
+use sxd_document::parser;
+
+use crate::common::read_source;
+
+/// URL to DICOM standard Part 3 in XML
+const DEFAULT_LOCATION: &str =
+    "https://dicom.nema.org/medical/dicom/current/source/docbook/part03/part03.xml";
+
+/// URL to DICOM standard Part 4 in XML
+const DEFAULT_SOP_CLASS_LOCATION: &str =
+    "https://dicom.nema.org/medical/dicom/current/source/docbook/part04/part04.xml";
+
+/// URL to DICOM standard Part 6 in XML
+const DEFAULT_DATA_ELEMENT_LOCATION: &str =
+    "https://dicom.nema.org/medical/dicom/current/source/docbook/part06/part06.xml";
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Fetch and build a dictionary of DICOM modules and IODs
+#[derive(Debug, Parser)]
+#[clap(name = "modules", alias = "iods")]
+pub struct ModulesApp {
+    /// Path or URL to the XML file containing the IOD and module definitions
+    #[clap(default_value(DEFAULT_LOCATION))]
+    from: String,
+
+    /// Path or URL to the XML file containing the SOP class table
+    #[clap(long, default_value(DEFAULT_SOP_CLASS_LOCATION))]
+    sop_classes: String,
+
+    /// Path or URL to the XML file containing the data element table
+    #[clap(long, default_value(DEFAULT_DATA_ELEMENT_LOCATION))]
+    data_elements: String,
+
+    /// The output file
+    #[clap(short('o'), default_value("modules.rs"))]
+    output: String,
+}
+
+pub fn run(app: ModulesApp) -> Result<()> {
+    let ModulesApp {
+        from,
+        sop_classes,
+        data_elements,
+        output,
+    } = app;
+
+    let data_elements = read_source(&data_elements)?;
+    let data_elements = retrieve_data_elements(&data_elements)?;
+
+    let sop_classes = read_source(&sop_classes)?;
+    let sop_classes = retrieve_sop_classes(&sop_classes)?;
+
+    let part03 = read_source(&from)?;
+    let dictionary = retrieve_iods(&part03, &sop_classes, &data_elements)?;
+
+    to_code_file(output, &dictionary)?;
+
+    Ok(())
+}
+
+/// The keyword and value multiplicity of a data element, from PS3.6.
+struct DataElement {
+    keyword: String,
+    vm: String,
+}
+
+/// A SOP class, from PS3.4.
+#[derive(Debug, Clone)]
+struct SopClass {
+    uid: String,
+    name: String,
+}
+
+/// An attribute of a module or sequence item, from PS3.3.
+#[derive(Debug)]
+struct Attribute {
+    group: u16,
+    element: u16,
+    keyword: String,
+    r#type: &'static str,
+    vm: String,
+    enumerated_values: Vec<String>,
+    defined_terms: Vec<String>,
+    items: Vec<Attribute>,
+}
+
+struct Module {
+    name: String,
+    constant: String,
+    attributes: Vec<Attribute>,
+}
+
+struct Iod {
+    name: String,
+    sop_classes: Vec<SopClass>,
+    /// constant name of the module and its usage
+    modules: Vec<(String, &'static str)>,
+}
+
+struct Dictionary {
+    modules: Vec<Module>,
+    iods: Vec<Iod>,
+}
+
+/// Collects data element keywords and multiplicities from PS3.6 table 6-1,
+/// keyed by tag in the form `GGGGEEEE`.
+fn retrieve_data_elements(xml_data: &str) -> Result<HashMap<String, DataElement>> {
+    let xml = parser::parse(xml_data)?;
+    let doc = xml.as_document();
+    let table = find_table_by_label(&doc, "6-1").context("No data element table found")?;
+
+    let mut entries = HashMap::new();
+    for row in table_rows(table) {
+        let cells = children_named(row, "td");
+        if cells.len() < 5 {
+            continue;
+        }
+        let tag = text_of(cells[0]).replace(['(', ')', ','], "");
+        let keyword = text_of(cells[2]);
+        let vm = text_of(cells[4]);
+        if tag.len() != 8 || keyword.is_empty() {
+            continue;
+        }
+        entries.insert(tag.to_uppercase(), DataElement { keyword, vm });
+    }
+
+    println!("Retrieved {} data elements", entries.len());
+    Ok(entries)
+}
+
+/// Collects the SOP classes of PS3.4 table B.5-1,
+/// keyed by the identifier of the IOD section they refer to.
+fn retrieve_sop_classes(xml_data: &str) -> Result<HashMap<String, Vec<SopClass>>> {
+    let xml = parser::parse(xml_data)?;
+    let doc = xml.as_document();
+    let table = find_table_by_label(&doc, "B.5-1").context("No SOP class table found")?;
+
+    let mut sop_classes: HashMap<String, Vec<SopClass>> = HashMap::new();
+    let mut count = 0;
+    for row in table_rows(table) {
+        let cells = children_named(row, "td");
+        if cells.len() < 3 {
+            continue;
+        }
+        let name = text_of(cells[0]);
+        let uid = text_of(cells[1]);
+        let Some(target) = link_target(cells[2]) else {
+            continue;
+        };
+        sop_classes
+            .entry(target)
+            .or_default()
+            .push(SopClass { uid, name });
+        count += 1;
+    }
+
+    println!("Retrieved {} SOP classes", count);
+    Ok(sop_classes)
+}
+
+/// Collects the IODs and their modules from PS3.3.
+fn retrieve_iods(
+    xml_data: &str,
+    sop_classes: &HashMap<String, Vec<SopClass>>,
+    data_elements: &HashMap<String, DataElement>,
+) -> Result<Dictionary> {
+    let xml = parser::parse(xml_data)?;
+    let doc = xml.as_document();
+
+    // index sections and tables by their identifier
+    let mut by_id = HashMap::new();
+    for elem in descendants(
+        doc.root()
+            .children()
+            .into_iter()
+            .filter_map(|c| c.element()),
+    ) {
+        if let Some(id) = elem.attribute_value((XML_NAMESPACE, "id")) {
+            by_id.insert(id.to_string(), elem);
+        }
+    }
+
+    let regex_tag = Regex::new(r"^\(([0-9A-F]{4}),([0-9A-F]{4})\)$")?;
+    let parser = TableParser {
+        by_id: &by_id,
+        data_elements,
+        regex_tag,
+    };
+
+    // module section identifier → module constant name
+    let mut module_constants: HashMap<String, String> = HashMap::new();
+    let mut used_constants = HashSet::new();
+    let mut modules = vec![];
+    let mut iods = vec![];
+
+    let mut sections: Vec<_> = sop_classes.keys().collect();
+    sections.sort();
+    for section_id in sections {
+        let Some(section) = by_id.get(section_id.as_str()) else {
+            eprintln!("IOD section {} not found", section_id);
+            continue;
+        };
+        let Some(table) = descendants(std::iter::once(*section))
+            .find(|e| is_named(*e, "table") && caption_of(*e).ends_with("IOD Modules"))
+        else {
+            eprintln!("No module table in IOD section {}", section_id);
+            continue;
+        };
+        let name = caption_of(table)
+            .trim_end_matches("IOD Modules")
+            .trim()
+            .to_string();
+
+        let mut iod_modules = vec![];
+        for row in table_rows(table) {
+            let cells = children_named(row, "td");
+            // the information entity column spans several rows
+            if cells.len() < 3 {
+                continue;
+            }
+            let cells = &cells[cells.len() - 3..];
+            let usage = match text_of(cells[2]).chars().next() {
+                Some('M') => "Mandatory",
+                Some('C') => "Conditional",
+                Some('U') => "UserOption",
+                _ => continue,
+            };
+            let Some(module_id) = link_target(cells[1]) else {
+                continue;
+            };
+
+            if let Some(constant) = module_constants.get(&module_id) {
+                iod_modules.push((constant.clone(), usage));
+                continue;
+            }
+
+            let Some(module) = parser.parse_module(&module_id)? else {
+                eprintln!("No attribute table in module section {}", module_id);
+                continue;
+            };
+            let mut constant = module.0.to_shouty_snake_case();
+            if !used_constants.insert(constant.clone()) {
+                constant = format!("{}_{}", constant, module_id.to_shouty_snake_case());
+                used_constants.insert(constant.clone());
+            }
+            module_constants.insert(module_id, constant.clone());
+            iod_modules.push((constant.clone(), usage));
+            modules.push(Module {
+                name: module.0,
+                constant,
+                attributes: module.1,
+            });
+        }
+
+        iods.push(Iod {
+            name,
+            sop_classes: sop_classes[section_id].clone(),
+            modules: iod_modules,
+        });
+    }
+
+    iods.sort_by(|a, b| a.name.cmp(&b.name));
+
+    println!(
+        "Retrieved {} IODs and {} modules",
+        iods.len(),
+        modules.len()
+    );
+    Ok(Dictionary { modules, iods })
+}
+
+/// Parses module attribute tables,
+/// resolving the macros included in them.
+struct TableParser<'a, 'd> {
+    by_id: &'a HashMap<String, Element<'d>>,
+    data_elements: &'a HashMap<String, DataElement>,
+    regex_tag: Regex,
+}
+
+impl<'d> TableParser<'_, 'd> {
+    /// Parse the attribute table of the module in the given section,
+    /// returning the module name and its attributes.
+    fn parse_module(&self, section_id: &str) -> Result<Option<(String, Vec<Attribute>)>> {
+        let Some(section) = self.by_id.get(section_id) else {
+            return Ok(None);
+        };
+        let Some(table) = descendants(std::iter::once(*section)).find(|e| is_named(*e, "table"))
+        else {
+            return Ok(None);
+        };
+        let name = caption_of(table)
+            .trim_end_matches("Attributes")
+            .trim()
+            .trim_end_matches("Module")
+            .trim()
+            .to_string();
+
+        let mut rows = vec![];
+        self.collect_rows(table, 0, &mut rows, &mut HashSet::new())?;
+        Ok(Some((name, nest(&mut rows.into_iter().peekable(), 0))))
+    }
+
+    /// Collect the attributes of a table in document order,
+    /// paired with their nesting depth.
+    fn collect_rows(
+        &self,
+        table: Element<'d>,
+        depth: usize,
+        out: &mut Vec<(usize, Attribute)>,
+        visiting: &mut HashSet<String>,
+    ) -> Result<()> {
+        for row in table_rows(table) {
+            let cells = children_named(row, "td");
+            let Some(first) = cells.first() else {
+                continue;
+            };
+            let name = text_of(*first);
+            let level = depth + name.chars().take_while(|c| *c == '>').count();
+
+            if cells.len() < 3 {
+                // "Include Table ..." rows
+                if !name.trim_start_matches('>').trim().starts_with("Include") {
+                    continue;
+                }
+                let Some(target) = link_target(*first) else {
+                    continue;
+                };
+                let Some(macro_table) = self.by_id.get(&target) else {
+                    eprintln!("Included table {} not found", target);
+                    continue;
+                };
+                // guard against macros including themselves
+                if visiting.insert(target.clone()) {
+                    self.collect_rows(*macro_table, level, out, visiting)?;
+                    visiting.remove(&target);
+                }
+                continue;
+            }
+
+            let tag = text_of(cells[1]);
+            let Some(cap) = self.regex_tag.captures(&tag) else {
+                continue;
+            };
+            let r#type = match text_of(cells[2]).as_str() {
+                "1" => "Type1",
+                "1C" => "Type1C",
+                "2" => "Type2",
+                "2C" => "Type2C",
+                "3" => "Type3",
+                _ => continue,
+            };
+            let Some(data_element) = self.data_elements.get(&format!("{}{}", &cap[1], &cap[2]))
+            else {
+                eprintln!("Unknown data element {}", tag);
+                continue;
+            };
+
+            let (enumerated_values, defined_terms) = cells
+                .get(3)
+                .map(|description| terms_of(*description))
+                .unwrap_or_default();
+
+            out.push((
+                level,
+                Attribute {
+                    group: u16::from_str_radix(&cap[1], 16)?,
+                    element: u16::from_str_radix(&cap[2], 16)?,
+                    keyword: data_element.keyword.clone(),
+                    r#type,
+                    vm: data_element.vm.clone(),
+                    enumerated_values,
+                    defined_terms,
+                    items: vec![],
+                },
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Turn a flat list of attributes with nesting depths
+/// into a tree of attributes and their sequence items.
+fn nest(
+    rows: &mut std::iter::Peekable<impl Iterator<Item = (usize, Attribute)>>,
+    depth: usize,
+) -> Vec<Attribute> {
+    let mut attributes: Vec<Attribute> = vec![];
+    while let Some((level, _)) = rows.peek() {
+        if *level < depth {
+            break;
+        }
+        if *level > depth {
+            let items = nest(rows, depth + 1);
+            match attributes.last_mut() {
+                Some(parent) => parent.items.extend(items),
+                None => attributes.extend(items),
+            }
+            continue;
+        }
+        let (_, attribute) = rows.next().unwrap();
+        attributes.push(attribute);
+    }
+    attributes
+}
+
+/// Collect the enumerated values and defined terms
+/// listed in an attribute description.
+///
+/// Lists which only apply to a specific value of the attribute
+/// (such as "Enumerated Values for Value 1") are not collected.
+fn terms_of(description: Element) -> (Vec<String>, Vec<String>) {
+    let mut enumerated_values = vec![];
+    let mut defined_terms = vec![];
+    for list in descendants(std::iter::once(description)).filter(|e| is_named(*e, "variablelist")) {
+        let title = children_named(list, "title")
+            .first()
+            .map(|t| text_of(*t))
+            .unwrap_or_default();
+        let terms = match title.trim_end_matches(':') {
+            "Enumerated Values" => &mut enumerated_values,
+            "Defined Terms" => &mut defined_terms,
+            _ => continue,
+        };
+        for entry in children_named(list, "varlistentry") {
+            for term in children_named(entry, "term") {
+                terms.push(normalize_term(&text_of(term)));
+            }
+        }
+    }
+    (enumerated_values, defined_terms)
+}
+
+/// Turn hexadecimal terms such as `0001H` into decimal numbers,
+/// as they would be read from a binary value.
+fn normalize_term(term: &str) -> String {
+    term.strip_suffix('H')
+        .filter(|hex| !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| term.to_string())
+}
+
+/// Translate a value multiplicity such as `1-n` into Rust code.
+fn vm_code(vm: &str) -> String {
+    let vm = vm.split_whitespace().next().unwrap_or("1");
+    match vm.split_once('-') {
+        None => format!("Vm::exactly({})", vm),
+        Some((min, max)) => match max.strip_suffix('n') {
+            Some("") => format!("Vm::unbounded({}, 1)", min),
+            Some(step) => format!("Vm::unbounded({}, {})", min, step),
+            None => format!("Vm::range({}, {})", min, max),
+        },
+    }
+}
+
+fn find_table_by_label<'d>(doc: &Document<'d>, label: &str) -> Option<Element<'d>> {
+    descendants(
+        doc.root()
+            .children()
+            .into_iter()
+            .filter_map(|c| c.element()),
+    )
+    .find(|e| is_named(*e, "table") && e.attribute_value("label") == Some(label))
+}
+
+/// Iterate over the given elements and all of their descendant elements,
+/// in document order.
+fn descendants<'d>(
+    roots: impl IntoIterator<Item = Element<'d>>,
+) -> impl Iterator<Item = Element<'d>> {
+    let mut stack: Vec<_> = roots.into_iter().collect();
+    stack.reverse();
+    std::iter::from_fn(move || {
+        let elem = stack.pop()?;
+        stack.extend(
+            elem.children()
+                .into_iter()
+                .rev()
+                .filter_map(|c| c.element()),
+        );
+        Some(elem)
+    })
+}
+
+fn is_named(elem: Element, name: &str) -> bool {
+    elem.name().local_part() == name
+}
+
+fn children_named<'d>(elem: Element<'d>, name: &str) -> Vec<Element<'d>> {
+    elem.children()
+        .into_iter()
+        .filter_map(|c| c.element())
+        .filter(|e| is_named(*e, name))
+        .collect()
+}
+
+fn table_rows(table: Element) -> Vec<Element> {
+    children_named(table, "tbody")
+        .into_iter()
+        .flat_map(|body| children_named(body, "tr"))
+        .collect()
+}
+
+fn caption_of(table: Element) -> String {
+    children_named(table, "caption")
+        .first()
+        .map(|c| text_of(*c))
+        .unwrap_or_default()
+}
+
+/// The identifier of the first section or table linked from an element,
+/// either within the same document or in another part of the standard.
+fn link_target(elem: Element) -> Option<String> {
+    descendants(std::iter::once(elem)).find_map(|e| {
+        e.attribute_value("linkend")
+            .or_else(|| e.attribute_value("targetptr"))
+            .map(|target| target.to_string())
+    })
+}
+
+/// The text content of an element,
+/// with whitespace collapsed and zero width spaces removed.
+fn text_of(elem: Element) -> String {
+    fn collect(elem: Element, out: &mut String) {
+        for child in elem.children() {
+            if let Some(text) = child.text() {
+                out.push_str(text.text());
+            } else if let Some(child) = child.element() {
+                collect(child, out);
+            }
+        }
+    }
+    let mut text = String::new();
+    collect(elem, &mut text);
+    text.replace('\u{200b}', "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Write the module and IOD dictionary as Rust code.
+fn to_code_file<P>(dest_path: P, dictionary: &Dictionary) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    if let Some(p_dir) = dest_path.as_ref().parent() {
+        create_dir_all(p_dir)?;
+    }
+    let mut f = BufWriter::new(File::create(&dest_path)?);
+    write_code(&mut f, dictionary).context("Failed to write file")
+}
+
+fn write_code(f: &mut impl Write, dictionary: &Dictionary) -> Result<()> {
+    f.write_all(b"//! Module and IOD declarations\n")?;
+    f.write_all(b"//!\n//! Collected from DICOM PS3.3 and PS3.4.\n")?;
+    f.write_all(b"// Automatically generated. Edit at your own risk.\n")?;
+    f.write_all(
+        b"\nuse crate::iod::{\n    \
+        AttributeDefinition, AttributeType::*, IodDefinition, IodModule, ModuleDefinition,\n    \
+        ModuleUsage::*, ValueMultiplicity as Vm,\n};\n\
+        use dicom_core::Tag;\n\ntype A = AttributeDefinition;\n",
+    )?;
+
+    for module in &dictionary.modules {
+        writeln!(f, "\n/// {} Module", module.name)?;
+        writeln!(f, "#[rustfmt::skip]")?;
+        writeln!(
+            f,
+            "pub static {}: ModuleDefinition = ModuleDefinition {{",
+            module.constant
+        )?;
+        writeln!(f, "    name: {:?},", module.name)?;
+        writeln!(f, "    attributes: &[")?;
+        write_attributes(f, &module.attributes, 2)?;
+        writeln!(f, "    ],\n}};")?;
+    }
+
+    writeln!(f, "\n#[rustfmt::skip]")?;
+    writeln!(f, "pub(crate) static IODS: &[IodDefinition] = &[")?;
+    for iod in &dictionary.iods {
+        writeln!(f, "    IodDefinition {{")?;
+        writeln!(f, "        name: {:?},", iod.name)?;
+        writeln!(f, "        sop_classes: &[")?;
+        for sop_class in &iod.sop_classes {
+            writeln!(f, "            {:?}, // {}", sop_class.uid, sop_class.name)?;
+        }
+        writeln!(f, "        ],")?;
+        writeln!(f, "        modules: &[")?;
+        for (module, usage) in &iod.modules {
+            writeln!(
+                f,
+                "            IodModule {{ module: &{}, usage: {} }},",
+                module, usage
+            )?;
+        }
+        writeln!(f, "        ],")?;
+        writeln!(f, "    }},")?;
+    }
+    writeln!(f, "];")?;
+
+    Ok(())
+}
+
+fn write_attributes(f: &mut impl Write, attributes: &[Attribute], indent: usize) -> Result<()> {
+    let pad = "    ".repeat(indent);
+    for attribute in attributes {
+        write!(
+            f,
+            "{}A::new(Tag(0x{:04X}, 0x{:04X}), {}, {})",
+            pad,
+            attribute.group,
+            attribute.element,
+            attribute.r#type,
+            vm_code(&attribute.vm)
+        )?;
+        if !attribute.enumerated_values.is_empty() {
+            write!(
+                f,
+                ".with_enumerated_values(&{:?})",
+                attribute.enumerated_values
+            )?;
+        }
+        if !attribute.defined_terms.is_empty() {
+            write!(f, ".with_defined_terms(&{:?})", attribute.defined_terms)?;
+        }
+        if attribute.items.is_empty() {
+            writeln!(f, ", // {}", attribute.keyword)?;
+        } else {
+            writeln!(f, ".with_items(&[ // {}", attribute.keyword)?;
+            write_attributes(f, &attribute.items, indent + 1)?;
+            writeln!(f, "{}]),", pad)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PART03: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<book xmlns="http://docbook.org/ns/docbook" xmlns:xl="http://www.w3.org/1999/xlink">
+<section xml:id="sect_A.1"><title>Test Image IOD</title>
+  <table xml:id="table_A.1-1"><caption>Test Image IOD Modules</caption>
+    <tbody>
+      <tr><td rowspan="2"><para>Patient</para></td><td><para>Patient</para></td>
+        <td><para><xref linkend="sect_C.1"/></para></td><td><para>M</para></td></tr>
+      <tr><td><para>Test Image</para></td>
+        <td><para><xref linkend="sect_C.2"/></para></td><td><para>U</para></td></tr>
+    </tbody>
+  </table>
+</section>
+<section xml:id="sect_C.1"><title>Patient Module</title>
+  <table xml:id="table_C.1-1"><caption>Patient Module Attributes</caption>
+    <tbody>
+      <tr><td><para>Patient's Name</para></td><td><para>(0010,0010)</para></td>
+        <td><para>2</para></td><td><para>Name.</para></td></tr>
+      <tr><td><para>Patient's Sex</para></td><td><para>(0010,0040)</para></td>
+        <td><para>2</para></td><td><variablelist><title>Enumerated Values:</title>
+          <varlistentry><term>M</term></varlistentry>
+          <varlistentry><term>F</term></varlistentry></variablelist></td></tr>
+      <tr><td><para>Referenced Patient Sequence</para></td><td><para>(0008,1120)</para></td>
+        <td><para>3</para></td><td><para>References.</para></td></tr>
+      <tr><td colspan="4"><para>&gt;Include <xref linkend="table_10-11"/></para></td></tr>
+    </tbody>
+  </table>
+</section>
+<section xml:id="sect_C.2"><title>Test Image Module</title>
+  <table xml:id="table_C.2-1"><caption>Test Image Module Attributes</caption>
+    <tbody>
+      <tr><td><para>Pixel Representation</para></td><td><para>(0028,0103)</para></td>
+        <td><para>1</para></td><td><variablelist><title>Enumerated Values:</title>
+          <varlistentry><term>0000H</term></varlistentry>
+          <varlistentry><term>0001H</term></varlistentry></variablelist></td></tr>
+      <tr><td><para>Image Type</para></td><td><para>(0008,0008)</para></td>
+        <td><para>1</para></td><td><variablelist><title>Enumerated Values for Value 1:</title>
+          <varlistentry><term>ORIGINAL</term></varlistentry></variablelist></td></tr>
+    </tbody>
+  </table>
+</section>
+<table xml:id="table_10-11"><caption>SOP Instance Reference Macro Attributes</caption>
+  <tbody>
+    <tr><td><para>Referenced SOP Class UID</para></td><td><para>(0008,1150)</para></td>
+      <td><para>1</para></td><td><para>Class.</para></td></tr>
+    <tr><td><para>Referenced SOP Instance UID</para></td><td><para>(0008,1155)</para></td>
+      <td><para>1</para></td><td><para>Instance.</para></td></tr>
+  </tbody>
+</table>
+</book>"#;
+
+    const PART04: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<book xmlns="http://docbook.org/ns/docbook" xmlns:xl="http://www.w3.org/1999/xlink">
+<table label="B.5-1"><tbody>
+  <tr><td><para>Test Image Storage</para></td><td><para>1.2.3.4</para></td>
+    <td><para><olink targetdoc="PS3.3" targetptr="sect_A.1"/></para></td></tr>
+</tbody></table>
+</book>"#;
+
+    const PART06: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<book xmlns="http://docbook.org/ns/docbook" xmlns:xl="http://www.w3.org/1999/xlink">
+<table label="6-1"><tbody>
+  <tr><td><para>(0008,0008)</para></td><td><para>Image Type</para></td>
+    <td><para>ImageType</para></td><td><para>CS</para></td><td><para>2-n</para></td></tr>
+  <tr><td><para>(0008,1120)</para></td><td><para>Referenced Patient Sequence</para></td>
+    <td><para>Referenced&#8203;Patient&#8203;Sequence</para></td><td><para>SQ</para></td><td><para>1</para></td></tr>
+  <tr><td><para>(0008,1150)</para></td><td><para>Referenced SOP Class UID</para></td>
+    <td><para>ReferencedSOPClassUID</para></td><td><para>UI</para></td><td><para>1</para></td></tr>
+  <tr><td><para>(0008,1155)</para></td><td><para>Referenced SOP Instance UID</para></td>
+    <td><para>ReferencedSOPInstanceUID</para></td><td><para>UI</para></td><td><para>1</para></td></tr>
+  <tr><td><para>(0010,0010)</para></td><td><para>Patient's Name</para></td>
+    <td><para>PatientName</para></td><td><para>PN</para></td><td><para>1</para></td></tr>
+  <tr><td><para>(0010,0040)</para></td><td><para>Patient's Sex</para></td>
+    <td><para>PatientSex</para></td><td><para>CS</para></td><td><para>1</para></td></tr>
+  <tr><td><para>(0028,0103)</para></td><td><para>Pixel Representation</para></td>
+    <td><para>PixelRepresentation</para></td><td><para>US</para></td><td><para>1</para></td></tr>
+</tbody></table>
+</book>"#;
+
+    #[test]
+    fn vm_to_code() {
+        assert_eq!(vm_code("1"), "Vm::exactly(1)");
+        assert_eq!(vm_code("1-3"), "Vm::range(1, 3)");
+        assert_eq!(vm_code("1-n"), "Vm::unbounded(1, 1)");
+        assert_eq!(vm_code("2-2n"), "Vm::unbounded(2, 2)");
+        assert_eq!(normalize_term("0001H"), "1");
+        assert_eq!(normalize_term("HH"), "HH");
+    }
+
+    #[test]
+    fn build_modules_and_iods() {
+        let data_elements = retrieve_data_elements(PART06).unwrap();
+        let sop_classes = retrieve_sop_classes(PART04).unwrap();
+        let dictionary = retrieve_iods(PART03, &sop_classes, &data_elements).unwrap();
+
+        let mut code = vec![];
+        write_code(&mut code, &dictionary).unwrap();
+        let code = String::from_utf8(code).unwrap();
+
+        assert!(code.contains(
+            "pub static PATIENT: ModuleDefinition = ModuleDefinition {
+    name: \"Patient\",
+    attributes: &[
+        A::new(Tag(0x0010, 0x0010), Type2, Vm::exactly(1)), // PatientName
+        A::new(Tag(0x0010, 0x0040), Type2, Vm::exactly(1)).with_enumerated_values(&[\"M\", \"F\"]), // PatientSex
+        A::new(Tag(0x0008, 0x1120), Type3, Vm::exactly(1)).with_items(&[ // ReferencedPatientSequence
+            A::new(Tag(0x0008, 0x1150), Type1, Vm::exactly(1)), // ReferencedSOPClassUID
+            A::new(Tag(0x0008, 0x1155), Type1, Vm::exactly(1)), // ReferencedSOPInstanceUID
+        ]),
+    ],
+};"
+        ));
+        assert!(code.contains(
+            "        A::new(Tag(0x0028, 0x0103), Type1, Vm::exactly(1)).with_enumerated_values(&[\"0\", \"1\"]), // PixelRepresentation
+        A::new(Tag(0x0008, 0x0008), Type1, Vm::unbounded(2, 1)), // ImageType
+"
+        ));
+        assert!(code.contains(
+            "    IodDefinition {
+        name: \"Test Image\",
+        sop_classes: &[
+            \"1.2.3.4\", // Test Image Storage
+        ],
+        modules: &[
+            IodModule { module: &PATIENT, usage: Mandatory },
+            IodModule { module: &TEST_IMAGE, usage: UserOption },
+        ],
+    },"
+        ));
+    }
+}
@@ -17,7 +17,7 @@ use heck::ToShoutySnakeCase;
 use sxd_document::parser;
 use sxd_xpath::{Factory, Value};
 
-use crate::common::RetiredOptions;
+use crate::common::{read_source, RetiredOptions};
 
 /// URL to DICOM standard Part 6 in XML
 const DEFAULT_LOCATION: &str =
@@ -62,16 +62,7 @@ pub fn run(app: UidApp) -> Result<()> {
 
     let retired_options = RetiredOptions::from_flags(ignore_retired, deprecate_retired);
 
-    let xml_data = if src.starts_with("http:") || src.starts_with("https:") {
-        // read from URL
-        println!("Downloading DICOM dictionary ...");
-        let resp = ureq::get(&src).call()?;
-        resp.into_string()?
-    } else {
-        // read from File
-        println!("Reading from file {}", src);
-        std::fs::read_to_string(src)?
-    };
+    let xml_data = read_source(&src)?;
 
     // collect all UID values
 
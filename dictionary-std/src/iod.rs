@@ -0,0 +1,296 @@
+//! Information object definition (IOD) dictionary implementation
+//!
+//! The definitions in this module describe
+//! which modules make up each composite IOD
+//! and which attributes make up each module,
+//! as specified in [DICOM PS3.3].
+//! They are meant for checking the attributes of a DICOM object
+//! against its IOD.
+//!
+//! Only the Secondary Capture Image, CT Image, MR Image, US Image
+//! and a few Structured Report IODs are currently defined (see [`modules`](crate::modules)).
+//!
+//! [DICOM PS3.3]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/ps3.3.html
+
+use std::collections::HashMap;
+use std::fmt;
+
+use dicom_core::Tag;
+use once_cell::sync::Lazy;
+
+use crate::modules::IODS;
+
+static DICT: Lazy<HashMap<&'static str, &'static IodDefinition>> = Lazy::new(init_dictionary);
+
+/// Retrieve the definition of the IOD
+/// which applies to instances of the given SOP class.
+///
+/// Trailing null characters in `sop_class_uid` are ignored.
+/// Returns `None` for SOP classes whose IOD is not defined yet.
+pub fn iod_for_sop_class(sop_class_uid: &str) -> Option<&'static IodDefinition> {
+    DICT.get(sop_class_uid.trim_end_matches('\0')).copied()
+}
+
+/// Retrieve all known IOD definitions.
+#[inline]
+pub fn iods() -> &'static [IodDefinition] {
+    IODS
+}
+
+fn init_dictionary() -> HashMap<&'static str, &'static IodDefinition> {
+    IODS.iter()
+        .flat_map(|iod| iod.sop_classes.iter().map(move |uid| (*uid, iod)))
+        .collect()
+}
+
+/// The type of an attribute in a module,
+/// which determines whether it must be present and have a value.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub enum AttributeType {
+    /// Type 1: required, with a value
+    Type1,
+    /// Type 1C: required under some condition, with a value
+    Type1C,
+    /// Type 2: required, but may be empty
+    Type2,
+    /// Type 2C: required under some condition, but may be empty
+    Type2C,
+    /// Type 3: optional
+    Type3,
+}
+
+impl AttributeType {
+    /// Whether this attribute type demands the attribute
+    /// to be present unconditionally.
+    pub fn is_required(self) -> bool {
+        matches!(self, AttributeType::Type1 | AttributeType::Type2)
+    }
+
+    /// Whether this attribute type demands the attribute
+    /// to have a value when present.
+    pub fn needs_value(self) -> bool {
+        matches!(self, AttributeType::Type1 | AttributeType::Type1C)
+    }
+}
+
+impl fmt::Display for AttributeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AttributeType::Type1 => "1",
+            AttributeType::Type1C => "1C",
+            AttributeType::Type2 => "2",
+            AttributeType::Type2C => "2C",
+            AttributeType::Type3 => "3",
+        })
+    }
+}
+
+/// The value multiplicity (VM) of an attribute,
+/// such as `1`, `1-3` or `2-2n`.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub struct ValueMultiplicity {
+    /// the minimum number of values
+    pub min: u32,
+    /// the maximum number of values, if bounded
+    pub max: Option<u32>,
+    /// the number of values must be a multiple of this number
+    pub step: u32,
+}
+
+impl ValueMultiplicity {
+    /// Exactly `n` values.
+    pub const fn exactly(n: u32) -> Self {
+        ValueMultiplicity {
+            min: n,
+            max: Some(n),
+            step: 1,
+        }
+    }
+
+    /// Between `min` and `max` values, inclusive.
+    pub const fn range(min: u32, max: u32) -> Self {
+        ValueMultiplicity {
+            min,
+            max: Some(max),
+            step: 1,
+        }
+    }
+
+    /// At least `min` values, in multiples of `step` (as in `2-2n`).
+    pub const fn unbounded(min: u32, step: u32) -> Self {
+        ValueMultiplicity {
+            min,
+            max: None,
+            step,
+        }
+    }
+
+    /// Check whether the given number of values
+    /// satisfies this value multiplicity.
+    pub fn contains(self, count: u32) -> bool {
+        count >= self.min && self.max.map_or(true, |max| count <= max) && count % self.step == 0
+    }
+}
+
+impl fmt::Display for ValueMultiplicity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", max),
+            Some(max) => write!(f, "{}-{}", self.min, max),
+            None if self.step == 1 => write!(f, "{}-n", self.min),
+            None => write!(f, "{}-{}n", self.min, self.step),
+        }
+    }
+}
+
+/// The definition of an attribute in a module
+/// or in a sequence item of another attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeDefinition {
+    /// the attribute tag
+    pub tag: Tag,
+    /// the attribute type
+    pub r#type: AttributeType,
+    /// the value multiplicity of the attribute
+    pub vm: ValueMultiplicity,
+    /// the enumerated values of the attribute,
+    /// empty if the attribute has none
+    pub enumerated_values: &'static [&'static str],
+    /// the defined terms of the attribute,
+    /// empty if the attribute has none
+    pub defined_terms: &'static [&'static str],
+    /// the attributes expected in each item,
+    /// if the attribute is a sequence
+    pub items: &'static [AttributeDefinition],
+}
+
+impl AttributeDefinition {
+    /// Create an attribute definition
+    /// with no enumerated values, defined terms, or sequence items.
+    pub const fn new(tag: Tag, r#type: AttributeType, vm: ValueMultiplicity) -> Self {
+        AttributeDefinition {
+            tag,
+            r#type,
+            vm,
+            enumerated_values: &[],
+            defined_terms: &[],
+            items: &[],
+        }
+    }
+
+    /// Set the enumerated values of the attribute.
+    pub const fn with_enumerated_values(mut self, values: &'static [&'static str]) -> Self {
+        self.enumerated_values = values;
+        self
+    }
+
+    /// Set the defined terms of the attribute.
+    pub const fn with_defined_terms(mut self, terms: &'static [&'static str]) -> Self {
+        self.defined_terms = terms;
+        self
+    }
+
+    /// Set the attributes of each sequence item.
+    pub const fn with_items(mut self, items: &'static [AttributeDefinition]) -> Self {
+        self.items = items;
+        self
+    }
+}
+
+/// The definition of a module:
+/// a named set of related attributes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleDefinition {
+    /// the module name, such as `"Patient"`
+    pub name: &'static str,
+    /// the attributes of the module
+    pub attributes: &'static [AttributeDefinition],
+}
+
+/// How a module is used in an IOD.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub enum ModuleUsage {
+    /// M: the module is mandatory
+    Mandatory,
+    /// C: the module is required under some condition
+    Conditional,
+    /// U: the module is optional
+    UserOption,
+}
+
+impl fmt::Display for ModuleUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ModuleUsage::Mandatory => "M",
+            ModuleUsage::Conditional => "C",
+            ModuleUsage::UserOption => "U",
+        })
+    }
+}
+
+/// A reference to a module in an IOD.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IodModule {
+    /// the module
+    pub module: &'static ModuleDefinition,
+    /// the usage of the module in the IOD
+    pub usage: ModuleUsage,
+}
+
+/// The definition of a composite information object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IodDefinition {
+    /// the IOD name, such as `"CT Image"`
+    pub name: &'static str,
+    /// the UIDs of the SOP classes whose instances follow this IOD
+    pub sop_classes: &'static [&'static str],
+    /// the modules of the IOD
+    pub modules: &'static [IodModule],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_multiplicity_contains() {
+        assert!(ValueMultiplicity::exactly(1).contains(1));
+        assert!(!ValueMultiplicity::exactly(1).contains(2));
+        assert!(ValueMultiplicity::range(1, 3).contains(3));
+        assert!(!ValueMultiplicity::range(1, 3).contains(4));
+        assert!(ValueMultiplicity::unbounded(2, 2).contains(4));
+        assert!(!ValueMultiplicity::unbounded(2, 2).contains(3));
+        assert!(!ValueMultiplicity::unbounded(1, 1).contains(0));
+
+        assert_eq!(ValueMultiplicity::exactly(6).to_string(), "6");
+        assert_eq!(ValueMultiplicity::range(1, 3).to_string(), "1-3");
+        assert_eq!(ValueMultiplicity::unbounded(1, 1).to_string(), "1-n");
+        assert_eq!(ValueMultiplicity::unbounded(2, 2).to_string(), "2-2n");
+    }
+
+    #[test]
+    fn iods_by_sop_class() {
+        let iod = iod_for_sop_class(crate::uids::CT_IMAGE_STORAGE).unwrap();
+        assert_eq!(iod.name, "CT Image");
+        assert!(iod
+            .modules
+            .iter()
+            .any(|m| m.module.name == "Patient" && m.usage == ModuleUsage::Mandatory));
+
+        let iod = iod_for_sop_class("1.2.840.10008.5.1.4.1.1.7\0").unwrap();
+        assert_eq!(iod.name, "Secondary Capture Image");
+
+        let iod = iod_for_sop_class(crate::uids::MR_IMAGE_STORAGE).unwrap();
+        assert_eq!(iod.name, "MR Image");
+        let iod = iod_for_sop_class(crate::uids::ULTRASOUND_IMAGE_STORAGE).unwrap();
+        assert_eq!(iod.name, "US Image");
+        let iod = iod_for_sop_class(crate::uids::COMPREHENSIVE_SR_STORAGE).unwrap();
+        assert_eq!(iod.name, "Comprehensive SR");
+        assert!(iod
+            .modules
+            .iter()
+            .any(|m| m.module.name == "SR Document Content" && m.usage == ModuleUsage::Mandatory));
+
+        assert!(iod_for_sop_class("1.2.3.4").is_none());
+    }
+}
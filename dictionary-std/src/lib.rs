@@ -12,6 +12,15 @@
 //! - `sop_class` (requires Cargo feature **sop-class**):
 //!   Contains information about DICOM Service-Object Pair (SOP) classes
 //!   and their respective unique identifiers.
+//! - [`iod`]: Contains information object definitions (IODs)
+//!   of composite SOP classes,
+//!   describing their modules and the attributes in each module.
+//!   The records in this dictionary are collected from [DICOM PS3.3],
+//!   and currently cover only a few IODs.
+//! - [`private`]: Provides a dictionary of private attributes
+//!   keyed by private creator,
+//!   which is not filled in by default
+//!   but can be loaded from DCMTK or GDCM private dictionary files.
 //!
 //! The records in these dictionaries are typically collected
 //! from [DICOM PS3.6] directly,
@@ -20,6 +29,7 @@
 //! behind a unit type for efficiency and ease of use.
 //!
 //! [DICOM PS3.6]: https://dicom.nema.org/medical/dicom/current/output/chtml/part06/ps3.6.html
+//! [DICOM PS3.3]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/ps3.3.html
 //!
 //! ## Constants
 //!
@@ -29,7 +39,11 @@
 //!
 //! - [`tags`], which map an attribute alias to a DICOM tag
 //! - [`uids`], for various normative DICOM unique identifiers
+//! - [`modules`], for the module definitions of the IOD dictionary
 pub mod data_element;
+pub mod iod;
+pub mod modules;
+pub mod private;
 
 #[cfg(feature = "sop-class")]
 pub mod sop_class;
@@ -37,6 +51,7 @@ pub mod tags;
 pub mod uids;
 
 pub use data_element::{StandardDataDictionary, StandardDataDictionaryRegistry};
+pub use private::PrivateDataDictionary;
 #[cfg(feature = "sop-class")]
 pub use sop_class::StandardSopClassDictionary;
 
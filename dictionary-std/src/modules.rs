@@ -0,0 +1,677 @@
+//! Module and IOD declarations
+//!
+//! **Note:** this is a hand-written selection,
+//! not the output of `dicom-dictionary-builder modules`.
+//! It only covers the Secondary Capture Image, CT Image, MR Image,
+//! US Image, and Basic Text, Enhanced and Comprehensive SR IODs,
+//! transcribed from DICOM PS3.3 and PS3.4,
+//! so no other SOP class has an IOD definition yet.
+//! It is to be replaced by the output of the builder
+//! run against the full standard.
+
+use crate::iod::{
+    AttributeDefinition, AttributeType::*, IodDefinition, IodModule, ModuleDefinition,
+    ModuleUsage::*, ValueMultiplicity as Vm,
+};
+use dicom_core::Tag;
+
+type A = AttributeDefinition;
+
+/// Patient Module
+#[rustfmt::skip]
+pub static PATIENT: ModuleDefinition = ModuleDefinition {
+    name: "Patient",
+    attributes: &[
+        A::new(Tag(0x0010, 0x0010), Type2, Vm::exactly(1)), // PatientName
+        A::new(Tag(0x0010, 0x0020), Type2, Vm::exactly(1)), // PatientID
+        A::new(Tag(0x0010, 0x0021), Type3, Vm::exactly(1)), // IssuerOfPatientID
+        A::new(Tag(0x0010, 0x0030), Type2, Vm::exactly(1)), // PatientBirthDate
+        A::new(Tag(0x0010, 0x0040), Type2, Vm::exactly(1)).with_enumerated_values(&["M", "F", "O"]), // PatientSex
+        A::new(Tag(0x0008, 0x1120), Type3, Vm::exactly(1)).with_items(&[ // ReferencedPatientSequence
+            A::new(Tag(0x0008, 0x1150), Type1, Vm::exactly(1)), // ReferencedSOPClassUID
+            A::new(Tag(0x0008, 0x1155), Type1, Vm::exactly(1)), // ReferencedSOPInstanceUID
+        ]),
+        A::new(Tag(0x0010, 0x1002), Type3, Vm::exactly(1)).with_items(&[ // OtherPatientIDsSequence
+            A::new(Tag(0x0010, 0x0020), Type1, Vm::exactly(1)), // PatientID
+            A::new(Tag(0x0010, 0x0021), Type3, Vm::exactly(1)), // IssuerOfPatientID
+            A::new(Tag(0x0010, 0x0022), Type1, Vm::exactly(1)).with_defined_terms(&["TEXT", "RFID", "BARCODE"]), // TypeOfPatientID
+        ]),
+        A::new(Tag(0x0010, 0x4000), Type3, Vm::exactly(1)), // PatientComments
+        A::new(Tag(0x0012, 0x0062), Type3, Vm::exactly(1)).with_enumerated_values(&["YES", "NO"]), // PatientIdentityRemoved
+        A::new(Tag(0x0012, 0x0063), Type1C, Vm::unbounded(1, 1)), // DeidentificationMethod
+        A::new(Tag(0x0012, 0x0064), Type1C, Vm::exactly(1)).with_items(&[ // DeidentificationMethodCodeSequence
+            A::new(Tag(0x0008, 0x0100), Type1C, Vm::exactly(1)), // CodeValue
+            A::new(Tag(0x0008, 0x0102), Type1C, Vm::exactly(1)), // CodingSchemeDesignator
+            A::new(Tag(0x0008, 0x0103), Type1C, Vm::exactly(1)), // CodingSchemeVersion
+            A::new(Tag(0x0008, 0x0104), Type1, Vm::exactly(1)), // CodeMeaning
+        ]),
+    ],
+};
+
+/// General Study Module
+#[rustfmt::skip]
+pub static GENERAL_STUDY: ModuleDefinition = ModuleDefinition {
+    name: "General Study",
+    attributes: &[
+        A::new(Tag(0x0020, 0x000D), Type1, Vm::exactly(1)), // StudyInstanceUID
+        A::new(Tag(0x0008, 0x0020), Type2, Vm::exactly(1)), // StudyDate
+        A::new(Tag(0x0008, 0x0030), Type2, Vm::exactly(1)), // StudyTime
+        A::new(Tag(0x0008, 0x0090), Type2, Vm::exactly(1)), // ReferringPhysicianName
+        A::new(Tag(0x0020, 0x0010), Type2, Vm::exactly(1)), // StudyID
+        A::new(Tag(0x0008, 0x0050), Type2, Vm::exactly(1)), // AccessionNumber
+        A::new(Tag(0x0008, 0x1030), Type3, Vm::exactly(1)), // StudyDescription
+        A::new(Tag(0x0008, 0x1048), Type3, Vm::unbounded(1, 1)), // PhysiciansOfRecord
+        A::new(Tag(0x0008, 0x1060), Type3, Vm::unbounded(1, 1)), // NameOfPhysiciansReadingStudy
+        A::new(Tag(0x0008, 0x1110), Type3, Vm::exactly(1)).with_items(&[ // ReferencedStudySequence
+            A::new(Tag(0x0008, 0x1150), Type1, Vm::exactly(1)), // ReferencedSOPClassUID
+            A::new(Tag(0x0008, 0x1155), Type1, Vm::exactly(1)), // ReferencedSOPInstanceUID
+        ]),
+    ],
+};
+
+/// Patient Study Module
+#[rustfmt::skip]
+pub static PATIENT_STUDY: ModuleDefinition = ModuleDefinition {
+    name: "Patient Study",
+    attributes: &[
+        A::new(Tag(0x0008, 0x1080), Type3, Vm::unbounded(1, 1)), // AdmittingDiagnosesDescription
+        A::new(Tag(0x0010, 0x1010), Type3, Vm::exactly(1)), // PatientAge
+        A::new(Tag(0x0010, 0x1020), Type3, Vm::exactly(1)), // PatientSize
+        A::new(Tag(0x0010, 0x1030), Type3, Vm::exactly(1)), // PatientWeight
+        A::new(Tag(0x0010, 0x2180), Type3, Vm::exactly(1)), // Occupation
+        A::new(Tag(0x0010, 0x21B0), Type3, Vm::exactly(1)), // AdditionalPatientHistory
+    ],
+};
+
+/// General Series Module
+#[rustfmt::skip]
+pub static GENERAL_SERIES: ModuleDefinition = ModuleDefinition {
+    name: "General Series",
+    attributes: &[
+        A::new(Tag(0x0008, 0x0060), Type1, Vm::exactly(1)).with_defined_terms(&["ANN", "AR", "ASMT", "AU", "BDUS", "BI", "BMD", "CFM", "CR", "CT", "CTPROTOCOL", "DMS", "DG", "DOC", "DX", "ECG", "EEG", "EMG", "EOG", "EPS", "ES", "FID", "GM", "HC", "HD", "IO", "IOL", "IVOCT", "IVUS", "KER", "KO", "LEN", "LS", "MG", "MR", "M3D", "NM", "OAM", "OCT", "OP", "OPM", "OPT", "OPTBSV", "OPTENF", "OPV", "OSS", "OT", "PA", "PLAN", "POS", "PR", "PT", "PX", "REG", "RESP", "RF", "RG", "RTDOSE", "RTIMAGE", "RTINTENT", "RTPLAN", "RTRAD", "RTRECORD", "RTSEGANN", "RTSTRUCT", "RWV", "SEG", "SM", "SMR", "SR", "SRF", "STAIN", "TEXTUREMAP", "TG", "US", "VA", "XA", "XAPROTOCOL", "XC"]), // Modality
+        A::new(Tag(0x0020, 0x000E), Type1, Vm::exactly(1)), // SeriesInstanceUID
+        A::new(Tag(0x0020, 0x0011), Type2, Vm::exactly(1)), // SeriesNumber
+        A::new(Tag(0x0020, 0x0060), Type2C, Vm::exactly(1)).with_enumerated_values(&["R", "L"]), // Laterality
+        A::new(Tag(0x0008, 0x0021), Type3, Vm::exactly(1)), // SeriesDate
+        A::new(Tag(0x0008, 0x0031), Type3, Vm::exactly(1)), // SeriesTime
+        A::new(Tag(0x0008, 0x1050), Type3, Vm::unbounded(1, 1)), // PerformingPhysicianName
+        A::new(Tag(0x0018, 0x1030), Type3, Vm::exactly(1)), // ProtocolName
+        A::new(Tag(0x0008, 0x103E), Type3, Vm::exactly(1)), // SeriesDescription
+        A::new(Tag(0x0008, 0x1070), Type3, Vm::unbounded(1, 1)), // OperatorsName
+        A::new(Tag(0x0008, 0x1111), Type3, Vm::exactly(1)).with_items(&[ // ReferencedPerformedProcedureStepSequence
+            A::new(Tag(0x0008, 0x1150), Type1, Vm::exactly(1)), // ReferencedSOPClassUID
+            A::new(Tag(0x0008, 0x1155), Type1, Vm::exactly(1)), // ReferencedSOPInstanceUID
+        ]),
+        A::new(Tag(0x0018, 0x0015), Type3, Vm::exactly(1)), // BodyPartExamined
+        A::new(Tag(0x0018, 0x5100), Type2C, Vm::exactly(1)).with_defined_terms(&["HFP", "HFS", "HFDR", "HFDL", "FFDR", "FFDL", "FFP", "FFS", "LFP", "LFS", "RFP", "RFS", "AFDR", "AFDL", "PFDR", "PFDL"]), // PatientPosition
+        A::new(Tag(0x0028, 0x0108), Type3, Vm::exactly(1)), // SmallestPixelValueInSeries
+        A::new(Tag(0x0028, 0x0109), Type3, Vm::exactly(1)), // LargestPixelValueInSeries
+    ],
+};
+
+/// Frame of Reference Module
+#[rustfmt::skip]
+pub static FRAME_OF_REFERENCE: ModuleDefinition = ModuleDefinition {
+    name: "Frame of Reference",
+    attributes: &[
+        A::new(Tag(0x0020, 0x0052), Type1, Vm::exactly(1)), // FrameOfReferenceUID
+        A::new(Tag(0x0020, 0x1040), Type2, Vm::exactly(1)), // PositionReferenceIndicator
+    ],
+};
+
+/// General Equipment Module
+#[rustfmt::skip]
+pub static GENERAL_EQUIPMENT: ModuleDefinition = ModuleDefinition {
+    name: "General Equipment",
+    attributes: &[
+        A::new(Tag(0x0008, 0x0070), Type2, Vm::exactly(1)), // Manufacturer
+        A::new(Tag(0x0008, 0x0080), Type3, Vm::exactly(1)), // InstitutionName
+        A::new(Tag(0x0008, 0x0081), Type3, Vm::exactly(1)), // InstitutionAddress
+        A::new(Tag(0x0008, 0x1010), Type3, Vm::exactly(1)), // StationName
+        A::new(Tag(0x0008, 0x1040), Type3, Vm::exactly(1)), // InstitutionalDepartmentName
+        A::new(Tag(0x0008, 0x1090), Type3, Vm::exactly(1)), // ManufacturerModelName
+        A::new(Tag(0x0018, 0x1000), Type3, Vm::exactly(1)), // DeviceSerialNumber
+        A::new(Tag(0x0018, 0x1020), Type3, Vm::unbounded(1, 1)), // SoftwareVersions
+        A::new(Tag(0x0018, 0x1050), Type3, Vm::exactly(1)), // SpatialResolution
+        A::new(Tag(0x0018, 0x1200), Type3, Vm::unbounded(1, 1)), // DateOfLastCalibration
+        A::new(Tag(0x0018, 0x1201), Type3, Vm::unbounded(1, 1)), // TimeOfLastCalibration
+        A::new(Tag(0x0028, 0x0120), Type3, Vm::exactly(1)), // PixelPaddingValue
+    ],
+};
+
+/// SC Equipment Module
+#[rustfmt::skip]
+pub static SC_EQUIPMENT: ModuleDefinition = ModuleDefinition {
+    name: "SC Equipment",
+    attributes: &[
+        A::new(Tag(0x0008, 0x0064), Type1, Vm::exactly(1)).with_defined_terms(&["DV", "DI", "DF", "WSD", "SD", "SI", "DRW", "SYN"]), // ConversionType
+        A::new(Tag(0x0008, 0x0060), Type3, Vm::exactly(1)), // Modality
+        A::new(Tag(0x0018, 0x1010), Type3, Vm::exactly(1)), // SecondaryCaptureDeviceID
+        A::new(Tag(0x0018, 0x1016), Type3, Vm::exactly(1)), // SecondaryCaptureDeviceManufacturer
+        A::new(Tag(0x0018, 0x1018), Type3, Vm::exactly(1)), // SecondaryCaptureDeviceManufacturerModelName
+        A::new(Tag(0x0018, 0x1019), Type3, Vm::unbounded(1, 1)), // SecondaryCaptureDeviceSoftwareVersions
+        A::new(Tag(0x0018, 0x1022), Type3, Vm::exactly(1)), // VideoImageFormatAcquired
+        A::new(Tag(0x0018, 0x1023), Type3, Vm::exactly(1)), // DigitalImageFormatAcquired
+    ],
+};
+
+/// General Image Module
+#[rustfmt::skip]
+pub static GENERAL_IMAGE: ModuleDefinition = ModuleDefinition {
+    name: "General Image",
+    attributes: &[
+        A::new(Tag(0x0020, 0x0013), Type2, Vm::exactly(1)), // InstanceNumber
+        A::new(Tag(0x0020, 0x0020), Type2C, Vm::exactly(2)), // PatientOrientation
+        A::new(Tag(0x0008, 0x0023), Type2C, Vm::exactly(1)), // ContentDate
+        A::new(Tag(0x0008, 0x0033), Type2C, Vm::exactly(1)), // ContentTime
+        A::new(Tag(0x0008, 0x0008), Type3, Vm::unbounded(2, 1)), // ImageType
+        A::new(Tag(0x0020, 0x0012), Type3, Vm::exactly(1)), // AcquisitionNumber
+        A::new(Tag(0x0008, 0x0022), Type3, Vm::exactly(1)), // AcquisitionDate
+        A::new(Tag(0x0008, 0x0032), Type3, Vm::exactly(1)), // AcquisitionTime
+        A::new(Tag(0x0008, 0x002A), Type3, Vm::exactly(1)), // AcquisitionDateTime
+        A::new(Tag(0x0008, 0x1140), Type3, Vm::exactly(1)).with_items(&[ // ReferencedImageSequence
+            A::new(Tag(0x0008, 0x1150), Type1, Vm::exactly(1)), // ReferencedSOPClassUID
+            A::new(Tag(0x0008, 0x1155), Type1, Vm::exactly(1)), // ReferencedSOPInstanceUID
+            A::new(Tag(0x0008, 0x1160), Type1C, Vm::unbounded(1, 1)), // ReferencedFrameNumber
+        ]),
+        A::new(Tag(0x0008, 0x2111), Type3, Vm::exactly(1)), // DerivationDescription
+        A::new(Tag(0x0020, 0x1002), Type3, Vm::exactly(1)), // ImagesInAcquisition
+        A::new(Tag(0x0020, 0x4000), Type3, Vm::exactly(1)), // ImageComments
+        A::new(Tag(0x0028, 0x0300), Type3, Vm::exactly(1)).with_enumerated_values(&["YES", "NO"]), // QualityControlImage
+        A::new(Tag(0x0028, 0x0301), Type3, Vm::exactly(1)).with_enumerated_values(&["YES", "NO"]), // BurnedInAnnotation
+        A::new(Tag(0x0028, 0x0302), Type3, Vm::exactly(1)).with_enumerated_values(&["YES", "NO"]), // RecognizableVisualFeatures
+        A::new(Tag(0x0028, 0x2110), Type3, Vm::exactly(1)).with_enumerated_values(&["00", "01"]), // LossyImageCompression
+        A::new(Tag(0x0028, 0x2112), Type3, Vm::unbounded(1, 1)), // LossyImageCompressionRatio
+        A::new(Tag(0x0028, 0x2114), Type3, Vm::unbounded(1, 1)), // LossyImageCompressionMethod
+        A::new(Tag(0x2050, 0x0020), Type3, Vm::exactly(1)).with_enumerated_values(&["IDENTITY", "INVERSE"]), // PresentationLUTShape
+    ],
+};
+
+/// Image Plane Module
+#[rustfmt::skip]
+pub static IMAGE_PLANE: ModuleDefinition = ModuleDefinition {
+    name: "Image Plane",
+    attributes: &[
+        A::new(Tag(0x0028, 0x0030), Type1, Vm::exactly(2)), // PixelSpacing
+        A::new(Tag(0x0020, 0x0037), Type1, Vm::exactly(6)), // ImageOrientationPatient
+        A::new(Tag(0x0020, 0x0032), Type1, Vm::exactly(3)), // ImagePositionPatient
+        A::new(Tag(0x0018, 0x0050), Type2, Vm::exactly(1)), // SliceThickness
+        A::new(Tag(0x0018, 0x0088), Type3, Vm::exactly(1)), // SpacingBetweenSlices
+        A::new(Tag(0x0020, 0x1041), Type3, Vm::exactly(1)), // SliceLocation
+    ],
+};
+
+/// Image Pixel Module
+#[rustfmt::skip]
+pub static IMAGE_PIXEL: ModuleDefinition = ModuleDefinition {
+    name: "Image Pixel",
+    attributes: &[
+        A::new(Tag(0x0028, 0x0002), Type1, Vm::exactly(1)), // SamplesPerPixel
+        A::new(Tag(0x0028, 0x0004), Type1, Vm::exactly(1)).with_defined_terms(&["MONOCHROME1", "MONOCHROME2", "PALETTE COLOR", "RGB", "YBR_FULL", "YBR_FULL_422", "YBR_PARTIAL_420", "YBR_ICT", "YBR_RCT", "XYB"]), // PhotometricInterpretation
+        A::new(Tag(0x0028, 0x0010), Type1, Vm::exactly(1)), // Rows
+        A::new(Tag(0x0028, 0x0011), Type1, Vm::exactly(1)), // Columns
+        A::new(Tag(0x0028, 0x0100), Type1, Vm::exactly(1)), // BitsAllocated
+        A::new(Tag(0x0028, 0x0101), Type1, Vm::exactly(1)), // BitsStored
+        A::new(Tag(0x0028, 0x0102), Type1, Vm::exactly(1)), // HighBit
+        A::new(Tag(0x0028, 0x0103), Type1, Vm::exactly(1)).with_enumerated_values(&["0", "1"]), // PixelRepresentation
+        A::new(Tag(0x7FE0, 0x0010), Type1C, Vm::exactly(1)), // PixelData
+        A::new(Tag(0x0028, 0x0006), Type1C, Vm::exactly(1)).with_enumerated_values(&["0", "1"]), // PlanarConfiguration
+        A::new(Tag(0x0028, 0x0034), Type1C, Vm::exactly(2)), // PixelAspectRatio
+        A::new(Tag(0x0028, 0x0106), Type3, Vm::exactly(1)), // SmallestImagePixelValue
+        A::new(Tag(0x0028, 0x0107), Type3, Vm::exactly(1)), // LargestImagePixelValue
+        A::new(Tag(0x0028, 0x1101), Type1C, Vm::exactly(3)), // RedPaletteColorLookupTableDescriptor
+        A::new(Tag(0x0028, 0x1102), Type1C, Vm::exactly(3)), // GreenPaletteColorLookupTableDescriptor
+        A::new(Tag(0x0028, 0x1103), Type1C, Vm::exactly(3)), // BluePaletteColorLookupTableDescriptor
+        A::new(Tag(0x0028, 0x1201), Type1C, Vm::exactly(1)), // RedPaletteColorLookupTableData
+        A::new(Tag(0x0028, 0x1202), Type1C, Vm::exactly(1)), // GreenPaletteColorLookupTableData
+        A::new(Tag(0x0028, 0x1203), Type1C, Vm::exactly(1)), // BluePaletteColorLookupTableData
+        A::new(Tag(0x0028, 0x2000), Type3, Vm::exactly(1)), // ICCProfile
+        A::new(Tag(0x0028, 0x2002), Type3, Vm::exactly(1)), // ColorSpace
+    ],
+};
+
+/// SC Image Module
+#[rustfmt::skip]
+pub static SC_IMAGE: ModuleDefinition = ModuleDefinition {
+    name: "SC Image",
+    attributes: &[
+        A::new(Tag(0x0018, 0x1012), Type3, Vm::exactly(1)), // DateOfSecondaryCapture
+        A::new(Tag(0x0018, 0x1014), Type3, Vm::exactly(1)), // TimeOfSecondaryCapture
+        A::new(Tag(0x0018, 0x2010), Type3, Vm::exactly(2)), // NominalScannedPixelSpacing
+        A::new(Tag(0x0028, 0x0A02), Type3, Vm::exactly(1)).with_enumerated_values(&["FIXED", "IMAGE"]), // PixelSpacingCalibrationType
+        A::new(Tag(0x0028, 0x0A04), Type3, Vm::exactly(1)), // PixelSpacingCalibrationDescription
+    ],
+};
+
+/// Contrast/Bolus Module
+#[rustfmt::skip]
+pub static CONTRAST_BOLUS: ModuleDefinition = ModuleDefinition {
+    name: "Contrast/Bolus",
+    attributes: &[
+        A::new(Tag(0x0018, 0x0010), Type2, Vm::exactly(1)), // ContrastBolusAgent
+        A::new(Tag(0x0018, 0x0012), Type3, Vm::exactly(1)).with_items(&[ // ContrastBolusAgentSequence
+            A::new(Tag(0x0008, 0x0100), Type1C, Vm::exactly(1)), // CodeValue
+            A::new(Tag(0x0008, 0x0102), Type1C, Vm::exactly(1)), // CodingSchemeDesignator
+            A::new(Tag(0x0008, 0x0103), Type1C, Vm::exactly(1)), // CodingSchemeVersion
+            A::new(Tag(0x0008, 0x0104), Type1, Vm::exactly(1)), // CodeMeaning
+        ]),
+        A::new(Tag(0x0018, 0x1040), Type3, Vm::exactly(1)), // ContrastBolusRoute
+        A::new(Tag(0x0018, 0x1041), Type3, Vm::exactly(1)), // ContrastBolusVolume
+        A::new(Tag(0x0018, 0x1042), Type3, Vm::exactly(1)), // ContrastBolusStartTime
+        A::new(Tag(0x0018, 0x1043), Type3, Vm::exactly(1)), // ContrastBolusStopTime
+        A::new(Tag(0x0018, 0x1044), Type3, Vm::exactly(1)), // ContrastBolusTotalDose
+        A::new(Tag(0x0018, 0x1046), Type3, Vm::unbounded(1, 1)), // ContrastFlowRate
+        A::new(Tag(0x0018, 0x1047), Type3, Vm::unbounded(1, 1)), // ContrastFlowDuration
+        A::new(Tag(0x0018, 0x1048), Type3, Vm::exactly(1)).with_defined_terms(&["IODINE", "GADOLINIUM", "CARBON DIOXIDE", "BARIUM"]), // ContrastBolusIngredient
+        A::new(Tag(0x0018, 0x1049), Type3, Vm::exactly(1)), // ContrastBolusIngredientConcentration
+    ],
+};
+
+/// CT Image Module
+#[rustfmt::skip]
+pub static CT_IMAGE: ModuleDefinition = ModuleDefinition {
+    name: "CT Image",
+    attributes: &[
+        A::new(Tag(0x0008, 0x0008), Type1, Vm::unbounded(2, 1)), // ImageType
+        A::new(Tag(0x0028, 0x0002), Type1, Vm::exactly(1)).with_enumerated_values(&["1"]), // SamplesPerPixel
+        A::new(Tag(0x0028, 0x0004), Type1, Vm::exactly(1)).with_enumerated_values(&["MONOCHROME1", "MONOCHROME2"]), // PhotometricInterpretation
+        A::new(Tag(0x0028, 0x0100), Type1, Vm::exactly(1)).with_enumerated_values(&["16"]), // BitsAllocated
+        A::new(Tag(0x0028, 0x0101), Type1, Vm::exactly(1)).with_enumerated_values(&["12", "13", "14", "15", "16"]), // BitsStored
+        A::new(Tag(0x0028, 0x0102), Type1, Vm::exactly(1)), // HighBit
+        A::new(Tag(0x0028, 0x1052), Type1, Vm::exactly(1)), // RescaleIntercept
+        A::new(Tag(0x0028, 0x1053), Type1, Vm::exactly(1)), // RescaleSlope
+        A::new(Tag(0x0028, 0x1054), Type1C, Vm::exactly(1)).with_defined_terms(&["HU", "US"]), // RescaleType
+        A::new(Tag(0x0018, 0x0060), Type2, Vm::exactly(1)), // KVP
+        A::new(Tag(0x0020, 0x0012), Type2, Vm::exactly(1)), // AcquisitionNumber
+        A::new(Tag(0x0018, 0x0022), Type3, Vm::unbounded(1, 1)), // ScanOptions
+        A::new(Tag(0x0018, 0x0090), Type3, Vm::exactly(1)), // DataCollectionDiameter
+        A::new(Tag(0x0018, 0x1100), Type3, Vm::exactly(1)), // ReconstructionDiameter
+        A::new(Tag(0x0018, 0x1110), Type3, Vm::exactly(1)), // DistanceSourceToDetector
+        A::new(Tag(0x0018, 0x1111), Type3, Vm::exactly(1)), // DistanceSourceToPatient
+        A::new(Tag(0x0018, 0x1120), Type3, Vm::exactly(1)), // GantryDetectorTilt
+        A::new(Tag(0x0018, 0x1130), Type3, Vm::exactly(1)), // TableHeight
+        A::new(Tag(0x0018, 0x1140), Type3, Vm::exactly(1)).with_enumerated_values(&["CW", "CC"]), // RotationDirection
+        A::new(Tag(0x0018, 0x1150), Type3, Vm::exactly(1)), // ExposureTime
+        A::new(Tag(0x0018, 0x1151), Type3, Vm::exactly(1)), // XRayTubeCurrent
+        A::new(Tag(0x0018, 0x1152), Type3, Vm::exactly(1)), // Exposure
+        A::new(Tag(0x0018, 0x1160), Type3, Vm::exactly(1)), // FilterType
+        A::new(Tag(0x0018, 0x1170), Type3, Vm::exactly(1)), // GeneratorPower
+        A::new(Tag(0x0018, 0x1190), Type3, Vm::unbounded(1, 1)), // FocalSpots
+        A::new(Tag(0x0018, 0x1210), Type3, Vm::unbounded(1, 1)), // ConvolutionKernel
+    ],
+};
+
+/// MR Image Module
+#[rustfmt::skip]
+pub static MR_IMAGE: ModuleDefinition = ModuleDefinition {
+    name: "MR Image",
+    attributes: &[
+        A::new(Tag(0x0008, 0x0008), Type1, Vm::unbounded(2, 1)), // ImageType
+        A::new(Tag(0x0028, 0x0002), Type1, Vm::exactly(1)).with_enumerated_values(&["1"]), // SamplesPerPixel
+        A::new(Tag(0x0028, 0x0004), Type1, Vm::exactly(1)).with_enumerated_values(&["MONOCHROME1", "MONOCHROME2"]), // PhotometricInterpretation
+        A::new(Tag(0x0028, 0x0100), Type1, Vm::exactly(1)).with_enumerated_values(&["16"]), // BitsAllocated
+        A::new(Tag(0x0028, 0x0101), Type1, Vm::exactly(1)), // BitsStored
+        A::new(Tag(0x0028, 0x0102), Type1, Vm::exactly(1)), // HighBit
+        A::new(Tag(0x0018, 0x0020), Type1, Vm::unbounded(1, 1)).with_defined_terms(&["SE", "IR", "GR", "EP", "RM"]), // ScanningSequence
+        A::new(Tag(0x0018, 0x0021), Type1, Vm::unbounded(1, 1)).with_defined_terms(&["SK", "MTC", "SS", "TRSS", "SP", "MP", "OSP", "NONE"]), // SequenceVariant
+        A::new(Tag(0x0018, 0x0022), Type2, Vm::unbounded(1, 1)).with_defined_terms(&["PER", "RG", "CG", "PPG", "FC", "PFF", "PFP", "SP", "FS"]), // ScanOptions
+        A::new(Tag(0x0018, 0x0023), Type2, Vm::exactly(1)).with_enumerated_values(&["2D", "3D"]), // MRAcquisitionType
+        A::new(Tag(0x0018, 0x0080), Type2C, Vm::exactly(1)), // RepetitionTime
+        A::new(Tag(0x0018, 0x0081), Type2, Vm::exactly(1)), // EchoTime
+        A::new(Tag(0x0018, 0x0091), Type2, Vm::exactly(1)), // EchoTrainLength
+        A::new(Tag(0x0018, 0x0082), Type2C, Vm::exactly(1)), // InversionTime
+        A::new(Tag(0x0018, 0x1060), Type2C, Vm::exactly(1)), // TriggerTime
+        A::new(Tag(0x0018, 0x0024), Type3, Vm::exactly(1)), // SequenceName
+        A::new(Tag(0x0018, 0x0025), Type3, Vm::exactly(1)).with_enumerated_values(&["Y", "N"]), // AngioFlag
+        A::new(Tag(0x0018, 0x0083), Type3, Vm::exactly(1)), // NumberOfAverages
+        A::new(Tag(0x0018, 0x0084), Type3, Vm::exactly(1)), // ImagingFrequency
+        A::new(Tag(0x0018, 0x0085), Type3, Vm::exactly(1)), // ImagedNucleus
+        A::new(Tag(0x0018, 0x0086), Type3, Vm::unbounded(1, 1)), // EchoNumbers
+        A::new(Tag(0x0018, 0x0087), Type3, Vm::exactly(1)), // MagneticFieldStrength
+        A::new(Tag(0x0018, 0x0088), Type3, Vm::exactly(1)), // SpacingBetweenSlices
+        A::new(Tag(0x0018, 0x0089), Type3, Vm::exactly(1)), // NumberOfPhaseEncodingSteps
+        A::new(Tag(0x0018, 0x0093), Type3, Vm::exactly(1)), // PercentSampling
+        A::new(Tag(0x0018, 0x0094), Type3, Vm::exactly(1)), // PercentPhaseFieldOfView
+        A::new(Tag(0x0018, 0x0095), Type3, Vm::exactly(1)), // PixelBandwidth
+        A::new(Tag(0x0018, 0x1062), Type3, Vm::exactly(1)), // NominalInterval
+        A::new(Tag(0x0018, 0x1080), Type3, Vm::exactly(1)).with_enumerated_values(&["Y", "N"]), // BeatRejectionFlag
+        A::new(Tag(0x0018, 0x1081), Type3, Vm::exactly(1)), // LowRRValue
+        A::new(Tag(0x0018, 0x1082), Type3, Vm::exactly(1)), // HighRRValue
+        A::new(Tag(0x0018, 0x1083), Type3, Vm::exactly(1)), // IntervalsAcquired
+        A::new(Tag(0x0018, 0x1084), Type3, Vm::exactly(1)), // IntervalsRejected
+        A::new(Tag(0x0018, 0x1085), Type3, Vm::exactly(1)), // PVCRejection
+        A::new(Tag(0x0018, 0x1086), Type3, Vm::exactly(1)), // SkipBeats
+        A::new(Tag(0x0018, 0x1088), Type3, Vm::exactly(1)), // HeartRate
+        A::new(Tag(0x0018, 0x1090), Type3, Vm::exactly(1)), // CardiacNumberOfImages
+        A::new(Tag(0x0018, 0x1094), Type3, Vm::exactly(1)), // TriggerWindow
+        A::new(Tag(0x0018, 0x1100), Type3, Vm::exactly(1)), // ReconstructionDiameter
+        A::new(Tag(0x0018, 0x1250), Type3, Vm::exactly(1)), // ReceiveCoilName
+        A::new(Tag(0x0018, 0x1251), Type3, Vm::exactly(1)), // TransmitCoilName
+        A::new(Tag(0x0018, 0x1310), Type3, Vm::exactly(4)), // AcquisitionMatrix
+        A::new(Tag(0x0018, 0x1312), Type3, Vm::exactly(1)).with_enumerated_values(&["ROW", "COL"]), // InPlanePhaseEncodingDirection
+        A::new(Tag(0x0018, 0x1314), Type3, Vm::exactly(1)), // FlipAngle
+        A::new(Tag(0x0018, 0x1315), Type3, Vm::exactly(1)).with_enumerated_values(&["Y", "N"]), // VariableFlipAngleFlag
+        A::new(Tag(0x0018, 0x1316), Type3, Vm::exactly(1)), // SAR
+        A::new(Tag(0x0018, 0x1318), Type3, Vm::exactly(1)), // dBdt
+        A::new(Tag(0x0020, 0x0100), Type3, Vm::exactly(1)), // TemporalPositionIdentifier
+        A::new(Tag(0x0020, 0x0105), Type3, Vm::exactly(1)), // NumberOfTemporalPositions
+        A::new(Tag(0x0020, 0x0110), Type3, Vm::exactly(1)), // TemporalResolution
+    ],
+};
+
+/// US Image Module
+#[rustfmt::skip]
+pub static US_IMAGE: ModuleDefinition = ModuleDefinition {
+    name: "US Image",
+    attributes: &[
+        A::new(Tag(0x0028, 0x0002), Type1, Vm::exactly(1)).with_enumerated_values(&["1", "3"]), // SamplesPerPixel
+        A::new(Tag(0x0028, 0x0004), Type1, Vm::exactly(1)).with_enumerated_values(&["MONOCHROME2", "PALETTE COLOR", "RGB", "YBR_FULL", "YBR_FULL_422", "YBR_PARTIAL_422", "YBR_PARTIAL_420", "YBR_ICT", "YBR_RCT"]), // PhotometricInterpretation
+        A::new(Tag(0x0028, 0x0100), Type1, Vm::exactly(1)).with_enumerated_values(&["8", "16"]), // BitsAllocated
+        A::new(Tag(0x0028, 0x0101), Type1, Vm::exactly(1)), // BitsStored
+        A::new(Tag(0x0028, 0x0102), Type1, Vm::exactly(1)), // HighBit
+        A::new(Tag(0x0028, 0x0006), Type1C, Vm::exactly(1)).with_enumerated_values(&["0", "1"]), // PlanarConfiguration
+        A::new(Tag(0x0028, 0x0103), Type1, Vm::exactly(1)).with_enumerated_values(&["0"]), // PixelRepresentation
+        A::new(Tag(0x0028, 0x0009), Type1C, Vm::unbounded(1, 1)), // FrameIncrementPointer
+        A::new(Tag(0x0008, 0x0008), Type2, Vm::range(2, 4)), // ImageType
+        A::new(Tag(0x0028, 0x2110), Type1C, Vm::exactly(1)).with_enumerated_values(&["00", "01"]), // LossyImageCompression
+        A::new(Tag(0x0008, 0x2124), Type2C, Vm::exactly(1)), // NumberOfStages
+        A::new(Tag(0x0008, 0x212A), Type2C, Vm::exactly(1)), // NumberOfViewsInStage
+        A::new(Tag(0x0028, 0x0014), Type3, Vm::exactly(1)).with_enumerated_values(&["0", "1"]), // UltrasoundColorDataPresent
+        A::new(Tag(0x0008, 0x2120), Type3, Vm::exactly(1)), // StageName
+        A::new(Tag(0x0008, 0x2122), Type3, Vm::exactly(1)), // StageNumber
+        A::new(Tag(0x0008, 0x2127), Type3, Vm::exactly(1)), // ViewName
+        A::new(Tag(0x0008, 0x2128), Type3, Vm::exactly(1)), // ViewNumber
+        A::new(Tag(0x0008, 0x2129), Type3, Vm::exactly(1)), // NumberOfEventTimers
+        A::new(Tag(0x0008, 0x2130), Type3, Vm::unbounded(1, 1)), // EventElapsedTimes
+        A::new(Tag(0x0008, 0x2132), Type3, Vm::unbounded(1, 1)), // EventTimerNames
+        A::new(Tag(0x0018, 0x1060), Type3, Vm::exactly(1)), // TriggerTime
+        A::new(Tag(0x0018, 0x1062), Type3, Vm::exactly(1)), // NominalInterval
+        A::new(Tag(0x0018, 0x1080), Type3, Vm::exactly(1)).with_enumerated_values(&["Y", "N"]), // BeatRejectionFlag
+        A::new(Tag(0x0018, 0x1081), Type3, Vm::exactly(1)), // LowRRValue
+        A::new(Tag(0x0018, 0x1082), Type3, Vm::exactly(1)), // HighRRValue
+        A::new(Tag(0x0018, 0x1088), Type3, Vm::exactly(1)), // HeartRate
+        A::new(Tag(0x0018, 0x5000), Type3, Vm::unbounded(1, 1)), // OutputPower
+        A::new(Tag(0x0018, 0x5010), Type3, Vm::range(1, 3)), // TransducerData
+        A::new(Tag(0x0018, 0x5012), Type3, Vm::exactly(1)), // FocusDepth
+        A::new(Tag(0x0018, 0x5020), Type3, Vm::exactly(1)), // ProcessingFunction
+        A::new(Tag(0x0018, 0x5022), Type3, Vm::exactly(1)), // MechanicalIndex
+        A::new(Tag(0x0018, 0x5024), Type3, Vm::exactly(1)), // BoneThermalIndex
+        A::new(Tag(0x0018, 0x5026), Type3, Vm::exactly(1)), // CranialThermalIndex
+        A::new(Tag(0x0018, 0x5027), Type3, Vm::exactly(1)), // SoftTissueThermalIndex
+        A::new(Tag(0x0018, 0x5028), Type3, Vm::exactly(1)), // SoftTissueFocusThermalIndex
+        A::new(Tag(0x0018, 0x5029), Type3, Vm::exactly(1)), // SoftTissueSurfaceThermalIndex
+        A::new(Tag(0x0018, 0x5050), Type3, Vm::exactly(1)), // DepthOfScanField
+        A::new(Tag(0x0018, 0x5210), Type3, Vm::exactly(6)), // ImageTransformationMatrix
+        A::new(Tag(0x0018, 0x5212), Type3, Vm::exactly(3)), // ImageTranslationVector
+        A::new(Tag(0x0018, 0x6031), Type3, Vm::exactly(1)), // TransducerType
+    ],
+};
+
+/// SR Document Series Module
+#[rustfmt::skip]
+pub static SR_DOCUMENT_SERIES: ModuleDefinition = ModuleDefinition {
+    name: "SR Document Series",
+    attributes: &[
+        A::new(Tag(0x0008, 0x0060), Type1, Vm::exactly(1)).with_enumerated_values(&["SR"]), // Modality
+        A::new(Tag(0x0020, 0x000E), Type1, Vm::exactly(1)), // SeriesInstanceUID
+        A::new(Tag(0x0020, 0x0011), Type1, Vm::exactly(1)), // SeriesNumber
+        A::new(Tag(0x0008, 0x0021), Type3, Vm::exactly(1)), // SeriesDate
+        A::new(Tag(0x0008, 0x0031), Type3, Vm::exactly(1)), // SeriesTime
+        A::new(Tag(0x0018, 0x1030), Type3, Vm::exactly(1)), // ProtocolName
+        A::new(Tag(0x0008, 0x103E), Type3, Vm::exactly(1)), // SeriesDescription
+        A::new(Tag(0x0008, 0x1111), Type2, Vm::exactly(1)).with_items(&[ // ReferencedPerformedProcedureStepSequence
+            A::new(Tag(0x0008, 0x1150), Type1, Vm::exactly(1)), // ReferencedSOPClassUID
+            A::new(Tag(0x0008, 0x1155), Type1, Vm::exactly(1)), // ReferencedSOPInstanceUID
+        ]),
+    ],
+};
+
+/// SR Document General Module
+#[rustfmt::skip]
+pub static SR_DOCUMENT_GENERAL: ModuleDefinition = ModuleDefinition {
+    name: "SR Document General",
+    attributes: &[
+        A::new(Tag(0x0020, 0x0013), Type1, Vm::exactly(1)), // InstanceNumber
+        A::new(Tag(0x0040, 0xA496), Type3, Vm::exactly(1)).with_enumerated_values(&["PRELIMINARY", "FINAL"]), // PreliminaryFlag
+        A::new(Tag(0x0040, 0xA491), Type1, Vm::exactly(1)).with_enumerated_values(&["PARTIAL", "COMPLETE"]), // CompletionFlag
+        A::new(Tag(0x0040, 0xA492), Type3, Vm::exactly(1)), // CompletionFlagDescription
+        A::new(Tag(0x0040, 0xA493), Type1, Vm::exactly(1)).with_enumerated_values(&["UNVERIFIED", "VERIFIED"]), // VerificationFlag
+        A::new(Tag(0x0008, 0x0023), Type1, Vm::exactly(1)), // ContentDate
+        A::new(Tag(0x0008, 0x0033), Type1, Vm::exactly(1)), // ContentTime
+        A::new(Tag(0x0040, 0xA073), Type1C, Vm::exactly(1)).with_items(&[ // VerifyingObserverSequence
+            A::new(Tag(0x0040, 0xA075), Type1, Vm::exactly(1)), // VerifyingObserverName
+            A::new(Tag(0x0040, 0xA088), Type2, Vm::exactly(1)), // VerifyingObserverIdentificationCodeSequence
+            A::new(Tag(0x0040, 0xA027), Type1, Vm::exactly(1)), // VerifyingOrganization
+            A::new(Tag(0x0040, 0xA030), Type1, Vm::exactly(1)), // VerificationDateTime
+        ]),
+        A::new(Tag(0x0040, 0xA078), Type3, Vm::exactly(1)), // AuthorObserverSequence
+        A::new(Tag(0x0040, 0xA07A), Type3, Vm::exactly(1)), // ParticipantSequence
+        A::new(Tag(0x0040, 0xA07C), Type3, Vm::exactly(1)), // CustodialOrganizationSequence
+        A::new(Tag(0x0040, 0xA370), Type1C, Vm::exactly(1)).with_items(&[ // ReferencedRequestSequence
+            A::new(Tag(0x0020, 0x000D), Type1, Vm::exactly(1)), // StudyInstanceUID
+            A::new(Tag(0x0008, 0x1110), Type2, Vm::exactly(1)), // ReferencedStudySequence
+            A::new(Tag(0x0008, 0x0050), Type2, Vm::exactly(1)), // AccessionNumber
+            A::new(Tag(0x0040, 0x2016), Type2, Vm::exactly(1)), // PlacerOrderNumberImagingServiceRequest
+            A::new(Tag(0x0040, 0x2017), Type2, Vm::exactly(1)), // FillerOrderNumberImagingServiceRequest
+            A::new(Tag(0x0040, 0x1001), Type2, Vm::exactly(1)), // RequestedProcedureID
+            A::new(Tag(0x0032, 0x1060), Type2, Vm::exactly(1)), // RequestedProcedureDescription
+            A::new(Tag(0x0032, 0x1064), Type2, Vm::exactly(1)), // RequestedProcedureCodeSequence
+        ]),
+        A::new(Tag(0x0040, 0xA372), Type2, Vm::exactly(1)).with_items(&[ // PerformedProcedureCodeSequence
+            A::new(Tag(0x0008, 0x0100), Type1C, Vm::exactly(1)), // CodeValue
+            A::new(Tag(0x0008, 0x0102), Type1C, Vm::exactly(1)), // CodingSchemeDesignator
+            A::new(Tag(0x0008, 0x0103), Type1C, Vm::exactly(1)), // CodingSchemeVersion
+            A::new(Tag(0x0008, 0x0104), Type1, Vm::exactly(1)), // CodeMeaning
+        ]),
+        A::new(Tag(0x0040, 0xA375), Type1C, Vm::exactly(1)).with_items(&[ // CurrentRequestedProcedureEvidenceSequence
+            A::new(Tag(0x0020, 0x000D), Type1, Vm::exactly(1)), // StudyInstanceUID
+            A::new(Tag(0x0008, 0x1115), Type1, Vm::exactly(1)).with_items(&[ // ReferencedSeriesSequence
+                A::new(Tag(0x0020, 0x000E), Type1, Vm::exactly(1)), // SeriesInstanceUID
+                A::new(Tag(0x0008, 0x1199), Type1, Vm::exactly(1)).with_items(&[ // ReferencedSOPSequence
+                    A::new(Tag(0x0008, 0x1150), Type1, Vm::exactly(1)), // ReferencedSOPClassUID
+                    A::new(Tag(0x0008, 0x1155), Type1, Vm::exactly(1)), // ReferencedSOPInstanceUID
+                ]),
+            ]),
+        ]),
+        A::new(Tag(0x0040, 0xA385), Type1C, Vm::exactly(1)).with_items(&[ // PertinentOtherEvidenceSequence
+            A::new(Tag(0x0020, 0x000D), Type1, Vm::exactly(1)), // StudyInstanceUID
+            A::new(Tag(0x0008, 0x1115), Type1, Vm::exactly(1)).with_items(&[ // ReferencedSeriesSequence
+                A::new(Tag(0x0020, 0x000E), Type1, Vm::exactly(1)), // SeriesInstanceUID
+                A::new(Tag(0x0008, 0x1199), Type1, Vm::exactly(1)).with_items(&[ // ReferencedSOPSequence
+                    A::new(Tag(0x0008, 0x1150), Type1, Vm::exactly(1)), // ReferencedSOPClassUID
+                    A::new(Tag(0x0008, 0x1155), Type1, Vm::exactly(1)), // ReferencedSOPInstanceUID
+                ]),
+            ]),
+        ]),
+    ],
+};
+
+/// SR Document Content Module
+#[rustfmt::skip]
+pub static SR_DOCUMENT_CONTENT: ModuleDefinition = ModuleDefinition {
+    name: "SR Document Content",
+    attributes: &[
+        A::new(Tag(0x0040, 0xA040), Type1, Vm::exactly(1)).with_enumerated_values(&["CONTAINER"]), // ValueType
+        A::new(Tag(0x0040, 0xA043), Type1, Vm::exactly(1)).with_items(&[ // ConceptNameCodeSequence
+            A::new(Tag(0x0008, 0x0100), Type1C, Vm::exactly(1)), // CodeValue
+            A::new(Tag(0x0008, 0x0102), Type1C, Vm::exactly(1)), // CodingSchemeDesignator
+            A::new(Tag(0x0008, 0x0103), Type1C, Vm::exactly(1)), // CodingSchemeVersion
+            A::new(Tag(0x0008, 0x0104), Type1, Vm::exactly(1)), // CodeMeaning
+        ]),
+        A::new(Tag(0x0040, 0xA050), Type1, Vm::exactly(1)).with_enumerated_values(&["SEPARATE", "CONTINUOUS"]), // ContinuityOfContent
+        A::new(Tag(0x0040, 0xA504), Type1C, Vm::exactly(1)).with_items(&[ // ContentTemplateSequence
+            A::new(Tag(0x0008, 0x0105), Type1, Vm::exactly(1)), // MappingResource
+            A::new(Tag(0x0040, 0xDB00), Type1, Vm::exactly(1)), // TemplateIdentifier
+        ]),
+        A::new(Tag(0x0040, 0xA730), Type1C, Vm::exactly(1)).with_items(&[ // ContentSequence
+            A::new(Tag(0x0040, 0xA010), Type1, Vm::exactly(1)).with_enumerated_values(&["CONTAINS", "HAS OBS CONTEXT", "HAS ACQ CONTEXT", "HAS CONCEPT MOD", "HAS PROPERTIES", "INFERRED FROM", "SELECTED FROM"]), // RelationshipType
+            A::new(Tag(0x0040, 0xA040), Type1, Vm::exactly(1)).with_enumerated_values(&["TEXT", "NUM", "CODE", "DATETIME", "DATE", "TIME", "UIDREF", "PNAME", "COMPOSITE", "IMAGE", "WAVEFORM", "SCOORD", "SCOORD3D", "TCOORD", "CONTAINER", "TABLE"]), // ValueType
+        ]),
+    ],
+};
+
+/// VOI LUT Module
+#[rustfmt::skip]
+pub static VOI_LUT: ModuleDefinition = ModuleDefinition {
+    name: "VOI LUT",
+    attributes: &[
+        A::new(Tag(0x0028, 0x3010), Type1C, Vm::exactly(1)).with_items(&[ // VOILUTSequence
+            A::new(Tag(0x0028, 0x3002), Type1, Vm::exactly(3)), // LUTDescriptor
+            A::new(Tag(0x0028, 0x3003), Type3, Vm::exactly(1)), // LUTExplanation
+            A::new(Tag(0x0028, 0x3006), Type1, Vm::unbounded(1, 1)), // LUTData
+        ]),
+        A::new(Tag(0x0028, 0x1050), Type1C, Vm::unbounded(1, 1)), // WindowCenter
+        A::new(Tag(0x0028, 0x1051), Type1C, Vm::unbounded(1, 1)), // WindowWidth
+        A::new(Tag(0x0028, 0x1055), Type3, Vm::unbounded(1, 1)), // WindowCenterWidthExplanation
+        A::new(Tag(0x0028, 0x1056), Type3, Vm::exactly(1)).with_enumerated_values(&["LINEAR", "LINEAR_EXACT", "SIGMOID"]), // VOILUTFunction
+    ],
+};
+
+/// SOP Common Module
+#[rustfmt::skip]
+pub static SOP_COMMON: ModuleDefinition = ModuleDefinition {
+    name: "SOP Common",
+    attributes: &[
+        A::new(Tag(0x0008, 0x0016), Type1, Vm::exactly(1)), // SOPClassUID
+        A::new(Tag(0x0008, 0x0018), Type1, Vm::exactly(1)), // SOPInstanceUID
+        A::new(Tag(0x0008, 0x0005), Type1C, Vm::unbounded(1, 1)), // SpecificCharacterSet
+        A::new(Tag(0x0008, 0x0012), Type3, Vm::exactly(1)), // InstanceCreationDate
+        A::new(Tag(0x0008, 0x0013), Type3, Vm::exactly(1)), // InstanceCreationTime
+        A::new(Tag(0x0008, 0x0014), Type3, Vm::exactly(1)), // InstanceCreatorUID
+        A::new(Tag(0x0008, 0x0201), Type3, Vm::exactly(1)), // TimezoneOffsetFromUTC
+        A::new(Tag(0x0020, 0x0013), Type3, Vm::exactly(1)), // InstanceNumber
+        A::new(Tag(0x0100, 0x0410), Type3, Vm::exactly(1)).with_enumerated_values(&["NS", "OR", "AO", "AC"]), // SOPInstanceStatus
+    ],
+};
+
+#[rustfmt::skip]
+pub(crate) static IODS: &[IodDefinition] = &[
+    IodDefinition {
+        name: "Basic Text SR",
+        sop_classes: &[
+            "1.2.840.10008.5.1.4.1.1.88.11", // Basic Text SR Storage
+        ],
+        modules: &[
+            IodModule { module: &PATIENT, usage: Mandatory },
+            IodModule { module: &GENERAL_STUDY, usage: Mandatory },
+            IodModule { module: &PATIENT_STUDY, usage: UserOption },
+            IodModule { module: &SR_DOCUMENT_SERIES, usage: Mandatory },
+            IodModule { module: &GENERAL_EQUIPMENT, usage: Mandatory },
+            IodModule { module: &SR_DOCUMENT_GENERAL, usage: Mandatory },
+            IodModule { module: &SR_DOCUMENT_CONTENT, usage: Mandatory },
+            IodModule { module: &SOP_COMMON, usage: Mandatory },
+        ],
+    },
+    IodDefinition {
+        name: "CT Image",
+        sop_classes: &[
+            "1.2.840.10008.5.1.4.1.1.2", // CT Image Storage
+        ],
+        modules: &[
+            IodModule { module: &PATIENT, usage: Mandatory },
+            IodModule { module: &GENERAL_STUDY, usage: Mandatory },
+            IodModule { module: &PATIENT_STUDY, usage: UserOption },
+            IodModule { module: &GENERAL_SERIES, usage: Mandatory },
+            IodModule { module: &FRAME_OF_REFERENCE, usage: Mandatory },
+            IodModule { module: &GENERAL_EQUIPMENT, usage: Mandatory },
+            IodModule { module: &GENERAL_IMAGE, usage: Mandatory },
+            IodModule { module: &IMAGE_PLANE, usage: Mandatory },
+            IodModule { module: &IMAGE_PIXEL, usage: Mandatory },
+            IodModule { module: &CT_IMAGE, usage: Mandatory },
+            IodModule { module: &VOI_LUT, usage: UserOption },
+            IodModule { module: &SOP_COMMON, usage: Mandatory },
+        ],
+    },
+    IodDefinition {
+        name: "Comprehensive SR",
+        sop_classes: &[
+            "1.2.840.10008.5.1.4.1.1.88.33", // Comprehensive SR Storage
+        ],
+        modules: &[
+            IodModule { module: &PATIENT, usage: Mandatory },
+            IodModule { module: &GENERAL_STUDY, usage: Mandatory },
+            IodModule { module: &PATIENT_STUDY, usage: UserOption },
+            IodModule { module: &SR_DOCUMENT_SERIES, usage: Mandatory },
+            IodModule { module: &GENERAL_EQUIPMENT, usage: Mandatory },
+            IodModule { module: &SR_DOCUMENT_GENERAL, usage: Mandatory },
+            IodModule { module: &SR_DOCUMENT_CONTENT, usage: Mandatory },
+            IodModule { module: &SOP_COMMON, usage: Mandatory },
+        ],
+    },
+    IodDefinition {
+        name: "Enhanced SR",
+        sop_classes: &[
+            "1.2.840.10008.5.1.4.1.1.88.22", // Enhanced SR Storage
+        ],
+        modules: &[
+            IodModule { module: &PATIENT, usage: Mandatory },
+            IodModule { module: &GENERAL_STUDY, usage: Mandatory },
+            IodModule { module: &PATIENT_STUDY, usage: UserOption },
+            IodModule { module: &SR_DOCUMENT_SERIES, usage: Mandatory },
+            IodModule { module: &GENERAL_EQUIPMENT, usage: Mandatory },
+            IodModule { module: &SR_DOCUMENT_GENERAL, usage: Mandatory },
+            IodModule { module: &SR_DOCUMENT_CONTENT, usage: Mandatory },
+            IodModule { module: &SOP_COMMON, usage: Mandatory },
+        ],
+    },
+    IodDefinition {
+        name: "MR Image",
+        sop_classes: &[
+            "1.2.840.10008.5.1.4.1.1.4", // MR Image Storage
+        ],
+        modules: &[
+            IodModule { module: &PATIENT, usage: Mandatory },
+            IodModule { module: &GENERAL_STUDY, usage: Mandatory },
+            IodModule { module: &PATIENT_STUDY, usage: UserOption },
+            IodModule { module: &GENERAL_SERIES, usage: Mandatory },
+            IodModule { module: &FRAME_OF_REFERENCE, usage: Mandatory },
+            IodModule { module: &GENERAL_EQUIPMENT, usage: Mandatory },
+            IodModule { module: &GENERAL_IMAGE, usage: Mandatory },
+            IodModule { module: &IMAGE_PLANE, usage: Mandatory },
+            IodModule { module: &IMAGE_PIXEL, usage: Mandatory },
+            IodModule { module: &CONTRAST_BOLUS, usage: Conditional },
+            IodModule { module: &MR_IMAGE, usage: Mandatory },
+            IodModule { module: &VOI_LUT, usage: UserOption },
+            IodModule { module: &SOP_COMMON, usage: Mandatory },
+        ],
+    },
+    IodDefinition {
+        name: "Secondary Capture Image",
+        sop_classes: &[
+            "1.2.840.10008.5.1.4.1.1.7", // Secondary Capture Image Storage
+        ],
+        modules: &[
+            IodModule { module: &PATIENT, usage: Mandatory },
+            IodModule { module: &GENERAL_STUDY, usage: Mandatory },
+            IodModule { module: &PATIENT_STUDY, usage: UserOption },
+            IodModule { module: &GENERAL_SERIES, usage: Mandatory },
+            IodModule { module: &GENERAL_EQUIPMENT, usage: UserOption },
+            IodModule { module: &SC_EQUIPMENT, usage: Mandatory },
+            IodModule { module: &GENERAL_IMAGE, usage: Mandatory },
+            IodModule { module: &IMAGE_PIXEL, usage: Mandatory },
+            IodModule { module: &SC_IMAGE, usage: Mandatory },
+            IodModule { module: &VOI_LUT, usage: UserOption },
+            IodModule { module: &SOP_COMMON, usage: Mandatory },
+        ],
+    },
+    IodDefinition {
+        name: "US Image",
+        sop_classes: &[
+            "1.2.840.10008.5.1.4.1.1.6.1", // Ultrasound Image Storage
+        ],
+        modules: &[
+            IodModule { module: &PATIENT, usage: Mandatory },
+            IodModule { module: &GENERAL_STUDY, usage: Mandatory },
+            IodModule { module: &PATIENT_STUDY, usage: UserOption },
+            IodModule { module: &GENERAL_SERIES, usage: Mandatory },
+            IodModule { module: &FRAME_OF_REFERENCE, usage: UserOption },
+            IodModule { module: &GENERAL_EQUIPMENT, usage: Mandatory },
+            IodModule { module: &GENERAL_IMAGE, usage: Mandatory },
+            IodModule { module: &IMAGE_PIXEL, usage: Mandatory },
+            IodModule { module: &CONTRAST_BOLUS, usage: Conditional },
+            IodModule { module: &US_IMAGE, usage: Mandatory },
+            IodModule { module: &VOI_LUT, usage: UserOption },
+            IodModule { module: &SOP_COMMON, usage: Mandatory },
+        ],
+    },
+];
@@ -0,0 +1,601 @@
+//! Private data element dictionary implementation
+//!
+//! Private attributes are not identified by their tag alone.
+//! A private data element `(gggg,xxEE)` belongs to the block `xx`
+//! which was reserved in the data set
+//! by a _Private Creator_ data element `(gggg,00xx)`,
+//! and only the value of that element,
+//! together with the group `gggg` and the element offset `EE`,
+//! identifies the attribute
+//! (see [DICOM PS3.5 section 7.8.1][1]).
+//!
+//! [`PrivateDataDictionary`] holds private attribute records
+//! keyed by private creator, group, and element offset.
+//! It can be built programmatically
+//! or loaded from private dictionary files in one of these formats:
+//!
+//! - the DCMTK data dictionary format,
+//!   one attribute per line with tab separated fields:
+//!   `(0019,"GEMS_ACQU_01",0c) DS CellSpacing 1 PrivateTag`
+//! - the GDCM XML private dictionary format,
+//!   one `entry` element per attribute:
+//!   `<entry owner="GEMS_ACQU_01" group="0019" element="xx0c" vr="DS" vm="1" name="Cell Spacing"/>`
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part05/sect_7.8.html
+
+use std::collections::HashMap;
+use std::fs;
+use std::iter::FromIterator;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use dicom_core::dictionary::{DataDictionaryEntry, TagRange, VirtualVr};
+use dicom_core::header::GroupNumber;
+use dicom_core::{Tag, VR};
+use snafu::{OptionExt, ResultExt, Snafu};
+
+/// An error which may occur when loading a private data dictionary.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// Could not read the dictionary file
+    #[snafu(display("Could not read private dictionary file {}", filename.display()))]
+    ReadFile {
+        filename: PathBuf,
+        source: std::io::Error,
+    },
+    /// An entry is missing a required field
+    #[snafu(display("Missing {} in private dictionary entry at line {}", field, line))]
+    MissingField { field: &'static str, line: usize },
+    /// An entry has an invalid tag
+    #[snafu(display("Invalid tag `{}` in private dictionary entry at line {}", tag, line))]
+    InvalidTag { tag: String, line: usize },
+    /// An entry has an unrecognized value representation
+    #[snafu(display("Invalid VR `{}` in private dictionary entry at line {}", vr, line))]
+    InvalidVr { vr: String, line: usize },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A record of a private DICOM attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrivateDataDictionaryEntry {
+    /// The private creator reserving the block of the attribute
+    pub creator: String,
+    /// The attribute group, which is always odd
+    pub group: GroupNumber,
+    /// The element offset of the attribute within its block
+    /// (the `EE` in `(gggg,xxEE)`)
+    pub element: u8,
+    /// The alias of the attribute, with no spaces, usually in UpperCamelCase
+    pub alias: String,
+    /// The _typical_ value representation of the attribute
+    pub vr: VirtualVr,
+}
+
+impl DataDictionaryEntry for PrivateDataDictionaryEntry {
+    /// The tag of the attribute in the first private block,
+    /// `(gggg,10EE)`.
+    ///
+    /// The actual block depends on the data set
+    /// in which the attribute is found.
+    fn tag_range(&self) -> TagRange {
+        TagRange::Single(Tag(self.group, 0x1000 | self.element as u16))
+    }
+
+    fn alias(&self) -> &str {
+        &self.alias
+    }
+
+    fn vr(&self) -> VirtualVr {
+        self.vr
+    }
+}
+
+/// A dictionary of private DICOM attributes,
+/// keyed by private creator.
+///
+/// Since the tag of a private data element
+/// depends on the block reserved by its private creator,
+/// look-ups require the value of the private creator
+/// alongside the tag (see [`by_private_tag`](Self::by_private_tag)).
+/// Leading and trailing spaces and null characters
+/// in private creator values are not significant.
+///
+/// # Example
+///
+/// ```
+/// # use dicom_core::{Tag, VR};
+/// # use dicom_core::dictionary::VirtualVr;
+/// # use dicom_dictionary_std::PrivateDataDictionary;
+/// let dict = PrivateDataDictionary::from_dcmtk_str(
+///     "(0019,\"GEMS_ACQU_01\",0c)\tDS\tCellSpacing\t1\tPrivateTag\n",
+/// )?;
+///
+/// // the block reserved for the private creator is not relevant
+/// let entry = dict
+///     .by_private_tag("GEMS_ACQU_01", Tag(0x0019, 0x110C))
+///     .unwrap();
+/// assert_eq!(entry.alias, "CellSpacing");
+/// assert_eq!(entry.vr, VirtualVr::Exact(VR::DS));
+/// # Ok::<(), dicom_dictionary_std::private::Error>(())
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PrivateDataDictionary {
+    entries: HashMap<String, HashMap<(GroupNumber, u8), PrivateDataDictionaryEntry>>,
+}
+
+impl PrivateDataDictionary {
+    /// Create an empty private data dictionary.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a private data dictionary from a file.
+    ///
+    /// The format of the file (DCMTK or GDCM XML)
+    /// is detected from its contents.
+    pub fn open_file(path: impl AsRef<Path>) -> Result<Self> {
+        let mut dict = Self::new();
+        dict.load_file(path)?;
+        Ok(dict)
+    }
+
+    /// Load the entries of a private data dictionary file
+    /// into this dictionary,
+    /// replacing any existing entries for the same attributes.
+    ///
+    /// The format of the file (DCMTK or GDCM XML)
+    /// is detected from its contents.
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).context(ReadFileSnafu { filename: path })?;
+        self.extend(
+            Self::parse_str(&text)?
+                .entries
+                .into_values()
+                .flat_map(|e| e.into_values()),
+        );
+        Ok(())
+    }
+
+    /// Parse a private data dictionary,
+    /// detecting whether it is in the DCMTK or GDCM XML format.
+    pub fn parse_str(text: &str) -> Result<Self> {
+        if text.trim_start().starts_with('<') {
+            Self::from_gdcm_xml_str(text)
+        } else {
+            Self::from_dcmtk_str(text)
+        }
+    }
+
+    /// Parse a private data dictionary in the DCMTK format.
+    ///
+    /// Each line describes one attribute with tab separated fields:
+    /// tag, VR, keyword, VM, and version.
+    /// The tag contains the private creator in quotes,
+    /// as in `(0019,"GEMS_ACQU_01",0c)`.
+    /// Empty lines, comments (`#`),
+    /// public attributes,
+    /// and attributes over a range of groups or elements
+    /// are ignored.
+    pub fn from_dcmtk_str(text: &str) -> Result<Self> {
+        let mut dict = Self::new();
+        for (i, line) in text.lines().enumerate() {
+            if let Some(entry) = parse_dcmtk_line(line, i + 1)? {
+                dict.insert(entry);
+            }
+        }
+        Ok(dict)
+    }
+
+    /// Parse a private data dictionary in the GDCM XML format.
+    ///
+    /// Each attribute is described by an `entry` element
+    /// with the attributes
+    /// `owner`, `group`, `element` (such as `xx0c`), `vr`, and `name`.
+    /// Entries without an owner
+    /// and entries over a range of groups
+    /// are ignored.
+    /// Keywords are derived from the attribute names.
+    pub fn from_gdcm_xml_str(text: &str) -> Result<Self> {
+        let mut dict = Self::new();
+        for (pos, _) in text.match_indices("<entry") {
+            let rest = &text[pos + "<entry".len()..];
+            if !rest.starts_with(|c: char| c.is_whitespace() || c == '/' || c == '>') {
+                continue;
+            }
+            let line = text[..pos].matches('\n').count() + 1;
+            let end = rest.find('>').unwrap_or(rest.len());
+            if let Some(entry) = parse_gdcm_entry(&rest[..end], line)? {
+                dict.insert(entry);
+            }
+        }
+        Ok(dict)
+    }
+
+    /// Insert an attribute record into the dictionary,
+    /// returning any previous record of the same attribute.
+    pub fn insert(
+        &mut self,
+        mut entry: PrivateDataDictionaryEntry,
+    ) -> Option<PrivateDataDictionaryEntry> {
+        let creator = trim_creator(&entry.creator);
+        if creator.len() != entry.creator.len() {
+            entry.creator = creator.to_string();
+        }
+        self.entries
+            .entry(entry.creator.clone())
+            .or_default()
+            .insert((entry.group, entry.element), entry)
+    }
+
+    /// Fetch the record of a private attribute
+    /// by its group, private creator, and element offset.
+    pub fn get(
+        &self,
+        group: GroupNumber,
+        creator: &str,
+        element: u8,
+    ) -> Option<&PrivateDataDictionaryEntry> {
+        self.entries
+            .get(trim_creator(creator))?
+            .get(&(group, element))
+    }
+
+    /// Fetch the record of a private attribute
+    /// by the value of its private creator
+    /// and the tag of the data element,
+    /// in any private block.
+    ///
+    /// Returns `None` if the tag is not a private data element tag.
+    pub fn by_private_tag(&self, creator: &str, tag: Tag) -> Option<&PrivateDataDictionaryEntry> {
+        if tag.group() & 1 == 0 || tag.element() < 0x1000 {
+            return None;
+        }
+        self.get(tag.group(), creator, (tag.element() & 0xFF) as u8)
+    }
+
+    /// The number of attribute records in the dictionary.
+    pub fn len(&self) -> usize {
+        self.entries.values().map(HashMap::len).sum()
+    }
+
+    /// Whether the dictionary has no attribute records.
+    pub fn is_empty(&self) -> bool {
+        self.entries.values().all(HashMap::is_empty)
+    }
+
+    /// Iterate over all attribute records in the dictionary,
+    /// in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &PrivateDataDictionaryEntry> {
+        self.entries.values().flat_map(HashMap::values)
+    }
+}
+
+impl Extend<PrivateDataDictionaryEntry> for PrivateDataDictionary {
+    fn extend<I: IntoIterator<Item = PrivateDataDictionaryEntry>>(&mut self, iter: I) {
+        for entry in iter {
+            self.insert(entry);
+        }
+    }
+}
+
+impl FromIterator<PrivateDataDictionaryEntry> for PrivateDataDictionary {
+    fn from_iter<I: IntoIterator<Item = PrivateDataDictionaryEntry>>(iter: I) -> Self {
+        let mut dict = Self::new();
+        dict.extend(iter);
+        dict
+    }
+}
+
+impl FromStr for PrivateDataDictionary {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse_str(s)
+    }
+}
+
+fn trim_creator(creator: &str) -> &str {
+    creator.trim_matches(|c| c == ' ' || c == '\0')
+}
+
+/// Parse one line of a DCMTK data dictionary,
+/// returning `None` if it does not describe a private attribute.
+fn parse_dcmtk_line(line: &str, line_no: usize) -> Result<Option<PrivateDataDictionaryEntry>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    // the tag may contain spaces inside of the private creator,
+    // so look for its end after the closing quote
+    let quotes = line
+        .match_indices('"')
+        .map(|(i, _)| i)
+        .take(2)
+        .collect::<Vec<_>>();
+    let tag_end = match *quotes.as_slice() {
+        [_, close] => line[close..].find(')').map(|i| close + i),
+        _ => line.find(')'),
+    }
+    .context(InvalidTagSnafu {
+        tag: line,
+        line: line_no,
+    })?;
+    let tag = &line[..=tag_end];
+    if quotes.is_empty() {
+        // public attribute
+        return Ok(None);
+    }
+
+    let mut fields = line[tag_end + 1..]
+        .split('\t')
+        .map(str::trim)
+        .filter(|f| !f.is_empty());
+    let vr = fields.next().context(MissingFieldSnafu {
+        field: "VR",
+        line: line_no,
+    })?;
+    let alias = fields.next().context(MissingFieldSnafu {
+        field: "keyword",
+        line: line_no,
+    })?;
+
+    let invalid_tag = || InvalidTagSnafu { tag, line: line_no }.build();
+    let inner = tag
+        .strip_prefix('(')
+        .and_then(|t| t.strip_suffix(')'))
+        .ok_or_else(invalid_tag)?;
+    let group = inner[..inner.find(',').ok_or_else(invalid_tag)?].trim();
+    let element = inner[inner.rfind(',').ok_or_else(invalid_tag)? + 1..].trim();
+    let creator = &line[quotes[0] + 1..quotes[1]];
+
+    let (group, element) = match parse_private_tag(group, element) {
+        Ok(Some(tag)) => tag,
+        Ok(None) => return Ok(None),
+        Err(()) => return Err(invalid_tag()),
+    };
+
+    Ok(Some(PrivateDataDictionaryEntry {
+        creator: creator.to_string(),
+        group,
+        element,
+        alias: alias.to_string(),
+        vr: parse_vr(vr).context(InvalidVrSnafu { vr, line: line_no })?,
+    }))
+}
+
+/// Parse the attributes of a GDCM dictionary `entry` element,
+/// returning `None` if it does not describe a private attribute.
+fn parse_gdcm_entry(attributes: &str, line: usize) -> Result<Option<PrivateDataDictionaryEntry>> {
+    let mut owner = None;
+    let mut group = None;
+    let mut element = None;
+    let mut vr = None;
+    let mut name = None;
+
+    let mut rest = attributes;
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim();
+        let value_start = rest[eq + 1..].trim_start();
+        let quote = match value_start.chars().next() {
+            Some(q @ '"') | Some(q @ '\'') => q,
+            _ => break,
+        };
+        let value_end = match value_start[1..].find(quote) {
+            Some(i) => i + 1,
+            None => break,
+        };
+        let value = unescape_xml(&value_start[1..value_end]);
+        match key {
+            "owner" => owner = Some(value),
+            "group" => group = Some(value),
+            "element" => element = Some(value),
+            "vr" => vr = Some(value),
+            "name" => name = Some(value),
+            _ => {}
+        }
+        rest = &value_start[value_end + 1..];
+    }
+
+    let owner = match owner {
+        Some(owner) if !trim_creator(&owner).is_empty() => owner,
+        _ => return Ok(None),
+    };
+    let group = group.context(MissingFieldSnafu {
+        field: "group",
+        line,
+    })?;
+    let element = element.context(MissingFieldSnafu {
+        field: "element",
+        line,
+    })?;
+    let vr = vr.context(MissingFieldSnafu { field: "VR", line })?;
+
+    let (group, element) = match parse_private_tag(&group, &element) {
+        Ok(Some(tag)) => tag,
+        Ok(None) => return Ok(None),
+        Err(()) => {
+            return InvalidTagSnafu {
+                tag: format!("({},{})", group, element),
+                line,
+            }
+            .fail()
+        }
+    };
+
+    let mut alias = name.as_deref().map(keyword_from_name).unwrap_or_default();
+    if alias.is_empty() {
+        alias = format!("Private{:04X}xx{:02X}", group, element);
+    }
+
+    Ok(Some(PrivateDataDictionaryEntry {
+        creator: owner,
+        group,
+        element,
+        alias,
+        vr: parse_vr(&vr).context(InvalidVrSnafu { vr: &vr, line })?,
+    }))
+}
+
+/// Parse the group and element parts of a private attribute tag,
+/// where the element part is either the element offset (`0c`)
+/// or a full element with an open block (`xx0c`, `100c`).
+///
+/// Returns `Ok(None)` for group ranges and element ranges,
+/// and for groups which cannot hold private attributes.
+fn parse_private_tag(group: &str, element: &str) -> std::result::Result<Option<(u16, u8)>, ()> {
+    if group.contains(|c: char| c == '-' || c.eq_ignore_ascii_case(&'x')) {
+        return Ok(None);
+    }
+    let element = match element.len() {
+        2 => element,
+        4 => &element[2..],
+        _ => return Err(()),
+    };
+    if element.contains(|c: char| c.eq_ignore_ascii_case(&'x')) {
+        return Ok(None);
+    }
+    if group.len() != 4 {
+        return Err(());
+    }
+
+    let group = u16::from_str_radix(group, 16).map_err(|_| ())?;
+    let element = u8::from_str_radix(element, 16).map_err(|_| ())?;
+    if group & 1 == 0 {
+        return Ok(None);
+    }
+    Ok(Some((group, element)))
+}
+
+/// Parse a value representation as written in DCMTK and GDCM dictionaries.
+fn parse_vr(vr: &str) -> Option<VirtualVr> {
+    match vr {
+        "xs" | "US or SS" | "US_SS" => Some(VirtualVr::Xs),
+        "ox" | "OB or OW" | "OB_OW" => Some(VirtualVr::Ox),
+        "px" => Some(VirtualVr::Px),
+        "lt" | "US or OW" | "US_OW" | "US or SS or OW" | "US_SS_OW" => Some(VirtualVr::Lt),
+        // DCMTK's unsigned "pointer" VR
+        "up" => Some(VirtualVr::Exact(VR::UL)),
+        vr => vr.parse().ok().map(VirtualVr::Exact),
+    }
+}
+
+/// Derive an UpperCamelCase keyword from an attribute name,
+/// such as `"Cell Spacing"`.
+fn keyword_from_name(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect()
+}
+
+fn unescape_xml(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dcmtk_dictionary() {
+        let dict = PrivateDataDictionary::from_dcmtk_str(
+            "# private tags\n\
+             \n\
+             (0008,0020)\tDA\tStudyDate\t1\tDICOM\n\
+             (0019,\"GEMS_ACQU_01\",0c)\tDS\tCellSpacing\t1\tPrivateTag\n\
+             (0029,\"SIEMENS CSA HEADER\",10)\tOB\tCSAImageHeaderInfo\t1\tPrivateTag\n\
+             (0043,\"GEMS_PARM_01\",1e)\tUS or SS\tDeltaStartTime\t1\tPrivateTag\n\
+             (6001-o-60ff,\"SOME CREATOR\",10)\tLO\tRepeatingThing\t1\tPrivateTag\n",
+        )
+        .unwrap();
+
+        assert_eq!(dict.len(), 3);
+        assert_eq!(
+            dict.get(0x0029, "SIEMENS CSA HEADER", 0x10),
+            Some(&PrivateDataDictionaryEntry {
+                creator: "SIEMENS CSA HEADER".to_string(),
+                group: 0x0029,
+                element: 0x10,
+                alias: "CSAImageHeaderInfo".to_string(),
+                vr: VirtualVr::Exact(VR::OB),
+            })
+        );
+        assert_eq!(
+            dict.by_private_tag("GEMS_PARM_01 ", Tag(0x0043, 0x101E))
+                .map(|e| e.vr),
+            Some(VirtualVr::Xs)
+        );
+        // the creator must match
+        assert_eq!(
+            dict.by_private_tag("GEMS_ACQU_02", Tag(0x0019, 0x100C)),
+            None
+        );
+        // not a private data element
+        assert_eq!(
+            dict.by_private_tag("GEMS_ACQU_01", Tag(0x0019, 0x000C)),
+            None
+        );
+
+        let err = PrivateDataDictionary::from_dcmtk_str(
+            "(0019,\"GEMS_ACQU_01\",0c)\tDS\tCellSpacing\t1\tPrivateTag\n\
+             (0019,\"GEMS_ACQU_01\",0d)\tXY\tSomethingElse\t1\tPrivateTag\n",
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::InvalidVr { line: 2, .. }));
+    }
+
+    #[test]
+    fn parse_gdcm_dictionary() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+<dict edition="2008">
+  <entry owner="GEMS_ACQU_01" group="0019" element="xx0c" vr="DS" vm="1" name="Cell Spacing"/>
+  <entry owner="A &amp; B" group="0009" element="xx01" vr="SQ" vm="1" name="?">
+    <description>some sequence</description>
+  </entry>
+  <entry group="0008" element="0020" vr="DA" vm="1" name="Study Date"/>
+  <entry owner="GEMS_ACQU_01" group="60xx" element="xx01" vr="LO" vm="1" name="Overlay Thing"/>
+</dict>
+"#;
+        let dict: PrivateDataDictionary = text.parse().unwrap();
+
+        assert_eq!(dict.len(), 2);
+        let entry = dict
+            .by_private_tag("GEMS_ACQU_01", Tag(0x0019, 0x120C))
+            .unwrap();
+        assert_eq!(entry.alias, "CellSpacing");
+        assert_eq!(entry.vr, VirtualVr::Exact(VR::DS));
+        assert_eq!(entry.tag(), Tag(0x0019, 0x100C));
+
+        let entry = dict.get(0x0009, "A & B", 0x01).unwrap();
+        assert_eq!(entry.alias, "Private0009xx01");
+        assert_eq!(entry.vr, VirtualVr::Exact(VR::SQ));
+
+        let err = PrivateDataDictionary::from_gdcm_xml_str(
+            "<dict>\n<entry owner=\"X\" group=\"0009\" vr=\"LO\"/>\n</dict>",
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::MissingField {
+                field: "element",
+                line: 2
+            }
+        ));
+    }
+}
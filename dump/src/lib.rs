@@ -39,11 +39,13 @@ use dicom_core::dictionary::UidDictionary;
 use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry};
 use dicom_core::header::Header;
 use dicom_core::value::{PrimitiveValue, Value as DicomValue};
-use dicom_core::VR;
+use dicom_core::{Tag, VR};
+use dicom_dictionary_std::PrivateDataDictionary;
 #[cfg(feature = "sop-class")]
 use dicom_dictionary_std::StandardSopClassDictionary;
 use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
 use dicom_json::DicomJson;
+use dicom_object::diff::{Difference, DifferenceKind};
 use dicom_object::mem::{InMemDicomObject, InMemElement};
 use dicom_object::{FileDicomObject, FileMetaTable, StandardDataDictionary};
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
@@ -52,6 +54,7 @@ use std::borrow::Cow;
 use std::fmt::{self, Display, Formatter};
 use std::io::{stdout, Result as IoResult, Write};
 use std::str::FromStr;
+use std::sync::Arc;
 
 #[derive(Clone, Debug, PartialEq, Default)]
 #[cfg_attr(feature = "cli", derive(ValueEnum))]
@@ -124,6 +127,8 @@ pub struct DumpOptions {
     pub no_text_limit: bool,
     /// never trim out any values (implies `no_text_limit`)
     pub no_limit: bool,
+    /// the dictionary for naming private attributes, if any
+    pub private_dictionary: Option<Arc<PrivateDataDictionary>>,
 }
 
 impl DumpOptions {
@@ -183,6 +188,17 @@ impl DumpOptions {
         self
     }
 
+    /// Set a dictionary of private attributes,
+    /// so that private data elements are named
+    /// according to the private creator reserving their block.
+    pub fn private_dictionary(
+        &mut self,
+        private_dictionary: impl Into<Arc<PrivateDataDictionary>>,
+    ) -> &mut Self {
+        self.private_dictionary = Some(private_dictionary.into());
+        self
+    }
+
     /// Dump the contents of an open DICOM file to standard output.
     pub fn dump_file<D>(&self, obj: &FileDicomObject<InMemDicomObject<D>>) -> IoResult<()>
     where
@@ -233,7 +249,15 @@ impl DumpOptions {
 
                 writeln!(to, "{:-<58}", "")?;
 
-                dump(&mut to, obj, width, 0, no_text_limit, no_limit)?;
+                dump(
+                    &mut to,
+                    obj,
+                    self.private_dictionary.as_deref(),
+                    width,
+                    0,
+                    no_text_limit,
+                    no_limit,
+                )?;
 
                 Ok(())
             },
@@ -290,7 +314,15 @@ impl DumpOptions {
                     (true, true)
                 };
 
-                dump(&mut to, obj, width, 0, no_text_limit, no_limit)?;
+                dump(
+                    &mut to,
+                    obj,
+                    self.private_dictionary.as_deref(),
+                    width,
+                    0,
+                    no_text_limit,
+                    no_limit,
+                )?;
 
                 Ok(())
             }
@@ -301,6 +333,58 @@ impl DumpOptions {
             }
         }
     }
+
+    /// Dump the differences between two DICOM objects
+    /// (see [`dicom_object::diff`]) to standard output.
+    ///
+    /// Differences are always printed as text,
+    /// regardless of the output format.
+    pub fn dump_diff<D>(&self, differences: &[Difference<'_, D>]) -> IoResult<()>
+    where
+        D: DataDictionary,
+    {
+        self.dump_diff_impl(stdout(), differences, true)
+    }
+
+    /// Dump the differences between two DICOM objects
+    /// (see [`dicom_object::diff`]) to the given writer.
+    pub fn dump_diff_to<D>(&self, to: impl Write, differences: &[Difference<'_, D>]) -> IoResult<()>
+    where
+        D: DataDictionary,
+    {
+        self.dump_diff_impl(to, differences, false)
+    }
+
+    fn dump_diff_impl<D>(
+        &self,
+        mut to: impl Write,
+        differences: &[Difference<'_, D>],
+        to_stdout: bool,
+    ) -> IoResult<()>
+    where
+        D: DataDictionary,
+    {
+        match (self.color, to_stdout) {
+            (ColorMode::Never, _) => owo_colors::set_override(false),
+            (ColorMode::Always, _) => owo_colors::set_override(true),
+            (ColorMode::Auto, false) => owo_colors::set_override(false),
+            (ColorMode::Auto, true) => owo_colors::unset_override(),
+        }
+
+        let width = determine_width(self.width);
+
+        let (no_text_limit, no_limit) = if to_stdout {
+            (self.no_text_limit, self.no_limit)
+        } else {
+            (true, true)
+        };
+
+        for difference in differences {
+            dump_difference(&mut to, difference, width, no_text_limit, no_limit)?;
+        }
+
+        Ok(())
+    }
 }
 
 /// Enumeration of output coloring modes.
@@ -583,6 +667,7 @@ where
 fn dump<W, D>(
     to: &mut W,
     obj: &InMemDicomObject<D>,
+    private_dictionary: Option<&PrivateDataDictionary>,
     width: u32,
     depth: u32,
     no_text_limit: bool,
@@ -592,13 +677,57 @@ where
     W: ?Sized + Write,
     D: DataDictionary,
 {
+    // private creators are always found before the elements in their block
+    let mut private_creators = Vec::new();
     for elem in obj {
-        dump_element(&mut *to, elem, width, depth, no_text_limit, no_limit)?;
+        let tag = elem.tag();
+        if private_dictionary.is_some()
+            && tag.group() % 2 == 1
+            && (0x0010..=0x00FF).contains(&tag.element())
+        {
+            if let Ok(creator) = elem.value().to_str() {
+                private_creators.push((tag, creator));
+            }
+        }
+        let tag_alias = element_alias(tag, &private_creators, private_dictionary);
+        dump_element_impl(
+            &mut *to,
+            elem,
+            tag_alias,
+            private_dictionary,
+            width,
+            depth,
+            no_text_limit,
+            no_limit,
+        )?;
     }
 
     Ok(())
 }
 
+/// Look up the alias of an attribute,
+/// resolving private attributes through the private creator
+/// reserving their block.
+fn element_alias<'a>(
+    tag: Tag,
+    private_creators: &[(Tag, Cow<str>)],
+    private_dictionary: Option<&'a PrivateDataDictionary>,
+) -> &'a str {
+    private_dictionary
+        .and_then(|dict| {
+            let creator_tag = Tag(tag.group(), tag.element() >> 8);
+            let (_, creator) = private_creators.iter().find(|(t, _)| *t == creator_tag)?;
+            dict.by_private_tag(creator, tag)
+        })
+        .map(|entry| entry.alias.as_str())
+        .or_else(|| {
+            StandardDataDictionary
+                .by_tag(tag)
+                .map(DataDictionaryEntry::alias)
+        })
+        .unwrap_or("«Unknown Attribute»")
+}
+
 pub fn dump_element<W, D>(
     to: &mut W,
     elem: &InMemElement<D>,
@@ -611,11 +740,38 @@ where
     W: ?Sized + Write,
     D: DataDictionary,
 {
-    let indent = vec![b' '; (depth * 2) as usize];
     let tag_alias = StandardDataDictionary
         .by_tag(elem.tag())
         .map(DataDictionaryEntry::alias)
         .unwrap_or("«Unknown Attribute»");
+    dump_element_impl(
+        to,
+        elem,
+        tag_alias,
+        None,
+        width,
+        depth,
+        no_text_limit,
+        no_limit,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn dump_element_impl<W, D>(
+    to: &mut W,
+    elem: &InMemElement<D>,
+    tag_alias: &str,
+    private_dictionary: Option<&PrivateDataDictionary>,
+    width: u32,
+    depth: u32,
+    no_text_limit: bool,
+    no_limit: bool,
+) -> IoResult<()>
+where
+    W: ?Sized + Write,
+    D: DataDictionary,
+{
+    let indent = vec![b' '; (depth * 2) as usize];
     to.write_all(&indent)?;
     let vm = match elem.vr() {
         VR::OB | VR::OW | VR::UN => 1,
@@ -634,7 +790,15 @@ where
                 if vm == 1 { "" } else { "s" },
             )?;
             for item in seq.items() {
-                dump_item(&mut *to, item, width, depth + 2, no_text_limit, no_limit)?;
+                dump_item(
+                    &mut *to,
+                    item,
+                    private_dictionary,
+                    width,
+                    depth + 2,
+                    no_text_limit,
+                    no_limit,
+                )?;
             }
             to.write_all(&indent)?;
             writeln!(
@@ -722,6 +886,7 @@ where
 fn dump_item<W, D>(
     to: &mut W,
     item: &InMemDicomObject<D>,
+    private_dictionary: Option<&PrivateDataDictionary>,
     width: u32,
     depth: u32,
     no_text_limit: bool,
@@ -739,7 +904,15 @@ where
         DumpValue::TagNum("(FFFE,E000)"),
         DumpValue::Alias("Item"),
     )?;
-    dump(to, item, width, depth + 1, no_text_limit, no_limit)?;
+    dump(
+        to,
+        item,
+        private_dictionary,
+        width,
+        depth + 1,
+        no_text_limit,
+        no_limit,
+    )?;
     writeln!(
         to,
         "{}{} {}",
@@ -750,6 +923,123 @@ where
     Ok(())
 }
 
+/// A marker for the kind of a difference between two objects.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum DiffMarker {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl fmt::Display for DiffMarker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiffMarker::Added => write!(
+                f,
+                "{}",
+                "+".if_supports_color(Stream::Stdout, |v| v.green())
+            ),
+            DiffMarker::Removed => {
+                write!(f, "{}", "-".if_supports_color(Stream::Stdout, |v| v.red()))
+            }
+            DiffMarker::Changed => write!(
+                f,
+                "{}",
+                "~".if_supports_color(Stream::Stdout, |v| v.yellow())
+            ),
+        }
+    }
+}
+
+fn dump_difference<W, D>(
+    to: &mut W,
+    difference: &Difference<'_, D>,
+    width: u32,
+    no_text_limit: bool,
+    no_limit: bool,
+) -> IoResult<()>
+where
+    W: ?Sized + Write,
+    D: DataDictionary,
+{
+    let selector = difference.selector.to_string();
+    let tag_alias = StandardDataDictionary
+        .by_tag(difference.selector.last_tag())
+        .map(DataDictionaryEntry::alias)
+        .unwrap_or("«Unknown Attribute»");
+
+    let mut dump_line = |marker: DiffMarker, description: &dyn Display| {
+        writeln!(
+            to,
+            "{} {} {:28} {}",
+            marker,
+            DumpValue::TagNum(&selector),
+            DumpValue::Alias(tag_alias),
+            description,
+        )
+    };
+
+    let element_summary = |elem: &InMemElement<D>| -> String {
+        match elem.value() {
+            DicomValue::Primitive(value) => format!(
+                "{}: {}",
+                elem.vr(),
+                value_summary(
+                    value,
+                    elem.vr(),
+                    width.saturating_sub(48 + selector.len() as u32),
+                    no_text_limit,
+                    no_limit,
+                )
+            ),
+            DicomValue::Sequence(seq) => {
+                let n = seq.items().len();
+                format!(
+                    "{} ({} Item{})",
+                    elem.vr(),
+                    n,
+                    if n == 1 { "" } else { "s" }
+                )
+            }
+            DicomValue::PixelSequence(seq) => {
+                let n = seq.fragments().len();
+                format!(
+                    "{} (PixelSequence, {} Fragment{})",
+                    elem.vr(),
+                    n,
+                    if n == 1 { "" } else { "s" }
+                )
+            }
+        }
+    };
+
+    match &difference.kind {
+        DifferenceKind::Added(elem) => dump_line(DiffMarker::Added, &element_summary(elem)),
+        DifferenceKind::Removed(elem) => dump_line(DiffMarker::Removed, &element_summary(elem)),
+        DifferenceKind::Changed { left, right } => {
+            dump_line(DiffMarker::Removed, &element_summary(left))?;
+            dump_line(DiffMarker::Added, &element_summary(right))
+        }
+        DifferenceKind::ItemAdded { index } => {
+            dump_line(DiffMarker::Added, &format_args!("Item #{}", index))
+        }
+        DifferenceKind::ItemRemoved { index } => {
+            dump_line(DiffMarker::Removed, &format_args!("Item #{}", index))
+        }
+        DifferenceKind::OffsetTableChanged => dump_line(DiffMarker::Changed, &"offset table"),
+        DifferenceKind::FragmentAdded { index } => {
+            dump_line(DiffMarker::Added, &format_args!("Fragment #{}", index))
+        }
+        DifferenceKind::FragmentRemoved { index } => {
+            dump_line(DiffMarker::Removed, &format_args!("Fragment #{}", index))
+        }
+        DifferenceKind::FragmentChanged { index } => {
+            dump_line(DiffMarker::Changed, &format_args!("Fragment #{}", index))
+        }
+        _ => dump_line(DiffMarker::Changed, &"(changed)"),
+    }
+}
+
 fn value_summary(
     value: &PrimitiveValue,
     vr: VR,
@@ -973,8 +1263,8 @@ fn determine_width(user_width: Option<u32>) -> u32 {
 #[cfg(test)]
 mod tests {
 
-    use dicom_core::{value::DicomDate, DataElement, PrimitiveValue, VR};
-    use dicom_dictionary_std::tags;
+    use dicom_core::{value::DicomDate, DataElement, PrimitiveValue, Tag, VR};
+    use dicom_dictionary_std::{tags, PrivateDataDictionary};
     use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
 
     use super::whitespace_or_null;
@@ -1115,6 +1405,77 @@ mod tests {
         }
     }
 
+    #[test]
+    fn dump_object_to_names_private_elements() {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(Tag(0x0019, 0x0010), VR::LO, PrimitiveValue::from("ACME 1")),
+            DataElement::new(Tag(0x0019, 0x0011), VR::LO, PrimitiveValue::from("ACME 2")),
+            DataElement::new(Tag(0x0019, 0x1001), VR::US, PrimitiveValue::from(1_u16)),
+            DataElement::new(Tag(0x0019, 0x1101), VR::US, PrimitiveValue::from(2_u16)),
+        ]);
+        let private_dictionary = PrivateDataDictionary::from_dcmtk_str(
+            "(0019,\"ACME 2\",01)\tUS\tAcmeThing\t1\tPrivateTag\n",
+        )
+        .unwrap();
+
+        let mut out = Vec::new();
+        DumpOptions::new()
+            .color_mode(ColorMode::Never)
+            .private_dictionary(private_dictionary)
+            .dump_object_to(&mut out, &obj)
+            .unwrap();
+
+        let lines: Vec<_> = std::str::from_utf8(&out)
+            .expect("output is not valid UTF-8")
+            .split('\n')
+            .collect();
+        let aliases: Vec<_> = lines[..4]
+            .iter()
+            .map(|line| line.split(' ').filter(|p| !p.is_empty()).nth(1).unwrap())
+            .collect();
+        assert_eq!(
+            aliases,
+            [
+                "PrivateCreator",
+                "PrivateCreator",
+                "«Unknown",
+                "AcmeThing"
+            ]
+        );
+    }
+
+    #[test]
+    fn dump_diff_to_covers_differences() {
+        let left = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::from("Doe^John")),
+            DataElement::new(tags::PATIENT_ID, VR::LO, PrimitiveValue::from("12345")),
+        ]);
+        let right = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::from("Doe^Jane")),
+            DataElement::new(tags::MODALITY, VR::CS, PrimitiveValue::from("OT")),
+        ]);
+        let differences = dicom_object::diff::diff(&left, &right);
+
+        let mut out = Vec::new();
+        DumpOptions::new()
+            .color_mode(ColorMode::Never)
+            .dump_diff_to(&mut out, &differences)
+            .unwrap();
+
+        let lines: Vec<_> = std::str::from_utf8(&out)
+            .expect("output is not valid UTF-8")
+            .lines()
+            .map(|line| line.split(' ').filter(|p| !p.is_empty()).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(&lines[0][..3], &["+", "(0008,0060)", "Modality"]);
+        assert_eq!(&lines[1][..4], &["-", "(0010,0010)", "PatientName", "PN:"]);
+        assert_eq!(lines[1][4], "\"Doe^John\"");
+        assert_eq!(&lines[2][..4], &["+", "(0010,0010)", "PatientName", "PN:"]);
+        assert_eq!(lines[2][4], "\"Doe^Jane\"");
+        assert_eq!(&lines[3][..3], &["-", "(0010,0020)", "PatientID"]);
+    }
+
     #[test]
     fn dump_json() {
         // create object
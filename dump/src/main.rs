@@ -2,12 +2,14 @@
 //! by printing it in a human readable format.
 use clap::Parser;
 use dicom_core::Tag;
-use dicom_dictionary_std::tags;
+use dicom_dictionary_std::{tags, PrivateDataDictionary};
 use dicom_dump::{ColorMode, DumpOptions, DumpFormat};
+use dicom_object::diff::DiffOptions;
 use dicom_object::{OpenFileOptions, StandardDataDictionary};
-use snafu::{Report, Whatever};
+use snafu::{Report, ResultExt, Whatever};
 use std::io::{ErrorKind, IsTerminal};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Exit code for when an error emerged while reading the DICOM file.
 const ERROR_READ: i32 = -2;
@@ -53,6 +55,26 @@ struct App {
     #[arg(value_enum)]
     #[clap(short = 'f', long = "format", default_value = "text")]
     format: DumpFormat,
+    /// Load a private data dictionary file
+    /// in the DCMTK or GDCM XML format
+    /// (can be repeated)
+    #[clap(long = "private-dict")]
+    private_dicts: Vec<PathBuf>,
+    /// Print the differences between two DICOM files
+    /// instead of their contents
+    #[clap(long = "diff")]
+    diff: bool,
+    /// Ignore trailing padding in values when comparing files
+    #[clap(long = "ignore-padding", requires = "diff")]
+    ignore_padding: bool,
+    /// Compare decimal and integer strings by numeric value
+    /// when comparing files
+    #[clap(long = "numeric", requires = "diff")]
+    numeric: bool,
+    /// Leave an attribute out of the comparison of files
+    /// (can be repeated)
+    #[clap(long = "ignore-tag", value_parser = parse_tag, requires = "diff")]
+    ignore_tags: Vec<Tag>,
 }
 
 fn parse_tag(s: &str) -> Result<Tag, &'static str> {
@@ -81,6 +103,11 @@ fn run() -> Result<(), Whatever> {
         color,
         fail_first,
         format,
+        private_dicts,
+        diff,
+        ignore_padding,
+        numeric,
+        ignore_tags,
     } = App::parse();
 
     let width = width
@@ -95,6 +122,31 @@ fn run() -> Result<(), Whatever> {
         .width(width)
         .color_mode(color)
         .format(format);
+
+    let mut open_options = match read_until {
+        Some(stop_tag) => OpenFileOptions::new().read_until(stop_tag),
+        None => OpenFileOptions::new(),
+    };
+
+    if !private_dicts.is_empty() {
+        let mut private_dictionary = PrivateDataDictionary::new();
+        for path in &private_dicts {
+            private_dictionary
+                .load_file(path)
+                .whatever_context("Could not load private data dictionary")?;
+        }
+        let private_dictionary = Arc::new(private_dictionary);
+        options.private_dictionary(private_dictionary.clone());
+        open_options = open_options.private_dictionary(private_dictionary);
+    }
+
+    if diff {
+        let diff_options = DiffOptions::new()
+            .ignore_padding(ignore_padding)
+            .numeric_equivalence(numeric)
+            .ignore_tags(ignore_tags);
+        run_diff(&filenames, &open_options, &options, &diff_options);
+    }
     let fail_first = filenames.len() == 1 || fail_first;
     let mut errors: i32 = 0;
 
@@ -102,12 +154,7 @@ fn run() -> Result<(), Whatever> {
         // Write filename to stderr to make piping easier, i.e. dicom-dump -o json file.dcm | jq
         eprintln!("{}: ", filename.display());
 
-        let open_options = match read_until {
-            Some(stop_tag) => OpenFileOptions::new().read_until(stop_tag),
-            None => OpenFileOptions::new(),
-        };
-
-        match open_options.open_file(filename) {
+        match open_options.clone().open_file(filename) {
             Err(e) => {
                 eprintln!("{}", Report::from_error(e));
                 if fail_first {
@@ -143,6 +190,42 @@ fn run() -> Result<(), Whatever> {
     std::process::exit(errors);
 }
 
+/// Print the differences between the data sets of two DICOM files,
+/// exiting with 0 if there are none and 1 otherwise.
+fn run_diff(
+    filenames: &[PathBuf],
+    open_options: &OpenFileOptions,
+    options: &DumpOptions,
+    diff_options: &DiffOptions,
+) -> ! {
+    let (left, right) = match filenames {
+        [left, right] => (left, right),
+        _ => {
+            eprintln!("[ERROR] --diff requires exactly two files");
+            std::process::exit(ERROR_READ);
+        }
+    };
+
+    let open = |filename: &PathBuf| {
+        open_options.clone().open_file(filename).unwrap_or_else(|e| {
+            eprintln!("{}: {}", filename.display(), Report::from_error(e));
+            std::process::exit(ERROR_READ);
+        })
+    };
+    let left = open(left);
+    let right = open(right);
+
+    let differences = diff_options.diff(&left, &right);
+    if let Err(e) = options.dump_diff(&differences) {
+        if e.kind() != ErrorKind::BrokenPipe {
+            eprintln!("[ERROR] {}", Report::from_error(e));
+            std::process::exit(ERROR_PRINT);
+        }
+    }
+
+    std::process::exit(if differences.is_empty() { 0 } else { 1 });
+}
+
 #[cfg(test)]
 mod tests {
     use crate::App;
@@ -1,13 +1,16 @@
-use clap::Parser;
-use dicom_core::{dicom_value, DataElement, VR};
-use dicom_dictionary_std::{tags, uids};
-use dicom_object::{mem::InMemDicomObject, StandardDataDictionary};
+use clap::{Args, Parser};
 use dicom_ul::{
-    association::client::ClientAssociationOptions,
-    pdu::{self, PDataValueType, Pdu},
+    association::{client::ClientAssociationOptions, ClientAssociation, Transport},
+    dimse::{
+        composite::{CEchoRq, CEchoRsp},
+        DimseMessage, StatusType,
+    },
+    registry::{AeEntry, AeRegistry},
+    tls::{TlsOptions, TlsProfile},
 };
-use pdu::PDataValue;
 use snafu::{prelude::*, Whatever};
+use std::convert::TryFrom;
+use std::path::PathBuf;
 use tracing::{debug, error, info, warn, Level};
 
 /// DICOM C-ECHO SCU
@@ -16,8 +19,13 @@ use tracing::{debug, error, info, warn, Level};
 struct App {
     /// socket address to SCP,
     /// optionally with AE title
-    /// (example: "QUERY-SCP@127.0.0.1:1045")
+    /// (example: "QUERY-SCP@127.0.0.1:1045"),
+    /// or the name of a node in the AE configuration
     addr: String,
+    /// AE configuration file describing the known nodes, in TOML or JSON
+    /// [default: $DICOM_AE_CONFIG]
+    #[arg(long = "ae-config")]
+    ae_config: Option<PathBuf>,
     /// verbose mode
     #[arg(short = 'v', long = "verbose")]
     verbose: bool,
@@ -31,6 +39,34 @@ struct App {
     /// overrides AE title in address if present [default: ANY-SCP]
     #[arg(long = "called-ae-title")]
     called_ae_title: Option<String>,
+    #[command(flatten)]
+    tls: TlsArgs,
+}
+
+/// Secure transport options
+#[derive(Debug, Args)]
+struct TlsArgs {
+    /// secure the association with TLS
+    #[arg(long = "tls")]
+    tls: bool,
+    /// PEM file with the certificate chain of this node,
+    /// for client authentication
+    #[arg(long = "tls-cert", requires = "tls", requires = "key")]
+    cert: Option<PathBuf>,
+    /// PEM file with the private key of this node
+    #[arg(long = "tls-key", requires = "tls", requires = "cert")]
+    key: Option<PathBuf>,
+    /// PEM file with the CA certificates trusted to verify the SCP
+    #[arg(long = "tls-ca", requires = "tls")]
+    ca: Option<PathBuf>,
+    /// the TLS secure transport connection profile
+    /// (bcp195, non-downgrading, or extended) [default: bcp195]
+    #[arg(long = "tls-profile", requires = "tls")]
+    profile: Option<TlsProfile>,
+    /// the name to verify the SCP's certificate against
+    /// [default: host name in the address]
+    #[arg(long = "tls-server-name", requires = "tls")]
+    server_name: Option<String>,
 }
 
 fn main() {
@@ -43,10 +79,12 @@ fn main() {
 fn run() -> Result<(), Whatever> {
     let App {
         addr,
+        ae_config,
         verbose,
         message_id,
         called_ae_title,
         calling_ae_title,
+        tls,
     } = App::parse();
 
     tracing::subscriber::set_global_default(
@@ -59,16 +97,65 @@ fn run() -> Result<(), Whatever> {
         eprintln!("[ERROR] {}", snafu::Report::from_error(e));
     });
 
+    let registry = AeRegistry::locate(ae_config.as_deref())
+        .whatever_context("Could not load AE configuration")?;
+    let node = registry.as_ref().and_then(|registry| registry.get(&addr));
+    let addr = match node {
+        Some(node) => node
+            .address()
+            .with_whatever_context(|| format!("No address configured for node {}", addr))?,
+        None => addr,
+    };
+
     let mut association_opt = ClientAssociationOptions::new()
         .with_abstract_syntax("1.2.840.10008.1.1")
         .calling_ae_title(calling_ae_title);
+    if let Some(node) = node {
+        association_opt = node
+            .configure(association_opt)
+            .whatever_context("Could not configure association with node")?;
+    }
     if let Some(called_ae_title) = called_ae_title {
         association_opt = association_opt.called_ae_title(called_ae_title);
     }
-    let mut association = association_opt
-        .establish_with(&addr)
-        .whatever_context("Could not establish association with SCP")?;
 
+    let use_tls = tls.tls || node.is_some_and(AeEntry::uses_tls);
+    if tls.tls {
+        let mut tls_options = TlsOptions::new().profile(tls.profile.unwrap_or_default());
+        if let Some(ca) = tls.ca {
+            tls_options = tls_options.ca_certificates(ca);
+        }
+        if let (Some(cert), Some(key)) = (tls.cert, tls.key) {
+            tls_options = tls_options.certificate_chain(cert).private_key(key);
+        }
+        let tls_config = tls_options
+            .client_config()
+            .whatever_context("Could not set up TLS")?;
+        association_opt = association_opt.tls_config(tls_config);
+        if let Some(server_name) = tls.server_name {
+            association_opt = association_opt.server_name(server_name);
+        }
+    }
+
+    if use_tls {
+        let association = association_opt
+            .establish_with_tls(&addr)
+            .whatever_context("Could not establish association with SCP")?;
+        echo(association, &addr, message_id, verbose)
+    } else {
+        let association = association_opt
+            .establish_with(&addr)
+            .whatever_context("Could not establish association with SCP")?;
+        echo(association, &addr, message_id, verbose)
+    }
+}
+
+fn echo<S: Transport>(
+    mut association: ClientAssociation<S>,
+    addr: &str,
+    message_id: u16,
+    verbose: bool,
+) -> Result<(), Whatever> {
     let pc = association
         .presentation_contexts()
         .first()
@@ -79,25 +166,8 @@ fn run() -> Result<(), Whatever> {
         debug!("Association with {} successful", addr);
     }
 
-    // commands are always in implicit VR LE
-    let ts = dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased();
-
-    let obj = create_echo_command(message_id);
-
-    let mut data = Vec::new();
-
-    obj.write_dataset_with_ts(&mut data, &ts)
-        .whatever_context("Failed to construct C-ECHO request")?;
-
     association
-        .send(&Pdu::PData {
-            data: vec![PDataValue {
-                presentation_context_id: pc.id,
-                value_type: PDataValueType::Command,
-                is_last: true,
-                data,
-            }],
-        })
+        .send_dimse(&DimseMessage::new(pc.id, CEchoRq::new(message_id)))
         .whatever_context("Failed to send C-ECHO request")?;
 
     if verbose {
@@ -107,88 +177,51 @@ fn run() -> Result<(), Whatever> {
         );
     }
 
-    let pdu = association
-        .receive()
+    let msg = association
+        .receive_dimse()
         .whatever_context("Could not receive response from SCP")?;
 
-    match pdu {
-        Pdu::PData { data } => {
-            let data_value = &data[0];
-            let v = &data_value.data;
+    if verbose {
+        let obj = msg.command.to_command_set(msg.has_data_set());
+        dicom_dump::dump_object(&obj).whatever_context("Failed to output DICOM response")?;
+    }
 
-            let obj = InMemDicomObject::read_dataset_with_ts(v.as_slice(), &ts)
-                .whatever_context("Failed to read response dataset from SCP")?;
-            if verbose {
-                dicom_dump::dump_object(&obj)
-                    .whatever_context("Failed to output DICOM response")?;
-            }
+    let rsp = CEchoRsp::try_from(msg.command).whatever_context("Unexpected response from SCP")?;
 
-            // check status
-            let status = obj
-                .element(tags::STATUS)
-                .whatever_context("Missing Status code in response")?
-                .to_int::<u16>()
-                .whatever_context("Status code in response is not a valid integer")?;
+    // check status
+    let status = rsp.status;
+    if verbose {
+        debug!("Status: {:04X}H", status.code());
+    }
+    match status.status_type() {
+        StatusType::Success => {
             if verbose {
-                debug!("Status: {:04X}H", status);
-            }
-            match status {
-                // Success
-                0 => {
-                    if verbose {
-                        info!("✓ C-ECHO successful");
-                    }
-                }
-                // Warning
-                1 | 0x0107 | 0x0116 | 0xB000..=0xBFFF => {
-                    warn!("Possible issue in C-ECHO (status code {:04X}H)", status);
-                }
-                0xFF00 | 0xFF01 => {
-                    warn!(
-                        "Possible issue in C-ECHO: status is pending (status code {:04X}H)",
-                        status
-                    );
-                }
-                0xFE00 => {
-                    warn!("Operation cancelled");
-                }
-                _ => {
-                    error!("C-ECHO failed (status code {:04X}H)", status);
-                }
-            }
-
-            // msg ID response, should be equal to sent msg ID
-            let got_msg_id: u16 = obj
-                .element(tags::MESSAGE_ID_BEING_RESPONDED_TO)
-                .whatever_context("Could not retrieve Message ID from response")?
-                .to_int()
-                .whatever_context("Message ID is not a valid integer")?;
-
-            if message_id != got_msg_id {
-                whatever!("Message ID mismatch");
+                info!("✓ C-ECHO successful");
             }
         }
-        pdu => whatever!("Unexpected PDU {:?}", pdu),
+        StatusType::Warning => {
+            warn!("Possible issue in C-ECHO (status code {})", status);
+        }
+        StatusType::Pending => {
+            warn!(
+                "Possible issue in C-ECHO: status is pending (status code {})",
+                status
+            );
+        }
+        StatusType::Cancel => {
+            warn!("Operation cancelled");
+        }
+        StatusType::Failure => {
+            error!("C-ECHO failed (status code {})", status);
+        }
     }
 
-    Ok(())
-}
+    // msg ID response, should be equal to sent msg ID
+    if message_id != rsp.message_id_being_responded_to {
+        whatever!("Message ID mismatch");
+    }
 
-fn create_echo_command(message_id: u16) -> InMemDicomObject<StandardDataDictionary> {
-    InMemDicomObject::command_from_element_iter([
-        // service
-        DataElement::new(tags::AFFECTED_SOP_CLASS_UID, VR::UI, uids::VERIFICATION),
-        // command
-        DataElement::new(tags::COMMAND_FIELD, VR::US, dicom_value!(U16, [0x0030])),
-        // message ID
-        DataElement::new(tags::MESSAGE_ID, VR::US, dicom_value!(U16, [message_id])),
-        // data set type
-        DataElement::new(
-            tags::COMMAND_DATA_SET_TYPE,
-            VR::US,
-            dicom_value!(U16, [0x0101]),
-        ),
-    ])
+    Ok(())
 }
 
 #[cfg(test)]
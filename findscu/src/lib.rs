@@ -0,0 +1,6 @@
+//! DICOM C-FIND SCU support library
+//!
+//! This library exposes the query building facilities
+//! of the `dicom-findscu` tool,
+//! so that they can be reused by other query/retrieve tools.
+pub mod query;
@@ -1,31 +1,37 @@
-use clap::Parser;
-use dicom_core::dicom_value;
+use clap::{Args, Parser};
 use dicom_core::{DataElement, PrimitiveValue, VR};
 use dicom_dictionary_std::{tags, uids};
 use dicom_dump::DumpOptions;
 use dicom_encoding::transfer_syntax;
-use dicom_object::{mem::InMemDicomObject, open_file, StandardDataDictionary};
-use dicom_transfer_syntax_registry::{entries, TransferSyntaxRegistry};
-use dicom_ul::pdu::Pdu;
+use dicom_findscu::query::parse_queries;
+use dicom_object::{mem::InMemDicomObject, open_file};
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
 use dicom_ul::{
-    association::ClientAssociationOptions,
-    pdu::{PDataValue, PDataValueType},
+    association::{ClientAssociation, ClientAssociationOptions, Transport},
+    dimse::{
+        composite::{CFindRq, CFindRsp},
+        DimseMessage, StatusType,
+    },
+    registry::{AeEntry, AeRegistry},
+    tls::{TlsOptions, TlsProfile},
 };
-use query::parse_queries;
 use snafu::prelude::*;
-use std::io::{stderr, BufRead as _, Read};
+use std::io::{stderr, BufRead as _};
 use std::path::PathBuf;
 use tracing::{debug, error, info, warn, Level};
 use transfer_syntax::TransferSyntaxIndex;
 
-mod query;
-
 /// DICOM C-FIND SCU
 #[derive(Debug, Parser)]
 #[command(version)]
 struct App {
-    /// socket address to FIND SCP (example: "127.0.0.1:1045")
+    /// socket address to FIND SCP (example: "127.0.0.1:1045"),
+    /// or the name of a node in the AE configuration
     addr: String,
+    /// AE configuration file describing the known nodes, in TOML or JSON
+    /// [default: $DICOM_AE_CONFIG]
+    #[arg(long = "ae-config")]
+    ae_config: Option<PathBuf>,
     /// a DICOM file representing the query object
     file: Option<PathBuf>,
     /// a file containing lines of queries
@@ -66,6 +72,35 @@ struct App {
         conflicts_with = "patient"
     )]
     mwl: bool,
+
+    #[command(flatten)]
+    tls: TlsArgs,
+}
+
+/// Secure transport options
+#[derive(Debug, Args)]
+struct TlsArgs {
+    /// secure the association with TLS
+    #[arg(long = "tls")]
+    tls: bool,
+    /// PEM file with the certificate chain of this node,
+    /// for client authentication
+    #[arg(long = "tls-cert", requires = "tls", requires = "key")]
+    cert: Option<PathBuf>,
+    /// PEM file with the private key of this node
+    #[arg(long = "tls-key", requires = "tls", requires = "cert")]
+    key: Option<PathBuf>,
+    /// PEM file with the CA certificates trusted to verify the SCP
+    #[arg(long = "tls-ca", requires = "tls")]
+    ca: Option<PathBuf>,
+    /// the TLS secure transport connection profile
+    /// (bcp195, non-downgrading, or extended) [default: bcp195]
+    #[arg(long = "tls-profile", requires = "tls")]
+    profile: Option<TlsProfile>,
+    /// the name to verify the SCP's certificate against
+    /// [default: host name in the address]
+    #[arg(long = "tls-server-name", requires = "tls")]
+    server_name: Option<String>,
 }
 
 fn main() {
@@ -85,8 +120,8 @@ enum Error {
     /// Could not construct DICOM command
     CreateCommand { source: dicom_object::ReadError },
 
-    /// Could not read DICOM command
-    ReadCommand { source: dicom_object::ReadError },
+    /// Could not read response data set
+    ReadDataSet { source: dicom_ul::dimse::Error },
 
     /// Could not dump DICOM output
     DumpOutput { source: std::io::Error },
@@ -176,6 +211,7 @@ fn build_query(
 fn run() -> Result<(), Error> {
     let App {
         addr,
+        ae_config,
         file,
         query_file,
         query,
@@ -186,6 +222,7 @@ fn run() -> Result<(), Error> {
         patient,
         study,
         mwl,
+        tls,
     } = App::parse();
 
     tracing::subscriber::set_global_default(
@@ -212,6 +249,16 @@ fn run() -> Result<(), Error> {
         _ => unreachable!("Unexpected flag combination"),
     };
 
+    let registry = AeRegistry::locate(ae_config.as_deref())
+        .whatever_context("Could not load AE configuration")?;
+    let node = registry.as_ref().and_then(|registry| registry.get(&addr));
+    let addr = match node {
+        Some(node) => node
+            .address()
+            .with_whatever_context(|| format!("No address configured for node {addr}"))?,
+        None => addr,
+    };
+
     if verbose {
         info!("Establishing association with '{}'...", &addr);
     }
@@ -221,12 +268,48 @@ fn run() -> Result<(), Error> {
         .calling_ae_title(calling_ae_title)
         .max_pdu_length(max_pdu_length);
 
+    if let Some(node) = node {
+        scu_opt = node
+            .configure(scu_opt)
+            .whatever_context("Could not configure association with node")?;
+    }
     if let Some(called_ae_title) = called_ae_title {
         scu_opt = scu_opt.called_ae_title(called_ae_title);
     }
 
-    let mut scu = scu_opt.establish_with(&addr).context(InitScuSnafu)?;
+    let use_tls = tls.tls || node.is_some_and(AeEntry::uses_tls);
+    if tls.tls {
+        let mut tls_options = TlsOptions::new().profile(tls.profile.unwrap_or_default());
+        if let Some(ca) = tls.ca {
+            tls_options = tls_options.ca_certificates(ca);
+        }
+        if let (Some(cert), Some(key)) = (tls.cert, tls.key) {
+            tls_options = tls_options.certificate_chain(cert).private_key(key);
+        }
+        let tls_config = tls_options
+            .client_config()
+            .whatever_context("Could not set up TLS")?;
+        scu_opt = scu_opt.tls_config(tls_config);
+        if let Some(server_name) = tls.server_name {
+            scu_opt = scu_opt.server_name(server_name);
+        }
+    }
 
+    if use_tls {
+        let scu = scu_opt.establish_with_tls(&addr).context(InitScuSnafu)?;
+        find(scu, abstract_syntax, &dcm_query, verbose)
+    } else {
+        let scu = scu_opt.establish_with(&addr).context(InitScuSnafu)?;
+        find(scu, abstract_syntax, &dcm_query, verbose)
+    }
+}
+
+fn find<S: Transport>(
+    mut scu: ClientAssociation<S>,
+    abstract_syntax: &str,
+    dcm_query: &InMemDicomObject,
+    verbose: bool,
+) -> Result<(), Error> {
     if verbose {
         info!("Association established");
     }
@@ -252,42 +335,18 @@ fn run() -> Result<(), Error> {
         debug!("Transfer Syntax: {}", ts.name());
     }
 
-    let cmd = find_req_command(abstract_syntax, 1);
-
-    let mut cmd_data = Vec::with_capacity(128);
-    cmd.write_dataset_with_ts(&mut cmd_data, &entries::IMPLICIT_VR_LITTLE_ENDIAN.erased())
-        .whatever_context("Failed to write command")?;
-
-    let mut iod_data = Vec::with_capacity(128);
-    dcm_query
-        .write_dataset_with_ts(&mut iod_data, ts)
+    let msg = DimseMessage::new(pc_selected_id, CFindRq::new(1, abstract_syntax))
+        .with_dataset(dcm_query, ts)
         .whatever_context("failed to write identifier dataset")?;
 
-    let nbytes = cmd_data.len() + iod_data.len();
-
     if verbose {
-        debug!("Sending query ({} B)...", nbytes);
+        debug!(
+            "Sending query ({} B)...",
+            msg.data.as_ref().map(|d| d.len()).unwrap_or(0)
+        );
     }
 
-    let pdu = Pdu::PData {
-        data: vec![PDataValue {
-            presentation_context_id: pc_selected_id,
-            value_type: PDataValueType::Command,
-            is_last: true,
-            data: cmd_data,
-        }],
-    };
-    scu.send(&pdu).whatever_context("Could not send command")?;
-
-    let pdu = Pdu::PData {
-        data: vec![PDataValue {
-            presentation_context_id: pc_selected_id,
-            value_type: PDataValueType::Data,
-            is_last: true,
-            data: iod_data,
-        }],
-    };
-    scu.send(&pdu)
+    scu.send_dimse(&msg)
         .whatever_context("Could not send C-Find request")?;
 
     if verbose {
@@ -296,108 +355,72 @@ fn run() -> Result<(), Error> {
 
     let mut i = 0;
     loop {
-        let rsp_pdu = scu
-            .receive()
-            .whatever_context("Failed to receive response from remote node")?;
-
-        match rsp_pdu {
-            Pdu::PData { data } => {
-                if data.is_empty() {
-                    error!("Empty PData response");
-                    break;
-                } else if ![1, 2].contains(&data.len()) {
-                    warn!(
-                        "Unexpected number of PDataValue parts: {} (allowed 1 or 2)",
-                        data.len()
-                    );
-                    break;
-                }
+        let rsp = match scu.receive_dimse() {
+            Ok(rsp) => rsp,
+            Err(dicom_ul::association::client::Error::UnexpectedResponse { pdu, .. }) => {
+                error!("Unexpected SCP response: {:?}", pdu);
+                let _ = scu.abort();
+                std::process::exit(-2);
+            }
+            Err(e) => {
+                return Err(e).whatever_context("Failed to receive response from remote node")
+            }
+        };
 
-                let data_value = &data[0];
+        if verbose {
+            eprintln!("Match #{} Response command:", i);
+            DumpOptions::new()
+                .dump_object_to(stderr(), &rsp.command.to_command_set(rsp.has_data_set()))
+                .context(DumpOutputSnafu)?;
+        }
 
-                let cmd_obj = InMemDicomObject::read_dataset_with_ts(
-                    &data_value.data[..],
-                    &entries::IMPLICIT_VR_LITTLE_ENDIAN.erased(),
-                )
-                .context(ReadCommandSnafu)?;
+        let dcm = rsp.dataset(ts).context(ReadDataSetSnafu)?;
+        let rsp_cmd = CFindRsp::try_from(rsp.command)
+            .whatever_context("Unexpected response from remote node")?;
+        let status = rsp_cmd.status;
+        match status.status_type() {
+            StatusType::Success => {
                 if verbose {
-                    eprintln!("Match #{} Response command:", i);
-                    DumpOptions::new()
-                        .dump_object_to(stderr(), &cmd_obj)
-                        .context(DumpOutputSnafu)?;
+                    debug!("Matching is complete");
+                }
+                if i == 0 {
+                    info!("No results matching query");
+                }
+                break;
+            }
+            StatusType::Pending => {
+                if verbose {
+                    debug!("Operation pending: {:x}", status.code());
                 }
-                let status = cmd_obj
-                    .get(tags::STATUS)
-                    .whatever_context("status code from response is missing")?
-                    .to_int::<u16>()
-                    .whatever_context("failed to read status code")?;
-                if status == 0 {
-                    if verbose {
-                        debug!("Matching is complete");
-                    }
-                    if i == 0 {
-                        info!("No results matching query");
-                    }
-                    break;
-                } else if status == 0xFF00 || status == 0xFF01 {
-                    if verbose {
-                        debug!("Operation pending: {:x}", status);
-                    }
 
-                    // fetch DICOM data
-                    // Some worklist servers sends both command and data in the same PData
-                    // So there is no need to download another PData
-                    let dcm = if let Some(second_pdata) = data.get(1) {
-                        InMemDicomObject::read_dataset_with_ts(second_pdata.data.as_slice(), ts)
-                            .whatever_context("Could not read response data set")?
-                    } else {
-                        let mut rsp = scu.receive_pdata();
-                        let mut response_data = Vec::new();
-                        rsp.read_to_end(&mut response_data)
-                            .whatever_context("Failed to read response data")?;
-
-                        InMemDicomObject::read_dataset_with_ts(&response_data[..], ts)
-                            .whatever_context("Could not read response data set")?
-                    };
-
-                    println!(
-                        "------------------------ Match #{} ------------------------",
-                        i
-                    );
-                    DumpOptions::new()
-                        .dump_object(&dcm)
-                        .context(DumpOutputSnafu)?;
-
-                    // check DICOM status in response data,
-                    // as some implementations might report status code 0
-                    // upon sending the response data
-                    if let Some(status) = dcm.get(tags::STATUS) {
-                        let status = status.to_int::<u16>().ok();
-                        if status == Some(0) {
-                            if verbose {
-                                debug!("Matching is complete");
-                            }
-                            break;
+                let dcm = dcm.whatever_context("Missing response data set")?;
+
+                println!(
+                    "------------------------ Match #{} ------------------------",
+                    i
+                );
+                DumpOptions::new()
+                    .dump_object(&dcm)
+                    .context(DumpOutputSnafu)?;
+
+                // check DICOM status in response data,
+                // as some implementations might report status code 0
+                // upon sending the response data
+                if let Some(status) = dcm.get(tags::STATUS) {
+                    let status = status.to_int::<u16>().ok();
+                    if status == Some(0) {
+                        if verbose {
+                            debug!("Matching is complete");
                         }
+                        break;
                     }
-
-                    i += 1;
-                } else {
-                    warn!("Operation failed (status code {})", status);
-                    break;
                 }
-            }
 
-            pdu @ Pdu::Unknown { .. }
-            | pdu @ Pdu::AssociationRQ { .. }
-            | pdu @ Pdu::AssociationAC { .. }
-            | pdu @ Pdu::AssociationRJ { .. }
-            | pdu @ Pdu::ReleaseRQ
-            | pdu @ Pdu::ReleaseRP
-            | pdu @ Pdu::AbortRQ { .. } => {
-                error!("Unexpected SCP response: {:?}", pdu);
-                let _ = scu.abort();
-                std::process::exit(-2);
+                i += 1;
+            }
+            _ => {
+                warn!("Operation failed (status code {})", status);
+                break;
             }
         }
     }
@@ -406,42 +429,6 @@ fn run() -> Result<(), Error> {
     Ok(())
 }
 
-fn find_req_command(
-    sop_class_uid: &str,
-    message_id: u16,
-) -> InMemDicomObject<StandardDataDictionary> {
-    InMemDicomObject::command_from_element_iter([
-        // SOP Class UID
-        DataElement::new(
-            tags::AFFECTED_SOP_CLASS_UID,
-            VR::UI,
-            PrimitiveValue::from(sop_class_uid),
-        ),
-        // command field
-        DataElement::new(
-            tags::COMMAND_FIELD,
-            VR::US,
-            // 0020H: C-FIND-RQ message
-            dicom_value!(U16, [0x0020]),
-        ),
-        // message ID
-        DataElement::new(tags::MESSAGE_ID, VR::US, dicom_value!(U16, [message_id])),
-        //priority
-        DataElement::new(
-            tags::PRIORITY,
-            VR::US,
-            // medium
-            dicom_value!(U16, [0x0000]),
-        ),
-        // data set type
-        DataElement::new(
-            tags::COMMAND_DATA_SET_TYPE,
-            VR::US,
-            dicom_value!(U16, [0x0001]),
-        ),
-    ])
-}
-
 #[cfg(test)]
 mod tests {
     use crate::App;
@@ -42,6 +42,8 @@ impl FromStr for TermQuery {
     }
 }
 
+/// Extend the base object with the given query terms,
+/// each of the form `«field_path»=«field_value»`.
 pub fn parse_queries<T>(base: InMemDicomObject, qs: &[T]) -> Result<InMemDicomObject, Whatever>
 where
     T: AsRef<str>,
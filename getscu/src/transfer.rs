@@ -0,0 +1,42 @@
+//! Storage SOP classes proposed for C-STORE sub-operations
+
+use dicom_dictionary_std::uids::*;
+
+/// The storage SOP classes for which the SCP role is proposed by default
+#[allow(deprecated)]
+pub static STORAGE_SOP_CLASSES: &[&str] = &[
+    CT_IMAGE_STORAGE,
+    ENHANCED_CT_IMAGE_STORAGE,
+    STANDALONE_CURVE_STORAGE,
+    STANDALONE_OVERLAY_STORAGE,
+    SECONDARY_CAPTURE_IMAGE_STORAGE,
+    ULTRASOUND_IMAGE_STORAGE_RETIRED,
+    NUCLEAR_MEDICINE_IMAGE_STORAGE_RETIRED,
+    MR_IMAGE_STORAGE,
+    ENHANCED_MR_IMAGE_STORAGE,
+    MR_SPECTROSCOPY_STORAGE,
+    ENHANCED_MR_COLOR_IMAGE_STORAGE,
+    ULTRASOUND_MULTI_FRAME_IMAGE_STORAGE_RETIRED,
+    COMPUTED_RADIOGRAPHY_IMAGE_STORAGE,
+    DIGITAL_X_RAY_IMAGE_STORAGE_FOR_PRESENTATION,
+    DIGITAL_X_RAY_IMAGE_STORAGE_FOR_PROCESSING,
+    ENCAPSULATED_PDF_STORAGE,
+    ENCAPSULATED_CDA_STORAGE,
+    ENCAPSULATED_STL_STORAGE,
+    GRAYSCALE_SOFTCOPY_PRESENTATION_STATE_STORAGE,
+    POSITRON_EMISSION_TOMOGRAPHY_IMAGE_STORAGE,
+    BREAST_TOMOSYNTHESIS_IMAGE_STORAGE,
+    BREAST_PROJECTION_X_RAY_IMAGE_STORAGE_FOR_PRESENTATION,
+    BREAST_PROJECTION_X_RAY_IMAGE_STORAGE_FOR_PROCESSING,
+    ENHANCED_PET_IMAGE_STORAGE,
+    RT_IMAGE_STORAGE,
+    NUCLEAR_MEDICINE_IMAGE_STORAGE,
+    ULTRASOUND_MULTI_FRAME_IMAGE_STORAGE,
+    MULTI_FRAME_SINGLE_BIT_SECONDARY_CAPTURE_IMAGE_STORAGE,
+    MULTI_FRAME_GRAYSCALE_BYTE_SECONDARY_CAPTURE_IMAGE_STORAGE,
+    MULTI_FRAME_GRAYSCALE_WORD_SECONDARY_CAPTURE_IMAGE_STORAGE,
+    MULTI_FRAME_TRUE_COLOR_SECONDARY_CAPTURE_IMAGE_STORAGE,
+    BASIC_TEXT_SR_STORAGE,
+    ENHANCED_SR_STORAGE,
+    COMPREHENSIVE_SR_STORAGE,
+];
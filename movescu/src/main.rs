@@ -0,0 +1,359 @@
+use clap::Parser;
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_dump::DumpOptions;
+use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
+use dicom_findscu::query::parse_queries;
+use dicom_object::{mem::InMemDicomObject, open_file};
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use dicom_ul::{
+    association::ClientAssociationOptions,
+    dimse::{
+        composite::{CMoveRq, CMoveRsp, SubOperations},
+        DimseMessage, StatusType,
+    },
+    registry::{AeEntry, AeRegistry},
+};
+use snafu::prelude::*;
+use std::io::{stderr, BufRead as _};
+use std::path::PathBuf;
+use tracing::{debug, error, info, warn, Level};
+
+/// DICOM C-MOVE SCU
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// socket address to MOVE SCP (example: "127.0.0.1:1045"),
+    /// or the name of a node in the AE configuration
+    addr: String,
+    /// AE configuration file describing the known nodes, in TOML or JSON
+    /// [default: $DICOM_AE_CONFIG]
+    #[arg(long = "ae-config")]
+    ae_config: Option<PathBuf>,
+    /// a DICOM file representing the retrieve identifier
+    file: Option<PathBuf>,
+    /// a file containing lines of query terms
+    #[arg(long)]
+    query_file: Option<PathBuf>,
+    /// a sequence of query terms
+    #[arg(short('q'))]
+    query: Vec<String>,
+    /// the AE title of the destination of the retrieved instances
+    /// (default is the calling AE title)
+    #[arg(short = 'm', long = "move-destination")]
+    move_destination: Option<String>,
+
+    /// verbose mode
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+    /// the calling AE title
+    #[arg(long = "calling-ae-title", default_value = "MOVE-SCU")]
+    calling_ae_title: String,
+    /// the called AE title
+    #[arg(long = "called-ae-title")]
+    called_ae_title: Option<String>,
+    /// the maximum PDU length
+    #[arg(
+        long = "max-pdu-length",
+        default_value = "16384",
+        value_parser(clap::value_parser!(u32).range(4096..=131_072))
+    )]
+    max_pdu_length: u32,
+
+    /// use patient root information model
+    #[arg(short = 'P', long, conflicts_with = "study")]
+    patient: bool,
+    /// use study root information model (default)
+    #[arg(short = 'S', long, conflicts_with = "patient")]
+    study: bool,
+}
+
+fn main() {
+    run().unwrap_or_else(|err| {
+        error!("{}", snafu::Report::from_error(err));
+        std::process::exit(-2);
+    });
+}
+
+#[derive(Debug, Snafu)]
+enum Error {
+    /// Could not initialize SCU
+    InitScu {
+        source: dicom_ul::association::client::Error,
+    },
+
+    /// Could not construct DICOM command
+    CreateCommand { source: dicom_object::ReadError },
+
+    /// Could not read response data set
+    ReadDataSet { source: dicom_ul::dimse::Error },
+
+    /// Could not dump DICOM output
+    DumpOutput { source: std::io::Error },
+
+    #[snafu(display("retrieve failed (status code {})", status))]
+    Failed { status: dicom_ul::dimse::Status },
+
+    #[snafu(whatever, display("{}", message))]
+    Other {
+        message: String,
+        #[snafu(source(from(Box<dyn std::error::Error + 'static>, Some)))]
+        source: Option<Box<dyn std::error::Error + 'static>>,
+    },
+}
+
+fn build_identifier(
+    file: Option<PathBuf>,
+    query_file: Option<PathBuf>,
+    q: Vec<String>,
+    patient: bool,
+    verbose: bool,
+) -> Result<InMemDicomObject, Error> {
+    // read identifier file if provided
+    let (base_obj, mut has_base) = if let Some(file) = file {
+        if verbose {
+            info!("Opening file '{}'...", file.display());
+        }
+
+        (
+            open_file(file).context(CreateCommandSnafu)?.into_inner(),
+            true,
+        )
+    } else {
+        (InMemDicomObject::new_empty(), false)
+    };
+
+    // read query terms from text file
+    let mut obj = base_obj;
+    if let Some(query_file) = query_file {
+        let mut queries = Vec::new();
+        let file = std::fs::File::open(query_file).whatever_context("Could not open query file")?;
+        let reader = std::io::BufReader::new(file);
+        for line in reader.lines() {
+            let line = line.whatever_context("Could not read line from query file")?;
+            {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+            }
+            queries.push(line);
+        }
+
+        obj = parse_queries(obj, &queries)
+            .whatever_context("Could not build identifier from query file")?;
+        has_base = true;
+    }
+
+    if q.is_empty() && !has_base {
+        whatever!("Retrieve identifier not specified");
+    }
+
+    let mut obj =
+        parse_queries(obj, &q).whatever_context("Could not build identifier from terms")?;
+
+    // infer query retrieve level if not defined by the user
+    if obj.get(tags::QUERY_RETRIEVE_LEVEL).is_none() {
+        let level = if patient { "PATIENT" } else { "STUDY" };
+        obj.put(DataElement::new(
+            tags::QUERY_RETRIEVE_LEVEL,
+            VR::CS,
+            PrimitiveValue::from(level),
+        ));
+    }
+
+    Ok(obj)
+}
+
+/// Describe the sub-operation counts of a C-MOVE response.
+fn describe(sub_operations: &SubOperations) -> String {
+    let count = |n: Option<u16>| n.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string());
+    format!(
+        "remaining: {}, completed: {}, failed: {}, warning: {}",
+        count(sub_operations.remaining),
+        count(sub_operations.completed),
+        count(sub_operations.failed),
+        count(sub_operations.warning),
+    )
+}
+
+fn run() -> Result<(), Error> {
+    let App {
+        addr,
+        ae_config,
+        file,
+        query_file,
+        query,
+        move_destination,
+        verbose,
+        calling_ae_title,
+        called_ae_title,
+        max_pdu_length,
+        patient,
+        study: _,
+    } = App::parse();
+
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(if verbose { Level::DEBUG } else { Level::INFO })
+            .finish(),
+    )
+    .unwrap_or_else(|e| {
+        error!("{}", snafu::Report::from_error(e));
+    });
+
+    let identifier = build_identifier(file, query_file, query, patient, verbose)?;
+
+    let abstract_syntax = if patient {
+        // Patient Root Query/Retrieve Information Model - MOVE
+        uids::PATIENT_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_MOVE
+    } else {
+        // Study Root Query/Retrieve Information Model – MOVE (default)
+        uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_MOVE
+    };
+
+    let move_destination = move_destination.unwrap_or_else(|| calling_ae_title.clone());
+
+    let registry = AeRegistry::locate(ae_config.as_deref())
+        .whatever_context("Could not load AE configuration")?;
+    let node = registry.as_ref().and_then(|registry| registry.get(&addr));
+    let addr = match node {
+        Some(node) => node
+            .address()
+            .with_whatever_context(|| format!("No address configured for node {addr}"))?,
+        None => addr,
+    };
+
+    if verbose {
+        info!("Establishing association with '{}'...", &addr);
+    }
+
+    let mut scu_opt = ClientAssociationOptions::new()
+        .with_abstract_syntax(abstract_syntax)
+        .calling_ae_title(calling_ae_title)
+        .max_pdu_length(max_pdu_length);
+
+    if let Some(node) = node {
+        scu_opt = node
+            .configure(scu_opt)
+            .whatever_context("Could not configure association with node")?;
+    }
+    if let Some(called_ae_title) = called_ae_title {
+        scu_opt = scu_opt.called_ae_title(called_ae_title);
+    }
+
+    if node.is_some_and(AeEntry::uses_tls) {
+        whatever!("Node {addr} requires TLS, which is not supported by this tool");
+    }
+    let mut scu = scu_opt.establish_with(&addr).context(InitScuSnafu)?;
+
+    if verbose {
+        info!("Association established");
+    }
+
+    let pc_selected = if let Some(pc_selected) = scu.presentation_contexts().first() {
+        pc_selected
+    } else {
+        error!("Could not choose a presentation context");
+        let _ = scu.abort();
+        std::process::exit(-2);
+    };
+    let pc_selected_id = pc_selected.id;
+
+    let ts = if let Some(ts) = TransferSyntaxRegistry.get(&pc_selected.transfer_syntax) {
+        ts
+    } else {
+        error!("Poorly negotiated transfer syntax");
+        let _ = scu.abort();
+        std::process::exit(-2);
+    };
+
+    if verbose {
+        debug!("Transfer Syntax: {}", ts.name());
+    }
+
+    let msg = DimseMessage::new(
+        pc_selected_id,
+        CMoveRq::new(1, abstract_syntax, move_destination.as_str()),
+    )
+    .with_dataset(&identifier, ts)
+    .whatever_context("failed to write identifier dataset")?;
+
+    if verbose {
+        debug!("Sending C-MOVE request to move to {}...", move_destination);
+    }
+
+    scu.send_dimse(&msg)
+        .whatever_context("Could not send C-MOVE request")?;
+
+    let status = loop {
+        let rsp = match scu.receive_dimse() {
+            Ok(rsp) => rsp,
+            Err(dicom_ul::association::client::Error::UnexpectedResponse { pdu, .. }) => {
+                error!("Unexpected SCP response: {:?}", pdu);
+                let _ = scu.abort();
+                std::process::exit(-2);
+            }
+            Err(e) => {
+                return Err(e).whatever_context("Failed to receive response from remote node")
+            }
+        };
+
+        if verbose {
+            eprintln!("Response command:");
+            DumpOptions::new()
+                .dump_object_to(stderr(), &rsp.command.to_command_set(rsp.has_data_set()))
+                .context(DumpOutputSnafu)?;
+        }
+
+        let dcm = rsp.dataset(ts).context(ReadDataSetSnafu)?;
+        let rsp_cmd = CMoveRsp::try_from(rsp.command)
+            .whatever_context("Unexpected response from remote node")?;
+        let status = rsp_cmd.status;
+        match status.status_type() {
+            StatusType::Pending => {
+                info!("Pending ({})", describe(&rsp_cmd.sub_operations));
+            }
+            StatusType::Success => {
+                info!("Retrieve complete ({})", describe(&rsp_cmd.sub_operations));
+                break status;
+            }
+            StatusType::Cancel => {
+                warn!("Retrieve cancelled ({})", describe(&rsp_cmd.sub_operations));
+                break status;
+            }
+            StatusType::Warning | StatusType::Failure => {
+                if let Some(comment) = &rsp_cmd.error_comment {
+                    warn!("{}", comment);
+                }
+                warn!(
+                    "Retrieve finished with status {} ({})",
+                    status,
+                    describe(&rsp_cmd.sub_operations)
+                );
+                if let Some(dcm) = dcm {
+                    // contains the Failed SOP Instance UID List, if any
+                    DumpOptions::new()
+                        .dump_object(&dcm)
+                        .context(DumpOutputSnafu)?;
+                }
+                break status;
+            }
+        }
+    };
+    let _ = scu.release();
+
+    ensure!(!status.is_failure(), FailedSnafu { status });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
@@ -0,0 +1,278 @@
+//! DICOM Modality Performed Procedure Step SCP support library
+//!
+//! This library exposes the [`ProcedureStepStore`] service
+//! of the `dicom-mppsscp` tool,
+//! which keeps the performed procedure steps reported by modalities
+//! in a local directory
+//! when registered in a [`ServiceClassProvider`].
+//!
+//! Each procedure step is kept as a DICOM file
+//! named after its SOP instance UID.
+//! N-CREATE and N-SET requests are checked
+//! against the performed procedure step state model
+//! (see [`dicom_ul::mpps`]):
+//! steps are created _IN PROGRESS_,
+//! and may no longer be updated
+//! once _COMPLETED_ or _DISCONTINUED_.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_mppsscp::{ProcedureStepStore, MODALITY_PERFORMED_PROCEDURE_STEP};
+//! use dicom_ul::scp::{AcceptEcho, ServiceClassProvider};
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let scp = ServiceClassProvider::new()
+//!     .ae_title("MPPS-SCP")
+//!     .with_echo_handler(AcceptEcho)
+//!     .with_normalized_handler(
+//!         [MODALITY_PERFORMED_PROCEDURE_STEP],
+//!         ProcedureStepStore::new("mpps"),
+//!     );
+//! scp.serve(std::net::TcpListener::bind("0.0.0.0:11113")?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`ServiceClassProvider`]: dicom_ul::scp::ServiceClassProvider
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use dicom_dictionary_std::uids;
+use dicom_object::{open_file, FileMetaTableBuilder, InMemDicomObject};
+use dicom_ul::{
+    dimse::{
+        normalized::{NCreateRq, NCreateRsp, NSetRq, NSetRsp},
+        Status,
+    },
+    mpps::{apply_modifications, validate_create, validate_set},
+    scp::{NormalizedHandler, ServiceContext},
+};
+use snafu::{ensure, ResultExt, Snafu};
+use tracing::{error, info, warn};
+
+pub use dicom_ul::mpps::MODALITY_PERFORMED_PROCEDURE_STEP;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[snafu(display("Invalid SOP instance UID {:?}", uid))]
+    InvalidUid { uid: String },
+    #[snafu(display("Could not read procedure step directory {}", path.display()))]
+    ReadDirectory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not read procedure step file {}", path.display()))]
+    ReadStep {
+        path: PathBuf,
+        #[snafu(source(from(dicom_object::ReadError, Box::from)))]
+        source: Box<dicom_object::ReadError>,
+    },
+    #[snafu(display("Could not prepare procedure step file {}", path.display()))]
+    PrepareStep {
+        path: PathBuf,
+        #[snafu(source(from(dicom_object::WithMetaError, Box::from)))]
+        source: Box<dicom_object::WithMetaError>,
+    },
+    #[snafu(display("Could not write procedure step file {}", path.display()))]
+    WriteStep {
+        path: PathBuf,
+        #[snafu(source(from(dicom_object::WriteError, Box::from)))]
+        source: Box<dicom_object::WriteError>,
+    },
+    #[snafu(display("Could not replace procedure step file {}", path.display()))]
+    ReplaceStep {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A directory of performed procedure steps,
+/// answering N-CREATE and N-SET requests
+/// of the Modality Performed Procedure Step SOP class.
+#[derive(Debug)]
+pub struct ProcedureStepStore {
+    dir: PathBuf,
+    /// serializes modifications to the procedure step files
+    lock: Mutex<()>,
+}
+
+impl ProcedureStepStore {
+    /// Keep the procedure steps in the given directory.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        ProcedureStepStore {
+            dir: dir.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// The SOP instance UIDs of all procedure steps in the directory.
+    pub fn step_uids(&self) -> Result<Vec<String>> {
+        let mut step_uids = Vec::new();
+        for entry in std::fs::read_dir(&self.dir).context(ReadDirectorySnafu { path: &self.dir })? {
+            let path = entry
+                .context(ReadDirectorySnafu { path: &self.dir })?
+                .path();
+            if path.extension().map(|ext| ext == "dcm").unwrap_or(false) {
+                if let Some(uid) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    step_uids.push(uid.to_string());
+                }
+            }
+        }
+        step_uids.sort();
+        Ok(step_uids)
+    }
+
+    /// Read the attributes of the procedure step with the given SOP instance UID,
+    /// or `None` if there is no such step.
+    pub fn get(&self, sop_instance_uid: &str) -> Result<Option<InMemDicomObject>> {
+        let path = self.path(sop_instance_uid)?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let obj = open_file(&path).context(ReadStepSnafu { path })?;
+        Ok(Some(obj.into_inner()))
+    }
+
+    /// The path to the file of a procedure step.
+    fn path(&self, sop_instance_uid: &str) -> Result<PathBuf> {
+        // the SOP instance UID becomes the file name
+        ensure!(
+            !sop_instance_uid.is_empty()
+                && sop_instance_uid
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || c == '.'),
+            InvalidUidSnafu {
+                uid: sop_instance_uid
+            }
+        );
+        Ok(self.dir.join(format!("{}.dcm", sop_instance_uid)))
+    }
+
+    /// Write the attributes of a procedure step to its file,
+    /// replacing it at once.
+    fn put(&self, path: &Path, sop_instance_uid: &str, attributes: InMemDicomObject) -> Result<()> {
+        let file_obj = attributes
+            .with_meta(
+                FileMetaTableBuilder::new()
+                    .media_storage_sop_class_uid(MODALITY_PERFORMED_PROCEDURE_STEP)
+                    .media_storage_sop_instance_uid(sop_instance_uid)
+                    .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN),
+            )
+            .context(PrepareStepSnafu { path })?;
+        let tmp_path = path.with_extension("dcm.tmp");
+        file_obj
+            .write_to_file(&tmp_path)
+            .context(WriteStepSnafu { path: &tmp_path })?;
+        std::fs::rename(&tmp_path, path).context(ReplaceStepSnafu { path })
+    }
+
+    fn create(
+        &self,
+        sop_instance_uid: &str,
+        attributes: InMemDicomObject,
+    ) -> Result<(), (Status, Option<String>)> {
+        validate_create(&attributes)
+            .map_err(|violation| (violation.status(), Some(violation.to_string())))?;
+
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let path = self
+            .path(sop_instance_uid)
+            .map_err(|e| (Status::INVALID_ARGUMENT_VALUE, Some(e.to_string())))?;
+        if path.exists() {
+            return Err((Status::DUPLICATE_SOP_INSTANCE, None));
+        }
+        self.put(&path, sop_instance_uid, attributes)
+            .map_err(processing_failure)
+    }
+
+    fn set(
+        &self,
+        sop_instance_uid: &str,
+        modifications: InMemDicomObject,
+    ) -> Result<(), (Status, Option<String>)> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut attributes = match self.get(sop_instance_uid) {
+            Ok(Some(attributes)) => attributes,
+            Ok(None) | Err(Error::InvalidUid { .. }) => {
+                return Err((Status::NO_SUCH_SOP_INSTANCE, None))
+            }
+            Err(e) => return Err(processing_failure(e)),
+        };
+        let status = validate_set(&attributes, &modifications)
+            .map_err(|violation| (violation.status(), Some(violation.to_string())))?;
+        apply_modifications(&mut attributes, modifications);
+        let path = self.path(sop_instance_uid).map_err(processing_failure)?;
+        self.put(&path, sop_instance_uid, attributes)
+            .map_err(processing_failure)?;
+        info!("Procedure step {} is {}", sop_instance_uid, status);
+        Ok(())
+    }
+}
+
+fn processing_failure(e: Error) -> (Status, Option<String>) {
+    error!("{}", snafu::Report::from_error(e));
+    (Status::PROCESSING_FAILURE, None)
+}
+
+impl NormalizedHandler for ProcedureStepStore {
+    fn n_create(
+        &self,
+        _ctx: &ServiceContext,
+        rq: &NCreateRq,
+        dataset: Option<InMemDicomObject>,
+    ) -> (NCreateRsp, Option<InMemDicomObject>) {
+        // the SOP instance UID is assigned here if the requester did not
+        let sop_instance_uid = rq
+            .affected_sop_instance_uid
+            .clone()
+            .unwrap_or_else(|| format!("2.25.{}", uuid::Uuid::new_v4().as_u128()));
+        let mut rsp = NCreateRsp::new(rq, Status::SUCCESS);
+        rsp.affected_sop_instance_uid = Some(sop_instance_uid.clone());
+
+        let result = match dataset {
+            Some(attributes) => self.create(&sop_instance_uid, attributes),
+            None => Err((Status::MISSING_ATTRIBUTE, None)),
+        };
+        match result {
+            Ok(()) => info!("Procedure step {} is IN PROGRESS", sop_instance_uid),
+            Err((status, error_comment)) => {
+                warn!(
+                    "Refused to create procedure step {} ({}): {}",
+                    sop_instance_uid,
+                    status,
+                    error_comment.as_deref().unwrap_or("-")
+                );
+                rsp.status = status;
+                rsp.error_comment = error_comment;
+            }
+        }
+        (rsp, None)
+    }
+
+    fn n_set(
+        &self,
+        _ctx: &ServiceContext,
+        rq: &NSetRq,
+        dataset: Option<InMemDicomObject>,
+    ) -> (NSetRsp, Option<InMemDicomObject>) {
+        let mut rsp = NSetRsp::new(rq, Status::SUCCESS);
+        let result = match dataset {
+            Some(modifications) => self.set(&rq.requested_sop_instance_uid, modifications),
+            None => Err((Status::MISSING_ATTRIBUTE, None)),
+        };
+        if let Err((status, error_comment)) = result {
+            warn!(
+                "Refused to update procedure step {} ({}): {}",
+                rq.requested_sop_instance_uid,
+                status,
+                error_comment.as_deref().unwrap_or("-")
+            );
+            rsp.status = status;
+            rsp.error_comment = error_comment;
+        }
+        (rsp, None)
+    }
+}
@@ -0,0 +1,148 @@
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::tags;
+use dicom_mppsscp::{ProcedureStepStore, MODALITY_PERFORMED_PROCEDURE_STEP};
+use dicom_object::InMemDicomObject;
+use dicom_ul::{
+    association::{client::ClientAssociationOptions, duplex::duplex},
+    dimse::Status,
+    mpps::{create_procedure_step, set_procedure_step, Error, StepStatus},
+    scp::ServiceClassProvider,
+};
+
+static IMPLICIT_VR_LE: &str = "1.2.840.10008.1.2";
+static STEP_UID: &str = "2.25.1001";
+
+fn attributes(status: StepStatus) -> InMemDicomObject {
+    InMemDicomObject::from_element_iter([
+        DataElement::new(tags::PATIENT_ID, VR::LO, PrimitiveValue::from("P1")),
+        DataElement::new(tags::MODALITY, VR::CS, PrimitiveValue::from("CT")),
+        DataElement::new(
+            tags::PERFORMED_PROCEDURE_STEP_ID,
+            VR::SH,
+            PrimitiveValue::from("PPS1"),
+        ),
+        status.to_element(),
+    ])
+}
+
+fn completion() -> InMemDicomObject {
+    InMemDicomObject::from_element_iter([
+        StepStatus::Completed.to_element(),
+        DataElement::new(
+            tags::PERFORMED_PROCEDURE_STEP_END_DATE,
+            VR::DA,
+            PrimitiveValue::from("20240315"),
+        ),
+        DataElement::new(
+            tags::PERFORMED_PROCEDURE_STEP_END_TIME,
+            VR::TM,
+            PrimitiveValue::from("101500"),
+        ),
+    ])
+}
+
+fn refused_status<T: std::fmt::Debug>(result: Result<T, Error>) -> Status {
+    match result {
+        Err(Error::Refused { status, .. }) => status,
+        other => panic!("expected refusal, got {:?}", other),
+    }
+}
+
+/// A procedure step goes through its whole life cycle,
+/// with requests breaking the state model refused along the way.
+#[test]
+fn procedure_step_life_cycle() {
+    let dir = tempfile::tempdir().unwrap();
+    let scp = ServiceClassProvider::new()
+        .ae_title("MPPS-SCP")
+        .with_normalized_handler(
+            [MODALITY_PERFORMED_PROCEDURE_STEP],
+            ProcedureStepStore::new(dir.path()),
+        );
+    let (scu_stream, scp_stream) = duplex(64 * 1024);
+    let scp_handle = std::thread::spawn(move || scp.handle_over(scp_stream));
+
+    let mut association = ClientAssociationOptions::new()
+        .calling_ae_title("MODALITY")
+        .called_ae_title("MPPS-SCP")
+        .with_presentation_context(MODALITY_PERFORMED_PROCEDURE_STEP, vec![IMPLICIT_VR_LE])
+        .establish_over(scu_stream)
+        .unwrap();
+
+    // steps can only be created in progress
+    assert_eq!(
+        refused_status(create_procedure_step(
+            &mut association,
+            1,
+            STEP_UID,
+            &attributes(StepStatus::Completed),
+        )),
+        Status::INVALID_ATTRIBUTE_VALUE
+    );
+    let rsp = create_procedure_step(
+        &mut association,
+        2,
+        STEP_UID,
+        &attributes(StepStatus::InProgress),
+    )
+    .unwrap();
+    assert_eq!(rsp.affected_sop_instance_uid.as_deref(), Some(STEP_UID));
+    assert_eq!(
+        refused_status(create_procedure_step(
+            &mut association,
+            3,
+            STEP_UID,
+            &attributes(StepStatus::InProgress),
+        )),
+        Status::DUPLICATE_SOP_INSTANCE
+    );
+
+    // completion requires an end date and time
+    let no_end = InMemDicomObject::from_element_iter([StepStatus::Completed.to_element()]);
+    assert_eq!(
+        refused_status(set_procedure_step(&mut association, 4, STEP_UID, &no_end)),
+        Status::MISSING_ATTRIBUTE_VALUE
+    );
+    set_procedure_step(&mut association, 5, STEP_UID, &completion()).unwrap();
+
+    // a completed step may no longer be updated
+    let reopen = InMemDicomObject::from_element_iter([StepStatus::InProgress.to_element()]);
+    assert_eq!(
+        refused_status(set_procedure_step(&mut association, 6, STEP_UID, &reopen)),
+        Status::PROCESSING_FAILURE
+    );
+    assert_eq!(
+        refused_status(set_procedure_step(
+            &mut association,
+            7,
+            "2.25.1002",
+            &completion()
+        )),
+        Status::NO_SUCH_SOP_INSTANCE
+    );
+
+    association
+        .release()
+        .expect("did not have a peaceful release");
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+
+    // the final state of the step was kept on disk
+    let store = ProcedureStepStore::new(dir.path());
+    assert_eq!(store.step_uids().unwrap(), vec![STEP_UID.to_string()]);
+    let step = store.get(STEP_UID).unwrap().unwrap();
+    assert_eq!(
+        StepStatus::from_dataset(&step).unwrap().unwrap(),
+        StepStatus::Completed
+    );
+    assert_eq!(
+        step.get(tags::PERFORMED_PROCEDURE_STEP_END_TIME)
+            .unwrap()
+            .to_str()
+            .unwrap(),
+        "101500"
+    );
+    assert_eq!(step.get(tags::PATIENT_ID).unwrap().to_str().unwrap(), "P1");
+}
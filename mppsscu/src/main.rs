@@ -0,0 +1,313 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, ValueEnum};
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::tags;
+use dicom_findscu::query::parse_queries;
+use dicom_object::{open_file, InMemDicomObject};
+use dicom_ul::{
+    association::{ClientAssociation, ClientAssociationOptions, Transport},
+    mpps::{
+        create_procedure_step, set_procedure_step, StepStatus, MODALITY_PERFORMED_PROCEDURE_STEP,
+    },
+    registry::{AeEntry, AeRegistry},
+    tls::{TlsOptions, TlsProfile},
+};
+use snafu::prelude::*;
+use tracing::{error, info, Level};
+
+/// DICOM Modality Performed Procedure Step SCU
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// socket address to MPPS SCP,
+    /// optionally with AE title
+    /// (example: "MPPS-SCP@127.0.0.1:11113"),
+    /// or the name of a node in the AE configuration
+    addr: String,
+    /// AE configuration file describing the known nodes, in TOML or JSON
+    /// [default: $DICOM_AE_CONFIG]
+    #[arg(long = "ae-config")]
+    ae_config: Option<PathBuf>,
+    /// the status of the performed procedure step to report:
+    /// in-progress creates the step (N-CREATE),
+    /// completed and discontinued update it (N-SET)
+    #[arg(value_enum)]
+    status: ReportedStatus,
+    /// the SOP instance UID of the performed procedure step
+    /// [default: a new UID when in progress]
+    #[arg(
+        long = "instance-uid",
+        required_if_eq_any([("status", "completed"), ("status", "discontinued")])
+    )]
+    instance_uid: Option<String>,
+    /// a DICOM file with the attributes to report
+    #[arg(short = 'f', long = "file")]
+    file: Option<PathBuf>,
+    /// an attribute to report, as «tag»=«value»
+    /// (can be repeated)
+    #[arg(short = 'a', long = "attribute")]
+    attributes: Vec<String>,
+
+    /// verbose mode
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+    /// the calling AE title
+    #[arg(long = "calling-ae-title", default_value = "MPPS-SCU")]
+    calling_ae_title: String,
+    /// the called AE title,
+    /// overrides AE title in address if present
+    #[arg(long = "called-ae-title")]
+    called_ae_title: Option<String>,
+    /// the maximum PDU length
+    #[arg(
+        long = "max-pdu-length",
+        default_value = "16384",
+        value_parser(clap::value_parser!(u32).range(4096..=131_072))
+    )]
+    max_pdu_length: u32,
+
+    #[command(flatten)]
+    tls: TlsArgs,
+}
+
+/// Secure transport options
+#[derive(Debug, Args)]
+struct TlsArgs {
+    /// secure the association with TLS
+    #[arg(long = "tls")]
+    tls: bool,
+    /// PEM file with the certificate chain of this node,
+    /// for client authentication
+    #[arg(long = "tls-cert", requires = "tls", requires = "key")]
+    cert: Option<PathBuf>,
+    /// PEM file with the private key of this node
+    #[arg(long = "tls-key", requires = "tls", requires = "cert")]
+    key: Option<PathBuf>,
+    /// PEM file with the CA certificates trusted to verify the SCP
+    #[arg(long = "tls-ca", requires = "tls")]
+    ca: Option<PathBuf>,
+    /// the TLS secure transport connection profile
+    /// (bcp195, non-downgrading, or extended) [default: bcp195]
+    #[arg(long = "tls-profile", requires = "tls")]
+    profile: Option<TlsProfile>,
+    /// the name to verify the SCP's certificate against
+    /// [default: host name in the address]
+    #[arg(long = "tls-server-name", requires = "tls")]
+    server_name: Option<String>,
+}
+
+/// The status of the performed procedure step to report.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum ReportedStatus {
+    InProgress,
+    Completed,
+    Discontinued,
+}
+
+impl From<ReportedStatus> for StepStatus {
+    fn from(status: ReportedStatus) -> Self {
+        match status {
+            ReportedStatus::InProgress => StepStatus::InProgress,
+            ReportedStatus::Completed => StepStatus::Completed,
+            ReportedStatus::Discontinued => StepStatus::Discontinued,
+        }
+    }
+}
+
+fn main() {
+    run().unwrap_or_else(|err| {
+        error!("{}", snafu::Report::from_error(err));
+        std::process::exit(-2);
+    });
+}
+
+#[derive(Debug, Snafu)]
+enum Error {
+    /// Could not initialize SCU
+    InitScu {
+        source: dicom_ul::association::client::Error,
+    },
+
+    /// Could not read attributes file
+    ReadFile { source: dicom_object::ReadError },
+
+    /// Could not report the performed procedure step
+    Report { source: dicom_ul::mpps::Error },
+
+    #[snafu(whatever, display("{}", message))]
+    Other {
+        message: String,
+        #[snafu(source(from(Box<dyn std::error::Error + 'static>, Some)))]
+        source: Option<Box<dyn std::error::Error + 'static>>,
+    },
+}
+
+/// Build the attributes to report,
+/// filling in the status
+/// and the start or end date and time of the step if missing.
+fn build_attributes(
+    file: Option<PathBuf>,
+    attributes: &[String],
+    status: StepStatus,
+    verbose: bool,
+) -> Result<InMemDicomObject, Error> {
+    let base = if let Some(file) = file {
+        if verbose {
+            info!("Opening file '{}'...", file.display());
+        }
+        open_file(file).context(ReadFileSnafu)?.into_inner()
+    } else {
+        InMemDicomObject::new_empty()
+    };
+    let mut obj = parse_queries(base, attributes).whatever_context("Could not parse attributes")?;
+
+    obj.put(status.to_element());
+    let (date_tag, time_tag) = if status.is_final() {
+        (
+            tags::PERFORMED_PROCEDURE_STEP_END_DATE,
+            tags::PERFORMED_PROCEDURE_STEP_END_TIME,
+        )
+    } else {
+        (
+            tags::PERFORMED_PROCEDURE_STEP_START_DATE,
+            tags::PERFORMED_PROCEDURE_STEP_START_TIME,
+        )
+    };
+    let now = chrono::Local::now();
+    put_if_missing(&mut obj, date_tag, VR::DA, now.format("%Y%m%d").to_string());
+    put_if_missing(&mut obj, time_tag, VR::TM, now.format("%H%M%S").to_string());
+    Ok(obj)
+}
+
+fn put_if_missing(obj: &mut InMemDicomObject, tag: Tag, vr: VR, value: String) {
+    if obj.get(tag).is_none() {
+        obj.put(DataElement::new(tag, vr, PrimitiveValue::from(value)));
+    }
+}
+
+fn run() -> Result<(), Error> {
+    let App {
+        addr,
+        ae_config,
+        status,
+        instance_uid,
+        file,
+        attributes,
+        verbose,
+        calling_ae_title,
+        called_ae_title,
+        max_pdu_length,
+        tls,
+    } = App::parse();
+
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(if verbose { Level::DEBUG } else { Level::INFO })
+            // keep standard output for the UID of the procedure step
+            .with_writer(std::io::stderr)
+            .finish(),
+    )
+    .unwrap_or_else(|e| {
+        error!("{}", snafu::Report::from_error(e));
+    });
+
+    let status = StepStatus::from(status);
+    let obj = build_attributes(file, &attributes, status, verbose)?;
+    let instance_uid =
+        instance_uid.unwrap_or_else(|| format!("2.25.{}", uuid::Uuid::new_v4().as_u128()));
+
+    let registry = AeRegistry::locate(ae_config.as_deref())
+        .whatever_context("Could not load AE configuration")?;
+    let node = registry.as_ref().and_then(|registry| registry.get(&addr));
+    let addr = match node {
+        Some(node) => node
+            .address()
+            .with_whatever_context(|| format!("No address configured for node {addr}"))?,
+        None => addr,
+    };
+
+    if verbose {
+        info!("Establishing association with '{}'...", &addr);
+    }
+
+    let mut scu_opt = ClientAssociationOptions::new()
+        .with_abstract_syntax(MODALITY_PERFORMED_PROCEDURE_STEP)
+        .calling_ae_title(calling_ae_title)
+        .max_pdu_length(max_pdu_length);
+
+    if let Some(node) = node {
+        scu_opt = node
+            .configure(scu_opt)
+            .whatever_context("Could not configure association with node")?;
+    }
+    if let Some(called_ae_title) = called_ae_title {
+        scu_opt = scu_opt.called_ae_title(called_ae_title);
+    }
+
+    let use_tls = tls.tls || node.is_some_and(AeEntry::uses_tls);
+    if tls.tls {
+        let mut tls_options = TlsOptions::new().profile(tls.profile.unwrap_or_default());
+        if let Some(ca) = tls.ca {
+            tls_options = tls_options.ca_certificates(ca);
+        }
+        if let (Some(cert), Some(key)) = (tls.cert, tls.key) {
+            tls_options = tls_options.certificate_chain(cert).private_key(key);
+        }
+        let tls_config = tls_options
+            .client_config()
+            .whatever_context("Could not set up TLS")?;
+        scu_opt = scu_opt.tls_config(tls_config);
+        if let Some(server_name) = tls.server_name {
+            scu_opt = scu_opt.server_name(server_name);
+        }
+    }
+
+    if use_tls {
+        let scu = scu_opt.establish_with_tls(&addr).context(InitScuSnafu)?;
+        report(scu, status, &instance_uid, &obj)
+    } else {
+        let scu = scu_opt.establish_with(&addr).context(InitScuSnafu)?;
+        report(scu, status, &instance_uid, &obj)
+    }
+}
+
+fn report<S: Transport>(
+    mut scu: ClientAssociation<S>,
+    status: StepStatus,
+    instance_uid: &str,
+    obj: &InMemDicomObject,
+) -> Result<(), Error> {
+    let result = if status.is_final() {
+        set_procedure_step(&mut scu, 1, instance_uid, obj).map(|rsp| rsp.status)
+    } else {
+        create_procedure_step(&mut scu, 1, instance_uid, obj).map(|rsp| rsp.status)
+    };
+    let rsp_status = match result {
+        Ok(rsp_status) => rsp_status,
+        Err(e) => {
+            let _ = scu.abort();
+            return Err(e).context(ReportSnafu);
+        }
+    };
+    let _ = scu.release();
+
+    if !rsp_status.is_success() {
+        info!("Reported with warning status {}", rsp_status);
+    }
+    info!("Performed procedure step {} is {}", instance_uid, status);
+    // print the UID for the next report on the same step
+    println!("{}", instance_uid);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
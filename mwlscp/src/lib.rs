@@ -0,0 +1,213 @@
+//! DICOM Modality Worklist SCP support library
+//!
+//! This library exposes the [`Worklist`] service of the `dicom-mwlscp` tool,
+//! which answers Modality Worklist C-FIND requests
+//! out of worklist files in a directory
+//! when registered in a [`ServiceClassProvider`].
+//!
+//! Worklist files may be DICOM files
+//! or DICOM JSON files (with the `.json` extension)
+//! holding either a single object or an array of objects.
+//! Each file describes a requested procedure
+//! with one or more items in the _Scheduled Procedure Step Sequence_,
+//! and each of these scheduled procedure steps
+//! is matched and reported separately.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_mwlscp::{Worklist, MODALITY_WORKLIST_FIND};
+//! use dicom_ul::scp::{AcceptEcho, ServiceClassProvider};
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let scp = ServiceClassProvider::new()
+//!     .ae_title("MWL-SCP")
+//!     .with_echo_handler(AcceptEcho)
+//!     .with_find_handler([MODALITY_WORKLIST_FIND], Worklist::from_dir("worklist"));
+//! scp.serve(std::net::TcpListener::bind("0.0.0.0:11112")?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`ServiceClassProvider`]: dicom_ul::scp::ServiceClassProvider
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use dicom_core::{value::DataSetSequence, DataElement, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::InMemDicomObject;
+use dicom_ul::{
+    dimse::{composite::CFindRq, Status},
+    scp::{
+        matching::{matches, response},
+        FindHandler, FindResult, ServiceContext,
+    },
+};
+use snafu::{ResultExt, Snafu};
+use tracing::{debug, error, warn};
+
+/// The Modality Worklist information model SOP class UID.
+pub const MODALITY_WORKLIST_FIND: &str = uids::MODALITY_WORKLIST_INFORMATION_MODEL_FIND;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[snafu(display("Could not read worklist directory {}", path.display()))]
+    ReadDirectory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not open worklist file {}", path.display()))]
+    OpenFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not read DICOM worklist file {}", path.display()))]
+    ReadDicom {
+        path: PathBuf,
+        #[snafu(source(from(dicom_object::ReadError, Box::from)))]
+        source: Box<dicom_object::ReadError>,
+    },
+    #[snafu(display("Could not read DICOM JSON worklist file {}", path.display()))]
+    ReadJson {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A source of scheduled procedure steps
+/// answering Modality Worklist queries.
+#[derive(Debug, Clone)]
+pub struct Worklist {
+    source: Source,
+}
+
+#[derive(Debug, Clone)]
+enum Source {
+    Directory(PathBuf),
+    Items(Vec<InMemDicomObject>),
+}
+
+impl Worklist {
+    /// Serve the worklist files in the given directory.
+    ///
+    /// The directory is read again on every query,
+    /// so that worklist files can be added or removed
+    /// while the service is running.
+    pub fn from_dir(dir: impl Into<PathBuf>) -> Self {
+        Worklist {
+            source: Source::Directory(dir.into()),
+        }
+    }
+
+    /// Serve a fixed list of worklist items.
+    pub fn from_items(items: impl IntoIterator<Item = InMemDicomObject>) -> Self {
+        Worklist {
+            source: Source::Items(items.into_iter().flat_map(split_steps).collect()),
+        }
+    }
+
+    /// Load all scheduled procedure steps,
+    /// one record for each item of the _Scheduled Procedure Step Sequence_.
+    ///
+    /// Files in the worklist directory which cannot be read are skipped.
+    pub fn load(&self) -> Result<Vec<InMemDicomObject>> {
+        match &self.source {
+            Source::Items(items) => Ok(items.clone()),
+            Source::Directory(dir) => {
+                let mut paths = std::fs::read_dir(dir)
+                    .context(ReadDirectorySnafu { path: dir })?
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .context(ReadDirectorySnafu { path: dir })?;
+                paths.sort();
+
+                let mut items = Vec::new();
+                for path in paths.into_iter().filter(|path| path.is_file()) {
+                    match load_file(&path) {
+                        Ok(records) => items.extend(records.into_iter().flat_map(split_steps)),
+                        Err(e) => warn!("{}", snafu::Report::from_error(e)),
+                    }
+                }
+                Ok(items)
+            }
+        }
+    }
+}
+
+/// Read the worklist items in a file,
+/// either a DICOM file or a DICOM JSON file
+/// (recognized by the `.json` extension).
+pub fn load_file(path: &Path) -> Result<Vec<InMemDicomObject>> {
+    let is_json = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+    if is_json {
+        let file = File::open(path).context(OpenFileSnafu { path })?;
+        let value: serde_json::Value =
+            serde_json::from_reader(BufReader::new(file)).context(ReadJsonSnafu { path })?;
+        match value {
+            serde_json::Value::Array(values) => values
+                .into_iter()
+                .map(|value| dicom_json::from_value(value).context(ReadJsonSnafu { path }))
+                .collect(),
+            value => Ok(vec![
+                dicom_json::from_value(value).context(ReadJsonSnafu { path })?
+            ]),
+        }
+    } else {
+        let obj = dicom_object::open_file(path).context(ReadDicomSnafu { path })?;
+        Ok(vec![obj.into_inner()])
+    }
+}
+
+/// Turn a worklist item into one record per scheduled procedure step.
+fn split_steps(item: InMemDicomObject) -> Vec<InMemDicomObject> {
+    let steps = match item
+        .get(tags::SCHEDULED_PROCEDURE_STEP_SEQUENCE)
+        .and_then(|e| e.items())
+    {
+        Some(steps) if steps.len() > 1 => steps.to_vec(),
+        _ => return vec![item],
+    };
+    steps
+        .into_iter()
+        .map(|step| {
+            let mut record = item.clone();
+            record.put(DataElement::new(
+                tags::SCHEDULED_PROCEDURE_STEP_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![step]),
+            ));
+            record
+        })
+        .collect()
+}
+
+impl FindHandler for Worklist {
+    fn find(
+        &self,
+        _ctx: &ServiceContext,
+        _rq: &CFindRq,
+        identifier: &InMemDicomObject,
+    ) -> FindResult<'_> {
+        let items = self.load().map_err(|e| {
+            error!("{}", snafu::Report::from_error(e));
+            Status::UNABLE_TO_PROCESS
+        })?;
+        let found: Vec<_> = items
+            .iter()
+            .filter(|item| matches(identifier, item))
+            .map(|item| response(identifier, item))
+            .collect();
+        debug!(
+            "{} of {} scheduled procedure steps match",
+            found.len(),
+            items.len()
+        );
+        Ok(Box::new(found.into_iter()))
+    }
+}
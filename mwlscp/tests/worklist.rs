@@ -0,0 +1,249 @@
+use std::convert::TryFrom;
+use std::path::Path;
+
+use dicom_core::{value::DataSetSequence, DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::tags;
+use dicom_mwlscp::{Worklist, MODALITY_WORKLIST_FIND};
+use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
+use dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN;
+use dicom_ul::{
+    association::{client::ClientAssociationOptions, duplex::duplex, ClientAssociation},
+    dimse::{
+        composite::{CFindRq, CFindRsp},
+        DimseMessage, Status,
+    },
+    scp::ServiceClassProvider,
+};
+
+static IMPLICIT_VR_LE: &str = "1.2.840.10008.1.2";
+
+/// A requested procedure for John Doe with two scheduled steps,
+/// a CT on one day and an MR on the next.
+const DOE_JSON: &str = r#"{
+    "00100010": { "vr": "PN", "Value": [{ "Alphabetic": "Doe^John" }] },
+    "00100020": { "vr": "LO", "Value": ["P1"] },
+    "00080050": { "vr": "SH", "Value": ["A1"] },
+    "00400100": { "vr": "SQ", "Value": [
+        {
+            "00400001": { "vr": "AE", "Value": ["CT1"] },
+            "00400002": { "vr": "DA", "Value": ["20240315"] },
+            "00080060": { "vr": "CS", "Value": ["CT"] },
+            "00400009": { "vr": "SH", "Value": ["SPS1"] }
+        },
+        {
+            "00400001": { "vr": "AE", "Value": ["MR1"] },
+            "00400002": { "vr": "DA", "Value": ["20240316"] },
+            "00080060": { "vr": "CS", "Value": ["MR"] },
+            "00400009": { "vr": "SH", "Value": ["SPS2"] }
+        }
+    ]}
+}"#;
+
+fn step(station: &str, date: &str, modality: &str, id: &str) -> InMemDicomObject {
+    InMemDicomObject::from_element_iter([
+        DataElement::new(
+            tags::SCHEDULED_STATION_AE_TITLE,
+            VR::AE,
+            PrimitiveValue::from(station),
+        ),
+        DataElement::new(
+            tags::SCHEDULED_PROCEDURE_STEP_START_DATE,
+            VR::DA,
+            PrimitiveValue::from(date),
+        ),
+        DataElement::new(tags::MODALITY, VR::CS, PrimitiveValue::from(modality)),
+        DataElement::new(
+            tags::SCHEDULED_PROCEDURE_STEP_ID,
+            VR::SH,
+            PrimitiveValue::from(id),
+        ),
+    ])
+}
+
+/// Write a requested procedure for Jane Roe as a DICOM file.
+fn write_roe(dir: &Path) {
+    let obj = InMemDicomObject::from_element_iter([
+        DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::from("Roe^Jane")),
+        DataElement::new(tags::PATIENT_ID, VR::LO, PrimitiveValue::from("P2")),
+        DataElement::new(tags::ACCESSION_NUMBER, VR::SH, PrimitiveValue::from("A2")),
+        DataElement::new(
+            tags::SCHEDULED_PROCEDURE_STEP_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![step("CT1", "20240316", "CT", "SPS3")]),
+        ),
+    ]);
+    let file = obj
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .media_storage_sop_class_uid("1.2.276.0.7230010.3.1.0.1")
+                .media_storage_sop_instance_uid("2.25.2")
+                .transfer_syntax(IMPLICIT_VR_LE),
+        )
+        .unwrap();
+    file.write_to_file(dir.join("roe.wl")).unwrap();
+}
+
+/// A worklist directory with three scheduled procedure steps
+/// in a DICOM JSON file and a DICOM file.
+fn worklist_dir() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("doe.json"), DOE_JSON).unwrap();
+    write_roe(dir.path());
+    // not a worklist file
+    std::fs::write(dir.path().join("README.txt"), "not DICOM").unwrap();
+    dir
+}
+
+/// A worklist query with the given keys
+/// in the _Scheduled Procedure Step Sequence_.
+fn identifier(step_keys: &[(Tag, VR, &str)]) -> InMemDicomObject {
+    let mut step = InMemDicomObject::new_empty();
+    for &(tag, vr, value) in step_keys {
+        step.put(DataElement::new(tag, vr, PrimitiveValue::from(value)));
+    }
+    InMemDicomObject::from_element_iter([
+        DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::Empty),
+        DataElement::new(tags::PATIENT_ID, VR::LO, PrimitiveValue::Empty),
+        DataElement::new(
+            tags::SCHEDULED_PROCEDURE_STEP_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![step]),
+        ),
+    ])
+}
+
+/// Send a C-FIND request and collect the identifiers found.
+fn find<S: dicom_ul::association::Transport>(
+    association: &mut ClientAssociation<S>,
+    identifier: &InMemDicomObject,
+) -> (Status, Vec<InMemDicomObject>) {
+    let ts = IMPLICIT_VR_LITTLE_ENDIAN.erased();
+    let pc_id = association.presentation_contexts()[0].id;
+    let msg = DimseMessage::new(pc_id, CFindRq::new(1, MODALITY_WORKLIST_FIND))
+        .with_dataset(identifier, &ts)
+        .unwrap();
+    association.send_dimse(&msg).unwrap();
+    let mut found = Vec::new();
+    loop {
+        let msg = association.receive_dimse().unwrap();
+        let rsp = CFindRsp::try_from(msg.command.clone()).unwrap();
+        if !rsp.status.is_pending() {
+            return (rsp.status, found);
+        }
+        found.push(msg.dataset(&ts).unwrap().unwrap());
+    }
+}
+
+fn value(obj: &InMemDicomObject, tag: Tag) -> String {
+    obj.get(tag)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .trim_end()
+        .to_string()
+}
+
+/// The scheduled procedure step IDs in the responses.
+fn step_ids(found: &[InMemDicomObject]) -> Vec<String> {
+    let mut ids: Vec<_> = found
+        .iter()
+        .map(|obj| {
+            let steps = obj
+                .get(tags::SCHEDULED_PROCEDURE_STEP_SEQUENCE)
+                .unwrap()
+                .items()
+                .unwrap();
+            assert_eq!(steps.len(), 1);
+            value(&steps[0], tags::SCHEDULED_PROCEDURE_STEP_ID)
+        })
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn loads_one_record_per_step() {
+    let dir = worklist_dir();
+    let items = Worklist::from_dir(dir.path()).load().unwrap();
+    assert_eq!(items.len(), 3);
+}
+
+#[test]
+fn query_worklist_by_scheduled_step() {
+    let dir = worklist_dir();
+    let scp = ServiceClassProvider::new()
+        .ae_title("MWL-SCP")
+        .with_find_handler([MODALITY_WORKLIST_FIND], Worklist::from_dir(dir.path()));
+
+    let (scu_stream, scp_stream) = duplex(64 * 1024);
+    let scp_handle = std::thread::spawn(move || scp.handle_over(scp_stream));
+
+    let mut association = ClientAssociationOptions::new()
+        .called_ae_title("MWL-SCP")
+        .with_presentation_context(MODALITY_WORKLIST_FIND, vec![IMPLICIT_VR_LE])
+        .establish_over(scu_stream)
+        .unwrap();
+
+    // all steps, with the step keys returned
+    let (status, found) = find(
+        &mut association,
+        &identifier(&[
+            (tags::SCHEDULED_PROCEDURE_STEP_ID, VR::SH, ""),
+            (tags::MODALITY, VR::CS, ""),
+        ]),
+    );
+    assert!(status.is_success());
+    assert_eq!(step_ids(&found), ["SPS1", "SPS2", "SPS3"]);
+
+    // steps for a station
+    let (status, found) = find(
+        &mut association,
+        &identifier(&[
+            (tags::SCHEDULED_STATION_AE_TITLE, VR::AE, "CT1"),
+            (tags::SCHEDULED_PROCEDURE_STEP_ID, VR::SH, ""),
+        ]),
+    );
+    assert!(status.is_success());
+    assert_eq!(step_ids(&found), ["SPS1", "SPS3"]);
+
+    // steps by modality and date range,
+    // only the matching step of a procedure is reported
+    let (status, found) = find(
+        &mut association,
+        &identifier(&[
+            (tags::MODALITY, VR::CS, "MR"),
+            (
+                tags::SCHEDULED_PROCEDURE_STEP_START_DATE,
+                VR::DA,
+                "20240316-",
+            ),
+            (tags::SCHEDULED_PROCEDURE_STEP_ID, VR::SH, ""),
+        ]),
+    );
+    assert!(status.is_success());
+    assert_eq!(step_ids(&found), ["SPS2"]);
+    assert_eq!(value(&found[0], tags::PATIENT_NAME), "Doe^John");
+    assert_eq!(value(&found[0], tags::PATIENT_ID), "P1");
+    // keys not requested are not returned
+    assert!(found[0].get(tags::ACCESSION_NUMBER).is_none());
+
+    // no steps on that day
+    let (status, found) = find(
+        &mut association,
+        &identifier(&[(
+            tags::SCHEDULED_PROCEDURE_STEP_START_DATE,
+            VR::DA,
+            "20240101",
+        )]),
+    );
+    assert!(status.is_success());
+    assert!(found.is_empty());
+
+    association
+        .release()
+        .expect("did not have a peaceful release");
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+}
@@ -0,0 +1,359 @@
+//! Reconstruction of PDUs and DIMSE messages from the byte streams
+//! of an association.
+use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
+use dicom_encoding::TransferSyntax;
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use dicom_ul::association::trim_padding;
+use dicom_ul::dimse::{DimseMessage, MessageAssembler};
+use dicom_ul::pdu::{read_pdu, Pdu, PresentationContextResultReason, MAXIMUM_PDU_SIZE};
+use snafu::{ResultExt, Snafu};
+
+use crate::Side;
+
+/// The size of the PDU type, reserved byte and PDU length fields.
+const PDU_HEADER_LEN: usize = 6;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// Could not decode PDU
+    DecodePdu { source: dicom_ul::pdu::ReadError },
+    #[snafu(display("Malformed PDU of type {:02X}H and length {}", pdu_type, length))]
+    MalformedPdu { pdu_type: u8, length: u32 },
+    /// Could not reassemble DIMSE message
+    AssembleMessage { source: dicom_ul::dimse::Error },
+    #[snafu(display("Stream ended in the middle of a PDU ({} bytes left)", length))]
+    IncompletePdu { length: usize },
+    /// Stream ended in the middle of a DIMSE message
+    IncompleteMessage,
+}
+
+/// A presentation context as negotiated in the association.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresentationContext {
+    /// the presentation context identifier
+    pub id: u8,
+    /// the proposed abstract syntax UID
+    pub abstract_syntax: String,
+    /// the proposed transfer syntax UIDs
+    pub transfer_syntaxes: Vec<String>,
+    /// the outcome of the negotiation, if the association was accepted
+    pub result: Option<PresentationContextResultReason>,
+    /// the accepted transfer syntax UID
+    pub transfer_syntax: Option<String>,
+}
+
+/// Something found in the byte streams of an association.
+#[derive(Debug)]
+pub enum Event {
+    /// A complete PDU
+    Pdu { from: Side, pdu: Pdu },
+    /// A complete DIMSE message,
+    /// following the PDU which completed it
+    Message { from: Side, message: DimseMessage },
+    /// Data which could not be decoded
+    Error { from: Side, error: Error },
+}
+
+/// A decoder of the byte streams sent by both sides of an association.
+///
+/// Data is fed via [`push`](Decoder::push)
+/// in the order in which it was sent,
+/// and the decoder reports the PDUs and DIMSE messages
+/// as soon as they are complete.
+/// Both sides of the association must be fed
+/// for the presentation contexts to be known.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    /// bytes not yet decoded, sent by the SCU and by the SCP
+    buffers: [Vec<u8>; 2],
+    assemblers: [MessageAssembler; 2],
+    presentation_contexts: Vec<PresentationContext>,
+}
+
+impl Decoder {
+    /// Create a decoder for a new association.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The presentation contexts negotiated so far.
+    pub fn presentation_contexts(&self) -> &[PresentationContext] {
+        &self.presentation_contexts
+    }
+
+    /// The presentation context with the given identifier.
+    pub fn presentation_context(&self, id: u8) -> Option<&PresentationContext> {
+        self.presentation_contexts.iter().find(|pc| pc.id == id)
+    }
+
+    /// The transfer syntax accepted for the given presentation context,
+    /// if it is known and supported.
+    pub fn transfer_syntax(&self, id: u8) -> Option<&'static TransferSyntax> {
+        self.presentation_context(id)
+            .and_then(|pc| pc.transfer_syntax.as_deref())
+            .and_then(|uid| TransferSyntaxRegistry.get(uid))
+    }
+
+    /// Feed data sent by one side of the association,
+    /// returning everything which could be decoded with it.
+    pub fn push(&mut self, from: Side, data: &[u8]) -> Vec<Event> {
+        self.buffers[from.index()].extend_from_slice(data);
+        let mut events = Vec::new();
+        while let Some(frame) = self.next_frame(from) {
+            let pdu_type = frame[0];
+            let length = (frame.len() - PDU_HEADER_LEN) as u32;
+            match read_pdu(&frame[..], MAXIMUM_PDU_SIZE, false) {
+                Ok(Some(pdu)) => self.pdu(from, pdu, &mut events),
+                Ok(None) => events.push(Event::Error {
+                    from,
+                    error: Error::MalformedPdu { pdu_type, length },
+                }),
+                Err(e) => events.push(Event::Error {
+                    from,
+                    error: Error::DecodePdu { source: e },
+                }),
+            }
+        }
+        events
+    }
+
+    /// Report what was left incomplete in the streams,
+    /// once they have ended.
+    pub fn finish(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        for from in [Side::Scu, Side::Scp] {
+            let buffer = std::mem::take(&mut self.buffers[from.index()]);
+            if !buffer.is_empty() {
+                events.push(Event::Error {
+                    from,
+                    error: Error::IncompletePdu {
+                        length: buffer.len(),
+                    },
+                });
+            }
+            if self.assemblers[from.index()].is_partial() {
+                self.assemblers[from.index()] = MessageAssembler::new();
+                events.push(Event::Error {
+                    from,
+                    error: Error::IncompleteMessage,
+                });
+            }
+        }
+        events
+    }
+
+    /// Take the bytes of the next complete PDU sent by the given side.
+    fn next_frame(&mut self, from: Side) -> Option<Vec<u8>> {
+        let buffer = &mut self.buffers[from.index()];
+        let length = u32::from_be_bytes(buffer.get(2..PDU_HEADER_LEN)?.try_into().ok()?);
+        let end = PDU_HEADER_LEN + length as usize;
+        if buffer.len() < end {
+            return None;
+        }
+        Some(buffer.drain(..end).collect())
+    }
+
+    fn pdu(&mut self, from: Side, pdu: Pdu, events: &mut Vec<Event>) {
+        match &pdu {
+            Pdu::AssociationRQ(rq) => {
+                self.assemblers = Default::default();
+                self.presentation_contexts = rq
+                    .presentation_contexts
+                    .iter()
+                    .map(|pc| PresentationContext {
+                        id: pc.id,
+                        abstract_syntax: trim_padding(&pc.abstract_syntax).to_string(),
+                        transfer_syntaxes: pc
+                            .transfer_syntaxes
+                            .iter()
+                            .map(|ts| trim_padding(ts).to_string())
+                            .collect(),
+                        result: None,
+                        transfer_syntax: None,
+                    })
+                    .collect();
+            }
+            Pdu::AssociationAC(ac) => {
+                for result in &ac.presentation_contexts {
+                    let accepted = result.reason == PresentationContextResultReason::Acceptance;
+                    match self
+                        .presentation_contexts
+                        .iter_mut()
+                        .find(|pc| pc.id == result.id)
+                    {
+                        Some(pc) => {
+                            pc.result = Some(result.reason.clone());
+                            if accepted {
+                                pc.transfer_syntax =
+                                    Some(trim_padding(&result.transfer_syntax).to_string());
+                            }
+                        }
+                        // the request was not seen
+                        None => self.presentation_contexts.push(PresentationContext {
+                            id: result.id,
+                            abstract_syntax: String::new(),
+                            transfer_syntaxes: Vec::new(),
+                            result: Some(result.reason.clone()),
+                            transfer_syntax: Some(
+                                trim_padding(&result.transfer_syntax).to_string(),
+                            )
+                            .filter(|_| accepted),
+                        }),
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let data = match &pdu {
+            Pdu::PData { data } => Some(data.clone()),
+            _ => None,
+        };
+        events.push(Event::Pdu { from, pdu });
+
+        if let Some(data) = data {
+            let assembler = &mut self.assemblers[from.index()];
+            if let Err(e) = assembler.push_values(data).context(AssembleMessageSnafu) {
+                *assembler = MessageAssembler::new();
+                events.push(Event::Error { from, error: e });
+                return;
+            }
+            while let Some(message) = assembler.pop_message() {
+                events.push(Event::Message { from, message });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_ul::dimse::composite::{CEchoRq, CEchoRsp};
+    use dicom_ul::dimse::Status;
+    use dicom_ul::pdu::{
+        write_pdu, AssociationAC, AssociationRQ, PresentationContextProposed,
+        PresentationContextResult,
+    };
+
+    fn encode(pdus: &[Pdu]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for pdu in pdus {
+            write_pdu(&mut data, pdu).unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn decode_echo_association() {
+        let rq = Pdu::AssociationRQ(AssociationRQ {
+            protocol_version: 1,
+            calling_ae_title: "ECHO-SCU".to_string(),
+            called_ae_title: "ANY-SCP".to_string(),
+            application_context_name: "1.2.840.10008.3.1.1.1".to_string(),
+            presentation_contexts: vec![
+                PresentationContextProposed {
+                    id: 1,
+                    abstract_syntax: "1.2.840.10008.1.1".to_string(),
+                    transfer_syntaxes: vec!["1.2.840.10008.1.2".to_string()],
+                },
+                PresentationContextProposed {
+                    id: 3,
+                    abstract_syntax: "1.2.840.10008.5.1.4.1.1.7".to_string(),
+                    transfer_syntaxes: vec!["1.2.840.10008.1.2.4.50".to_string()],
+                },
+            ],
+            user_variables: vec![],
+        });
+        let ac = Pdu::AssociationAC(AssociationAC {
+            protocol_version: 1,
+            calling_ae_title: "ECHO-SCU".to_string(),
+            called_ae_title: "ANY-SCP".to_string(),
+            application_context_name: "1.2.840.10008.3.1.1.1".to_string(),
+            presentation_contexts: vec![
+                PresentationContextResult {
+                    id: 1,
+                    reason: PresentationContextResultReason::Acceptance,
+                    transfer_syntax: "1.2.840.10008.1.2".to_string(),
+                },
+                PresentationContextResult {
+                    id: 3,
+                    reason: PresentationContextResultReason::TransferSyntaxesNotSupported,
+                    transfer_syntax: "1.2.840.10008.1.2".to_string(),
+                },
+            ],
+            user_variables: vec![],
+        });
+        let echo = CEchoRq::new(1);
+        let echo_rsp = CEchoRsp::new(&echo, Status::SUCCESS);
+        let rq = encode(&[rq]);
+        let ac = encode(&[ac]);
+        let echo = encode(&DimseMessage::new(1, echo).to_pdus(16384).unwrap());
+        let echo_rsp = encode(&DimseMessage::new(1, echo_rsp).to_pdus(16384).unwrap());
+
+        let mut decoder = Decoder::new();
+        let mut events = decoder.push(Side::Scu, &rq[..20]);
+        assert!(events.is_empty());
+        events.extend(decoder.push(Side::Scu, &rq[20..]));
+        events.extend(decoder.push(Side::Scp, &ac));
+        assert_eq!(decoder.presentation_contexts().len(), 2);
+        assert_eq!(
+            decoder.transfer_syntax(1).map(|ts| ts.uid()),
+            Some("1.2.840.10008.1.2")
+        );
+        assert_eq!(
+            decoder.presentation_context(3).unwrap().result,
+            Some(PresentationContextResultReason::TransferSyntaxesNotSupported)
+        );
+        assert!(decoder.transfer_syntax(3).is_none());
+
+        // the C-ECHO request arrives in two parts
+        events.extend(decoder.push(Side::Scu, &echo[..12]));
+        events.extend(decoder.push(Side::Scu, &echo[12..]));
+        events.extend(decoder.push(Side::Scp, &echo_rsp));
+        events.extend(decoder.push(Side::Scu, &encode(&[Pdu::ReleaseRQ])));
+        events.extend(decoder.push(Side::Scp, &encode(&[Pdu::ReleaseRP])));
+        events.extend(decoder.finish());
+
+        let pdus = events
+            .iter()
+            .filter(|event| matches!(event, Event::Pdu { .. }))
+            .count();
+        assert_eq!(pdus, 6);
+        let messages: Vec<String> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Message { from, message } => {
+                    Some(format!("{} {}", from, message.command.command_field()))
+                }
+                Event::Error { error, .. } => panic!("unexpected error: {}", error),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(messages, vec!["SCU C-ECHO-RQ", "SCP C-ECHO-RSP"]);
+    }
+
+    #[test]
+    fn report_malformed_and_incomplete_data() {
+        let mut decoder = Decoder::new();
+        // an A-ASSOCIATE-AC PDU which is too short
+        let mut events = decoder.push(Side::Scp, &[0x02, 0, 0, 0, 0, 2, 0, 1]);
+        // the beginning of an A-RELEASE-RP PDU
+        events.extend(decoder.push(Side::Scp, &[0x06, 0, 0, 0]));
+        events.extend(decoder.finish());
+        let errors: Vec<String> = events
+            .iter()
+            .map(|event| match event {
+                Event::Error { error, .. } => error.to_string(),
+                event => panic!("unexpected event {:?}", event),
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "Malformed PDU of type 02H and length 2",
+                "Stream ended in the middle of a PDU (4 bytes left)"
+            ]
+        );
+    }
+}
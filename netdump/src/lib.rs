@@ -0,0 +1,74 @@
+//! DICOM network session decoder support library
+//!
+//! This library reconstructs the DICOM upper layer traffic
+//! of associations recorded in capture files,
+//! as done by the `dicom-netdump` tool.
+//!
+//! - [`pcap`] reads captures in the pcap or pcapng format
+//!   and reassembles the TCP byte stream sent by each side of a connection.
+//! - [`raw`] handles raw captures,
+//!   in which the bytes sent by each side are recorded on their own.
+//! - [`Decoder`] turns the byte streams of an association
+//!   into PDUs, presentation contexts and DIMSE messages.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_netdump::{pcap::read_capture, Decoder, Event};
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let file = std::fs::File::open("session.pcap")?;
+//! for connection in read_capture(file)? {
+//!     let mut decoder = Decoder::new();
+//!     for payload in &connection.payloads {
+//!         for event in decoder.push(payload.from, &payload.data) {
+//!             if let Event::Message { from, message } = event {
+//!                 println!("{}: {}", from, message.command.command_field());
+//!             }
+//!         }
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+
+pub mod decode;
+pub mod pcap;
+pub mod raw;
+
+pub use decode::{Decoder, Event, PresentationContext};
+
+/// One of the two nodes of an association.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Side {
+    /// The service class user, which requested the association
+    Scu,
+    /// The service class provider, which accepted the association
+    Scp,
+}
+
+impl Side {
+    /// The node on the other side of the association.
+    pub fn peer(self) -> Self {
+        match self {
+            Side::Scu => Side::Scp,
+            Side::Scp => Side::Scu,
+        }
+    }
+
+    pub(crate) fn index(self) -> usize {
+        match self {
+            Side::Scu => 0,
+            Side::Scp => 1,
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Side::Scu => "SCU",
+            Side::Scp => "SCP",
+        })
+    }
+}
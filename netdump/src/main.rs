@@ -0,0 +1,353 @@
+//! A CLI tool for inspecting the DICOM network sessions
+//! recorded in capture files.
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Parser;
+use dicom_core::dictionary::{UidDictionary, UidDictionaryEntry};
+use dicom_dictionary_std::StandardSopClassDictionary;
+use dicom_dump::{ColorMode, DumpOptions};
+use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
+use dicom_netdump::{
+    pcap::{is_capture, read_capture, TcpConnection},
+    raw::{guess_side, interleave},
+    Decoder, Event, Side,
+};
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use dicom_ul::dimse::DimseMessage;
+use dicom_ul::pdu::{AssociationRJ, Pdu, PresentationContextResultReason, UserVariableItem};
+use snafu::{whatever, Report, ResultExt, Whatever};
+
+/// Decode DICOM network sessions from capture files
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// A capture file in the pcap or pcapng format,
+    /// or the raw bytes sent by one side of an association
+    file: PathBuf,
+    /// The raw bytes sent by the other side of the association
+    other_file: Option<PathBuf>,
+    /// Print every P-DATA PDU
+    #[arg(long = "pdus")]
+    pdus: bool,
+    /// Do not print the data sets of DIMSE messages
+    #[arg(long = "no-datasets")]
+    no_datasets: bool,
+    /// The width of the display
+    /// (default is to check automatically)
+    #[arg(short = 'w', long = "width")]
+    width: Option<u32>,
+    /// The color mode
+    #[arg(long = "color", default_value = "auto")]
+    color: ColorMode,
+}
+
+fn main() {
+    tracing::subscriber::set_global_default(tracing_subscriber::FmtSubscriber::new())
+        .unwrap_or_else(|e| {
+            eprintln!(
+                "Could not set up global logger: {}",
+                snafu::Report::from_error(e)
+            );
+        });
+
+    run(App::parse()).unwrap_or_else(|e| {
+        eprintln!("{}", Report::from_error(e));
+        std::process::exit(-2);
+    });
+}
+
+fn run(args: App) -> Result<(), Whatever> {
+    let data = std::fs::read(&args.file)
+        .with_whatever_context(|_| format!("Could not read {}", args.file.display()))?;
+
+    let mut options = DumpOptions::new();
+    options
+        .no_limit(!std::io::stdout().is_terminal())
+        .color_mode(args.color);
+    match args.width {
+        Some(width) => options.width(width),
+        None => options.width_auto(),
+    };
+    let mut printer = Printer {
+        options,
+        pdus: args.pdus,
+        datasets: !args.no_datasets,
+    };
+
+    if is_capture(&data) {
+        if args.other_file.is_some() {
+            whatever!("Only raw captures can be given for each side of the association");
+        }
+        let connections =
+            read_capture(&data[..]).whatever_context("Could not read capture file")?;
+        if connections.is_empty() {
+            println!("No TCP connections found");
+        }
+        for (i, connection) in connections.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            printer.connection(i + 1, connection);
+        }
+    } else {
+        let (scu, scp) = match &args.other_file {
+            Some(other_file) => {
+                let other = std::fs::read(other_file).with_whatever_context(|_| {
+                    format!("Could not read {}", other_file.display())
+                })?;
+                match guess_side(&data) {
+                    Side::Scu => (data, other),
+                    Side::Scp => (other, data),
+                }
+            }
+            None => match guess_side(&data) {
+                Side::Scu => (data, Vec::new()),
+                Side::Scp => (Vec::new(), data),
+            },
+        };
+        let mut decoder = Decoder::new();
+        for (from, bytes) in interleave(&scu, &scp) {
+            for event in decoder.push(from, bytes) {
+                printer.event(&decoder, None, &event);
+            }
+        }
+        for event in decoder.finish() {
+            printer.event(&decoder, None, &event);
+        }
+    }
+    Ok(())
+}
+
+/// The output of the decoded sessions.
+struct Printer {
+    options: DumpOptions,
+    pdus: bool,
+    datasets: bool,
+}
+
+impl Printer {
+    fn connection(&mut self, number: usize, connection: &TcpConnection) {
+        println!(
+            "# Connection {}: {} (SCU) -> {} (SCP)",
+            number, connection.scu, connection.scp
+        );
+        if connection.missing_bytes > 0 {
+            println!(
+                "# {} bytes are missing from the capture",
+                connection.missing_bytes
+            );
+        }
+        let start = connection
+            .payloads
+            .first()
+            .map(|payload| payload.timestamp)
+            .unwrap_or_default();
+        let mut decoder = Decoder::new();
+        for payload in &connection.payloads {
+            let elapsed = payload.timestamp.saturating_sub(start);
+            for event in decoder.push(payload.from, &payload.data) {
+                self.event(&decoder, Some(elapsed), &event);
+            }
+        }
+        for event in decoder.finish() {
+            self.event(&decoder, None, &event);
+        }
+    }
+
+    fn event(&mut self, decoder: &Decoder, elapsed: Option<Duration>, event: &Event) {
+        let (from, what) = match event {
+            Event::Pdu {
+                pdu: Pdu::PData { .. },
+                ..
+            } if !self.pdus => return,
+            Event::Pdu { from, pdu } => (from, describe_pdu(pdu)),
+            Event::Message { from, message } => (from, describe_message(decoder, message)),
+            Event::Error { from, error } => (from, format!("Error: {}", Report::from_error(error))),
+        };
+        let time = elapsed
+            .map(|elapsed| format!("{:>10.6} ", elapsed.as_secs_f64()))
+            .unwrap_or_default();
+        println!("{}{} -> {}: {}", time, from, from.peer(), what);
+
+        match event {
+            Event::Pdu {
+                pdu: Pdu::AssociationRQ(rq),
+                ..
+            } => {
+                println!("    Application context: {}", rq.application_context_name);
+                print_user_variables(&rq.user_variables);
+                for pc in &rq.presentation_contexts {
+                    let transfer_syntaxes: Vec<_> = pc
+                        .transfer_syntaxes
+                        .iter()
+                        .map(|ts| transfer_syntax_name(ts))
+                        .collect();
+                    println!(
+                        "    [{}] {}: {}",
+                        pc.id,
+                        sop_class_name(&pc.abstract_syntax),
+                        transfer_syntaxes.join(", ")
+                    );
+                }
+            }
+            Event::Pdu {
+                pdu: Pdu::AssociationAC(ac),
+                ..
+            } => {
+                print_user_variables(&ac.user_variables);
+                for pc in decoder.presentation_contexts() {
+                    let outcome = match (&pc.result, &pc.transfer_syntax) {
+                        (Some(PresentationContextResultReason::Acceptance), Some(ts)) => {
+                            transfer_syntax_name(ts)
+                        }
+                        (Some(reason), _) => format!("rejected ({})", reason),
+                        (None, _) => "no answer".to_string(),
+                    };
+                    println!(
+                        "    [{}] {}: {}",
+                        pc.id,
+                        sop_class_name(&pc.abstract_syntax),
+                        outcome
+                    );
+                }
+            }
+            Event::Message { message, .. } => self.message(decoder, message),
+            _ => {}
+        }
+    }
+
+    fn message(&mut self, decoder: &Decoder, message: &DimseMessage) {
+        let command_set = message.command.to_command_set(message.has_data_set());
+        if let Err(e) = self.options.dump_object(&command_set) {
+            eprintln!("{}", Report::from_error(e));
+        }
+        let data = match (&message.data, self.datasets) {
+            (Some(data), true) => data,
+            _ => return,
+        };
+        let ts = match decoder.transfer_syntax(message.presentation_context_id) {
+            Some(ts) => ts,
+            None => {
+                println!(
+                    "    Data set of {} bytes in an unknown transfer syntax",
+                    data.len()
+                );
+                return;
+            }
+        };
+        match message.dataset(ts) {
+            Ok(Some(dataset)) => {
+                println!("    ---");
+                if let Err(e) = self.options.dump_object(&dataset) {
+                    eprintln!("{}", Report::from_error(e));
+                }
+            }
+            Ok(None) => {}
+            Err(e) => println!(
+                "    Could not decode data set of {} bytes: {}",
+                data.len(),
+                Report::from_error(e)
+            ),
+        }
+    }
+}
+
+fn describe_pdu(pdu: &Pdu) -> String {
+    match pdu {
+        Pdu::AssociationRQ(rq) => format!(
+            "A-ASSOCIATE-RQ from {:?} to {:?}",
+            rq.calling_ae_title.trim(),
+            rq.called_ae_title.trim()
+        ),
+        Pdu::AssociationAC(ac) => format!(
+            "A-ASSOCIATE-AC from {:?} to {:?}",
+            ac.called_ae_title.trim(),
+            ac.calling_ae_title.trim()
+        ),
+        Pdu::AssociationRJ(AssociationRJ { result, source }) => {
+            format!("A-ASSOCIATE-RJ ({:?}): {}", result, source)
+        }
+        Pdu::PData { .. } => pdu.short_description().to_string(),
+        Pdu::ReleaseRQ => "A-RELEASE-RQ".to_string(),
+        Pdu::ReleaseRP => "A-RELEASE-RP".to_string(),
+        Pdu::AbortRQ { source } => format!("A-ABORT ({:?})", source),
+        Pdu::Unknown { pdu_type, data } => {
+            format!(
+                "Unknown PDU of type {:02X}H ({} bytes)",
+                pdu_type,
+                data.len()
+            )
+        }
+    }
+}
+
+fn describe_message(decoder: &Decoder, message: &DimseMessage) -> String {
+    let ts = decoder
+        .presentation_context(message.presentation_context_id)
+        .and_then(|pc| pc.transfer_syntax.as_deref())
+        .map(transfer_syntax_name)
+        .unwrap_or_else(|| "unknown transfer syntax".to_string());
+    format!(
+        "{} (presentation context {}, {})",
+        message.command.command_field(),
+        message.presentation_context_id,
+        ts
+    )
+}
+
+fn print_user_variables(user_variables: &[UserVariableItem]) {
+    for item in user_variables {
+        match item {
+            UserVariableItem::MaxLength(len) => println!("    Maximum length: {}", len),
+            UserVariableItem::ImplementationClassUID(uid) => {
+                println!("    Implementation class UID: {}", uid)
+            }
+            UserVariableItem::ImplementationVersionName(name) => {
+                println!("    Implementation version name: {}", name)
+            }
+            UserVariableItem::RoleSelectionSubItem(uid, scu, scp) => println!(
+                "    Role selection: {} (SCU: {}, SCP: {})",
+                sop_class_name(uid),
+                scu,
+                scp
+            ),
+            UserVariableItem::AsyncOperationsWindow(invoked, performed) => println!(
+                "    Asynchronous operations window: {} invoked, {} performed",
+                invoked, performed
+            ),
+            UserVariableItem::UserIdentityItem(identity) => {
+                println!("    User identity: {:?}", identity.identity_type())
+            }
+            item => println!("    {:?}", item),
+        }
+    }
+}
+
+fn sop_class_name(uid: &str) -> String {
+    let uid = uid.trim_end_matches('\0');
+    match StandardSopClassDictionary.by_uid(uid) {
+        Some(entry) => format!("{} ({})", uid, entry.name()),
+        None => uid.to_string(),
+    }
+}
+
+fn transfer_syntax_name(uid: &str) -> String {
+    let uid = uid.trim_end_matches('\0');
+    match TransferSyntaxRegistry.get(uid) {
+        Some(ts) => format!("{} ({})", uid, ts.name()),
+        None => uid.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
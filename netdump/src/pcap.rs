@@ -0,0 +1,683 @@
+//! Reading of packet capture files and TCP stream reassembly.
+//!
+//! Both the classic pcap format and the pcapng format are supported,
+//! with Ethernet, Linux cooked, loopback or raw IP link layers.
+//! The TCP segments of each connection are put back in order,
+//! discarding retransmissions,
+//! so that the byte stream sent by each side can be decoded.
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+use tracing::warn;
+
+use crate::Side;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// Could not read the capture
+    ReadCapture {
+        backtrace: Backtrace,
+        source: std::io::Error,
+    },
+    /// Not a pcap or pcapng capture
+    UnrecognizedFormat { backtrace: Backtrace },
+    /// The capture ends in the middle of a record
+    Truncated { backtrace: Backtrace },
+    #[snafu(display("Unsupported link type {}", link_type))]
+    UnsupportedLinkType {
+        link_type: u32,
+        backtrace: Backtrace,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_ACK: u8 = 0x10;
+
+/// Whether the given bytes start a pcap or pcapng capture.
+pub fn is_capture(header: &[u8]) -> bool {
+    matches!(
+        header.get(..4),
+        Some([0xd4, 0xc3, 0xb2, 0xa1])
+            | Some([0xa1, 0xb2, 0xc3, 0xd4])
+            | Some([0x4d, 0x3c, 0xb2, 0xa1])
+            | Some([0xa1, 0xb2, 0x3c, 0x4d])
+            | Some([0x0a, 0x0d, 0x0d, 0x0a])
+    )
+}
+
+/// A chunk of contiguous data sent by one side of a TCP connection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Payload {
+    /// the side which sent the data
+    pub from: Side,
+    /// the capture time of the first segment, since the Unix epoch
+    pub timestamp: Duration,
+    /// the data, in stream order
+    pub data: Vec<u8>,
+}
+
+/// A reassembled TCP connection between an SCU and an SCP.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TcpConnection {
+    /// the address of the node which opened the connection
+    pub scu: SocketAddr,
+    /// the address of the node which accepted the connection
+    pub scp: SocketAddr,
+    /// the data sent by both sides, in the order of capture
+    pub payloads: Vec<Payload>,
+    /// the number of bytes which were missing from the capture
+    pub missing_bytes: u64,
+}
+
+/// Read a pcap or pcapng capture in full,
+/// returning the TCP connections found in it
+/// in the order in which they began.
+///
+/// Connections without any payload are left out.
+pub fn read_capture(mut reader: impl Read) -> Result<Vec<TcpConnection>> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).context(ReadCaptureSnafu)?;
+    let mut reassembler = Reassembler::default();
+    if data.starts_with(&[0x0a, 0x0d, 0x0d, 0x0a]) {
+        read_pcapng(&data, &mut reassembler)?;
+    } else {
+        read_pcap(&data, &mut reassembler)?;
+    }
+    Ok(reassembler.finish())
+}
+
+/// A cursor over the bytes of a capture in its byte order.
+struct Bytes<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Bytes<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        ensure!(self.data.len() >= len, TruncatedSnafu);
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        let bytes = [bytes[0], bytes[1]];
+        Ok(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+}
+
+fn read_pcap(data: &[u8], reassembler: &mut Reassembler) -> Result<()> {
+    let (big_endian, nanos) = match data.get(..4) {
+        Some([0xd4, 0xc3, 0xb2, 0xa1]) => (false, false),
+        Some([0xa1, 0xb2, 0xc3, 0xd4]) => (true, false),
+        Some([0x4d, 0x3c, 0xb2, 0xa1]) => (false, true),
+        Some([0xa1, 0xb2, 0x3c, 0x4d]) => (true, true),
+        _ => return UnrecognizedFormatSnafu.fail(),
+    };
+    let mut bytes = Bytes {
+        data: &data[4..],
+        big_endian,
+    };
+    // version, time zone, accuracy, snapshot length
+    bytes.take(16)?;
+    let link_type = bytes.u32()? & 0x0fff_ffff;
+    check_link_type(link_type)?;
+
+    while !bytes.data.is_empty() {
+        let seconds = bytes.u32()?;
+        let fraction = bytes.u32()?;
+        let captured_len = bytes.u32()? as usize;
+        let original_len = bytes.u32()? as usize;
+        let packet = bytes.take(captured_len)?;
+        let timestamp = if nanos {
+            Duration::new(u64::from(seconds), fraction)
+        } else {
+            Duration::new(u64::from(seconds), 0) + Duration::from_micros(u64::from(fraction))
+        };
+        if captured_len < original_len {
+            warn!("Packet truncated in the capture, data will be missing");
+        }
+        reassembler.packet(link_type, timestamp, packet);
+    }
+    Ok(())
+}
+
+fn read_pcapng(data: &[u8], reassembler: &mut Reassembler) -> Result<()> {
+    let mut bytes = Bytes {
+        data,
+        big_endian: false,
+    };
+    // link type and timestamp resolution of each interface in the section
+    let mut interfaces: Vec<(u32, u64)> = Vec::new();
+
+    while !bytes.data.is_empty() {
+        if bytes.data.starts_with(&[0x0a, 0x0d, 0x0d, 0x0a]) {
+            // section header block, which defines the byte order
+            let magic = bytes.data.get(8..12).context(TruncatedSnafu)?;
+            bytes.big_endian = magic == [0x1a, 0x2b, 0x3c, 0x4d];
+            interfaces.clear();
+        }
+        let block_type = bytes.u32()?;
+        let total_len = bytes.u32()? as usize;
+        ensure!(total_len >= 12 && total_len % 4 == 0, TruncatedSnafu);
+        let mut body = Bytes {
+            data: bytes.take(total_len - 12)?,
+            big_endian: bytes.big_endian,
+        };
+        bytes.take(4)?;
+
+        match block_type {
+            // interface description block
+            1 => {
+                let link_type = u32::from(body.u16()?);
+                body.take(6)?;
+                let mut units_per_second = 1_000_000;
+                // look for the timestamp resolution option
+                while body.data.len() >= 4 {
+                    let code = body.u16()?;
+                    let len = usize::from(body.u16()?);
+                    let value = body.take((len + 3) / 4 * 4)?;
+                    match (code, value.first()) {
+                        (0, _) => break,
+                        (9, Some(resolution)) => {
+                            let exponent = u32::from(resolution & 0x7f);
+                            units_per_second = if resolution & 0x80 == 0 {
+                                10_u64.saturating_pow(exponent)
+                            } else {
+                                2_u64.saturating_pow(exponent)
+                            };
+                        }
+                        _ => {}
+                    }
+                }
+                check_link_type(link_type)?;
+                interfaces.push((link_type, units_per_second.max(1)));
+            }
+            // enhanced packet block
+            6 => {
+                let interface = body.u32()? as usize;
+                let high = u64::from(body.u32()?);
+                let low = u64::from(body.u32()?);
+                let captured_len = body.u32()? as usize;
+                let original_len = body.u32()? as usize;
+                let packet = body.take(captured_len)?;
+                let &(link_type, units_per_second) =
+                    interfaces.get(interface).context(TruncatedSnafu)?;
+                let units = (high << 32) | low;
+                let timestamp = Duration::from_secs(units / units_per_second)
+                    + Duration::from_nanos(
+                        (units % units_per_second) * 1_000_000_000 / units_per_second,
+                    );
+                if captured_len < original_len {
+                    warn!("Packet truncated in the capture, data will be missing");
+                }
+                reassembler.packet(link_type, timestamp, packet);
+            }
+            // simple packet block, always from the first interface
+            3 => {
+                let original_len = body.u32()? as usize;
+                let &(link_type, _) = interfaces.first().context(TruncatedSnafu)?;
+                let packet = &body.data[..original_len.min(body.data.len())];
+                reassembler.packet(link_type, Duration::default(), packet);
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn check_link_type(link_type: u32) -> Result<()> {
+    ensure!(
+        matches!(
+            link_type,
+            LINKTYPE_NULL
+                | LINKTYPE_ETHERNET
+                | LINKTYPE_RAW
+                | LINKTYPE_LINUX_SLL
+                | LINKTYPE_IPV4
+                | LINKTYPE_IPV6
+                | LINKTYPE_LINUX_SLL2
+        ),
+        UnsupportedLinkTypeSnafu { link_type }
+    );
+    Ok(())
+}
+
+/// Strip the link layer header of a packet,
+/// returning the IP packet within, if any.
+fn ip_packet(link_type: u32, packet: &[u8]) -> Option<&[u8]> {
+    match link_type {
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => Some(packet),
+        // the address family is in the byte order of the capturing host,
+        // so rely on the IP version instead
+        LINKTYPE_NULL => packet.get(4..),
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            let mut ether_type = u16::from_be_bytes([*packet.get(12)?, *packet.get(13)?]);
+            // skip VLAN tags
+            while ether_type == 0x8100 || ether_type == 0x88a8 {
+                offset += 4;
+                ether_type = u16::from_be_bytes([*packet.get(offset)?, *packet.get(offset + 1)?]);
+            }
+            match ether_type {
+                0x0800 | 0x86dd => packet.get(offset + 2..),
+                _ => None,
+            }
+        }
+        LINKTYPE_LINUX_SLL => packet.get(16..),
+        LINKTYPE_LINUX_SLL2 => packet.get(20..),
+        _ => None,
+    }
+}
+
+/// A TCP segment extracted from a packet.
+struct Segment<'a> {
+    src: SocketAddr,
+    dst: SocketAddr,
+    seq: u32,
+    flags: u8,
+    payload: &'a [u8],
+}
+
+/// Parse the IP and TCP headers of a packet.
+fn tcp_segment(ip: &[u8]) -> Option<Segment<'_>> {
+    let (src, dst, tcp) = match ip.first()? >> 4 {
+        4 => {
+            let header_len = usize::from(ip[0] & 0x0f) * 4;
+            let total_len = usize::from(u16::from_be_bytes([*ip.get(2)?, *ip.get(3)?]));
+            let fragment = u16::from_be_bytes([*ip.get(6)?, *ip.get(7)?]);
+            if *ip.get(9)? != 6 {
+                return None;
+            }
+            if fragment & 0x3fff != 0 {
+                warn!("Fragmented IP packets are not supported, data will be missing");
+                return None;
+            }
+            let src: [u8; 4] = ip.get(12..16)?.try_into().ok()?;
+            let dst: [u8; 4] = ip.get(16..20)?.try_into().ok()?;
+            // leave out any link layer padding
+            let end = total_len.clamp(header_len, ip.len());
+            (
+                IpAddr::from(Ipv4Addr::from(src)),
+                IpAddr::from(Ipv4Addr::from(dst)),
+                ip.get(header_len..end)?,
+            )
+        }
+        6 => {
+            let payload_len = usize::from(u16::from_be_bytes([*ip.get(4)?, *ip.get(5)?]));
+            let src: [u8; 16] = ip.get(8..24)?.try_into().ok()?;
+            let dst: [u8; 16] = ip.get(24..40)?.try_into().ok()?;
+            let end = (40 + payload_len).min(ip.len());
+            let mut next_header = *ip.get(6)?;
+            let mut offset = 40;
+            // skip extension headers
+            while matches!(next_header, 0 | 43 | 60) {
+                let len = (usize::from(*ip.get(offset + 1)?) + 1) * 8;
+                next_header = *ip.get(offset)?;
+                offset += len;
+            }
+            if next_header != 6 {
+                return None;
+            }
+            (
+                IpAddr::from(Ipv6Addr::from(src)),
+                IpAddr::from(Ipv6Addr::from(dst)),
+                ip.get(offset..end)?,
+            )
+        }
+        _ => return None,
+    };
+
+    let src_port = u16::from_be_bytes([*tcp.first()?, *tcp.get(1)?]);
+    let dst_port = u16::from_be_bytes([*tcp.get(2)?, *tcp.get(3)?]);
+    let seq = u32::from_be_bytes(tcp.get(4..8)?.try_into().ok()?);
+    let header_len = usize::from(tcp.get(12)? >> 4) * 4;
+    let flags = *tcp.get(13)?;
+    Some(Segment {
+        src: SocketAddr::new(src, src_port),
+        dst: SocketAddr::new(dst, dst_port),
+        seq,
+        flags,
+        payload: tcp.get(header_len..)?,
+    })
+}
+
+/// The reassembly state of one direction of a connection.
+#[derive(Debug, Default)]
+struct HalfStream {
+    /// the sequence number of the next expected byte
+    next_seq: Option<u32>,
+    /// segments received ahead of the expected one, by sequence number
+    ahead: BTreeMap<u32, Vec<u8>>,
+    finished: bool,
+}
+
+/// A connection being reassembled.
+#[derive(Debug)]
+struct Connection {
+    /// the endpoint which sent the first SYN, if seen
+    initiator: Option<SocketAddr>,
+    endpoints: [SocketAddr; 2],
+    streams: [HalfStream; 2],
+    /// contiguous data in order of capture, by endpoint index
+    payloads: Vec<(usize, Duration, Vec<u8>)>,
+    missing_bytes: u64,
+}
+
+impl Connection {
+    fn new(a: SocketAddr, b: SocketAddr) -> Self {
+        Connection {
+            initiator: None,
+            endpoints: [a, b],
+            streams: Default::default(),
+            payloads: Vec::new(),
+            missing_bytes: 0,
+        }
+    }
+
+    fn segment(&mut self, timestamp: Duration, segment: &Segment) {
+        let side = if segment.src == self.endpoints[0] {
+            0
+        } else {
+            1
+        };
+        let stream = &mut self.streams[side];
+        let mut seq = segment.seq;
+        if segment.flags & TCP_SYN != 0 {
+            if segment.flags & TCP_ACK == 0 {
+                self.initiator = Some(segment.src);
+            }
+            seq = seq.wrapping_add(1);
+            stream.next_seq = Some(seq);
+        }
+        if segment.flags & TCP_FIN != 0 {
+            stream.finished = true;
+        }
+        if segment.payload.is_empty() {
+            return;
+        }
+        let next_seq = *stream.next_seq.get_or_insert(seq);
+
+        // position of the segment relative to the next expected byte
+        let offset = seq.wrapping_sub(next_seq) as i32;
+        if offset > 0 {
+            let entry = stream.ahead.entry(seq).or_default();
+            if entry.len() < segment.payload.len() {
+                *entry = segment.payload.to_vec();
+            }
+            return;
+        }
+        self.deliver(side, timestamp, seq, segment.payload);
+        self.drain_ahead(side, timestamp);
+    }
+
+    /// Take in data starting at the given sequence number,
+    /// which is not after the next expected byte.
+    fn deliver(&mut self, side: usize, timestamp: Duration, seq: u32, data: &[u8]) {
+        let stream = &mut self.streams[side];
+        let next_seq = stream.next_seq.unwrap_or(seq);
+        let overlap = next_seq.wrapping_sub(seq) as usize;
+        if overlap >= data.len() {
+            // retransmission
+            return;
+        }
+        let data = &data[overlap..];
+        stream.next_seq = Some(next_seq.wrapping_add(data.len() as u32));
+        match self.payloads.last_mut() {
+            Some((last_side, _, last)) if *last_side == side => last.extend_from_slice(data),
+            _ => self.payloads.push((side, timestamp, data.to_vec())),
+        }
+    }
+
+    fn drain_ahead(&mut self, side: usize, timestamp: Duration) {
+        loop {
+            let next_seq = match self.streams[side].next_seq {
+                Some(seq) => seq,
+                None => return,
+            };
+            let ready = self.streams[side]
+                .ahead
+                .keys()
+                .copied()
+                .find(|seq| next_seq.wrapping_sub(*seq) as i32 >= 0);
+            match ready {
+                Some(seq) => {
+                    let data = self.streams[side].ahead.remove(&seq).unwrap_or_default();
+                    self.deliver(side, timestamp, seq, &data);
+                }
+                None => return,
+            }
+        }
+    }
+
+    /// Deliver the segments which were left after gaps in the capture.
+    fn flush_gaps(&mut self, timestamp: Duration) {
+        for side in 0..2 {
+            while let Some((&seq, _)) = self.streams[side].ahead.iter().next() {
+                let next_seq = self.streams[side].next_seq.unwrap_or(seq);
+                let gap = seq.wrapping_sub(next_seq);
+                warn!(
+                    "{} bytes missing from the capture after {} -> {}",
+                    gap,
+                    self.endpoints[side],
+                    self.endpoints[1 - side]
+                );
+                self.missing_bytes += u64::from(gap);
+                self.streams[side].next_seq = Some(seq);
+                self.drain_ahead(side, timestamp);
+            }
+        }
+    }
+
+    /// Settle which side is the SCU:
+    /// the side which opened the connection,
+    /// else the side which sent an A-ASSOCIATE-RQ PDU first,
+    /// else the side with the highest (likely ephemeral) port.
+    fn into_tcp_connection(mut self, timestamp: Duration) -> TcpConnection {
+        self.flush_gaps(timestamp);
+        let scu = self
+            .initiator
+            .map(|addr| if addr == self.endpoints[0] { 0 } else { 1 })
+            .or_else(|| {
+                self.payloads
+                    .iter()
+                    .find(|(_, _, data)| data.first() == Some(&0x01))
+                    .map(|(side, _, _)| *side)
+            })
+            .unwrap_or(if self.endpoints[0].port() > self.endpoints[1].port() {
+                0
+            } else {
+                1
+            });
+        TcpConnection {
+            scu: self.endpoints[scu],
+            scp: self.endpoints[1 - scu],
+            payloads: self
+                .payloads
+                .into_iter()
+                .map(|(side, timestamp, data)| Payload {
+                    from: if side == scu { Side::Scu } else { Side::Scp },
+                    timestamp,
+                    data,
+                })
+                .collect(),
+            missing_bytes: self.missing_bytes,
+        }
+    }
+}
+
+/// The reassembler of all TCP connections in a capture.
+#[derive(Debug, Default)]
+struct Reassembler {
+    /// connections being reassembled, by their endpoints in order
+    open: HashMap<(SocketAddr, SocketAddr), usize>,
+    connections: Vec<Connection>,
+    last_timestamp: Duration,
+}
+
+impl Reassembler {
+    fn packet(&mut self, link_type: u32, timestamp: Duration, packet: &[u8]) {
+        let segment = match ip_packet(link_type, packet).and_then(tcp_segment) {
+            Some(segment) => segment,
+            None => return,
+        };
+        self.last_timestamp = self.last_timestamp.max(timestamp);
+        let key = if segment.src < segment.dst {
+            (segment.src, segment.dst)
+        } else {
+            (segment.dst, segment.src)
+        };
+        let opening = segment.flags & (TCP_SYN | TCP_ACK) == TCP_SYN;
+        let index = match self.open.get(&key) {
+            // a new connection reusing the same ports
+            Some(&index) if opening && !self.connections[index].payloads.is_empty() => None,
+            Some(&index) => Some(index),
+            None => None,
+        };
+        let index = index.unwrap_or_else(|| {
+            self.connections
+                .push(Connection::new(segment.src, segment.dst));
+            self.open.insert(key, self.connections.len() - 1);
+            self.connections.len() - 1
+        });
+        self.connections[index].segment(timestamp, &segment);
+    }
+
+    fn finish(self) -> Vec<TcpConnection> {
+        let timestamp = self.last_timestamp;
+        self.connections
+            .into_iter()
+            .filter(|connection| {
+                !connection.payloads.is_empty()
+                    || connection.streams.iter().any(|s| !s.ahead.is_empty())
+            })
+            .map(|connection| connection.into_tcp_connection(timestamp))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a raw IPv4 packet with a TCP segment.
+    fn packet(src: &str, dst: &str, seq: u32, flags: u8, payload: &[u8]) -> Vec<u8> {
+        let src: SocketAddr = src.parse().unwrap();
+        let dst: SocketAddr = dst.parse().unwrap();
+        let ip = |addr: SocketAddr| match addr.ip() {
+            IpAddr::V4(ip) => ip.octets(),
+            IpAddr::V6(_) => unreachable!(),
+        };
+        let mut packet = vec![0x45, 0];
+        packet.extend_from_slice(&((40 + payload.len()) as u16).to_be_bytes());
+        packet.extend_from_slice(&[0, 0, 0x40, 0, 64, 6, 0, 0]);
+        packet.extend_from_slice(&ip(src));
+        packet.extend_from_slice(&ip(dst));
+        packet.extend_from_slice(&src.port().to_be_bytes());
+        packet.extend_from_slice(&dst.port().to_be_bytes());
+        packet.extend_from_slice(&seq.to_be_bytes());
+        packet.extend_from_slice(&[0, 0, 0, 0, 0x50, flags, 0xff, 0xff, 0, 0, 0, 0]);
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    fn pcap(packets: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0xd4, 0xc3, 0xb2, 0xa1, 2, 0, 4, 0];
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&65535_u32.to_le_bytes());
+        data.extend_from_slice(&LINKTYPE_RAW.to_le_bytes());
+        for (i, packet) in packets.iter().enumerate() {
+            data.extend_from_slice(&(i as u32).to_le_bytes());
+            data.extend_from_slice(&0_u32.to_le_bytes());
+            data.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+            data.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+            data.extend_from_slice(packet);
+        }
+        data
+    }
+
+    const SCU: &str = "10.0.0.1:50000";
+    const SCP: &str = "10.0.0.2:104";
+
+    #[test]
+    fn reassemble_out_of_order_and_retransmitted_segments() {
+        let capture = pcap(&[
+            packet(SCP, SCU, 500, TCP_SYN | TCP_ACK, b""),
+            packet(SCU, SCP, 100, TCP_SYN, b""),
+            packet(SCU, SCP, 101, TCP_ACK, b"abc"),
+            // out of order
+            packet(SCU, SCP, 107, TCP_ACK, b"ghi"),
+            packet(SCU, SCP, 104, TCP_ACK, b"def"),
+            // retransmitted, partly overlapping
+            packet(SCU, SCP, 105, TCP_ACK, b"efg"),
+            packet(SCP, SCU, 501, TCP_ACK, b"xyz"),
+            packet(SCU, SCP, 110, TCP_ACK | TCP_FIN, b"j"),
+        ]);
+
+        assert!(is_capture(&capture));
+        let connections = read_capture(&capture[..]).unwrap();
+        assert_eq!(connections.len(), 1);
+        let connection = &connections[0];
+        assert_eq!(connection.scu, SCU.parse().unwrap());
+        assert_eq!(connection.scp, SCP.parse().unwrap());
+        assert_eq!(connection.missing_bytes, 0);
+        let payloads: Vec<_> = connection
+            .payloads
+            .iter()
+            .map(|p| (p.from, String::from_utf8_lossy(&p.data).into_owned()))
+            .collect();
+        assert_eq!(
+            payloads,
+            vec![
+                (Side::Scu, "abcdefghi".to_string()),
+                (Side::Scp, "xyz".to_string()),
+                (Side::Scu, "j".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_scu_without_handshake() {
+        let capture = pcap(&[
+            packet(SCP, SCU, 7, TCP_ACK, b"\x02\0\0\0\0\0"),
+            packet(SCU, SCP, 1, TCP_ACK, b"\x05\0\0\0\0\0"),
+            // an A-ASSOCIATE-RQ from the node with the lowest port
+            packet("10.0.0.3:104", "10.0.0.4:60000", 1, TCP_ACK, b"\x01\0"),
+        ]);
+        let connections = read_capture(&capture[..]).unwrap();
+        assert_eq!(connections.len(), 2);
+        // the higher port is taken to be the SCU
+        assert_eq!(connections[0].scu, SCU.parse().unwrap());
+        assert_eq!(connections[1].scu, "10.0.0.3:104".parse().unwrap());
+    }
+}
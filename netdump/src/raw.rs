@@ -0,0 +1,160 @@
+//! Support for raw captures,
+//! holding the bytes sent by each side of an association
+//! without any packet headers or timestamps.
+use dicom_ul::dimse::{MessageAssembler, StatusType};
+use dicom_ul::pdu::{read_pdu, Pdu, MAXIMUM_PDU_SIZE};
+
+use crate::Side;
+
+/// Guess which side of an association sent a raw byte stream
+/// from its first PDU,
+/// assuming that it is the SCU unless an SCP response is found.
+pub fn guess_side(data: &[u8]) -> Side {
+    match data.first() {
+        // A-ASSOCIATE-AC or A-ASSOCIATE-RJ
+        Some(0x02) | Some(0x03) => Side::Scp,
+        _ => Side::Scu,
+    }
+}
+
+/// Split a byte stream into the bytes of each PDU,
+/// leaving any incomplete PDU at the end.
+fn split_pdus(mut data: &[u8]) -> Vec<&[u8]> {
+    let mut pdus = Vec::new();
+    while data.len() >= 6 {
+        let length = u32::from_be_bytes([data[2], data[3], data[4], data[5]]) as usize;
+        let end = (6 + length).min(data.len());
+        let (pdu, rest) = data.split_at(end);
+        pdus.push(pdu);
+        data = rest;
+    }
+    if !data.is_empty() {
+        pdus.push(data);
+    }
+    pdus
+}
+
+/// The progress of one side through its byte stream.
+struct Turns<'a> {
+    pdus: std::vec::IntoIter<&'a [u8]>,
+    assembler: MessageAssembler,
+}
+
+impl<'a> Turns<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Turns {
+            pdus: split_pdus(data).into_iter(),
+            assembler: MessageAssembler::new(),
+        }
+    }
+
+    /// Take the PDUs of the next turn of this side,
+    /// up to the first PDU which expects an answer:
+    /// one which is not P-DATA,
+    /// or one which completes a DIMSE message
+    /// other than a pending response.
+    fn next_turn(&mut self, out: &mut Vec<&'a [u8]>) {
+        for bytes in self.pdus.by_ref() {
+            out.push(bytes);
+            match read_pdu(bytes, MAXIMUM_PDU_SIZE, false) {
+                Ok(Some(Pdu::PData { data })) => {
+                    if self.assembler.push_values(data).is_err() {
+                        self.assembler = MessageAssembler::new();
+                        continue;
+                    }
+                    let mut completed = false;
+                    let mut pending = false;
+                    while let Some(message) = self.assembler.pop_message() {
+                        completed = true;
+                        pending = message
+                            .command
+                            .status()
+                            .map(|status| status.status_type() == StatusType::Pending)
+                            .unwrap_or(false);
+                    }
+                    if completed && !pending {
+                        return;
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+}
+
+/// Put the PDUs sent by the SCU and by the SCP of an association,
+/// recorded separately, in a plausible order,
+/// so that they can be fed to a [`Decoder`](crate::Decoder).
+///
+/// The two sides are assumed to take turns,
+/// each side sending PDUs until it expects an answer,
+/// as in the exchange of requests and responses.
+pub fn interleave<'a>(scu: &'a [u8], scp: &'a [u8]) -> Vec<(Side, &'a [u8])> {
+    let mut sides = [Turns::new(scu), Turns::new(scp)];
+    let mut out = Vec::new();
+    let mut turn = Vec::new();
+    let mut side = Side::Scu;
+    let mut idle = 0;
+    while idle < 2 {
+        sides[side.index()].next_turn(&mut turn);
+        if turn.is_empty() {
+            idle += 1;
+        } else {
+            idle = 0;
+            out.extend(turn.drain(..).map(|bytes| (side, bytes)));
+        }
+        side = side.peer();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_ul::dimse::composite::{CFindRq, CFindRsp};
+    use dicom_ul::dimse::{DimseMessage, Status};
+    use dicom_ul::pdu::write_pdu;
+
+    fn encode(pdus: &[Pdu]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for pdu in pdus {
+            write_pdu(&mut data, pdu).unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn interleave_requests_and_responses() {
+        let rq = CFindRq::new(1, "1.2.840.10008.5.1.4.1.2.2.1");
+        let find = DimseMessage::new(1, rq.clone()).with_data(vec![]);
+        let pending = DimseMessage::new(1, CFindRsp::new(&rq, Status::PENDING)).with_data(vec![]);
+        let done = DimseMessage::new(1, CFindRsp::new(&rq, Status::SUCCESS));
+        let scu = encode(&[find.to_pdus(16384).unwrap(), vec![Pdu::ReleaseRQ]].concat());
+        let scp = encode(
+            &[
+                pending.to_pdus(16384).unwrap(),
+                pending.to_pdus(16384).unwrap(),
+                done.to_pdus(16384).unwrap(),
+                vec![Pdu::ReleaseRP],
+            ]
+            .concat(),
+        );
+
+        let sides: Vec<Side> = interleave(&scu, &scp)
+            .into_iter()
+            .map(|(side, _)| side)
+            .collect();
+        assert_eq!(
+            sides,
+            vec![
+                Side::Scu,
+                Side::Scp,
+                Side::Scp,
+                Side::Scp,
+                Side::Scu,
+                Side::Scp
+            ]
+        );
+        assert_eq!(guess_side(&scu), Side::Scu);
+    }
+}
@@ -0,0 +1,1550 @@
+//! De-identification of DICOM objects.
+//!
+//! This module implements the
+//! Basic Application Level Confidentiality Profile
+//! (PS3.15, annex E),
+//! which removes or replaces the attributes
+//! which could identify the patient
+//! according to the actions of table E.1-1,
+//! including in nested data sets.
+//! The profile options
+//! (see [`ProfileOption`])
+//! relax or refine these actions,
+//! such as for retaining dates or device identity.
+//!
+//! A [`Deidentifier`] is configured once
+//! and then applied to each object of a batch,
+//! so that the same original UID
+//! is always replaced with the same new UID,
+//! preserving the references between the objects.
+//! The objects are also marked as de-identified,
+//! through the _Patient Identity Removed_
+//! and _De-identification Method_ attributes.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_object::deidentify::{Deidentifier, ProfileOption};
+//! use dicom_object::open_file;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut deidentifier = Deidentifier::new()
+//!     .option(ProfileOption::RetainLongitudinalFullDates)
+//!     .option(ProfileOption::RetainPatientCharacteristics);
+//!
+//! for (i, path) in ["1/0001.dcm", "1/0002.dcm"].iter().enumerate() {
+//!     let mut obj = open_file(path)?;
+//!     deidentifier.deidentify_file(&mut obj)?;
+//!     obj.write_to_file(format!("out/{:04}.dcm", i))?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use dicom_core::chrono::{Duration, NaiveDate};
+use dicom_core::ops::{ApplyOp, AttributeAction, AttributeOp};
+use dicom_core::value::DataSetSequence;
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::tags;
+use snafu::{ensure, Backtrace, ResultExt, Snafu};
+
+use crate::ops::ApplyError;
+use crate::{FileDicomObject, InMemDicomObject};
+
+/// An error which may occur when de-identifying an object.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// Could not apply de-identification action to {tag}
+    ApplyAction {
+        tag: Tag,
+        source: ApplyError,
+        backtrace: Backtrace,
+    },
+    /// Retaining modified dates requires a non-zero date offset
+    MissingDateOffset { backtrace: Backtrace },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The value given to attributes replaced with a dummy value.
+const DUMMY_TEXT: &str = "ANONYMIZED";
+
+/// The text recorded in _De-identification Method_.
+const METHOD_DESCRIPTION: &str = "PS3.15 Basic Application Level Confidentiality Profile";
+
+/// An option of the Basic Application Level Confidentiality Profile
+/// (PS3.15, section E.3).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum ProfileOption {
+    /// Retain private attributes known to be safe
+    /// (see [`Deidentifier::safe_private`]).
+    RetainSafePrivate,
+    /// Retain the original UIDs.
+    RetainUids,
+    /// Retain the attributes identifying the equipment.
+    RetainDeviceIdentity,
+    /// Retain the attributes identifying the institution.
+    RetainInstitutionIdentity,
+    /// Retain the patient's age, sex, size, weight,
+    /// and similar characteristics.
+    RetainPatientCharacteristics,
+    /// Retain dates and times as they are.
+    RetainLongitudinalFullDates,
+    /// Retain dates shifted by the offset given
+    /// with [`Deidentifier::date_offset`],
+    /// which must not be zero.
+    RetainLongitudinalModifiedDates,
+    /// Keep descriptive text attributes,
+    /// cleaned with the function given with [`Deidentifier::cleaner`].
+    CleanDescriptors,
+    /// Keep structured content,
+    /// its text cleaned with the function given with [`Deidentifier::cleaner`].
+    CleanStructuredContent,
+    /// Keep graphic annotations and overlays,
+    /// assumed to have been cleaned of identifying information.
+    CleanGraphics,
+}
+
+impl ProfileOption {
+    /// The code value and meaning of the option
+    /// in the DCMR context group 7050 (De-identification Method).
+    pub fn code(self) -> (&'static str, &'static str) {
+        match self {
+            ProfileOption::RetainSafePrivate => ("113111", "Retain Safe Private Option"),
+            ProfileOption::RetainUids => ("113110", "Retain UIDs Option"),
+            ProfileOption::RetainDeviceIdentity => ("113109", "Retain Device Identity Option"),
+            ProfileOption::RetainInstitutionIdentity => {
+                ("113112", "Retain Institution Identity Option")
+            }
+            ProfileOption::RetainPatientCharacteristics => {
+                ("113108", "Retain Patient Characteristics Option")
+            }
+            ProfileOption::RetainLongitudinalFullDates => (
+                "113106",
+                "Retain Longitudinal Temporal Information Full Dates Option",
+            ),
+            ProfileOption::RetainLongitudinalModifiedDates => (
+                "113107",
+                "Retain Longitudinal Temporal Information Modified Dates Option",
+            ),
+            ProfileOption::CleanDescriptors => ("113105", "Clean Descriptors Option"),
+            ProfileOption::CleanStructuredContent => ("113104", "Clean Structured Content Option"),
+            ProfileOption::CleanGraphics => ("113103", "Clean Graphics Option"),
+        }
+    }
+}
+
+/// An action of table E.1-1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Action {
+    /// `X`: remove the attribute
+    Remove,
+    /// `Z`: replace with a zero length value
+    Empty,
+    /// `D`: replace with a dummy value
+    Dummy,
+    /// `U`: replace with a consistently mapped UID
+    Uid,
+    /// `K`: keep the attribute,
+    /// de-identifying its nested data sets
+    Keep,
+    /// `C`: clean the value
+    Clean,
+}
+
+use self::Action::*;
+use self::ProfileOption::*;
+
+type OptionActions = &'static [(ProfileOption, Action)];
+
+const NONE: OptionActions = &[];
+const UIDS: OptionActions = &[(RetainUids, Keep)];
+const DEVICE: OptionActions = &[(RetainDeviceIdentity, Keep)];
+const DEVICE_OR_UIDS: OptionActions = &[(RetainDeviceIdentity, Keep), (RetainUids, Keep)];
+const DEVICE_OR_DESCRIPTORS: OptionActions =
+    &[(RetainDeviceIdentity, Keep), (CleanDescriptors, Clean)];
+const INSTITUTION: OptionActions = &[(RetainInstitutionIdentity, Keep)];
+const PATIENT: OptionActions = &[(RetainPatientCharacteristics, Keep)];
+const DATES: OptionActions = &[
+    (RetainLongitudinalFullDates, Keep),
+    (RetainLongitudinalModifiedDates, Clean),
+];
+const TIMEZONE: OptionActions = &[
+    (RetainLongitudinalFullDates, Keep),
+    (RetainLongitudinalModifiedDates, Keep),
+];
+const DESCRIPTORS: OptionActions = &[(CleanDescriptors, Clean)];
+const STRUCTURED_CONTENT: OptionActions = &[(CleanStructuredContent, Clean)];
+const GRAPHICS: OptionActions = &[(CleanGraphics, Clean)];
+
+/// The attributes affected by the profile (PS3.15, table E.1-1),
+/// with their basic action
+/// and the actions of the options affecting them,
+/// the first enabled option taking precedence.
+///
+/// Where the table leaves the choice to the implementation,
+/// the action keeping the attribute is taken
+/// (`Z` for `X/Z` and `D` for `Z/D`, `X/D`, and `X/Z/D`),
+/// so that type 1 and type 2 attributes remain present.
+/// Sequences marked `X/Z/U*` are kept,
+/// the UIDs within being replaced as their data sets are de-identified.
+// retired attributes are still listed by the profile
+#[allow(deprecated)]
+static PROFILE: &[(Tag, Action, OptionActions)] = &[
+    (tags::ACCESSION_NUMBER, Empty, NONE),
+    (tags::ACQUISITION_COMMENTS, Remove, DESCRIPTORS),
+    (tags::ACQUISITION_CONTEXT_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::ACQUISITION_CONTEXT_SEQUENCE, Remove, NONE),
+    (tags::ACQUISITION_DATE, Empty, DATES),
+    (tags::ACQUISITION_DATE_TIME, Dummy, DATES),
+    (
+        tags::ACQUISITION_DEVICE_PROCESSING_DESCRIPTION,
+        Dummy,
+        DEVICE_OR_DESCRIPTORS,
+    ),
+    (tags::ACQUISITION_FIELD_OF_VIEW_LABEL, Dummy, DESCRIPTORS),
+    (tags::ACQUISITION_PROTOCOL_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::ACQUISITION_TIME, Empty, DATES),
+    (tags::ACQUISITION_UID, Uid, UIDS),
+    (tags::ACTUAL_HUMAN_PERFORMERS_SEQUENCE, Remove, NONE),
+    (tags::ADDITIONAL_PATIENT_HISTORY, Remove, DESCRIPTORS),
+    (tags::ADDRESS_TRIAL, Remove, NONE),
+    (tags::ADMISSION_ID, Remove, NONE),
+    (tags::ADMITTING_DATE, Remove, DATES),
+    (tags::ADMITTING_DIAGNOSES_CODE_SEQUENCE, Remove, DESCRIPTORS),
+    (tags::ADMITTING_DIAGNOSES_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::ADMITTING_TIME, Remove, DATES),
+    (tags::AFFECTED_SOP_INSTANCE_UID, Remove, NONE),
+    (tags::ALLERGIES, Remove, DESCRIPTORS),
+    (tags::ATTRIBUTE_MODIFICATION_DATE_TIME, Dummy, DATES),
+    (tags::AUTHOR_OBSERVER_SEQUENCE, Remove, NONE),
+    (tags::BEAM_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::BEAM_HOLD_TRANSITION_DATE_TIME, Remove, DATES),
+    (tags::BOLUS_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::BRANCH_OF_SERVICE, Remove, NONE),
+    (tags::CASSETTE_ID, Remove, DEVICE),
+    (tags::CERTIFICATE_OF_SIGNER, Remove, NONE),
+    (tags::CERTIFIED_TIMESTAMP, Remove, NONE),
+    (tags::CLINICAL_TRIAL_COORDINATING_CENTER_NAME, Empty, NONE),
+    (
+        tags::CLINICAL_TRIAL_PROTOCOL_ETHICS_COMMITTEE_APPROVAL_NUMBER,
+        Remove,
+        NONE,
+    ),
+    (
+        tags::CLINICAL_TRIAL_PROTOCOL_ETHICS_COMMITTEE_NAME,
+        Dummy,
+        NONE,
+    ),
+    (tags::CLINICAL_TRIAL_SERIES_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::CLINICAL_TRIAL_SERIES_ID, Remove, NONE),
+    (tags::CLINICAL_TRIAL_SPONSOR_NAME, Dummy, NONE),
+    (tags::CLINICAL_TRIAL_SUBJECT_ID, Dummy, NONE),
+    (tags::CLINICAL_TRIAL_SUBJECT_READING_ID, Dummy, NONE),
+    (
+        tags::CLINICAL_TRIAL_TIME_POINT_DESCRIPTION,
+        Remove,
+        DESCRIPTORS,
+    ),
+    (tags::CLINICAL_TRIAL_TIME_POINT_ID, Empty, NONE),
+    (tags::COMMENTS_ON_RADIATION_DOSE, Remove, DESCRIPTORS),
+    (
+        tags::COMMENTS_ON_THE_PERFORMED_PROCEDURE_STEP,
+        Remove,
+        DESCRIPTORS,
+    ),
+    (tags::COMPENSATOR_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::CONCATENATION_UID, Uid, UIDS),
+    (
+        tags::CONCEPTUAL_VOLUME_COMBINATION_DESCRIPTION,
+        Remove,
+        DESCRIPTORS,
+    ),
+    (tags::CONCEPTUAL_VOLUME_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::CONCEPTUAL_VOLUME_UID, Uid, UIDS),
+    (
+        tags::CONFIDENTIALITY_CONSTRAINT_ON_PATIENT_DATA_DESCRIPTION,
+        Remove,
+        NONE,
+    ),
+    (tags::CONSTITUENT_CONCEPTUAL_VOLUME_UID, Uid, UIDS),
+    (
+        tags::CONSULTING_PHYSICIAN_IDENTIFICATION_SEQUENCE,
+        Remove,
+        NONE,
+    ),
+    (tags::CONSULTING_PHYSICIAN_NAME, Remove, NONE),
+    (tags::CONTAINER_COMPONENT_ID, Remove, NONE),
+    (tags::CONTAINER_DESCRIPTION, Remove, NONE),
+    (tags::CONTAINER_IDENTIFIER, Dummy, NONE),
+    (
+        tags::CONTENT_CREATOR_IDENTIFICATION_CODE_SEQUENCE,
+        Remove,
+        NONE,
+    ),
+    (tags::CONTENT_CREATOR_NAME, Empty, NONE),
+    (tags::CONTENT_DATE, Dummy, DATES),
+    (tags::CONTENT_SEQUENCE, Remove, STRUCTURED_CONTENT),
+    (tags::CONTENT_TIME, Dummy, DATES),
+    (tags::CONTEXT_GROUP_EXTENSION_CREATOR_UID, Uid, UIDS),
+    (tags::CONTRAST_BOLUS_AGENT, Dummy, DESCRIPTORS),
+    (tags::CONTRAST_BOLUS_START_TIME, Remove, DATES),
+    (tags::CONTRAST_BOLUS_STOP_TIME, Remove, DATES),
+    (tags::CONTRIBUTION_DATE_TIME, Remove, DATES),
+    (tags::CONTRIBUTION_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::COUNTRY_OF_RESIDENCE, Remove, NONE),
+    (tags::CURRENT_OBSERVER_TRIAL, Remove, NONE),
+    (tags::CURRENT_PATIENT_LOCATION, Remove, NONE),
+    (tags::CURVE_DATE, Remove, DATES),
+    (tags::CURVE_TIME, Remove, DATES),
+    (tags::CUSTODIAL_ORGANIZATION_SEQUENCE, Remove, INSTITUTION),
+    (tags::DATA_SET_TRAILING_PADDING, Remove, NONE),
+    (tags::DATE, Remove, DATES),
+    (
+        tags::DATE_OF_DOCUMENT_OR_VERBAL_TRANSACTION_TRIAL,
+        Remove,
+        DATES,
+    ),
+    (tags::DATE_OF_LAST_CALIBRATION, Remove, DATES),
+    (tags::DATE_OF_LAST_DETECTOR_CALIBRATION, Remove, DATES),
+    (tags::DATE_OF_SECONDARY_CAPTURE, Remove, DATES),
+    (tags::DATE_TIME, Remove, DATES),
+    (tags::DATE_TIME_OF_LAST_CALIBRATION, Remove, DATES),
+    (tags::DECAY_CORRECTION_DATE_TIME, Dummy, DATES),
+    (tags::DECOMPOSITION_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::DERIVATION_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::DESTINATION_AE, Remove, NONE),
+    (tags::DETECTOR_ID, Dummy, DEVICE),
+    (tags::DEVICE_ALTERNATE_IDENTIFIER, Remove, DEVICE),
+    (tags::DEVICE_DESCRIPTION, Remove, DEVICE_OR_DESCRIPTORS),
+    (tags::DEVICE_LABEL, Remove, DEVICE),
+    (tags::DEVICE_SERIAL_NUMBER, Dummy, DEVICE),
+    (tags::DEVICE_SETTING_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::DEVICE_UID, Uid, DEVICE_OR_UIDS),
+    (tags::DIGITAL_SIGNATURES_SEQUENCE, Remove, NONE),
+    (tags::DIGITAL_SIGNATURE_DATE_TIME, Remove, DATES),
+    (tags::DIGITAL_SIGNATURE_UID, Remove, NONE),
+    (tags::DIMENSION_ORGANIZATION_UID, Uid, UIDS),
+    (tags::DISCHARGE_DIAGNOSIS_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::DISTRIBUTION_ADDRESS, Remove, NONE),
+    (tags::DISTRIBUTION_NAME, Remove, NONE),
+    (tags::DOSE_REFERENCE_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::DOSE_REFERENCE_UID, Uid, UIDS),
+    (tags::DOSIMETRIC_OBJECTIVE_UID, Uid, UIDS),
+    (tags::EFFECTIVE_DATE_TIME, Remove, DATES),
+    (tags::ENCAPSULATED_DOCUMENT, Remove, NONE),
+    (tags::ENCRYPTED_ATTRIBUTES_SEQUENCE, Remove, NONE),
+    (tags::END_ACQUISITION_DATE_TIME, Dummy, DATES),
+    (tags::ENTITY_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::ENTITY_LABEL, Dummy, DESCRIPTORS),
+    (tags::ENTITY_LONG_LABEL, Dummy, DESCRIPTORS),
+    (tags::ENTITY_NAME, Remove, DESCRIPTORS),
+    (
+        tags::EQUIPMENT_FRAME_OF_REFERENCE_DESCRIPTION,
+        Remove,
+        DESCRIPTORS,
+    ),
+    (tags::ETHNIC_GROUP, Remove, PATIENT),
+    (tags::EXCLUSION_START_DATE_TIME, Remove, DATES),
+    (tags::EXPECTED_COMPLETION_DATE_TIME, Remove, DATES),
+    (tags::FAILED_SOP_INSTANCE_UID_LIST, Uid, UIDS),
+    (tags::FIDUCIAL_UID, Uid, UIDS),
+    (
+        tags::FILLER_ORDER_NUMBER_IMAGING_SERVICE_REQUEST,
+        Empty,
+        NONE,
+    ),
+    (tags::FIRST_TREATMENT_DATE, Remove, DATES),
+    (tags::FRAME_ACQUISITION_DATE_TIME, Dummy, DATES),
+    (tags::FRAME_COMMENTS, Remove, DESCRIPTORS),
+    (tags::FRAME_OF_REFERENCE_UID, Uid, UIDS),
+    (tags::FRAME_ORIGIN_TIMESTAMP, Dummy, DATES),
+    (tags::FRAME_REFERENCE_DATE_TIME, Dummy, DATES),
+    (tags::GANTRY_ID, Remove, DEVICE),
+    (tags::GENERATOR_ID, Remove, DEVICE),
+    (tags::GPSDOP, Remove, NONE),
+    (tags::GPS_ALTITUDE, Remove, NONE),
+    (tags::GPS_ALTITUDE_REF, Remove, NONE),
+    (tags::GPS_AREA_INFORMATION, Remove, NONE),
+    (tags::GPS_DATE_STAMP, Remove, NONE),
+    (tags::GPS_DEST_BEARING, Remove, NONE),
+    (tags::GPS_DEST_BEARING_REF, Remove, NONE),
+    (tags::GPS_DEST_DISTANCE, Remove, NONE),
+    (tags::GPS_DEST_DISTANCE_REF, Remove, NONE),
+    (tags::GPS_DEST_LATITUDE, Remove, NONE),
+    (tags::GPS_DEST_LATITUDE_REF, Remove, NONE),
+    (tags::GPS_DEST_LONGITUDE, Remove, NONE),
+    (tags::GPS_DEST_LONGITUDE_REF, Remove, NONE),
+    (tags::GPS_DIFFERENTIAL, Remove, NONE),
+    (tags::GPS_IMG_DIRECTION, Remove, NONE),
+    (tags::GPS_IMG_DIRECTION_REF, Remove, NONE),
+    (tags::GPS_LATITUDE, Remove, NONE),
+    (tags::GPS_LATITUDE_REF, Remove, NONE),
+    (tags::GPS_LONGITUDE, Remove, NONE),
+    (tags::GPS_LONGITUDE_REF, Remove, NONE),
+    (tags::GPS_MAP_DATUM, Remove, NONE),
+    (tags::GPS_MEASURE_MODE, Remove, NONE),
+    (tags::GPS_PROCESSING_METHOD, Remove, NONE),
+    (tags::GPS_SATELLITES, Remove, NONE),
+    (tags::GPS_SPEED, Remove, NONE),
+    (tags::GPS_SPEED_REF, Remove, NONE),
+    (tags::GPS_STATUS, Remove, NONE),
+    (tags::GPS_TIME_STAMP, Remove, NONE),
+    (tags::GPS_TRACK, Remove, NONE),
+    (tags::GPS_TRACK_REF, Remove, NONE),
+    (tags::GPS_VERSION_ID, Remove, NONE),
+    (tags::GRAPHIC_ANNOTATION_SEQUENCE, Dummy, GRAPHICS),
+    (tags::HUMAN_PERFORMER_NAME, Remove, NONE),
+    (tags::HUMAN_PERFORMER_ORGANIZATION, Remove, INSTITUTION),
+    (tags::ICON_IMAGE_SEQUENCE, Remove, NONE),
+    (tags::IDENTIFYING_COMMENTS, Remove, DESCRIPTORS),
+    (tags::IMAGE_COMMENTS, Remove, DESCRIPTORS),
+    (tags::IMAGE_PRESENTATION_COMMENTS, Remove, DESCRIPTORS),
+    (tags::IMAGING_SERVICE_REQUEST_COMMENTS, Remove, DESCRIPTORS),
+    (tags::IMPRESSIONS, Remove, DESCRIPTORS),
+    (tags::INSTANCE_COERCION_DATE_TIME, Remove, DATES),
+    (tags::INSTANCE_CREATION_DATE, Remove, DATES),
+    (tags::INSTANCE_CREATION_TIME, Remove, DATES),
+    (tags::INSTANCE_CREATOR_UID, Uid, UIDS),
+    (tags::INSTANCE_ORIGIN_STATUS, Remove, NONE),
+    (tags::INSTITUTIONAL_DEPARTMENT_NAME, Remove, INSTITUTION),
+    (
+        tags::INSTITUTIONAL_DEPARTMENT_TYPE_CODE_SEQUENCE,
+        Remove,
+        INSTITUTION,
+    ),
+    (tags::INSTITUTION_ADDRESS, Remove, INSTITUTION),
+    (tags::INSTITUTION_CODE_SEQUENCE, Empty, INSTITUTION),
+    (tags::INSTITUTION_NAME, Empty, INSTITUTION),
+    (tags::INSURANCE_PLAN_IDENTIFICATION, Remove, NONE),
+    (tags::INTENDED_PHASE_START_DATE, Remove, DATES),
+    (
+        tags::INTENDED_RECIPIENTS_OF_RESULTS_IDENTIFICATION_SEQUENCE,
+        Remove,
+        NONE,
+    ),
+    (tags::INTERLOCK_DATE_TIME, Remove, DATES),
+    (tags::INTERLOCK_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::INTERLOCK_ORIGIN_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::INTERPRETATION_APPROVAL_DATE, Remove, DATES),
+    (tags::INTERPRETATION_APPROVAL_TIME, Remove, DATES),
+    (tags::INTERPRETATION_APPROVER_SEQUENCE, Remove, NONE),
+    (tags::INTERPRETATION_AUTHOR, Remove, NONE),
+    (
+        tags::INTERPRETATION_DIAGNOSIS_DESCRIPTION,
+        Remove,
+        DESCRIPTORS,
+    ),
+    (tags::INTERPRETATION_ID, Remove, NONE),
+    (tags::INTERPRETATION_ID_ISSUER, Remove, NONE),
+    (tags::INTERPRETATION_RECORDED_DATE, Remove, DATES),
+    (tags::INTERPRETATION_RECORDED_TIME, Remove, DATES),
+    (tags::INTERPRETATION_RECORDER, Remove, NONE),
+    (tags::INTERPRETATION_TEXT, Remove, DESCRIPTORS),
+    (tags::INTERPRETATION_TRANSCRIBER, Remove, NONE),
+    (tags::INTERPRETATION_TRANSCRIPTION_DATE, Remove, DATES),
+    (tags::INTERPRETATION_TRANSCRIPTION_TIME, Remove, DATES),
+    (tags::IRRADIATION_EVENT_UID, Uid, UIDS),
+    (tags::ISSUER_OF_ACCESSION_NUMBER_SEQUENCE, Remove, NONE),
+    (tags::ISSUER_OF_ADMISSION_ID, Remove, NONE),
+    (tags::ISSUER_OF_ADMISSION_ID_SEQUENCE, Remove, NONE),
+    (tags::ISSUER_OF_PATIENT_ID, Remove, NONE),
+    (tags::ISSUER_OF_SERVICE_EPISODE_ID, Remove, NONE),
+    (tags::ISSUER_OF_SERVICE_EPISODE_ID_SEQUENCE, Remove, NONE),
+    (tags::ISSUE_DATE_OF_IMAGING_SERVICE_REQUEST, Remove, DATES),
+    (tags::ISSUE_TIME_OF_IMAGING_SERVICE_REQUEST, Remove, DATES),
+    (tags::LARGE_PALETTE_COLOR_LOOKUP_TABLE_UID, Uid, UIDS),
+    (tags::LAST_MENSTRUAL_DATE, Remove, DATES),
+    (tags::LENS_MAKE, Remove, DEVICE),
+    (tags::LENS_MODEL, Remove, DEVICE),
+    (tags::LENS_SERIAL_NUMBER, Remove, DEVICE),
+    (tags::LONG_DEVICE_DESCRIPTION, Remove, DEVICE),
+    (tags::MAC, Remove, NONE),
+    (tags::MAKER_NOTE, Remove, NONE),
+    (tags::MANUFACTURER_DEVICE_CLASS_UID, Uid, DEVICE_OR_UIDS),
+    (tags::MAPPING_RESOURCE_UID, Uid, UIDS),
+    (tags::MEDICAL_ALERTS, Remove, DESCRIPTORS),
+    (tags::MEDICAL_RECORD_LOCATOR, Remove, NONE),
+    (tags::MILITARY_RANK, Remove, NONE),
+    (tags::MODIFIED_ATTRIBUTES_SEQUENCE, Remove, NONE),
+    (tags::MODIFIED_IMAGE_DATE, Remove, DATES),
+    (tags::MODIFIED_IMAGE_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::MODIFIED_IMAGE_TIME, Remove, DATES),
+    (tags::MODIFYING_DEVICE_ID, Remove, DEVICE),
+    (tags::MODIFYING_DEVICE_MANUFACTURER, Remove, DEVICE),
+    (tags::NAMES_OF_INTENDED_RECIPIENTS_OF_RESULTS, Remove, NONE),
+    (tags::NAME_OF_PHYSICIANS_READING_STUDY, Remove, NONE),
+    (tags::OBSERVATION_DATE_TIME, Dummy, DATES),
+    (tags::OBSERVATION_START_DATE_TIME, Remove, DATES),
+    (tags::OBSERVATION_SUBJECT_UID_TRIAL, Uid, UIDS),
+    (tags::OBSERVATION_UID, Uid, UIDS),
+    (tags::OCCUPATION, Remove, DESCRIPTORS),
+    (tags::OPERATORS_NAME, Empty, NONE),
+    (tags::OPERATOR_IDENTIFICATION_SEQUENCE, Remove, NONE),
+    (tags::ORDER_CALLBACK_PHONE_NUMBER, Remove, NONE),
+    (tags::ORDER_CALLBACK_TELECOM_INFORMATION, Remove, NONE),
+    (tags::ORDER_ENTERED_BY, Remove, NONE),
+    (tags::ORDER_ENTERER_LOCATION, Remove, NONE),
+    (tags::ORIGINAL_ATTRIBUTES_SEQUENCE, Remove, NONE),
+    (tags::OTHER_PATIENT_I_DS, Remove, NONE),
+    (tags::OTHER_PATIENT_I_DS_SEQUENCE, Remove, NONE),
+    (tags::OTHER_PATIENT_NAMES, Remove, NONE),
+    (tags::PARTICIPANT_SEQUENCE, Remove, NONE),
+    (tags::PATIENT_ADDRESS, Remove, NONE),
+    (tags::PATIENT_AGE, Remove, PATIENT),
+    (tags::PATIENT_ALTERNATIVE_CALENDAR, Remove, NONE),
+    (tags::PATIENT_BIRTH_DATE, Empty, NONE),
+    (
+        tags::PATIENT_BIRTH_DATE_IN_ALTERNATIVE_CALENDAR,
+        Remove,
+        NONE,
+    ),
+    (tags::PATIENT_BIRTH_NAME, Remove, NONE),
+    (tags::PATIENT_BIRTH_TIME, Remove, NONE),
+    (tags::PATIENT_COMMENTS, Remove, DESCRIPTORS),
+    (
+        tags::PATIENT_DEATH_DATE_IN_ALTERNATIVE_CALENDAR,
+        Remove,
+        NONE,
+    ),
+    (tags::PATIENT_ID, Empty, NONE),
+    (tags::PATIENT_INSURANCE_PLAN_CODE_SEQUENCE, Remove, NONE),
+    (tags::PATIENT_MOTHER_BIRTH_NAME, Remove, NONE),
+    (tags::PATIENT_NAME, Empty, NONE),
+    (tags::PATIENT_PRIMARY_LANGUAGE_CODE_SEQUENCE, Remove, NONE),
+    (
+        tags::PATIENT_PRIMARY_LANGUAGE_MODIFIER_CODE_SEQUENCE,
+        Remove,
+        NONE,
+    ),
+    (tags::PATIENT_RELIGIOUS_PREFERENCE, Remove, NONE),
+    (tags::PATIENT_SETUP_PHOTO_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::PATIENT_SEX, Empty, PATIENT),
+    (tags::PATIENT_SEX_NEUTERED, Empty, PATIENT),
+    (tags::PATIENT_SIZE, Remove, PATIENT),
+    (tags::PATIENT_STATE, Remove, DESCRIPTORS),
+    (tags::PATIENT_TELECOM_INFORMATION, Remove, NONE),
+    (tags::PATIENT_TELEPHONE_NUMBERS, Remove, NONE),
+    (tags::PATIENT_TRANSPORT_ARRANGEMENTS, Remove, NONE),
+    (tags::PATIENT_WEIGHT, Remove, PATIENT),
+    (tags::PERFORMED_LOCATION, Remove, NONE),
+    (
+        tags::PERFORMED_PROCEDURE_STEP_DESCRIPTION,
+        Remove,
+        DESCRIPTORS,
+    ),
+    (tags::PERFORMED_PROCEDURE_STEP_END_DATE, Remove, DATES),
+    (tags::PERFORMED_PROCEDURE_STEP_END_DATE_TIME, Remove, DATES),
+    (tags::PERFORMED_PROCEDURE_STEP_END_TIME, Remove, DATES),
+    (tags::PERFORMED_PROCEDURE_STEP_ID, Remove, NONE),
+    (tags::PERFORMED_PROCEDURE_STEP_START_DATE, Remove, DATES),
+    (
+        tags::PERFORMED_PROCEDURE_STEP_START_DATE_TIME,
+        Remove,
+        DATES,
+    ),
+    (tags::PERFORMED_PROCEDURE_STEP_START_TIME, Remove, DATES),
+    (tags::PERFORMED_STATION_AE_TITLE, Remove, NONE),
+    (
+        tags::PERFORMED_STATION_GEOGRAPHIC_LOCATION_CODE_SEQUENCE,
+        Remove,
+        NONE,
+    ),
+    (tags::PERFORMED_STATION_NAME, Remove, NONE),
+    (tags::PERFORMED_STATION_NAME_CODE_SEQUENCE, Remove, NONE),
+    (
+        tags::PERFORMING_PHYSICIAN_IDENTIFICATION_SEQUENCE,
+        Remove,
+        NONE,
+    ),
+    (tags::PERFORMING_PHYSICIAN_NAME, Remove, NONE),
+    (tags::PERSON_ADDRESS, Remove, NONE),
+    (tags::PERSON_IDENTIFICATION_CODE_SEQUENCE, Dummy, NONE),
+    (tags::PERSON_NAME, Dummy, NONE),
+    (tags::PERSON_TELECOM_INFORMATION, Remove, NONE),
+    (tags::PERSON_TELEPHONE_NUMBERS, Remove, NONE),
+    (tags::PHYSICIANS_OF_RECORD, Remove, NONE),
+    (
+        tags::PHYSICIANS_OF_RECORD_IDENTIFICATION_SEQUENCE,
+        Remove,
+        NONE,
+    ),
+    (
+        tags::PHYSICIANS_READING_STUDY_IDENTIFICATION_SEQUENCE,
+        Remove,
+        NONE,
+    ),
+    (tags::PHYSICIAN_APPROVING_INTERPRETATION, Remove, NONE),
+    (
+        tags::PLACER_ORDER_NUMBER_IMAGING_SERVICE_REQUEST,
+        Empty,
+        NONE,
+    ),
+    (tags::PLATE_ID, Remove, DEVICE),
+    (tags::PREGNANCY_STATUS, Remove, PATIENT),
+    (tags::PRESENTATION_DISPLAY_COLLECTION_UID, Uid, UIDS),
+    (tags::PRESENTATION_SEQUENCE_COLLECTION_UID, Uid, UIDS),
+    (tags::PRE_MEDICATION, Remove, DESCRIPTORS),
+    (tags::PROCEDURE_STEP_CANCELLATION_DATE_TIME, Remove, DATES),
+    (tags::PRODUCT_EXPIRATION_DATE_TIME, Remove, DATES),
+    (tags::PROTOCOL_NAME, Dummy, DESCRIPTORS),
+    (tags::RADIOPHARMACEUTICAL_START_DATE_TIME, Remove, DATES),
+    (tags::RADIOPHARMACEUTICAL_START_TIME, Remove, DATES),
+    (tags::RADIOPHARMACEUTICAL_STOP_DATE_TIME, Remove, DATES),
+    (tags::RADIOPHARMACEUTICAL_STOP_TIME, Remove, DATES),
+    (tags::REASON_FOR_OMISSION_DESCRIPTION, Remove, DESCRIPTORS),
+    (
+        tags::REASON_FOR_REQUESTED_PROCEDURE_CODE_SEQUENCE,
+        Remove,
+        DESCRIPTORS,
+    ),
+    (tags::REASON_FOR_STUDY, Remove, DESCRIPTORS),
+    (
+        tags::REASON_FOR_THE_IMAGING_SERVICE_REQUEST,
+        Remove,
+        DESCRIPTORS,
+    ),
+    (
+        tags::REASON_FOR_THE_REQUESTED_PROCEDURE,
+        Remove,
+        DESCRIPTORS,
+    ),
+    (tags::REFERENCED_DIGITAL_SIGNATURE_SEQUENCE, Remove, NONE),
+    (tags::REFERENCED_FRAME_OF_REFERENCE_UID, Uid, UIDS),
+    (
+        tags::REFERENCED_GENERAL_PURPOSE_SCHEDULED_PROCEDURE_STEP_TRANSACTION_UID,
+        Uid,
+        UIDS,
+    ),
+    (tags::REFERENCED_IMAGE_SEQUENCE, Keep, NONE),
+    (tags::REFERENCED_OBSERVATION_UID_TRIAL, Uid, UIDS),
+    (tags::REFERENCED_PATIENT_ALIAS_SEQUENCE, Remove, NONE),
+    (tags::REFERENCED_PATIENT_PHOTO_SEQUENCE, Remove, NONE),
+    (tags::REFERENCED_PATIENT_SEQUENCE, Remove, NONE),
+    (
+        tags::REFERENCED_PERFORMED_PROCEDURE_STEP_SEQUENCE,
+        Keep,
+        NONE,
+    ),
+    (tags::REFERENCED_SOP_INSTANCE_MAC_SEQUENCE, Remove, NONE),
+    (tags::REFERENCED_SOP_INSTANCE_UID, Uid, UIDS),
+    (tags::REFERENCED_SOP_INSTANCE_UID_IN_FILE, Uid, UIDS),
+    (tags::REFERENCED_STUDY_SEQUENCE, Keep, NONE),
+    (tags::REFERRING_PHYSICIAN_ADDRESS, Remove, NONE),
+    (
+        tags::REFERRING_PHYSICIAN_IDENTIFICATION_SEQUENCE,
+        Remove,
+        NONE,
+    ),
+    (tags::REFERRING_PHYSICIAN_NAME, Empty, NONE),
+    (tags::REFERRING_PHYSICIAN_TELEPHONE_NUMBERS, Remove, NONE),
+    (tags::REGION_OF_RESIDENCE, Remove, NONE),
+    (tags::RELATED_FRAME_OF_REFERENCE_UID, Uid, UIDS),
+    (tags::REQUESTED_CONTRAST_AGENT, Remove, DESCRIPTORS),
+    (tags::REQUESTED_PROCEDURE_COMMENTS, Remove, DESCRIPTORS),
+    (tags::REQUESTED_PROCEDURE_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::REQUESTED_PROCEDURE_ID, Remove, NONE),
+    (tags::REQUESTED_PROCEDURE_LOCATION, Remove, NONE),
+    (tags::REQUESTED_SOP_INSTANCE_UID, Uid, UIDS),
+    (tags::REQUESTING_PHYSICIAN, Remove, NONE),
+    (
+        tags::REQUESTING_PHYSICIAN_IDENTIFICATION_SEQUENCE,
+        Remove,
+        NONE,
+    ),
+    (tags::REQUESTING_SERVICE, Remove, NONE),
+    (tags::REQUESTING_SERVICE_CODE_SEQUENCE, Remove, NONE),
+    (tags::REQUEST_ATTRIBUTES_SEQUENCE, Remove, NONE),
+    (tags::RESPONSIBLE_ORGANIZATION, Remove, NONE),
+    (tags::RESPONSIBLE_PERSON, Remove, NONE),
+    (tags::RESULTS_COMMENTS, Remove, DESCRIPTORS),
+    (tags::RESULTS_DISTRIBUTION_LIST_SEQUENCE, Remove, NONE),
+    (tags::RESULTS_ID, Remove, NONE),
+    (tags::RESULTS_ID_ISSUER, Remove, NONE),
+    (tags::RT_PLAN_DATE, Dummy, DATES),
+    (tags::RT_PLAN_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::RT_PLAN_LABEL, Dummy, DESCRIPTORS),
+    (tags::RT_PLAN_NAME, Remove, DESCRIPTORS),
+    (tags::RT_PLAN_TIME, Dummy, DATES),
+    (tags::RT_TREATMENT_PHASE_INTERVAL_SEQUENCE, Remove, DATES),
+    (tags::SCHEDULED_HUMAN_PERFORMERS_SEQUENCE, Remove, NONE),
+    (tags::SCHEDULED_PATIENT_INSTITUTION_RESIDENCE, Remove, NONE),
+    (
+        tags::SCHEDULED_PERFORMING_PHYSICIAN_IDENTIFICATION_SEQUENCE,
+        Remove,
+        NONE,
+    ),
+    (tags::SCHEDULED_PERFORMING_PHYSICIAN_NAME, Remove, NONE),
+    (
+        tags::SCHEDULED_PROCEDURE_STEP_DESCRIPTION,
+        Remove,
+        DESCRIPTORS,
+    ),
+    (tags::SCHEDULED_PROCEDURE_STEP_END_DATE, Remove, DATES),
+    (tags::SCHEDULED_PROCEDURE_STEP_END_TIME, Remove, DATES),
+    (
+        tags::SCHEDULED_PROCEDURE_STEP_EXPIRATION_DATE_TIME,
+        Remove,
+        DATES,
+    ),
+    (tags::SCHEDULED_PROCEDURE_STEP_ID, Remove, NONE),
+    (tags::SCHEDULED_PROCEDURE_STEP_LOCATION, Remove, NONE),
+    (
+        tags::SCHEDULED_PROCEDURE_STEP_MODIFICATION_DATE_TIME,
+        Remove,
+        DATES,
+    ),
+    (tags::SCHEDULED_PROCEDURE_STEP_START_DATE, Remove, DATES),
+    (
+        tags::SCHEDULED_PROCEDURE_STEP_START_DATE_TIME,
+        Remove,
+        DATES,
+    ),
+    (tags::SCHEDULED_PROCEDURE_STEP_START_TIME, Remove, DATES),
+    (tags::SCHEDULED_STATION_AE_TITLE, Remove, NONE),
+    (
+        tags::SCHEDULED_STATION_GEOGRAPHIC_LOCATION_CODE_SEQUENCE,
+        Remove,
+        NONE,
+    ),
+    (tags::SCHEDULED_STATION_NAME, Remove, NONE),
+    (tags::SCHEDULED_STATION_NAME_CODE_SEQUENCE, Remove, NONE),
+    (tags::SCHEDULED_STUDY_LOCATION, Remove, NONE),
+    (tags::SCHEDULED_STUDY_LOCATION_AE_TITLE, Remove, NONE),
+    (tags::SCHEDULED_STUDY_START_DATE, Remove, DATES),
+    (tags::SCHEDULED_STUDY_START_TIME, Remove, DATES),
+    (tags::SCHEDULED_STUDY_STOP_DATE, Remove, DATES),
+    (tags::SCHEDULED_STUDY_STOP_TIME, Remove, DATES),
+    (tags::SERIES_DATE, Remove, DATES),
+    (tags::SERIES_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::SERIES_INSTANCE_UID, Uid, UIDS),
+    (tags::SERIES_TIME, Remove, DATES),
+    (tags::SERVICE_EPISODE_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::SERVICE_EPISODE_ID, Remove, NONE),
+    (tags::SETUP_TECHNIQUE_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::SHIELDING_DEVICE_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::SLIDE_IDENTIFIER, Remove, NONE),
+    (tags::SMOKING_STATUS, Remove, PATIENT),
+    (tags::SOP_AUTHORIZATION_DATE_TIME, Remove, DATES),
+    (tags::SOP_INSTANCE_UID, Uid, UIDS),
+    (tags::SOURCE_IMAGE_SEQUENCE, Keep, NONE),
+    (tags::SOURCE_MANUFACTURER, Remove, DEVICE),
+    (tags::SOURCE_SERIAL_NUMBER, Remove, DEVICE),
+    (tags::SPECIAL_NEEDS, Remove, PATIENT),
+    (tags::SPECIMEN_ACCESSION_NUMBER, Remove, NONE),
+    (tags::SPECIMEN_DETAILED_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::SPECIMEN_IDENTIFIER, Dummy, NONE),
+    (tags::SPECIMEN_SHORT_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::SPECIMEN_UID, Uid, UIDS),
+    (tags::START_ACQUISITION_DATE_TIME, Dummy, DATES),
+    (tags::STATION_AE_TITLE, Remove, DEVICE),
+    (tags::STATION_NAME, Empty, DEVICE),
+    (tags::STORAGE_MEDIA_FILE_SET_UID, Uid, UIDS),
+    (tags::STRUCTURE_SET_DATE, Empty, DATES),
+    (tags::STRUCTURE_SET_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::STRUCTURE_SET_LABEL, Dummy, DESCRIPTORS),
+    (tags::STRUCTURE_SET_NAME, Remove, DESCRIPTORS),
+    (tags::STRUCTURE_SET_TIME, Empty, DATES),
+    (tags::STUDY_ARRIVAL_DATE, Remove, DATES),
+    (tags::STUDY_ARRIVAL_TIME, Remove, DATES),
+    (tags::STUDY_COMMENTS, Remove, DESCRIPTORS),
+    (tags::STUDY_COMPLETION_DATE, Remove, DATES),
+    (tags::STUDY_COMPLETION_TIME, Remove, DATES),
+    (tags::STUDY_DATE, Empty, DATES),
+    (tags::STUDY_DESCRIPTION, Remove, DESCRIPTORS),
+    (tags::STUDY_ID, Empty, NONE),
+    (tags::STUDY_ID_ISSUER, Remove, NONE),
+    (tags::STUDY_INSTANCE_UID, Uid, UIDS),
+    (tags::STUDY_READ_DATE, Remove, DATES),
+    (tags::STUDY_READ_TIME, Remove, DATES),
+    (tags::STUDY_TIME, Empty, DATES),
+    (tags::STUDY_VERIFIED_DATE, Remove, DATES),
+    (tags::STUDY_VERIFIED_TIME, Remove, DATES),
+    (tags::SUBSTANCE_ADMINISTRATION_DATE_TIME, Remove, DATES),
+    (tags::SYNCHRONIZATION_FRAME_OF_REFERENCE_UID, Uid, UIDS),
+    (tags::TARGET_UID, Uid, UIDS),
+    (tags::TELEPHONE_NUMBER_TRIAL, Remove, NONE),
+    (tags::TEMPLATE_EXTENSION_CREATOR_UID, Uid, UIDS),
+    (tags::TEMPLATE_EXTENSION_ORGANIZATION_UID, Uid, UIDS),
+    (tags::TEMPLATE_LOCAL_VERSION, Remove, DATES),
+    (tags::TEMPLATE_VERSION, Remove, DATES),
+    (tags::TEXT_COMMENTS, Remove, DESCRIPTORS),
+    (tags::TEXT_STRING, Dummy, DESCRIPTORS),
+    (tags::TEXT_VALUE, Remove, STRUCTURED_CONTENT),
+    (tags::TIME, Remove, DATES),
+    (tags::TIMEZONE_OFFSET_FROM_UTC, Remove, TIMEZONE),
+    (
+        tags::TIME_OF_DOCUMENT_CREATION_OR_VERBAL_TRANSACTION_TRIAL,
+        Remove,
+        DATES,
+    ),
+    (tags::TIME_OF_LAST_CALIBRATION, Remove, DATES),
+    (tags::TIME_OF_LAST_DETECTOR_CALIBRATION, Remove, DATES),
+    (tags::TIME_OF_SECONDARY_CAPTURE, Remove, DATES),
+    (tags::TOPIC_AUTHOR, Remove, NONE),
+    (tags::TOPIC_KEYWORDS, Remove, NONE),
+    (tags::TOPIC_SUBJECT, Remove, NONE),
+    (tags::TOPIC_TITLE, Remove, NONE),
+    (tags::TRANSACTION_UID, Uid, UIDS),
+    (tags::TREATMENT_CONTROL_POINT_DATE, Remove, DATES),
+    (tags::TREATMENT_CONTROL_POINT_TIME, Remove, DATES),
+    (tags::TREATMENT_DATE, Remove, DATES),
+    (tags::TREATMENT_SESSION_UID, Uid, UIDS),
+    (tags::TREATMENT_SITE, Remove, DESCRIPTORS),
+    (tags::TREATMENT_TIME, Remove, DATES),
+    (tags::UID, Uid, UIDS),
+    (tags::UNIQUE_DEVICE_IDENTIFIER, Remove, DEVICE),
+    (tags::VERIFICATION_DATE_TIME, Dummy, DATES),
+    (
+        tags::VERIFYING_OBSERVER_IDENTIFICATION_CODE_SEQUENCE,
+        Empty,
+        NONE,
+    ),
+    (tags::VERIFYING_OBSERVER_NAME, Dummy, NONE),
+    (tags::VERIFYING_OBSERVER_SEQUENCE, Dummy, NONE),
+    (tags::VERIFYING_ORGANIZATION, Remove, NONE),
+    (tags::VISIT_COMMENTS, Remove, DESCRIPTORS),
+    (tags::X_RAY_DETECTOR_ID, Remove, DEVICE),
+    (tags::X_RAY_DETECTOR_LABEL, Remove, DEVICE),
+    (tags::X_RAY_SOURCE_ID, Remove, DEVICE),
+];
+
+/// The actions for the given standard attribute,
+/// if it is affected by the profile.
+fn rule_for(tag: Tag) -> Option<(Action, OptionActions)> {
+    if let Some((_, basic, options)) = PROFILE.iter().find(|(t, _, _)| *t == tag) {
+        return Some((*basic, options));
+    }
+    match (tag.group() & 0xFF00, tag.element()) {
+        // curve data
+        (0x5000, _) => Some((Remove, NONE)),
+        // overlay data and comments
+        (0x6000, 0x3000) => Some((Remove, GRAPHICS)),
+        (0x6000, 0x4000) => Some((Remove, DESCRIPTORS)),
+        _ => None,
+    }
+}
+
+/// A function cleaning a textual value of identifying information,
+/// given the attribute's tag.
+type Cleaner = Box<dyn Fn(Tag, &str) -> String + Send + Sync>;
+
+/// A de-identification engine
+/// implementing the Basic Application Level Confidentiality Profile.
+///
+/// See the [module-level documentation](self) for more details.
+pub struct Deidentifier {
+    options: BTreeSet<ProfileOption>,
+    /// days to add to dates under the modified dates option
+    date_offset: i64,
+    /// safe private attributes, by group, private creator, and element
+    safe_private: Vec<(u16, String, u8)>,
+    cleaner: Option<Cleaner>,
+    /// original UID to replacement UID
+    uid_map: HashMap<String, String>,
+}
+
+impl fmt::Debug for Deidentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deidentifier")
+            .field("options", &self.options)
+            .field("date_offset", &self.date_offset)
+            .field("safe_private", &self.safe_private)
+            .field("cleaner", &self.cleaner.as_ref().map(|_| "..."))
+            .field("uid_map", &self.uid_map)
+            .finish()
+    }
+}
+
+impl Default for Deidentifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deidentifier {
+    /// Create a de-identifier applying the basic profile,
+    /// without any options.
+    pub fn new() -> Self {
+        Deidentifier {
+            options: BTreeSet::new(),
+            date_offset: 0,
+            safe_private: Vec::new(),
+            cleaner: None,
+            uid_map: HashMap::new(),
+        }
+    }
+
+    /// Enable a profile option.
+    pub fn option(mut self, option: ProfileOption) -> Self {
+        self.options.insert(option);
+        self
+    }
+
+    /// Define the number of days to add to all dates
+    /// under the option [`RetainLongitudinalModifiedDates`](ProfileOption::RetainLongitudinalModifiedDates).
+    ///
+    /// The same offset should be used for all objects of a patient,
+    /// so that the intervals between dates are preserved.
+    /// De-identification fails under that option
+    /// if the offset is left at zero,
+    /// since the dates would not be modified at all.
+    pub fn date_offset(mut self, days: i64) -> Self {
+        self.date_offset = days;
+        self
+    }
+
+    /// Declare a private attribute as safe,
+    /// by its group, private creator,
+    /// and element number within the private block
+    /// (the lower byte of the element).
+    ///
+    /// Safe private attributes are retained under the option
+    /// [`RetainSafePrivate`](ProfileOption::RetainSafePrivate).
+    /// All other private attributes are removed.
+    pub fn safe_private(mut self, group: u16, creator: impl Into<String>, element: u8) -> Self {
+        self.safe_private.push((group, creator.into(), element));
+        self
+    }
+
+    /// Define the function cleaning textual values
+    /// under the options [`CleanDescriptors`](ProfileOption::CleanDescriptors)
+    /// and [`CleanStructuredContent`](ProfileOption::CleanStructuredContent).
+    ///
+    /// Without a cleaning function,
+    /// these attributes are handled as in the basic profile.
+    pub fn cleaner(
+        mut self,
+        cleaner: impl Fn(Tag, &str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.cleaner = Some(Box::new(cleaner));
+        self
+    }
+
+    /// Start from an existing mapping of original UIDs to replacement UIDs,
+    /// such as one saved from an earlier batch.
+    pub fn uid_map(mut self, uid_map: HashMap<String, String>) -> Self {
+        self.uid_map = uid_map;
+        self
+    }
+
+    /// The mapping of original UIDs to replacement UIDs
+    /// used so far.
+    pub fn uids(&self) -> &HashMap<String, String> {
+        &self.uid_map
+    }
+
+    /// Whether the given option is enabled.
+    pub fn has_option(&self, option: ProfileOption) -> bool {
+        self.options.contains(&option)
+    }
+
+    /// De-identify a DICOM file,
+    /// updating its media storage SOP instance UID accordingly.
+    pub fn deidentify_file(&mut self, obj: &mut FileDicomObject<InMemDicomObject>) -> Result<()> {
+        self.deidentify(obj)?;
+        if let Some(uid) = obj
+            .get(tags::SOP_INSTANCE_UID)
+            .and_then(|e| e.to_str().ok())
+        {
+            let uid = uid.trim_end_matches('\0').to_string();
+            apply(
+                obj,
+                tags::MEDIA_STORAGE_SOP_INSTANCE_UID,
+                AttributeAction::SetStr(uid.into()),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// De-identify a DICOM data set,
+    /// including its nested data sets,
+    /// and record the method of de-identification.
+    ///
+    /// Fails without touching the data set
+    /// if dates are to be modified with a zero offset.
+    pub fn deidentify(&mut self, obj: &mut InMemDicomObject) -> Result<()> {
+        ensure!(
+            self.date_offset != 0
+                || !self.has_option(RetainLongitudinalModifiedDates)
+                || self.has_option(RetainLongitudinalFullDates),
+            MissingDateOffsetSnafu
+        );
+        self.deidentify_data_set(obj)?;
+
+        apply(
+            obj,
+            tags::PATIENT_IDENTITY_REMOVED,
+            AttributeAction::SetStr("YES".into()),
+        )?;
+        apply(
+            obj,
+            tags::DEIDENTIFICATION_METHOD,
+            AttributeAction::SetStr(METHOD_DESCRIPTION.into()),
+        )?;
+        let codes = std::iter::once(("113100", "Basic Application Confidentiality Profile"))
+            .chain(self.options.iter().map(|option| option.code()))
+            .map(|(value, meaning)| {
+                InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::CODE_VALUE, VR::SH, value),
+                    DataElement::new(tags::CODING_SCHEME_DESIGNATOR, VR::SH, "DCM"),
+                    DataElement::new(tags::CODE_MEANING, VR::LO, meaning),
+                ])
+            })
+            .collect::<Vec<_>>();
+        obj.put(DataElement::new(
+            tags::DEIDENTIFICATION_METHOD_CODE_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(codes),
+        ));
+        let temporal = if self.has_option(RetainLongitudinalFullDates) {
+            "UNMODIFIED"
+        } else if self.has_option(RetainLongitudinalModifiedDates) {
+            "MODIFIED"
+        } else {
+            "REMOVED"
+        };
+        apply(
+            obj,
+            tags::LONGITUDINAL_TEMPORAL_INFORMATION_MODIFIED,
+            AttributeAction::SetStr(temporal.into()),
+        )
+    }
+
+    fn deidentify_data_set(&mut self, obj: &mut InMemDicomObject) -> Result<()> {
+        let tags: Vec<Tag> = obj.tags().collect();
+        for &tag in tags.iter().filter(|tag| tag.group() % 2 == 0) {
+            match rule_for(tag) {
+                Some((basic, options)) => {
+                    let action = options
+                        .iter()
+                        .find(|(option, _)| self.has_option(*option))
+                        .map(|(_, action)| *action)
+                        .unwrap_or(basic);
+                    self.apply_action(obj, tag, action, basic)?;
+                }
+                None => self.deidentify_items(obj, tag)?,
+            }
+        }
+        self.deidentify_private(obj, &tags)
+    }
+
+    /// Remove the private attributes which are not safe,
+    /// and the private creators no longer in use.
+    fn deidentify_private(&mut self, obj: &mut InMemDicomObject, tags: &[Tag]) -> Result<()> {
+        let private = tags.iter().filter(|tag| tag.group() % 2 == 1);
+        for &tag in private.clone().filter(|tag| tag.element() >= 0x1000) {
+            let creator = obj
+                .get(Tag(tag.group(), tag.element() >> 8))
+                .and_then(|e| e.to_str().ok())
+                .map(|creator| creator.trim_end_matches(['\0', ' ']).to_string());
+            let safe = self.has_option(RetainSafePrivate)
+                && creator.is_some_and(|creator| {
+                    self.safe_private.iter().any(|(group, c, element)| {
+                        *group == tag.group()
+                            && *c == creator
+                            && *element as u16 == tag.element() & 0xFF
+                    })
+                });
+            if safe {
+                self.deidentify_items(obj, tag)?;
+            } else {
+                apply(obj, tag, AttributeAction::Remove)?;
+            }
+        }
+        for &tag in private.filter(|tag| tag.element() < 0x1000) {
+            let in_use = (0x0010..=0x00FF).contains(&tag.element())
+                && obj
+                    .tags()
+                    .any(|t| t.group() == tag.group() && t.element() >> 8 == tag.element());
+            if !in_use {
+                apply(obj, tag, AttributeAction::Remove)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_action(
+        &mut self,
+        obj: &mut InMemDicomObject,
+        tag: Tag,
+        action: Action,
+        basic: Action,
+    ) -> Result<()> {
+        let vr = match obj.get(tag) {
+            Some(e) => e.vr(),
+            None => return Ok(()),
+        };
+        match (action, vr) {
+            (Remove, _) => apply(obj, tag, AttributeAction::Remove),
+            (Empty, _) => apply(obj, tag, AttributeAction::Empty),
+            (Keep, _) | (Dummy, VR::SQ) | (Clean, VR::SQ) => self.deidentify_items(obj, tag),
+            (Uid, _) | (Dummy, VR::UI) => self.replace_uids(obj, tag),
+            (Dummy, vr) => match dummy_value(vr) {
+                Some(value) => apply(obj, tag, AttributeAction::Replace(value)),
+                None => apply(obj, tag, AttributeAction::Empty),
+            },
+            (Clean, VR::DA) | (Clean, VR::DT) => self.shift_dates(obj, tag, vr),
+            (Clean, VR::TM) => Ok(()),
+            (Clean, VR::AE)
+            | (Clean, VR::CS)
+            | (Clean, VR::LO)
+            | (Clean, VR::LT)
+            | (Clean, VR::PN)
+            | (Clean, VR::SH)
+            | (Clean, VR::ST)
+            | (Clean, VR::UC)
+            | (Clean, VR::UT) => match &self.cleaner {
+                Some(cleaner) => {
+                    let values = obj
+                        .get(tag)
+                        .and_then(|e| e.to_multi_str().ok())
+                        .map(|values| values.iter().map(|v| cleaner(tag, v)).collect())
+                        .unwrap_or_default();
+                    apply(
+                        obj,
+                        tag,
+                        AttributeAction::Replace(PrimitiveValue::Strs(values)),
+                    )
+                }
+                None => self.apply_action(obj, tag, basic, basic),
+            },
+            // any other value is assumed to be clean
+            (Clean, _) => Ok(()),
+        }
+    }
+
+    /// De-identify the data sets nested in the given attribute,
+    /// if it is a sequence.
+    fn deidentify_items(&mut self, obj: &mut InMemDicomObject, tag: Tag) -> Result<()> {
+        if obj.get(tag).map(|e| e.vr()) != Some(VR::SQ) {
+            return Ok(());
+        }
+        let mut result = Ok(());
+        obj.update_value(tag, |value| {
+            if let Some(items) = value.items_mut() {
+                for item in items.iter_mut() {
+                    if result.is_ok() {
+                        result = self.deidentify_data_set(item);
+                    }
+                }
+            }
+        });
+        result
+    }
+
+    /// Replace each UID in the attribute
+    /// with the UID it is mapped to,
+    /// creating a new UID for UIDs not seen before.
+    fn replace_uids(&mut self, obj: &mut InMemDicomObject, tag: Tag) -> Result<()> {
+        let uids = match obj.get(tag).and_then(|e| e.to_multi_str().ok()) {
+            Some(uids) => uids.into_owned(),
+            None => return Ok(()),
+        };
+        let uids = uids
+            .iter()
+            .map(|uid| {
+                let uid = uid.trim_end_matches(['\0', ' ']);
+                self.uid_map
+                    .entry(uid.to_string())
+                    .or_insert_with(crate::generate_uid)
+                    .clone()
+            })
+            .collect();
+        apply(
+            obj,
+            tag,
+            AttributeAction::Replace(PrimitiveValue::Strs(uids)),
+        )
+    }
+
+    /// Shift the dates in the attribute by the date offset,
+    /// keeping the time part of date-times.
+    /// Values which are not complete dates are emptied.
+    fn shift_dates(&mut self, obj: &mut InMemDicomObject, tag: Tag, vr: VR) -> Result<()> {
+        let offset = Duration::days(self.date_offset);
+        let values = match obj.get(tag).and_then(|e| e.to_multi_str().ok()) {
+            Some(values) => values.into_owned(),
+            None => return Ok(()),
+        };
+        let values = values
+            .iter()
+            .map(|value| {
+                let value = value.trim_end_matches(['\0', ' ']);
+                let (date, rest) = if value.len() >= 8 && value.is_char_boundary(8) {
+                    value.split_at(8)
+                } else {
+                    return String::new();
+                };
+                if vr == VR::DA && !rest.is_empty() {
+                    return String::new();
+                }
+                NaiveDate::parse_from_str(date, "%Y%m%d")
+                    .ok()
+                    .and_then(|date| date.checked_add_signed(offset))
+                    .map(|date| format!("{}{}", date.format("%Y%m%d"), rest))
+                    .unwrap_or_default()
+            })
+            .collect();
+        apply(
+            obj,
+            tag,
+            AttributeAction::Replace(PrimitiveValue::Strs(values)),
+        )
+    }
+}
+
+/// A dummy value for the given value representation,
+/// if one can be made.
+fn dummy_value(vr: VR) -> Option<PrimitiveValue> {
+    let value = match vr {
+        VR::AE | VR::CS | VR::LO | VR::LT | VR::PN | VR::SH | VR::ST | VR::UC | VR::UT => {
+            PrimitiveValue::from(DUMMY_TEXT)
+        }
+        VR::AS => PrimitiveValue::from("000D"),
+        VR::DA => PrimitiveValue::from("19000101"),
+        VR::DT => PrimitiveValue::from("19000101000000"),
+        VR::TM => PrimitiveValue::from("000000"),
+        VR::DS | VR::IS => PrimitiveValue::from("0"),
+        VR::US => PrimitiveValue::from(0_u16),
+        VR::SS => PrimitiveValue::from(0_i16),
+        VR::UL => PrimitiveValue::from(0_u32),
+        VR::SL => PrimitiveValue::from(0_i32),
+        VR::UV => PrimitiveValue::from(0_u64),
+        VR::SV => PrimitiveValue::from(0_i64),
+        VR::FL => PrimitiveValue::from(0_f32),
+        VR::FD => PrimitiveValue::from(0_f64),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn apply<T>(obj: &mut T, tag: Tag, action: AttributeAction) -> Result<()>
+where
+    T: ApplyOp<Err = ApplyError>,
+{
+    obj.apply(AttributeOp::new(tag, action))
+        .context(ApplyActionSnafu { tag })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileMetaTableBuilder;
+    use dicom_core::value::DataSetSequence;
+    use dicom_dictionary_std::uids;
+
+    fn image(sop_instance_uid: &str) -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, uids::CT_IMAGE_STORAGE),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, sop_instance_uid),
+            DataElement::new(tags::STUDY_DATE, VR::DA, "20240131"),
+            DataElement::new(tags::CONTENT_DATE, VR::DA, "20240131"),
+            DataElement::new(tags::ACQUISITION_DATE_TIME, VR::DT, "20240131120000"),
+            DataElement::new(tags::MODALITY, VR::CS, "CT"),
+            DataElement::new(tags::INSTITUTION_NAME, VR::LO, "General Hospital"),
+            DataElement::new(tags::STATION_NAME, VR::SH, "CT01"),
+            DataElement::new(tags::STUDY_DESCRIPTION, VR::LO, "Head for Doe"),
+            DataElement::new(
+                tags::REFERENCED_IMAGE_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(
+                        tags::REFERENCED_SOP_CLASS_UID,
+                        VR::UI,
+                        uids::CT_IMAGE_STORAGE,
+                    ),
+                    DataElement::new(tags::REFERENCED_SOP_INSTANCE_UID, VR::UI, "1.2.3.4.1"),
+                    DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+                ])]),
+            ),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::PATIENT_ID, VR::LO, "123456"),
+            DataElement::new(tags::PATIENT_BIRTH_DATE, VR::DA, "19700101"),
+            DataElement::new(tags::PATIENT_SEX, VR::CS, "M"),
+            DataElement::new(tags::PATIENT_AGE, VR::AS, "054Y"),
+            DataElement::new(tags::OTHER_PATIENT_NAMES, VR::PN, "Doe^Johnny"),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "1.2.3"),
+            DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, "1.2.3.4"),
+            DataElement::new(Tag(0x0009, 0x0010), VR::LO, "ACME 1.1"),
+            DataElement::new(Tag(0x0009, 0x1001), VR::LO, "Doe^John"),
+            DataElement::new(Tag(0x0009, 0x1002), VR::DS, "2.5"),
+            DataElement::new(Tag(0x6000, 0x4000), VR::LT, "John's overlay"),
+        ])
+    }
+
+    fn str_value(obj: &InMemDicomObject, tag: Tag) -> Option<String> {
+        obj.get(tag).map(|e| {
+            e.to_str()
+                .unwrap()
+                .trim_end_matches(['\0', ' '])
+                .to_string()
+        })
+    }
+
+    #[test]
+    fn basic_profile() {
+        let mut deidentifier = Deidentifier::new();
+        let mut obj = image("1.2.3.4.1");
+        deidentifier.deidentify(&mut obj).unwrap();
+
+        // Z
+        assert_eq!(str_value(&obj, tags::PATIENT_NAME).as_deref(), Some(""));
+        assert_eq!(str_value(&obj, tags::PATIENT_ID).as_deref(), Some(""));
+        assert_eq!(str_value(&obj, tags::STUDY_DATE).as_deref(), Some(""));
+        assert_eq!(str_value(&obj, tags::INSTITUTION_NAME).as_deref(), Some(""));
+        // X
+        for tag in [
+            tags::OTHER_PATIENT_NAMES,
+            tags::PATIENT_AGE,
+            tags::STUDY_DESCRIPTION,
+            Tag(0x6000, 0x4000),
+        ] {
+            assert!(obj.get(tag).is_none(), "{} should be removed", tag);
+        }
+        // D
+        assert_eq!(
+            str_value(&obj, tags::CONTENT_DATE).as_deref(),
+            Some("19000101")
+        );
+        // K (not in the profile)
+        assert_eq!(str_value(&obj, tags::MODALITY).as_deref(), Some("CT"));
+
+        // U
+        let sop_instance_uid = str_value(&obj, tags::SOP_INSTANCE_UID).unwrap();
+        assert_ne!(sop_instance_uid, "1.2.3.4.1");
+        assert!(sop_instance_uid.starts_with("2.25."));
+        assert_eq!(
+            deidentifier.uids().get("1.2.3.4.1"),
+            Some(&sop_instance_uid)
+        );
+
+        // nested data sets are de-identified as well
+        let item = &obj
+            .get(tags::REFERENCED_IMAGE_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap()[0];
+        assert_eq!(
+            str_value(item, tags::REFERENCED_SOP_INSTANCE_UID),
+            Some(sop_instance_uid)
+        );
+        assert_eq!(
+            str_value(item, tags::REFERENCED_SOP_CLASS_UID).as_deref(),
+            Some(uids::CT_IMAGE_STORAGE)
+        );
+        assert_eq!(str_value(item, tags::PATIENT_NAME).as_deref(), Some(""));
+
+        // private attributes are removed
+        assert!(obj.tags().all(|tag| tag.group() % 2 == 0));
+
+        // the method is recorded
+        assert_eq!(
+            str_value(&obj, tags::PATIENT_IDENTITY_REMOVED).as_deref(),
+            Some("YES")
+        );
+        let codes = obj
+            .get(tags::DEIDENTIFICATION_METHOD_CODE_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(codes.len(), 1);
+        assert_eq!(
+            str_value(&codes[0], tags::CODE_VALUE).as_deref(),
+            Some("113100")
+        );
+        assert_eq!(
+            str_value(&obj, tags::LONGITUDINAL_TEMPORAL_INFORMATION_MODIFIED).as_deref(),
+            Some("REMOVED")
+        );
+    }
+
+    #[test]
+    fn consistent_uids_across_objects() {
+        let mut deidentifier = Deidentifier::new();
+        let mut first = image("1.2.3.4.1");
+        let mut second = image("1.2.3.4.2");
+        deidentifier.deidentify(&mut first).unwrap();
+        deidentifier.deidentify(&mut second).unwrap();
+
+        assert_eq!(
+            str_value(&first, tags::STUDY_INSTANCE_UID),
+            str_value(&second, tags::STUDY_INSTANCE_UID)
+        );
+        assert_ne!(
+            str_value(&first, tags::SOP_INSTANCE_UID),
+            str_value(&second, tags::SOP_INSTANCE_UID)
+        );
+        // the reference in the second object points to the first object
+        let item = &second
+            .get(tags::REFERENCED_IMAGE_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap()[0];
+        assert_eq!(
+            str_value(item, tags::REFERENCED_SOP_INSTANCE_UID),
+            str_value(&first, tags::SOP_INSTANCE_UID)
+        );
+    }
+
+    #[test]
+    fn profile_options() {
+        let mut deidentifier = Deidentifier::new()
+            .option(RetainUids)
+            .option(RetainPatientCharacteristics)
+            .option(RetainDeviceIdentity)
+            .option(RetainLongitudinalModifiedDates)
+            .date_offset(-31)
+            .option(CleanDescriptors)
+            .cleaner(|_, value| value.replace("Doe", "XXX"));
+        let mut obj = image("1.2.3.4.1");
+        deidentifier.deidentify(&mut obj).unwrap();
+
+        assert_eq!(
+            str_value(&obj, tags::SOP_INSTANCE_UID).as_deref(),
+            Some("1.2.3.4.1")
+        );
+        assert_eq!(str_value(&obj, tags::PATIENT_AGE).as_deref(), Some("054Y"));
+        assert_eq!(str_value(&obj, tags::PATIENT_SEX).as_deref(), Some("M"));
+        assert_eq!(str_value(&obj, tags::STATION_NAME).as_deref(), Some("CT01"));
+        assert_eq!(str_value(&obj, tags::INSTITUTION_NAME).as_deref(), Some(""));
+        assert_eq!(
+            str_value(&obj, tags::STUDY_DATE).as_deref(),
+            Some("20231231")
+        );
+        assert_eq!(
+            str_value(&obj, tags::ACQUISITION_DATE_TIME).as_deref(),
+            Some("20231231120000")
+        );
+        assert_eq!(
+            str_value(&obj, tags::STUDY_DESCRIPTION).as_deref(),
+            Some("Head for XXX")
+        );
+        // patient birth date is not affected by the options
+        assert_eq!(
+            str_value(&obj, tags::PATIENT_BIRTH_DATE).as_deref(),
+            Some("")
+        );
+        assert_eq!(
+            str_value(&obj, tags::LONGITUDINAL_TEMPORAL_INFORMATION_MODIFIED).as_deref(),
+            Some("MODIFIED")
+        );
+        assert_eq!(
+            obj.get(tags::DEIDENTIFICATION_METHOD_CODE_SEQUENCE)
+                .unwrap()
+                .items()
+                .unwrap()
+                .len(),
+            6
+        );
+    }
+
+    #[test]
+    fn modified_dates_require_offset() {
+        let mut deidentifier = Deidentifier::new().option(RetainLongitudinalModifiedDates);
+        let mut obj = image("1.2.3.4.1");
+        assert!(matches!(
+            deidentifier.deidentify(&mut obj),
+            Err(Error::MissingDateOffset { .. })
+        ));
+        // nothing was changed
+        assert_eq!(
+            str_value(&obj, tags::PATIENT_NAME).as_deref(),
+            Some("Doe^John")
+        );
+        assert!(obj.get(tags::PATIENT_IDENTITY_REMOVED).is_none());
+
+        // full dates take precedence, so no offset is needed
+        let mut deidentifier = deidentifier.option(RetainLongitudinalFullDates);
+        deidentifier.deidentify(&mut obj).unwrap();
+        assert_eq!(
+            str_value(&obj, tags::STUDY_DATE).as_deref(),
+            Some("20240131")
+        );
+    }
+
+    #[test]
+    fn retain_safe_private_attributes() {
+        let mut deidentifier = Deidentifier::new()
+            .option(RetainSafePrivate)
+            .safe_private(0x0009, "ACME 1.1", 0x02);
+        let mut obj = image("1.2.3.4.1");
+        deidentifier.deidentify(&mut obj).unwrap();
+
+        assert_eq!(
+            str_value(&obj, Tag(0x0009, 0x0010)).as_deref(),
+            Some("ACME 1.1")
+        );
+        assert!(obj.get(Tag(0x0009, 0x1001)).is_none());
+        assert_eq!(str_value(&obj, Tag(0x0009, 0x1002)).as_deref(), Some("2.5"));
+
+        // not retained without the option
+        let mut deidentifier = Deidentifier::new().safe_private(0x0009, "ACME 1.1", 0x02);
+        let mut obj = image("1.2.3.4.1");
+        deidentifier.deidentify(&mut obj).unwrap();
+        assert!(obj.tags().all(|tag| tag.group() != 0x0009));
+    }
+
+    #[test]
+    fn profile_has_one_rule_per_attribute() {
+        let mut seen = BTreeSet::new();
+        for (tag, _, _) in PROFILE {
+            assert!(seen.insert(*tag), "{} listed twice", tag);
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn identifying_attributes_are_removed() {
+        let identifying = [
+            (tags::CONSULTING_PHYSICIAN_IDENTIFICATION_SEQUENCE, VR::SQ),
+            (tags::REQUESTING_PHYSICIAN_IDENTIFICATION_SEQUENCE, VR::SQ),
+            (
+                tags::SCHEDULED_PERFORMING_PHYSICIAN_IDENTIFICATION_SEQUENCE,
+                VR::SQ,
+            ),
+            (tags::CONTENT_CREATOR_IDENTIFICATION_CODE_SEQUENCE, VR::SQ),
+            (tags::PATIENT_TELECOM_INFORMATION, VR::LT),
+            (tags::PATIENT_BIRTH_DATE_IN_ALTERNATIVE_CALENDAR, VR::LO),
+            (tags::STATION_AE_TITLE, VR::AE),
+            (tags::CURRENT_OBSERVER_TRIAL, VR::PN),
+            (tags::DEVICE_DESCRIPTION, VR::LO),
+        ];
+
+        let mut obj = image("1.2.3.4.1");
+        for (tag, vr) in identifying {
+            assert!(rule_for(tag).is_some(), "{} not in the profile", tag);
+            if vr == VR::SQ {
+                let item = InMemDicomObject::from_element_iter([DataElement::new(
+                    tags::PERSON_NAME,
+                    VR::PN,
+                    "Doe^John",
+                )]);
+                obj.put(DataElement::new(tag, vr, DataSetSequence::from(vec![item])));
+            } else {
+                obj.put(DataElement::new(tag, vr, "Doe"));
+            }
+        }
+        Deidentifier::new().deidentify(&mut obj).unwrap();
+
+        for (tag, _) in identifying {
+            assert!(obj.get(tag).is_none(), "{} was retained", tag);
+        }
+    }
+
+    #[test]
+    fn deidentify_file_updates_meta() {
+        let mut obj = image("1.2.3.4.1")
+            .with_meta(FileMetaTableBuilder::new().transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN))
+            .unwrap();
+        Deidentifier::new().deidentify_file(&mut obj).unwrap();
+        assert_eq!(
+            Some(obj.meta().media_storage_sop_instance_uid()),
+            str_value(&obj, tags::SOP_INSTANCE_UID).as_deref()
+        );
+        assert_ne!(obj.meta().media_storage_sop_instance_uid(), "1.2.3.4.1");
+    }
+}
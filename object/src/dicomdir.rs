@@ -0,0 +1,1188 @@
+//! Reading and writing of File-set directories (DICOMDIR).
+//!
+//! A File-set is a collection of DICOM files sharing a common name space,
+//! such as the files on removable media.
+//! It is described by a DICOMDIR file at its root
+//! (see PS3.10, section 8, and PS3.3, annex F),
+//! which lists the instances of the File-set
+//! as a hierarchy of directory records,
+//! usually from patients to studies, series, and images.
+//! The records are linked to one another
+//! by byte offsets into the DICOMDIR file.
+//!
+//! [`DicomDir::open`] reads a DICOMDIR into a tree of [`DirectoryRecord`]s,
+//! resolving these offsets.
+//! A directory can also be built from scratch or updated
+//! by adding the files of the File-set with [`DicomDir::add_file`],
+//! and saved with [`DicomDir::write_to_file`],
+//! which lays out the records and computes their offsets anew.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_object::dicomdir::{DicomDir, RecordType};
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! // list the images on some media
+//! let dicomdir = DicomDir::open("/media/usb/DICOMDIR")?;
+//! for record in dicomdir.iter() {
+//!     if record.record_type() == &RecordType::Image {
+//!         println!("{:?}", record.referenced_file("/media/usb"));
+//!     }
+//! }
+//!
+//! // describe a new File-set
+//! let mut dicomdir = DicomDir::new("STUDIES");
+//! dicomdir.add_file("/media/usb", "/media/usb/DICOM/IM0001")?;
+//! dicomdir.write_to_file("/media/usb/DICOMDIR")?;
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use dicom_core::{header::Header, DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
+use dicom_parser::dataset::{read::DataSetReader, DataToken};
+use dicom_transfer_syntax_registry::{entries::EXPLICIT_VR_LITTLE_ENDIAN, TransferSyntaxRegistry};
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    FileDicomObject, FileMetaTable, FileMetaTableBuilder, InMemDicomObject, OpenFileOptions,
+    ReadError, WriteError,
+};
+
+/// An error which may occur when reading or writing a File-set directory.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// Could not open the DICOMDIR file
+    OpenDicomDir {
+        #[snafu(backtrace, source(from(ReadError, Box::from)))]
+        source: Box<ReadError>,
+    },
+    /// Could not read the DICOMDIR file
+    ReadDicomDir {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+    /// Unsupported transfer syntax `{uid}`
+    UnsupportedTransferSyntax { uid: String, backtrace: Backtrace },
+    /// Could not parse the directory data set
+    ParseDirectory {
+        #[snafu(backtrace, source(from(ReadError, Box::from)))]
+        source: Box<ReadError>,
+    },
+    /// Could not read the directory records
+    ReadRecords {
+        #[snafu(backtrace, source(from(dicom_parser::dataset::read::Error, Box::from)))]
+        source: Box<dicom_parser::dataset::read::Error>,
+    },
+    /// Missing directory record sequence
+    MissingDirectoryRecordSequence { backtrace: Backtrace },
+    /// No directory record at offset {offset}
+    InvalidOffset { offset: u32, backtrace: Backtrace },
+    /// Directory record at offset {offset} is referenced more than once
+    RepeatedRecord { offset: u32, backtrace: Backtrace },
+    /// Directory record at offset {offset} has no record type
+    MissingRecordType { offset: u32, backtrace: Backtrace },
+    #[snafu(display("Could not open file '{}'", path.display()))]
+    OpenFile {
+        path: PathBuf,
+        #[snafu(backtrace, source(from(ReadError, Box::from)))]
+        source: Box<ReadError>,
+    },
+    #[snafu(display("File '{}' is not in the File-set at '{}'", path.display(), root.display()))]
+    NotInFileSet {
+        path: PathBuf,
+        root: PathBuf,
+        backtrace: Backtrace,
+    },
+    /// Invalid file ID component `{component}`
+    InvalidFileId {
+        component: String,
+        backtrace: Backtrace,
+    },
+    /// Missing attribute {tag} in the instance
+    MissingAttribute { tag: Tag, backtrace: Backtrace },
+    /// Could not build the file meta group
+    BuildMeta {
+        #[snafu(backtrace, source(from(crate::meta::Error, Box::from)))]
+        source: Box<crate::meta::Error>,
+    },
+    /// Could not encode the directory
+    EncodeDirectory {
+        #[snafu(backtrace, source(from(WriteError, Box::from)))]
+        source: Box<WriteError>,
+    },
+    /// The directory is too large for its record offsets
+    DirectoryTooLarge { backtrace: Backtrace },
+    #[snafu(display("Could not write to file '{}'", filename.display()))]
+    WriteFile {
+        filename: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+    /// Could not write the directory
+    WriteDirectory {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The maximum number of components in a file ID.
+const MAX_FILE_ID_COMPONENTS: usize = 8;
+
+/// The attributes which link directory records to one another,
+/// managed by the directory itself.
+const RECORD_LINKS: [Tag; 4] = [
+    tags::OFFSET_OF_THE_NEXT_DIRECTORY_RECORD,
+    tags::RECORD_IN_USE_FLAG,
+    tags::OFFSET_OF_REFERENCED_LOWER_LEVEL_DIRECTORY_ENTITY,
+    tags::DIRECTORY_RECORD_TYPE,
+];
+
+/// The root attributes of the directory managed by the directory itself.
+const DIRECTORY_ATTRIBUTES: [Tag; 5] = [
+    tags::FILE_SET_ID,
+    tags::OFFSET_OF_THE_FIRST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+    tags::OFFSET_OF_THE_LAST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+    tags::FILE_SET_CONSISTENCY_FLAG,
+    tags::DIRECTORY_RECORD_SEQUENCE,
+];
+
+/// The keys of patient records, and whether they are required.
+const PATIENT_KEYS: [(Tag, VR, bool); 3] = [
+    (tags::SPECIFIC_CHARACTER_SET, VR::CS, false),
+    (tags::PATIENT_NAME, VR::PN, true),
+    (tags::PATIENT_ID, VR::LO, true),
+];
+
+/// The keys of study records, and whether they are required.
+const STUDY_KEYS: [(Tag, VR, bool); 7] = [
+    (tags::SPECIFIC_CHARACTER_SET, VR::CS, false),
+    (tags::STUDY_DATE, VR::DA, true),
+    (tags::STUDY_TIME, VR::TM, true),
+    (tags::ACCESSION_NUMBER, VR::SH, true),
+    (tags::STUDY_DESCRIPTION, VR::LO, true),
+    (tags::STUDY_INSTANCE_UID, VR::UI, true),
+    (tags::STUDY_ID, VR::SH, true),
+];
+
+/// The keys of series records, and whether they are required.
+const SERIES_KEYS: [(Tag, VR, bool); 4] = [
+    (tags::SPECIFIC_CHARACTER_SET, VR::CS, false),
+    (tags::MODALITY, VR::CS, true),
+    (tags::SERIES_INSTANCE_UID, VR::UI, true),
+    (tags::SERIES_NUMBER, VR::IS, true),
+];
+
+/// The keys of the records referencing an instance,
+/// and whether they are required.
+const INSTANCE_KEYS: [(Tag, VR, bool); 4] = [
+    (tags::SPECIFIC_CHARACTER_SET, VR::CS, false),
+    (tags::CONTENT_DATE, VR::DA, false),
+    (tags::CONTENT_TIME, VR::TM, false),
+    (tags::INSTANCE_NUMBER, VR::IS, true),
+];
+
+/// The type of a directory record.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RecordType {
+    Patient,
+    Study,
+    Series,
+    Image,
+    /// Any other record type, by its defined term
+    /// (such as `SR DOCUMENT` or `PRESENTATION`)
+    Other(String),
+}
+
+impl RecordType {
+    /// The defined term of the record type,
+    /// as in _Directory Record Type_.
+    pub fn as_str(&self) -> &str {
+        match self {
+            RecordType::Patient => "PATIENT",
+            RecordType::Study => "STUDY",
+            RecordType::Series => "SERIES",
+            RecordType::Image => "IMAGE",
+            RecordType::Other(term) => term,
+        }
+    }
+
+    /// The type of the records referencing instances
+    /// of the given storage SOP class.
+    pub fn for_sop_class(sop_class_uid: &str) -> RecordType {
+        let other = |term: &str| RecordType::Other(term.to_string());
+        match sop_class_uid {
+            uids::KEY_OBJECT_SELECTION_DOCUMENT_STORAGE => other("KEY OBJECT DOC"),
+            uids::RT_DOSE_STORAGE => other("RT DOSE"),
+            uids::RT_STRUCTURE_SET_STORAGE => other("RT STRUCTURE SET"),
+            uids::RT_PLAN_STORAGE | uids::RT_ION_PLAN_STORAGE => other("RT PLAN"),
+            uids::RT_BEAMS_TREATMENT_RECORD_STORAGE
+            | uids::RT_BRACHY_TREATMENT_RECORD_STORAGE
+            | uids::RT_TREATMENT_SUMMARY_RECORD_STORAGE
+            | uids::RT_ION_BEAMS_TREATMENT_RECORD_STORAGE => other("RT TREAT RECORD"),
+            uids::MR_SPECTROSCOPY_STORAGE => other("SPECTROSCOPY"),
+            uids::RAW_DATA_STORAGE => other("RAW DATA"),
+            uids::SPATIAL_REGISTRATION_STORAGE | uids::DEFORMABLE_SPATIAL_REGISTRATION_STORAGE => {
+                other("REGISTRATION")
+            }
+            uids::SPATIAL_FIDUCIALS_STORAGE => other("FIDUCIAL"),
+            uid if uid.starts_with("1.2.840.10008.5.1.4.1.1.11.") => other("PRESENTATION"),
+            uid if uid.starts_with("1.2.840.10008.5.1.4.1.1.88.") => other("SR DOCUMENT"),
+            uid if uid.starts_with("1.2.840.10008.5.1.4.1.1.9.") => other("WAVEFORM"),
+            uid if uid.starts_with("1.2.840.10008.5.1.4.1.1.104.") => other("ENCAP DOC"),
+            _ => RecordType::Image,
+        }
+    }
+}
+
+impl From<&str> for RecordType {
+    fn from(term: &str) -> Self {
+        match term.trim_end() {
+            "PATIENT" => RecordType::Patient,
+            "STUDY" => RecordType::Study,
+            "SERIES" => RecordType::Series,
+            "IMAGE" => RecordType::Image,
+            term => RecordType::Other(term.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for RecordType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A directory record,
+/// alongside the records of its lower-level directory entity.
+#[derive(Debug, Clone)]
+pub struct DirectoryRecord {
+    record_type: RecordType,
+    attributes: InMemDicomObject,
+    children: Vec<DirectoryRecord>,
+}
+
+impl DirectoryRecord {
+    /// Create a directory record of the given type
+    /// with the given keys,
+    /// without lower-level records.
+    pub fn new(record_type: RecordType, attributes: InMemDicomObject) -> Self {
+        DirectoryRecord {
+            record_type,
+            attributes,
+            children: Vec::new(),
+        }
+    }
+
+    /// The type of the record.
+    pub fn record_type(&self) -> &RecordType {
+        &self.record_type
+    }
+
+    /// The attributes of the record,
+    /// other than those linking it to other records.
+    pub fn attributes(&self) -> &InMemDicomObject {
+        &self.attributes
+    }
+
+    /// Mutable access to the attributes of the record.
+    pub fn attributes_mut(&mut self) -> &mut InMemDicomObject {
+        &mut self.attributes
+    }
+
+    /// The records of the lower-level directory entity.
+    pub fn children(&self) -> &[DirectoryRecord] {
+        &self.children
+    }
+
+    /// Mutable access to the records of the lower-level directory entity.
+    pub fn children_mut(&mut self) -> &mut Vec<DirectoryRecord> {
+        &mut self.children
+    }
+
+    /// The components of the _Referenced File ID_ of the record,
+    /// if it references a file.
+    pub fn referenced_file_id(&self) -> Option<Vec<String>> {
+        let file_id = self.attributes.get(tags::REFERENCED_FILE_ID)?;
+        let components = file_id.to_multi_str().ok()?;
+        Some(
+            components
+                .iter()
+                .map(|c| c.trim_end().to_string())
+                .collect(),
+        )
+    }
+
+    /// The path to the file referenced by the record,
+    /// if any,
+    /// given the root directory of the File-set.
+    pub fn referenced_file(&self, file_set_root: impl AsRef<Path>) -> Option<PathBuf> {
+        let file_id = self.referenced_file_id()?;
+        let mut path = file_set_root.as_ref().to_path_buf();
+        path.extend(file_id);
+        Some(path)
+    }
+
+    /// The _Referenced SOP Instance UID in File_ of the record,
+    /// if it references a file.
+    pub fn referenced_sop_instance_uid(&self) -> Option<String> {
+        str_attribute(&self.attributes, tags::REFERENCED_SOP_INSTANCE_UID_IN_FILE)
+    }
+}
+
+/// A File-set directory,
+/// as described by a DICOMDIR file.
+///
+/// See the [module-level documentation](self) for more details.
+#[derive(Debug, Clone)]
+pub struct DicomDir {
+    file_set_id: String,
+    sop_instance_uid: String,
+    /// other attributes of the directory,
+    /// such as the File-set descriptor file ID
+    attributes: InMemDicomObject,
+    records: Vec<DirectoryRecord>,
+}
+
+impl DicomDir {
+    /// Create an empty directory for the File-set with the given ID.
+    pub fn new(file_set_id: impl Into<String>) -> Self {
+        DicomDir {
+            file_set_id: file_set_id.into(),
+            sop_instance_uid: crate::generate_uid(),
+            attributes: InMemDicomObject::new_empty(),
+            records: Vec::new(),
+        }
+    }
+
+    /// Read the DICOMDIR file at the given path.
+    ///
+    /// Records marked as inactive are left out.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let (meta, mut reader) = OpenFileOptions::new()
+            .open_file_raw(path)
+            .context(OpenDicomDirSnafu)?;
+        // record offsets are relative to the beginning of the file
+        let start = reader.stream_position().context(ReadDicomDirSnafu)?;
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).context(ReadDicomDirSnafu)?;
+        Self::from_data_set(&meta, start, &data)
+    }
+
+    fn from_data_set(meta: &FileMetaTable, start: u64, data: &[u8]) -> Result<Self> {
+        let ts = TransferSyntaxRegistry
+            .get(meta.transfer_syntax())
+            .with_context(|| UnsupportedTransferSyntaxSnafu {
+                uid: meta.transfer_syntax().to_string(),
+            })?;
+        let mut attributes =
+            InMemDicomObject::read_dataset_with_ts(data, ts).context(ParseDirectorySnafu)?;
+
+        // find where each record starts
+        let mut offsets = Vec::new();
+        let mut reader = DataSetReader::new_with_ts(data, ts).context(ReadRecordsSnafu)?;
+        let mut depth = 0;
+        let mut in_records = false;
+        while let Some(token) = reader.next() {
+            match token.context(ReadRecordsSnafu)? {
+                DataToken::SequenceStart { tag, .. } => {
+                    in_records = depth == 0 && tag == tags::DIRECTORY_RECORD_SEQUENCE;
+                    depth += 1;
+                }
+                DataToken::PixelSequenceStart => depth += 1,
+                DataToken::ItemStart { .. } => {
+                    if in_records && depth == 1 {
+                        // the item header was just read
+                        offsets.push(start + reader.position() - 8);
+                    }
+                    depth += 1;
+                }
+                DataToken::ItemEnd | DataToken::SequenceEnd => depth -= 1,
+                _ => {}
+            }
+        }
+
+        let items = attributes
+            .take(tags::DIRECTORY_RECORD_SEQUENCE)
+            .and_then(|e| e.into_value().into_items())
+            .context(MissingDirectoryRecordSequenceSnafu)?;
+        let mut items: HashMap<u32, InMemDicomObject> = offsets
+            .into_iter()
+            .filter_map(|offset| u32::try_from(offset).ok())
+            .zip(items)
+            .collect();
+
+        let first = uint_attribute(
+            &attributes,
+            tags::OFFSET_OF_THE_FIRST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+        );
+        let records = take_records(&mut items, first, &mut HashSet::new())?;
+
+        let file_set_id = str_attribute(&attributes, tags::FILE_SET_ID).unwrap_or_default();
+        for tag in &DIRECTORY_ATTRIBUTES {
+            attributes.remove_element(*tag);
+        }
+        Ok(DicomDir {
+            file_set_id,
+            sop_instance_uid: meta.media_storage_sop_instance_uid().to_string(),
+            attributes,
+            records,
+        })
+    }
+
+    /// The ID of the File-set.
+    pub fn file_set_id(&self) -> &str {
+        &self.file_set_id
+    }
+
+    /// The SOP Instance UID of the DICOMDIR file.
+    pub fn sop_instance_uid(&self) -> &str {
+        &self.sop_instance_uid
+    }
+
+    /// The records of the root directory entity,
+    /// usually patient records.
+    pub fn records(&self) -> &[DirectoryRecord] {
+        &self.records
+    }
+
+    /// Mutable access to the records of the root directory entity.
+    pub fn records_mut(&mut self) -> &mut Vec<DirectoryRecord> {
+        &mut self.records
+    }
+
+    /// Iterate over all records of the directory, depth first,
+    /// each record being followed by its lower-level records.
+    pub fn iter(&self) -> Records<'_> {
+        Records {
+            stack: vec![self.records.iter()],
+        }
+    }
+
+    /// Add the DICOM file at the given path to the directory,
+    /// given the root directory of the File-set.
+    ///
+    /// See [`add_instance`](Self::add_instance) for more details.
+    pub fn add_file<P, Q>(&mut self, file_set_root: P, path: Q) -> Result<()>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let root = file_set_root.as_ref();
+        let path = path.as_ref();
+        let relative = path
+            .strip_prefix(root)
+            .ok()
+            .context(NotInFileSetSnafu { path, root })?;
+        let file_id: Vec<String> = relative
+            .iter()
+            .map(|component| component.to_string_lossy().into_owned())
+            .collect();
+        let obj = OpenFileOptions::new()
+            .read_until(tags::PIXEL_DATA)
+            .open_file(path)
+            .context(OpenFileSnafu { path })?;
+        self.add_instance(file_id, &obj)
+    }
+
+    /// Add the instance stored in the file with the given file ID,
+    /// as the path components from the root of the File-set.
+    ///
+    /// The patient, study, and series records of the instance
+    /// are created if they are not in the directory yet,
+    /// and the instance is referenced by a record of the type
+    /// corresponding to its SOP class.
+    /// Any record referencing the same SOP instance is replaced.
+    ///
+    /// Fails if the file ID does not consist of
+    /// up to 8 components of up to 8 uppercase letters, digits, or underscores.
+    pub fn add_instance<I, S>(
+        &mut self,
+        file_id: I,
+        obj: &FileDicomObject<InMemDicomObject>,
+    ) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let file_id: Vec<String> = file_id.into_iter().map(Into::into).collect();
+        check_file_id(&file_id)?;
+
+        let meta = obj.meta();
+        let sop_instance_uid = meta.media_storage_sop_instance_uid();
+        let study_instance_uid =
+            str_attribute(obj, tags::STUDY_INSTANCE_UID).context(MissingAttributeSnafu {
+                tag: tags::STUDY_INSTANCE_UID,
+            })?;
+        let series_instance_uid =
+            str_attribute(obj, tags::SERIES_INSTANCE_UID).context(MissingAttributeSnafu {
+                tag: tags::SERIES_INSTANCE_UID,
+            })?;
+        let patient_id = str_attribute(obj, tags::PATIENT_ID).unwrap_or_default();
+
+        self.remove_instance(sop_instance_uid);
+
+        let patient = find_or_insert(
+            &mut self.records,
+            RecordType::Patient,
+            || record_keys(obj, &PATIENT_KEYS),
+            tags::PATIENT_ID,
+            &patient_id,
+        );
+        let study = find_or_insert(
+            &mut patient.children,
+            RecordType::Study,
+            || record_keys(obj, &STUDY_KEYS),
+            tags::STUDY_INSTANCE_UID,
+            &study_instance_uid,
+        );
+        let series = find_or_insert(
+            &mut study.children,
+            RecordType::Series,
+            || record_keys(obj, &SERIES_KEYS),
+            tags::SERIES_INSTANCE_UID,
+            &series_instance_uid,
+        );
+
+        let mut keys = record_keys(obj, &INSTANCE_KEYS);
+        keys.put(DataElement::new(
+            tags::REFERENCED_FILE_ID,
+            VR::CS,
+            PrimitiveValue::Strs(file_id.into_iter().collect()),
+        ));
+        keys.put(DataElement::new(
+            tags::REFERENCED_SOP_CLASS_UID_IN_FILE,
+            VR::UI,
+            PrimitiveValue::from(meta.media_storage_sop_class_uid()),
+        ));
+        keys.put(DataElement::new(
+            tags::REFERENCED_SOP_INSTANCE_UID_IN_FILE,
+            VR::UI,
+            PrimitiveValue::from(sop_instance_uid),
+        ));
+        keys.put(DataElement::new(
+            tags::REFERENCED_TRANSFER_SYNTAX_UID_IN_FILE,
+            VR::UI,
+            PrimitiveValue::from(meta.transfer_syntax()),
+        ));
+        series.children.push(DirectoryRecord::new(
+            RecordType::for_sop_class(meta.media_storage_sop_class_uid()),
+            keys,
+        ));
+        Ok(())
+    }
+
+    /// Remove the records referencing the SOP instance with the given UID,
+    /// along with the records left without lower-level records.
+    ///
+    /// Returns whether any record was removed.
+    pub fn remove_instance(&mut self, sop_instance_uid: &str) -> bool {
+        remove_instance(&mut self.records, sop_instance_uid)
+    }
+
+    /// Write the directory as a DICOMDIR file to the given path.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let file = File::create(path).context(WriteFileSnafu { filename: path })?;
+        let mut to = BufWriter::new(file);
+        self.write_all(&mut to)?;
+        to.flush().context(WriteFileSnafu { filename: path })
+    }
+
+    /// Write the directory as a DICOMDIR file
+    /// into the given writer.
+    ///
+    /// Records are laid out depth first,
+    /// each record being followed by its lower-level records.
+    pub fn write_all<W: Write>(&self, mut to: W) -> Result<()> {
+        let ts = EXPLICIT_VR_LITTLE_ENDIAN.erased();
+        let meta = FileMetaTableBuilder::new()
+            .media_storage_sop_class_uid(uids::MEDIA_STORAGE_DIRECTORY_STORAGE)
+            .media_storage_sop_instance_uid(self.sop_instance_uid.as_str())
+            .transfer_syntax(ts.uid())
+            .build()
+            .context(BuildMetaSnafu)?;
+        let mut meta_bytes = Vec::new();
+        meta.write(&mut meta_bytes).context(BuildMetaSnafu)?;
+
+        let mut records = Vec::new();
+        let (first, last) = flatten(&self.records, &mut records);
+
+        // the lengths of the records do not depend on the offsets,
+        // so they are encoded once to learn where each record starts
+        let header_len = self.encode_header(0, 0)?.len();
+        let mut offset = 128 + 4 + meta_bytes.len() + header_len + 12;
+        let mut offsets = Vec::with_capacity(records.len());
+        for record in &records {
+            offsets.push(u32::try_from(offset).ok().context(DirectoryTooLargeSnafu)?);
+            offset += 8 + encode_record(record.record, 0, 0)?.len();
+        }
+        let offset_of = |i: Option<usize>| i.map(|i| offsets[i]).unwrap_or(0);
+
+        let mut items = Vec::new();
+        for record in &records {
+            let item = encode_record(
+                record.record,
+                offset_of(record.next),
+                offset_of(record.lower),
+            )?;
+            items.extend_from_slice(&[0xFE, 0xFF, 0x00, 0xE0]);
+            items.extend_from_slice(&(item.len() as u32).to_le_bytes());
+            items.extend(item);
+        }
+        let items_len = u32::try_from(items.len())
+            .ok()
+            .context(DirectoryTooLargeSnafu)?;
+
+        to.write_all(&[0; 128]).context(WriteDirectorySnafu)?;
+        to.write_all(b"DICM").context(WriteDirectorySnafu)?;
+        to.write_all(&meta_bytes).context(WriteDirectorySnafu)?;
+        to.write_all(&self.encode_header(offset_of(first), offset_of(last))?)
+            .context(WriteDirectorySnafu)?;
+        // Directory Record Sequence, with an explicit length
+        to.write_all(&[0x04, 0x00, 0x20, 0x12, b'S', b'Q', 0x00, 0x00])
+            .context(WriteDirectorySnafu)?;
+        to.write_all(&items_len.to_le_bytes())
+            .context(WriteDirectorySnafu)?;
+        to.write_all(&items).context(WriteDirectorySnafu)?;
+
+        // any other attributes after the sequence
+        let trailing = InMemDicomObject::from_element_iter(
+            self.attributes
+                .iter()
+                .filter(|e| e.tag() > tags::DIRECTORY_RECORD_SEQUENCE)
+                .cloned(),
+        );
+        trailing
+            .write_dataset_with_ts(&mut to, &ts)
+            .context(EncodeDirectorySnafu)?;
+        Ok(())
+    }
+
+    /// Encode the attributes of the directory
+    /// which come before the directory record sequence.
+    fn encode_header(&self, first: u32, last: u32) -> Result<Vec<u8>> {
+        let mut header = InMemDicomObject::from_element_iter(
+            self.attributes
+                .iter()
+                .filter(|e| e.tag() < tags::DIRECTORY_RECORD_SEQUENCE)
+                .cloned(),
+        );
+        header.put(DataElement::new(
+            tags::FILE_SET_ID,
+            VR::CS,
+            PrimitiveValue::from(self.file_set_id.as_str()),
+        ));
+        header.put(DataElement::new(
+            tags::OFFSET_OF_THE_FIRST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+            VR::UL,
+            PrimitiveValue::from(first),
+        ));
+        header.put(DataElement::new(
+            tags::OFFSET_OF_THE_LAST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+            VR::UL,
+            PrimitiveValue::from(last),
+        ));
+        header.put(DataElement::new(
+            tags::FILE_SET_CONSISTENCY_FLAG,
+            VR::US,
+            PrimitiveValue::from(0_u16),
+        ));
+        let mut bytes = Vec::new();
+        header
+            .write_dataset_with_ts(&mut bytes, &EXPLICIT_VR_LITTLE_ENDIAN.erased())
+            .context(EncodeDirectorySnafu)?;
+        Ok(bytes)
+    }
+}
+
+/// An iterator over the records of a directory, depth first.
+///
+/// See [`DicomDir::iter`].
+#[derive(Debug, Clone)]
+pub struct Records<'a> {
+    stack: Vec<std::slice::Iter<'a, DirectoryRecord>>,
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = &'a DirectoryRecord;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let level = self.stack.last_mut()?;
+            match level.next() {
+                Some(record) => {
+                    self.stack.push(record.children.iter());
+                    return Some(record);
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Take the records linked from the record at the given offset
+/// out of the directory record items,
+/// along with their lower-level records.
+fn take_records(
+    items: &mut HashMap<u32, InMemDicomObject>,
+    mut offset: u32,
+    visited: &mut HashSet<u32>,
+) -> Result<Vec<DirectoryRecord>> {
+    let mut records = Vec::new();
+    while offset != 0 {
+        ensure!(visited.insert(offset), RepeatedRecordSnafu { offset });
+        let mut attributes = items
+            .remove(&offset)
+            .context(InvalidOffsetSnafu { offset })?;
+        let next = uint_attribute(&attributes, tags::OFFSET_OF_THE_NEXT_DIRECTORY_RECORD);
+        let lower = uint_attribute(
+            &attributes,
+            tags::OFFSET_OF_REFERENCED_LOWER_LEVEL_DIRECTORY_ENTITY,
+        );
+        let in_use = attributes
+            .get(tags::RECORD_IN_USE_FLAG)
+            .and_then(|e| e.to_int::<u16>().ok())
+            .map(|flag| flag != 0)
+            .unwrap_or(true);
+        let record_type = str_attribute(&attributes, tags::DIRECTORY_RECORD_TYPE)
+            .context(MissingRecordTypeSnafu { offset })?;
+
+        if in_use {
+            let children = take_records(items, lower, visited)?;
+            for tag in &RECORD_LINKS {
+                attributes.remove_element(*tag);
+            }
+            records.push(DirectoryRecord {
+                record_type: RecordType::from(record_type.as_str()),
+                attributes,
+                children,
+            });
+        }
+        offset = next;
+    }
+    Ok(records)
+}
+
+/// A directory record in the order of the DICOMDIR file,
+/// with the positions of the records it links to.
+struct FlatRecord<'a> {
+    record: &'a DirectoryRecord,
+    next: Option<usize>,
+    lower: Option<usize>,
+}
+
+/// Lay out the given records depth first,
+/// returning the positions of the first and last of them.
+fn flatten<'a>(
+    records: &'a [DirectoryRecord],
+    out: &mut Vec<FlatRecord<'a>>,
+) -> (Option<usize>, Option<usize>) {
+    let mut first = None;
+    let mut previous: Option<usize> = None;
+    for record in records {
+        let i = out.len();
+        out.push(FlatRecord {
+            record,
+            next: None,
+            lower: None,
+        });
+        match previous {
+            Some(previous) => out[previous].next = Some(i),
+            None => first = Some(i),
+        }
+        previous = Some(i);
+        out[i].lower = flatten(&record.children, out).0;
+    }
+    (first, previous)
+}
+
+/// Encode a directory record item
+/// with the given offsets to the next and lower-level records.
+fn encode_record(record: &DirectoryRecord, next: u32, lower: u32) -> Result<Vec<u8>> {
+    let mut item = record.attributes.clone();
+    item.put(DataElement::new(
+        tags::OFFSET_OF_THE_NEXT_DIRECTORY_RECORD,
+        VR::UL,
+        PrimitiveValue::from(next),
+    ));
+    item.put(DataElement::new(
+        tags::RECORD_IN_USE_FLAG,
+        VR::US,
+        PrimitiveValue::from(0xFFFF_u16),
+    ));
+    item.put(DataElement::new(
+        tags::OFFSET_OF_REFERENCED_LOWER_LEVEL_DIRECTORY_ENTITY,
+        VR::UL,
+        PrimitiveValue::from(lower),
+    ));
+    item.put(DataElement::new(
+        tags::DIRECTORY_RECORD_TYPE,
+        VR::CS,
+        PrimitiveValue::from(record.record_type.as_str()),
+    ));
+    let mut bytes = Vec::new();
+    item.write_dataset_with_ts(&mut bytes, &EXPLICIT_VR_LITTLE_ENDIAN.erased())
+        .context(EncodeDirectorySnafu)?;
+    Ok(bytes)
+}
+
+/// Find the record of the given type with the given key value,
+/// or insert a new one with the keys provided.
+fn find_or_insert<'a>(
+    records: &'a mut Vec<DirectoryRecord>,
+    record_type: RecordType,
+    keys: impl FnOnce() -> InMemDicomObject,
+    key: Tag,
+    value: &str,
+) -> &'a mut DirectoryRecord {
+    let position = records.iter().position(|record| {
+        record.record_type == record_type
+            && str_attribute(&record.attributes, key).unwrap_or_default() == value
+    });
+    match position {
+        Some(i) => &mut records[i],
+        None => {
+            records.push(DirectoryRecord::new(record_type, keys()));
+            records.last_mut().expect("record was just inserted")
+        }
+    }
+}
+
+/// Copy the given keys from the instance,
+/// with an empty value for the required keys which are missing.
+fn record_keys(obj: &InMemDicomObject, keys: &[(Tag, VR, bool)]) -> InMemDicomObject {
+    let mut record = InMemDicomObject::new_empty();
+    for &(tag, vr, required) in keys {
+        match obj.get(tag) {
+            Some(e) => {
+                record.put(e.clone());
+            }
+            None if required => {
+                record.put(DataElement::new(tag, vr, PrimitiveValue::Empty));
+            }
+            None => {}
+        }
+    }
+    record
+}
+
+fn remove_instance(records: &mut Vec<DirectoryRecord>, sop_instance_uid: &str) -> bool {
+    let mut removed = false;
+    let mut i = 0;
+    while i < records.len() {
+        let record = &mut records[i];
+        if record.referenced_sop_instance_uid().as_deref() == Some(sop_instance_uid) {
+            records.remove(i);
+            removed = true;
+            continue;
+        }
+        if remove_instance(&mut record.children, sop_instance_uid) {
+            removed = true;
+            if record.children.is_empty() && record.referenced_file_id().is_none() {
+                records.remove(i);
+                continue;
+            }
+        }
+        i += 1;
+    }
+    removed
+}
+
+/// Check that the file ID consists of up to 8 components
+/// of up to 8 uppercase letters, digits, or underscores
+/// (PS3.10, section 8.2).
+fn check_file_id(file_id: &[String]) -> Result<()> {
+    ensure!(
+        !file_id.is_empty() && file_id.len() <= MAX_FILE_ID_COMPONENTS,
+        InvalidFileIdSnafu {
+            component: file_id.join("\\"),
+        }
+    );
+    for component in file_id {
+        ensure!(
+            !component.is_empty()
+                && component.len() <= 8
+                && component
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'),
+            InvalidFileIdSnafu {
+                component: component.as_str(),
+            }
+        );
+    }
+    Ok(())
+}
+
+fn str_attribute(obj: &InMemDicomObject, tag: Tag) -> Option<String> {
+    let value = obj.get(tag)?.to_str().ok()?;
+    Some(
+        value
+            .trim_end_matches(|c: char| c.is_whitespace() || c == '\0')
+            .to_string(),
+    )
+}
+
+fn uint_attribute(obj: &InMemDicomObject, tag: Tag) -> u32 {
+    obj.get(tag)
+        .and_then(|e| e.to_int::<u32>().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CT_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.2";
+
+    fn instance(
+        patient_id: &str,
+        study_uid: &str,
+        series_uid: &str,
+        sop_instance_uid: &str,
+    ) -> FileDicomObject<InMemDicomObject> {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, CT_IMAGE_STORAGE),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, sop_instance_uid),
+            DataElement::new(tags::STUDY_DATE, VR::DA, "20240101"),
+            DataElement::new(tags::MODALITY, VR::CS, "CT"),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::PATIENT_ID, VR::LO, patient_id),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, study_uid),
+            DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, series_uid),
+            DataElement::new(tags::INSTANCE_NUMBER, VR::IS, "1"),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(CT_IMAGE_STORAGE),
+        )
+        .unwrap()
+    }
+
+    fn sample_dicomdir() -> DicomDir {
+        let mut dicomdir = DicomDir::new("TEST");
+        for (i, (patient, study, series)) in [
+            ("P1", "2.25.1", "2.25.1.1"),
+            ("P1", "2.25.1", "2.25.1.1"),
+            ("P1", "2.25.1", "2.25.1.2"),
+            ("P2", "2.25.2", "2.25.2.1"),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let sop_instance_uid = format!("{}.{}", series, i);
+            let file_id = vec!["DICOM".to_string(), format!("IM{:04}", i)];
+            dicomdir
+                .add_instance(
+                    file_id,
+                    &instance(patient, study, series, &sop_instance_uid),
+                )
+                .unwrap();
+        }
+        dicomdir
+    }
+
+    fn record_types(dicomdir: &DicomDir) -> Vec<&str> {
+        dicomdir.iter().map(|r| r.record_type().as_str()).collect()
+    }
+
+    #[test]
+    fn build_record_tree() {
+        let dicomdir = sample_dicomdir();
+        assert_eq!(
+            record_types(&dicomdir),
+            vec![
+                "PATIENT", "STUDY", "SERIES", "IMAGE", "IMAGE", "SERIES", "IMAGE", "PATIENT",
+                "STUDY", "SERIES", "IMAGE"
+            ]
+        );
+        let image = &dicomdir.records()[0].children()[0].children()[0].children()[1];
+        assert_eq!(
+            image.referenced_file_id(),
+            Some(vec!["DICOM".to_string(), "IM0001".to_string()])
+        );
+        assert_eq!(
+            image.referenced_file("/media"),
+            Some(Path::new("/media").join("DICOM").join("IM0001"))
+        );
+        assert_eq!(
+            image.referenced_sop_instance_uid().as_deref(),
+            Some("2.25.1.1.1")
+        );
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let dicomdir = sample_dicomdir();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("DICOMDIR");
+        dicomdir.write_to_file(&path).unwrap();
+
+        // the file is a regular DICOM file
+        let obj = crate::open_file(&path).unwrap();
+        assert_eq!(
+            obj.meta().media_storage_sop_class_uid(),
+            uids::MEDIA_STORAGE_DIRECTORY_STORAGE
+        );
+        assert_eq!(
+            obj.element(tags::DIRECTORY_RECORD_SEQUENCE)
+                .unwrap()
+                .items()
+                .unwrap()
+                .len(),
+            11
+        );
+
+        let read = DicomDir::open(&path).unwrap();
+        assert_eq!(read.file_set_id(), "TEST");
+        assert_eq!(read.sop_instance_uid(), dicomdir.sop_instance_uid());
+        assert_eq!(record_types(&read), record_types(&dicomdir));
+        let uids: Vec<_> = read
+            .iter()
+            .filter_map(|r| r.referenced_sop_instance_uid())
+            .collect();
+        assert_eq!(
+            uids,
+            vec!["2.25.1.1.0", "2.25.1.1.1", "2.25.1.2.2", "2.25.2.1.3"]
+        );
+        assert_eq!(
+            str_attribute(read.records()[1].attributes(), tags::PATIENT_ID).as_deref(),
+            Some("P2")
+        );
+    }
+
+    #[test]
+    fn skip_inactive_records() {
+        let dicomdir = sample_dicomdir();
+        let mut bytes = Vec::new();
+        dicomdir.write_all(&mut bytes).unwrap();
+
+        // mark the first series record as inactive
+        let series_offset = {
+            let obj = crate::from_reader(&bytes[128..]).unwrap();
+            let items = obj
+                .element(tags::DIRECTORY_RECORD_SEQUENCE)
+                .unwrap()
+                .items()
+                .unwrap();
+            uint_attribute(
+                &items[1],
+                tags::OFFSET_OF_REFERENCED_LOWER_LEVEL_DIRECTORY_ENTITY,
+            )
+        };
+        let flag = [0x04, 0x00, 0x10, 0x14, b'U', b'S', 0x02, 0x00, 0xFF, 0xFF];
+        let start = series_offset as usize + 8;
+        let position = bytes[start..]
+            .windows(flag.len())
+            .position(|w| w == flag)
+            .unwrap();
+        bytes[start + position + 8] = 0;
+        bytes[start + position + 9] = 0;
+
+        let mut reader = std::io::Cursor::new(&bytes[128..]);
+        let meta = FileMetaTable::from_reader(&mut reader).unwrap();
+        let read = DicomDir::from_data_set(
+            &meta,
+            128 + reader.position(),
+            &bytes[128 + reader.position() as usize..],
+        )
+        .unwrap();
+        assert_eq!(
+            record_types(&read),
+            vec!["PATIENT", "STUDY", "SERIES", "IMAGE", "PATIENT", "STUDY", "SERIES", "IMAGE"]
+        );
+    }
+
+    #[test]
+    fn add_files_of_file_set() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("DICOM")).unwrap();
+        let path = dir.path().join("DICOM").join("IM0001");
+        instance("P1", "2.25.1", "2.25.1.1", "2.25.1.1.1")
+            .write_to_file(&path)
+            .unwrap();
+
+        let mut dicomdir = DicomDir::new("TEST");
+        dicomdir.add_file(dir.path(), &path).unwrap();
+        let image = dicomdir.iter().last().unwrap();
+        assert_eq!(image.record_type(), &RecordType::Image);
+        assert_eq!(image.referenced_file(dir.path()), Some(path));
+
+        assert!(matches!(
+            dicomdir.add_file(dir.path().join("DICOM"), "/tmp/IM0001"),
+            Err(Error::NotInFileSet { .. })
+        ));
+    }
+
+    #[test]
+    fn replace_and_remove_instances() {
+        let mut dicomdir = sample_dicomdir();
+
+        // adding the same instance again replaces its record
+        dicomdir
+            .add_instance(
+                vec!["DICOM", "NEW"],
+                &instance("P2", "2.25.2", "2.25.2.1", "2.25.2.1.3"),
+            )
+            .unwrap();
+        assert_eq!(dicomdir.iter().count(), 11);
+        let image = dicomdir.iter().last().unwrap();
+        assert_eq!(
+            image.referenced_file_id(),
+            Some(vec!["DICOM".to_string(), "NEW".to_string()])
+        );
+
+        // removing the last instance of a patient removes the patient
+        assert!(dicomdir.remove_instance("2.25.2.1.3"));
+        assert_eq!(dicomdir.records().len(), 1);
+        assert!(!dicomdir.remove_instance("2.25.2.1.3"));
+    }
+
+    #[test]
+    fn reject_invalid_file_ids() {
+        let mut dicomdir = DicomDir::new("TEST");
+        let obj = instance("P1", "2.25.1", "2.25.1.1", "2.25.1.1.1");
+        for file_id in [
+            vec!["dicom", "IM0001"],
+            vec!["DICOM", "IMAGE0001"],
+            vec!["DICOM", "IM0001.DCM"],
+            vec![],
+        ] {
+            assert!(matches!(
+                dicomdir.add_instance(file_id, &obj),
+                Err(Error::InvalidFileId { .. })
+            ));
+        }
+        assert!(dicomdir.records().is_empty());
+    }
+
+    #[test]
+    fn record_type_for_sop_class() {
+        assert_eq!(
+            RecordType::for_sop_class(CT_IMAGE_STORAGE),
+            RecordType::Image
+        );
+        assert_eq!(
+            RecordType::for_sop_class(uids::BASIC_TEXT_SR_STORAGE).as_str(),
+            "SR DOCUMENT"
+        );
+        assert_eq!(
+            RecordType::for_sop_class(uids::GRAYSCALE_SOFTCOPY_PRESENTATION_STATE_STORAGE).as_str(),
+            "PRESENTATION"
+        );
+        assert_eq!(RecordType::from("SERIES "), RecordType::Series);
+    }
+}
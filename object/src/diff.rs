@@ -0,0 +1,497 @@
+//! Structural comparison of DICOM objects.
+//!
+//! [`diff`] compares two in-memory DICOM objects attribute by attribute,
+//! recursing into the items of data set sequences
+//! and into the fragments of encapsulated pixel data,
+//! and reports each [`Difference`] found
+//! along with the [selector](AttributeSelector) of the attribute concerned.
+//! [`DiffOptions`] makes the comparison more tolerant,
+//! such as to value padding or to the notation of decimal numbers,
+//! or leaves some attributes out of it.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_dictionary_std::tags;
+//! use dicom_object::diff::DiffOptions;
+//! use dicom_object::open_file;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let original = open_file("0001.dcm")?;
+//! let forwarded = open_file("0001-forwarded.dcm")?;
+//!
+//! let differences = DiffOptions::new()
+//!     .ignore_padding(true)
+//!     .numeric_equivalence(true)
+//!     .ignore_tag(tags::INSTANCE_CREATION_TIME)
+//!     .diff(&original, &forwarded);
+//! for difference in &differences {
+//!     println!("{}", difference);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::BTreeSet;
+use std::fmt;
+
+use dicom_core::header::Header;
+use dicom_core::ops::{AttributeSelector, AttributeSelectorStep};
+use dicom_core::value::{PrimitiveValue, Value};
+use dicom_core::{Tag, VR};
+
+use crate::mem::{InMemDicomObject, InMemElement};
+use crate::{selector, StandardDataDictionary};
+
+/// A difference between two DICOM objects,
+/// the first one being on the left side of the comparison
+/// and the second one on the right side.
+#[derive(Debug, Clone)]
+pub struct Difference<'a, D = StandardDataDictionary> {
+    /// The attribute which differs.
+    pub selector: AttributeSelector,
+    /// How the attribute differs.
+    pub kind: DifferenceKind<'a, D>,
+}
+
+/// The kind of a [`Difference`] between two DICOM objects.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum DifferenceKind<'a, D = StandardDataDictionary> {
+    /// The attribute is only in the right object.
+    Added(&'a InMemElement<D>),
+    /// The attribute is only in the left object.
+    Removed(&'a InMemElement<D>),
+    /// The attribute has a different value representation or value.
+    Changed {
+        left: &'a InMemElement<D>,
+        right: &'a InMemElement<D>,
+    },
+    /// The sequence item at this index is only in the right object.
+    ItemAdded { index: u32 },
+    /// The sequence item at this index is only in the left object.
+    ItemRemoved { index: u32 },
+    /// The basic offset tables of the encapsulated pixel data differ.
+    OffsetTableChanged,
+    /// The pixel data fragment at this index is only in the right object.
+    FragmentAdded { index: u32 },
+    /// The pixel data fragment at this index is only in the left object.
+    FragmentRemoved { index: u32 },
+    /// The pixel data fragments at this index differ.
+    FragmentChanged { index: u32 },
+}
+
+impl<D> fmt::Display for Difference<'_, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            DifferenceKind::Added(_) => write!(f, "{}: added", self.selector),
+            DifferenceKind::Removed(_) => write!(f, "{}: removed", self.selector),
+            DifferenceKind::Changed { .. } => write!(f, "{}: changed", self.selector),
+            DifferenceKind::ItemAdded { index } => {
+                write!(f, "{}: item #{} added", self.selector, index)
+            }
+            DifferenceKind::ItemRemoved { index } => {
+                write!(f, "{}: item #{} removed", self.selector, index)
+            }
+            DifferenceKind::OffsetTableChanged => {
+                write!(f, "{}: offset table changed", self.selector)
+            }
+            DifferenceKind::FragmentAdded { index } => {
+                write!(f, "{}: fragment #{} added", self.selector, index)
+            }
+            DifferenceKind::FragmentRemoved { index } => {
+                write!(f, "{}: fragment #{} removed", self.selector, index)
+            }
+            DifferenceKind::FragmentChanged { index } => {
+                write!(f, "{}: fragment #{} changed", self.selector, index)
+            }
+        }
+    }
+}
+
+/// Compare two DICOM objects exactly,
+/// returning their differences in attribute order.
+///
+/// See [`DiffOptions`] for a more tolerant comparison.
+pub fn diff<'a, D>(
+    left: &'a InMemDicomObject<D>,
+    right: &'a InMemDicomObject<D>,
+) -> Vec<Difference<'a, D>> {
+    DiffOptions::new().diff(left, right)
+}
+
+/// Options for comparing DICOM objects.
+///
+/// By default, values must be exactly the same.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DiffOptions {
+    ignore_padding: bool,
+    numeric_equivalence: bool,
+    ignored_tags: BTreeSet<Tag>,
+}
+
+impl DiffOptions {
+    /// Create the default comparison options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether to ignore trailing padding,
+    /// so that text values only differing by trailing spaces or null characters
+    /// and binary values only differing by a trailing zero byte
+    /// are considered equal.
+    pub fn ignore_padding(mut self, ignore_padding: bool) -> Self {
+        self.ignore_padding = ignore_padding;
+        self
+    }
+
+    /// Set whether to compare decimal strings (DS) and integer strings (IS)
+    /// by their numeric value,
+    /// so that `1.50` and `1.5` are considered equal.
+    pub fn numeric_equivalence(mut self, numeric_equivalence: bool) -> Self {
+        self.numeric_equivalence = numeric_equivalence;
+        self
+    }
+
+    /// Leave the attribute with the given tag out of the comparison,
+    /// at any level of nesting.
+    pub fn ignore_tag(mut self, tag: Tag) -> Self {
+        self.ignored_tags.insert(tag);
+        self
+    }
+
+    /// Leave the attributes with the given tags out of the comparison,
+    /// at any level of nesting.
+    pub fn ignore_tags(mut self, tags: impl IntoIterator<Item = Tag>) -> Self {
+        self.ignored_tags.extend(tags);
+        self
+    }
+
+    /// Compare two DICOM objects,
+    /// returning their differences in attribute order.
+    pub fn diff<'a, D>(
+        &self,
+        left: &'a InMemDicomObject<D>,
+        right: &'a InMemDicomObject<D>,
+    ) -> Vec<Difference<'a, D>> {
+        let mut differences = Vec::new();
+        self.diff_data_set(&mut Vec::new(), left, right, &mut differences);
+        differences
+    }
+
+    fn diff_data_set<'a, D>(
+        &self,
+        path: &mut Vec<AttributeSelectorStep>,
+        left: &'a InMemDicomObject<D>,
+        right: &'a InMemDicomObject<D>,
+        differences: &mut Vec<Difference<'a, D>>,
+    ) {
+        let mut left_elements = left.into_iter().peekable();
+        let mut right_elements = right.into_iter().peekable();
+        loop {
+            let (kind, tag) = match (left_elements.peek(), right_elements.peek()) {
+                (None, None) => break,
+                (Some(l), Some(r)) if l.tag() == r.tag() => {
+                    let (l, r) = (
+                        left_elements.next().unwrap(),
+                        right_elements.next().unwrap(),
+                    );
+                    if !self.ignored_tags.contains(&l.tag()) {
+                        self.diff_element(path, l, r, differences);
+                    }
+                    continue;
+                }
+                (Some(l), Some(r)) if l.tag() < r.tag() => (DifferenceKind::Removed(*l), l.tag()),
+                (Some(l), None) => (DifferenceKind::Removed(*l), l.tag()),
+                (_, Some(r)) => (DifferenceKind::Added(*r), r.tag()),
+            };
+            match kind {
+                DifferenceKind::Removed(_) => left_elements.next(),
+                _ => right_elements.next(),
+            };
+            if !self.ignored_tags.contains(&tag) {
+                differences.push(Difference {
+                    selector: selector(path, tag),
+                    kind,
+                });
+            }
+        }
+    }
+
+    fn diff_element<'a, D>(
+        &self,
+        path: &mut Vec<AttributeSelectorStep>,
+        left: &'a InMemElement<D>,
+        right: &'a InMemElement<D>,
+        differences: &mut Vec<Difference<'a, D>>,
+    ) {
+        let tag = left.tag();
+        let changed = Difference {
+            selector: selector(path, tag),
+            kind: DifferenceKind::Changed { left, right },
+        };
+        if left.vr() != right.vr() {
+            differences.push(changed);
+            return;
+        }
+        match (left.value(), right.value()) {
+            (Value::Primitive(l), Value::Primitive(r)) => {
+                if !self.primitive_eq(left.vr(), l, r) {
+                    differences.push(changed);
+                }
+            }
+            (Value::Sequence(l), Value::Sequence(r)) => {
+                let (l, r) = (l.items(), r.items());
+                for (index, (l, r)) in l.iter().zip(r).enumerate() {
+                    path.push(AttributeSelectorStep::Nested {
+                        tag,
+                        item: index as u32,
+                    });
+                    self.diff_data_set(path, l, r, differences);
+                    path.pop();
+                }
+                for index in r.len()..l.len() {
+                    differences.push(Difference {
+                        selector: selector(path, tag),
+                        kind: DifferenceKind::ItemRemoved {
+                            index: index as u32,
+                        },
+                    });
+                }
+                for index in l.len()..r.len() {
+                    differences.push(Difference {
+                        selector: selector(path, tag),
+                        kind: DifferenceKind::ItemAdded {
+                            index: index as u32,
+                        },
+                    });
+                }
+            }
+            (Value::PixelSequence(l), Value::PixelSequence(r)) => {
+                if l.offset_table() != r.offset_table() {
+                    differences.push(Difference {
+                        selector: selector(path, tag),
+                        kind: DifferenceKind::OffsetTableChanged,
+                    });
+                }
+                let (l, r) = (l.fragments(), r.fragments());
+                for (index, (l, r)) in l.iter().zip(r).enumerate() {
+                    if !self.bytes_eq(l, r) {
+                        differences.push(Difference {
+                            selector: selector(path, tag),
+                            kind: DifferenceKind::FragmentChanged {
+                                index: index as u32,
+                            },
+                        });
+                    }
+                }
+                for index in r.len()..l.len() {
+                    differences.push(Difference {
+                        selector: selector(path, tag),
+                        kind: DifferenceKind::FragmentRemoved {
+                            index: index as u32,
+                        },
+                    });
+                }
+                for index in l.len()..r.len() {
+                    differences.push(Difference {
+                        selector: selector(path, tag),
+                        kind: DifferenceKind::FragmentAdded {
+                            index: index as u32,
+                        },
+                    });
+                }
+            }
+            _ => differences.push(changed),
+        }
+    }
+
+    fn primitive_eq(&self, vr: VR, left: &PrimitiveValue, right: &PrimitiveValue) -> bool {
+        match (left, right) {
+            (PrimitiveValue::U8(l), PrimitiveValue::U8(r)) => self.bytes_eq(l, r),
+            _ if self.numeric_equivalence && matches!(vr, VR::DS | VR::IS) => {
+                match (numbers(left), numbers(right)) {
+                    (Some(l), Some(r)) => l == r,
+                    _ => false,
+                }
+            }
+            (
+                PrimitiveValue::Str(_) | PrimitiveValue::Strs(_),
+                PrimitiveValue::Str(_) | PrimitiveValue::Strs(_),
+            ) => {
+                let padding: &[char] = if self.ignore_padding {
+                    &[' ', '\0']
+                } else {
+                    &[]
+                };
+                raw_strs(left)
+                    .map(|s| s.trim_end_matches(padding))
+                    .eq(raw_strs(right).map(|s| s.trim_end_matches(padding)))
+            }
+            _ => left == right,
+        }
+    }
+
+    fn bytes_eq(&self, left: &[u8], right: &[u8]) -> bool {
+        if left == right {
+            return true;
+        }
+        if !self.ignore_padding {
+            return false;
+        }
+        let (shorter, longer) = if left.len() < right.len() {
+            (left, right)
+        } else {
+            (right, left)
+        };
+        longer.len() == shorter.len() + 1 && longer.ends_with(&[0]) && longer.starts_with(shorter)
+    }
+}
+
+/// The individual string values of a textual value, as they are,
+/// unlike [`PrimitiveValue::to_multi_str`] which trims padding.
+fn raw_strs(value: &PrimitiveValue) -> impl Iterator<Item = &str> {
+    let values: &[String] = match value {
+        PrimitiveValue::Str(s) => std::slice::from_ref(s),
+        PrimitiveValue::Strs(values) => values,
+        _ => &[],
+    };
+    values.iter().map(String::as_str)
+}
+
+/// The numeric values of a decimal or integer string,
+/// if they are all valid numbers.
+fn numbers(value: &PrimitiveValue) -> Option<Vec<f64>> {
+    value
+        .to_multi_str()
+        .iter()
+        .map(|v| v.trim_matches([' ', '\0']).parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::{DataSetSequence, PixelFragmentSequence};
+    use dicom_core::{dicom_value, DataElement};
+    use dicom_dictionary_std::tags;
+
+    fn base() -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::PATIENT_ID, VR::LO, "12345"),
+            DataElement::new(tags::SLICE_THICKNESS, VR::DS, "1.50"),
+            DataElement::new(
+                tags::REFERENCED_IMAGE_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::REFERENCED_SOP_INSTANCE_UID, VR::UI, "1.2.3.4"),
+                ])]),
+            ),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PixelFragmentSequence::new(vec![], vec![vec![1, 2, 3, 4], vec![5, 6]]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn identical_objects() {
+        assert!(diff(&base(), &base()).is_empty());
+    }
+
+    #[test]
+    fn added_removed_and_changed() {
+        let left = base();
+        let mut right = base();
+        right.remove_element(tags::PATIENT_ID);
+        right.put(DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^Jane"));
+        right.put(DataElement::new(tags::PATIENT_SEX, VR::CS, "F"));
+
+        let differences = diff(&left, &right);
+        let summary: Vec<_> = differences.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            summary,
+            vec![
+                "(0010,0010): changed",
+                "(0010,0020): removed",
+                "(0010,0040): added",
+            ]
+        );
+        match &differences[0].kind {
+            DifferenceKind::Changed { left, right } => {
+                assert_eq!(left.value().to_str().unwrap(), "Doe^John");
+                assert_eq!(right.value().to_str().unwrap(), "Doe^Jane");
+            }
+            kind => panic!("unexpected difference {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn nested_items_and_fragments() {
+        let left = base();
+        let mut right = base();
+        right.put(DataElement::new(
+            tags::REFERENCED_IMAGE_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![
+                InMemDicomObject::from_element_iter([DataElement::new(
+                    tags::REFERENCED_SOP_INSTANCE_UID,
+                    VR::UI,
+                    "1.2.3.5",
+                )]),
+                InMemDicomObject::new_empty(),
+            ]),
+        ));
+        right.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PixelFragmentSequence::new(vec![0], vec![vec![1, 2, 3, 4], vec![5, 7]]),
+        ));
+
+        let summary: Vec<_> = diff(&left, &right).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            summary,
+            vec![
+                "(0008,1140)[0].(0008,1155): changed",
+                "(0008,1140): item #1 added",
+                "(7FE0,0010): offset table changed",
+                "(7FE0,0010): fragment #1 changed",
+            ]
+        );
+    }
+
+    #[test]
+    fn tolerances() {
+        let left = base();
+        let mut right = base();
+        right.put(DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John "));
+        right.put(DataElement::new(tags::SLICE_THICKNESS, VR::DS, "1.5"));
+        right.put(DataElement::new(tags::PATIENT_ID, VR::LO, "54321"));
+        right.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PixelFragmentSequence::new(vec![], vec![vec![1, 2, 3, 4], vec![5, 6, 0]]),
+        ));
+        assert_eq!(diff(&left, &right).len(), 4);
+
+        let differences = DiffOptions::new()
+            .ignore_padding(true)
+            .numeric_equivalence(true)
+            .ignore_tag(tags::PATIENT_ID)
+            .diff(&left, &right);
+        assert!(differences.is_empty(), "{:?}", differences);
+
+        // numbers are still compared
+        right.put(DataElement::new(
+            tags::SLICE_THICKNESS,
+            VR::DS,
+            dicom_value!(Strs, ["1.6"]),
+        ));
+        let differences = DiffOptions::new()
+            .numeric_equivalence(true)
+            .diff(&left, &right);
+        assert!(differences
+            .iter()
+            .any(|d| d.selector == AttributeSelector::from(tags::SLICE_THICKNESS)));
+    }
+}
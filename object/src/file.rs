@@ -1,14 +1,20 @@
 use dicom_core::{DataDictionary, Tag};
-use dicom_dictionary_std::StandardDataDictionary;
+use dicom_dictionary_std::{PrivateDataDictionary, StandardDataDictionary};
 use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
 
 // re-export from dicom_parser
 pub use dicom_parser::dataset::read::OddLengthStrategy;
 
-use crate::{DefaultDicomObject, ReadError};
-use std::io::Read;
+use crate::{
+    DefaultDicomObject, FileMetaTable, OpenFileSnafu, ParseMetaDataSetSnafu, ReadError,
+    ReadFileSnafu,
+};
+use snafu::ResultExt;
+use std::fs::File;
+use std::io::{BufReader, Read};
 use std::path::Path;
+use std::sync::Arc;
 
 pub type Result<T, E = ReadError> = std::result::Result<T, E>;
 
@@ -62,6 +68,7 @@ pub struct OpenFileOptions<D = StandardDataDictionary, T = TransferSyntaxRegistr
     read_until: Option<Tag>,
     read_preamble: ReadPreamble,
     odd_length: OddLengthStrategy,
+    private_dictionary: Option<Arc<PrivateDataDictionary>>,
 }
 
 impl OpenFileOptions {
@@ -102,6 +109,26 @@ impl<D, T> OpenFileOptions<D, T> {
         self
     }
 
+    /// Set a dictionary of private attributes,
+    /// used to resolve the VR of private data elements
+    /// which would otherwise be read as unknown (UN),
+    /// as happens in implicit VR transfer syntaxes.
+    ///
+    /// ```no_run
+    /// # use dicom_object::OpenFileOptions;
+    /// use dicom_dictionary_std::PrivateDataDictionary;
+    ///
+    /// let private_dictionary = PrivateDataDictionary::open_file("path/to/private.dic")?;
+    /// let file = OpenFileOptions::new()
+    ///     .private_dictionary(private_dictionary)
+    ///     .open_file("path/to/file.dcm")?;
+    /// # Result::<(), Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn private_dictionary(mut self, dict: impl Into<Arc<PrivateDataDictionary>>) -> Self {
+        self.private_dictionary = Some(dict.into());
+        self
+    }
+
     /// Set the transfer syntax index to use when reading the file.
     pub fn transfer_syntax_index<Tr>(self, ts_index: Tr) -> OpenFileOptions<D, Tr>
     where
@@ -113,6 +140,7 @@ impl<D, T> OpenFileOptions<D, T> {
             read_preamble: self.read_preamble,
             ts_index,
             odd_length: self.odd_length,
+            private_dictionary: self.private_dictionary,
         }
     }
 
@@ -137,6 +165,7 @@ impl<D, T> OpenFileOptions<D, T> {
             read_preamble: self.read_preamble,
             ts_index: self.ts_index,
             odd_length: self.odd_length,
+            private_dictionary: self.private_dictionary,
         }
     }
 
@@ -155,9 +184,53 @@ impl<D, T> OpenFileOptions<D, T> {
             self.read_until,
             self.read_preamble,
             self.odd_length,
+            self.private_dictionary,
         )
     }
 
+    /// Open the file at the given path,
+    /// reading only its preamble (as configured) and file meta group.
+    ///
+    /// Returns the file meta table
+    /// and a reader positioned at the beginning of the data set,
+    /// which is left as is in the transfer syntax of the file.
+    /// This is useful for passing the encoded data set elsewhere,
+    /// such as to another DICOM node,
+    /// without holding the whole object in memory.
+    /// The remaining options do not apply.
+    ///
+    /// ```no_run
+    /// # use dicom_object::OpenFileOptions;
+    /// let (meta, mut data) = OpenFileOptions::new().open_file_raw("path/to/file.dcm")?;
+    /// println!("data set in {}", meta.transfer_syntax());
+    /// std::io::copy(&mut data, &mut std::io::sink())?;
+    /// # Result::<(), Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn open_file_raw<P>(self, path: P) -> Result<(FileMetaTable, BufReader<File>)>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let mut file =
+            BufReader::new(File::open(path).with_context(|_| OpenFileSnafu { filename: path })?);
+
+        let mut read_preamble = self.read_preamble;
+        if read_preamble == ReadPreamble::Auto {
+            read_preamble =
+                DefaultDicomObject::<StandardDataDictionary>::detect_preamble(&mut file)
+                    .with_context(|_| ReadFileSnafu { filename: path })?;
+        }
+        if read_preamble == ReadPreamble::Auto || read_preamble == ReadPreamble::Always {
+            let mut buf = [0u8; 128];
+            // skip the preamble
+            file.read_exact(&mut buf)
+                .with_context(|_| ReadFileSnafu { filename: path })?;
+        }
+
+        let meta = FileMetaTable::from_reader(&mut file).context(ParseMetaDataSetSnafu)?;
+        Ok((meta, file))
+    }
+
     /// Obtain a DICOM object by reading from a byte source.
     ///
     /// This method assumes
@@ -177,6 +250,7 @@ impl<D, T> OpenFileOptions<D, T> {
             self.read_until,
             self.read_preamble,
             self.odd_length,
+            self.private_dictionary,
         )
     }
 }
@@ -133,16 +133,30 @@
 //! # }
 //! # run().unwrap();
 //! ```
+//!
+//! File-set directories (DICOMDIR files),
+//! such as those found on removable media,
+//! can be read and written through the [`dicomdir`] module.
+//! See the [`deidentify`] module for de-identifying objects
+//! according to the confidentiality profiles of the standard,
+//! and the [`diff`] module for comparing two objects.
+//! The [`validate`] module checks the attributes of an object
+//! against the information object definition (IOD) of its SOP class,
+//! for the few IODs currently in the standard dictionary.
+pub mod deidentify;
+pub mod dicomdir;
+pub mod diff;
 pub mod file;
 pub mod mem;
 pub mod meta;
 pub mod ops;
 pub mod tokens;
+pub mod validate;
 
 pub use crate::file::{from_reader, open_file, OpenFileOptions};
 pub use crate::mem::InMemDicomObject;
 pub use crate::meta::{FileMetaTable, FileMetaTableBuilder};
-use dicom_core::ops::AttributeSelector;
+use dicom_core::ops::{AttributeSelector, AttributeSelectorStep};
 use dicom_core::DataDictionary;
 pub use dicom_core::Tag;
 pub use dicom_dictionary_std::StandardDataDictionary;
@@ -176,6 +190,34 @@ pub const IMPLEMENTATION_CLASS_UID: &str = "2.25.2620864068291104199312978947725
 /// even between patch versions.
 pub const IMPLEMENTATION_VERSION_NAME: &str = "DICOM-rs 0.8.1";
 
+/// Build the selector of the attribute with the given tag
+/// in the nested data set reached through the given path.
+pub(crate) fn selector(path: &[AttributeSelectorStep], tag: Tag) -> AttributeSelector {
+    AttributeSelector::new(
+        path.iter()
+            .copied()
+            .chain(std::iter::once(AttributeSelectorStep::Tag(tag))),
+    )
+    .expect("selector should end with a tag")
+}
+
+/// Generate a UID under the `2.25` root
+/// from a random version 4 UUID.
+pub(crate) fn generate_uid() -> String {
+    use std::collections::hash_map::RandomState;
+    use std::hash::BuildHasher;
+
+    let mut bits: u128 = 0;
+    for _ in 0..2 {
+        let hash = RandomState::new().hash_one(std::time::SystemTime::now());
+        bits = (bits << 64) | u128::from(hash);
+    }
+    // set the version and variant bits
+    bits = (bits & !(0xF << 76)) | (0x4 << 76);
+    bits = (bits & !(0x3 << 62)) | (0x2 << 62);
+    format!("2.25.{}", bits)
+}
+
 /// Trait type for a DICOM object.
 /// This is a high-level abstraction where an object is accessed and
 /// manipulated as dictionary of entries indexed by tags, which in
@@ -799,6 +841,45 @@ mod tests {
         let _ = std::fs::remove_file(FILE_NAME);
     }
 
+    #[test]
+    fn open_file_raw_yields_encoded_data_set() {
+        use std::io::Read;
+
+        const FILE_NAME: &str = ".raw-test.dcm";
+
+        let mut obj = InMemDicomObject::new_empty();
+        obj.put(DataElement::new(
+            dicom_dictionary_std::tags::PATIENT_NAME,
+            VR::PN,
+            PrimitiveValue::from("Doe^John"),
+        ));
+        let obj = obj
+            .with_meta(
+                FileMetaTableBuilder::new()
+                    .media_storage_sop_class_uid("1.2.840.10008.5.1.4.1.1.7")
+                    .media_storage_sop_instance_uid("1.2.23456789")
+                    .transfer_syntax("1.2.840.10008.1.2.1"),
+            )
+            .unwrap();
+        obj.write_to_file(FILE_NAME).unwrap();
+
+        let (meta, mut data) = crate::OpenFileOptions::new()
+            .open_file_raw(FILE_NAME)
+            .unwrap();
+        let mut raw = Vec::new();
+        data.read_to_end(&mut raw).unwrap();
+        let _ = std::fs::remove_file(FILE_NAME);
+
+        assert_eq!(&meta, obj.meta());
+        let mut expected = Vec::new();
+        obj.write_dataset_with_ts(
+            &mut expected,
+            &dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased(),
+        )
+        .unwrap();
+        assert_eq!(raw, expected);
+    }
+
     /// A FileDicomObject<InMemDicomObject>
     /// can be used like a DICOM object.
     #[test]
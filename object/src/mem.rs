@@ -47,6 +47,7 @@ use std::borrow::Cow;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
+use std::sync::Arc;
 use std::{collections::BTreeMap, io::Write};
 
 use crate::file::ReadPreamble;
@@ -68,7 +69,7 @@ use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry};
 use dicom_core::header::{GroupNumber, HasLength, Header};
 use dicom_core::value::{DataSetSequence, PixelFragmentSequence, Value, ValueType, C};
 use dicom_core::{DataElement, Length, PrimitiveValue, Tag, VR};
-use dicom_dictionary_std::{tags, StandardDataDictionary};
+use dicom_dictionary_std::{tags, PrivateDataDictionary, StandardDataDictionary};
 use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
 use dicom_encoding::{encode::EncodeTo, text::SpecificCharacterSet, TransferSyntax};
 use dicom_parser::dataset::{DataSetReader, DataToken, IntoTokensOptions};
@@ -317,12 +318,13 @@ where
             None,
             ReadPreamble::Auto,
             Default::default(),
+            None,
         )
     }
 
     // detect the presence of a preamble
     // and provide a better `ReadPreamble` option accordingly
-    fn detect_preamble<S>(reader: &mut BufReader<S>) -> std::io::Result<ReadPreamble>
+    pub(crate) fn detect_preamble<S>(reader: &mut BufReader<S>) -> std::io::Result<ReadPreamble>
     where
         S: Read,
     {
@@ -352,6 +354,7 @@ where
         read_until: Option<Tag>,
         mut read_preamble: ReadPreamble,
         odd_length: OddLengthStrategy,
+        private_dictionary: Option<Arc<PrivateDataDictionary>>,
     ) -> Result<Self, ReadError>
     where
         P: AsRef<Path>,
@@ -387,6 +390,9 @@ where
                 options,
             )
             .context(CreateParserSnafu)?;
+            if let Some(private_dictionary) = private_dictionary {
+                dataset = dataset.with_private_dictionary(private_dictionary);
+            }
             let obj = InMemDicomObject::build_object(
                 &mut dataset,
                 dict,
@@ -465,6 +471,7 @@ where
             None,
             ReadPreamble::Auto,
             Default::default(),
+            None,
         )
     }
 
@@ -475,6 +482,7 @@ where
         read_until: Option<Tag>,
         mut read_preamble: ReadPreamble,
         odd_length: OddLengthStrategy,
+        private_dictionary: Option<Arc<PrivateDataDictionary>>,
     ) -> Result<Self, ReadError>
     where
         S: Read + 's,
@@ -500,12 +508,11 @@ where
         if let Some(ts) = ts_index.get(&meta.transfer_syntax) {
             let mut options = DataSetReaderOptions::default();
             options.odd_length = odd_length;
-            let mut dataset = DataSetReader::new_with_ts_options(
-                file,
-                ts,
-                options,
-            )
-            .context(CreateParserSnafu)?;
+            let mut dataset =
+                DataSetReader::new_with_ts_options(file, ts, options).context(CreateParserSnafu)?;
+            if let Some(private_dictionary) = private_dictionary {
+                dataset = dataset.with_private_dictionary(private_dictionary);
+            }
             let obj = InMemDicomObject::build_object(
                 &mut dataset,
                 dict,
@@ -820,6 +827,42 @@ where
         })
     }
 
+    /// Get the value of the private creator
+    /// reserving the block of the given private data element tag,
+    /// if it is present in the dataset.
+    ///
+    /// Returns `None` if the tag is not a private data element tag
+    /// (`(gggg,xxEE)` with an odd group and `xx` of at least `10`),
+    /// or if the private creator is not found.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use dicom_core::{VR, PrimitiveValue, Tag, DataElement};
+    /// # use dicom_object::InMemDicomObject;
+    /// let ds = InMemDicomObject::from_element_iter([
+    ///     DataElement::new(
+    ///         Tag(0x0009, 0x0010),
+    ///         VR::LO,
+    ///         PrimitiveValue::from("CREATOR 1"),
+    ///     ),
+    ///     DataElement::new(Tag(0x0009, 0x1001), VR::DS, "1.0"),
+    /// ]);
+    /// assert_eq!(
+    ///     ds.private_creator(Tag(0x0009, 0x1001)).as_deref(),
+    ///     Some("CREATOR 1"),
+    /// );
+    /// assert_eq!(ds.private_creator(Tag(0x0009, 0x1101)), None);
+    /// ```
+    pub fn private_creator(&self, tag: Tag) -> Option<Cow<'_, str>> {
+        if tag.group() % 2 == 0 || tag.element() < 0x1000 {
+            return None;
+        }
+        self.get(Tag(tag.group(), tag.element() >> 8))?
+            .to_str()
+            .ok()
+    }
+
     /// Insert a data element to the object, replacing (and returning) any
     /// previous element of the same attribute.
     /// This might invalidate all sequence and item lengths if the charset of the
@@ -4011,4 +4054,53 @@ mod tests {
             "No space available in group 0x0009"
         );
     }
+
+    #[test]
+    fn read_private_elements_with_private_dictionary() {
+        use crate::OpenFileOptions;
+        use dicom_core::dictionary::VirtualVr;
+        use dicom_dictionary_std::private::PrivateDataDictionaryEntry;
+
+        let mut obj = InMemDicomObject::new_empty();
+        obj.put_str(Tag(0x0009, 0x0010), VR::LO, "ACME 1");
+        obj.put_private_element(0x0009, "ACME 1", 0x01, VR::US, PrimitiveValue::from(10_u16))
+            .unwrap();
+        let file_object = obj
+            .with_meta(
+                FileMetaTableBuilder::default()
+                    // Implicit VR Little Endian
+                    .transfer_syntax("1.2.840.10008.1.2")
+                    // Secondary Capture image storage
+                    .media_storage_sop_class_uid("1.2.840.10008.5.1.4.1.1.7")
+                    .media_storage_sop_instance_uid("2.25.221314879990624101283043547144116927116"),
+            )
+            .unwrap();
+        let mut data = Vec::new();
+        file_object.write_all(&mut data).unwrap();
+
+        // the VR of the private element is unknown by default
+        let obj = OpenFileOptions::new().from_reader(&data[..]).unwrap();
+        assert_eq!(obj.element(Tag(0x0009, 0x1001)).unwrap().vr(), VR::UN);
+
+        let private_dictionary: PrivateDataDictionary = vec![PrivateDataDictionaryEntry {
+            creator: "ACME 1".to_string(),
+            group: 0x0009,
+            element: 0x01,
+            alias: "AcmeThing".to_string(),
+            vr: VirtualVr::Exact(VR::US),
+        }]
+        .into_iter()
+        .collect();
+        let obj = OpenFileOptions::new()
+            .private_dictionary(private_dictionary)
+            .from_reader(&data[..])
+            .unwrap();
+        let elem = obj.element(Tag(0x0009, 0x1001)).unwrap();
+        assert_eq!(elem.vr(), VR::US);
+        assert_eq!(elem.to_int::<u16>().unwrap(), 10);
+        assert_eq!(
+            obj.private_creator(Tag(0x0009, 0x1001)).as_deref(),
+            Some("ACME 1")
+        );
+    }
 }
@@ -0,0 +1,901 @@
+//! Checking the attributes of DICOM objects against their IOD.
+//!
+//! [`validate`] looks up the information object definition (IOD)
+//! of the object's SOP class in the [IOD dictionary](dicom_dictionary_std::iod)
+//! and checks the attributes of each of its modules:
+//!
+//! - Type 1 and Type 2 attributes must be present,
+//!   and Type 1 and Type 1C attributes must not be empty;
+//! - the value representation must match the one in the
+//!   [standard data dictionary](StandardDataDictionary);
+//! - the number of values must satisfy the value multiplicity;
+//! - values must be one of the enumerated values of the attribute,
+//!   and should be one of its defined terms;
+//! - sequence items are checked against the attributes expected in them.
+//!
+//! Mandatory modules are always checked,
+//! whereas conditional and user option modules
+//! are only checked if the object has any of their attributes.
+//! The conditions of Type 1C and Type 2C attributes are not evaluated,
+//! so this is not a full conformance check.
+//! Objects of SOP classes whose IOD is not in the dictionary,
+//! which currently has only a few IODs,
+//! are rejected with [`Error::UnknownSopClass`].
+//! The outcome is a [`ValidationReport`] listing each [`Issue`] found.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_object::open_file;
+//! use dicom_object::validate::validate;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let obj = open_file("0001.dcm")?;
+//!
+//! let report = validate(&obj)?;
+//! for issue in report.issues() {
+//!     println!("{}", issue);
+//! }
+//! if !report.is_valid() {
+//!     println!("object does not conform to the {} IOD", report.iod().name);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+
+use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry, VirtualVr};
+use dicom_core::ops::{AttributeSelector, AttributeSelectorStep};
+use dicom_core::value::{PrimitiveValue, Value};
+use dicom_core::VR;
+use dicom_dictionary_std::iod::{
+    iod_for_sop_class, AttributeDefinition, AttributeType, IodDefinition, ModuleDefinition,
+    ModuleUsage, ValueMultiplicity,
+};
+use dicom_dictionary_std::tags;
+use snafu::{OptionExt, Snafu};
+
+use crate::mem::{InMemDicomObject, InMemElement};
+use crate::{selector, StandardDataDictionary};
+
+/// An error which may occur when validating an object.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// Missing SOP Class UID
+    MissingSopClass,
+    /// No IOD definition for SOP class {uid}
+    UnknownSopClass { uid: String },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Validate a DICOM object against the IOD of its SOP class.
+///
+/// Returns an error if the object has no _SOP Class UID_
+/// or if there is no IOD definition for its SOP class.
+pub fn validate<D>(obj: &InMemDicomObject<D>) -> Result<ValidationReport>
+where
+    D: DataDictionary + Clone,
+{
+    let uid = obj
+        .get(tags::SOP_CLASS_UID)
+        .and_then(|e| e.to_str().ok())
+        .context(MissingSopClassSnafu)?;
+    let uid = uid.trim_end_matches(['\0', ' ']);
+    let iod = iod_for_sop_class(uid).context(UnknownSopClassSnafu { uid })?;
+    Ok(validate_iod(obj, iod))
+}
+
+/// Validate a DICOM object against the given IOD.
+pub fn validate_iod<D>(obj: &InMemDicomObject<D>, iod: &'static IodDefinition) -> ValidationReport
+where
+    D: DataDictionary + Clone,
+{
+    let mut issues = Vec::new();
+    for iod_module in iod.modules {
+        let module = iod_module.module;
+        let checked = iod_module.usage == ModuleUsage::Mandatory
+            || module.attributes.iter().any(|a| obj.get(a.tag).is_some());
+        if checked {
+            check_attributes(module, &mut Vec::new(), module.attributes, obj, &mut issues);
+        }
+    }
+    ValidationReport { iod, issues }
+}
+
+/// The outcome of validating a DICOM object.
+#[derive(Debug, Clone)]
+pub struct ValidationReport {
+    iod: &'static IodDefinition,
+    issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    /// The IOD which the object was validated against.
+    pub fn iod(&self) -> &'static IodDefinition {
+        self.iod
+    }
+
+    /// All issues found, in the order of the IOD's modules.
+    pub fn issues(&self) -> &[Issue] {
+        &self.issues
+    }
+
+    /// Iterate over the issues of error severity.
+    pub fn errors(&self) -> impl Iterator<Item = &Issue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+    }
+
+    /// Iterate over the issues of warning severity.
+    pub fn warnings(&self) -> impl Iterator<Item = &Issue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    /// Whether the object conforms to the IOD,
+    /// meaning that no errors were found.
+    /// Warnings are allowed.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+}
+
+/// The severity of an [`Issue`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The object might not be handled as intended.
+    Warning,
+    /// The object does not conform to its IOD.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// A conformance issue of an attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    /// How serious the issue is.
+    pub severity: Severity,
+    /// The name of the module which defines the attribute.
+    pub module: &'static str,
+    /// The attribute concerned.
+    pub selector: AttributeSelector,
+    /// What is wrong with the attribute.
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} ({}): {}",
+            self.severity, self.selector, self.module, self.kind
+        )
+    }
+}
+
+/// The kind of an [`Issue`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum IssueKind {
+    /// A required attribute is missing.
+    Missing(AttributeType),
+    /// An attribute which needs a value is empty.
+    Empty(AttributeType),
+    /// The value representation differs from the one in the dictionary.
+    UnexpectedVr { expected: VirtualVr, found: VR },
+    /// The number of values does not satisfy the value multiplicity.
+    InvalidVm {
+        expected: ValueMultiplicity,
+        found: u32,
+    },
+    /// A value is not one of the enumerated values of the attribute.
+    NotEnumerated { value: String },
+    /// A value is not one of the defined terms of the attribute.
+    UnknownTerm { value: String },
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IssueKind::Missing(typ) => write!(f, "missing Type {} attribute", typ),
+            IssueKind::Empty(typ) => write!(f, "empty Type {} attribute", typ),
+            IssueKind::UnexpectedVr { expected, found } => {
+                let expected = match expected {
+                    VirtualVr::Xs => "US or SS",
+                    VirtualVr::Ox | VirtualVr::Px => "OB or OW",
+                    VirtualVr::Lt => "US or OW",
+                    other => other.relaxed().to_string(),
+                };
+                write!(f, "value representation {} should be {}", found, expected)
+            }
+            IssueKind::InvalidVm { expected, found } => {
+                write!(
+                    f,
+                    "{} values do not satisfy multiplicity {}",
+                    found, expected
+                )
+            }
+            IssueKind::NotEnumerated { value } => {
+                write!(f, "value {:?} is not an enumerated value", value)
+            }
+            IssueKind::UnknownTerm { value } => {
+                write!(f, "value {:?} is not a defined term", value)
+            }
+        }
+    }
+}
+
+/// Check the given attribute definitions against a data set,
+/// which is either the object itself or a sequence item.
+fn check_attributes<D>(
+    module: &'static ModuleDefinition,
+    path: &mut Vec<AttributeSelectorStep>,
+    definitions: &'static [AttributeDefinition],
+    data_set: &InMemDicomObject<D>,
+    issues: &mut Vec<Issue>,
+) where
+    D: DataDictionary + Clone,
+{
+    for definition in definitions {
+        let mut report = |severity, kind| {
+            issues.push(Issue {
+                severity,
+                module: module.name,
+                selector: selector(path, definition.tag),
+                kind,
+            })
+        };
+
+        let elem = match data_set.get(definition.tag) {
+            Some(elem) => elem,
+            None => {
+                if definition.r#type.is_required() {
+                    report(Severity::Error, IssueKind::Missing(definition.r#type));
+                }
+                continue;
+            }
+        };
+
+        if let Some(expected) = StandardDataDictionary
+            .by_tag(definition.tag)
+            .map(|entry| entry.vr())
+        {
+            if !vr_matches(expected, elem.vr()) {
+                // UN is what implicit VR readers fall back to
+                let severity = if elem.vr() == VR::UN {
+                    Severity::Warning
+                } else {
+                    Severity::Error
+                };
+                report(
+                    severity,
+                    IssueKind::UnexpectedVr {
+                        expected,
+                        found: elem.vr(),
+                    },
+                );
+            }
+        }
+
+        if is_empty(elem) {
+            if definition.r#type.needs_value() {
+                report(Severity::Error, IssueKind::Empty(definition.r#type));
+            }
+            continue;
+        }
+
+        match elem.value() {
+            Value::Primitive(value) => {
+                if has_multiplicity(elem.vr()) && !definition.vm.contains(value.multiplicity()) {
+                    report(
+                        Severity::Error,
+                        IssueKind::InvalidVm {
+                            expected: definition.vm,
+                            found: value.multiplicity(),
+                        },
+                    );
+                }
+                check_terms(definition, value, &mut report);
+            }
+            Value::Sequence(sequence) => {
+                if definition.items.is_empty() {
+                    continue;
+                }
+                for (i, item) in sequence.items().iter().enumerate() {
+                    path.push(AttributeSelectorStep::Nested {
+                        tag: definition.tag,
+                        item: i as u32,
+                    });
+                    check_attributes(module, path, definition.items, item, issues);
+                    path.pop();
+                }
+            }
+            Value::PixelSequence(_) => {}
+        }
+    }
+}
+
+/// Check each value against the enumerated values and defined terms.
+fn check_terms(
+    definition: &AttributeDefinition,
+    value: &PrimitiveValue,
+    report: &mut impl FnMut(Severity, IssueKind),
+) {
+    if definition.enumerated_values.is_empty() && definition.defined_terms.is_empty() {
+        return;
+    }
+    for value in value.to_multi_str().iter() {
+        let value = value.trim_matches([' ', '\0']);
+        if value.is_empty() {
+            continue;
+        }
+        if !definition.enumerated_values.is_empty()
+            && !definition.enumerated_values.contains(&value)
+        {
+            report(
+                Severity::Error,
+                IssueKind::NotEnumerated {
+                    value: value.to_string(),
+                },
+            );
+        } else if !definition.defined_terms.is_empty() && !definition.defined_terms.contains(&value)
+        {
+            report(
+                Severity::Warning,
+                IssueKind::UnknownTerm {
+                    value: value.to_string(),
+                },
+            );
+        }
+    }
+}
+
+fn vr_matches(expected: VirtualVr, found: VR) -> bool {
+    match expected {
+        VirtualVr::Exact(vr) => vr == found,
+        VirtualVr::Xs => matches!(found, VR::US | VR::SS),
+        VirtualVr::Ox | VirtualVr::Px => matches!(found, VR::OB | VR::OW),
+        VirtualVr::Lt => matches!(found, VR::US | VR::OW),
+        other => other.relaxed() == found,
+    }
+}
+
+/// Whether the number of values of this value representation
+/// is subject to the value multiplicity of the attribute.
+/// Binary data and long text are always a single value.
+fn has_multiplicity(vr: VR) -> bool {
+    !matches!(
+        vr,
+        VR::OB
+            | VR::OD
+            | VR::OF
+            | VR::OL
+            | VR::OV
+            | VR::OW
+            | VR::UN
+            | VR::LT
+            | VR::ST
+            | VR::UT
+            | VR::UR
+    )
+}
+
+fn is_empty<D>(elem: &InMemElement<D>) -> bool {
+    match elem.value() {
+        Value::Primitive(PrimitiveValue::Str(s)) => s.trim_matches([' ', '\0']).is_empty(),
+        Value::Primitive(PrimitiveValue::Strs(s)) => {
+            s.iter().all(|s| s.trim_matches([' ', '\0']).is_empty())
+        }
+        Value::Primitive(value) => value.multiplicity() == 0,
+        Value::Sequence(sequence) => sequence.items().is_empty(),
+        Value::PixelSequence(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{dicom_value, DataElement, Length, Tag};
+    use dicom_dictionary_std::uids;
+
+    fn put(obj: &mut InMemDicomObject, tag: Tag, vr: VR, value: impl Into<PrimitiveValue>) {
+        obj.put(DataElement::new(tag, vr, value.into()));
+    }
+
+    /// a secondary capture object with all Type 1 and Type 2 attributes
+    fn secondary_capture() -> InMemDicomObject {
+        let mut obj = InMemDicomObject::new_empty();
+        put(
+            &mut obj,
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            uids::SECONDARY_CAPTURE_IMAGE_STORAGE,
+        );
+        put(&mut obj, tags::SOP_INSTANCE_UID, VR::UI, "2.25.1");
+        put(&mut obj, tags::PATIENT_NAME, VR::PN, "Doe^John");
+        put(&mut obj, tags::PATIENT_ID, VR::LO, "1234");
+        put(
+            &mut obj,
+            tags::PATIENT_BIRTH_DATE,
+            VR::DA,
+            PrimitiveValue::Empty,
+        );
+        put(&mut obj, tags::PATIENT_SEX, VR::CS, "M");
+        put(&mut obj, tags::STUDY_INSTANCE_UID, VR::UI, "2.25.2");
+        put(&mut obj, tags::STUDY_DATE, VR::DA, "20240101");
+        put(&mut obj, tags::STUDY_TIME, VR::TM, "120000");
+        put(
+            &mut obj,
+            tags::REFERRING_PHYSICIAN_NAME,
+            VR::PN,
+            PrimitiveValue::Empty,
+        );
+        put(&mut obj, tags::STUDY_ID, VR::SH, "1");
+        put(
+            &mut obj,
+            tags::ACCESSION_NUMBER,
+            VR::SH,
+            PrimitiveValue::Empty,
+        );
+        put(&mut obj, tags::MODALITY, VR::CS, "OT");
+        put(&mut obj, tags::SERIES_INSTANCE_UID, VR::UI, "2.25.3");
+        put(&mut obj, tags::SERIES_NUMBER, VR::IS, "1");
+        put(&mut obj, tags::CONVERSION_TYPE, VR::CS, "WSD");
+        put(&mut obj, tags::INSTANCE_NUMBER, VR::IS, "1");
+        put(&mut obj, tags::SAMPLES_PER_PIXEL, VR::US, 1_u16);
+        put(
+            &mut obj,
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "MONOCHROME2",
+        );
+        put(&mut obj, tags::ROWS, VR::US, 2_u16);
+        put(&mut obj, tags::COLUMNS, VR::US, 2_u16);
+        put(&mut obj, tags::BITS_ALLOCATED, VR::US, 8_u16);
+        put(&mut obj, tags::BITS_STORED, VR::US, 8_u16);
+        put(&mut obj, tags::HIGH_BIT, VR::US, 7_u16);
+        put(&mut obj, tags::PIXEL_REPRESENTATION, VR::US, 0_u16);
+        put(&mut obj, tags::PIXEL_DATA, VR::OB, vec![0_u8, 1, 2, 3]);
+        obj
+    }
+
+    /// a code sequence item
+    fn code(value: &str, scheme: &str, meaning: &str) -> InMemDicomObject {
+        let mut item = InMemDicomObject::new_empty();
+        put(&mut item, tags::CODE_VALUE, VR::SH, value);
+        put(&mut item, tags::CODING_SCHEME_DESIGNATOR, VR::SH, scheme);
+        put(&mut item, tags::CODE_MEANING, VR::LO, meaning);
+        item
+    }
+
+    /// the kinds of issues found, by attribute
+    fn issue_kinds(report: &ValidationReport) -> Vec<(Tag, IssueKind)> {
+        report
+            .issues()
+            .iter()
+            .map(|issue| (issue.selector.last_tag(), issue.kind.clone()))
+            .collect()
+    }
+
+    /// an MR image with all Type 1 and Type 2 attributes
+    fn mr_image() -> InMemDicomObject {
+        let mut obj = secondary_capture();
+        obj.remove_element(tags::CONVERSION_TYPE);
+        put(
+            &mut obj,
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            uids::MR_IMAGE_STORAGE,
+        );
+        put(&mut obj, tags::MODALITY, VR::CS, "MR");
+        put(&mut obj, tags::FRAME_OF_REFERENCE_UID, VR::UI, "2.25.4");
+        put(
+            &mut obj,
+            tags::POSITION_REFERENCE_INDICATOR,
+            VR::LO,
+            PrimitiveValue::Empty,
+        );
+        put(&mut obj, tags::MANUFACTURER, VR::LO, "ACME");
+        put(
+            &mut obj,
+            tags::PIXEL_SPACING,
+            VR::DS,
+            dicom_value!(Strs, ["0.5", "0.5"]),
+        );
+        put(
+            &mut obj,
+            tags::IMAGE_ORIENTATION_PATIENT,
+            VR::DS,
+            dicom_value!(Strs, ["1", "0", "0", "0", "1", "0"]),
+        );
+        put(
+            &mut obj,
+            tags::IMAGE_POSITION_PATIENT,
+            VR::DS,
+            dicom_value!(Strs, ["0", "0", "0"]),
+        );
+        put(&mut obj, tags::SLICE_THICKNESS, VR::DS, "3");
+        put(
+            &mut obj,
+            tags::IMAGE_TYPE,
+            VR::CS,
+            dicom_value!(Strs, ["ORIGINAL", "PRIMARY"]),
+        );
+        put(&mut obj, tags::BITS_ALLOCATED, VR::US, 16_u16);
+        put(&mut obj, tags::BITS_STORED, VR::US, 12_u16);
+        put(&mut obj, tags::HIGH_BIT, VR::US, 11_u16);
+        put(&mut obj, tags::PIXEL_DATA, VR::OW, vec![0_u8; 8]);
+        put(&mut obj, tags::SCANNING_SEQUENCE, VR::CS, "SE");
+        put(&mut obj, tags::SEQUENCE_VARIANT, VR::CS, "NONE");
+        put(&mut obj, tags::SCAN_OPTIONS, VR::CS, PrimitiveValue::Empty);
+        put(&mut obj, tags::MR_ACQUISITION_TYPE, VR::CS, "2D");
+        put(&mut obj, tags::REPETITION_TIME, VR::DS, "500");
+        put(&mut obj, tags::ECHO_TIME, VR::DS, "20");
+        put(&mut obj, tags::ECHO_TRAIN_LENGTH, VR::IS, "1");
+        obj
+    }
+
+    /// an ultrasound image with all Type 1 and Type 2 attributes
+    fn us_image() -> InMemDicomObject {
+        let mut obj = secondary_capture();
+        obj.remove_element(tags::CONVERSION_TYPE);
+        put(
+            &mut obj,
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            uids::ULTRASOUND_IMAGE_STORAGE,
+        );
+        put(&mut obj, tags::MODALITY, VR::CS, "US");
+        put(&mut obj, tags::MANUFACTURER, VR::LO, "ACME");
+        put(
+            &mut obj,
+            tags::IMAGE_TYPE,
+            VR::CS,
+            dicom_value!(Strs, ["ORIGINAL", "PRIMARY"]),
+        );
+        obj
+    }
+
+    /// a comprehensive SR document with all Type 1 and Type 2 attributes
+    fn sr_document() -> InMemDicomObject {
+        let mut obj = secondary_capture();
+        for tag in [
+            tags::CONVERSION_TYPE,
+            tags::SAMPLES_PER_PIXEL,
+            tags::PHOTOMETRIC_INTERPRETATION,
+            tags::ROWS,
+            tags::COLUMNS,
+            tags::BITS_ALLOCATED,
+            tags::BITS_STORED,
+            tags::HIGH_BIT,
+            tags::PIXEL_REPRESENTATION,
+            tags::PIXEL_DATA,
+        ] {
+            obj.remove_element(tag);
+        }
+        put(
+            &mut obj,
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            uids::COMPREHENSIVE_SR_STORAGE,
+        );
+        put(&mut obj, tags::MODALITY, VR::CS, "SR");
+        put(&mut obj, tags::MANUFACTURER, VR::LO, "ACME");
+        obj.put(DataElement::new(
+            tags::REFERENCED_PERFORMED_PROCEDURE_STEP_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::new(vec![], Length::UNDEFINED),
+        ));
+        put(&mut obj, tags::COMPLETION_FLAG, VR::CS, "COMPLETE");
+        put(&mut obj, tags::VERIFICATION_FLAG, VR::CS, "UNVERIFIED");
+        put(&mut obj, tags::CONTENT_DATE, VR::DA, "20240101");
+        put(&mut obj, tags::CONTENT_TIME, VR::TM, "120000");
+        obj.put(DataElement::new(
+            tags::PERFORMED_PROCEDURE_CODE_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::new(vec![], Length::UNDEFINED),
+        ));
+        put(&mut obj, tags::VALUE_TYPE, VR::CS, "CONTAINER");
+        obj.put(DataElement::new(
+            tags::CONCEPT_NAME_CODE_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::new(
+                vec![code("18748-4", "LN", "Diagnostic Imaging Report")],
+                Length::UNDEFINED,
+            ),
+        ));
+        put(&mut obj, tags::CONTINUITY_OF_CONTENT, VR::CS, "SEPARATE");
+        obj
+    }
+
+    #[test]
+    fn conforming_object() {
+        let report = validate(&secondary_capture()).unwrap();
+        assert_eq!(report.iod().name, "Secondary Capture Image");
+        assert!(report.is_valid(), "{:?}", report.issues());
+        assert_eq!(report.issues(), &[]);
+    }
+
+    #[test]
+    fn unknown_sop_class() {
+        let mut obj = secondary_capture();
+        assert!(matches!(
+            validate(&InMemDicomObject::new_empty()),
+            Err(Error::MissingSopClass)
+        ));
+        put(&mut obj, tags::SOP_CLASS_UID, VR::UI, "1.2.3.4");
+        assert!(matches!(
+            validate(&obj),
+            Err(Error::UnknownSopClass { uid }) if uid == "1.2.3.4"
+        ));
+    }
+
+    #[test]
+    fn attribute_issues() {
+        let mut obj = secondary_capture();
+        obj.remove_element(tags::PATIENT_NAME);
+        put(
+            &mut obj,
+            tags::SERIES_INSTANCE_UID,
+            VR::UI,
+            PrimitiveValue::Empty,
+        );
+        put(&mut obj, tags::PATIENT_SEX, VR::CS, "X");
+        put(&mut obj, tags::CONVERSION_TYPE, VR::CS, "SCANNER");
+        put(&mut obj, tags::ROWS, VR::UL, 2_u32);
+        put(&mut obj, tags::IMAGE_TYPE, VR::CS, "DERIVED");
+        // Type 1C attributes are only checked when present
+        put(
+            &mut obj,
+            tags::PLANAR_CONFIGURATION,
+            VR::US,
+            PrimitiveValue::Empty,
+        );
+
+        let report = validate(&obj).unwrap();
+        let issues: Vec<_> = report
+            .issues()
+            .iter()
+            .map(|issue| {
+                (
+                    issue.severity,
+                    issue.selector.last_tag(),
+                    issue.kind.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (
+                    Severity::Error,
+                    tags::PATIENT_NAME,
+                    IssueKind::Missing(AttributeType::Type2)
+                ),
+                (
+                    Severity::Error,
+                    tags::PATIENT_SEX,
+                    IssueKind::NotEnumerated {
+                        value: "X".to_string()
+                    }
+                ),
+                (
+                    Severity::Error,
+                    tags::SERIES_INSTANCE_UID,
+                    IssueKind::Empty(AttributeType::Type1)
+                ),
+                (
+                    Severity::Warning,
+                    tags::CONVERSION_TYPE,
+                    IssueKind::UnknownTerm {
+                        value: "SCANNER".to_string()
+                    }
+                ),
+                (
+                    Severity::Error,
+                    tags::IMAGE_TYPE,
+                    IssueKind::InvalidVm {
+                        expected: ValueMultiplicity::unbounded(2, 1),
+                        found: 1
+                    }
+                ),
+                (
+                    Severity::Error,
+                    tags::ROWS,
+                    IssueKind::UnexpectedVr {
+                        expected: VirtualVr::Exact(VR::US),
+                        found: VR::UL
+                    }
+                ),
+                (
+                    Severity::Error,
+                    tags::PLANAR_CONFIGURATION,
+                    IssueKind::Empty(AttributeType::Type1C)
+                ),
+            ]
+        );
+        assert!(!report.is_valid());
+        assert_eq!(report.errors().count(), 6);
+        assert_eq!(report.warnings().count(), 1);
+        assert_eq!(
+            report.issues()[0].to_string(),
+            "error: (0010,0010) (Patient): missing Type 2 attribute"
+        );
+    }
+
+    #[test]
+    fn sequence_items_and_optional_modules() {
+        let mut obj = secondary_capture();
+        let mut item = InMemDicomObject::new_empty();
+        put(
+            &mut item,
+            tags::REFERENCED_SOP_CLASS_UID,
+            VR::UI,
+            uids::CT_IMAGE_STORAGE,
+        );
+        obj.put(DataElement::new(
+            tags::REFERENCED_IMAGE_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::new(vec![item], Length::UNDEFINED),
+        ));
+        // the General Equipment module is a user option in this IOD
+        // and is only checked once one of its attributes is present
+        put(&mut obj, tags::INSTITUTION_NAME, VR::LO, "Hospital");
+
+        let report = validate(&obj).unwrap();
+        assert_eq!(report.issues().len(), 2);
+        let issue = &report.issues()[0];
+        assert_eq!(issue.module, "General Equipment");
+        assert_eq!(issue.selector.last_tag(), tags::MANUFACTURER);
+        let issue = &report.issues()[1];
+        assert_eq!(issue.module, "General Image");
+        assert_eq!(
+            issue.selector,
+            AttributeSelector::from((
+                tags::REFERENCED_IMAGE_SEQUENCE,
+                0,
+                tags::REFERENCED_SOP_INSTANCE_UID
+            ))
+        );
+        assert_eq!(issue.kind, IssueKind::Missing(AttributeType::Type1));
+    }
+
+    #[test]
+    fn mr_image_issues() {
+        let report = validate(&mr_image()).unwrap();
+        assert_eq!(report.iod().name, "MR Image");
+        assert_eq!(report.issues(), &[]);
+
+        let mut obj = mr_image();
+        obj.remove_element(tags::SCANNING_SEQUENCE);
+        put(&mut obj, tags::MR_ACQUISITION_TYPE, VR::CS, "4D");
+        put(&mut obj, tags::BITS_ALLOCATED, VR::US, 8_u16);
+        // Contrast/Bolus is checked once one of its attributes is present
+        put(&mut obj, tags::CONTRAST_BOLUS_ROUTE, VR::LO, "IV");
+
+        let report = validate(&obj).unwrap();
+        assert_eq!(
+            issue_kinds(&report),
+            vec![
+                (
+                    tags::CONTRAST_BOLUS_AGENT,
+                    IssueKind::Missing(AttributeType::Type2)
+                ),
+                (
+                    tags::BITS_ALLOCATED,
+                    IssueKind::NotEnumerated {
+                        value: "8".to_string()
+                    }
+                ),
+                (
+                    tags::SCANNING_SEQUENCE,
+                    IssueKind::Missing(AttributeType::Type1)
+                ),
+                (
+                    tags::MR_ACQUISITION_TYPE,
+                    IssueKind::NotEnumerated {
+                        value: "4D".to_string()
+                    }
+                ),
+            ]
+        );
+        assert!(report
+            .issues()
+            .iter()
+            .skip(1)
+            .all(|issue| issue.module == "MR Image"));
+    }
+
+    #[test]
+    fn us_image_issues() {
+        let report = validate(&us_image()).unwrap();
+        assert_eq!(report.iod().name, "US Image");
+        assert_eq!(report.issues(), &[]);
+
+        let mut obj = us_image();
+        put(
+            &mut obj,
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "MONOCHROME1",
+        );
+        put(&mut obj, tags::PIXEL_REPRESENTATION, VR::US, 1_u16);
+        obj.remove_element(tags::IMAGE_TYPE);
+
+        let report = validate(&obj).unwrap();
+        assert_eq!(
+            issue_kinds(&report),
+            vec![
+                (
+                    tags::PHOTOMETRIC_INTERPRETATION,
+                    IssueKind::NotEnumerated {
+                        value: "MONOCHROME1".to_string()
+                    }
+                ),
+                (
+                    tags::PIXEL_REPRESENTATION,
+                    IssueKind::NotEnumerated {
+                        value: "1".to_string()
+                    }
+                ),
+                (tags::IMAGE_TYPE, IssueKind::Missing(AttributeType::Type2)),
+            ]
+        );
+        assert!(report
+            .issues()
+            .iter()
+            .all(|issue| issue.module == "US Image"));
+    }
+
+    #[test]
+    fn sr_document_issues() {
+        let report = validate(&sr_document()).unwrap();
+        assert_eq!(report.iod().name, "Comprehensive SR");
+        assert_eq!(report.issues(), &[]);
+
+        let mut obj = sr_document();
+        put(&mut obj, tags::MODALITY, VR::CS, "OT");
+        obj.remove_element(tags::COMPLETION_FLAG);
+        obj.put(DataElement::new(
+            tags::CONCEPT_NAME_CODE_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::new(vec![InMemDicomObject::new_empty()], Length::UNDEFINED),
+        ));
+
+        let report = validate(&obj).unwrap();
+        assert_eq!(
+            issue_kinds(&report),
+            vec![
+                (
+                    tags::MODALITY,
+                    IssueKind::NotEnumerated {
+                        value: "OT".to_string()
+                    }
+                ),
+                (
+                    tags::COMPLETION_FLAG,
+                    IssueKind::Missing(AttributeType::Type1)
+                ),
+                (tags::CODE_MEANING, IssueKind::Missing(AttributeType::Type1)),
+            ]
+        );
+        let issue = &report.issues()[2];
+        assert_eq!(issue.module, "SR Document Content");
+        assert_eq!(
+            issue.selector,
+            AttributeSelector::from((tags::CONCEPT_NAME_CODE_SEQUENCE, 0, tags::CODE_MEANING))
+        );
+    }
+}
@@ -7,11 +7,13 @@
 use crate::stateful::decode::{DynStatefulDecoder, Error as DecoderError, StatefulDecode};
 use dicom_core::header::{DataElementHeader, Header, Length, SequenceItemHeader};
 use dicom_core::{PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::PrivateDataDictionary;
 use dicom_encoding::text::SpecificCharacterSet;
 use dicom_encoding::transfer_syntax::TransferSyntax;
 use snafu::{Backtrace, ResultExt, Snafu};
 use std::cmp::Ordering;
 use std::io::Read;
+use std::sync::Arc;
 
 use super::{DataToken, SeqTokenType};
 
@@ -97,6 +99,18 @@ struct SeqToken {
     base_offset: u64,
 }
 
+/// A private creator found in the data set being read.
+#[derive(Debug, Clone, PartialEq)]
+struct PrivateCreator {
+    /// The depth of the data set in which the private creator was found,
+    /// as the number of sequence and item delimiters around it.
+    depth: usize,
+    /// The tag of the private creator element `(gggg,00xx)`.
+    tag: Tag,
+    /// The private creator value.
+    creator: String,
+}
+
 /// The value reading strategy for the data set reader.
 ///
 /// It defines how the `PrimitiveValue`s in value tokens are constructed.
@@ -199,6 +213,10 @@ pub struct DataSetReader<S> {
     last_header: Option<DataElementHeader>,
     /// if a peek was taken, this holds the token peeked
     peek: Option<DataToken>,
+    /// the dictionary for resolving the VR of private elements, if any
+    private_dictionary: Option<Arc<PrivateDataDictionary>>,
+    /// the private creators in the data sets currently open
+    private_creators: Vec<PrivateCreator>,
 }
 
 impl<R> DataSetReader<DynStatefulDecoder<R>> {
@@ -264,6 +282,8 @@ impl<R> DataSetReader<DynStatefulDecoder<R>> {
             hard_break: false,
             last_header: None,
             peek: None,
+            private_dictionary: None,
+            private_creators: Vec::new(),
         })
     }
 }
@@ -281,8 +301,25 @@ impl<S> DataSetReader<S> {
             hard_break: false,
             last_header: None,
             peek: None,
+            private_dictionary: None,
+            private_creators: Vec::new(),
         }
     }
+
+    /// Use the given private data dictionary
+    /// to resolve the VR of private data elements
+    /// which would otherwise be read as unknown (UN),
+    /// as happens in implicit VR transfer syntaxes.
+    ///
+    /// The attributes are looked up
+    /// by the value of the private creator reserving their block
+    /// in the same data set.
+    /// Private sequences of defined length are still read as UN,
+    /// since their items cannot be told apart from other UN values.
+    pub fn with_private_dictionary(mut self, dictionary: Arc<PrivateDataDictionary>) -> Self {
+        self.private_dictionary = Some(dictionary);
+        self
+    }
 }
 
 impl<S> Iterator for DataSetReader<S>
@@ -492,6 +529,7 @@ where
                 };
 
                 self.last_header = None;
+                self.record_private_creator(&header, &value);
 
                 // sequences can end after this token
                 self.delimiter_check_pending = true;
@@ -500,7 +538,8 @@ where
             }
         } else {
             // a data element header or item delimiter is expected
-            match self.parser.decode_header() {
+            let header = self.parser.decode_header();
+            match header.map(|header| self.resolve_private_vr(header)) {
                 Ok(DataElementHeader {
                     tag,
                     vr: VR::SQ,
@@ -635,6 +674,14 @@ where
         Ok(self.peek.as_ref())
     }
 
+    /// Retrieve the number of bytes read so far from the source.
+    ///
+    /// When a token was [peeked](Self::peek),
+    /// this includes the bytes of that token.
+    pub fn position(&self) -> u64 {
+        self.parser.position()
+    }
+
     fn update_seq_delimiters(&mut self) -> Result<Option<DataToken>> {
         if let Some(sd) = self.seq_delimiters.last() {
             if let Some(len) = sd.len.get() {
@@ -674,6 +721,9 @@ where
 
     #[inline]
     fn push_sequence_token(&mut self, typ: SeqTokenType, len: Length, pixel_data: bool) {
+        // private creators of previous items at this depth no longer apply
+        let depth = self.seq_delimiters.len();
+        self.private_creators.retain(|c| c.depth <= depth);
         self.seq_delimiters.push(SeqToken {
             typ,
             pixel_data,
@@ -682,6 +732,58 @@ where
         })
     }
 
+    /// Resolve the VR of a private data element of unknown VR
+    /// through the private data dictionary, if one was given.
+    fn resolve_private_vr(&self, mut header: DataElementHeader) -> DataElementHeader {
+        let dictionary = match &self.private_dictionary {
+            Some(dictionary) => dictionary,
+            None => return header,
+        };
+        let tag = header.tag;
+        if header.vr != VR::UN || tag.group() & 1 == 0 || tag.element() < 0x1000 {
+            return header;
+        }
+
+        let creator_tag = Tag(tag.group(), tag.element() >> 8);
+        let depth = self.seq_delimiters.len();
+        let vr = self
+            .private_creators
+            .iter()
+            .rev()
+            .find(|c| c.depth == depth && c.tag == creator_tag)
+            .and_then(|c| dictionary.by_private_tag(&c.creator, tag))
+            .map(|entry| entry.vr.relaxed());
+        if let Some(vr) = vr.filter(|vr| *vr != VR::SQ) {
+            header.vr = vr;
+        }
+        header
+    }
+
+    /// Keep the value of a private creator element
+    /// for resolving private data elements later on.
+    fn record_private_creator(&mut self, header: &DataElementHeader, value: &PrimitiveValue) {
+        let tag = header.tag;
+        if self.private_dictionary.is_none()
+            || tag.group() & 1 == 0
+            || !(0x0010..=0x00FF).contains(&tag.element())
+        {
+            return;
+        }
+
+        let creator = match value {
+            PrimitiveValue::U8(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+            value => value.to_str().into_owned(),
+        };
+        let depth = self.seq_delimiters.len();
+        self.private_creators
+            .retain(|c| c.depth != depth || c.tag != tag);
+        self.private_creators.push(PrivateCreator {
+            depth,
+            tag,
+            creator,
+        });
+    }
+
     fn read_value(&mut self, header: &DataElementHeader) -> Result<PrimitiveValue> {
         match self.options.value_read {
             ValueReadStrategy::Interpreted => self.parser.read_value(header),
@@ -718,11 +820,13 @@ mod tests {
     use dicom_core::header::{DataElementHeader, Length};
     use dicom_core::value::PrimitiveValue;
     use dicom_core::{Tag, VR};
+    use dicom_dictionary_std::PrivateDataDictionary;
     use dicom_encoding::decode::basic::LittleEndianBasicDecoder;
     use dicom_encoding::decode::{
         explicit_le::ExplicitVRLittleEndianDecoder, implicit_le::ImplicitVRLittleEndianDecoder,
     };
     use dicom_encoding::text::SpecificCharacterSet;
+    use std::sync::Arc;
 
     fn validate_read_data_implicit_vr<I>(data: &[u8], ground_truth: I)
     where
@@ -1092,6 +1196,94 @@ mod tests {
         validate_read_data_implicit_vr(DATA, ground_truth);
     }
 
+    #[test]
+    fn read_private_elements_implicit_vr_with_private_dictionary() {
+        #[rustfmt::skip]
+        static DATA: &[u8] = &[
+            0x09, 0x00, 0x10, 0x00, // (0009,0010) PrivateCreator
+            0x06, 0x00, 0x00, 0x00, // length: 6
+            b'A', b'C', b'M', b'E', b' ', b'1',
+            // -- 14 --
+            0x09, 0x00, 0x01, 0x10, // (0009,1001) known to the private dictionary
+            0x02, 0x00, 0x00, 0x00, // length: 2
+            0x0a, 0x00,
+            // -- 24 --
+            0x09, 0x00, 0x02, 0x10, // (0009,1002) unknown
+            0x02, 0x00, 0x00, 0x00, // length: 2
+            0x0b, 0x00,
+            // -- 34 --
+            0x08, 0x00, 0x15, 0x11, // (0008,1115) ReferencedSeriesSequence
+            0xff, 0xff, 0xff, 0xff, // length: undefined
+            // -- 42 --
+            0xfe, 0xff, 0x00, 0xe0, // item begin
+            0xff, 0xff, 0xff, 0xff, // length: undefined
+            // -- 50 --
+            0x09, 0x00, 0x01, 0x10, // (0009,1001) without a private creator in the item
+            0x02, 0x00, 0x00, 0x00, // length: 2
+            0x0c, 0x00,
+            // -- 60 --
+            0xfe, 0xff, 0x0d, 0xe0, // item end
+            0x00, 0x00, 0x00, 0x00,
+            // -- 68 --
+            0xfe, 0xff, 0xdd, 0xe0, // sequence end
+            0x00, 0x00, 0x00, 0x00,
+            // -- 76 --
+        ];
+
+        let dictionary =
+            PrivateDataDictionary::from_dcmtk_str("(0009,\"ACME 1\",01)\tUS\tThing\t1\tPrivateTag")
+                .unwrap();
+
+        let ground_truth = vec![
+            DataToken::ElementHeader(DataElementHeader {
+                tag: Tag(0x0009, 0x0010),
+                vr: VR::LO,
+                len: Length(6),
+            }),
+            DataToken::PrimitiveValue(PrimitiveValue::Strs(["ACME 1".to_owned()].as_ref().into())),
+            DataToken::ElementHeader(DataElementHeader {
+                tag: Tag(0x0009, 0x1001),
+                vr: VR::US,
+                len: Length(2),
+            }),
+            DataToken::PrimitiveValue(PrimitiveValue::U16([10].as_ref().into())),
+            DataToken::ElementHeader(DataElementHeader {
+                tag: Tag(0x0009, 0x1002),
+                vr: VR::UN,
+                len: Length(2),
+            }),
+            DataToken::PrimitiveValue(PrimitiveValue::U8([0x0b, 0x00].as_ref().into())),
+            DataToken::SequenceStart {
+                tag: Tag(0x0008, 0x1115),
+                len: Length::UNDEFINED,
+            },
+            DataToken::ItemStart {
+                len: Length::UNDEFINED,
+            },
+            // the private creator of the outer data set does not apply here
+            DataToken::ElementHeader(DataElementHeader {
+                tag: Tag(0x0009, 0x1001),
+                vr: VR::UN,
+                len: Length(2),
+            }),
+            DataToken::PrimitiveValue(PrimitiveValue::U8([0x0c, 0x00].as_ref().into())),
+            DataToken::ItemEnd,
+            DataToken::SequenceEnd,
+        ];
+
+        let mut cursor = DATA;
+        let parser = StatefulDecoder::new(
+            &mut cursor,
+            ImplicitVRLittleEndianDecoder::default(),
+            LittleEndianBasicDecoder,
+            SpecificCharacterSet::default(),
+        );
+        let dset_reader = DataSetReader::new(parser, Default::default())
+            .with_private_dictionary(Arc::new(dictionary));
+
+        validate_data_set_reader(DATA, dset_reader, ground_truth);
+    }
+
     #[test]
     fn read_encapsulated_pixeldata() {
         #[rustfmt::skip]
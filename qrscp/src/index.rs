@@ -0,0 +1,314 @@
+//! Index of a directory of DICOM files.
+//!
+//! The [`Index`] keeps the attributes of each file in memory
+//! (everything before the pixel data),
+//! so that queries can be answered without reopening the files.
+//! Only the files being retrieved are read in full.
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::{InMemDicomObject, OpenFileOptions};
+use dicom_ul::association::trim_padding;
+use dicom_ul::scp::{matching::matches, RetrieveItem};
+use snafu::{ResultExt, Snafu};
+use tracing::{debug, warn};
+use walkdir::WalkDir;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// Could not read directory
+    ReadDirectory { source: walkdir::Error },
+    #[snafu(display("Could not read DICOM file {}", path.display()))]
+    ReadFile {
+        path: PathBuf,
+        #[snafu(source(from(dicom_object::ReadError, Box::from)))]
+        source: Box<dicom_object::ReadError>,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A level of the query/retrieve information models.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum QueryRetrieveLevel {
+    Patient,
+    Study,
+    Series,
+    Image,
+}
+
+impl QueryRetrieveLevel {
+    /// Read the level of a query or retrieve identifier.
+    pub fn of(identifier: &InMemDicomObject) -> Option<Self> {
+        identifier
+            .get(tags::QUERY_RETRIEVE_LEVEL)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    /// The attribute uniquely identifying the entities at this level.
+    pub fn unique_key(self) -> Tag {
+        match self {
+            QueryRetrieveLevel::Patient => tags::PATIENT_ID,
+            QueryRetrieveLevel::Study => tags::STUDY_INSTANCE_UID,
+            QueryRetrieveLevel::Series => tags::SERIES_INSTANCE_UID,
+            QueryRetrieveLevel::Image => tags::SOP_INSTANCE_UID,
+        }
+    }
+}
+
+impl FromStr for QueryRetrieveLevel {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, ()> {
+        match trim_padding(s).trim_start_matches(' ') {
+            "PATIENT" => Ok(QueryRetrieveLevel::Patient),
+            "STUDY" => Ok(QueryRetrieveLevel::Study),
+            "SERIES" => Ok(QueryRetrieveLevel::Series),
+            "IMAGE" => Ok(QueryRetrieveLevel::Image),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A DICOM file known to the index.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    path: PathBuf,
+    sop_class_uid: String,
+    sop_instance_uid: String,
+    transfer_syntax: String,
+    record: InMemDicomObject,
+}
+
+impl Entry {
+    /// Read the attributes of a DICOM file, up to the pixel data.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let obj = OpenFileOptions::new()
+            .read_until(tags::PIXEL_DATA)
+            .open_file(&path)
+            .context(ReadFileSnafu { path: &path })?;
+        let meta = obj.meta();
+        Ok(Entry {
+            sop_class_uid: trim_padding(&meta.media_storage_sop_class_uid).to_string(),
+            sop_instance_uid: trim_padding(&meta.media_storage_sop_instance_uid).to_string(),
+            transfer_syntax: trim_padding(&meta.transfer_syntax).to_string(),
+            record: obj.into_inner(),
+            path,
+        })
+    }
+
+    /// The path to the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The attributes of the file, up to the pixel data.
+    pub fn record(&self) -> &InMemDicomObject {
+        &self.record
+    }
+
+    /// The value of an attribute of the file as a string,
+    /// without trailing padding.
+    fn key(&self, tag: Tag) -> Option<String> {
+        let value = self.record.get(tag)?.to_str().ok()?;
+        let value = trim_padding(&value);
+        if value.is_empty() {
+            None
+        } else {
+            Some(value.to_string())
+        }
+    }
+
+    /// Prepare the file to be sent in a C-STORE sub-operation.
+    pub fn retrieve_item(&self) -> RetrieveItem {
+        RetrieveItem::new(
+            &self.sop_class_uid,
+            &self.sop_instance_uid,
+            &self.transfer_syntax,
+            &self.path,
+        )
+    }
+}
+
+/// An in-memory index of DICOM files,
+/// answering queries at the patient, study, series and image levels.
+#[derive(Debug, Default, Clone)]
+pub struct Index {
+    entries: Vec<Entry>,
+}
+
+impl Index {
+    /// Create an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index all DICOM files in a directory and its subdirectories.
+    ///
+    /// Files which cannot be read as DICOM files are skipped.
+    pub fn scan(dir: impl AsRef<Path>) -> Result<Self> {
+        let mut index = Index::new();
+        for entry in WalkDir::new(dir).sort_by_file_name() {
+            let entry = entry.context(ReadDirectorySnafu)?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            match Entry::open(entry.path()) {
+                Ok(entry) => index.insert(entry),
+                Err(e) => debug!("Skipping {}: {}", entry.path().display(), e),
+            }
+        }
+        Ok(index)
+    }
+
+    /// Add a file to the index,
+    /// replacing any file with the same SOP instance UID.
+    pub fn insert(&mut self, entry: Entry) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|e| e.sop_instance_uid == entry.sop_instance_uid)
+        {
+            Some(existing) => {
+                warn!(
+                    "{} replaces {} as instance {}",
+                    entry.path.display(),
+                    existing.path.display(),
+                    entry.sop_instance_uid
+                );
+                *existing = entry;
+            }
+            None => self.entries.push(entry),
+        }
+    }
+
+    /// The number of files in the index.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the index has no files.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over the files in the index.
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter()
+    }
+
+    /// Look for the entities at the given level matching the identifier.
+    ///
+    /// Each record holds the attributes of the first file of the entity
+    /// and the attributes computed from all of its files,
+    /// such as _Number of Study Related Instances_
+    /// or _Modalities in Study_.
+    pub fn find(
+        &self,
+        level: QueryRetrieveLevel,
+        identifier: &InMemDicomObject,
+    ) -> Vec<InMemDicomObject> {
+        self.group(level)
+            .into_iter()
+            .map(|entries| record(level, &entries))
+            .filter(|record| matches(identifier, record))
+            .collect()
+    }
+
+    /// Look for the files matching a retrieve identifier.
+    pub fn retrieve(&self, identifier: &InMemDicomObject) -> Vec<&Entry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches(identifier, &entry.record))
+            .collect()
+    }
+
+    /// Group the files by the entity they belong to at the given level,
+    /// in order of appearance.
+    fn group(&self, level: QueryRetrieveLevel) -> Vec<Vec<&Entry>> {
+        let mut groups: Vec<Vec<&Entry>> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for entry in &self.entries {
+            let key = match entry.key(level.unique_key()) {
+                Some(key) => key,
+                None => continue,
+            };
+            match positions.get(&key) {
+                Some(&i) => groups[i].push(entry),
+                None => {
+                    positions.insert(key, groups.len());
+                    groups.push(vec![entry]);
+                }
+            }
+        }
+        groups
+    }
+}
+
+/// Build the record of an entity out of its files.
+fn record(level: QueryRetrieveLevel, entries: &[&Entry]) -> InMemDicomObject {
+    let mut record = entries[0].record.clone();
+    let distinct = |tag| {
+        entries
+            .iter()
+            .filter_map(|e| e.key(tag))
+            .collect::<BTreeSet<_>>()
+    };
+    let number = |tag, n: usize| DataElement::new(tag, VR::IS, PrimitiveValue::from(n.to_string()));
+    let strs = |tag, vr, values: BTreeSet<String>| {
+        DataElement::new(tag, vr, PrimitiveValue::Strs(values.into_iter().collect()))
+    };
+
+    match level {
+        QueryRetrieveLevel::Patient => {
+            record.put(number(
+                tags::NUMBER_OF_PATIENT_RELATED_STUDIES,
+                distinct(tags::STUDY_INSTANCE_UID).len(),
+            ));
+            record.put(number(
+                tags::NUMBER_OF_PATIENT_RELATED_SERIES,
+                distinct(tags::SERIES_INSTANCE_UID).len(),
+            ));
+            record.put(number(
+                tags::NUMBER_OF_PATIENT_RELATED_INSTANCES,
+                entries.len(),
+            ));
+        }
+        QueryRetrieveLevel::Study => {
+            record.put(number(
+                tags::NUMBER_OF_STUDY_RELATED_SERIES,
+                distinct(tags::SERIES_INSTANCE_UID).len(),
+            ));
+            record.put(number(
+                tags::NUMBER_OF_STUDY_RELATED_INSTANCES,
+                entries.len(),
+            ));
+            record.put(strs(
+                tags::MODALITIES_IN_STUDY,
+                VR::CS,
+                distinct(tags::MODALITY),
+            ));
+            record.put(strs(
+                tags::SOP_CLASSES_IN_STUDY,
+                VR::UI,
+                entries.iter().map(|e| e.sop_class_uid.clone()).collect(),
+            ));
+        }
+        QueryRetrieveLevel::Series => {
+            record.put(number(
+                tags::NUMBER_OF_SERIES_RELATED_INSTANCES,
+                entries.len(),
+            ));
+        }
+        QueryRetrieveLevel::Image => {}
+    }
+    record
+}
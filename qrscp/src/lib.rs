@@ -0,0 +1,39 @@
+//! DICOM Query/Retrieve SCP support library
+//!
+//! This library exposes the building blocks of the `dicom-qrscp` tool:
+//! an [`Index`] of the DICOM files in a directory,
+//! and the [`QueryRetrieve`] service,
+//! which answers C-FIND, C-MOVE and C-GET requests
+//! for the patient root and study root information models
+//! when registered in a [`ServiceClassProvider`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_qrscp::{
+//!     Index, QueryRetrieve, FIND_SOP_CLASSES, GET_SOP_CLASSES, MOVE_SOP_CLASSES,
+//! };
+//! use dicom_ul::scp::{AcceptEcho, ServiceClassProvider};
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let index = Index::scan("/var/lib/dicom")?;
+//! let service = QueryRetrieve::new(index)
+//!     .retrieve_ae_title("QR-SCP")
+//!     .with_destination("STORE-SCP", "127.0.0.1:11111");
+//!
+//! let scp = ServiceClassProvider::new()
+//!     .ae_title("QR-SCP")
+//!     .with_echo_handler(AcceptEcho)
+//!     .with_find_handler(FIND_SOP_CLASSES.iter().copied(), service.clone())
+//!     .with_move_handler(MOVE_SOP_CLASSES.iter().copied(), service.clone())
+//!     .with_get_handler(GET_SOP_CLASSES.iter().copied(), service);
+//! scp.serve(std::net::TcpListener::bind("0.0.0.0:1045")?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`ServiceClassProvider`]: dicom_ul::scp::ServiceClassProvider
+pub mod index;
+pub mod service;
+
+pub use index::{Entry, Index, QueryRetrieveLevel};
+pub use service::{QueryRetrieve, FIND_SOP_CLASSES, GET_SOP_CLASSES, MOVE_SOP_CLASSES};
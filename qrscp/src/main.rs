@@ -0,0 +1,188 @@
+use std::{
+    collections::BTreeSet,
+    net::{Ipv4Addr, SocketAddrV4},
+    path::PathBuf,
+};
+
+use clap::{Args, Parser};
+use dicom_qrscp::{Index, QueryRetrieve, FIND_SOP_CLASSES, GET_SOP_CLASSES, MOVE_SOP_CLASSES};
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use dicom_ul::{
+    scp::{AcceptEcho, ServiceClassProvider},
+    tls::{TlsOptions, TlsProfile},
+};
+use tracing::{error, info, Level};
+
+/// DICOM Query/Retrieve SCP
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// Directory of DICOM files to serve
+    dir: PathBuf,
+    /// Verbose mode
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+    /// Application Entity title of this node
+    #[arg(long = "ae-title", default_value = "QR-SCP")]
+    ae_title: String,
+    /// Which port to listen on
+    #[arg(short, default_value = "1045")]
+    port: u16,
+    /// Enforce max pdu length
+    #[arg(short = 's', long = "strict")]
+    strict: bool,
+    /// Only accept native/uncompressed transfer syntaxes
+    #[arg(long)]
+    uncompressed_only: bool,
+    /// Maximum PDU length
+    #[arg(
+        short = 'm',
+        long = "max-pdu-length",
+        default_value = "16384",
+        value_parser(clap::value_parser!(u32).range(4096..=131_072))
+    )]
+    max_pdu_length: u32,
+    /// A C-MOVE destination, as AE_TITLE=HOST:PORT (can be repeated)
+    #[arg(long = "move-destination", value_parser = parse_destination)]
+    move_destination: Vec<(String, String)>,
+    #[command(flatten)]
+    tls: TlsArgs,
+}
+
+/// Secure transport options
+#[derive(Debug, Args)]
+struct TlsArgs {
+    /// Accept associations over TLS only
+    #[arg(long = "tls", requires = "cert", requires = "key")]
+    tls: bool,
+    /// PEM file with the certificate chain of this node
+    #[arg(long = "tls-cert", requires = "tls")]
+    cert: Option<PathBuf>,
+    /// PEM file with the private key of this node
+    #[arg(long = "tls-key", requires = "tls")]
+    key: Option<PathBuf>,
+    /// PEM file with the CA certificates trusted to verify SCU certificates
+    #[arg(long = "tls-ca", requires = "tls")]
+    ca: Option<PathBuf>,
+    /// Require SCUs to present a certificate signed by a trusted CA
+    #[arg(long = "tls-require-client-auth", requires = "ca")]
+    require_client_auth: bool,
+    /// TLS secure transport connection profile
+    /// (bcp195, non-downgrading, or extended) [default: bcp195]
+    #[arg(long = "tls-profile", requires = "tls")]
+    profile: Option<TlsProfile>,
+}
+
+fn parse_destination(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((ae_title, address)) if !ae_title.is_empty() && !address.is_empty() => {
+            Ok((ae_title.to_string(), address.to_string()))
+        }
+        _ => Err(format!("expected AE_TITLE=HOST:PORT, got `{}`", s)),
+    }
+}
+
+fn main() {
+    run(App::parse()).unwrap_or_else(|e| {
+        error!("{}", e);
+        std::process::exit(-2);
+    });
+}
+
+/// Build the service class provider for the given options,
+/// serving queries and retrievals over the index.
+fn build_scp(args: &App, index: Index) -> Result<ServiceClassProvider, Box<dyn std::error::Error>> {
+    let mut scp = ServiceClassProvider::new()
+        .ae_title(&args.ae_title)
+        .strict(args.strict)
+        .max_pdu_length(args.max_pdu_length);
+
+    if args.uncompressed_only {
+        scp = scp
+            .with_transfer_syntax("1.2.840.10008.1.2")
+            .with_transfer_syntax("1.2.840.10008.1.2.1");
+    } else {
+        for ts in TransferSyntaxRegistry.iter() {
+            if !ts.is_unsupported() {
+                scp = scp.with_transfer_syntax(ts.uid());
+            }
+        }
+    };
+
+    if args.tls.tls {
+        let mut tls_options = TlsOptions::new()
+            .profile(args.tls.profile.unwrap_or_default())
+            .require_client_auth(args.tls.require_client_auth);
+        if let (Some(cert), Some(key)) = (&args.tls.cert, &args.tls.key) {
+            tls_options = tls_options.certificate_chain(cert).private_key(key);
+        }
+        if let Some(ca) = &args.tls.ca {
+            tls_options = tls_options.ca_certificates(ca);
+        }
+        scp = scp.tls_config(tls_options.server_config()?);
+    }
+
+    // C-GET sends the instances back over the same association,
+    // so their storage SOP classes must be accepted as well
+    let storage_sop_classes: BTreeSet<_> = index
+        .entries()
+        .map(|entry| entry.retrieve_item().sop_class_uid().to_string())
+        .collect();
+    for uid in storage_sop_classes {
+        scp = scp.with_abstract_syntax(uid);
+    }
+
+    let mut service = QueryRetrieve::new(index).retrieve_ae_title(&args.ae_title);
+    for (ae_title, address) in &args.move_destination {
+        service = service.with_destination(ae_title, address);
+    }
+
+    Ok(scp
+        .with_echo_handler(AcceptEcho)
+        .with_find_handler(FIND_SOP_CLASSES.iter().copied(), service.clone())
+        .with_move_handler(MOVE_SOP_CLASSES.iter().copied(), service.clone())
+        .with_get_handler(GET_SOP_CLASSES.iter().copied(), service))
+}
+
+fn run(args: App) -> Result<(), Box<dyn std::error::Error>> {
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(if args.verbose {
+                Level::DEBUG
+            } else {
+                Level::INFO
+            })
+            .finish(),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!(
+            "Could not set up global logger: {}",
+            snafu::Report::from_error(e)
+        );
+    });
+
+    let index = Index::scan(&args.dir)?;
+    info!(
+        "Indexed {} DICOM files in {}",
+        index.len(),
+        args.dir.display()
+    );
+
+    let listen_addr = SocketAddrV4::new(Ipv4Addr::from(0), args.port);
+    let listener = std::net::TcpListener::bind(listen_addr)?;
+    info!("{} listening on: tcp://{}", &args.ae_title, listen_addr);
+
+    build_scp(&args, index)?.serve(listener);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
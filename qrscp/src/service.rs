@@ -0,0 +1,163 @@
+//! Query/retrieve service handlers backed by an [`Index`].
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::InMemDicomObject;
+use dicom_ul::{
+    dimse::{
+        composite::{CFindRq, CGetRq, CMoveRq},
+        Status,
+    },
+    scp::{
+        matching::response, FindHandler, FindResult, GetHandler, MoveHandler, RetrieveResult,
+        ServiceContext,
+    },
+};
+use tracing::debug;
+
+use crate::index::{Index, QueryRetrieveLevel};
+
+/// The C-FIND information models supported.
+pub static FIND_SOP_CLASSES: &[&str] = &[
+    uids::PATIENT_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND,
+    uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND,
+];
+
+/// The C-MOVE information models supported.
+pub static MOVE_SOP_CLASSES: &[&str] = &[
+    uids::PATIENT_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_MOVE,
+    uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_MOVE,
+];
+
+/// The C-GET information models supported.
+pub static GET_SOP_CLASSES: &[&str] = &[
+    uids::PATIENT_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_GET,
+    uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_GET,
+];
+
+/// A query/retrieve service over an index of DICOM files,
+/// for the patient root and study root information models.
+///
+/// The same value serves as the C-FIND, C-MOVE and C-GET handler,
+/// sharing the index.
+#[derive(Debug, Clone)]
+pub struct QueryRetrieve {
+    index: Arc<Index>,
+    retrieve_ae_title: Option<String>,
+    destinations: HashMap<String, String>,
+}
+
+impl QueryRetrieve {
+    /// Serve queries and retrievals over the given index.
+    pub fn new(index: impl Into<Arc<Index>>) -> Self {
+        QueryRetrieve {
+            index: index.into(),
+            retrieve_ae_title: None,
+            destinations: HashMap::new(),
+        }
+    }
+
+    /// Set the AE title to report as the _Retrieve AE Title_
+    /// of the records found.
+    pub fn retrieve_ae_title(mut self, ae_title: impl Into<String>) -> Self {
+        self.retrieve_ae_title = Some(ae_title.into());
+        self
+    }
+
+    /// Register the network address (`host:port`)
+    /// of a C-MOVE destination.
+    pub fn with_destination(
+        mut self,
+        ae_title: impl Into<String>,
+        address: impl Into<String>,
+    ) -> Self {
+        self.destinations.insert(ae_title.into(), address.into());
+        self
+    }
+
+    /// The index being served.
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    fn retrieve(&self, sop_class_uid: &str, identifier: &InMemDicomObject) -> RetrieveResult {
+        level(sop_class_uid, identifier)?;
+        let items: Vec<_> = self
+            .index
+            .retrieve(identifier)
+            .into_iter()
+            .map(|entry| entry.retrieve_item())
+            .collect();
+        debug!("{} instances to retrieve", items.len());
+        Ok(items)
+    }
+}
+
+/// Check the level of an identifier against the information model.
+fn level(sop_class_uid: &str, identifier: &InMemDicomObject) -> Result<QueryRetrieveLevel, Status> {
+    let level =
+        QueryRetrieveLevel::of(identifier).ok_or(Status::IDENTIFIER_DOES_NOT_MATCH_SOP_CLASS)?;
+    // there are no patients in the study root information model
+    let study_root = [
+        uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND,
+        uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_MOVE,
+        uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_GET,
+    ];
+    if level == QueryRetrieveLevel::Patient && study_root.contains(&sop_class_uid) {
+        return Err(Status::IDENTIFIER_DOES_NOT_MATCH_SOP_CLASS);
+    }
+    Ok(level)
+}
+
+impl FindHandler for QueryRetrieve {
+    fn find(
+        &self,
+        _ctx: &ServiceContext,
+        rq: &CFindRq,
+        identifier: &InMemDicomObject,
+    ) -> FindResult<'_> {
+        let level = level(&rq.affected_sop_class_uid, identifier)?;
+        let records = self.index.find(level, identifier);
+        debug!("{} matches at level {:?}", records.len(), level);
+
+        let identifier = identifier.clone();
+        Ok(Box::new(records.into_iter().map(move |mut record| {
+            if let Some(ae_title) = &self.retrieve_ae_title {
+                record.put(DataElement::new(
+                    tags::RETRIEVE_AE_TITLE,
+                    VR::AE,
+                    PrimitiveValue::from(ae_title.as_str()),
+                ));
+            }
+            response(&identifier, &record)
+        })))
+    }
+}
+
+impl MoveHandler for QueryRetrieve {
+    fn retrieve(
+        &self,
+        _ctx: &ServiceContext,
+        rq: &CMoveRq,
+        identifier: &InMemDicomObject,
+    ) -> RetrieveResult {
+        QueryRetrieve::retrieve(self, &rq.affected_sop_class_uid, identifier)
+    }
+
+    fn destination(&self, ae_title: &str) -> Option<String> {
+        self.destinations.get(ae_title.trim()).cloned()
+    }
+}
+
+impl GetHandler for QueryRetrieve {
+    fn retrieve(
+        &self,
+        _ctx: &ServiceContext,
+        rq: &CGetRq,
+        identifier: &InMemDicomObject,
+    ) -> RetrieveResult {
+        QueryRetrieve::retrieve(self, &rq.affected_sop_class_uid, identifier)
+    }
+}
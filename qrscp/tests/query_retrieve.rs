@@ -0,0 +1,316 @@
+use std::convert::TryFrom;
+use std::path::Path;
+
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
+use dicom_qrscp::{Index, QueryRetrieve, FIND_SOP_CLASSES, GET_SOP_CLASSES, MOVE_SOP_CLASSES};
+use dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN;
+use dicom_ul::{
+    association::{client::ClientAssociationOptions, duplex::duplex, ClientAssociation},
+    dimse::{
+        composite::{CFindRq, CFindRsp, CGetRq, CStoreRsp},
+        Command, DimseMessage, Status,
+    },
+    scp::ServiceClassProvider,
+};
+
+static IMPLICIT_VR_LE: &str = "1.2.840.10008.1.2";
+static SECONDARY_CAPTURE: &str = "1.2.840.10008.5.1.4.1.1.7";
+
+/// Write an instance of the given patient, study and series.
+fn write_instance(dir: &Path, patient: (&str, &str), study: (&str, &str), series: &str, sop: &str) {
+    let (patient_id, patient_name) = patient;
+    let (study_uid, study_date) = study;
+    let obj = InMemDicomObject::from_element_iter([
+        DataElement::new(
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            PrimitiveValue::from(SECONDARY_CAPTURE),
+        ),
+        DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, PrimitiveValue::from(sop)),
+        DataElement::new(tags::STUDY_DATE, VR::DA, PrimitiveValue::from(study_date)),
+        DataElement::new(tags::MODALITY, VR::CS, PrimitiveValue::from("OT")),
+        DataElement::new(
+            tags::PATIENT_NAME,
+            VR::PN,
+            PrimitiveValue::from(patient_name),
+        ),
+        DataElement::new(tags::PATIENT_ID, VR::LO, PrimitiveValue::from(patient_id)),
+        DataElement::new(
+            tags::STUDY_INSTANCE_UID,
+            VR::UI,
+            PrimitiveValue::from(study_uid),
+        ),
+        DataElement::new(
+            tags::SERIES_INSTANCE_UID,
+            VR::UI,
+            PrimitiveValue::from(series),
+        ),
+    ]);
+    let file = obj
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .media_storage_sop_class_uid(SECONDARY_CAPTURE)
+                .media_storage_sop_instance_uid(sop)
+                .transfer_syntax(IMPLICIT_VR_LE),
+        )
+        .unwrap();
+    file.write_to_file(dir.join(format!("{}.dcm", sop)))
+        .unwrap();
+}
+
+/// A directory with two patients, one study each,
+/// the first one with two series.
+fn archive() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let doe = ("P1", "Doe^John");
+    let roe = ("P2", "Roe^Jane");
+    write_instance(
+        dir.path(),
+        doe,
+        ("2.25.10", "20240315"),
+        "2.25.11",
+        "2.25.111",
+    );
+    write_instance(
+        dir.path(),
+        doe,
+        ("2.25.10", "20240315"),
+        "2.25.11",
+        "2.25.112",
+    );
+    write_instance(
+        dir.path(),
+        doe,
+        ("2.25.10", "20240315"),
+        "2.25.12",
+        "2.25.121",
+    );
+    write_instance(
+        dir.path(),
+        roe,
+        ("2.25.20", "20231102"),
+        "2.25.21",
+        "2.25.211",
+    );
+    // not a DICOM file
+    std::fs::write(dir.path().join("README.txt"), "not DICOM").unwrap();
+    dir
+}
+
+fn identifier(level: &str, keys: &[(dicom_core::Tag, VR, &str)]) -> InMemDicomObject {
+    let mut obj = InMemDicomObject::from_element_iter([DataElement::new(
+        tags::QUERY_RETRIEVE_LEVEL,
+        VR::CS,
+        PrimitiveValue::from(level),
+    )]);
+    for &(tag, vr, value) in keys {
+        obj.put(DataElement::new(tag, vr, PrimitiveValue::from(value)));
+    }
+    obj
+}
+
+/// Send a C-FIND request and collect the identifiers found.
+fn find<S: dicom_ul::association::Transport>(
+    association: &mut ClientAssociation<S>,
+    pc_id: u8,
+    sop_class: &str,
+    identifier: &InMemDicomObject,
+) -> (Status, Vec<InMemDicomObject>) {
+    let ts = IMPLICIT_VR_LITTLE_ENDIAN.erased();
+    let msg = DimseMessage::new(pc_id, CFindRq::new(1, sop_class))
+        .with_dataset(identifier, &ts)
+        .unwrap();
+    association.send_dimse(&msg).unwrap();
+    let mut found = Vec::new();
+    loop {
+        let msg = association.receive_dimse().unwrap();
+        let rsp = CFindRsp::try_from(msg.command.clone()).unwrap();
+        if !rsp.status.is_pending() {
+            return (rsp.status, found);
+        }
+        found.push(msg.dataset(&ts).unwrap().unwrap());
+    }
+}
+
+fn value(obj: &InMemDicomObject, tag: dicom_core::Tag) -> String {
+    obj.get(tag)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .trim_end()
+        .to_string()
+}
+
+#[test]
+fn query_and_get_from_indexed_directory() {
+    let dir = archive();
+    let index = Index::scan(dir.path()).unwrap();
+    assert_eq!(index.len(), 4);
+
+    let service = QueryRetrieve::new(index).retrieve_ae_title("QR-SCP");
+    let scp = ServiceClassProvider::new()
+        .ae_title("QR-SCP")
+        .with_abstract_syntax(SECONDARY_CAPTURE)
+        .with_find_handler(FIND_SOP_CLASSES.iter().copied(), service.clone())
+        .with_move_handler(MOVE_SOP_CLASSES.iter().copied(), service.clone())
+        .with_get_handler(GET_SOP_CLASSES.iter().copied(), service);
+
+    let (scu_stream, scp_stream) = duplex(64 * 1024);
+    let scp_handle = std::thread::spawn(move || scp.handle_over(scp_stream));
+
+    let mut association = ClientAssociationOptions::new()
+        .called_ae_title("QR-SCP")
+        .with_presentation_context(
+            uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND,
+            vec![IMPLICIT_VR_LE],
+        )
+        .with_presentation_context(
+            uids::PATIENT_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND,
+            vec![IMPLICIT_VR_LE],
+        )
+        .with_presentation_context(
+            uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_GET,
+            vec![IMPLICIT_VR_LE],
+        )
+        .with_presentation_context(SECONDARY_CAPTURE, vec![IMPLICIT_VR_LE])
+        .with_role_selection(SECONDARY_CAPTURE, false, true)
+        .establish_over(scu_stream)
+        .unwrap();
+    let study_root = uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND;
+    let patient_root = uids::PATIENT_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND;
+
+    // studies by patient name and date range
+    let (status, studies) = find(
+        &mut association,
+        1,
+        study_root,
+        &identifier(
+            "STUDY",
+            &[
+                (tags::PATIENT_NAME, VR::PN, "Doe^*"),
+                (tags::STUDY_DATE, VR::DA, "20240101-"),
+                (tags::STUDY_INSTANCE_UID, VR::UI, ""),
+                (tags::NUMBER_OF_STUDY_RELATED_INSTANCES, VR::IS, ""),
+                (tags::MODALITIES_IN_STUDY, VR::CS, ""),
+                (tags::RETRIEVE_AE_TITLE, VR::AE, ""),
+            ],
+        ),
+    );
+    assert_eq!(status, Status::SUCCESS);
+    assert_eq!(studies.len(), 1);
+    assert_eq!(value(&studies[0], tags::STUDY_INSTANCE_UID), "2.25.10");
+    assert_eq!(
+        value(&studies[0], tags::NUMBER_OF_STUDY_RELATED_INSTANCES),
+        "3"
+    );
+    assert_eq!(value(&studies[0], tags::MODALITIES_IN_STUDY), "OT");
+    assert_eq!(value(&studies[0], tags::RETRIEVE_AE_TITLE), "QR-SCP");
+    assert_eq!(value(&studies[0], tags::QUERY_RETRIEVE_LEVEL), "STUDY");
+    // only the requested keys are returned
+    assert!(studies[0].get(tags::PATIENT_ID).is_none());
+
+    // series of a study
+    let (status, series) = find(
+        &mut association,
+        1,
+        study_root,
+        &identifier(
+            "SERIES",
+            &[
+                (tags::STUDY_INSTANCE_UID, VR::UI, "2.25.10"),
+                (tags::SERIES_INSTANCE_UID, VR::UI, ""),
+                (tags::NUMBER_OF_SERIES_RELATED_INSTANCES, VR::IS, ""),
+            ],
+        ),
+    );
+    assert_eq!(status, Status::SUCCESS);
+    let series: Vec<_> = series
+        .iter()
+        .map(|obj| {
+            (
+                value(obj, tags::SERIES_INSTANCE_UID),
+                value(obj, tags::NUMBER_OF_SERIES_RELATED_INSTANCES),
+            )
+        })
+        .collect();
+    assert_eq!(
+        series,
+        vec![
+            ("2.25.11".to_string(), "2".to_string()),
+            ("2.25.12".to_string(), "1".to_string())
+        ]
+    );
+
+    // patients through the patient root information model
+    let (status, patients) = find(
+        &mut association,
+        3,
+        patient_root,
+        &identifier(
+            "PATIENT",
+            &[
+                (tags::PATIENT_ID, VR::LO, ""),
+                (tags::NUMBER_OF_PATIENT_RELATED_STUDIES, VR::IS, ""),
+            ],
+        ),
+    );
+    assert_eq!(status, Status::SUCCESS);
+    assert_eq!(patients.len(), 2);
+    assert_eq!(value(&patients[1], tags::PATIENT_ID), "P2");
+
+    // there is no patient level in the study root information model
+    let (status, _) = find(
+        &mut association,
+        1,
+        study_root,
+        &identifier("PATIENT", &[(tags::PATIENT_ID, VR::LO, "")]),
+    );
+    assert_eq!(status, Status::IDENTIFIER_DOES_NOT_MATCH_SOP_CLASS);
+
+    // retrieve a series with C-GET
+    let ts = IMPLICIT_VR_LITTLE_ENDIAN.erased();
+    let msg = DimseMessage::new(
+        5,
+        CGetRq::new(2, uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_GET),
+    )
+    .with_dataset(
+        &identifier(
+            "SERIES",
+            &[
+                (tags::STUDY_INSTANCE_UID, VR::UI, "2.25.10"),
+                (tags::SERIES_INSTANCE_UID, VR::UI, "2.25.11"),
+            ],
+        ),
+        &ts,
+    )
+    .unwrap();
+    association.send_dimse(&msg).unwrap();
+    let mut received = Vec::new();
+    let rsp = loop {
+        let msg = association.receive_dimse().unwrap();
+        match msg.command {
+            Command::CStoreRq(ref rq) => {
+                let obj = msg.dataset(&ts).unwrap().unwrap();
+                assert_eq!(value(&obj, tags::PATIENT_ID), "P1");
+                received.push(rq.affected_sop_instance_uid.clone());
+                association
+                    .send_dimse(&DimseMessage::new(
+                        msg.presentation_context_id,
+                        CStoreRsp::new(rq, Status::SUCCESS),
+                    ))
+                    .unwrap();
+            }
+            Command::CGetRsp(rsp) if !rsp.status.is_pending() => break rsp,
+            Command::CGetRsp(_) => {}
+            command => panic!("unexpected {:?}", command),
+        }
+    };
+    assert_eq!(rsp.status, Status::SUCCESS);
+    assert_eq!(rsp.sub_operations.completed, Some(2));
+    assert_eq!(received, vec!["2.25.111", "2.25.112"]);
+
+    association.release().unwrap();
+    scp_handle.join().unwrap().unwrap();
+}
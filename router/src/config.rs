@@ -0,0 +1,322 @@
+//! Router configuration.
+//!
+//! The configuration is usually read from a TOML file
+//! declaring the destinations of the router,
+//! the rules choosing the destinations of each instance,
+//! and how failed transfers are retried:
+//!
+//! ```toml
+//! [retry]
+//! initial-delay = "10s"
+//! max-delay = "10m"
+//! max-attempts = 100
+//!
+//! [destinations.archive]
+//! address = "ARCHIVE@10.0.0.5:104"
+//!
+//! [destinations.ai]
+//! address = "AI-NODE@10.0.0.9:11112"
+//! keep-alive = "2m"
+//!
+//! # everything goes to the archive
+//! [[rules]]
+//! destinations = ["archive"]
+//!
+//! # chest CTs from the scanners also go to the AI node
+//! [[rules]]
+//! destinations = ["ai"]
+//! calling-ae-title = "SCANNER*"
+//! modality = ["CT"]
+//! tags = { StudyDescription = "*CHEST*" }
+//! ```
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+use snafu::{ensure, ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[snafu(display("Could not read configuration file {}", path.display()))]
+    ReadConfig {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Invalid configuration file {}", path.display()))]
+    ParseConfig {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[snafu(display("Invalid destination name {:?}", name))]
+    InvalidDestinationName { name: String },
+    #[snafu(display("Rule #{} refers to unknown destination {:?}", rule, name))]
+    UnknownDestination { rule: usize, name: String },
+    #[snafu(display("Rule #{} has no destinations", rule))]
+    NoDestinations { rule: usize },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The configuration of a router.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RouterConfig {
+    /// How failed transfers are retried
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// The destinations of the router, by name
+    #[serde(default)]
+    pub destinations: BTreeMap<String, DestinationConfig>,
+    /// The routing rules,
+    /// each instance being forwarded to the destinations
+    /// of all rules which it matches
+    #[serde(default)]
+    pub rules: Vec<RuleConfig>,
+}
+
+impl RouterConfig {
+    /// Read the router configuration from a TOML file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).context(ReadConfigSnafu { path })?;
+        let config: RouterConfig = toml::from_str(&text).context(ParseConfigSnafu { path })?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check that all destinations can be kept in the queue
+    /// and that all rules refer to known destinations.
+    pub fn validate(&self) -> Result<()> {
+        for name in self.destinations.keys() {
+            // destination names become directory names in the queue
+            ensure!(
+                !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+                InvalidDestinationNameSnafu { name }
+            );
+        }
+        for (i, rule) in self.rules.iter().enumerate() {
+            ensure!(
+                !rule.destinations.is_empty(),
+                NoDestinationsSnafu { rule: i + 1 }
+            );
+            if let Some(name) = rule
+                .destinations
+                .iter()
+                .find(|name| !self.destinations.contains_key(*name))
+            {
+                return UnknownDestinationSnafu { rule: i + 1, name }.fail();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A node to which instances are forwarded.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct DestinationConfig {
+    /// The address of the node, with its AE title
+    /// (example: `"ARCHIVE@10.0.0.5:104"`)
+    pub address: String,
+    /// The AE title of the router when calling this node
+    /// [default: the AE title of the router]
+    #[serde(default)]
+    pub calling_ae_title: Option<String>,
+    /// How long to keep the association with this node open
+    /// after forwarding, for the instances arriving next
+    /// (`"0s"` to release it right away)
+    #[serde(
+        default = "DestinationConfig::default_keep_alive",
+        with = "humantime_serde"
+    )]
+    pub keep_alive: Duration,
+}
+
+impl DestinationConfig {
+    /// A destination at the given address.
+    pub fn new(address: impl Into<String>) -> Self {
+        DestinationConfig {
+            address: address.into(),
+            calling_ae_title: None,
+            keep_alive: Self::default_keep_alive(),
+        }
+    }
+
+    fn default_keep_alive() -> Duration {
+        Duration::from_secs(30)
+    }
+}
+
+/// A routing rule,
+/// choosing the destinations of the instances matching all of its criteria.
+///
+/// Criteria on AE titles and attribute values
+/// may use the wildcards `*` and `?`.
+/// A rule without criteria matches every instance.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RuleConfig {
+    /// The names of the destinations of the matching instances
+    pub destinations: Vec<String>,
+    /// The AE title of the node which sent the instance
+    #[serde(default)]
+    pub calling_ae_title: Option<String>,
+    /// The AE title which the sending node called
+    #[serde(default)]
+    pub called_ae_title: Option<String>,
+    /// Any of these modalities
+    #[serde(default)]
+    pub modality: Vec<String>,
+    /// Any of these SOP classes
+    #[serde(default)]
+    pub sop_class_uid: Vec<String>,
+    /// Attribute values, keyed by attribute keyword or tag
+    /// (examples: `StudyDescription`, `(0008,1030)`),
+    /// with the matching rules of C-FIND
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+}
+
+/// How failed transfers to a destination are retried.
+///
+/// The delay before the next attempt doubles after each failure,
+/// from the initial delay up to the maximum delay.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RetryPolicy {
+    /// The delay after the first failure
+    #[serde(
+        default = "RetryPolicy::default_initial_delay",
+        with = "humantime_serde"
+    )]
+    pub initial_delay: Duration,
+    /// The longest delay between attempts
+    #[serde(default = "RetryPolicy::default_max_delay", with = "humantime_serde")]
+    pub max_delay: Duration,
+    /// The number of attempts after which an instance is set aside
+    /// as failed [default: retry forever]
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+}
+
+impl RetryPolicy {
+    fn default_initial_delay() -> Duration {
+        Duration::from_secs(10)
+    }
+
+    fn default_max_delay() -> Duration {
+        Duration::from_secs(600)
+    }
+
+    /// The delay before the next attempt
+    /// after the given number of failed attempts.
+    pub fn delay(&self, attempts: u32) -> Duration {
+        let factor = 1_u32
+            .checked_shl(attempts.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+
+    /// Whether an instance should be set aside
+    /// after the given number of failed attempts.
+    pub fn gives_up(&self, attempts: u32) -> bool {
+        self.max_attempts
+            .map(|max_attempts| attempts >= max_attempts)
+            .unwrap_or(false)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            initial_delay: RetryPolicy::default_initial_delay(),
+            max_delay: RetryPolicy::default_max_delay(),
+            max_attempts: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config() {
+        let config: RouterConfig = toml::from_str(
+            r#"
+            [retry]
+            initial-delay = "5s"
+            max-delay = "1m"
+            max-attempts = 3
+
+            [destinations.archive]
+            address = "ARCHIVE@10.0.0.5:104"
+
+            [destinations.ai]
+            address = "AI-NODE@10.0.0.9:11112"
+            keep-alive = "2m"
+
+            [[rules]]
+            destinations = ["archive"]
+            calling-ae-title = "SCANNER*"
+            modality = ["CT", "MR"]
+            tags = { StudyDescription = "*CHEST*" }
+            "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+
+        assert_eq!(config.retry.initial_delay, Duration::from_secs(5));
+        assert_eq!(config.retry.max_attempts, Some(3));
+        assert_eq!(
+            config.destinations["archive"],
+            DestinationConfig::new("ARCHIVE@10.0.0.5:104")
+        );
+        assert_eq!(
+            config.destinations["ai"].keep_alive,
+            Duration::from_secs(120)
+        );
+        assert_eq!(config.rules.len(), 1);
+        assert_eq!(config.rules[0].modality, vec!["CT", "MR"]);
+        assert_eq!(config.rules[0].tags["StudyDescription"], "*CHEST*");
+    }
+
+    #[test]
+    fn reject_unknown_destinations() {
+        let config: RouterConfig = toml::from_str(
+            r#"
+            [[rules]]
+            destinations = ["nowhere"]
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(Error::UnknownDestination { rule: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn retry_delays() {
+        let policy = RetryPolicy {
+            initial_delay: Duration::from_secs(10),
+            max_delay: Duration::from_secs(60),
+            max_attempts: Some(5),
+        };
+        assert_eq!(policy.delay(1), Duration::from_secs(10));
+        assert_eq!(policy.delay(2), Duration::from_secs(20));
+        assert_eq!(policy.delay(3), Duration::from_secs(40));
+        assert_eq!(policy.delay(4), Duration::from_secs(60));
+        assert_eq!(policy.delay(100), Duration::from_secs(60));
+        assert!(!policy.gives_up(4));
+        assert!(policy.gives_up(5));
+    }
+}
@@ -0,0 +1,366 @@
+//! Forwarding of queued instances to their destinations.
+use std::convert::TryFrom;
+use std::net::TcpStream;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
+use dicom_transfer_syntax_registry::{entries, TransferSyntaxRegistry};
+use dicom_ul::{
+    association::{pool::AssociationPool, ClientAssociation, ClientAssociationOptions},
+    dimse::{
+        composite::{CStoreRq, CStoreRsp},
+        DimseMessage, StatusType,
+    },
+};
+use snafu::Report;
+use tracing::{debug, error, info, warn};
+
+use crate::config::{DestinationConfig, RetryPolicy};
+use crate::queue::{self, Entry, Queue};
+
+/// There can be no more than 128 presentation contexts in an association.
+const MAX_PRESENTATION_CONTEXTS: usize = 128;
+
+/// The agent forwarding the instances queued for one destination.
+#[derive(Debug, Clone)]
+pub struct Forwarder {
+    name: String,
+    destination: DestinationConfig,
+    retry: RetryPolicy,
+    poll_interval: Duration,
+    queue: Arc<Queue>,
+    /// the associations with the destination kept open between transfers
+    pool: Arc<AssociationPool>,
+}
+
+/// The outcome of an attempt to forward an instance.
+enum Outcome {
+    /// the destination took the instance
+    Forwarded,
+    /// the instance may be sent again later
+    Failed,
+    /// the association can no longer be used
+    Broken,
+}
+
+impl Forwarder {
+    pub(crate) fn new(
+        name: String,
+        destination: DestinationConfig,
+        calling_ae_title: String,
+        max_pdu_length: u32,
+        retry: RetryPolicy,
+        poll_interval: Duration,
+        queue: Arc<Queue>,
+    ) -> Self {
+        let options = ClientAssociationOptions::new()
+            .calling_ae_title(
+                destination
+                    .calling_ae_title
+                    .clone()
+                    .unwrap_or(calling_ae_title),
+            )
+            .max_pdu_length(max_pdu_length);
+        let max_idle = if destination.keep_alive.is_zero() {
+            0
+        } else {
+            1
+        };
+        let pool = AssociationPool::new(options)
+            .max_idle(max_idle)
+            .idle_timeout(destination.keep_alive);
+        Forwarder {
+            name,
+            destination,
+            retry,
+            poll_interval,
+            queue,
+            pool: Arc::new(pool),
+        }
+    }
+
+    /// The name of the destination.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Keep forwarding instances as they become due,
+    /// waking up early whenever a message arrives through the given channel,
+    /// until the channel is closed.
+    pub fn run(&self, wake: Receiver<()>) {
+        loop {
+            let wait = match self.forward_due() {
+                Ok(_) => self.next_wait(),
+                Err(e) => {
+                    error!("{}", Report::from_error(e));
+                    self.poll_interval
+                }
+            };
+            // wake up in time to release an association left idle
+            let wait = if self.pool.idle() > 0 {
+                wait.min(self.destination.keep_alive)
+            } else {
+                wait
+            };
+            self.pool.release_idle();
+            match wake.recv_timeout(wait) {
+                Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+
+    /// How long to wait for the next instance to become due.
+    fn next_wait(&self) -> Duration {
+        match self.queue.entries(&self.name) {
+            Ok(entries) => entries
+                .first()
+                .map(|entry| {
+                    entry
+                        .next_attempt
+                        .duration_since(SystemTime::now())
+                        .unwrap_or_default()
+                })
+                .unwrap_or(self.poll_interval)
+                .min(self.poll_interval),
+            Err(_) => self.poll_interval,
+        }
+    }
+
+    /// Forward the instances which are due,
+    /// within a single association,
+    /// reusing the association from the previous transfer
+    /// if it is still open and covers these instances.
+    ///
+    /// Returns the number of instances forwarded.
+    pub fn forward_due(&self) -> queue::Result<usize> {
+        let mut entries = self.queue.due(&self.name, SystemTime::now())?;
+        if entries.is_empty() {
+            return Ok(0);
+        }
+
+        // leave the instances which do not fit in the association for later
+        let proposed = propose(&entries);
+        entries.retain(|entry| {
+            proposed.iter().any(|(abstract_syntax, transfer_syntaxes)| {
+                abstract_syntax == &entry.sop_class_uid
+                    && transfer_syntaxes[0] == entry.transfer_syntax
+            })
+        });
+
+        let mut association = match self
+            .pool
+            .get(&self.destination.address, proposed.iter().cloned())
+        {
+            Ok(association) => association,
+            Err(e) => {
+                warn!(
+                    "Could not connect to destination {} ({}): {}",
+                    self.name,
+                    self.destination.address,
+                    Report::from_error(e)
+                );
+                for entry in &entries {
+                    self.retry_later(entry)?;
+                }
+                return Ok(0);
+            }
+        };
+
+        let mut forwarded = 0;
+        let mut pending = entries.iter();
+        let mut message_id: u16 = 1;
+        for entry in pending.by_ref() {
+            match self.send(&mut association, entry, message_id) {
+                Outcome::Forwarded => {
+                    info!("Forwarded {} to {}", entry.sop_instance_uid, self.name);
+                    self.queue.complete(&self.name, &entry.sop_instance_uid)?;
+                    forwarded += 1;
+                }
+                Outcome::Failed => self.retry_later(entry)?,
+                Outcome::Broken => {
+                    self.retry_later(entry)?;
+                    association.discard();
+                    for entry in pending {
+                        self.retry_later(entry)?;
+                    }
+                    return Ok(forwarded);
+                }
+            }
+            message_id = message_id.wrapping_add(1).max(1);
+        }
+        // keep the association for the next transfer
+        drop(association);
+        Ok(forwarded)
+    }
+
+    /// Send a queued instance through the association.
+    fn send(
+        &self,
+        association: &mut ClientAssociation<TcpStream>,
+        entry: &Entry,
+        message_id: u16,
+    ) -> Outcome {
+        let accepted: Vec<_> = association
+            .presentation_contexts()
+            .iter()
+            .filter(|pc| association.abstract_syntax(pc.id) == Some(&*entry.sop_class_uid))
+            .collect();
+        let selected = accepted
+            .iter()
+            .find(|pc| pc.transfer_syntax == entry.transfer_syntax)
+            .or_else(|| {
+                accepted
+                    .iter()
+                    .find(|pc| is_native(&pc.transfer_syntax) && is_native(&entry.transfer_syntax))
+            })
+            .and_then(|pc| {
+                TransferSyntaxRegistry
+                    .get(&pc.transfer_syntax)
+                    .map(|ts| (pc.id, ts))
+            });
+        let Some((pc_id, ts)) = selected else {
+            warn!(
+                "Destination {} did not accept {} in {}",
+                self.name, entry.sop_class_uid, entry.transfer_syntax
+            );
+            return Outcome::Failed;
+        };
+
+        let obj = match self.queue.read(&entry.sop_instance_uid) {
+            Ok(obj) => obj,
+            Err(e) => {
+                warn!("{}", Report::from_error(e));
+                return Outcome::Failed;
+            }
+        };
+        let msg = match DimseMessage::new(
+            pc_id,
+            CStoreRq::new(message_id, &entry.sop_class_uid, &entry.sop_instance_uid),
+        )
+        .with_dataset(&obj, ts)
+        {
+            Ok(msg) => msg,
+            Err(e) => {
+                warn!(
+                    "Could not encode instance {}: {}",
+                    entry.sop_instance_uid,
+                    Report::from_error(e)
+                );
+                return Outcome::Failed;
+            }
+        };
+
+        debug!(
+            "Sending {} to {} in {}",
+            entry.sop_instance_uid,
+            self.name,
+            ts.uid()
+        );
+        if let Err(e) = association.send_dimse(&msg) {
+            warn!("Failed to send C-STORE request: {}", Report::from_error(e));
+            return Outcome::Broken;
+        }
+        let rsp = match association.receive_dimse() {
+            Ok(rsp) => rsp,
+            Err(e) => {
+                warn!(
+                    "Failed to receive C-STORE response: {}",
+                    Report::from_error(e)
+                );
+                return Outcome::Broken;
+            }
+        };
+        match CStoreRsp::try_from(rsp.command) {
+            Ok(rsp) => match rsp.status.status_type() {
+                StatusType::Success => Outcome::Forwarded,
+                StatusType::Warning => {
+                    warn!(
+                        "Destination {} stored {} with warning status {}",
+                        self.name, entry.sop_instance_uid, rsp.status
+                    );
+                    Outcome::Forwarded
+                }
+                _ => {
+                    warn!(
+                        "Destination {} could not store {} (status code {})",
+                        self.name, entry.sop_instance_uid, rsp.status
+                    );
+                    Outcome::Failed
+                }
+            },
+            Err(e) => {
+                warn!("Unexpected C-STORE response: {}", Report::from_error(e));
+                Outcome::Broken
+            }
+        }
+    }
+
+    /// Record a failed attempt to forward an instance,
+    /// setting it aside if the retry policy gives up on it.
+    fn retry_later(&self, entry: &Entry) -> queue::Result<()> {
+        let attempts = entry.attempts + 1;
+        if self.retry.gives_up(attempts) {
+            let path = self.queue.fail(&self.name, &entry.sop_instance_uid)?;
+            error!(
+                "Gave up forwarding {} to {} after {} attempts, kept in {}",
+                entry.sop_instance_uid,
+                self.name,
+                attempts,
+                path.display()
+            );
+        } else {
+            let delay = self.retry.delay(attempts);
+            self.queue.reschedule(&self.name, entry, delay)?;
+            debug!(
+                "Retrying {} to {} in {}s",
+                entry.sop_instance_uid,
+                self.name,
+                delay.as_secs()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Choose the presentation contexts to propose for the given instances:
+/// one per SOP class and transfer syntax,
+/// the transfer syntax of the queued file first,
+/// followed by the uncompressed transfer syntaxes if it can be converted.
+fn propose(entries: &[Entry]) -> Vec<(String, Vec<String>)> {
+    let mut proposed: Vec<(String, Vec<String>)> = Vec::new();
+    for entry in entries {
+        if proposed.iter().any(|(abstract_syntax, transfer_syntaxes)| {
+            abstract_syntax == &entry.sop_class_uid && transfer_syntaxes[0] == entry.transfer_syntax
+        }) {
+            continue;
+        }
+        if proposed.len() == MAX_PRESENTATION_CONTEXTS {
+            break;
+        }
+        let mut transfer_syntaxes = vec![entry.transfer_syntax.clone()];
+        if is_native(&entry.transfer_syntax) {
+            for uid in [
+                entries::EXPLICIT_VR_LITTLE_ENDIAN.uid(),
+                entries::IMPLICIT_VR_LITTLE_ENDIAN.uid(),
+            ] {
+                if !transfer_syntaxes.iter().any(|ts| ts == uid) {
+                    transfer_syntaxes.push(uid.to_string());
+                }
+            }
+        }
+        proposed.push((entry.sop_class_uid.clone(), transfer_syntaxes));
+    }
+    proposed
+}
+
+/// Whether the transfer syntax can be converted to other native encodings
+/// without transcoding pixel data.
+fn is_native(uid: &str) -> bool {
+    TransferSyntaxRegistry
+        .get(uid)
+        .map(|ts| ts.is_codec_free())
+        .unwrap_or(false)
+}
@@ -0,0 +1,265 @@
+//! DICOM store-and-forward router support library
+//!
+//! This library exposes the [`Router`] service of the `dicom-router` tool,
+//! which receives composite instances with C-STORE requests
+//! when registered in a [`ServiceClassProvider`],
+//! and forwards them to other DICOM nodes.
+//!
+//! The destinations of each instance are chosen by routing rules
+//! (see [`config`]),
+//! on the calling and called AE titles
+//! and on the attributes of the instance.
+//! Instances are kept in a persistent [`Queue`]
+//! before their reception is acknowledged,
+//! and remain there until every destination has taken them.
+//! Each destination has its own [`Forwarder`],
+//! which retries failed transfers with an increasing delay
+//! and resumes pending transfers when the router is restarted.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_router::{config::RouterConfig, RouterOptions};
+//! use dicom_ul::scp::{AcceptEcho, ServiceClassProvider};
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let config = RouterConfig::from_file("router.toml")?;
+//! let router = RouterOptions::new(config)
+//!     .ae_title("ROUTER")
+//!     .queue_dir("queue")
+//!     .start()?;
+//! let scp = ServiceClassProvider::new()
+//!     .ae_title("ROUTER")
+//!     .promiscuous(true)
+//!     .with_echo_handler(AcceptEcho)
+//!     .with_default_store_handler(router);
+//! scp.serve(std::net::TcpListener::bind("0.0.0.0:11114")?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`ServiceClassProvider`]: dicom_ul::scp::ServiceClassProvider
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
+use dicom_ul::{
+    association::trim_padding,
+    dimse::{composite::CStoreRq, Status},
+    scp::{ServiceContext, StoreHandler},
+};
+use snafu::{ResultExt, Snafu};
+use tracing::{info, warn};
+
+pub mod config;
+pub mod forward;
+pub mod queue;
+pub mod rules;
+
+pub use forward::Forwarder;
+pub use queue::Queue;
+
+use config::RouterConfig;
+use rules::Rule;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// Invalid router configuration
+    Config { source: config::Error },
+    /// Invalid routing rule
+    Rule { source: rules::Error },
+    /// Could not open the queue
+    OpenQueue { source: queue::Error },
+    /// Could not start forwarder thread
+    SpawnForwarder { source: std::io::Error },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Options for starting a [`Router`].
+#[derive(Debug, Clone)]
+pub struct RouterOptions {
+    config: RouterConfig,
+    ae_title: String,
+    queue_dir: PathBuf,
+    max_pdu_length: u32,
+    poll_interval: Duration,
+}
+
+impl RouterOptions {
+    /// Prepare a router with the given configuration.
+    pub fn new(config: RouterConfig) -> Self {
+        RouterOptions {
+            config,
+            ae_title: "ROUTER".to_string(),
+            queue_dir: PathBuf::from("queue"),
+            max_pdu_length: 16384,
+            poll_interval: Duration::from_secs(30),
+        }
+    }
+
+    /// Define the AE title of the router when calling destinations,
+    /// unless overridden by the destination.
+    pub fn ae_title(mut self, ae_title: impl Into<String>) -> Self {
+        self.ae_title = ae_title.into();
+        self
+    }
+
+    /// Define the directory of the persistent queue.
+    pub fn queue_dir(mut self, queue_dir: impl Into<PathBuf>) -> Self {
+        self.queue_dir = queue_dir.into();
+        self
+    }
+
+    /// Define the maximum PDU length proposed to destinations.
+    pub fn max_pdu_length(mut self, value: u32) -> Self {
+        self.max_pdu_length = value;
+        self
+    }
+
+    /// Define how often the queue is checked
+    /// for instances which became due.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Open the queue, recovering it from a previous run,
+    /// and start forwarding instances to each destination
+    /// in a thread of its own.
+    ///
+    /// The forwarder threads stop once the router is dropped.
+    pub fn start(self) -> Result<Router> {
+        self.config.validate().context(ConfigSnafu)?;
+        let rules = self
+            .config
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| Rule::new(i + 1, rule))
+            .collect::<Result<Vec<_>, _>>()
+            .context(RuleSnafu)?;
+
+        let queue = Arc::new(Queue::open(&self.queue_dir).context(OpenQueueSnafu)?);
+        for (destination, count) in queue.recover().context(OpenQueueSnafu)? {
+            if count > 0 {
+                info!("{} instances waiting for {}", count, destination);
+            }
+        }
+
+        let mut wake = HashMap::new();
+        for (name, destination) in &self.config.destinations {
+            let forwarder = Forwarder::new(
+                name.clone(),
+                destination.clone(),
+                self.ae_title.clone(),
+                self.max_pdu_length,
+                self.config.retry.clone(),
+                self.poll_interval,
+                Arc::clone(&queue),
+            );
+            // a single pending message is enough to wake up the forwarder
+            let (sender, receiver) = sync_channel(1);
+            std::thread::Builder::new()
+                .name(format!("forward-{}", name))
+                .spawn(move || forwarder.run(receiver))
+                .context(SpawnForwarderSnafu)?;
+            wake.insert(name.clone(), sender);
+        }
+
+        Ok(Router { rules, queue, wake })
+    }
+}
+
+/// Storage handler which queues every incoming instance
+/// for the destinations chosen by the routing rules.
+///
+/// Instances matching no rule are refused.
+#[derive(Debug)]
+pub struct Router {
+    rules: Vec<Rule>,
+    queue: Arc<Queue>,
+    /// wakes up the forwarder of each destination
+    wake: HashMap<String, SyncSender<()>>,
+}
+
+impl Router {
+    /// The persistent queue of the router.
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    /// The names of the destinations of an instance
+    /// received from the given AE title,
+    /// which called the given AE title.
+    pub fn route(
+        &self,
+        calling_ae_title: &str,
+        called_ae_title: &str,
+        dataset: &InMemDicomObject,
+    ) -> Vec<String> {
+        let mut destinations: Vec<String> = Vec::new();
+        for rule in &self.rules {
+            if rule.matches(calling_ae_title, called_ae_title, dataset) {
+                for destination in rule.destinations() {
+                    if !destinations.contains(destination) {
+                        destinations.push(destination.clone());
+                    }
+                }
+            }
+        }
+        destinations
+    }
+
+    fn enqueue(
+        &self,
+        ctx: &ServiceContext,
+        rq: &CStoreRq,
+        dataset: InMemDicomObject,
+        destinations: &[String],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let sop_instance_uid = trim_padding(&rq.affected_sop_instance_uid);
+        let file = dataset.with_meta(
+            FileMetaTableBuilder::new()
+                .media_storage_sop_class_uid(trim_padding(&rq.affected_sop_class_uid))
+                .media_storage_sop_instance_uid(sop_instance_uid)
+                .transfer_syntax(ctx.transfer_syntax().uid()),
+        )?;
+        self.queue.push(sop_instance_uid, &file, destinations)?;
+        Ok(())
+    }
+}
+
+impl StoreHandler for Router {
+    fn store(&self, ctx: &ServiceContext, rq: &CStoreRq, dataset: InMemDicomObject) -> Status {
+        let destinations = self.route(ctx.calling_ae_title(), ctx.called_ae_title(), &dataset);
+        if destinations.is_empty() {
+            warn!(
+                "No route for {} from {}",
+                rq.affected_sop_instance_uid,
+                ctx.calling_ae_title()
+            );
+            return Status::UNABLE_TO_PROCESS;
+        }
+
+        // only acknowledge the instance once it is safely queued
+        if let Err(e) = self.enqueue(ctx, rq, dataset, &destinations) {
+            warn!("Could not queue {}: {}", rq.affected_sop_instance_uid, e);
+            return Status::OUT_OF_RESOURCES;
+        }
+        info!(
+            "Queued {} for {}",
+            rq.affected_sop_instance_uid,
+            destinations.join(", ")
+        );
+        for destination in &destinations {
+            if let Some(wake) = self.wake.get(destination) {
+                let _ = wake.try_send(());
+            }
+        }
+        Status::SUCCESS
+    }
+}
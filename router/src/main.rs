@@ -0,0 +1,166 @@
+use std::{
+    net::{Ipv4Addr, SocketAddrV4},
+    path::PathBuf,
+    time::Duration,
+};
+
+use clap::{Args, Parser};
+use dicom_router::{config::RouterConfig, Router, RouterOptions};
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use dicom_ul::{
+    scp::{AcceptEcho, ServiceClassProvider},
+    tls::{TlsOptions, TlsProfile},
+};
+use tracing::{error, info, Level};
+
+/// DICOM store-and-forward router
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// TOML file with the destinations and routing rules
+    config: PathBuf,
+    /// Directory of the persistent forwarding queue
+    #[arg(short = 'q', long = "queue-dir", default_value = "queue")]
+    queue_dir: PathBuf,
+    /// Verbose mode
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+    /// Application Entity title of this node
+    #[arg(long = "ae-title", default_value = "ROUTER")]
+    ae_title: String,
+    /// Which port to listen on
+    #[arg(short, default_value = "11114")]
+    port: u16,
+    /// Enforce max pdu length
+    #[arg(short = 's', long = "strict")]
+    strict: bool,
+    /// Maximum PDU length
+    #[arg(
+        short = 'm',
+        long = "max-pdu-length",
+        default_value = "16384",
+        value_parser(clap::value_parser!(u32).range(4096..=131_072))
+    )]
+    max_pdu_length: u32,
+    /// How often to check the queue for instances to retry, in seconds
+    #[arg(long = "poll-interval", default_value = "30")]
+    poll_interval: u64,
+    #[command(flatten)]
+    tls: TlsArgs,
+}
+
+/// Secure transport options
+#[derive(Debug, Args)]
+struct TlsArgs {
+    /// Accept associations over TLS only
+    #[arg(long = "tls", requires = "cert", requires = "key")]
+    tls: bool,
+    /// PEM file with the certificate chain of this node
+    #[arg(long = "tls-cert", requires = "tls")]
+    cert: Option<PathBuf>,
+    /// PEM file with the private key of this node
+    #[arg(long = "tls-key", requires = "tls")]
+    key: Option<PathBuf>,
+    /// PEM file with the CA certificates trusted to verify SCU certificates
+    #[arg(long = "tls-ca", requires = "tls")]
+    ca: Option<PathBuf>,
+    /// Require SCUs to present a certificate signed by a trusted CA
+    #[arg(long = "tls-require-client-auth", requires = "ca")]
+    require_client_auth: bool,
+    /// TLS secure transport connection profile
+    /// (bcp195, non-downgrading, or extended) [default: bcp195]
+    #[arg(long = "tls-profile", requires = "tls")]
+    profile: Option<TlsProfile>,
+}
+
+fn main() {
+    run(App::parse()).unwrap_or_else(|e| {
+        error!("{}", e);
+        std::process::exit(-2);
+    });
+}
+
+/// Build the service class provider for the given options,
+/// queueing every received instance in the router.
+fn build_scp(
+    args: &App,
+    router: Router,
+) -> Result<ServiceClassProvider, Box<dyn std::error::Error>> {
+    let mut scp = ServiceClassProvider::new()
+        .ae_title(&args.ae_title)
+        .strict(args.strict)
+        .max_pdu_length(args.max_pdu_length)
+        .promiscuous(true);
+
+    for ts in TransferSyntaxRegistry.iter() {
+        if !ts.is_unsupported() {
+            scp = scp.with_transfer_syntax(ts.uid());
+        }
+    }
+
+    if args.tls.tls {
+        let mut tls_options = TlsOptions::new()
+            .profile(args.tls.profile.unwrap_or_default())
+            .require_client_auth(args.tls.require_client_auth);
+        if let (Some(cert), Some(key)) = (&args.tls.cert, &args.tls.key) {
+            tls_options = tls_options.certificate_chain(cert).private_key(key);
+        }
+        if let Some(ca) = &args.tls.ca {
+            tls_options = tls_options.ca_certificates(ca);
+        }
+        scp = scp.tls_config(tls_options.server_config()?);
+    }
+
+    Ok(scp
+        .with_echo_handler(AcceptEcho)
+        .with_default_store_handler(router))
+}
+
+fn run(args: App) -> Result<(), Box<dyn std::error::Error>> {
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(if args.verbose {
+                Level::DEBUG
+            } else {
+                Level::INFO
+            })
+            .finish(),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!(
+            "Could not set up global logger: {}",
+            snafu::Report::from_error(e)
+        );
+    });
+
+    let config = RouterConfig::from_file(&args.config)?;
+    info!(
+        "{} destinations, {} routing rules",
+        config.destinations.len(),
+        config.rules.len()
+    );
+    let router = RouterOptions::new(config)
+        .ae_title(&args.ae_title)
+        .queue_dir(&args.queue_dir)
+        .max_pdu_length(args.max_pdu_length)
+        .poll_interval(Duration::from_secs(args.poll_interval.max(1)))
+        .start()?;
+
+    let listen_addr = SocketAddrV4::new(Ipv4Addr::from(0), args.port);
+    let listener = std::net::TcpListener::bind(listen_addr)?;
+    info!("{} listening on: tcp://{}", &args.ae_title, listen_addr);
+
+    build_scp(&args, router)?.serve(listener);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
@@ -0,0 +1,379 @@
+//! Persistent forwarding queue.
+//!
+//! The queue lives in a directory with the following layout:
+//!
+//! - `objects/«SOP instance UID».dcm`:
+//!   the received instances, kept once for all destinations;
+//! - `pending/«destination»/«SOP instance UID»`:
+//!   an entry for each instance still to be forwarded to a destination,
+//!   holding the number of failed attempts,
+//!   the time of the next attempt,
+//!   and the SOP class and transfer syntax of the instance;
+//! - `failed/«destination»/«SOP instance UID».dcm`:
+//!   the instances which could not be forwarded
+//!   within the maximum number of attempts.
+//!
+//! Files are written to a temporary file first
+//! and synchronized to disk before they are renamed into place,
+//! so that the queue remains consistent if the router stops abruptly.
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use dicom_object::{open_file, DefaultDicomObject, FileDicomObject, InMemDicomObject};
+use dicom_ul::association::trim_padding;
+use snafu::{ensure, ResultExt, Snafu};
+use tracing::warn;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[snafu(display("Invalid SOP instance UID {:?}", uid))]
+    InvalidUid { uid: String },
+    #[snafu(display("Could not access queue directory {}", path.display()))]
+    Directory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not write queue file {}", path.display()))]
+    WriteFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not write instance to {}", path.display()))]
+    WriteObject {
+        path: PathBuf,
+        #[snafu(source(from(dicom_object::WriteError, Box::from)))]
+        source: Box<dicom_object::WriteError>,
+    },
+    #[snafu(display("Could not read queued instance {}", path.display()))]
+    ReadObject {
+        path: PathBuf,
+        #[snafu(source(from(dicom_object::ReadError, Box::from)))]
+        source: Box<dicom_object::ReadError>,
+    },
+    #[snafu(display("Could not remove queue file {}", path.display()))]
+    RemoveFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An instance waiting to be forwarded to a destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// the SOP instance UID of the instance
+    pub sop_instance_uid: String,
+    /// the SOP class UID of the instance
+    pub sop_class_uid: String,
+    /// the transfer syntax UID of the queued file
+    pub transfer_syntax: String,
+    /// the number of failed attempts to forward the instance
+    pub attempts: u32,
+    /// the earliest time of the next attempt
+    pub next_attempt: SystemTime,
+}
+
+/// A directory of instances waiting to be forwarded.
+#[derive(Debug)]
+pub struct Queue {
+    dir: PathBuf,
+    /// serializes the addition and removal of entries,
+    /// so that instances are only removed once no entry refers to them
+    lock: Mutex<()>,
+}
+
+impl Queue {
+    /// Open the queue in the given directory,
+    /// creating it if necessary.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let queue = Queue {
+            dir: dir.into(),
+            lock: Mutex::new(()),
+        };
+        for path in [queue.objects_dir(), queue.dir.join("pending")] {
+            std::fs::create_dir_all(&path).context(DirectorySnafu { path })?;
+        }
+        Ok(queue)
+    }
+
+    /// The directory of the queue.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn objects_dir(&self) -> PathBuf {
+        self.dir.join("objects")
+    }
+
+    fn pending_dir(&self, destination: &str) -> PathBuf {
+        self.dir.join("pending").join(destination)
+    }
+
+    fn failed_dir(&self, destination: &str) -> PathBuf {
+        self.dir.join("failed").join(destination)
+    }
+
+    /// The path to the file of a queued instance.
+    pub fn object_path(&self, sop_instance_uid: &str) -> Result<PathBuf> {
+        check_uid(sop_instance_uid)?;
+        Ok(self.objects_dir().join(format!("{}.dcm", sop_instance_uid)))
+    }
+
+    /// Keep an instance in the queue,
+    /// to be forwarded to each of the given destinations.
+    ///
+    /// Once this returns, the instance survives a restart of the router.
+    pub fn push(
+        &self,
+        sop_instance_uid: &str,
+        file: &FileDicomObject<InMemDicomObject>,
+        destinations: &[String],
+    ) -> Result<()> {
+        let path = self.object_path(sop_instance_uid)?;
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        write_atomically(&path, |out| {
+            file.write_all(&mut *out)
+                .context(WriteObjectSnafu { path: &path })
+        })?;
+
+        let entry = Entry {
+            sop_instance_uid: sop_instance_uid.to_string(),
+            sop_class_uid: trim_padding(file.meta().media_storage_sop_class_uid()).to_string(),
+            transfer_syntax: trim_padding(file.meta().transfer_syntax()).to_string(),
+            attempts: 0,
+            next_attempt: SystemTime::now(),
+        };
+        for destination in destinations {
+            let dir = self.pending_dir(destination);
+            std::fs::create_dir_all(&dir).context(DirectorySnafu { path: &dir })?;
+            self.write_entry(destination, &entry)?;
+        }
+        Ok(())
+    }
+
+    /// The instances waiting to be forwarded to a destination,
+    /// in the order of their next attempt.
+    pub fn entries(&self, destination: &str) -> Result<Vec<Entry>> {
+        let dir = self.pending_dir(destination);
+        let read_dir = match std::fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).context(DirectorySnafu { path: dir }),
+        };
+        let mut entries = Vec::new();
+        for dir_entry in read_dir {
+            let path = dir_entry.context(DirectorySnafu { path: &dir })?.path();
+            let Some(sop_instance_uid) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if check_uid(sop_instance_uid).is_err() {
+                // leftover temporary files
+                continue;
+            }
+            match std::fs::read_to_string(&path) {
+                Ok(text) => match parse_entry(sop_instance_uid, &text) {
+                    Some(entry) => entries.push(entry),
+                    None => warn!("Ignoring invalid queue entry {}", path.display()),
+                },
+                // the entry was removed in the meantime
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).context(DirectorySnafu { path }),
+            }
+        }
+        entries.sort_by(|a, b| {
+            (a.next_attempt, &a.sop_instance_uid).cmp(&(b.next_attempt, &b.sop_instance_uid))
+        });
+        Ok(entries)
+    }
+
+    /// The instances due to be forwarded to a destination by the given time.
+    pub fn due(&self, destination: &str, now: SystemTime) -> Result<Vec<Entry>> {
+        let mut entries = self.entries(destination)?;
+        entries.retain(|entry| entry.next_attempt <= now);
+        Ok(entries)
+    }
+
+    /// Read a queued instance.
+    pub fn read(&self, sop_instance_uid: &str) -> Result<DefaultDicomObject> {
+        let path = self.object_path(sop_instance_uid)?;
+        open_file(&path).context(ReadObjectSnafu { path })
+    }
+
+    /// Record that an instance was forwarded to a destination,
+    /// removing it from the queue if no other destination is waiting for it.
+    pub fn complete(&self, destination: &str, sop_instance_uid: &str) -> Result<()> {
+        check_uid(sop_instance_uid)?;
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        remove_if_exists(&self.pending_dir(destination).join(sop_instance_uid))?;
+        self.remove_unreferenced(sop_instance_uid)
+    }
+
+    /// Record a failed attempt to forward an instance to a destination,
+    /// scheduling the next attempt after the given delay.
+    pub fn reschedule(&self, destination: &str, entry: &Entry, delay: Duration) -> Result<Entry> {
+        let entry = Entry {
+            attempts: entry.attempts + 1,
+            next_attempt: SystemTime::now() + delay,
+            ..entry.clone()
+        };
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        // do not bring back entries completed in the meantime
+        if self
+            .pending_dir(destination)
+            .join(&entry.sop_instance_uid)
+            .exists()
+        {
+            self.write_entry(destination, &entry)?;
+        }
+        Ok(entry)
+    }
+
+    /// Give up forwarding an instance to a destination,
+    /// setting aside a copy of the instance among the failed ones.
+    pub fn fail(&self, destination: &str, sop_instance_uid: &str) -> Result<PathBuf> {
+        let object_path = self.object_path(sop_instance_uid)?;
+        let dir = self.failed_dir(destination);
+        std::fs::create_dir_all(&dir).context(DirectorySnafu { path: &dir })?;
+        let failed_path = dir.join(format!("{}.dcm", sop_instance_uid));
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        std::fs::copy(&object_path, &failed_path).context(WriteFileSnafu { path: &failed_path })?;
+        remove_if_exists(&self.pending_dir(destination).join(sop_instance_uid))?;
+        self.remove_unreferenced(sop_instance_uid)?;
+        Ok(failed_path)
+    }
+
+    /// Bring the queue back to a consistent state after a restart:
+    /// entries of instances which are no longer kept are removed,
+    /// as well as instances which no entry refers to
+    /// and leftover temporary files.
+    ///
+    /// Returns the number of entries waiting for each destination.
+    pub fn recover(&self) -> Result<Vec<(String, usize)>> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let pending = self.dir.join("pending");
+        let mut referenced = std::collections::HashSet::new();
+        let mut counts = Vec::new();
+        for dir_entry in std::fs::read_dir(&pending).context(DirectorySnafu { path: &pending })? {
+            let dir = dir_entry.context(DirectorySnafu { path: &pending })?.path();
+            let Some(destination) = dir.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let mut count = 0;
+            for dir_entry in std::fs::read_dir(&dir).context(DirectorySnafu { path: &dir })? {
+                let path = dir_entry.context(DirectorySnafu { path: &dir })?.path();
+                let name = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or_default()
+                    .to_string();
+                if check_uid(&name).is_err() || !self.object_path(&name)?.exists() {
+                    remove_if_exists(&path)?;
+                } else {
+                    count += 1;
+                    referenced.insert(name);
+                }
+            }
+            counts.push((destination.to_string(), count));
+        }
+
+        let objects = self.objects_dir();
+        for dir_entry in std::fs::read_dir(&objects).context(DirectorySnafu { path: &objects })? {
+            let path = dir_entry.context(DirectorySnafu { path: &objects })?.path();
+            let is_referenced = path.extension().map(|ext| ext == "dcm").unwrap_or(false)
+                && path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .map(|uid| referenced.contains(uid))
+                    .unwrap_or(false);
+            if !is_referenced {
+                remove_if_exists(&path)?;
+            }
+        }
+        counts.sort();
+        Ok(counts)
+    }
+
+    /// Remove a queued instance if no destination is waiting for it.
+    fn remove_unreferenced(&self, sop_instance_uid: &str) -> Result<()> {
+        let pending = self.dir.join("pending");
+        for dir_entry in std::fs::read_dir(&pending).context(DirectorySnafu { path: &pending })? {
+            let dir = dir_entry.context(DirectorySnafu { path: &pending })?.path();
+            if dir.join(sop_instance_uid).exists() {
+                return Ok(());
+            }
+        }
+        remove_if_exists(&self.object_path(sop_instance_uid)?)
+    }
+
+    fn write_entry(&self, destination: &str, entry: &Entry) -> Result<()> {
+        let path = self.pending_dir(destination).join(&entry.sop_instance_uid);
+        let next_attempt = entry
+            .next_attempt
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        write_atomically(&path, |out| {
+            writeln!(
+                out,
+                "{} {} {} {}",
+                entry.attempts, next_attempt, entry.sop_class_uid, entry.transfer_syntax
+            )
+            .context(WriteFileSnafu { path: &path })
+        })
+    }
+}
+
+/// Check that a SOP instance UID can be used as a file name.
+fn check_uid(sop_instance_uid: &str) -> Result<()> {
+    ensure!(
+        !sop_instance_uid.is_empty()
+            && sop_instance_uid.len() <= 64
+            && sop_instance_uid
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == '.'),
+        InvalidUidSnafu {
+            uid: sop_instance_uid
+        }
+    );
+    Ok(())
+}
+
+fn parse_entry(sop_instance_uid: &str, text: &str) -> Option<Entry> {
+    let mut parts = text.split_whitespace();
+    let attempts = parts.next()?.parse().ok()?;
+    let next_attempt = UNIX_EPOCH + Duration::from_millis(parts.next()?.parse().ok()?);
+    Some(Entry {
+        sop_instance_uid: sop_instance_uid.to_string(),
+        sop_class_uid: parts.next()?.to_string(),
+        transfer_syntax: parts.next()?.to_string(),
+        attempts,
+        next_attempt,
+    })
+}
+
+/// Write a file through a temporary file,
+/// synchronized to disk before it replaces the file at the given path.
+fn write_atomically(path: &Path, write: impl FnOnce(&mut File) -> Result<()>) -> Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    let mut file = File::create(&tmp_path).context(WriteFileSnafu { path: &tmp_path })?;
+    write(&mut file)?;
+    file.sync_all()
+        .context(WriteFileSnafu { path: &tmp_path })?;
+    std::fs::rename(&tmp_path, path).context(WriteFileSnafu { path })
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).context(RemoveFileSnafu { path })
+        }
+        _ => Ok(()),
+    }
+}
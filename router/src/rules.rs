@@ -0,0 +1,199 @@
+//! Routing rules.
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::tags;
+use dicom_findscu::query::parse_queries;
+use dicom_object::InMemDicomObject;
+use dicom_ul::scp::matching::matches;
+use snafu::{ResultExt, Snafu, Whatever};
+
+use crate::config::RuleConfig;
+
+#[derive(Debug, Snafu)]
+#[snafu(display("Invalid attribute criteria in rule #{}", rule))]
+pub struct Error {
+    rule: usize,
+    source: Whatever,
+}
+
+/// A routing rule ready to be checked against incoming instances.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    destinations: Vec<String>,
+    calling_ae_title: Option<String>,
+    called_ae_title: Option<String>,
+    /// the keys which the data set of an instance must match
+    identifier: InMemDicomObject,
+}
+
+impl Rule {
+    /// Prepare the rule at the given position (starting at 1)
+    /// of the configuration.
+    pub fn new(rule: usize, config: &RuleConfig) -> Result<Self, Error> {
+        let terms: Vec<_> = config
+            .tags
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        let mut identifier =
+            parse_queries(InMemDicomObject::new_empty(), &terms).context(Snafu { rule })?;
+        if !config.modality.is_empty() {
+            identifier.put(DataElement::new(
+                tags::MODALITY,
+                VR::CS,
+                PrimitiveValue::Strs(config.modality.iter().cloned().collect()),
+            ));
+        }
+        if !config.sop_class_uid.is_empty() {
+            identifier.put(DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                PrimitiveValue::Strs(config.sop_class_uid.iter().cloned().collect()),
+            ));
+        }
+
+        Ok(Rule {
+            destinations: config.destinations.clone(),
+            calling_ae_title: config.calling_ae_title.clone(),
+            called_ae_title: config.called_ae_title.clone(),
+            identifier,
+        })
+    }
+
+    /// The names of the destinations of the matching instances.
+    pub fn destinations(&self) -> &[String] {
+        &self.destinations
+    }
+
+    /// Check whether an instance received from the given AE title,
+    /// which called the given AE title,
+    /// matches all criteria of the rule.
+    pub fn matches(
+        &self,
+        calling_ae_title: &str,
+        called_ae_title: &str,
+        dataset: &InMemDicomObject,
+    ) -> bool {
+        let ae_title_matches = |pattern: &Option<String>, ae_title: &str| {
+            pattern
+                .as_deref()
+                .map(|pattern| wildcard_match(pattern.trim(), ae_title.trim()))
+                .unwrap_or(true)
+        };
+        ae_title_matches(&self.calling_ae_title, calling_ae_title)
+            && ae_title_matches(&self.called_ae_title, called_ae_title)
+            && matches(&self.identifier, dataset)
+    }
+}
+
+/// Match a value against a pattern
+/// in which `*` matches any sequence of characters
+/// and `?` matches any single character.
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    let (mut p, mut v) = (0, 0);
+    // position of the last `*` in the pattern and of the value at that point
+    let mut star: Option<(usize, usize)> = None;
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, v));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match star {
+                Some((star_p, star_v)) => {
+                    p = star_p + 1;
+                    v = star_v + 1;
+                    star = Some((star_p, star_v + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ct_image() -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                PrimitiveValue::from("1.2.840.10008.5.1.4.1.1.2"),
+            ),
+            DataElement::new(tags::MODALITY, VR::CS, PrimitiveValue::from("CT")),
+            DataElement::new(
+                tags::STUDY_DESCRIPTION,
+                VR::LO,
+                PrimitiveValue::from("CT CHEST W/O"),
+            ),
+        ])
+    }
+
+    #[test]
+    fn match_wildcards() {
+        assert!(wildcard_match("SCANNER*", "SCANNER1"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("S?AN*1", "SCANNER1"));
+        assert!(wildcard_match("*NER*", "SCANNER1"));
+        assert!(!wildcard_match("SCANNER?", "SCANNER"));
+        assert!(!wildcard_match("SCANNER", "SCANNER1"));
+    }
+
+    #[test]
+    fn match_rule_criteria() {
+        let rule = Rule::new(
+            1,
+            &RuleConfig {
+                destinations: vec!["ai".to_string()],
+                calling_ae_title: Some("SCANNER*".to_string()),
+                modality: vec!["MR".to_string(), "CT".to_string()],
+                tags: [("StudyDescription".to_string(), "*CHEST*".to_string())]
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(rule.matches("SCANNER1", "ROUTER", &ct_image()));
+        assert!(!rule.matches("WORKSTATION", "ROUTER", &ct_image()));
+
+        let mut head = ct_image();
+        head.put(DataElement::new(
+            tags::STUDY_DESCRIPTION,
+            VR::LO,
+            PrimitiveValue::from("CT HEAD"),
+        ));
+        assert!(!rule.matches("SCANNER1", "ROUTER", &head));
+
+        let mut us = ct_image();
+        us.put(DataElement::new(
+            tags::MODALITY,
+            VR::CS,
+            PrimitiveValue::from("US"),
+        ));
+        assert!(!rule.matches("SCANNER1", "ROUTER", &us));
+    }
+
+    #[test]
+    fn match_everything_without_criteria() {
+        let rule = Rule::new(
+            1,
+            &RuleConfig {
+                destinations: vec!["archive".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(rule.matches("ANY", "ROUTER", &ct_image()));
+        assert!(rule.matches("ANY", "ROUTER", &InMemDicomObject::new_empty()));
+    }
+}
@@ -0,0 +1,278 @@
+use std::convert::TryFrom;
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::InMemDicomObject;
+use dicom_router::{
+    config::{DestinationConfig, RetryPolicy, RouterConfig, RuleConfig},
+    RouterOptions,
+};
+use dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN;
+use dicom_ul::{
+    association::{client::ClientAssociationOptions, duplex::duplex},
+    dimse::{
+        composite::{CStoreRq, CStoreRsp},
+        DimseMessage, Status,
+    },
+    scp::ServiceClassProvider,
+};
+
+fn instance(sop_class_uid: &str, sop_instance_uid: &str, modality: &str) -> InMemDicomObject {
+    InMemDicomObject::from_element_iter([
+        DataElement::new(
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            PrimitiveValue::from(sop_class_uid),
+        ),
+        DataElement::new(
+            tags::SOP_INSTANCE_UID,
+            VR::UI,
+            PrimitiveValue::from(sop_instance_uid),
+        ),
+        DataElement::new(tags::MODALITY, VR::CS, PrimitiveValue::from(modality)),
+        DataElement::new(tags::PATIENT_ID, VR::LO, PrimitiveValue::from("P1")),
+    ])
+}
+
+/// Start a storage SCP on a local port,
+/// recording the SOP instance UIDs of the instances which it receives.
+fn start_destination(listener: TcpListener) -> Arc<Mutex<Vec<String>>> {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let received_by_scp = Arc::clone(&received);
+    let scp = ServiceClassProvider::new()
+        .promiscuous(true)
+        .with_transfer_syntax(IMPLICIT_VR_LITTLE_ENDIAN.uid())
+        .with_default_store_handler(move |_: &_, rq: &CStoreRq, _: InMemDicomObject| -> Status {
+            received_by_scp
+                .lock()
+                .unwrap()
+                .push(rq.affected_sop_instance_uid.clone());
+            Status::SUCCESS
+        });
+    std::thread::spawn(move || scp.serve(listener));
+    received
+}
+
+/// Send instances to the router through an in-memory association,
+/// returning the status of each C-STORE operation.
+fn send_to_router(
+    scp: ServiceClassProvider,
+    calling_ae_title: &str,
+    instances: &[InMemDicomObject],
+) -> Vec<Status> {
+    let (scu_stream, scp_stream) = duplex(64 * 1024);
+    let scp_handle = std::thread::spawn(move || scp.handle_over(scp_stream));
+
+    let mut options = ClientAssociationOptions::new()
+        .calling_ae_title(calling_ae_title)
+        .called_ae_title("ROUTER");
+    for sop_class_uid in [uids::CT_IMAGE_STORAGE, uids::MR_IMAGE_STORAGE] {
+        options =
+            options.with_presentation_context(sop_class_uid, vec![IMPLICIT_VR_LITTLE_ENDIAN.uid()]);
+    }
+    let mut association = options.establish_over(scu_stream).unwrap();
+
+    let mut statuses = Vec::new();
+    for (i, obj) in instances.iter().enumerate() {
+        let sop_class_uid = obj.element(tags::SOP_CLASS_UID).unwrap().to_str().unwrap();
+        let sop_instance_uid = obj
+            .element(tags::SOP_INSTANCE_UID)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        let pc = association
+            .presentation_contexts()
+            .iter()
+            .find(|pc| association.abstract_syntax(pc.id) == Some(&*sop_class_uid))
+            .unwrap()
+            .id;
+        let msg = DimseMessage::new(
+            pc,
+            CStoreRq::new(i as u16 + 1, &*sop_class_uid, &*sop_instance_uid),
+        )
+        .with_dataset(obj, &IMPLICIT_VR_LITTLE_ENDIAN.erased())
+        .unwrap();
+        association.send_dimse(&msg).unwrap();
+        let rsp = association.receive_dimse().unwrap();
+        statuses.push(CStoreRsp::try_from(rsp.command).unwrap().status);
+    }
+
+    association
+        .release()
+        .expect("did not have a peaceful release");
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    statuses
+}
+
+fn router_scp(config: RouterConfig, queue_dir: &Path) -> ServiceClassProvider {
+    let router = RouterOptions::new(config)
+        .queue_dir(queue_dir)
+        .poll_interval(Duration::from_millis(100))
+        .start()
+        .unwrap();
+    ServiceClassProvider::new()
+        .ae_title("ROUTER")
+        .promiscuous(true)
+        .with_transfer_syntax(IMPLICIT_VR_LITTLE_ENDIAN.uid())
+        .with_default_store_handler(router)
+}
+
+/// Wait until the condition holds, failing after a few seconds.
+fn wait_for(what: &str, condition: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !condition() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "timed out waiting for {}",
+            what
+        );
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+fn is_empty_dir(dir: &Path) -> bool {
+    std::fs::read_dir(dir)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(true)
+}
+
+/// Instances are forwarded to the destinations of all matching rules,
+/// and leave the queue once every destination has them.
+#[test]
+fn forward_by_rules() {
+    let archive_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let ct_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut config = RouterConfig::default();
+    config.destinations.insert(
+        "archive".to_string(),
+        DestinationConfig::new(format!(
+            "ARCHIVE@{}",
+            archive_listener.local_addr().unwrap()
+        )),
+    );
+    config.destinations.insert(
+        "ct".to_string(),
+        DestinationConfig::new(format!("CT-NODE@{}", ct_listener.local_addr().unwrap())),
+    );
+    config.rules = vec![
+        RuleConfig {
+            destinations: vec!["archive".to_string()],
+            ..Default::default()
+        },
+        RuleConfig {
+            destinations: vec!["ct".to_string()],
+            calling_ae_title: Some("SCANNER*".to_string()),
+            modality: vec!["CT".to_string()],
+            ..Default::default()
+        },
+    ];
+    let archive = start_destination(archive_listener);
+    let ct = start_destination(ct_listener);
+
+    let queue_dir = tempfile::tempdir().unwrap();
+    let statuses = send_to_router(
+        router_scp(config, queue_dir.path()),
+        "SCANNER1",
+        &[
+            instance(uids::CT_IMAGE_STORAGE, "2.25.1001", "CT"),
+            instance(uids::MR_IMAGE_STORAGE, "2.25.1002", "MR"),
+        ],
+    );
+    assert_eq!(statuses, vec![Status::SUCCESS, Status::SUCCESS]);
+
+    wait_for("instances in the archive", || {
+        archive.lock().unwrap().len() == 2
+    });
+    wait_for("instance in the CT node", || ct.lock().unwrap().len() == 1);
+    assert_eq!(*ct.lock().unwrap(), vec!["2.25.1001".to_string()]);
+    wait_for("queue to be emptied", || {
+        is_empty_dir(&queue_dir.path().join("objects"))
+    });
+}
+
+/// Instances which could not be forwarded
+/// are forwarded once the destination is back, even after a restart.
+#[test]
+fn retry_after_restart() {
+    // find a port with nothing listening on it
+    let address = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap()
+    };
+    let mut config = RouterConfig {
+        retry: RetryPolicy {
+            initial_delay: Duration::from_millis(50),
+            max_delay: Duration::from_millis(200),
+            max_attempts: None,
+        },
+        ..Default::default()
+    };
+    config.destinations.insert(
+        "archive".to_string(),
+        DestinationConfig::new(format!("ARCHIVE@{}", address)),
+    );
+    config.rules = vec![RuleConfig {
+        destinations: vec!["archive".to_string()],
+        ..Default::default()
+    }];
+
+    let queue_dir = tempfile::tempdir().unwrap();
+    let entry = queue_dir.path().join("pending/archive/2.25.2001");
+    let statuses = send_to_router(
+        router_scp(config.clone(), queue_dir.path()),
+        "MODALITY",
+        &[instance(uids::MR_IMAGE_STORAGE, "2.25.2001", "MR")],
+    );
+    assert_eq!(statuses, vec![Status::SUCCESS]);
+    wait_for("a failed attempt", || {
+        std::fs::read_to_string(&entry)
+            .map(|text| !text.starts_with("0 "))
+            .unwrap_or(false)
+    });
+    // the first router is gone with its service class provider
+    std::thread::sleep(Duration::from_millis(300));
+    assert!(entry.exists());
+
+    let archive = start_destination(TcpListener::bind(address).unwrap());
+    let _router = RouterOptions::new(config)
+        .queue_dir(queue_dir.path())
+        .poll_interval(Duration::from_millis(100))
+        .start()
+        .unwrap();
+    wait_for("instance in the archive", || {
+        archive.lock().unwrap().len() == 1
+    });
+    assert_eq!(*archive.lock().unwrap(), vec!["2.25.2001".to_string()]);
+    wait_for("queue to be emptied", || !entry.exists());
+}
+
+/// Instances matching no rule are refused.
+#[test]
+fn refuse_unrouted_instances() {
+    let mut config = RouterConfig::default();
+    config.destinations.insert(
+        "ct".to_string(),
+        DestinationConfig::new("CT-NODE@127.0.0.1:1"),
+    );
+    config.rules = vec![RuleConfig {
+        destinations: vec!["ct".to_string()],
+        modality: vec!["CT".to_string()],
+        ..Default::default()
+    }];
+
+    let queue_dir = tempfile::tempdir().unwrap();
+    let statuses = send_to_router(
+        router_scp(config, queue_dir.path()),
+        "MODALITY",
+        &[instance(uids::MR_IMAGE_STORAGE, "2.25.3001", "MR")],
+    );
+    assert_eq!(statuses, vec![Status::UNABLE_TO_PROCESS]);
+    assert!(is_empty_dir(&queue_dir.path().join("objects")));
+}
@@ -4,13 +4,23 @@ use dicom_ul::association::client::get_client_pdu;
 use dicom_ul::pdu::writer::write_pdu;
 use dicom_ul::pdu::Pdu;
 use snafu::{Backtrace, OptionExt, Report, ResultExt, Snafu, Whatever};
-use std::io::Write;
-use std::net::{Shutdown, TcpListener, TcpStream};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
 use std::thread;
 use std::thread::JoinHandle;
-use tracing::error;
+use tracing::{error, info, Level};
+
+mod record;
+mod rewrite;
+mod session;
+
+use record::{Connection, Recorder};
+use rewrite::{RewriteRules, Rewriter};
+use session::Session;
 
 type Result<T> = std::result::Result<T, Error>;
 
@@ -79,8 +89,9 @@ fn run(
     scu_stream: &mut TcpStream,
     destination_addr: &str,
     strict: bool,
-    verbose: bool,
     max_pdu_length: u32,
+    rules: &RewriteRules,
+    mut recorder: Option<&mut Recorder<BufWriter<File>>>,
 ) -> Result<()> {
     // Before we do anything, let's also open another connection to the destination
     // SCP.
@@ -169,28 +180,43 @@ fn run(
                 });
             }
             let mut buffer: Vec<u8> = Vec::with_capacity(max_pdu_length as usize);
+            let unknown_addr = || SocketAddr::from(([0, 0, 0, 0], 0));
+            let mut connection = Connection::new(
+                scu_stream.peer_addr().unwrap_or_else(|_| unknown_addr()),
+                scp_stream.peer_addr().unwrap_or_else(|_| unknown_addr()),
+            );
+            let mut session = Session::new();
+            let mut rewriter = Rewriter::new(rules, max_pdu_length);
 
-            loop {
+            'relay: loop {
                 let message = message_rx.recv().context(ReceiveMessageSnafu)?;
                 match message {
-                    ThreadMessage::SendPdu { to, pdu } => match to {
-                        ProviderType::Scu => {
-                            if verbose {
-                                println!("scu <---- scp: {}", pdu.short_description());
+                    ThreadMessage::SendPdu { to, pdu } => {
+                        let from = to.peer();
+                        // record the PDUs as sent by each node, before any rewriting
+                        if let Some(r) = recorder.as_mut() {
+                            if let Err(e) = r.record(&mut connection, from, &pdu) {
+                                error!("stopped recording: {}", Report::from_error(e));
+                                recorder = None;
                             }
-                            buffer.clear();
-                            write_pdu(&mut buffer, &pdu).unwrap();
-                            scu_stream.write_all(&buffer).unwrap();
                         }
-                        ProviderType::Scp => {
-                            if verbose {
-                                println!("scu ----> scp: {}", pdu.short_description());
-                            }
+                        session.inspect(from, &pdu);
+                        for pdu in rewriter.rewrite(&session, from, pdu) {
                             buffer.clear();
-                            write_pdu(&mut buffer, &pdu).unwrap();
-                            scp_stream.write_all(&buffer).unwrap();
+                            if let Err(err) = write_pdu(&mut buffer, &pdu) {
+                                error!("error writing to {:?}: {}", to, Report::from_error(err));
+                                break 'relay;
+                            }
+                            let stream = match to {
+                                ProviderType::Scu => &mut *scu_stream,
+                                ProviderType::Scp => &mut *scp_stream,
+                            };
+                            if let Err(err) = stream.write_all(&buffer) {
+                                error!("error writing to {:?}: {}", to, Report::from_error(err));
+                                break 'relay;
+                            }
                         }
-                    },
+                    }
                     ThreadMessage::ReadErr { from, err } => {
                         error!("error reading from {:?}: {}", from, Report::from_error(err));
                         break;
@@ -200,13 +226,16 @@ fn run(
                         break;
                     }
                     ThreadMessage::Shutdown { initiator } => {
-                        if verbose {
-                            println!("shutdown initiated from: {:?}", initiator);
-                        }
+                        info!("shutdown initiated from: {:?}", initiator);
                         break;
                     }
                 }
             }
+            if let Some(recorder) = recorder {
+                if let Err(e) = recorder.flush() {
+                    error!("{}", Report::from_error(e));
+                }
+            }
 
             scu_stream
                 .shutdown(Shutdown::Read)
@@ -280,32 +309,86 @@ fn command() -> Command {
                 .value_parser(value_parser!(u32).range(4096..=131_072))
                 .default_value("16384"),
         )
+        .arg(
+            Arg::new("record")
+                .help("Record the proxied sessions to a pcap capture file")
+                .long("record")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("map-ae-title")
+                .help("Replace an AE title in association requests (e.g. PACS=ARCHIVE)")
+                .long("map-ae-title")
+                .value_name("FROM=TO")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("coerce")
+                .help("Coerce an attribute in the data sets of C-STORE requests (e.g. InstitutionName=HOSPITAL)")
+                .long("coerce")
+                .value_name("TAG=VALUE")
+                .action(ArgAction::Append),
+        )
 }
 
-fn main() {
-    tracing::subscriber::set_global_default(tracing_subscriber::FmtSubscriber::new())
-        .whatever_context("Could not set up global tracing subscriber")
-        .unwrap_or_else(|e: snafu::Whatever| {
-            eprintln!("[ERROR] {}", Report::from_error(e));
-        });
+/// Collect the rewrite rules given in the command line.
+fn rewrite_rules(matches: &clap::ArgMatches) -> std::result::Result<RewriteRules, Whatever> {
+    let mut rules = RewriteRules::new();
+    for mapping in matches
+        .get_many::<String>("map-ae-title")
+        .into_iter()
+        .flatten()
+    {
+        let (from, to) = mapping
+            .split_once('=')
+            .with_whatever_context(|| format!("Invalid AE title mapping `{}`", mapping))?;
+        rules = rules.map_ae_title(from, to);
+    }
+    for term in matches.get_many::<String>("coerce").into_iter().flatten() {
+        rules = rules
+            .coerce(term.as_str())
+            .with_whatever_context(|_| format!("Invalid attribute coercion `{}`", term))?;
+    }
+    Ok(rules)
+}
 
+fn main() {
     let matches = command().get_matches();
+    let verbose = matches.get_flag("verbose");
+
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(if verbose { Level::DEBUG } else { Level::INFO })
+            .finish(),
+    )
+    .whatever_context("Could not set up global tracing subscriber")
+    .unwrap_or_else(|e: snafu::Whatever| {
+        eprintln!("[ERROR] {}", Report::from_error(e));
+    });
 
     let destination_host = matches.get_one::<String>("destination-host").unwrap();
     let destination_port = matches.get_one::<String>("destination-port").unwrap();
     let listen_port: u16 = *matches.get_one("listen-port").unwrap();
     let strict: bool = matches.get_flag("strict");
-    let verbose = matches.get_flag("verbose");
     let max_pdu_length: u32 = *matches.get_one("max-pdu-length").unwrap();
+    let rules = rewrite_rules(&matches).unwrap_or_else(|e| {
+        error!("{}", Report::from_error(e));
+        std::process::exit(-2);
+    });
+    let mut recorder = matches.get_one::<PathBuf>("record").map(|path| {
+        Recorder::create(path).unwrap_or_else(|e| {
+            error!("{}", Report::from_error(e));
+            std::process::exit(-2);
+        })
+    });
 
     let listen_addr = format!("0.0.0.0:{}", listen_port);
     let destination_addr = format!("{}:{}", destination_host, destination_port);
 
     let listener = TcpListener::bind(&listen_addr).unwrap();
-    if verbose {
-        println!("listening on: {}", listen_addr);
-        println!("forwarding to: {}", destination_addr);
-    }
+    info!("listening on: {}", listen_addr);
+    info!("forwarding to: {}", destination_addr);
 
     for mut stream in listener.incoming() {
         match stream {
@@ -314,8 +397,9 @@ fn main() {
                     scu_stream,
                     &destination_addr,
                     strict,
-                    verbose,
                     max_pdu_length,
+                    &rules,
+                    recorder.as_mut(),
                 ) {
                     error!("{}", Report::from_error(e));
                 }
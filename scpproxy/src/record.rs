@@ -0,0 +1,250 @@
+//! Recording of proxied sessions to a capture file.
+//!
+//! Sessions are written in the classic pcap format
+//! with raw IP packets (link type 101),
+//! so that they can be opened by common network analysis tools.
+//! Each PDU is wrapped in synthetic IP and TCP headers
+//! carrying the real addresses of the SCU and of the SCP,
+//! with consecutive sequence numbers in each direction.
+use dicom_ul::pdu::{write_pdu, Pdu};
+use snafu::{Backtrace, ResultExt, Snafu};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ProviderType;
+
+/// The link type of raw IPv4 or IPv6 packets.
+const LINKTYPE_RAW: u32 = 101;
+
+/// The maximum TCP payload of a single recorded packet,
+/// small enough for the total length of an IPv4 packet.
+const MAX_SEGMENT_SIZE: usize = 65_000;
+
+const TCP_FLAG_PSH: u8 = 0x08;
+const TCP_FLAG_ACK: u8 = 0x10;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Could not create capture file"))]
+    CreateFile {
+        backtrace: Backtrace,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not write to capture file"))]
+    WriteCapture {
+        backtrace: Backtrace,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not encode PDU"))]
+    EncodePdu {
+        #[snafu(backtrace)]
+        source: dicom_ul::pdu::WriteError,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A writer of proxied PDUs to a pcap capture.
+#[derive(Debug)]
+pub struct Recorder<W> {
+    out: W,
+    buffer: Vec<u8>,
+}
+
+impl Recorder<BufWriter<File>> {
+    /// Create a capture file at the given path,
+    /// replacing any existing file.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::create(path).context(CreateFileSnafu)?;
+        Recorder::new(BufWriter::new(file))
+    }
+}
+
+impl<W: Write> Recorder<W> {
+    /// Start a capture in the given writer.
+    pub fn new(mut out: W) -> Result<Self> {
+        let mut header = Vec::with_capacity(24);
+        header.extend_from_slice(&0xa1b2_c3d4_u32.to_le_bytes());
+        // version 2.4
+        header.extend_from_slice(&2_u16.to_le_bytes());
+        header.extend_from_slice(&4_u16.to_le_bytes());
+        // time zone offset and timestamp accuracy
+        header.extend_from_slice(&0_i32.to_le_bytes());
+        header.extend_from_slice(&0_u32.to_le_bytes());
+        // snapshot length
+        header.extend_from_slice(&(MAX_SEGMENT_SIZE as u32 + 100).to_le_bytes());
+        header.extend_from_slice(&LINKTYPE_RAW.to_le_bytes());
+        out.write_all(&header).context(WriteCaptureSnafu)?;
+        Ok(Recorder {
+            out,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Record a PDU sent by one of the nodes of the connection.
+    pub fn record(
+        &mut self,
+        connection: &mut Connection,
+        from: ProviderType,
+        pdu: &Pdu,
+    ) -> Result<()> {
+        self.buffer.clear();
+        write_pdu(&mut self.buffer, pdu).context(EncodePduSnafu)?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        for segment in self.buffer.chunks(MAX_SEGMENT_SIZE) {
+            let packet = connection.packet(from, segment);
+            let mut record = Vec::with_capacity(16 + packet.len());
+            record.extend_from_slice(&(timestamp.as_secs() as u32).to_le_bytes());
+            record.extend_from_slice(&timestamp.subsec_micros().to_le_bytes());
+            record.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+            record.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+            record.extend_from_slice(&packet);
+            self.out.write_all(&record).context(WriteCaptureSnafu)?;
+        }
+        Ok(())
+    }
+
+    /// Write any buffered packets to the capture.
+    pub fn flush(&mut self) -> Result<()> {
+        self.out.flush().context(WriteCaptureSnafu)
+    }
+}
+
+/// The state of a recorded TCP connection between an SCU and an SCP.
+#[derive(Debug, Clone)]
+pub struct Connection {
+    scu: SocketAddr,
+    scp: SocketAddr,
+    /// the next sequence number of the SCU and of the SCP
+    seq: [u32; 2],
+}
+
+impl Connection {
+    /// Begin recording a connection between the given addresses.
+    pub fn new(scu: SocketAddr, scp: SocketAddr) -> Self {
+        Connection {
+            scu,
+            scp,
+            seq: [1, 1],
+        }
+    }
+
+    /// Build an IP packet with the given TCP payload.
+    fn packet(&mut self, from: ProviderType, payload: &[u8]) -> Vec<u8> {
+        let (src, dst, seq, ack) = match from {
+            ProviderType::Scu => (self.scu, self.scp, self.seq[0], self.seq[1]),
+            ProviderType::Scp => (self.scp, self.scu, self.seq[1], self.seq[0]),
+        };
+        let next = seq.wrapping_add(payload.len() as u32);
+        match from {
+            ProviderType::Scu => self.seq[0] = next,
+            ProviderType::Scp => self.seq[1] = next,
+        }
+
+        let mut tcp = Vec::with_capacity(20 + payload.len());
+        tcp.extend_from_slice(&src.port().to_be_bytes());
+        tcp.extend_from_slice(&dst.port().to_be_bytes());
+        tcp.extend_from_slice(&seq.to_be_bytes());
+        tcp.extend_from_slice(&ack.to_be_bytes());
+        // data offset of 5 words, no options
+        tcp.push(5 << 4);
+        tcp.push(TCP_FLAG_PSH | TCP_FLAG_ACK);
+        // window, checksum (left unset), urgent pointer
+        tcp.extend_from_slice(&0xffff_u16.to_be_bytes());
+        tcp.extend_from_slice(&[0, 0, 0, 0]);
+        tcp.extend_from_slice(payload);
+
+        match (src.ip(), dst.ip()) {
+            (IpAddr::V4(src), IpAddr::V4(dst)) => {
+                let mut packet = Vec::with_capacity(20 + tcp.len());
+                packet.push(0x45);
+                packet.push(0);
+                packet.extend_from_slice(&((20 + tcp.len()) as u16).to_be_bytes());
+                // identification, don't fragment
+                packet.extend_from_slice(&[0, 0, 0x40, 0]);
+                // time to live, protocol (TCP)
+                packet.extend_from_slice(&[64, 6]);
+                packet.extend_from_slice(&[0, 0]);
+                packet.extend_from_slice(&src.octets());
+                packet.extend_from_slice(&dst.octets());
+                let checksum = ipv4_checksum(&packet);
+                packet[10..12].copy_from_slice(&checksum.to_be_bytes());
+                packet.extend_from_slice(&tcp);
+                packet
+            }
+            (src, dst) => {
+                let to_v6 = |ip: IpAddr| match ip {
+                    IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+                    IpAddr::V6(ip) => ip,
+                };
+                let mut packet = Vec::with_capacity(40 + tcp.len());
+                packet.extend_from_slice(&[0x60, 0, 0, 0]);
+                packet.extend_from_slice(&(tcp.len() as u16).to_be_bytes());
+                // next header (TCP), hop limit
+                packet.extend_from_slice(&[6, 64]);
+                packet.extend_from_slice(&to_v6(src).octets());
+                packet.extend_from_slice(&to_v6(dst).octets());
+                packet.extend_from_slice(&tcp);
+                packet
+            }
+        }
+    }
+}
+
+/// Compute the checksum of an IPv4 header.
+fn ipv4_checksum(header: &[u8]) -> u16 {
+    let mut sum: u32 = header
+        .chunks(2)
+        .map(|word| u32::from(u16::from_be_bytes([word[0], word[1]])))
+        .sum();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_pdus_as_tcp_segments() {
+        let mut recorder = Recorder::new(Vec::new()).unwrap();
+        let mut connection = Connection::new(
+            "10.0.0.1:40000".parse().unwrap(),
+            "10.0.0.2:104".parse().unwrap(),
+        );
+        recorder
+            .record(&mut connection, ProviderType::Scu, &Pdu::ReleaseRQ)
+            .unwrap();
+        recorder
+            .record(&mut connection, ProviderType::Scp, &Pdu::ReleaseRP)
+            .unwrap();
+        let capture = recorder.out;
+
+        assert_eq!(&capture[..4], &[0xd4, 0xc3, 0xb2, 0xa1]);
+        assert_eq!(&capture[20..24], &LINKTYPE_RAW.to_le_bytes());
+
+        // each A-RELEASE PDU is 10 bytes long
+        let record_len = 16 + 20 + 20 + 10;
+        assert_eq!(capture.len(), 24 + 2 * record_len);
+        let first = &capture[24 + 16..24 + record_len];
+        assert_eq!(ipv4_checksum(&first[..20]), 0);
+        assert_eq!(&first[12..16], &[10, 0, 0, 1]);
+        assert_eq!(&first[20..22], &40000_u16.to_be_bytes());
+        assert_eq!(&first[24..28], &1_u32.to_be_bytes());
+        assert_eq!(&first[40..], &[0x05, 0, 0, 0, 0, 4, 0, 0, 0, 0]);
+
+        let second = &capture[24 + record_len + 16..];
+        assert_eq!(&second[12..16], &[10, 0, 0, 2]);
+        // sequence number of the SCP, then acknowledging the SCU's PDU
+        assert_eq!(&second[24..28], &1_u32.to_be_bytes());
+        assert_eq!(&second[28..32], &11_u32.to_be_bytes());
+    }
+}
@@ -0,0 +1,350 @@
+//! Rewriting of the PDUs relayed by the proxy.
+//!
+//! AE titles in the association negotiation can be mapped to other titles,
+//! and the data sets of C-STORE requests can have attributes coerced
+//! before they reach the SCP.
+use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
+use dicom_findscu::query::parse_queries;
+use dicom_object::InMemDicomObject;
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use dicom_ul::dimse::{Command, DimseMessage, MessageAssembler};
+use dicom_ul::pdu::Pdu;
+use snafu::{Report, ResultExt, Whatever};
+use tracing::{info, warn};
+
+use crate::session::Session;
+use crate::ProviderType;
+
+/// The rewrite rules to apply to proxied associations.
+#[derive(Debug, Default, Clone)]
+pub struct RewriteRules {
+    /// AE titles to replace in association requests, and their replacements
+    ae_titles: Vec<(String, String)>,
+    /// attribute terms of the form `«field_path»=«field_value»`
+    coercions: Vec<String>,
+}
+
+impl RewriteRules {
+    /// Create an empty set of rules, which leaves all PDUs intact.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the AE title `from` with `to`
+    /// when used as the calling or called AE title of an association request,
+    /// and the other way around in the association acknowledgement.
+    pub fn map_ae_title(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.ae_titles.push((from.into(), to.into()));
+        self
+    }
+
+    /// Coerce an attribute of every data set sent in a C-STORE request,
+    /// with a term of the form `«field_path»=«field_value»`.
+    ///
+    /// An empty value removes the contents of the attribute.
+    pub fn coerce(mut self, term: impl Into<String>) -> Result<Self, Whatever> {
+        let term = term.into();
+        parse_queries(InMemDicomObject::new_empty(), &[&term])?;
+        self.coercions.push(term);
+        Ok(self)
+    }
+
+    fn map(&self, ae_title: &mut String, reverse: bool) {
+        for (from, to) in &self.ae_titles {
+            let (from, to) = if reverse { (to, from) } else { (from, to) };
+            if ae_title.trim() == from.trim() {
+                *ae_title = to.clone();
+                return;
+            }
+        }
+    }
+}
+
+/// The application of rewrite rules to a single association.
+#[derive(Debug)]
+pub struct Rewriter<'a> {
+    rules: &'a RewriteRules,
+    /// the maximum length of the PDUs sent to the SCP
+    /// when no limit is declared by the SCP
+    max_pdu_length: u32,
+    /// reassembler of the DIMSE messages from the SCU
+    assembler: MessageAssembler,
+    /// the PDUs of the messages from the SCU which are not complete yet
+    pending: Vec<Pdu>,
+}
+
+impl<'a> Rewriter<'a> {
+    /// Start rewriting a new association.
+    pub fn new(rules: &'a RewriteRules, max_pdu_length: u32) -> Self {
+        Rewriter {
+            rules,
+            max_pdu_length,
+            assembler: MessageAssembler::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Rewrite a PDU sent by the given node,
+    /// returning the PDUs to relay to its peer.
+    ///
+    /// The session must have already inspected the PDU.
+    /// P-Data PDUs from the SCU are held back
+    /// until they complete a DIMSE message.
+    pub fn rewrite(&mut self, session: &Session, from: ProviderType, pdu: Pdu) -> Vec<Pdu> {
+        match (from, pdu) {
+            (ProviderType::Scu, Pdu::AssociationRQ(mut rq)) => {
+                self.rules.map(&mut rq.calling_ae_title, false);
+                self.rules.map(&mut rq.called_ae_title, false);
+                vec![Pdu::AssociationRQ(rq)]
+            }
+            (ProviderType::Scp, Pdu::AssociationAC(mut ac)) => {
+                self.rules.map(&mut ac.calling_ae_title, true);
+                self.rules.map(&mut ac.called_ae_title, true);
+                vec![Pdu::AssociationAC(ac)]
+            }
+            (ProviderType::Scu, Pdu::PData { data }) if !self.rules.coercions.is_empty() => {
+                self.pdata(session, data)
+            }
+            (_, pdu) => vec![pdu],
+        }
+    }
+
+    fn pdata(&mut self, session: &Session, data: Vec<dicom_ul::pdu::PDataValue>) -> Vec<Pdu> {
+        self.pending.push(Pdu::PData { data: data.clone() });
+        if let Err(e) = self.assembler.push_values(data) {
+            warn!(
+                "Could not reassemble DIMSE message, relaying it as is: {}",
+                Report::from_error(e)
+            );
+            self.assembler = MessageAssembler::new();
+            return std::mem::take(&mut self.pending);
+        }
+        if self.assembler.is_partial() {
+            return Vec::new();
+        }
+
+        let mut messages = Vec::new();
+        let mut coerced = false;
+        while let Some(mut msg) = self.assembler.pop_message() {
+            coerced |= self.coerce(session, &mut msg);
+            messages.push(msg);
+        }
+        let pending = std::mem::take(&mut self.pending);
+        if !coerced {
+            // keep the original framing
+            return pending;
+        }
+
+        let max_pdu_length = match session.scp_max_pdu_length() {
+            Some(0) | None => self.max_pdu_length,
+            Some(len) => len,
+        };
+        let mut pdus = Vec::new();
+        for msg in &messages {
+            match msg.to_pdus(max_pdu_length) {
+                Ok(msg_pdus) => pdus.extend(msg_pdus),
+                Err(e) => {
+                    warn!(
+                        "Could not encode rewritten DIMSE message, relaying it as is: {}",
+                        Report::from_error(e)
+                    );
+                    return pending;
+                }
+            }
+        }
+        pdus
+    }
+
+    /// Apply the attribute coercions to the data set of a C-STORE request,
+    /// returning whether the message was changed.
+    fn coerce(&self, session: &Session, msg: &mut DimseMessage) -> bool {
+        let sop_instance_uid = match &msg.command {
+            Command::CStoreRq(rq) if msg.data.is_some() => rq.affected_sop_instance_uid.clone(),
+            _ => return false,
+        };
+        let ts = match session
+            .transfer_syntax(msg.presentation_context_id)
+            .and_then(|uid| TransferSyntaxRegistry.get(uid))
+        {
+            Some(ts) => ts,
+            None => {
+                warn!(
+                    "Unknown transfer syntax in presentation context {}, {} not coerced",
+                    msg.presentation_context_id, sop_instance_uid
+                );
+                return false;
+            }
+        };
+
+        let result = msg
+            .dataset(ts)
+            .whatever_context("Could not decode data set")
+            .and_then(|dataset| {
+                let dataset = dataset.unwrap_or_else(InMemDicomObject::new_empty);
+                parse_queries(dataset, &self.rules.coercions)
+            })
+            .and_then(|dataset| {
+                let mut data = Vec::new();
+                dataset
+                    .write_dataset_with_ts(&mut data, ts)
+                    .whatever_context("Could not encode data set")?;
+                Ok(data)
+            });
+        match result {
+            Ok(data) => {
+                info!(
+                    sop_instance_uid = sop_instance_uid.trim_end_matches('\0'),
+                    "Coerced data set"
+                );
+                msg.data = Some(data);
+                true
+            }
+            Err(e) => {
+                warn!(
+                    "Could not coerce {}, relaying it as is: {}",
+                    sop_instance_uid,
+                    Report::from_error(e)
+                );
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::tags;
+    use dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN;
+    use dicom_ul::dimse::composite::{CEchoRq, CStoreRq};
+    use dicom_ul::pdu::{
+        AssociationAC, AssociationRQ, PresentationContextProposed, PresentationContextResult,
+        PresentationContextResultReason, UserVariableItem,
+    };
+
+    const CT_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.2";
+
+    fn negotiate(session: &mut Session, rewriter: &mut Rewriter) -> (Pdu, Pdu) {
+        let rq = Pdu::AssociationRQ(AssociationRQ {
+            protocol_version: 1,
+            calling_ae_title: "MODALITY".to_string(),
+            called_ae_title: "PACS".to_string(),
+            application_context_name: "1.2.840.10008.3.1.1.1".to_string(),
+            presentation_contexts: vec![PresentationContextProposed {
+                id: 1,
+                abstract_syntax: CT_IMAGE_STORAGE.to_string(),
+                transfer_syntaxes: vec![EXPLICIT_VR_LITTLE_ENDIAN.uid().to_string()],
+            }],
+            user_variables: vec![UserVariableItem::MaxLength(16384)],
+        });
+        session.inspect(ProviderType::Scu, &rq);
+        let rq = rewriter.rewrite(session, ProviderType::Scu, rq).remove(0);
+
+        let ac = Pdu::AssociationAC(AssociationAC {
+            protocol_version: 1,
+            calling_ae_title: "MODALITY".to_string(),
+            called_ae_title: "ARCHIVE".to_string(),
+            application_context_name: "1.2.840.10008.3.1.1.1".to_string(),
+            presentation_contexts: vec![PresentationContextResult {
+                id: 1,
+                reason: PresentationContextResultReason::Acceptance,
+                transfer_syntax: EXPLICIT_VR_LITTLE_ENDIAN.uid().to_string(),
+            }],
+            user_variables: vec![UserVariableItem::MaxLength(4096)],
+        });
+        session.inspect(ProviderType::Scp, &ac);
+        let ac = rewriter.rewrite(session, ProviderType::Scp, ac).remove(0);
+        (rq, ac)
+    }
+
+    #[test]
+    fn map_ae_titles() {
+        let rules = RewriteRules::new().map_ae_title("PACS", "ARCHIVE");
+        let mut session = Session::new();
+        let mut rewriter = Rewriter::new(&rules, 16384);
+        match negotiate(&mut session, &mut rewriter) {
+            (Pdu::AssociationRQ(rq), Pdu::AssociationAC(ac)) => {
+                assert_eq!(rq.calling_ae_title, "MODALITY");
+                assert_eq!(rq.called_ae_title, "ARCHIVE");
+                assert_eq!(ac.calling_ae_title, "MODALITY");
+                assert_eq!(ac.called_ae_title, "PACS");
+            }
+            pdus => panic!("unexpected PDUs {:?}", pdus),
+        }
+    }
+
+    #[test]
+    fn coerce_store_datasets() {
+        let rules = RewriteRules::new()
+            .coerce("InstitutionName=General Hospital")
+            .unwrap()
+            .coerce("PatientName=")
+            .unwrap();
+        let mut session = Session::new();
+        let mut rewriter = Rewriter::new(&rules, 16384);
+        negotiate(&mut session, &mut rewriter);
+
+        let ts = EXPLICIT_VR_LITTLE_ENDIAN.erased();
+        let dataset = InMemDicomObject::from_element_iter(vec![
+            DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::from("Doe^John")),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::U8(vec![0x55; 8000].into()),
+            ),
+        ]);
+        let store = DimseMessage::new(1, CStoreRq::new(1, CT_IMAGE_STORAGE, "2.25.1"))
+            .with_dataset(&dataset, &ts)
+            .unwrap();
+        let echo = DimseMessage::new(1, CEchoRq::new(2));
+        let store_pdus = store.to_pdus(16384).unwrap();
+        let echo_pdus = echo.to_pdus(16384).unwrap();
+
+        // a message which is not coerced is relayed as is
+        let out = rewriter.rewrite(&session, ProviderType::Scu, echo_pdus[0].clone());
+        assert_eq!(out, echo_pdus);
+
+        let mut out = Vec::new();
+        for pdu in store_pdus {
+            out.extend(rewriter.rewrite(&session, ProviderType::Scu, pdu));
+        }
+        // split again within the maximum PDU length of the SCP
+        assert!(out.len() > 1);
+        let mut assembler = MessageAssembler::new();
+        for pdu in out {
+            match pdu {
+                Pdu::PData { data } => assembler.push_values(data).unwrap(),
+                pdu => panic!("unexpected PDU {:?}", pdu),
+            }
+        }
+        let msg = assembler.pop_message().unwrap();
+        assert_eq!(msg.command, store.command);
+        let coerced = msg.dataset(&ts).unwrap().unwrap();
+        assert_eq!(
+            coerced
+                .element(tags::INSTITUTION_NAME)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "General Hospital"
+        );
+        assert_eq!(
+            coerced
+                .element(tags::PATIENT_NAME)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            ""
+        );
+        assert_eq!(
+            coerced
+                .element(tags::PIXEL_DATA)
+                .unwrap()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            8000
+        );
+    }
+}
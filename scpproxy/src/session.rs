@@ -0,0 +1,232 @@
+//! Inspection of the PDUs exchanged in a proxied association.
+//!
+//! The [`Session`] keeps track of the association negotiation
+//! and reassembles DIMSE messages in both directions,
+//! logging them as structured events.
+use dicom_ul::dimse::{DimseMessage, MessageAssembler};
+use dicom_ul::pdu::{
+    AbortRQSource, AssociationAC, AssociationRJ, AssociationRQ, Pdu,
+    PresentationContextResultReason, UserVariableItem,
+};
+use snafu::Report;
+use std::collections::HashMap;
+use tracing::{debug, info, warn};
+
+use crate::ProviderType;
+
+impl ProviderType {
+    /// The node on the other side of the proxy.
+    pub fn peer(self) -> Self {
+        match self {
+            ProviderType::Scu => ProviderType::Scp,
+            ProviderType::Scp => ProviderType::Scu,
+        }
+    }
+
+    /// A description of the direction of PDUs sent by this node.
+    pub fn direction(self) -> &'static str {
+        match self {
+            ProviderType::Scu => "SCU->SCP",
+            ProviderType::Scp => "SCP->SCU",
+        }
+    }
+}
+
+/// The state of a proxied association.
+#[derive(Debug, Default)]
+pub struct Session {
+    /// the abstract syntax of each proposed presentation context
+    abstract_syntaxes: HashMap<u8, String>,
+    /// the transfer syntax of each accepted presentation context
+    transfer_syntaxes: HashMap<u8, String>,
+    /// the maximum PDU length which the SCP can receive, 0 if unlimited
+    scp_max_pdu_length: Option<u32>,
+    /// message assemblers for PDUs from the SCU and from the SCP
+    assemblers: [MessageAssembler; 2],
+}
+
+impl Session {
+    /// Start inspecting a new association.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The transfer syntax accepted for the given presentation context.
+    pub fn transfer_syntax(&self, presentation_context_id: u8) -> Option<&str> {
+        self.transfer_syntaxes
+            .get(&presentation_context_id)
+            .map(|uid| uid.as_str())
+    }
+
+    /// The maximum PDU length which the SCP declared it can receive,
+    /// where 0 means no limit.
+    pub fn scp_max_pdu_length(&self) -> Option<u32> {
+        self.scp_max_pdu_length
+    }
+
+    /// Inspect a PDU sent by the given node, logging its contents.
+    pub fn inspect(&mut self, from: ProviderType, pdu: &Pdu) {
+        let direction = from.direction();
+        debug!(direction, "{}", pdu.short_description());
+        match pdu {
+            Pdu::AssociationRQ(rq) => self.association_rq(direction, rq),
+            Pdu::AssociationAC(ac) => self.association_ac(direction, ac),
+            Pdu::AssociationRJ(AssociationRJ { result, source }) => {
+                info!(direction, result = ?result, source = %source, "A-ASSOCIATE-RJ");
+            }
+            Pdu::PData { data } => {
+                let assembler = match from {
+                    ProviderType::Scu => &mut self.assemblers[0],
+                    ProviderType::Scp => &mut self.assemblers[1],
+                };
+                if let Err(e) = assembler.push_values(data.clone()) {
+                    warn!(
+                        direction,
+                        "Could not reassemble DIMSE message: {}",
+                        Report::from_error(e)
+                    );
+                    *assembler = MessageAssembler::new();
+                    return;
+                }
+                while let Some(msg) = assembler.pop_message() {
+                    log_message(direction, &msg);
+                }
+            }
+            Pdu::ReleaseRQ => info!(direction, "A-RELEASE-RQ"),
+            Pdu::ReleaseRP => info!(direction, "A-RELEASE-RP"),
+            Pdu::AbortRQ { source } => match source {
+                AbortRQSource::ServiceProvider(reason) => {
+                    info!(direction, source = "service provider", reason = %reason, "A-ABORT")
+                }
+                _ => info!(direction, source = ?source, "A-ABORT"),
+            },
+            Pdu::Unknown { pdu_type, data } => {
+                warn!(direction, pdu_type, length = data.len(), "Unknown PDU");
+            }
+        }
+    }
+
+    fn association_rq(&mut self, direction: &str, rq: &AssociationRQ) {
+        info!(
+            direction,
+            calling_ae_title = rq.calling_ae_title.trim(),
+            called_ae_title = rq.called_ae_title.trim(),
+            application_context = %rq.application_context_name,
+            presentation_contexts = rq.presentation_contexts.len(),
+            "A-ASSOCIATE-RQ"
+        );
+        log_user_variables(direction, &rq.user_variables);
+        self.abstract_syntaxes.clear();
+        self.transfer_syntaxes.clear();
+        for pc in &rq.presentation_contexts {
+            info!(
+                direction,
+                id = pc.id,
+                abstract_syntax = %pc.abstract_syntax,
+                transfer_syntaxes = %pc.transfer_syntaxes.join(", "),
+                "Proposed presentation context"
+            );
+            self.abstract_syntaxes
+                .insert(pc.id, pc.abstract_syntax.clone());
+        }
+    }
+
+    fn association_ac(&mut self, direction: &str, ac: &AssociationAC) {
+        let accepted = ac
+            .presentation_contexts
+            .iter()
+            .filter(|pc| pc.reason == PresentationContextResultReason::Acceptance)
+            .count();
+        info!(
+            direction,
+            calling_ae_title = ac.calling_ae_title.trim(),
+            called_ae_title = ac.called_ae_title.trim(),
+            accepted,
+            rejected = ac.presentation_contexts.len() - accepted,
+            "A-ASSOCIATE-AC"
+        );
+        log_user_variables(direction, &ac.user_variables);
+        for pc in &ac.presentation_contexts {
+            let abstract_syntax = self
+                .abstract_syntaxes
+                .get(&pc.id)
+                .map(|uid| uid.as_str())
+                .unwrap_or("unknown");
+            if pc.reason == PresentationContextResultReason::Acceptance {
+                info!(
+                    direction,
+                    id = pc.id,
+                    abstract_syntax,
+                    transfer_syntax = %pc.transfer_syntax,
+                    "Accepted presentation context"
+                );
+                self.transfer_syntaxes
+                    .insert(pc.id, pc.transfer_syntax.clone());
+            } else {
+                info!(
+                    direction,
+                    id = pc.id,
+                    abstract_syntax,
+                    reason = %pc.reason,
+                    "Rejected presentation context"
+                );
+            }
+        }
+        self.scp_max_pdu_length = ac.user_variables.iter().find_map(|item| match item {
+            UserVariableItem::MaxLength(len) => Some(*len),
+            _ => None,
+        });
+    }
+}
+
+/// Log a complete DIMSE message.
+fn log_message(direction: &str, msg: &DimseMessage) {
+    let command = &msg.command;
+    info!(
+        direction,
+        presentation_context = msg.presentation_context_id,
+        message_id = command.message_id(),
+        responding_to = command.message_id_being_responded_to(),
+        sop_class_uid = command
+            .sop_class_uid()
+            .map(|uid| uid.trim_end_matches('\0')),
+        sop_instance_uid = command
+            .sop_instance_uid()
+            .map(|uid| uid.trim_end_matches('\0')),
+        status = command.status().map(tracing::field::display),
+        dataset_length = msg.data.as_ref().map(|data| data.len()),
+        "{}",
+        command.command_field()
+    );
+}
+
+/// Log the user information items of an association negotiation.
+fn log_user_variables(direction: &str, user_variables: &[UserVariableItem]) {
+    for item in user_variables {
+        match item {
+            UserVariableItem::MaxLength(max_length) => {
+                debug!(direction, max_length, "Maximum length")
+            }
+            UserVariableItem::ImplementationClassUID(uid) => {
+                debug!(direction, uid = %uid, "Implementation class UID")
+            }
+            UserVariableItem::ImplementationVersionName(name) => {
+                debug!(direction, name = %name, "Implementation version name")
+            }
+            UserVariableItem::RoleSelectionSubItem(uid, scu, scp) => {
+                debug!(direction, sop_class_uid = %uid, scu, scp, "Role selection")
+            }
+            UserVariableItem::AsyncOperationsWindow(invoked, performed) => {
+                debug!(
+                    direction,
+                    invoked, performed, "Asynchronous operations window"
+                )
+            }
+            // do not reveal the credentials of the user
+            UserVariableItem::UserIdentityItem(identity) => {
+                debug!(direction, identity_type = ?identity.identity_type(), "User identity")
+            }
+            item => debug!(direction, "{:?}", item),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use dicom_dictionary_std::tags;
+use dicom_object::OpenFileOptions;
+use dicom_ul::{
+    association::trim_padding,
+    commitment::{
+        failure_reason, CommitmentRequest, CommitmentResult, FailedInstance, InstanceReference,
+    },
+    scp::{CommitmentHandler, ServiceContext},
+};
+use tracing::{debug, warn};
+
+/// Storage commitment handler which verifies
+/// that the referenced instances were saved to the output directory
+/// by [`StoreToDirectory`](crate::store::StoreToDirectory).
+#[derive(Debug)]
+pub struct CommitFromDirectory {
+    out_dir: PathBuf,
+    /// network addresses of the requesters to report to on a new association,
+    /// by AE title
+    destinations: HashMap<String, String>,
+}
+
+impl CommitFromDirectory {
+    pub fn new(out_dir: PathBuf) -> Self {
+        CommitFromDirectory {
+            out_dir,
+            destinations: HashMap::new(),
+        }
+    }
+
+    /// Report results to the requester with this AE title
+    /// on a new association to the given address.
+    pub fn with_destination(
+        mut self,
+        ae_title: impl Into<String>,
+        address: impl Into<String>,
+    ) -> Self {
+        self.destinations.insert(ae_title.into(), address.into());
+        self
+    }
+
+    /// Check the file of an instance,
+    /// returning the reason why it cannot be committed to, if any.
+    fn verify(&self, instance: &InstanceReference) -> Result<(), u16> {
+        // the SOP instance UID becomes the file name
+        let uid = &instance.sop_instance_uid;
+        if uid.is_empty() || !uid.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            return Err(failure_reason::NO_SUCH_OBJECT_INSTANCE);
+        }
+        let path = self.out_dir.join(format!("{}.dcm", uid));
+        if !path.is_file() {
+            return Err(failure_reason::NO_SUCH_OBJECT_INSTANCE);
+        }
+        let obj = OpenFileOptions::new()
+            .read_until(tags::PIXEL_DATA)
+            .open_file(&path)
+            .map_err(|e| {
+                warn!("Could not read {}: {}", path.display(), e);
+                failure_reason::PROCESSING_FAILURE
+            })?;
+        let meta = obj.meta();
+        if trim_padding(&meta.media_storage_sop_instance_uid) != uid {
+            return Err(failure_reason::NO_SUCH_OBJECT_INSTANCE);
+        }
+        if trim_padding(&meta.media_storage_sop_class_uid) != instance.sop_class_uid {
+            return Err(failure_reason::CLASS_INSTANCE_CONFLICT);
+        }
+        Ok(())
+    }
+}
+
+impl CommitmentHandler for CommitFromDirectory {
+    fn commit(&self, _ctx: &ServiceContext, request: &CommitmentRequest) -> CommitmentResult {
+        let mut result = CommitmentResult::new(request.transaction_uid.clone());
+        for instance in &request.instances {
+            match self.verify(instance) {
+                Ok(()) => result.committed.push(instance.clone()),
+                Err(reason) => {
+                    debug!(
+                        "Cannot commit to {}: failure reason {:04X}H",
+                        instance.sop_instance_uid, reason
+                    );
+                    result
+                        .failed
+                        .push(FailedInstance::new(instance.clone(), reason));
+                }
+            }
+        }
+        result
+    }
+
+    fn report_destination(&self, ae_title: &str) -> Option<String> {
+        self.destinations.get(ae_title).cloned()
+    }
+}
@@ -3,18 +3,23 @@ use std::{
     path::PathBuf,
 };
 
-use clap::Parser;
-use dicom_core::{dicom_value, DataElement, VR};
-use dicom_dictionary_std::tags;
-use dicom_object::{InMemDicomObject, StandardDataDictionary};
-use snafu::Report;
+use clap::{Args, Parser};
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use dicom_ul::{
+    association::server::AccessControl,
+    auth::{Authenticator, JwtAuthenticator, PasswordAuthenticator, RequireAuthentication},
+    registry::AeRegistry,
+    scp::{AcceptEcho, ServiceClassProvider},
+    tls::{TlsOptions, TlsProfile},
+};
 use tracing::{error, info, Level};
 
-mod store_async;
-mod store_sync;
+mod commit;
+mod store;
 mod transfer;
-use store_async::run_store_async;
-use store_sync::run_store_sync;
+use commit::CommitFromDirectory;
+use store::StoreToDirectory;
+use transfer::ABSTRACT_SYNTAXES;
 
 /// DICOM C-STORE SCP
 #[derive(Debug, Parser)]
@@ -35,6 +40,11 @@ struct App {
     /// Accept unknown SOP classes
     #[arg(long)]
     promiscuous: bool,
+    /// Only accept these transfer syntaxes for this SOP class,
+    /// preferred in the given order,
+    /// as SOP_CLASS_UID=TS_UID[,TS_UID...] (can be repeated)
+    #[arg(long = "prefer-ts", value_parser = parse_ts_preference)]
+    prefer_ts: Vec<(String, Vec<String>)>,
     /// Maximum PDU length
     #[arg(
         short = 'm',
@@ -49,57 +59,99 @@ struct App {
     /// Which port to listen on
     #[arg(short, default_value = "11111")]
     port: u16,
-    /// Run in non-blocking mode (spins up an async task to handle each incoming stream)
+    /// Run in non-blocking mode (accepts incoming streams asynchronously)
     #[arg(short, long)]
     non_blocking: bool,
+    /// Report storage commitment results to this requester
+    /// on a new association, as AE_TITLE=HOST:PORT (can be repeated)
+    #[arg(long = "commitment-destination", value_parser = parse_destination)]
+    commitment_destination: Vec<(String, String)>,
+    /// Only accept associations from the nodes described
+    /// in this AE configuration file (TOML or JSON),
+    /// with their credentials and for their SOP classes
+    #[arg(long = "ae-config")]
+    ae_config: Option<PathBuf>,
+    #[command(flatten)]
+    auth: AuthArgs,
+    #[command(flatten)]
+    tls: TlsArgs,
+}
+
+/// User authentication options
+#[derive(Debug, Args)]
+struct AuthArgs {
+    /// Require requesters to authenticate with a username and password
+    /// from this file, with one `username:password` per line
+    #[arg(long = "users")]
+    users: Option<PathBuf>,
+    /// Require requesters to authenticate with a JSON Web Token
+    /// signed with the public key in this PEM file (can be repeated)
+    #[arg(long = "jwt-key")]
+    jwt_keys: Vec<PathBuf>,
+    /// Require requesters to authenticate with a JSON Web Token
+    /// signed with the HMAC secret in this file
+    #[arg(long = "jwt-secret")]
+    jwt_secret: Option<PathBuf>,
+    /// Only accept JSON Web Tokens from this issuer
+    #[arg(long = "jwt-issuer")]
+    jwt_issuer: Option<String>,
+    /// Only accept JSON Web Tokens for this audience
+    #[arg(long = "jwt-audience")]
+    jwt_audience: Option<String>,
 }
 
-fn create_cstore_response(
-    message_id: u16,
-    sop_class_uid: &str,
-    sop_instance_uid: &str,
-) -> InMemDicomObject<StandardDataDictionary> {
-    InMemDicomObject::command_from_element_iter([
-        DataElement::new(
-            tags::AFFECTED_SOP_CLASS_UID,
-            VR::UI,
-            dicom_value!(Str, sop_class_uid),
-        ),
-        DataElement::new(tags::COMMAND_FIELD, VR::US, dicom_value!(U16, [0x8001])),
-        DataElement::new(
-            tags::MESSAGE_ID_BEING_RESPONDED_TO,
-            VR::US,
-            dicom_value!(U16, [message_id]),
-        ),
-        DataElement::new(
-            tags::COMMAND_DATA_SET_TYPE,
-            VR::US,
-            dicom_value!(U16, [0x0101]),
-        ),
-        DataElement::new(tags::STATUS, VR::US, dicom_value!(U16, [0x0000])),
-        DataElement::new(
-            tags::AFFECTED_SOP_INSTANCE_UID,
-            VR::UI,
-            dicom_value!(Str, sop_instance_uid),
-        ),
-    ])
+/// Secure transport options
+#[derive(Debug, Args)]
+struct TlsArgs {
+    /// Accept associations over TLS only
+    #[arg(long = "tls", requires = "cert", requires = "key")]
+    tls: bool,
+    /// PEM file with the certificate chain of this node
+    #[arg(long = "tls-cert", requires = "tls")]
+    cert: Option<PathBuf>,
+    /// PEM file with the private key of this node
+    #[arg(long = "tls-key", requires = "tls")]
+    key: Option<PathBuf>,
+    /// PEM file with the CA certificates trusted to verify SCU certificates
+    #[arg(long = "tls-ca", requires = "tls")]
+    ca: Option<PathBuf>,
+    /// Require SCUs to present a certificate signed by a trusted CA
+    #[arg(long = "tls-require-client-auth", requires = "ca")]
+    require_client_auth: bool,
+    /// TLS secure transport connection profile
+    /// (bcp195, non-downgrading, or extended) [default: bcp195]
+    #[arg(long = "tls-profile", requires = "tls")]
+    profile: Option<TlsProfile>,
+}
+
+fn parse_destination(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((ae_title, address)) if !ae_title.is_empty() && !address.is_empty() => {
+            Ok((ae_title.to_string(), address.to_string()))
+        }
+        _ => Err(format!("expected AE_TITLE=HOST:PORT, got `{}`", s)),
+    }
 }
 
-fn create_cecho_response(message_id: u16) -> InMemDicomObject<StandardDataDictionary> {
-    InMemDicomObject::command_from_element_iter([
-        DataElement::new(tags::COMMAND_FIELD, VR::US, dicom_value!(U16, [0x8030])),
-        DataElement::new(
-            tags::MESSAGE_ID_BEING_RESPONDED_TO,
-            VR::US,
-            dicom_value!(U16, [message_id]),
-        ),
-        DataElement::new(
-            tags::COMMAND_DATA_SET_TYPE,
-            VR::US,
-            dicom_value!(U16, [0x0101]),
-        ),
-        DataElement::new(tags::STATUS, VR::US, dicom_value!(U16, [0x0000])),
-    ])
+fn parse_ts_preference(s: &str) -> Result<(String, Vec<String>), String> {
+    match s.split_once('=') {
+        Some((sop_class, transfer_syntaxes))
+            if !sop_class.is_empty() && !transfer_syntaxes.is_empty() =>
+        {
+            let transfer_syntaxes: Vec<_> = transfer_syntaxes
+                .split(',')
+                .map(|ts| ts.trim().to_string())
+                .collect();
+            if transfer_syntaxes.iter().any(|ts| ts.is_empty()) {
+                return Err(format!("empty transfer syntax UID in `{}`", s));
+            }
+            Ok((sop_class.trim().to_string(), transfer_syntaxes))
+        }
+        _ => Err(format!(
+            "expected SOP_CLASS_UID=TS_UID[,TS_UID...], got `{}`",
+            s
+        )),
+    }
 }
 
 fn main() {
@@ -123,9 +175,141 @@ fn main() {
     }
 }
 
+/// Build the service class provider for the given options,
+/// accepting verification, storage and storage commitment requests.
+fn build_scp(args: &App) -> Result<ServiceClassProvider, Box<dyn std::error::Error>> {
+    let mut scp = ServiceClassProvider::new()
+        .ae_title(&args.calling_ae_title)
+        .strict(args.strict)
+        .max_pdu_length(args.max_pdu_length)
+        .promiscuous(args.promiscuous);
+
+    if args.uncompressed_only {
+        scp = scp
+            .with_transfer_syntax("1.2.840.10008.1.2")
+            .with_transfer_syntax("1.2.840.10008.1.2.1");
+    } else {
+        for ts in TransferSyntaxRegistry.iter() {
+            if !ts.is_unsupported() {
+                scp = scp.with_transfer_syntax(ts.uid());
+            }
+        }
+    };
+
+    for (sop_class, transfer_syntaxes) in &args.prefer_ts {
+        if args.uncompressed_only {
+            for ts in transfer_syntaxes {
+                if ts != "1.2.840.10008.1.2" && ts != "1.2.840.10008.1.2.1" {
+                    return Err(format!(
+                        "transfer syntax {} preferred for {} is not uncompressed",
+                        ts, sop_class
+                    )
+                    .into());
+                }
+            }
+        }
+        scp = scp.with_transfer_syntax_preference(sop_class, transfer_syntaxes);
+    }
+
+    if args.tls.tls {
+        let mut tls_options = TlsOptions::new()
+            .profile(args.tls.profile.unwrap_or_default())
+            .require_client_auth(args.tls.require_client_auth);
+        if let (Some(cert), Some(key)) = (&args.tls.cert, &args.tls.key) {
+            tls_options = tls_options.certificate_chain(cert).private_key(key);
+        }
+        if let Some(ca) = &args.tls.ca {
+            tls_options = tls_options.ca_certificates(ca);
+        }
+        scp = scp.tls_config(tls_options.server_config()?);
+    }
+
+    let store = StoreToDirectory::new(args.out_dir.clone());
+    let mut commit = CommitFromDirectory::new(args.out_dir.clone());
+    for (ae_title, address) in &args.commitment_destination {
+        commit = commit.with_destination(ae_title, address);
+    }
+    Ok(scp
+        .with_echo_handler(AcceptEcho)
+        .with_store_handler(ABSTRACT_SYNTAXES.iter().copied(), store)
+        .with_default_store_handler(StoreToDirectory::new(args.out_dir.clone()))
+        .with_commitment_handler(commit))
+}
+
+/// Load the registry of the nodes which may request associations,
+/// if one was given.
+fn load_registry(args: &App) -> Result<Option<AeRegistry>, Box<dyn std::error::Error>> {
+    Ok(args
+        .ae_config
+        .as_ref()
+        .map(AeRegistry::from_file)
+        .transpose()?)
+}
+
+/// Build the authenticator of requesters' user identities,
+/// if any means of authentication was given.
+fn load_authenticator(
+    args: &AuthArgs,
+) -> Result<Option<Box<dyn Authenticator + Send + Sync>>, Box<dyn std::error::Error>> {
+    let mut authenticator: Option<Box<dyn Authenticator + Send + Sync>> = None;
+
+    if let Some(path) = &args.users {
+        let mut passwords = PasswordAuthenticator::new();
+        for (i, line) in std::fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (username, password) = line.split_once(':').ok_or_else(|| {
+                format!("{}:{}: expected `username:password`", path.display(), i + 1)
+            })?;
+            passwords = passwords.with_user(username, password);
+        }
+        authenticator = Some(Box::new(passwords));
+    }
+
+    if !args.jwt_keys.is_empty() || args.jwt_secret.is_some() {
+        let mut jwt = JwtAuthenticator::new();
+        for path in &args.jwt_keys {
+            jwt = jwt.with_public_key_pem(&std::fs::read(path)?)?;
+        }
+        if let Some(path) = &args.jwt_secret {
+            jwt = jwt.with_hmac_secret(std::fs::read_to_string(path)?.trim_end().as_bytes());
+        }
+        if let Some(issuer) = &args.jwt_issuer {
+            jwt = jwt.issuer(issuer);
+        }
+        if let Some(audience) = &args.jwt_audience {
+            jwt = jwt.audience(audience);
+        }
+        authenticator = Some(match authenticator {
+            Some(passwords) => Box::new(passwords.or(jwt)),
+            None => Box::new(jwt),
+        });
+    }
+
+    Ok(authenticator)
+}
+
+/// Combine the registry of known nodes and user authentication
+/// into the access control policy of this SCP,
+/// or `None` to accept any node.
+fn load_access_control(
+    args: &App,
+) -> Result<Option<Box<dyn AccessControl + Send + Sync>>, Box<dyn std::error::Error>> {
+    let registry = load_registry(args)?;
+    let authenticator = load_authenticator(&args.auth)?;
+    Ok(match (authenticator, registry) {
+        (Some(authenticator), Some(registry)) => Some(Box::new(
+            RequireAuthentication::new(authenticator).with_access_control(registry),
+        )),
+        (Some(authenticator), None) => Some(Box::new(RequireAuthentication::new(authenticator))),
+        (None, Some(registry)) => Some(Box::new(registry)),
+        (None, None) => None,
+    })
+}
+
 async fn run_async(args: App) -> Result<(), Box<dyn std::error::Error>> {
-    use std::sync::Arc;
-    let args = Arc::new(args);
     tracing::subscriber::set_global_default(
         tracing_subscriber::FmtSubscriber::builder()
             .with_max_level(if args.verbose {
@@ -147,6 +331,9 @@ async fn run_async(args: App) -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(-2);
     });
 
+    let scp = build_scp(&args)?;
+    let access_control = load_access_control(&args)?;
+
     let listen_addr = SocketAddrV4::new(Ipv4Addr::from(0), args.port);
     let listener = tokio::net::TcpListener::bind(listen_addr).await?;
     info!(
@@ -154,15 +341,15 @@ async fn run_async(args: App) -> Result<(), Box<dyn std::error::Error>> {
         &args.calling_ae_title, listen_addr
     );
 
-    loop {
-        let (socket, _addr) = listener.accept().await?;
-        let args = args.clone();
-        tokio::task::spawn(async move {
-            if let Err(e) = run_store_async(socket, &args).await {
-                error!("{}", Report::from_error(e));
-            }
-        });
+    match access_control {
+        Some(access_control) => {
+            scp.ae_access_control(access_control)
+                .serve_async(listener)
+                .await
+        }
+        None => scp.serve_async(listener).await,
     }
+    Ok(())
 }
 
 fn run_sync(args: App) -> Result<(), Box<dyn std::error::Error>> {
@@ -187,6 +374,9 @@ fn run_sync(args: App) -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(-2);
     });
 
+    let scp = build_scp(&args)?;
+    let access_control = load_access_control(&args)?;
+
     let listen_addr = SocketAddrV4::new(Ipv4Addr::from(0), args.port);
     let listener = std::net::TcpListener::bind(listen_addr)?;
     info!(
@@ -194,19 +384,10 @@ fn run_sync(args: App) -> Result<(), Box<dyn std::error::Error>> {
         &args.calling_ae_title, listen_addr
     );
 
-    for stream in listener.incoming() {
-        match stream {
-            Ok(scu_stream) => {
-                if let Err(e) = run_store_sync(scu_stream, &args) {
-                    error!("{}", snafu::Report::from_error(e));
-                }
-            }
-            Err(e) => {
-                error!("{}", snafu::Report::from_error(e));
-            }
-        }
+    match access_control {
+        Some(access_control) => scp.ae_access_control(access_control).serve(listener),
+        None => scp.serve(listener),
     }
-
     Ok(())
 }
 
@@ -0,0 +1,177 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::PathBuf,
+};
+
+use dicom_dictionary_std::tags;
+use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
+use dicom_ul::{
+    dimse::{composite::CStoreRq, Status},
+    scp::{ServiceContext, StoreHandler},
+};
+use snafu::{Report, ResultExt, Whatever};
+use tracing::{info, warn};
+
+/// Storage handler which saves every incoming object
+/// to a file named after its SOP Instance UID.
+#[derive(Debug)]
+pub struct StoreToDirectory {
+    out_dir: PathBuf,
+}
+
+impl StoreToDirectory {
+    pub fn new(out_dir: PathBuf) -> Self {
+        StoreToDirectory { out_dir }
+    }
+
+    /// The path of the file to save an object to,
+    /// or `None` if its SOP Instance UID cannot be used as a file name.
+    ///
+    /// Only digits and dots are allowed,
+    /// so that the file cannot be placed outside of the output directory.
+    fn file_path(&self, sop_instance_uid: &str) -> Option<PathBuf> {
+        if sop_instance_uid.is_empty()
+            || !sop_instance_uid
+                .bytes()
+                .all(|c| c.is_ascii_digit() || c == b'.')
+        {
+            return None;
+        }
+        Some(self.out_dir.join(format!("{}.dcm", sop_instance_uid)))
+    }
+
+    fn save(
+        &self,
+        ctx: &ServiceContext,
+        file_path: PathBuf,
+        obj: InMemDicomObject,
+    ) -> Result<PathBuf, Whatever> {
+        let file_meta = FileMetaTableBuilder::new()
+            .media_storage_sop_class_uid(
+                obj.element(tags::SOP_CLASS_UID)
+                    .whatever_context("missing SOP Class UID")?
+                    .to_str()
+                    .whatever_context("could not retrieve SOP Class UID")?,
+            )
+            .media_storage_sop_instance_uid(
+                obj.element(tags::SOP_INSTANCE_UID)
+                    .whatever_context("missing SOP Instance UID")?
+                    .to_str()
+                    .whatever_context("missing SOP Instance UID")?,
+            )
+            .transfer_syntax(ctx.transfer_syntax().uid())
+            .build()
+            .whatever_context("failed to build DICOM meta file information")?;
+        let file_obj = obj.with_exact_meta(file_meta);
+
+        file_obj
+            .write_to_file(&file_path)
+            .whatever_context("could not save DICOM object to file")?;
+        Ok(file_path)
+    }
+
+    /// Save the encoded data set as it is received,
+    /// without decoding it in memory.
+    fn save_from(
+        &self,
+        ctx: &ServiceContext,
+        rq: &CStoreRq,
+        file_path: PathBuf,
+        data: &mut dyn Read,
+    ) -> Result<PathBuf, Whatever> {
+        let file_meta = FileMetaTableBuilder::new()
+            .media_storage_sop_class_uid(rq.affected_sop_class_uid.as_str())
+            .media_storage_sop_instance_uid(rq.affected_sop_instance_uid.as_str())
+            .transfer_syntax(ctx.transfer_syntax().uid())
+            .build()
+            .whatever_context("failed to build DICOM meta file information")?;
+
+        // write to a temporary file first,
+        // so that an interrupted transfer leaves no partial object behind
+        let part_path = file_path.with_extension("dcm.part");
+
+        let written = File::create(&part_path)
+            .whatever_context("could not create file")
+            .and_then(|file| {
+                let mut to = BufWriter::new(file);
+                to.write_all(&[0_u8; 128][..])
+                    .and_then(|_| to.write_all(b"DICM"))
+                    .whatever_context("could not write file preamble")?;
+                file_meta
+                    .write(&mut to)
+                    .whatever_context("could not write file meta group")?;
+                std::io::copy(data, &mut to).whatever_context("could not write data set")?;
+                to.flush().whatever_context("could not write data set")
+            })
+            .and_then(|_| {
+                std::fs::rename(&part_path, &file_path)
+                    .whatever_context("could not save DICOM object to file")
+            });
+        if written.is_err() {
+            let _ = std::fs::remove_file(&part_path);
+        }
+        written.map(|_| file_path)
+    }
+}
+
+impl StoreHandler for StoreToDirectory {
+    fn store(&self, ctx: &ServiceContext, rq: &CStoreRq, obj: InMemDicomObject) -> Status {
+        let Some(file_path) = self.file_path(&rq.affected_sop_instance_uid) else {
+            warn!(
+                "Refusing to store object with invalid SOP Instance UID {:?}",
+                rq.affected_sop_instance_uid
+            );
+            return Status::INVALID_OBJECT_INSTANCE;
+        };
+        match self.save(ctx, file_path, obj) {
+            Ok(file_path) => {
+                info!("Stored {}", file_path.display());
+                Status::SUCCESS
+            }
+            Err(e) => {
+                warn!("{}", Report::from_error(e));
+                Status::OUT_OF_RESOURCES
+            }
+        }
+    }
+
+    fn store_from(&self, ctx: &ServiceContext, rq: &CStoreRq, data: &mut dyn Read) -> Status {
+        let Some(file_path) = self.file_path(&rq.affected_sop_instance_uid) else {
+            warn!(
+                "Refusing to store object with invalid SOP Instance UID {:?}",
+                rq.affected_sop_instance_uid
+            );
+            return Status::INVALID_OBJECT_INSTANCE;
+        };
+        match self.save_from(ctx, rq, file_path, data) {
+            Ok(file_path) => {
+                info!("Stored {}", file_path.display());
+                Status::SUCCESS
+            }
+            Err(e) => {
+                warn!("{}", Report::from_error(e));
+                Status::OUT_OF_RESOURCES
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StoreToDirectory;
+    use std::path::PathBuf;
+
+    #[test]
+    fn file_path_only_from_valid_uids() {
+        let store = StoreToDirectory::new(PathBuf::from("out"));
+        assert_eq!(
+            store.file_path("1.2.826.0.1.3680043.9.1234"),
+            Some(PathBuf::from("out").join("1.2.826.0.1.3680043.9.1234.dcm"))
+        );
+        assert_eq!(store.file_path(""), None);
+        assert_eq!(store.file_path("../../etc/passwd"), None);
+        assert_eq!(store.file_path("1.2.3/../../4"), None);
+        assert_eq!(store.file_path("/tmp/1.2.3"), None);
+    }
+}
@@ -0,0 +1,120 @@
+use std::net::{Ipv4Addr, TcpListener};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use dicom_ul::{
+    association::{ClientAssociation, Transport},
+    commitment::{
+        receive_result, request_commitment, CommitmentRequest, CommitmentResult, InstanceReference,
+        ResultCollector, STORAGE_COMMITMENT_PUSH_MODEL,
+    },
+    scp::ServiceClassProvider,
+};
+use snafu::ResultExt;
+use tracing::{info, warn};
+
+use crate::{CommitmentListenSnafu, CommitmentSnafu, CommitmentTimeoutSnafu, Error, ScuSnafu};
+
+/// Where to wait for the result of a storage commitment request.
+pub enum ResultFrom {
+    /// the same association
+    SameAssociation,
+    /// a new association from the SCP to this port,
+    /// within the given time
+    Port(u16, Duration),
+}
+
+/// Create a new transaction UID, derived from a UUID.
+fn new_transaction_uid() -> String {
+    format!("2.25.{}", uuid::Uuid::new_v4().as_u128())
+}
+
+/// Request storage commitment for the given instances
+/// and wait for the result,
+/// releasing the association.
+pub fn commit<S: Transport>(
+    mut scu: ClientAssociation<S>,
+    instances: Vec<InstanceReference>,
+    message_id: u16,
+    calling_ae_title: &str,
+    result_from: ResultFrom,
+) -> Result<CommitmentResult, Error> {
+    let request = CommitmentRequest::new(new_transaction_uid(), instances);
+    info!(
+        "Requesting storage commitment {} for {} instances",
+        request.transaction_uid,
+        request.instances.len()
+    );
+
+    match result_from {
+        ResultFrom::SameAssociation => {
+            request_commitment(&mut scu, message_id, &request).context(CommitmentSnafu)?;
+            let result = receive_result(&mut scu).context(CommitmentSnafu)?;
+            scu.release().map_err(Box::from).context(ScuSnafu)?;
+            Ok(result)
+        }
+        ResultFrom::Port(port, timeout) => {
+            // listen before requesting,
+            // so that the SCP can report at any time after the release
+            let listener =
+                TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).context(CommitmentListenSnafu)?;
+            request_commitment(&mut scu, message_id, &request).context(CommitmentSnafu)?;
+            scu.release().map_err(Box::from).context(ScuSnafu)?;
+            info!("Waiting for the storage commitment result on port {}", port);
+            wait_for_result(
+                listener,
+                calling_ae_title,
+                &request.transaction_uid,
+                timeout,
+            )
+        }
+    }
+}
+
+/// Accept associations from the SCP
+/// until the result of the given transaction is reported.
+fn wait_for_result(
+    listener: TcpListener,
+    ae_title: &str,
+    transaction_uid: &str,
+    timeout: Duration,
+) -> Result<CommitmentResult, Error> {
+    let collector = ResultCollector::new();
+    let receiver = ServiceClassProvider::new()
+        .ae_title(ae_title)
+        .with_normalized_handler([STORAGE_COMMITMENT_PUSH_MODEL], collector.clone());
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!("Failed to accept connection: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = receiver.handle(stream) {
+                warn!("{}", snafu::Report::from_error(e));
+            }
+            for result in collector.take() {
+                if tx.send(result).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok(result) if result.transaction_uid == transaction_uid => return Ok(result),
+            Ok(result) => warn!(
+                "Ignoring result of unknown storage commitment {}",
+                result.transaction_uid
+            ),
+            Err(_) => return CommitmentTimeoutSnafu.fail(),
+        }
+    }
+}
@@ -1,14 +1,20 @@
-use clap::Parser;
-use dicom_core::{dicom_value, header::Tag, DataElement, VR};
-use dicom_dictionary_std::{tags, uids};
+use clap::{Args, Parser};
+use dicom_core::header::Tag;
+use dicom_dictionary_std::uids;
 use dicom_encoding::transfer_syntax;
 use dicom_encoding::TransferSyntax;
-use dicom_object::{mem::InMemDicomObject, DefaultDicomObject, StandardDataDictionary};
+use dicom_object::DefaultDicomObject;
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use dicom_ul::{
+    association::{pipeline::Pipeline, ClientAssociation, ClientAssociationOptions, Transport},
+    commitment::{InstanceReference, STORAGE_COMMITMENT_PUSH_MODEL},
+    registry::{AeEntry, AeRegistry},
+    tls::{rustls::ClientConfig, TlsOptions, TlsProfile},
+};
 use indicatif::{ProgressBar, ProgressStyle};
 use snafu::prelude::*;
 use snafu::{Report, Whatever};
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -18,6 +24,9 @@ use tracing::{debug, error, info, warn, Level};
 use transfer_syntax::TransferSyntaxIndex;
 use walkdir::WalkDir;
 
+use crate::commit::ResultFrom;
+
+mod commit;
 mod store_async;
 mod store_sync;
 
@@ -27,8 +36,13 @@ mod store_sync;
 struct App {
     /// socket address to Store SCP,
     /// optionally with AE title
-    /// (example: "STORE-SCP@127.0.0.1:104")
+    /// (example: "STORE-SCP@127.0.0.1:104"),
+    /// or the name of a node in the AE configuration
     addr: String,
+    /// AE configuration file describing the known nodes, in TOML or JSON
+    /// [default: $DICOM_AE_CONFIG]
+    #[arg(long = "ae-config")]
+    ae_config: Option<PathBuf>,
     /// the DICOM file(s) to store
     #[arg(required = true)]
     files: Vec<PathBuf>,
@@ -36,6 +50,7 @@ struct App {
     #[arg(short = 'v', long = "verbose")]
     verbose: bool,
     /// the C-STORE message ID
+    /// (incremented for each request when sending many at once)
     #[arg(short = 'm', long = "message-id", default_value = "1")]
     message_id: u16,
     /// the calling Application Entity title
@@ -98,6 +113,56 @@ struct App {
     /// Dispatch these many service users to send files in parallel
     #[arg(short = 'c', long = "concurrency")]
     concurrency: Option<usize>,
+    /// Propose to keep up to these many C-STORE requests outstanding
+    /// before waiting for their responses
+    /// (0 for no limit, the SCP may accord fewer)
+    #[arg(
+        long = "max-operations",
+        default_value = "1",
+        conflicts_with = "concurrency"
+    )]
+    max_operations: u16,
+    /// request storage commitment for the files sent
+    /// and wait for the result
+    #[arg(long = "commit", conflicts_with = "concurrency")]
+    commit: bool,
+    /// receive the storage commitment result on a new association,
+    /// listening on this port after releasing the association
+    /// [default: receive on the same association]
+    #[arg(long = "commit-port", requires = "commit")]
+    commit_port: Option<u16>,
+    /// how long to wait for the storage commitment result
+    /// on a new association, in seconds
+    #[arg(long = "commit-timeout", default_value = "60")]
+    commit_timeout: u64,
+    #[command(flatten)]
+    tls: TlsArgs,
+}
+
+/// Secure transport options
+#[derive(Debug, Args)]
+struct TlsArgs {
+    /// secure the association with TLS
+    #[arg(long = "tls")]
+    tls: bool,
+    /// PEM file with the certificate chain of this node,
+    /// for client authentication
+    #[arg(long = "tls-cert", requires = "tls", requires = "key")]
+    cert: Option<PathBuf>,
+    /// PEM file with the private key of this node
+    #[arg(long = "tls-key", requires = "tls", requires = "cert")]
+    key: Option<PathBuf>,
+    /// PEM file with the CA certificates trusted to verify the SCP
+    #[arg(long = "tls-ca", requires = "tls")]
+    ca: Option<PathBuf>,
+    /// the TLS secure transport connection profile
+    /// (bcp195, non-downgrading, or extended) [default: bcp195]
+    #[arg(long = "tls-profile", requires = "tls")]
+    profile: Option<TlsProfile>,
+    /// the name to verify the SCP's certificate against
+    /// [default: host name in the address]
+    #[arg(long = "tls-server-name", requires = "tls")]
+    server_name: Option<String>,
 }
 
 struct DicomFile {
@@ -123,14 +188,10 @@ enum Error {
     },
 
     /// Could not construct DICOM command
-    CreateCommand {
-        source: Box<dicom_object::WriteError>,
-    },
+    CreateCommand { source: Box<dicom_ul::dimse::Error> },
 
     /// Unsupported file transfer syntax {uid}
-    UnsupportedFileTransferSyntax {
-        uid: std::borrow::Cow<'static, str>,
-    },
+    UnsupportedFileTransferSyntax { uid: std::borrow::Cow<'static, str> },
 
     /// Unsupported file
     FileNotSupported,
@@ -140,6 +201,11 @@ enum Error {
         path: String,
         source: Box<dicom_object::ReadError>,
     },
+    /// Error reading the data set of file {path}
+    ReadDataSet {
+        path: String,
+        source: std::io::Error,
+    },
     /// No matching presentation contexts
     NoPresentationContext,
     /// No TransferSyntax
@@ -152,20 +218,26 @@ enum Error {
     WriteDataset {
         source: Box<dicom_object::WriteError>,
     },
-    ReadDataset {
-        source: dicom_object::ReadError,
-    },
-    MissingAttribute {
-        tag: Tag,
-        source: dicom_object::AccessError,
-    },
-    ConvertField {
-        tag: Tag,
-        source: dicom_core::value::ConvertValueError,
-    },
-    WriteIO {
-        source: std::io::Error,
+    /// Unexpected response from the SCP
+    ReadResponse { source: Box<dicom_ul::dimse::Error> },
+    /// Could not exchange pipelined requests
+    Pipeline {
+        source: dicom_ul::association::pipeline::Error,
     },
+    /// Could not set up TLS
+    Tls { source: dicom_ul::tls::Error },
+    /// Could not load the AE configuration
+    AeConfig { source: dicom_ul::registry::Error },
+    /// No address configured for node {name}
+    NodeAddress { name: String },
+    /// Could not request storage commitment
+    Commitment { source: dicom_ul::commitment::Error },
+    /// Could not listen for the storage commitment result
+    CommitmentListen { source: std::io::Error },
+    /// Timed out waiting for the storage commitment result
+    CommitmentTimeout,
+    /// {failed} instances could not be committed
+    NotCommitted { failed: usize },
 }
 
 fn main() {
@@ -192,14 +264,113 @@ fn main() {
     }
 }
 
+/// Resolve the SCP address,
+/// which may name a node in the AE configuration.
+fn resolve_node(
+    addr: String,
+    ae_config: Option<&Path>,
+) -> Result<(String, Option<AeEntry>), Error> {
+    let registry = AeRegistry::locate(ae_config).context(AeConfigSnafu)?;
+    match registry.as_ref().and_then(|registry| registry.get(&addr)) {
+        Some(node) => {
+            let addr = node.address().context(NodeAddressSnafu { name: addr })?;
+            Ok((addr, Some(node.clone())))
+        }
+        None => Ok((addr, None)),
+    }
+}
+
+/// Prepare the options for establishing an association with the SCP.
+#[allow(clippy::too_many_arguments)]
+fn scu_options(
+    node: Option<&AeEntry>,
+    calling_ae_title: String,
+    called_ae_title: Option<String>,
+    max_pdu_length: u32,
+    username: Option<String>,
+    password: Option<String>,
+    kerberos_service_ticket: Option<String>,
+    saml_assertion: Option<String>,
+    jwt: Option<String>,
+    presentation_contexts: &BTreeSet<(String, String)>,
+    commit: bool,
+) -> Result<ClientAssociationOptions<'static>, Error> {
+    let mut scu_init = ClientAssociationOptions::new()
+        .calling_ae_title(calling_ae_title)
+        .max_pdu_length(max_pdu_length);
+
+    if let Some(node) = node {
+        scu_init = node.configure(scu_init).context(AeConfigSnafu)?;
+    }
+
+    for (storage_sop_class_uid, transfer_syntax) in presentation_contexts {
+        scu_init = scu_init.with_presentation_context(
+            storage_sop_class_uid.clone(),
+            vec![transfer_syntax.clone()],
+        );
+    }
+
+    if commit {
+        scu_init = scu_init.with_presentation_context(
+            STORAGE_COMMITMENT_PUSH_MODEL,
+            vec![
+                uids::EXPLICIT_VR_LITTLE_ENDIAN,
+                uids::IMPLICIT_VR_LITTLE_ENDIAN,
+            ],
+        );
+    }
+
+    if let Some(called_ae_title) = called_ae_title {
+        scu_init = scu_init.called_ae_title(called_ae_title);
+    }
+
+    if let Some(username) = username {
+        scu_init = scu_init.username(username);
+    }
+
+    if let Some(password) = password {
+        scu_init = scu_init.password(password);
+    }
+
+    if let Some(kerberos_service_ticket) = kerberos_service_ticket {
+        scu_init = scu_init.kerberos_service_ticket(kerberos_service_ticket);
+    }
+
+    if let Some(saml_assertion) = saml_assertion {
+        scu_init = scu_init.saml_assertion(saml_assertion);
+    }
+
+    if let Some(jwt) = jwt {
+        scu_init = scu_init.jwt(jwt);
+    }
+
+    Ok(scu_init)
+}
+
+/// Build the TLS configuration of the SCU, if TLS was requested.
+fn tls_config(tls: &TlsArgs) -> Result<Option<Arc<ClientConfig>>, Error> {
+    if !tls.tls {
+        return Ok(None);
+    }
+    let mut tls_options = TlsOptions::new().profile(tls.profile.unwrap_or_default());
+    if let Some(ca) = &tls.ca {
+        tls_options = tls_options.ca_certificates(ca);
+    }
+    if let (Some(cert), Some(key)) = (&tls.cert, &tls.key) {
+        tls_options = tls_options.certificate_chain(cert).private_key(key);
+    }
+    tls_options.client_config().map(Some).context(TlsSnafu)
+}
+
 fn check_files(
     files: Vec<PathBuf>,
     verbose: bool,
     never_transcode: bool,
-) -> (Vec<DicomFile>, HashSet<(String, String)>) {
+) -> (Vec<DicomFile>, BTreeSet<(String, String)>) {
     let mut checked_files: Vec<PathBuf> = vec![];
     let mut dicom_files: Vec<DicomFile> = vec![];
-    let mut presentation_contexts = HashSet::new();
+    // kept sorted, so that presentation context IDs are the same on every run
+    let mut presentation_contexts = BTreeSet::new();
 
     for file in files {
         if file.is_dir() {
@@ -257,9 +428,9 @@ fn check_files(
 }
 
 fn run(app: App) -> Result<(), Error> {
-    use crate::store_sync::{get_scu, send_file};
     let App {
         addr,
+        ae_config,
         files,
         verbose,
         message_id,
@@ -274,6 +445,11 @@ fn run(app: App) -> Result<(), Error> {
         saml_assertion,
         jwt,
         concurrency: _,
+        max_operations,
+        commit,
+        commit_port,
+        commit_timeout,
+        tls,
     } = app;
 
     // never transcode if the feature is disabled
@@ -291,14 +467,24 @@ fn run(app: App) -> Result<(), Error> {
         eprintln!("[ERROR] {}", Report::from_error(e));
     });
 
+    let (addr, node) = resolve_node(addr, ae_config.as_deref())?;
     if verbose {
         info!("Establishing association with '{}'...", &addr);
     }
-    let (mut dicom_files, presentation_contexts) = check_files(files, verbose, never_transcode);
+    let (dicom_files, presentation_contexts) = check_files(files, verbose, never_transcode);
+    let tls_config = tls_config(&tls)?;
+    let commit = if commit {
+        Some(match commit_port {
+            Some(port) => ResultFrom::Port(port, Duration::from_secs(commit_timeout)),
+            None => ResultFrom::SameAssociation,
+        })
+    } else {
+        None
+    };
 
-    let mut scu = get_scu(
-        addr,
-        calling_ae_title,
+    let scu_init = scu_options(
+        node.as_ref(),
+        calling_ae_title.clone(),
         called_ae_title,
         max_pdu_length,
         username,
@@ -306,17 +492,81 @@ fn run(app: App) -> Result<(), Error> {
         kerberos_service_ticket,
         saml_assertion,
         jwt,
-        presentation_contexts,
+        &presentation_contexts,
+        commit.is_some(),
     )?;
+    let scu_init = if max_operations != 1 {
+        scu_init.async_operations_window(max_operations, 1)
+    } else {
+        scu_init
+    };
+
+    if tls_config.is_some() || node.as_ref().is_some_and(AeEntry::uses_tls) {
+        let mut scu_init = scu_init;
+        if let Some(tls_config) = tls_config {
+            scu_init = scu_init.tls_config(tls_config);
+        }
+        if let Some(server_name) = tls.server_name {
+            scu_init = scu_init.server_name(server_name);
+        }
+        let scu = scu_init
+            .establish_with_tls(&addr)
+            .map_err(Box::from)
+            .context(ScuSnafu)?;
+        store_files(
+            scu,
+            dicom_files,
+            message_id,
+            verbose,
+            fail_first,
+            never_transcode,
+            commit.map(|result_from| (result_from, calling_ae_title)),
+        )
+    } else {
+        let scu = scu_init
+            .establish_with(&addr)
+            .map_err(Box::from)
+            .context(ScuSnafu)?;
+        store_files(
+            scu,
+            dicom_files,
+            message_id,
+            verbose,
+            fail_first,
+            never_transcode,
+            commit.map(|result_from| (result_from, calling_ae_title)),
+        )
+    }
+}
+
+/// Send all files through the established association and release it.
+fn store_files<S: Transport>(
+    mut scu: ClientAssociation<S>,
+    mut dicom_files: Vec<DicomFile>,
+    message_id: u16,
+    verbose: bool,
+    fail_first: bool,
+    never_transcode: bool,
+    commit: Option<(ResultFrom, String)>,
+) -> Result<(), Error> {
+    use crate::store_sync::{finish, send_file};
 
     if verbose {
         info!("Association established");
     }
 
+    // the storage commitment context is not for storing files
+    let storage_pcs: Vec<_> = scu
+        .presentation_contexts()
+        .iter()
+        .filter(|pc| scu.abstract_syntax(pc.id) != Some(STORAGE_COMMITMENT_PUSH_MODEL))
+        .cloned()
+        .collect();
+    let mut instances = Vec::new();
+
     for file in &mut dicom_files {
         // identify the right transfer syntax to use
-        let r: Result<_, Error> =
-            check_presentation_contexts(file, scu.presentation_contexts(), never_transcode);
+        let r: Result<_, Error> = check_presentation_contexts(file, &storage_pcs, never_transcode);
         match r {
             Ok((pc, ts)) => {
                 if verbose {
@@ -328,6 +578,10 @@ fn run(app: App) -> Result<(), Error> {
                 }
                 file.pc_selected = Some(pc);
                 file.ts_selected = Some(ts);
+                instances.push(InstanceReference::new(
+                    file.sop_class_uid.trim_end_matches('\0'),
+                    file.sop_instance_uid.trim_end_matches('\0'),
+                ));
             }
             Err(e) => {
                 error!("{}", Report::from_error(e));
@@ -354,21 +608,69 @@ fn run(app: App) -> Result<(), Error> {
         progress_bar = None;
     }
 
+    let mut pipeline = Pipeline::new(&mut scu);
+    if verbose && pipeline.window() != 1 {
+        info!(
+            "Up to {} C-STORE requests may be outstanding",
+            pipeline.window()
+        );
+    }
+    // message IDs must be unique among outstanding requests
+    let increment = u16::from(pipeline.window() != 1);
+    let mut message_id = message_id;
+    let mut keep_going = true;
     for file in dicom_files {
-        scu = send_file(
-            scu,
+        keep_going = send_file(
+            &mut pipeline,
             file,
             message_id,
             progress_bar.as_ref(),
             verbose,
             fail_first,
         )?;
+        if !keep_going {
+            break;
+        }
+        message_id = message_id.wrapping_add(increment);
+    }
+    let keep_going = if keep_going {
+        finish(pipeline, progress_bar.as_ref(), verbose, fail_first)?
+    } else {
+        drop(pipeline);
+        false
+    };
+    if !keep_going {
+        let _ = scu.abort();
+        std::process::exit(-2);
     }
 
     if let Some(pb) = progress_bar {
         pb.finish_with_message("done")
     };
 
+    if let Some((result_from, calling_ae_title)) = commit {
+        let result =
+            crate::commit::commit(scu, instances, message_id, &calling_ae_title, result_from)?;
+        info!(
+            "Storage commitment {}: {} instances committed",
+            result.transaction_uid,
+            result.committed.len()
+        );
+        for failed in &result.failed {
+            warn!(
+                "Instance {} not committed: failure reason {:04X}H",
+                failed.instance.sop_instance_uid, failed.failure_reason
+            );
+        }
+        if !result.is_success() {
+            return NotCommittedSnafu {
+                failed: result.failed.len(),
+            }
+            .fail();
+        }
+        return Ok(());
+    }
+
     scu.release().map_err(Box::from).context(ScuSnafu)?;
     Ok(())
 }
@@ -377,6 +679,7 @@ async fn run_async() -> Result<(), Error> {
     use crate::store_async::{get_scu, send_file};
     let App {
         addr,
+        ae_config,
         files,
         verbose,
         message_id,
@@ -391,6 +694,11 @@ async fn run_async() -> Result<(), Error> {
         saml_assertion,
         jwt,
         concurrency,
+        max_operations: _,
+        commit: _,
+        commit_port: _,
+        commit_timeout: _,
+        tls,
     } = App::parse();
 
     // never transcode if the feature is disabled
@@ -408,6 +716,7 @@ async fn run_async() -> Result<(), Error> {
         eprintln!("[ERROR] {}", Report::from_error(e));
     });
 
+    let (addr, node) = resolve_node(addr, ae_config.as_deref())?;
     if verbose {
         info!("Establishing association with '{}'...", &addr);
     }
@@ -415,6 +724,7 @@ async fn run_async() -> Result<(), Error> {
         tokio::task::spawn_blocking(move || check_files(files, verbose, never_transcode))
             .await
             .unwrap();
+    let tls_config = tls_config(&tls)?;
     let num_files = dicom_files.len();
     let dicom_files = Arc::new(Mutex::new(dicom_files));
     let mut tasks = tokio::task::JoinSet::new();
@@ -447,9 +757,12 @@ async fn run_async() -> Result<(), Error> {
         let password = password.clone();
         let called_ae_title = called_ae_title.clone();
         let calling_ae_title = calling_ae_title.clone();
+        let tls_config = tls_config.clone();
+        let tls_server_name = tls.server_name.clone();
+        let node = node.clone();
         tasks.spawn(async move {
-            let mut scu = get_scu(
-                addr,
+            let scu_init = scu_options(
+                node.as_ref(),
                 calling_ae_title,
                 called_ae_title,
                 max_pdu_length,
@@ -458,9 +771,11 @@ async fn run_async() -> Result<(), Error> {
                 kerberos_service_ticket,
                 saml_assertion,
                 jwt,
-                pc,
-            )
-            .await?;
+                &pc,
+                false,
+            )?;
+            let use_tls = tls_config.is_some() || node.as_ref().is_some_and(AeEntry::uses_tls);
+            let mut scu = get_scu(scu_init, &addr, use_tls, tls_config, tls_server_name).await?;
             loop {
                 let file = {
                     let mut files = d_files.lock().await;
@@ -516,39 +831,6 @@ async fn run_async() -> Result<(), Error> {
 
     Ok(())
 }
-fn store_req_command(
-    storage_sop_class_uid: &str,
-    storage_sop_instance_uid: &str,
-    message_id: u16,
-) -> InMemDicomObject<StandardDataDictionary> {
-    InMemDicomObject::command_from_element_iter([
-        // SOP Class UID
-        DataElement::new(
-            tags::AFFECTED_SOP_CLASS_UID,
-            VR::UI,
-            dicom_value!(Str, storage_sop_class_uid),
-        ),
-        // command field
-        DataElement::new(tags::COMMAND_FIELD, VR::US, dicom_value!(U16, [0x0001])),
-        // message ID
-        DataElement::new(tags::MESSAGE_ID, VR::US, dicom_value!(U16, [message_id])),
-        //priority
-        DataElement::new(tags::PRIORITY, VR::US, dicom_value!(U16, [0x0000])),
-        // data set type
-        DataElement::new(
-            tags::COMMAND_DATA_SET_TYPE,
-            VR::US,
-            dicom_value!(U16, [0x0000]),
-        ),
-        // affected SOP Instance UID
-        DataElement::new(
-            tags::AFFECTED_SOP_INSTANCE_UID,
-            VR::UI,
-            dicom_value!(Str, storage_sop_instance_uid),
-        ),
-    ])
-}
-
 fn check_file(file: &Path) -> Result<DicomFile, Error> {
     // Ignore DICOMDIR files until better support is added
     let _ = (file.file_name() != Some(OsStr::new("DICOMDIR")))
@@ -680,11 +962,157 @@ fn into_ts(
 
 #[cfg(test)]
 mod tests {
-    use crate::App;
+    use crate::{check_files, scu_options, store_files, App};
     use clap::CommandFactory;
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::{tags, uids};
+    use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
+    use dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN;
+    use dicom_ul::{
+        association::{
+            duplex::duplex,
+            replay::{Recording, Replay, Side},
+        },
+        dimse::{
+            composite::{CStoreRq, CStoreRsp},
+            DimseMessage, Status,
+        },
+        pdu::{
+            AssociationAC, AssociationRQ, Pdu, PresentationContextProposed,
+            PresentationContextResult, PresentationContextResultReason, UserVariableItem,
+        },
+        IMPLEMENTATION_CLASS_UID, IMPLEMENTATION_VERSION_NAME,
+    };
+
+    static SOP_INSTANCE_UID: &str = "2.25.1234567890";
 
     #[test]
     fn verify_cli() {
         App::command().debug_assert();
     }
+
+    /// A secondary capture instance without pixel data.
+    fn sample_dataset() -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                PrimitiveValue::from(uids::SECONDARY_CAPTURE_IMAGE_STORAGE),
+            ),
+            DataElement::new(
+                tags::SOP_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from(SOP_INSTANCE_UID),
+            ),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::from("Doe^John")),
+        ])
+    }
+
+    /// A file in explicit VR little endian
+    /// is transcoded to implicit VR little endian
+    /// when it is the only transfer syntax accepted by the SCP.
+    #[test]
+    fn store_transcoded_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sc.dcm");
+        sample_dataset()
+            .with_meta(
+                FileMetaTableBuilder::new()
+                    .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                    .media_storage_sop_instance_uid(SOP_INSTANCE_UID)
+                    .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN),
+            )
+            .unwrap()
+            .write_to_file(&path)
+            .unwrap();
+
+        let (dicom_files, presentation_contexts) = check_files(vec![path], false, false);
+        let scu_init = scu_options(
+            None,
+            "STORE-SCU".to_string(),
+            None,
+            16_384,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &presentation_contexts,
+            false,
+        )
+        .unwrap();
+
+        // the exchange expected with the SCP
+        let rq = AssociationRQ {
+            protocol_version: 1,
+            calling_ae_title: "STORE-SCU".to_string(),
+            called_ae_title: "ANY-SCP".to_string(),
+            application_context_name: "1.2.840.10008.3.1.1.1".to_string(),
+            presentation_contexts: vec![
+                PresentationContextProposed {
+                    id: 1,
+                    abstract_syntax: uids::SECONDARY_CAPTURE_IMAGE_STORAGE.to_string(),
+                    transfer_syntaxes: vec![uids::IMPLICIT_VR_LITTLE_ENDIAN.to_string()],
+                },
+                PresentationContextProposed {
+                    id: 3,
+                    abstract_syntax: uids::SECONDARY_CAPTURE_IMAGE_STORAGE.to_string(),
+                    transfer_syntaxes: vec![uids::EXPLICIT_VR_LITTLE_ENDIAN.to_string()],
+                },
+            ],
+            user_variables: vec![
+                UserVariableItem::MaxLength(16_384),
+                UserVariableItem::ImplementationClassUID(IMPLEMENTATION_CLASS_UID.to_string()),
+                UserVariableItem::ImplementationVersionName(
+                    IMPLEMENTATION_VERSION_NAME.to_string(),
+                ),
+            ],
+        };
+        let ac = AssociationAC {
+            protocol_version: 1,
+            calling_ae_title: "STORE-SCU".to_string(),
+            called_ae_title: "ANY-SCP".to_string(),
+            application_context_name: "1.2.840.10008.3.1.1.1".to_string(),
+            presentation_contexts: vec![
+                PresentationContextResult {
+                    id: 1,
+                    reason: PresentationContextResultReason::Acceptance,
+                    transfer_syntax: uids::IMPLICIT_VR_LITTLE_ENDIAN.to_string(),
+                },
+                PresentationContextResult {
+                    id: 3,
+                    reason: PresentationContextResultReason::TransferSyntaxesNotSupported,
+                    transfer_syntax: uids::EXPLICIT_VR_LITTLE_ENDIAN.to_string(),
+                },
+            ],
+            user_variables: vec![UserVariableItem::MaxLength(16_384)],
+        };
+        let store_rq = CStoreRq::new(1, uids::SECONDARY_CAPTURE_IMAGE_STORAGE, SOP_INSTANCE_UID);
+        let store_rsp = DimseMessage::new(1, CStoreRsp::new(&store_rq, Status::SUCCESS));
+        let store_rq = DimseMessage::new(1, store_rq)
+            .with_dataset(&sample_dataset(), &IMPLICIT_VR_LITTLE_ENDIAN.erased())
+            .unwrap();
+
+        let mut recording = Recording::new()
+            .with_pdu(Side::Requestor, rq)
+            .with_pdu(Side::Acceptor, ac);
+        for pdu in store_rq.to_pdus(16_384).unwrap() {
+            recording.push(Side::Requestor, pdu);
+        }
+        for pdu in store_rsp.to_pdus(16_384).unwrap() {
+            recording.push(Side::Acceptor, pdu);
+        }
+        let recording = recording
+            .with_pdu(Side::Requestor, Pdu::ReleaseRQ)
+            .with_pdu(Side::Acceptor, Pdu::ReleaseRP);
+
+        let (scu_stream, scp_stream) = duplex(64 * 1024);
+        let scp = Replay::new(recording, Side::Acceptor).spawn(scp_stream);
+        let scu = scu_init.establish_over(scu_stream).unwrap();
+        store_files(scu, dicom_files, 1, true, true, false, None).unwrap();
+
+        scp.join()
+            .expect("mock SCP panicked")
+            .expect("SCU did not send the expected PDUs");
+    }
 }
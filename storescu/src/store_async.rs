@@ -1,163 +1,233 @@
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    convert::TryFrom,
+    io::{Seek, SeekFrom},
+    sync::Arc,
+};
 
-use dicom_dictionary_std::tags;
 use dicom_encoding::TransferSyntaxIndex;
-use dicom_object::{open_file, InMemDicomObject};
+use dicom_object::{open_file, OpenFileOptions};
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
 use dicom_ul::{
-    pdu::{PDataValue, PDataValueType},
+    association::client,
+    dimse::{
+        composite::{CStoreRq, CStoreRsp},
+        Command, DimseMessage, StatusType,
+    },
+    pdu::{PDataValue, PDataValueType, PresentationContextResult},
+    tls::{rustls::ClientConfig, AsyncTlsClientStream},
     ClientAssociation, ClientAssociationOptions, Pdu,
 };
 use indicatif::ProgressBar;
 use snafu::{OptionExt, ResultExt};
-use tokio::{io::AsyncWriteExt, net::TcpStream};
+use tokio::{io::AsyncRead, net::TcpStream};
 use tracing::{debug, error, info, warn};
 
 use crate::{
-    into_ts, store_req_command, ConvertFieldSnafu, CreateCommandSnafu, DicomFile, Error,
-    MissingAttributeSnafu, ReadDatasetSnafu, ReadFilePathSnafu, ScuSnafu,
-    UnsupportedFileTransferSyntaxSnafu, WriteDatasetSnafu,
+    into_ts, CreateCommandSnafu, DicomFile, Error, ReadDataSetSnafu, ReadFilePathSnafu,
+    ReadResponseSnafu, ScuSnafu, UnsupportedFileTransferSyntaxSnafu, WriteDatasetSnafu,
 };
 
-#[allow(clippy::too_many_arguments)]
-pub async fn get_scu(
-    addr: String,
-    calling_ae_title: String,
-    called_ae_title: Option<String>,
-    max_pdu_length: u32,
-    username: Option<String>,
-    password: Option<String>,
-    kerberos_service_ticket: Option<String>,
-    saml_assertion: Option<String>,
-    jwt: Option<String>,
-    presentation_contexts: HashSet<(String, String)>,
-) -> Result<ClientAssociation<TcpStream>, Error> {
-    let mut scu_init = ClientAssociationOptions::new()
-        .calling_ae_title(calling_ae_title)
-        .max_pdu_length(max_pdu_length);
-
-    for (storage_sop_class_uid, transfer_syntax) in &presentation_contexts {
-        scu_init = scu_init.with_presentation_context(storage_sop_class_uid, vec![transfer_syntax]);
+/// An association with the SCP, either in plain TCP or secured with TLS.
+#[allow(clippy::large_enum_variant)]
+pub enum Scu {
+    Plain(ClientAssociation<TcpStream>),
+    Tls(ClientAssociation<AsyncTlsClientStream>),
+}
+
+/// Forward a method call to the association, whichever its transport.
+macro_rules! dispatch {
+    ($scu: expr, $a: ident => $e: expr) => {
+        match $scu {
+            Scu::Plain($a) => $e,
+            Scu::Tls($a) => $e,
+        }
+    };
+}
+
+impl Scu {
+    pub fn presentation_contexts(&self) -> &[PresentationContextResult] {
+        dispatch!(self, scu => scu.presentation_contexts())
     }
 
-    if let Some(called_ae_title) = called_ae_title {
-        scu_init = scu_init.called_ae_title(called_ae_title);
+    pub fn acceptor_max_pdu_length(&self) -> u32 {
+        dispatch!(self, scu => scu.acceptor_max_pdu_length())
     }
 
-    if let Some(username) = username {
-        scu_init = scu_init.username(username);
+    pub async fn send(&mut self, pdu: &Pdu) -> Result<(), client::Error> {
+        dispatch!(self, scu => scu.send(pdu).await)
     }
 
-    if let Some(password) = password {
-        scu_init = scu_init.password(password);
+    /// Send a DIMSE message with the data set read from the given source.
+    pub async fn send_dimse_from<R>(
+        &mut self,
+        presentation_context_id: u8,
+        command: &Command,
+        data: R,
+    ) -> Result<(), client::Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        dispatch!(self, scu => scu.send_dimse_from(presentation_context_id, command, data).await)
     }
 
-    if let Some(kerberos_service_ticket) = kerberos_service_ticket {
-        scu_init = scu_init.kerberos_service_ticket(kerberos_service_ticket);
+    pub async fn receive_dimse(&mut self) -> Result<DimseMessage, client::Error> {
+        dispatch!(self, scu => scu.receive_dimse().await)
     }
 
-    if let Some(saml_assertion) = saml_assertion {
-        scu_init = scu_init.saml_assertion(saml_assertion);
+    pub async fn abort(self) -> Result<(), client::Error> {
+        dispatch!(self, scu => scu.abort().await)
     }
 
-    if let Some(jwt) = jwt {
-        scu_init = scu_init.jwt(jwt);
+    pub async fn release(self) -> Result<(), client::Error> {
+        dispatch!(self, scu => scu.release().await)
     }
+}
 
-    scu_init
-        .establish_with_async(&addr)
-        .await
-        .map_err(Box::from)
-        .context(ScuSnafu)
+/// Establish an association with the SCP,
+/// secured with TLS if a configuration is given.
+pub async fn get_scu(
+    scu_init: ClientAssociationOptions<'static>,
+    addr: &str,
+    use_tls: bool,
+    tls_config: Option<Arc<ClientConfig>>,
+    tls_server_name: Option<String>,
+) -> Result<Scu, Error> {
+    if use_tls {
+        let mut scu_init = scu_init;
+        if let Some(tls_config) = tls_config {
+            scu_init = scu_init.tls_config(tls_config);
+        }
+        if let Some(server_name) = tls_server_name {
+            scu_init = scu_init.server_name(server_name);
+        }
+        scu_init
+            .establish_with_tls_async(addr)
+            .await
+            .map(Scu::Tls)
+            .map_err(Box::from)
+            .context(ScuSnafu)
+    } else {
+        scu_init
+            .establish_with_async(addr)
+            .await
+            .map(Scu::Plain)
+            .map_err(Box::from)
+            .context(ScuSnafu)
+    }
 }
 
 pub async fn send_file(
-    mut scu: ClientAssociation<TcpStream>,
+    mut scu: Scu,
     file: DicomFile,
     message_id: u16,
     progress_bar: Option<&Arc<tokio::sync::Mutex<ProgressBar>>>,
     verbose: bool,
     fail_first: bool,
-) -> Result<ClientAssociation<TcpStream>, Error> {
+) -> Result<Scu, Error> {
     if let (Some(pc_selected), Some(ts_uid_selected)) = (file.pc_selected, file.ts_selected) {
-        let cmd = store_req_command(&file.sop_class_uid, &file.sop_instance_uid, message_id);
-
-        let mut cmd_data = Vec::with_capacity(128);
-        cmd.write_dataset_with_ts(
-            &mut cmd_data,
-            &dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased(),
-        )
-        .map_err(Box::from)
-        .context(CreateCommandSnafu)?;
-
-        let mut object_data = Vec::with_capacity(2048);
-        let dicom_file = open_file(&file.file)
-            .map_err(Box::from)
-            .context(ReadFilePathSnafu {
-                path: file.file.display().to_string(),
-            })?;
-        let ts_selected = TransferSyntaxRegistry
-            .get(&ts_uid_selected)
-            .with_context(|| UnsupportedFileTransferSyntaxSnafu {
-                uid: ts_uid_selected.to_string(),
-            })?;
-
-        // transcode file if necessary
-        let dicom_file = into_ts(dicom_file, ts_selected, verbose)?;
-
-        dicom_file
-            .write_dataset_with_ts(&mut object_data, ts_selected)
-            .map_err(Box::from)
-            .context(WriteDatasetSnafu)?;
+        let cmd = Command::from(CStoreRq::new(
+            message_id,
+            &file.sop_class_uid,
+            &file.sop_instance_uid,
+        ));
 
-        let nbytes = cmd_data.len() + object_data.len();
+        if ts_uid_selected == file.file_transfer_syntax {
+            // no transcoding needed,
+            // so the data set is streamed as is from the file
+            let path = file.file.display().to_string();
+            let (_meta, mut data) = OpenFileOptions::new()
+                .open_file_raw(&file.file)
+                .map_err(Box::from)
+                .context(ReadFilePathSnafu { path: path.clone() })?;
+            // resume reading from the start of the data set,
+            // past anything already buffered
+            let position = data
+                .stream_position()
+                .context(ReadDataSetSnafu { path: path.clone() })?;
+            let mut data = data.into_inner();
+            data.seek(SeekFrom::Start(position))
+                .context(ReadDataSetSnafu { path })?;
 
-        if verbose {
-            info!(
-                "Sending file {} (~ {} kB), uid={}, sop={}, ts={}",
-                file.file.display(),
-                nbytes / 1_000,
-                &file.sop_instance_uid,
-                &file.sop_class_uid,
-                ts_uid_selected,
-            );
-        }
+            if verbose {
+                let file_len = data.metadata().map(|m| m.len()).unwrap_or(0);
+                info!(
+                    "Sending file {} (~ {} kB), uid={}, sop={}, ts={}",
+                    file.file.display(),
+                    file_len / 1_000,
+                    &file.sop_instance_uid,
+                    &file.sop_class_uid,
+                    ts_uid_selected,
+                );
+            }
 
-        if nbytes < scu.acceptor_max_pdu_length().saturating_sub(100) as usize {
-            let pdu = Pdu::PData {
-                data: vec![
-                    PDataValue {
-                        presentation_context_id: pc_selected.id,
-                        value_type: PDataValueType::Command,
-                        is_last: true,
-                        data: cmd_data,
-                    },
-                    PDataValue {
-                        presentation_context_id: pc_selected.id,
-                        value_type: PDataValueType::Data,
-                        is_last: true,
-                        data: object_data,
-                    },
-                ],
-            };
-
-            scu.send(&pdu).await.map_err(Box::from).context(ScuSnafu)?;
+            scu.send_dimse_from(pc_selected.id, &cmd, tokio::fs::File::from_std(data))
+                .await
+                .map_err(Box::from)
+                .context(ScuSnafu)?;
         } else {
-            let pdu = Pdu::PData {
-                data: vec![PDataValue {
-                    presentation_context_id: pc_selected.id,
-                    value_type: PDataValueType::Command,
-                    is_last: true,
-                    data: cmd_data,
-                }],
-            };
-
-            scu.send(&pdu).await.map_err(Box::from).context(ScuSnafu)?;
-
-            {
-                let mut pdata = scu.send_pdata(pc_selected.id).await;
-                pdata.write_all(&object_data).await.unwrap();
-                //.whatever_context("Failed to send C-STORE-RQ P-Data")?;
+            let cmd_data = cmd
+                .encode(true)
+                .map_err(Box::from)
+                .context(CreateCommandSnafu)?;
+
+            let mut object_data = Vec::with_capacity(2048);
+            let dicom_file =
+                open_file(&file.file)
+                    .map_err(Box::from)
+                    .context(ReadFilePathSnafu {
+                        path: file.file.display().to_string(),
+                    })?;
+            let ts_selected = TransferSyntaxRegistry
+                .get(&ts_uid_selected)
+                .with_context(|| UnsupportedFileTransferSyntaxSnafu {
+                    uid: ts_uid_selected.to_string(),
+                })?;
+
+            // transcode file if necessary
+            let dicom_file = into_ts(dicom_file, ts_selected, verbose)?;
+
+            dicom_file
+                .write_dataset_with_ts(&mut object_data, ts_selected)
+                .map_err(Box::from)
+                .context(WriteDatasetSnafu)?;
+
+            let nbytes = cmd_data.len() + object_data.len();
+
+            if verbose {
+                info!(
+                    "Sending file {} (~ {} kB), uid={}, sop={}, ts={}",
+                    file.file.display(),
+                    nbytes / 1_000,
+                    &file.sop_instance_uid,
+                    &file.sop_class_uid,
+                    ts_uid_selected,
+                );
+            }
+
+            if nbytes < scu.acceptor_max_pdu_length().saturating_sub(100) as usize {
+                let pdu = Pdu::PData {
+                    data: vec![
+                        PDataValue {
+                            presentation_context_id: pc_selected.id,
+                            value_type: PDataValueType::Command,
+                            is_last: true,
+                            data: cmd_data,
+                        },
+                        PDataValue {
+                            presentation_context_id: pc_selected.id,
+                            value_type: PDataValueType::Data,
+                            is_last: true,
+                            data: object_data,
+                        },
+                    ],
+                };
+
+                scu.send(&pdu).await.map_err(Box::from).context(ScuSnafu)?;
+            } else {
+                scu.send_dimse_from(pc_selected.id, &cmd, &object_data[..])
+                    .await
+                    .map_err(Box::from)
+                    .context(ScuSnafu)?;
             }
         }
 
@@ -165,82 +235,63 @@ pub async fn send_file(
             debug!("Awaiting response...");
         }
 
-        let rsp_pdu = scu.receive().await.map_err(Box::from).context(ScuSnafu)?;
-
-        match rsp_pdu {
-            Pdu::PData { data } => {
-                let data_value = &data[0];
+        let rsp = match scu.receive_dimse().await {
+            Ok(rsp) => rsp,
+            Err(dicom_ul::association::client::Error::UnexpectedResponse { pdu, .. }) => {
+                error!("Unexpected SCP response: {:?}", pdu);
+                let _ = scu.abort().await;
+                std::process::exit(-2);
+            }
+            Err(e) => return Err(Box::from(e)).context(ScuSnafu),
+        };
+        if verbose {
+            debug!("Full response: {:?}", rsp.command);
+        }
+        let rsp = CStoreRsp::try_from(rsp.command)
+            .map_err(Box::from)
+            .context(ReadResponseSnafu)?;
+        let status = rsp.status;
+        let storage_sop_instance_uid = file
+            .sop_instance_uid
+            .trim_end_matches(|c: char| c.is_whitespace() || c == '\0');
 
-                let cmd_obj = InMemDicomObject::read_dataset_with_ts(
-                    &data_value.data[..],
-                    &dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased(),
-                )
-                .context(ReadDatasetSnafu)?;
+        match status.status_type() {
+            StatusType::Success => {
                 if verbose {
-                    debug!("Full response: {:?}", cmd_obj);
+                    info!("Successfully stored instance {}", storage_sop_instance_uid);
                 }
-                let status = cmd_obj
-                    .element(tags::STATUS)
-                    .context(MissingAttributeSnafu { tag: tags::STATUS })?
-                    .to_int::<u16>()
-                    .context(ConvertFieldSnafu { tag: tags::STATUS })?;
-                let storage_sop_instance_uid = file
-                    .sop_instance_uid
-                    .trim_end_matches(|c: char| c.is_whitespace() || c == '\0');
-
-                match status {
-                    // Success
-                    0 => {
-                        if verbose {
-                            info!("Successfully stored instance {}", storage_sop_instance_uid);
-                        }
-                    }
-                    // Warning
-                    1 | 0x0107 | 0x0116 | 0xB000..=0xBFFF => {
-                        warn!(
-                            "Possible issue storing instance `{}` (status code {:04X}H)",
-                            storage_sop_instance_uid, status
-                        );
-                    }
-                    0xFF00 | 0xFF01 => {
-                        warn!(
-                            "Possible issue storing instance `{}`: status is pending (status code {:04X}H)",
-                            storage_sop_instance_uid, status
-                        );
-                    }
-                    0xFE00 => {
-                        error!(
-                            "Could not store instance `{}`: operation cancelled",
-                            storage_sop_instance_uid
-                        );
-                        if fail_first {
-                            let _ = scu.abort().await;
-                            std::process::exit(-2);
-                        }
-                    }
-                    _ => {
-                        error!(
-                            "Failed to store instance `{}` (status code {:04X}H)",
-                            storage_sop_instance_uid, status
-                        );
-                        if fail_first {
-                            let _ = scu.abort().await;
-                            std::process::exit(-2);
-                        }
-                    }
+            }
+            StatusType::Warning => {
+                warn!(
+                    "Possible issue storing instance `{}` (status code {})",
+                    storage_sop_instance_uid, status
+                );
+            }
+            StatusType::Pending => {
+                warn!(
+                    "Possible issue storing instance `{}`: status is pending (status code {})",
+                    storage_sop_instance_uid, status
+                );
+            }
+            StatusType::Cancel => {
+                error!(
+                    "Could not store instance `{}`: operation cancelled",
+                    storage_sop_instance_uid
+                );
+                if fail_first {
+                    let _ = scu.abort().await;
+                    std::process::exit(-2);
                 }
             }
-
-            pdu @ Pdu::Unknown { .. }
-            | pdu @ Pdu::AssociationRQ { .. }
-            | pdu @ Pdu::AssociationAC { .. }
-            | pdu @ Pdu::AssociationRJ { .. }
-            | pdu @ Pdu::ReleaseRQ
-            | pdu @ Pdu::ReleaseRP
-            | pdu @ Pdu::AbortRQ { .. } => {
-                error!("Unexpected SCP response: {:?}", pdu);
-                let _ = scu.abort().await;
-                std::process::exit(-2);
+            StatusType::Failure => {
+                error!(
+                    "Failed to store instance `{}` (status code {})",
+                    storage_sop_instance_uid, status
+                );
+                if fail_first {
+                    let _ = scu.abort().await;
+                    std::process::exit(-2);
+                }
             }
         }
     }
@@ -1,96 +1,79 @@
-use std::{collections::HashSet, io::Write, net::TcpStream};
+use std::convert::TryFrom;
 
-use dicom_dictionary_std::tags;
 use dicom_encoding::TransferSyntaxIndex;
-use dicom_object::{open_file, InMemDicomObject};
+use dicom_object::{open_file, OpenFileOptions};
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
 use dicom_ul::{
-    pdu::{PDataValue, PDataValueType},
-    ClientAssociation, ClientAssociationOptions, Pdu,
+    association::{client, pipeline, pipeline::Pipeline, Transport},
+    dimse::{
+        composite::{CStoreRq, CStoreRsp},
+        Command, DimseMessage, StatusType,
+    },
 };
 use indicatif::ProgressBar;
 use snafu::{OptionExt, ResultExt};
 use tracing::{debug, error, info, warn};
 
 use crate::{
-    into_ts, store_req_command, ConvertFieldSnafu, CreateCommandSnafu, DicomFile, Error,
-    MissingAttributeSnafu, ReadDatasetSnafu, ReadFilePathSnafu, ScuSnafu,
-    UnsupportedFileTransferSyntaxSnafu, WriteDatasetSnafu, WriteIOSnafu,
+    into_ts, DicomFile, Error, PipelineSnafu, ReadFilePathSnafu, ReadResponseSnafu,
+    UnsupportedFileTransferSyntaxSnafu, WriteDatasetSnafu,
 };
 
-#[allow(clippy::too_many_arguments)]
-pub fn get_scu(
-    addr: String,
-    calling_ae_title: String,
-    called_ae_title: Option<String>,
-    max_pdu_length: u32,
-    username: Option<String>,
-    password: Option<String>,
-    kerberos_service_ticket: Option<String>,
-    saml_assertion: Option<String>,
-    jwt: Option<String>,
-    presentation_contexts: HashSet<(String, String)>,
-) -> Result<ClientAssociation<TcpStream>, Error> {
-    let mut scu_init = ClientAssociationOptions::new()
-        .calling_ae_title(calling_ae_title)
-        .max_pdu_length(max_pdu_length);
-
-    for (storage_sop_class_uid, transfer_syntax) in &presentation_contexts {
-        scu_init = scu_init.with_presentation_context(storage_sop_class_uid, vec![transfer_syntax]);
-    }
-
-    if let Some(called_ae_title) = called_ae_title {
-        scu_init = scu_init.called_ae_title(called_ae_title);
-    }
-
-    if let Some(username) = username {
-        scu_init = scu_init.username(username);
-    }
-
-    if let Some(password) = password {
-        scu_init = scu_init.password(password);
-    }
-
-    if let Some(kerberos_service_ticket) = kerberos_service_ticket {
-        scu_init = scu_init.kerberos_service_ticket(kerberos_service_ticket);
-    }
-
-    if let Some(saml_assertion) = saml_assertion {
-        scu_init = scu_init.saml_assertion(saml_assertion);
-    }
-
-    if let Some(jwt) = jwt {
-        scu_init = scu_init.jwt(jwt);
-    }
-
-    scu_init
-        .establish_with(&addr)
-        .map_err(Box::from)
-        .context(ScuSnafu)
-}
-
-pub fn send_file(
-    mut scu: ClientAssociation<TcpStream>,
+/// Send a file through the pipeline of C-STORE requests,
+/// handling the response to an earlier request
+/// if the window of outstanding operations was full.
+///
+/// Returns `false` if the transfer should be interrupted.
+pub fn send_file<S: Transport>(
+    pipeline: &mut Pipeline<'_, S, DicomFile>,
     file: DicomFile,
     message_id: u16,
     progress_bar: Option<&ProgressBar>,
     verbose: bool,
     fail_first: bool,
-) -> Result<ClientAssociation<TcpStream>, Error> {
-    if let (Some(pc_selected), Some(ts_uid_selected)) = (file.pc_selected, file.ts_selected) {
-        if let Some(pb) = &progress_bar {
-            pb.set_message(file.sop_instance_uid.clone());
+) -> Result<bool, Error> {
+    let (pc_selected, ts_uid_selected) = match (&file.pc_selected, &file.ts_selected) {
+        (Some(pc_selected), Some(ts_uid_selected)) => (pc_selected.id, ts_uid_selected.clone()),
+        _ => {
+            if let Some(pb) = progress_bar {
+                pb.inc(1)
+            };
+            return Ok(true);
         }
-        let cmd = store_req_command(&file.sop_class_uid, &file.sop_instance_uid, message_id);
+    };
+    if let Some(pb) = progress_bar {
+        pb.set_message(file.sop_instance_uid.clone());
+    }
 
-        let mut cmd_data = Vec::with_capacity(128);
-        cmd.write_dataset_with_ts(
-            &mut cmd_data,
-            &dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased(),
-        )
-        .map_err(Box::from)
-        .context(CreateCommandSnafu)?;
+    let sent = if ts_uid_selected == file.file_transfer_syntax {
+        // no transcoding needed,
+        // so the data set is streamed as is from the file
+        let (_meta, data) = OpenFileOptions::new()
+            .open_file_raw(&file.file)
+            .map_err(Box::from)
+            .context(ReadFilePathSnafu {
+                path: file.file.display().to_string(),
+            })?;
+
+        if verbose {
+            let file_len = data.get_ref().metadata().map(|m| m.len()).unwrap_or(0);
+            info!(
+                "Sending file {} (~ {} kB), uid={}, sop={}, ts={}",
+                file.file.display(),
+                file_len / 1_000,
+                &file.sop_instance_uid,
+                &file.sop_class_uid,
+                ts_uid_selected,
+            );
+        }
 
+        let cmd = Command::from(CStoreRq::new(
+            message_id,
+            &file.sop_class_uid,
+            &file.sop_instance_uid,
+        ));
+        pipeline.send_from(pc_selected, &cmd, data, file)
+    } else {
         let mut object_data = Vec::with_capacity(2048);
         let dicom_file = open_file(&file.file)
             .map_err(Box::from)
@@ -111,141 +94,129 @@ pub fn send_file(
             .map_err(Box::from)
             .context(WriteDatasetSnafu)?;
 
-        let nbytes = cmd_data.len() + object_data.len();
-
         if verbose {
             info!(
                 "Sending file {} (~ {} kB), uid={}, sop={}, ts={}",
                 file.file.display(),
-                nbytes / 1_000,
+                object_data.len() / 1_000,
                 &file.sop_instance_uid,
                 &file.sop_class_uid,
                 ts_uid_selected,
             );
         }
 
-        if nbytes < scu.acceptor_max_pdu_length().saturating_sub(100) as usize {
-            let pdu = Pdu::PData {
-                data: vec![
-                    PDataValue {
-                        presentation_context_id: pc_selected.id,
-                        value_type: PDataValueType::Command,
-                        is_last: true,
-                        data: cmd_data,
-                    },
-                    PDataValue {
-                        presentation_context_id: pc_selected.id,
-                        value_type: PDataValueType::Data,
-                        is_last: true,
-                        data: object_data,
-                    },
-                ],
-            };
-
-            scu.send(&pdu).map_err(Box::from).context(ScuSnafu)?;
-        } else {
-            let pdu = Pdu::PData {
-                data: vec![PDataValue {
-                    presentation_context_id: pc_selected.id,
-                    value_type: PDataValueType::Command,
-                    is_last: true,
-                    data: cmd_data,
-                }],
-            };
+        let msg = DimseMessage::new(
+            pc_selected,
+            CStoreRq::new(message_id, &file.sop_class_uid, &file.sop_instance_uid),
+        )
+        .with_data(object_data);
 
-            scu.send(&pdu).map_err(Box::from).context(ScuSnafu)?;
+        pipeline.send(&msg, file)
+    };
 
-            {
-                let mut pdata = scu.send_pdata(pc_selected.id);
-                pdata.write_all(&object_data).context(WriteIOSnafu)?;
-            }
-        }
+    match sent {
+        Ok(Some((file, rsp))) => handle_response(file, rsp, progress_bar, verbose, fail_first),
+        Ok(None) => Ok(true),
+        Err(e) => handle_pipeline_error(e),
+    }
+}
 
+/// Wait for the responses to all outstanding C-STORE requests.
+///
+/// Returns `false` if the transfer should be interrupted.
+pub fn finish<S: Transport>(
+    mut pipeline: Pipeline<'_, S, DicomFile>,
+    progress_bar: Option<&ProgressBar>,
+    verbose: bool,
+    fail_first: bool,
+) -> Result<bool, Error> {
+    while pipeline.outstanding() > 0 {
         if verbose {
             debug!("Awaiting response...");
         }
+        let keep_going = match pipeline.receive() {
+            Ok((file, rsp)) => handle_response(file, rsp, progress_bar, verbose, fail_first)?,
+            Err(e) => handle_pipeline_error(e)?,
+        };
+        if !keep_going {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
 
-        let rsp_pdu = scu.receive().map_err(Box::from).context(ScuSnafu)?;
-
-        match rsp_pdu {
-            Pdu::PData { data } => {
-                let data_value = &data[0];
-
-                let cmd_obj = InMemDicomObject::read_dataset_with_ts(
-                    &data_value.data[..],
-                    &dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased(),
-                )
-                .context(ReadDatasetSnafu)?;
-                if verbose {
-                    debug!("Full response: {:?}", cmd_obj);
-                }
-                let status = cmd_obj
-                    .element(tags::STATUS)
-                    .context(MissingAttributeSnafu { tag: tags::STATUS })?
-                    .to_int::<u16>()
-                    .context(ConvertFieldSnafu { tag: tags::STATUS })?;
-                let storage_sop_instance_uid = file
-                    .sop_instance_uid
-                    .trim_end_matches(|c: char| c.is_whitespace() || c == '\0');
-
-                match status {
-                    // Success
-                    0 => {
-                        if verbose {
-                            info!("Successfully stored instance {}", storage_sop_instance_uid);
-                        }
-                    }
-                    // Warning
-                    1 | 0x0107 | 0x0116 | 0xB000..=0xBFFF => {
-                        warn!(
-                            "Possible issue storing instance `{}` (status code {:04X}H)",
-                            storage_sop_instance_uid, status
-                        );
-                    }
-                    0xFF00 | 0xFF01 => {
-                        warn!(
-                            "Possible issue storing instance `{}`: status is pending (status code {:04X}H)",
-                            storage_sop_instance_uid, status
-                        );
-                    }
-                    0xFE00 => {
-                        error!(
-                            "Could not store instance `{}`: operation cancelled",
-                            storage_sop_instance_uid
-                        );
-                        if fail_first {
-                            let _ = scu.abort();
-                            std::process::exit(-2);
-                        }
-                    }
-                    _ => {
-                        error!(
-                            "Failed to store instance `{}` (status code {:04X}H)",
-                            storage_sop_instance_uid, status
-                        );
-                        if fail_first {
-                            let _ = scu.abort();
-                            std::process::exit(-2);
-                        }
-                    }
-                }
-            }
+fn handle_pipeline_error(e: pipeline::Error) -> Result<bool, Error> {
+    match e {
+        pipeline::Error::Receive {
+            source: client::Error::UnexpectedResponse { pdu, .. },
+        } => {
+            error!("Unexpected SCP response: {:?}", pdu);
+            Ok(false)
+        }
+        e => Err(e).context(PipelineSnafu),
+    }
+}
 
-            pdu @ Pdu::Unknown { .. }
-            | pdu @ Pdu::AssociationRQ { .. }
-            | pdu @ Pdu::AssociationAC { .. }
-            | pdu @ Pdu::AssociationRJ { .. }
-            | pdu @ Pdu::ReleaseRQ
-            | pdu @ Pdu::ReleaseRP
-            | pdu @ Pdu::AbortRQ { .. } => {
-                error!("Unexpected SCP response: {:?}", pdu);
-                let _ = scu.abort();
-                std::process::exit(-2);
+/// Check the response to the C-STORE request of the given file.
+///
+/// Returns `false` if the transfer should be interrupted.
+fn handle_response(
+    file: DicomFile,
+    rsp: DimseMessage,
+    progress_bar: Option<&ProgressBar>,
+    verbose: bool,
+    fail_first: bool,
+) -> Result<bool, Error> {
+    if verbose {
+        debug!("Full response: {:?}", rsp.command);
+    }
+    let rsp = CStoreRsp::try_from(rsp.command)
+        .map_err(Box::from)
+        .context(ReadResponseSnafu)?;
+    let status = rsp.status;
+    let storage_sop_instance_uid = file
+        .sop_instance_uid
+        .trim_end_matches(|c: char| c.is_whitespace() || c == '\0');
+
+    match status.status_type() {
+        StatusType::Success => {
+            if verbose {
+                info!("Successfully stored instance {}", storage_sop_instance_uid);
+            }
+        }
+        StatusType::Warning => {
+            warn!(
+                "Possible issue storing instance `{}` (status code {})",
+                storage_sop_instance_uid, status
+            );
+        }
+        StatusType::Pending => {
+            warn!(
+                "Possible issue storing instance `{}`: status is pending (status code {})",
+                storage_sop_instance_uid, status
+            );
+        }
+        StatusType::Cancel => {
+            error!(
+                "Could not store instance `{}`: operation cancelled",
+                storage_sop_instance_uid
+            );
+            if fail_first {
+                return Ok(false);
+            }
+        }
+        StatusType::Failure => {
+            error!(
+                "Failed to store instance `{}` (status code {})",
+                storage_sop_instance_uid, status
+            );
+            if fail_first {
+                return Ok(false);
             }
         }
     }
-    if let Some(pb) = progress_bar.as_ref() {
+    if let Some(pb) = progress_bar {
         pb.inc(1)
     };
-    Ok(scu)
+    Ok(true)
 }
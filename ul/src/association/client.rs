@@ -8,11 +8,18 @@ use bytes::BytesMut;
 use std::{
     borrow::Cow,
     convert::TryInto,
-    io::{BufRead, BufReader, Cursor, Read, Write},
+    io::{BufRead, BufReader, Cursor, Read},
     net::{TcpStream, ToSocketAddrs},
     time::Duration,
 };
+#[cfg(feature = "sync-tls")]
+use std::{convert::TryFrom, sync::Arc};
 
+#[cfg(feature = "dimse")]
+use std::io::Write;
+
+#[cfg(feature = "dimse")]
+use crate::dimse::{message::command_to_pdus, Command, DimseMessage, MessageAssembler};
 use crate::{
     pdu::{
         read_pdu, write_pdu, AbortRQSource, AssociationAC, AssociationRJ, AssociationRQ, Pdu,
@@ -29,6 +36,12 @@ use bytes::Buf;
 use super::{
     pdata::{PDataReader, PDataWriter},
     uid::trim_uid,
+    Transport,
+};
+#[cfg(feature = "sync-tls")]
+use crate::tls::{
+    rustls::{pki_types::ServerName, ClientConfig, ClientConnection, StreamOwned},
+    TlsClientStream,
 };
 
 #[derive(Debug, Snafu)]
@@ -110,6 +123,14 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    /// failed to read the data set to send
+    #[cfg(feature = "dimse")]
+    #[non_exhaustive]
+    ReadDataSet {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
     /// Operation timed out
     #[non_exhaustive]
     Timeout {
@@ -133,6 +154,38 @@ pub enum Error {
 
     #[snafu(display("Connection closed by peer"))]
     ConnectionClosed,
+
+    /// failed to process DIMSE message
+    #[cfg(feature = "dimse")]
+    Dimse {
+        #[snafu(backtrace)]
+        source: crate::dimse::Error,
+    },
+
+    /// missing TLS configuration to establish a secure association
+    #[cfg(feature = "sync-tls")]
+    MissingTlsConfig { backtrace: Backtrace },
+
+    /// invalid server name for TLS
+    #[cfg(feature = "sync-tls")]
+    InvalidServerName {
+        source: crate::tls::rustls::pki_types::InvalidDnsNameError,
+        backtrace: Backtrace,
+    },
+
+    /// could not initiate TLS connection
+    #[cfg(feature = "sync-tls")]
+    TlsConnection {
+        source: crate::tls::rustls::Error,
+        backtrace: Backtrace,
+    },
+
+    /// TLS handshake failed
+    #[cfg(feature = "sync-tls")]
+    TlsHandshake {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -275,12 +328,23 @@ pub struct ClientAssociationOptions<'a> {
     saml_assertion: Option<Cow<'a, str>>,
     /// User identity JWT
     jwt: Option<Cow<'a, str>>,
+    /// whether to request a positive response to the user identity
+    user_identity_response: bool,
     /// TCP read timeout
     read_timeout: Option<Duration>,
     /// TCP write timeout
     write_timeout: Option<Duration>,
     /// TCP connection timeout
     connection_timeout: Option<Duration>,
+    /// extended negotiation sub-items
+    /// (role selection, SOP class extended negotiation)
+    extended_negotiation: Vec<UserVariableItem>,
+    /// TLS configuration for secure associations
+    #[cfg(feature = "sync-tls")]
+    tls_config: Option<Arc<ClientConfig>>,
+    /// server name to verify the acceptor's certificate against
+    #[cfg(feature = "sync-tls")]
+    server_name: Option<Cow<'a, str>>,
 }
 
 impl Default for ClientAssociationOptions<'_> {
@@ -302,9 +366,15 @@ impl Default for ClientAssociationOptions<'_> {
             kerberos_service_ticket: None,
             saml_assertion: None,
             jwt: None,
+            user_identity_response: false,
             read_timeout: None,
             write_timeout: None,
             connection_timeout: None,
+            extended_negotiation: Vec::new(),
+            #[cfg(feature = "sync-tls")]
+            tls_config: None,
+            #[cfg(feature = "sync-tls")]
+            server_name: None,
         }
     }
 }
@@ -377,6 +447,20 @@ impl<'a> ClientAssociationOptions<'a> {
         self.with_presentation_context(abstract_syntax_uid.into(), default_transfer_syntaxes)
     }
 
+    /// The presentation contexts proposed so far,
+    /// as pairs of abstract syntax and transfer syntaxes.
+    pub(crate) fn proposed_presentation_contexts(&self) -> Vec<(String, Vec<String>)> {
+        self.presentation_contexts
+            .iter()
+            .map(|(abstract_syntax, transfer_syntaxes)| {
+                (
+                    abstract_syntax.to_string(),
+                    transfer_syntaxes.iter().map(|ts| ts.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
     /// Override the maximum PDU length
     /// that this application entity will admit.
     pub fn max_pdu_length(mut self, value: u32) -> Self {
@@ -392,6 +476,94 @@ impl<'a> ClientAssociationOptions<'a> {
         self
     }
 
+    /// Propose an SCP/SCU role selection for the given SOP class.
+    ///
+    /// By default, the association requester only takes the SCU role.
+    /// Proposing the SCP role is necessary,
+    /// for instance, for receiving C-STORE sub-operations
+    /// on the same association of a C-GET request.
+    pub fn with_role_selection<T>(
+        mut self,
+        sop_class_uid: T,
+        scu_role: bool,
+        scp_role: bool,
+    ) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.extended_negotiation
+            .push(UserVariableItem::RoleSelectionSubItem(
+                trim_uid(sop_class_uid.into()).to_string(),
+                scu_role,
+                scp_role,
+            ));
+        self
+    }
+
+    /// Propose an asynchronous operations window,
+    /// with the maximum number of outstanding operations
+    /// which this node wishes to invoke and can perform on the association.
+    /// A value of 0 means unlimited.
+    ///
+    /// By default, no window is proposed,
+    /// so that only one operation may be outstanding at a time.
+    /// See [`max_operations_invoked`](ClientAssociation::max_operations_invoked)
+    /// for the window accepted by the acceptor.
+    pub fn async_operations_window(mut self, max_invoked: u16, max_performed: u16) -> Self {
+        self.extended_negotiation
+            .retain(|item| !matches!(item, UserVariableItem::AsyncOperationsWindow(..)));
+        self.extended_negotiation
+            .push(UserVariableItem::AsyncOperationsWindow(
+                max_invoked,
+                max_performed,
+            ));
+        self
+    }
+
+    /// Propose a SOP class extended negotiation sub-item
+    /// with the given service class application information,
+    /// as defined by the respective service class specification.
+    pub fn with_sop_class_extended_negotiation<T>(
+        mut self,
+        sop_class_uid: T,
+        application_information: Vec<u8>,
+    ) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.extended_negotiation
+            .push(UserVariableItem::SopClassExtendedNegotiationSubItem(
+                trim_uid(sop_class_uid.into()).to_string(),
+                application_information,
+            ));
+        self
+    }
+
+    /// Propose a SOP class common extended negotiation sub-item,
+    /// declaring the service class of the given SOP class
+    /// and the general SOP classes which it specializes.
+    pub fn with_sop_class_common_extended_negotiation<T, U>(
+        mut self,
+        sop_class_uid: T,
+        service_class_uid: T,
+        related_general_sop_class_uids: U,
+    ) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+        U: IntoIterator<Item = T>,
+    {
+        self.extended_negotiation
+            .push(UserVariableItem::SopClassCommonExtendedNegotiationSubItem(
+                trim_uid(sop_class_uid.into()).to_string(),
+                trim_uid(service_class_uid.into()).to_string(),
+                related_general_sop_class_uids
+                    .into_iter()
+                    .map(|uid| trim_uid(uid.into()).to_string())
+                    .collect(),
+            ));
+        self
+    }
+
     /// Sets the user identity username
     pub fn username<T>(mut self, username: T) -> Self
     where
@@ -501,6 +673,16 @@ impl<'a> ClientAssociationOptions<'a> {
         self
     }
 
+    /// Request a positive response to the user identity from the acceptor,
+    /// which is then available through
+    /// [`ClientAssociation::user_identity_response`].
+    ///
+    /// The default is not to request a response.
+    pub fn request_user_identity_response(mut self, request: bool) -> Self {
+        self.user_identity_response = request;
+        self
+    }
+
     /// Initiate the TCP connection to the given address
     /// and request a new DICOM association,
     /// negotiating the presentation contexts in the process.
@@ -508,7 +690,7 @@ impl<'a> ClientAssociationOptions<'a> {
         self,
         address: A,
     ) -> Result<ClientAssociation<std::net::TcpStream>> {
-        self.establish_impl(AeAddr::new_socket_addr(address))
+        self.establish_impl(AeAddr::new_socket_addr(address), Ok)
     }
 
     /// Initiate the TCP connection to the given address
@@ -540,11 +722,101 @@ impl<'a> ClientAssociationOptions<'a> {
         ae_address: &str,
     ) -> Result<ClientAssociation<std::net::TcpStream>> {
         match ae_address.try_into() {
-            Ok(ae_address) => self.establish_impl(ae_address),
-            Err(_) => self.establish_impl(AeAddr::new_socket_addr(ae_address)),
+            Ok(ae_address) => self.establish_impl(ae_address, Ok),
+            Err(_) => self.establish_impl(AeAddr::new_socket_addr(ae_address), Ok),
         }
     }
 
+    /// Define the TLS configuration
+    /// for establishing secure associations
+    /// with [`establish_tls`](Self::establish_tls)
+    /// or [`establish_with_tls`](Self::establish_with_tls).
+    ///
+    /// See the [`tls`](crate::tls) module
+    /// for a convenient way to build one.
+    #[cfg(feature = "sync-tls")]
+    pub fn tls_config(mut self, config: Arc<ClientConfig>) -> Self {
+        self.tls_config = Some(config);
+        self
+    }
+
+    /// Define the name of the server
+    /// against which the acceptor's certificate is verified.
+    ///
+    /// By default, the host name in the address is used,
+    /// or the peer IP address if not available.
+    #[cfg(feature = "sync-tls")]
+    pub fn server_name<T>(mut self, server_name: T) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.server_name = Some(server_name.into());
+        self
+    }
+
+    /// Initiate the TCP connection to the given address,
+    /// secure it with TLS,
+    /// and request a new DICOM association,
+    /// negotiating the presentation contexts in the process.
+    ///
+    /// A TLS configuration must have been defined via
+    /// [`tls_config`](Self::tls_config).
+    #[cfg(feature = "sync-tls")]
+    pub fn establish_tls<A: ToSocketAddrs>(
+        self,
+        address: A,
+    ) -> Result<ClientAssociation<TlsClientStream>> {
+        let (config, server_name) = self.tls_parameters(None)?;
+        self.establish_impl(AeAddr::new_socket_addr(address), move |socket| {
+            tls_connect(socket, config, server_name)
+        })
+    }
+
+    /// Initiate the TCP connection to the given address,
+    /// secure it with TLS,
+    /// and request a new DICOM association,
+    /// negotiating the presentation contexts in the process.
+    ///
+    /// This method allows you to specify the called AE title
+    /// alongside with the socket address,
+    /// as in [`establish_with`](Self::establish_with).
+    /// A TLS configuration must have been defined via
+    /// [`tls_config`](Self::tls_config).
+    #[cfg(feature = "sync-tls")]
+    #[allow(unreachable_patterns)]
+    pub fn establish_with_tls(
+        self,
+        ae_address: &str,
+    ) -> Result<ClientAssociation<TlsClientStream>> {
+        let (config, server_name) = self.tls_parameters(Some(host_name(ae_address)))?;
+        let connect = move |socket| tls_connect(socket, config, server_name);
+        match ae_address.try_into() {
+            Ok(ae_address) => self.establish_impl(ae_address, connect),
+            Err(_) => self.establish_impl(AeAddr::new_socket_addr(ae_address), connect),
+        }
+    }
+
+    /// Obtain the TLS configuration and the server name to verify,
+    /// which is only resolved here if given explicitly
+    /// or through a host name in the address.
+    #[cfg(feature = "sync-tls")]
+    fn tls_parameters(
+        &self,
+        host: Option<&str>,
+    ) -> Result<(Arc<ClientConfig>, Option<ServerName<'static>>)> {
+        let config = self
+            .tls_config
+            .clone()
+            .ok_or_else(|| MissingTlsConfigSnafu.build())?;
+        let server_name = self
+            .server_name
+            .as_deref()
+            .or(host)
+            .map(|name| ServerName::try_from(name.to_string()).context(InvalidServerNameSnafu))
+            .transpose()?;
+        Ok((config, server_name))
+    }
+
     /// Set the read timeout for the underlying TCP socket
     ///
     /// This is used to set both the read and write timeout.
@@ -571,13 +843,67 @@ impl<'a> ClientAssociationOptions<'a> {
         }
     }
 
-    fn establish_impl<T>(
-        self,
-        ae_address: AeAddr<T>,
-    ) -> Result<ClientAssociation<std::net::TcpStream>>
+    fn establish_impl<T, S, F>(self, ae_address: AeAddr<T>, wrap: F) -> Result<ClientAssociation<S>>
     where
         T: ToSocketAddrs,
+        S: Transport,
+        F: FnOnce(TcpStream) -> Result<S>,
     {
+        // fail before connecting if no presentation contexts were provided
+        ensure!(
+            !self.presentation_contexts.is_empty(),
+            MissingAbstractSyntaxSnafu
+        );
+
+        let address_ae_title = ae_address.ae_title().map(String::from);
+        let conn_result: Result<TcpStream> = if let Some(timeout) = self.connection_timeout {
+            let addresses = ae_address.to_socket_addrs().context(ToAddressSnafu)?;
+
+            let mut result: Result<TcpStream, std::io::Error> =
+                Result::Err(std::io::Error::from(std::io::ErrorKind::AddrNotAvailable));
+
+            for address in addresses {
+                result = std::net::TcpStream::connect_timeout(&address, timeout);
+                if result.is_ok() {
+                    break;
+                }
+            }
+            result.context(ConnectSnafu)
+        } else {
+            std::net::TcpStream::connect(ae_address).context(ConnectSnafu)
+        };
+
+        let socket = conn_result?;
+        socket
+            .set_read_timeout(self.read_timeout)
+            .context(SetReadTimeoutSnafu)?;
+        socket
+            .set_write_timeout(self.write_timeout)
+            .context(SetWriteTimeoutSnafu)?;
+        let socket = wrap(socket)?;
+        self.negotiate(socket, address_ae_title.as_deref())
+    }
+
+    /// Request a new DICOM association
+    /// over an already connected transport,
+    /// negotiating the presentation contexts in the process.
+    ///
+    /// This allows associations to run on streams other than TCP,
+    /// such as Unix domain sockets
+    /// or an in-memory [`duplex`](crate::association::duplex) pipe.
+    /// The called AE title is taken from the `called_ae_title` option,
+    /// or `ANY-SCP` if not specified.
+    /// Connection, read, and write timeouts are not applied to the stream.
+    pub fn establish_over<S: Transport>(self, stream: S) -> Result<ClientAssociation<S>> {
+        self.negotiate(stream, None)
+    }
+
+    /// Request the association through the given stream.
+    fn negotiate<S: Transport>(
+        self,
+        mut socket: S,
+        address_ae_title: Option<&str>,
+    ) -> Result<ClientAssociation<S>> {
         let ClientAssociationOptions {
             calling_ae_title,
             called_ae_title,
@@ -591,9 +917,15 @@ impl<'a> ClientAssociationOptions<'a> {
             kerberos_service_ticket,
             saml_assertion,
             jwt,
+            user_identity_response,
             read_timeout,
             write_timeout,
-            connection_timeout,
+            connection_timeout: _,
+            extended_negotiation,
+            #[cfg(feature = "sync-tls")]
+                tls_config: _,
+            #[cfg(feature = "sync-tls")]
+                server_name: _,
         } = self;
 
         // fail if no presentation contexts were provided: they represent intent,
@@ -604,7 +936,7 @@ impl<'a> ClientAssociationOptions<'a> {
         );
 
         // choose called AE title
-        let called_ae_title: &str = match (&called_ae_title, ae_address.ae_title()) {
+        let called_ae_title: &str = match (&called_ae_title, address_ae_title) {
             (Some(aec), Some(_)) => {
                 tracing::warn!(
                     "Option `called_ae_title` overrides the AE title to `{}`",
@@ -630,14 +962,20 @@ impl<'a> ClientAssociationOptions<'a> {
                     .collect(),
             })
             .collect();
+        let abstract_syntaxes: Vec<_> = presentation_contexts
+            .iter()
+            .map(|pc| (pc.id, pc.abstract_syntax.clone()))
+            .collect();
 
         let mut user_variables = vec![
             UserVariableItem::MaxLength(max_pdu_length),
             UserVariableItem::ImplementationClassUID(IMPLEMENTATION_CLASS_UID.to_string()),
             UserVariableItem::ImplementationVersionName(IMPLEMENTATION_VERSION_NAME.to_string()),
         ];
+        user_variables.extend(extended_negotiation);
 
         if let Some(user_identity) = Self::determine_user_identity(
+            user_identity_response,
             username,
             password,
             kerberos_service_ticket,
@@ -656,30 +994,6 @@ impl<'a> ClientAssociationOptions<'a> {
             user_variables,
         });
 
-        let conn_result: Result<TcpStream> = if let Some(timeout) = connection_timeout {
-            let addresses = ae_address.to_socket_addrs().context(ToAddressSnafu)?;
-
-            let mut result: Result<TcpStream, std::io::Error> =
-                Result::Err(std::io::Error::from(std::io::ErrorKind::AddrNotAvailable));
-
-            for address in addresses {
-                result = std::net::TcpStream::connect_timeout(&address, timeout);
-                if result.is_ok() {
-                    break;
-                }
-            }
-            result.context(ConnectSnafu)
-        } else {
-            std::net::TcpStream::connect(ae_address).context(ConnectSnafu)
-        };
-
-        let mut socket = conn_result?;
-        socket
-            .set_read_timeout(read_timeout)
-            .context(SetReadTimeoutSnafu)?;
-        socket
-            .set_write_timeout(write_timeout)
-            .context(SetWriteTimeoutSnafu)?;
         let mut buffer: Vec<u8> = Vec::with_capacity(max_pdu_length as usize);
         // send request
 
@@ -748,6 +1062,7 @@ impl<'a> ClientAssociationOptions<'a> {
                 }
                 Ok(ClientAssociation {
                     presentation_contexts,
+                    abstract_syntaxes,
                     requestor_max_pdu_length: max_pdu_length,
                     acceptor_max_pdu_length,
                     socket,
@@ -757,6 +1072,8 @@ impl<'a> ClientAssociationOptions<'a> {
                     read_timeout,
                     write_timeout,
                     user_variables,
+                    #[cfg(feature = "dimse")]
+                    dimse: MessageAssembler::default(),
                 })
             }
             Pdu::AssociationRJ(association_rj) => RejectedSnafu { association_rj }.fail(),
@@ -790,6 +1107,7 @@ impl<'a> ClientAssociationOptions<'a> {
     }
 
     fn determine_user_identity<T>(
+        positive_response_requested: bool,
         username: Option<T>,
         password: Option<T>,
         kerberos_service_ticket: Option<T>,
@@ -802,14 +1120,14 @@ impl<'a> ClientAssociationOptions<'a> {
         if let Some(username) = username {
             if let Some(password) = password {
                 return Some(UserIdentity::new(
-                    false,
+                    positive_response_requested,
                     UserIdentityType::UsernamePassword,
                     username.into().as_bytes().to_vec(),
                     password.into().as_bytes().to_vec(),
                 ));
             } else {
                 return Some(UserIdentity::new(
-                    false,
+                    positive_response_requested,
                     UserIdentityType::Username,
                     username.into().as_bytes().to_vec(),
                     vec![],
@@ -819,7 +1137,7 @@ impl<'a> ClientAssociationOptions<'a> {
 
         if let Some(kerberos_service_ticket) = kerberos_service_ticket {
             return Some(UserIdentity::new(
-                false,
+                positive_response_requested,
                 UserIdentityType::KerberosServiceTicket,
                 kerberos_service_ticket.into().as_bytes().to_vec(),
                 vec![],
@@ -828,7 +1146,7 @@ impl<'a> ClientAssociationOptions<'a> {
 
         if let Some(saml_assertion) = saml_assertion {
             return Some(UserIdentity::new(
-                false,
+                positive_response_requested,
                 UserIdentityType::SamlAssertion,
                 saml_assertion.into().as_bytes().to_vec(),
                 vec![],
@@ -837,7 +1155,7 @@ impl<'a> ClientAssociationOptions<'a> {
 
         if let Some(jwt) = jwt {
             return Some(UserIdentity::new(
-                false,
+                positive_response_requested,
                 UserIdentityType::Jwt,
                 jwt.into().as_bytes().to_vec(),
                 vec![],
@@ -848,6 +1166,38 @@ impl<'a> ClientAssociationOptions<'a> {
     }
 }
 
+/// Obtain the host part of an address
+/// in the form `[«ae_title»@]«host»:«port»`.
+#[cfg(feature = "sync-tls")]
+fn host_name(address: &str) -> &str {
+    let address = address.split_once('@').map_or(address, |(_, addr)| addr);
+    let host = address.rsplit_once(':').map_or(address, |(host, _)| host);
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+/// Secure a freshly connected TCP stream with TLS,
+/// driving the handshake to completion.
+#[cfg(feature = "sync-tls")]
+fn tls_connect(
+    socket: TcpStream,
+    config: Arc<ClientConfig>,
+    server_name: Option<ServerName<'static>>,
+) -> Result<TlsClientStream> {
+    let server_name = match server_name {
+        Some(server_name) => server_name,
+        None => ServerName::IpAddress(socket.peer_addr().context(ConnectSnafu)?.ip().into()),
+    };
+    let conn = ClientConnection::new(config, server_name).context(TlsConnectionSnafu)?;
+    let mut stream = StreamOwned::new(conn, socket);
+    while stream.conn.is_handshaking() {
+        stream
+            .conn
+            .complete_io(&mut stream.sock)
+            .context(TlsHandshakeSnafu)?;
+    }
+    Ok(stream)
+}
+
 /// Trait to close underlying socket
 pub trait CloseSocket {
     fn close(&mut self) -> std::io::Result<()>;
@@ -859,12 +1209,19 @@ impl CloseSocket for std::net::TcpStream {
     }
 }
 
+#[cfg(unix)]
+impl CloseSocket for std::os::unix::net::UnixStream {
+    fn close(&mut self) -> std::io::Result<()> {
+        self.shutdown(std::net::Shutdown::Both)
+    }
+}
+
 /// Trait to release association
 pub trait Release {
     fn release(&mut self) -> Result<()>;
 }
 
-impl Release for ClientAssociation<std::net::TcpStream> {
+impl<S: Transport> Release for ClientAssociation<S> {
     fn release(&mut self) -> Result<()> {
         self.release_impl()
     }
@@ -895,6 +1252,8 @@ where
     /// The presentation contexts accorded with the acceptor application entity,
     /// without the rejected ones.
     presentation_contexts: Vec<PresentationContextResult>,
+    /// The abstract syntax proposed for each presentation context, by identifier
+    abstract_syntaxes: Vec<(u8, String)>,
     /// The maximum PDU length that this application entity is expecting to receive
     requestor_max_pdu_length: u32,
     /// The maximum PDU length that the remote application entity accepts
@@ -913,6 +1272,9 @@ where
     read_buffer: BytesMut,
     /// User variables that were taken from the server
     user_variables: Vec<UserVariableItem>,
+    /// Reassembler of incoming DIMSE messages
+    #[cfg(feature = "dimse")]
+    dimse: MessageAssembler,
 }
 
 impl<S: CloseSocket> ClientAssociation<S>
@@ -934,6 +1296,15 @@ where
         &self.presentation_contexts
     }
 
+    /// Retrieve the abstract syntax proposed for the presentation context
+    /// with the given identifier.
+    pub fn abstract_syntax(&self, presentation_context_id: u8) -> Option<&str> {
+        self.abstract_syntaxes
+            .iter()
+            .find(|(id, _)| *id == presentation_context_id)
+            .map(|(_, uid)| uid.as_str())
+    }
+
     /// Retrieve the maximum PDU length
     /// admitted by the association acceptor.
     pub fn acceptor_max_pdu_length(&self) -> u32 {
@@ -957,12 +1328,38 @@ where
     pub fn user_variables(&self) -> &[UserVariableItem] {
         &self.user_variables
     }
+
+    /// Retrieve the server response to the user identity,
+    /// if the acceptor gave a positive response.
+    ///
+    /// A response is only given when requested
+    /// with [`ClientAssociationOptions::request_user_identity_response`].
+    /// It is empty for the username identity types.
+    pub fn user_identity_response(&self) -> Option<&[u8]> {
+        self.user_variables.iter().find_map(|item| match item {
+            UserVariableItem::UserIdentityResponse(response) => Some(response.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// Retrieve the maximum number of operations
+    /// which this node may have outstanding at once,
+    /// as accorded by the acceptor in the asynchronous operations window.
+    ///
+    /// This is 1 if no window was negotiated,
+    /// and 0 if the number is unlimited.
+    pub fn max_operations_invoked(&self) -> u16 {
+        self.user_variables
+            .iter()
+            .find_map(|item| match item {
+                UserVariableItem::AsyncOperationsWindow(_, max_performed) => Some(*max_performed),
+                _ => None,
+            })
+            .unwrap_or(1)
+    }
 }
 
-impl ClientAssociation<std::net::TcpStream>
-where
-    ClientAssociation<std::net::TcpStream>: Release,
-{
+impl<S: Transport> ClientAssociation<S> {
     /// Send a PDU message to the other intervenient.
     pub fn send(&mut self, msg: &Pdu) -> Result<()> {
         self.buffer.clear();
@@ -1012,7 +1409,7 @@ where
     /// and then shutting down the TCP connection.
     pub fn release(mut self) -> Result<()> {
         let out = self.release_impl();
-        let _ = self.socket.shutdown(std::net::Shutdown::Both);
+        let _ = self.socket.close();
         out
     }
 
@@ -1023,11 +1420,11 @@ where
             source: AbortRQSource::ServiceUser,
         };
         let out = self.send(&pdu);
-        let _ = self.socket.shutdown(std::net::Shutdown::Both);
+        let _ = self.socket.close();
         out
     }
 
-    /// Obtain access to the inner TCP stream
+    /// Obtain access to the inner stream
     /// connected to the association acceptor.
     ///
     /// This can be used to send the PDU in semantic fragments of the message,
@@ -1036,7 +1433,7 @@ where
     /// **Note:** reading and writing should be done with care
     /// to avoid inconsistencies in the association state.
     /// Do not call `send` and `receive` while not in a PDU boundary.
-    pub fn inner_stream(&mut self) -> &mut std::net::TcpStream {
+    pub fn inner_stream(&mut self) -> &mut S {
         &mut self.socket
     }
 
@@ -1045,10 +1442,7 @@ where
     ///
     /// Returns a writer which automatically
     /// splits the inner data into separate PDUs if necessary.
-    pub fn send_pdata(
-        &mut self,
-        presentation_context_id: u8,
-    ) -> PDataWriter<&mut std::net::TcpStream> {
+    pub fn send_pdata(&mut self, presentation_context_id: u8) -> PDataWriter<&mut S> {
         PDataWriter::new(
             &mut self.socket,
             presentation_context_id,
@@ -1061,7 +1455,7 @@ where
     ///
     /// Returns a reader which automatically
     /// receives more data PDUs once the bytes collected are consumed.
-    pub fn receive_pdata(&mut self) -> PDataReader<&mut std::net::TcpStream> {
+    pub fn receive_pdata(&mut self) -> PDataReader<&mut S> {
         PDataReader::new(
             &mut self.socket,
             self.requestor_max_pdu_length,
@@ -1069,6 +1463,81 @@ where
         )
     }
 
+    /// Send a full DIMSE message to the association acceptor,
+    /// split into as many P-Data PDUs as necessary.
+    #[cfg(feature = "dimse")]
+    pub fn send_dimse(&mut self, msg: &DimseMessage) -> Result<()> {
+        for pdu in msg
+            .to_pdus(self.acceptor_max_pdu_length)
+            .context(DimseSnafu)?
+        {
+            self.send(&pdu)?;
+        }
+        Ok(())
+    }
+
+    /// Send a DIMSE command to the association acceptor,
+    /// followed by a data set read from the given source
+    /// as it is sent.
+    ///
+    /// The data set must already be encoded
+    /// in the transfer syntax of the presentation context,
+    /// such as the data set of a file in that same transfer syntax.
+    /// Unlike [`send_dimse`](Self::send_dimse),
+    /// this does not need to hold the whole data set in memory.
+    /// If reading the data set fails,
+    /// part of it may have been sent already,
+    /// so the association should be aborted.
+    #[cfg(feature = "dimse")]
+    pub fn send_dimse_from<R>(
+        &mut self,
+        presentation_context_id: u8,
+        command: &Command,
+        mut data: R,
+    ) -> Result<()>
+    where
+        R: Read,
+    {
+        for pdu in command_to_pdus(
+            presentation_context_id,
+            command,
+            self.acceptor_max_pdu_length,
+        )
+        .context(DimseSnafu)?
+        {
+            self.send(&pdu)?;
+        }
+
+        let mut buffer = vec![0; self.acceptor_max_pdu_length as usize];
+        let mut writer = self.send_pdata(presentation_context_id);
+        loop {
+            let n = data.read(&mut buffer).context(ReadDataSetSnafu)?;
+            if n == 0 {
+                break;
+            }
+            writer.write_all(&buffer[..n]).context(WireSendSnafu)?;
+        }
+        writer.finish().context(WireSendSnafu)
+    }
+
+    /// Receive a full DIMSE message from the association acceptor.
+    ///
+    /// Fails with [`Error::UnexpectedResponse`]
+    /// if a PDU other than P-Data is received,
+    /// such as an abort request.
+    #[cfg(feature = "dimse")]
+    pub fn receive_dimse(&mut self) -> Result<DimseMessage> {
+        loop {
+            if let Some(msg) = self.dimse.pop_message() {
+                return Ok(msg);
+            }
+            match self.receive()? {
+                Pdu::PData { data } => self.dimse.push_values(data).context(DimseSnafu)?,
+                pdu => return UnexpectedResponseSnafu { pdu }.fail(),
+            }
+        }
+    }
+
     /// Release implementation function,
     /// which tries to send a release request and receive a release response.
     /// This is in a separate private function because
@@ -1133,7 +1602,17 @@ pub mod non_blocking {
     };
     use bytes::{Buf, BytesMut};
     use snafu::{ensure, ResultExt};
-    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+    #[cfg(feature = "async-tls")]
+    use {
+        super::TlsHandshakeSnafu,
+        crate::tls::{rustls::pki_types::ServerName, AsyncTlsClientStream},
+    };
+    #[cfg(feature = "dimse")]
+    use {
+        super::{DimseSnafu, ReadDataSetSnafu},
+        crate::dimse::{message::command_to_pdus, Command, DimseMessage, MessageAssembler},
+    };
 
     pub async fn get_client_pdu_async<R: AsyncRead + Unpin>(
         reader: &mut R,
@@ -1182,12 +1661,86 @@ pub mod non_blocking {
     }
 
     impl ClientAssociationOptions<'_> {
-        async fn establish_impl_async<T>(
+        async fn establish_impl_async<T, S, F, W>(
             self,
             ae_address: AeAddr<T>,
-        ) -> Result<ClientAssociation<tokio::net::TcpStream>>
+            wrap: F,
+        ) -> Result<ClientAssociation<S>>
         where
             T: tokio::net::ToSocketAddrs,
+            S: AsyncRead + AsyncWrite + CloseSocket + Unpin,
+            ClientAssociation<S>: Release,
+            F: FnOnce(tokio::net::TcpStream) -> W,
+            W: Future<Output = Result<S>>,
+        {
+            // fail before connecting if no presentation contexts were provided
+            ensure!(
+                !self.presentation_contexts.is_empty(),
+                MissingAbstractSyntaxSnafu
+            );
+
+            let address_ae_title = ae_address.ae_title().map(String::from);
+            let conn_result: Result<tokio::net::TcpStream> =
+                if let Some(timeout) = self.connection_timeout {
+                    let addresses = tokio::net::lookup_host(ae_address.socket_addr())
+                        .await
+                        .context(ToAddressSnafu)?;
+
+                    let mut result: Result<tokio::net::TcpStream, std::io::Error> =
+                        Result::Err(std::io::Error::from(std::io::ErrorKind::AddrNotAvailable));
+
+                    for address in addresses {
+                        result = match tokio::time::timeout(
+                            timeout,
+                            tokio::net::TcpStream::connect(&address),
+                        )
+                        .await
+                        {
+                            Ok(inner) => inner,
+                            Err(_) => result,
+                        };
+                        if result.is_ok() {
+                            break;
+                        }
+                    }
+                    result.context(ConnectSnafu)
+                } else {
+                    tokio::net::TcpStream::connect(ae_address.socket_addr())
+                        .await
+                        .context(ConnectSnafu)
+                };
+
+            let socket = wrap(conn_result?).await?;
+            self.negotiate_async(socket, address_ae_title.as_deref())
+                .await
+        }
+
+        /// Request a new DICOM association
+        /// over an already connected asynchronous transport,
+        /// negotiating the presentation contexts in the process.
+        ///
+        /// This allows associations to run on streams other than TCP,
+        /// such as Unix domain sockets
+        /// or an in-memory [`DuplexStream`](tokio::io::DuplexStream).
+        /// The called AE title is taken from the `called_ae_title` option,
+        /// or `ANY-SCP` if not specified.
+        pub async fn establish_over_async<S>(self, stream: S) -> Result<ClientAssociation<S>>
+        where
+            S: AsyncRead + AsyncWrite + CloseSocket + Unpin,
+            ClientAssociation<S>: Release,
+        {
+            self.negotiate_async(stream, None).await
+        }
+
+        /// Request the association through the given stream.
+        async fn negotiate_async<S>(
+            self,
+            mut socket: S,
+            address_ae_title: Option<&str>,
+        ) -> Result<ClientAssociation<S>>
+        where
+            S: AsyncRead + AsyncWrite + CloseSocket + Unpin,
+            ClientAssociation<S>: Release,
         {
             let ClientAssociationOptions {
                 calling_ae_title,
@@ -1202,9 +1755,15 @@ pub mod non_blocking {
                 kerberos_service_ticket,
                 saml_assertion,
                 jwt,
+                user_identity_response,
                 read_timeout,
                 write_timeout,
-                connection_timeout,
+                connection_timeout: _,
+                extended_negotiation,
+                #[cfg(feature = "sync-tls")]
+                    tls_config: _,
+                #[cfg(feature = "sync-tls")]
+                    server_name: _,
             } = self;
 
             // fail if no presentation contexts were provided: they represent intent,
@@ -1215,7 +1774,7 @@ pub mod non_blocking {
             );
 
             // choose called AE title
-            let called_ae_title: &str = match (&called_ae_title, ae_address.ae_title()) {
+            let called_ae_title: &str = match (&called_ae_title, address_ae_title) {
                 (Some(aec), Some(_)) => {
                     tracing::warn!(
                         "Option `called_ae_title` overrides the AE title to `{}`",
@@ -1241,6 +1800,10 @@ pub mod non_blocking {
                         .collect(),
                 })
                 .collect();
+            let abstract_syntaxes: Vec<_> = presentation_contexts
+                .iter()
+                .map(|pc| (pc.id, pc.abstract_syntax.clone()))
+                .collect();
 
             let mut user_variables = vec![
                 UserVariableItem::MaxLength(max_pdu_length),
@@ -1249,8 +1812,10 @@ pub mod non_blocking {
                     IMPLEMENTATION_VERSION_NAME.to_string(),
                 ),
             ];
+            user_variables.extend(extended_negotiation);
 
             if let Some(user_identity) = Self::determine_user_identity(
+                user_identity_response,
                 username,
                 password,
                 kerberos_service_ticket,
@@ -1268,37 +1833,6 @@ pub mod non_blocking {
                 presentation_contexts,
                 user_variables,
             });
-            let conn_result: Result<tokio::net::TcpStream> =
-                if let Some(timeout) = connection_timeout {
-                    let addresses = tokio::net::lookup_host(ae_address.socket_addr())
-                        .await
-                        .context(ToAddressSnafu)?;
-
-                    let mut result: Result<tokio::net::TcpStream, std::io::Error> =
-                        Result::Err(std::io::Error::from(std::io::ErrorKind::AddrNotAvailable));
-
-                    for address in addresses {
-                        result = match tokio::time::timeout(
-                            timeout,
-                            tokio::net::TcpStream::connect(&address),
-                        )
-                        .await
-                        {
-                            Ok(inner) => inner,
-                            Err(_) => result,
-                        };
-                        if result.is_ok() {
-                            break;
-                        }
-                    }
-                    result.context(ConnectSnafu)
-                } else {
-                    tokio::net::TcpStream::connect(ae_address.socket_addr())
-                        .await
-                        .context(ConnectSnafu)
-                };
-
-            let mut socket = conn_result?;
             let mut buffer: Vec<u8> = Vec::with_capacity(max_pdu_length as usize);
 
             // send request
@@ -1367,6 +1901,7 @@ pub mod non_blocking {
                     }
                     Ok(ClientAssociation {
                         presentation_contexts,
+                        abstract_syntaxes,
                         requestor_max_pdu_length: max_pdu_length,
                         acceptor_max_pdu_length,
                         socket,
@@ -1375,7 +1910,9 @@ pub mod non_blocking {
                         read_timeout,
                         write_timeout,
                         read_buffer: BytesMut::with_capacity(MAXIMUM_PDU_SIZE as usize),
-                        user_variables
+                        user_variables,
+                        #[cfg(feature = "dimse")]
+                        dimse: MessageAssembler::default(),
                     })
                 }
                 Pdu::AssociationRJ(association_rj) => RejectedSnafu { association_rj }.fail(),
@@ -1421,7 +1958,7 @@ pub mod non_blocking {
             self,
             address: A,
         ) -> Result<ClientAssociation<tokio::net::TcpStream>> {
-            self.establish_impl_async(AeAddr::new_socket_addr(address))
+            self.establish_impl_async(AeAddr::new_socket_addr(address), plain)
                 .await
         }
 
@@ -1456,184 +1993,352 @@ pub mod non_blocking {
             ae_address: &str,
         ) -> Result<ClientAssociation<tokio::net::TcpStream>> {
             match ae_address.try_into() {
-                Ok(ae_address) => self.establish_impl_async(ae_address).await,
+                Ok(ae_address) => self.establish_impl_async(ae_address, plain).await,
                 Err(_) => {
-                    self.establish_impl_async(AeAddr::new_socket_addr(ae_address))
+                    self.establish_impl_async(AeAddr::new_socket_addr(ae_address), plain)
                         .await
                 }
             }
         }
-    }
 
-    impl ClientAssociation<tokio::net::TcpStream>
-    where
-        ClientAssociation<tokio::net::TcpStream>: Release,
-    {
-        /// Send a PDU message to the other intervenient.
-        pub async fn send(&mut self, msg: &Pdu) -> Result<()> {
-            self.buffer.clear();
-            write_pdu(&mut self.buffer, msg).context(SendRequestSnafu)?;
-            if self.buffer.len() > self.acceptor_max_pdu_length as usize {
-                return SendTooLongPduSnafu {
-                    length: self.buffer.len(),
-                }
-                .fail();
-            }
-            timeout(self.write_timeout, async {
-                self.socket
-                    .write_all(&self.buffer)
-                    .await
-                    .context(WireSendSnafu)
+        /// Initiate the TCP connection to the given address,
+        /// secure it with TLS,
+        /// and request a new DICOM association,
+        /// negotiating the presentation contexts in the process.
+        ///
+        /// A TLS configuration must have been defined via
+        /// [`tls_config`](Self::tls_config).
+        #[cfg(feature = "async-tls")]
+        pub async fn establish_tls_async<A: tokio::net::ToSocketAddrs>(
+            self,
+            address: A,
+        ) -> Result<ClientAssociation<AsyncTlsClientStream>> {
+            let (config, server_name) = self.tls_parameters(None)?;
+            self.establish_impl_async(AeAddr::new_socket_addr(address), move |socket| {
+                tls_connect_async(socket, config, server_name)
             })
             .await
         }
 
-        /// Read a PDU message from the other intervenient.
-        pub async fn receive(&mut self) -> Result<Pdu> {
-            timeout(self.read_timeout, async {
-                loop {
-                    let mut buf = Cursor::new(&self.read_buffer[..]);
-                    match read_pdu(&mut buf, self.requestor_max_pdu_length, self.strict)
-                        .context(ReceiveResponseSnafu)?
-                    {
-                        Some(pdu) => {
-                            self.read_buffer.advance(buf.position() as usize);
-                            return Ok(pdu);
-                        }
-                        None => {
-                            // Reset position
-                            buf.set_position(0)
-                        }
-                    }
-                    let recv = self
-                        .socket
-                        .read_buf(&mut self.read_buffer)
+        /// Initiate the TCP connection to the given address,
+        /// secure it with TLS,
+        /// and request a new DICOM association,
+        /// negotiating the presentation contexts in the process.
+        ///
+        /// This method allows you to specify the called AE title
+        /// alongside with the socket address,
+        /// as in [`establish_with_async`](Self::establish_with_async).
+        /// A TLS configuration must have been defined via
+        /// [`tls_config`](Self::tls_config).
+        #[cfg(feature = "async-tls")]
+        #[allow(unreachable_patterns)]
+        pub async fn establish_with_tls_async(
+            self,
+            ae_address: &str,
+        ) -> Result<ClientAssociation<AsyncTlsClientStream>> {
+            let (config, server_name) = self.tls_parameters(Some(super::host_name(ae_address)))?;
+            let connect = move |socket| tls_connect_async(socket, config, server_name);
+            match ae_address.try_into() {
+                Ok(ae_address) => self.establish_impl_async(ae_address, connect).await,
+                Err(_) => {
+                    self.establish_impl_async(AeAddr::new_socket_addr(ae_address), connect)
                         .await
-                        .context(ReadPduSnafu)
-                        .context(ReceiveSnafu)?;
-                    ensure!(recv > 0, ConnectionClosedSnafu);
                 }
-            })
-            .await
+            }
         }
+    }
 
-        /// Gracefully terminate the association by exchanging release messages
-        /// and then shutting down the TCP connection.
-        pub async fn release(mut self) -> Result<()> {
-            timeout(self.write_timeout, async {
-                let out = self.release_impl().await;
-                let _ = self.socket.shutdown().await;
-                out
-            })
-            .await
-        }
+    /// Use the TCP stream as is.
+    async fn plain(socket: tokio::net::TcpStream) -> Result<tokio::net::TcpStream> {
+        Ok(socket)
+    }
 
-        /// Send an abort message and shut down the TCP connection,
-        /// terminating the association.
-        pub async fn abort(mut self) -> Result<()> {
-            timeout(self.write_timeout, async {
-                let pdu = Pdu::AbortRQ {
-                    source: AbortRQSource::ServiceUser,
-                };
-                let out = self.send(&pdu).await;
-                let _ = self.socket.shutdown().await;
-                out
-            })
+    /// Secure a freshly connected TCP stream with TLS.
+    #[cfg(feature = "async-tls")]
+    async fn tls_connect_async(
+        socket: tokio::net::TcpStream,
+        config: std::sync::Arc<crate::tls::rustls::ClientConfig>,
+        server_name: Option<ServerName<'static>>,
+    ) -> Result<AsyncTlsClientStream> {
+        let server_name = match server_name {
+            Some(server_name) => server_name,
+            None => ServerName::IpAddress(socket.peer_addr().context(ConnectSnafu)?.ip().into()),
+        };
+        tokio_rustls::TlsConnector::from(config)
+            .connect(server_name, socket)
             .await
-        }
+            .context(TlsHandshakeSnafu)
+    }
 
-        /// Prepare a P-Data writer for sending
-        /// one or more data items.
-        ///
-        /// Returns a writer which automatically
-        /// splits the inner data into separate PDUs if necessary.
-        pub async fn send_pdata(
-            &mut self,
-            presentation_context_id: u8,
-        ) -> AsyncPDataWriter<&mut tokio::net::TcpStream> {
-            AsyncPDataWriter::new(
-                &mut self.socket,
-                presentation_context_id,
-                self.acceptor_max_pdu_length,
-            )
-        }
+    /// Implement the asynchronous operations of an association requester
+    /// over the given stream type.
+    macro_rules! impl_async_client_association {
+        ($stream: ty) => {
+            impl ClientAssociation<$stream> {
+                /// Send a PDU message to the other intervenient.
+                pub async fn send(&mut self, msg: &Pdu) -> Result<()> {
+                    self.buffer.clear();
+                    write_pdu(&mut self.buffer, msg).context(SendRequestSnafu)?;
+                    if self.buffer.len() > self.acceptor_max_pdu_length as usize {
+                        return SendTooLongPduSnafu {
+                            length: self.buffer.len(),
+                        }
+                        .fail();
+                    }
+                    timeout(self.write_timeout, async {
+                        self.socket
+                            .write_all(&self.buffer)
+                            .await
+                            .context(WireSendSnafu)
+                    })
+                    .await
+                }
 
-        /// Prepare a P-Data reader for receiving
-        /// one or more data item PDUs.
-        ///
-        /// Returns a reader which automatically
-        /// receives more data PDUs once the bytes collected are consumed.
-        #[cfg(feature = "async")]
-        pub fn receive_pdata(&mut self) -> PDataReader<&mut tokio::net::TcpStream> {
-            PDataReader::new(
-                &mut self.socket,
-                self.requestor_max_pdu_length,
-                &mut self.read_buffer,
-            )
-        }
+                /// Read a PDU message from the other intervenient.
+                pub async fn receive(&mut self) -> Result<Pdu> {
+                    timeout(self.read_timeout, async {
+                        loop {
+                            let mut buf = Cursor::new(&self.read_buffer[..]);
+                            match read_pdu(&mut buf, self.requestor_max_pdu_length, self.strict)
+                                .context(ReceiveResponseSnafu)?
+                            {
+                                Some(pdu) => {
+                                    self.read_buffer.advance(buf.position() as usize);
+                                    return Ok(pdu);
+                                }
+                                None => {
+                                    // Reset position
+                                    buf.set_position(0)
+                                }
+                            }
+                            let recv = self
+                                .socket
+                                .read_buf(&mut self.read_buffer)
+                                .await
+                                .context(ReadPduSnafu)
+                                .context(ReceiveSnafu)?;
+                            ensure!(recv > 0, ConnectionClosedSnafu);
+                        }
+                    })
+                    .await
+                }
 
-        /// Release implementation function,
-        /// which tries to send a release request and receive a release response.
-        /// This is in a separate private function because
-        /// terminating a connection should still close the connection
-        /// if the exchange fails.
-        async fn release_impl(&mut self) -> Result<()> {
-            let pdu = Pdu::ReleaseRQ;
-            self.send(&pdu).await?;
-            use tokio::io::AsyncReadExt;
-            let mut read_buffer = BytesMut::with_capacity(MAXIMUM_PDU_SIZE as usize);
-
-            let pdu = loop {
-                if let Ok(Some(pdu)) = read_pdu(&mut read_buffer, MAXIMUM_PDU_SIZE, self.strict) {
-                    break pdu;
+                /// Gracefully terminate the association by exchanging release messages
+                /// and then shutting down the TCP connection.
+                pub async fn release(mut self) -> Result<()> {
+                    timeout(self.write_timeout, async {
+                        let out = self.release_impl().await;
+                        let _ = self.socket.shutdown().await;
+                        out
+                    })
+                    .await
                 }
-                let recv = self
-                    .socket
-                    .read_buf(&mut read_buffer)
+
+                /// Send an abort message and shut down the TCP connection,
+                /// terminating the association.
+                pub async fn abort(mut self) -> Result<()> {
+                    timeout(self.write_timeout, async {
+                        let pdu = Pdu::AbortRQ {
+                            source: AbortRQSource::ServiceUser,
+                        };
+                        let out = self.send(&pdu).await;
+                        let _ = self.socket.shutdown().await;
+                        out
+                    })
                     .await
-                    .context(ReadPduSnafu)
-                    .context(ReceiveSnafu)?;
-                ensure!(recv > 0, ConnectionClosedSnafu);
-            };
-            match pdu {
-                Pdu::ReleaseRP => {}
-                pdu @ Pdu::AbortRQ { .. }
-                | pdu @ Pdu::AssociationAC { .. }
-                | pdu @ Pdu::AssociationRJ { .. }
-                | pdu @ Pdu::AssociationRQ { .. }
-                | pdu @ Pdu::PData { .. }
-                | pdu @ Pdu::ReleaseRQ { .. } => return UnexpectedResponseSnafu { pdu }.fail(),
-                pdu @ Pdu::Unknown { .. } => return UnknownResponseSnafu { pdu }.fail(),
+                }
+
+                /// Prepare a P-Data writer for sending
+                /// one or more data items.
+                ///
+                /// Returns a writer which automatically
+                /// splits the inner data into separate PDUs if necessary.
+                pub async fn send_pdata(
+                    &mut self,
+                    presentation_context_id: u8,
+                ) -> AsyncPDataWriter<&mut $stream> {
+                    AsyncPDataWriter::new(
+                        &mut self.socket,
+                        presentation_context_id,
+                        self.acceptor_max_pdu_length,
+                    )
+                }
+
+                /// Prepare a P-Data reader for receiving
+                /// one or more data item PDUs.
+                ///
+                /// Returns a reader which automatically
+                /// receives more data PDUs once the bytes collected are consumed.
+                #[cfg(feature = "async")]
+                pub fn receive_pdata(&mut self) -> PDataReader<&mut $stream> {
+                    PDataReader::new(
+                        &mut self.socket,
+                        self.requestor_max_pdu_length,
+                        &mut self.read_buffer,
+                    )
+                }
+
+                /// Send a full DIMSE message to the association acceptor,
+                /// split into as many P-Data PDUs as necessary.
+                #[cfg(feature = "dimse")]
+                pub async fn send_dimse(&mut self, msg: &DimseMessage) -> Result<()> {
+                    for pdu in msg
+                        .to_pdus(self.acceptor_max_pdu_length)
+                        .context(DimseSnafu)?
+                    {
+                        self.send(&pdu).await?;
+                    }
+                    Ok(())
+                }
+
+                /// Send a DIMSE command to the association acceptor,
+                /// followed by a data set read from the given source
+                /// as it is sent.
+                ///
+                /// See the blocking counterpart of this method
+                /// for more details.
+                #[cfg(feature = "dimse")]
+                pub async fn send_dimse_from<R>(
+                    &mut self,
+                    presentation_context_id: u8,
+                    command: &Command,
+                    mut data: R,
+                ) -> Result<()>
+                where
+                    R: AsyncRead + Unpin,
+                {
+                    for pdu in command_to_pdus(
+                        presentation_context_id,
+                        command,
+                        self.acceptor_max_pdu_length,
+                    )
+                    .context(DimseSnafu)?
+                    {
+                        self.send(&pdu).await?;
+                    }
+
+                    let mut buffer = vec![0; self.acceptor_max_pdu_length as usize];
+                    let mut writer = self.send_pdata(presentation_context_id).await;
+                    loop {
+                        let n = data.read(&mut buffer).await.context(ReadDataSetSnafu)?;
+                        if n == 0 {
+                            break;
+                        }
+                        writer
+                            .write_all(&buffer[..n])
+                            .await
+                            .context(WireSendSnafu)?;
+                    }
+                    writer.finish().await.context(WireSendSnafu)
+                }
+
+                /// Receive a full DIMSE message from the association acceptor.
+                ///
+                /// Fails with [`Error::UnexpectedResponse`]
+                /// if a PDU other than P-Data is received,
+                /// such as an abort request.
+                #[cfg(feature = "dimse")]
+                pub async fn receive_dimse(&mut self) -> Result<DimseMessage> {
+                    loop {
+                        if let Some(msg) = self.dimse.pop_message() {
+                            return Ok(msg);
+                        }
+                        match self.receive().await? {
+                            Pdu::PData { data } => {
+                                self.dimse.push_values(data).context(DimseSnafu)?
+                            }
+                            pdu => return UnexpectedResponseSnafu { pdu }.fail(),
+                        }
+                    }
+                }
+
+                /// Release implementation function,
+                /// which tries to send a release request and receive a release response.
+                /// This is in a separate private function because
+                /// terminating a connection should still close the connection
+                /// if the exchange fails.
+                async fn release_impl(&mut self) -> Result<()> {
+                    let pdu = Pdu::ReleaseRQ;
+                    self.send(&pdu).await?;
+                    use tokio::io::AsyncReadExt;
+                    let mut read_buffer = BytesMut::with_capacity(MAXIMUM_PDU_SIZE as usize);
+
+                    let pdu = loop {
+                        if let Ok(Some(pdu)) =
+                            read_pdu(&mut read_buffer, MAXIMUM_PDU_SIZE, self.strict)
+                        {
+                            break pdu;
+                        }
+                        let recv = self
+                            .socket
+                            .read_buf(&mut read_buffer)
+                            .await
+                            .context(ReadPduSnafu)
+                            .context(ReceiveSnafu)?;
+                        ensure!(recv > 0, ConnectionClosedSnafu);
+                    };
+                    match pdu {
+                        Pdu::ReleaseRP => {}
+                        pdu @ Pdu::AbortRQ { .. }
+                        | pdu @ Pdu::AssociationAC { .. }
+                        | pdu @ Pdu::AssociationRJ { .. }
+                        | pdu @ Pdu::AssociationRQ { .. }
+                        | pdu @ Pdu::PData { .. }
+                        | pdu @ Pdu::ReleaseRQ { .. } => {
+                            return UnexpectedResponseSnafu { pdu }.fail()
+                        }
+                        pdu @ Pdu::Unknown { .. } => return UnknownResponseSnafu { pdu }.fail(),
+                    }
+                    Ok(())
+                }
+                /// Obtain access to the inner TCP stream
+                /// connected to the association acceptor.
+                ///
+                /// This can be used to send the PDU in semantic fragments of the message,
+                /// thus using less memory.
+                ///
+                /// **Note:** reading and writing should be done with care
+                /// to avoid inconsistencies in the association state.
+                /// Do not call `send` and `receive` while not in a PDU boundary.
+                pub fn inner_stream(&mut self) -> &mut $stream {
+                    &mut self.socket
+                }
             }
-            Ok(())
-        }
-        /// Obtain access to the inner TCP stream
-        /// connected to the association acceptor.
-        ///
-        /// This can be used to send the PDU in semantic fragments of the message,
-        /// thus using less memory.
-        ///
-        /// **Note:** reading and writing should be done with care
-        /// to avoid inconsistencies in the association state.
-        /// Do not call `send` and `receive` while not in a PDU boundary.
-        pub fn inner_stream(&mut self) -> &mut tokio::net::TcpStream {
-            &mut self.socket
-        }
-    }
 
-    impl Release for ClientAssociation<tokio::net::TcpStream> {
-        fn release(&mut self) -> super::Result<()> {
-            tokio::task::block_in_place(move || {
-                tokio::runtime::Handle::current().block_on(async move { self.release_impl().await })
-            })
-        }
+            impl Release for ClientAssociation<$stream> {
+                fn release(&mut self) -> super::Result<()> {
+                    tokio::task::block_in_place(move || {
+                        tokio::runtime::Handle::current()
+                            .block_on(async move { self.release_impl().await })
+                    })
+                }
+            }
+        };
     }
-    /// Automatically release the association and shut down the connection.
-    impl CloseSocket for tokio::net::TcpStream {
-        fn close(&mut self) -> std::io::Result<()> {
-            tokio::task::block_in_place(move || {
-                tokio::runtime::Handle::current().block_on(async move { self.shutdown().await })
-            })
-        }
+
+    impl_async_client_association!(tokio::net::TcpStream);
+    impl_async_client_association!(tokio::io::DuplexStream);
+    #[cfg(unix)]
+    impl_async_client_association!(tokio::net::UnixStream);
+    #[cfg(feature = "async-tls")]
+    impl_async_client_association!(AsyncTlsClientStream);
+
+    /// Shut down the stream from within an asynchronous runtime.
+    macro_rules! impl_async_close_socket {
+        ($stream: ty) => {
+            impl CloseSocket for $stream {
+                fn close(&mut self) -> std::io::Result<()> {
+                    tokio::task::block_in_place(move || {
+                        tokio::runtime::Handle::current()
+                            .block_on(async move { self.shutdown().await })
+                    })
+                }
+            }
+        };
     }
+
+    impl_async_close_socket!(tokio::net::TcpStream);
+    impl_async_close_socket!(tokio::io::DuplexStream);
+    #[cfg(unix)]
+    impl_async_close_socket!(tokio::net::UnixStream);
 }
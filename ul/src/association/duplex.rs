@@ -0,0 +1,235 @@
+//! In-memory transport for associations.
+//!
+//! [`duplex`] creates a pair of connected [`DuplexStream`]s,
+//! which work as a blocking [`Transport`](super::Transport)
+//! without opening any network ports.
+//! This is mostly useful for testing
+//! full exchanges between association requesters and acceptors
+//! running in different threads.
+//!
+//! # Example
+//!
+//! ```
+//! # use dicom_ul::association::{duplex::duplex, ClientAssociationOptions, ServerAssociationOptions};
+//! # use dicom_ul::Pdu;
+//! # type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+//! # fn main() -> Result<()> {
+//! let (scu_stream, scp_stream) = duplex(64 * 1024);
+//!
+//! let scp = std::thread::spawn(move || -> Result<()> {
+//!     let mut association = ServerAssociationOptions::new()
+//!         .with_abstract_syntax("1.2.840.10008.1.1")
+//!         .establish_over(scp_stream)?;
+//!     // accept the release request
+//!     assert_eq!(association.receive()?, Pdu::ReleaseRQ);
+//!     association.send(&Pdu::ReleaseRP)?;
+//!     Ok(())
+//! });
+//!
+//! let association = ClientAssociationOptions::new()
+//!     .with_abstract_syntax("1.2.840.10008.1.1")
+//!     .establish_over(scu_stream)?;
+//! association.release()?;
+//! scp.join().unwrap()?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::VecDeque,
+    io::{ErrorKind, Read, Write},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+};
+
+use super::{client::CloseSocket, Transport};
+
+/// Create a pair of connected in-memory streams.
+///
+/// Bytes written to one end can be read from the other.
+/// Each direction buffers up to `max_buf_size` bytes,
+/// after which writers block until the peer reads.
+///
+/// # Panics
+///
+/// Panics if `max_buf_size` is zero.
+pub fn duplex(max_buf_size: usize) -> (DuplexStream, DuplexStream) {
+    assert!(max_buf_size > 0, "duplex buffer size must not be zero");
+    let a_to_b = Arc::new(Pipe::new(max_buf_size));
+    let b_to_a = Arc::new(Pipe::new(max_buf_size));
+    (
+        DuplexStream {
+            read: Arc::clone(&b_to_a),
+            write: Arc::clone(&a_to_b),
+        },
+        DuplexStream {
+            read: a_to_b,
+            write: b_to_a,
+        },
+    )
+}
+
+/// One end of an in-memory bidirectional pipe,
+/// created with [`duplex`].
+///
+/// Dropping or closing one end
+/// signals the end of the stream to the other end.
+#[derive(Debug)]
+pub struct DuplexStream {
+    read: Arc<Pipe>,
+    write: Arc<Pipe>,
+}
+
+/// A bounded byte buffer in one direction.
+#[derive(Debug)]
+struct Pipe {
+    state: Mutex<PipeState>,
+    changed: Condvar,
+    max_buf_size: usize,
+}
+
+#[derive(Debug, Default)]
+struct PipeState {
+    buffer: VecDeque<u8>,
+    /// whether either end of this direction was closed
+    closed: bool,
+}
+
+impl Pipe {
+    fn new(max_buf_size: usize) -> Self {
+        Pipe {
+            state: Mutex::default(),
+            changed: Condvar::new(),
+            max_buf_size,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PipeState> {
+        // the state is always consistent, even if a holder panicked
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn close(&self) {
+        self.lock().closed = true;
+        self.changed.notify_all();
+    }
+}
+
+impl Read for DuplexStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut state = self.read.lock();
+        while state.buffer.is_empty() && !state.closed {
+            state = self
+                .read
+                .changed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        // an empty buffer here means end of stream
+        let n = state.buffer.len().min(buf.len());
+        for (dst, src) in buf.iter_mut().zip(state.buffer.drain(..n)) {
+            *dst = src;
+        }
+        drop(state);
+        self.read.changed.notify_all();
+        Ok(n)
+    }
+}
+
+impl Write for DuplexStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let max_buf_size = self.write.max_buf_size;
+        let mut state = self.write.lock();
+        while state.buffer.len() >= max_buf_size && !state.closed {
+            state = self
+                .write
+                .changed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        if state.closed {
+            return Err(ErrorKind::BrokenPipe.into());
+        }
+        let n = (max_buf_size - state.buffer.len()).min(buf.len());
+        state.buffer.extend(&buf[..n]);
+        drop(state);
+        self.write.changed.notify_all();
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl CloseSocket for DuplexStream {
+    fn close(&mut self) -> std::io::Result<()> {
+        self.read.close();
+        self.write.close();
+        Ok(())
+    }
+}
+
+impl Transport for DuplexStream {
+    fn has_pending_input(&mut self) -> std::io::Result<bool> {
+        let state = self.read.lock();
+        Ok(!state.buffer.is_empty() || state.closed)
+    }
+}
+
+impl Drop for DuplexStream {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::duplex;
+    use std::io::{ErrorKind, Read, Write};
+
+    #[test]
+    fn bytes_flow_both_ways() {
+        let (mut a, mut b) = duplex(16);
+        a.write_all(b"ping").unwrap();
+        let mut buf = [0; 4];
+        b.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+
+        b.write_all(b"pong").unwrap();
+        a.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"pong");
+    }
+
+    #[test]
+    fn writer_blocks_until_reader_catches_up() {
+        let (mut a, mut b) = duplex(4);
+        let data: Vec<u8> = (0..=255).collect();
+        let expected = data.clone();
+        let writer = std::thread::spawn(move || a.write_all(&data));
+
+        let mut received = Vec::new();
+        b.read_to_end(&mut received).unwrap();
+        writer.join().unwrap().unwrap();
+        assert_eq!(received, expected);
+    }
+
+    #[test]
+    fn closing_one_end_ends_the_stream() {
+        let (mut a, mut b) = duplex(16);
+        a.write_all(b"bye").unwrap();
+        drop(a);
+
+        let mut received = Vec::new();
+        b.read_to_end(&mut received).unwrap();
+        assert_eq!(received, b"bye");
+        assert_eq!(
+            b.write(b"anyone?").unwrap_err().kind(),
+            ErrorKind::BrokenPipe
+        );
+    }
+}
@@ -14,9 +14,26 @@
 //! a newly created [TCP stream][1] can be passed to
 //! a previously prepared [`ServerAssociationOptions`].
 //!
+//! Once established,
+//! associations can work over any [`Transport`],
+//! such as a plain TCP stream
+//! or a TLS stream (with the `sync-tls` feature).
+//! Associations can also be negotiated over an existing stream
+//! via `establish_over`,
+//! which enables Unix domain sockets
+//! and the in-memory pipes of the [`duplex`] module.
+//! The [`pool`] module keeps established associations open
+//! for reuse by later operations with the same peer.
+//! The [`replay`] module records the PDUs exchanged through an association
+//! and plays them back as a mock peer in tests.
 //!
 //! [1]: std::net::TcpStream
 pub mod client;
+pub mod duplex;
+#[cfg(feature = "dimse")]
+pub mod pipeline;
+pub mod pool;
+pub mod replay;
 pub mod server;
 
 mod uid;
@@ -28,3 +45,45 @@ pub use client::{ClientAssociation, ClientAssociationOptions};
 pub use pdata::non_blocking::AsyncPDataWriter;
 pub use pdata::{PDataReader, PDataWriter};
 pub use server::{ServerAssociation, ServerAssociationOptions};
+pub use uid::trim_padding;
+
+use std::io::{Read, Write};
+
+use client::CloseSocket;
+
+/// A blocking byte stream through which an association is maintained.
+///
+/// This is implemented for [`TcpStream`](std::net::TcpStream),
+/// Unix domain sockets,
+/// in-memory [`DuplexStream`](duplex::DuplexStream)s,
+/// and for TLS streams on top of other transports
+/// when the `sync-tls` feature is enabled.
+pub trait Transport: Read + Write + CloseSocket {
+    /// Check whether incoming bytes can be read from the stream
+    /// without blocking.
+    ///
+    /// This is only a hint:
+    /// transports which cannot tell without consuming any data
+    /// report that nothing is pending.
+    fn has_pending_input(&mut self) -> std::io::Result<bool> {
+        Ok(false)
+    }
+}
+
+impl Transport for std::net::TcpStream {
+    fn has_pending_input(&mut self) -> std::io::Result<bool> {
+        self.set_nonblocking(true)?;
+        let peeked = self.peek(&mut [0]);
+        self.set_nonblocking(false)?;
+        match peeked {
+            // a closed connection is also reported,
+            // so that the next read fails accordingly
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Transport for std::os::unix::net::UnixStream {}
@@ -0,0 +1,238 @@
+//! Pipelining of DIMSE requests.
+//!
+//! When an asynchronous operations window is negotiated
+//! (see [`ClientAssociationOptions::async_operations_window`]),
+//! the association requester may send several requests
+//! before receiving their responses.
+//! A [`Pipeline`] keeps track of the outstanding requests by message ID,
+//! waits for responses only when the window is full,
+//! and matches each response to the request it refers to.
+//!
+//! This is best suited for operations with a single response,
+//! such as C-STORE or C-ECHO.
+//! Pending responses are skipped,
+//! so that only the final response of each operation is yielded.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_ul::association::{pipeline::Pipeline, ClientAssociationOptions};
+//! # use dicom_ul::dimse::{composite::CEchoRq, DimseMessage};
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut association = ClientAssociationOptions::new()
+//!     .with_abstract_syntax("1.2.840.10008.1.1")
+//!     .async_operations_window(8, 1)
+//!     .establish_with("ECHO-SCP@127.0.0.1:104")?;
+//! let pc_id = association.presentation_contexts()[0].id;
+//!
+//! let mut pipeline = Pipeline::new(&mut association);
+//! for message_id in 1..=32 {
+//!     let msg = DimseMessage::new(pc_id, CEchoRq::new(message_id));
+//!     if let Some((id, rsp)) = pipeline.send(&msg, message_id)? {
+//!         println!("#{}: {:?}", id, rsp.command.status());
+//!     }
+//! }
+//! for (id, rsp) in pipeline.finish()? {
+//!     println!("#{}: {:?}", id, rsp.command.status());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`ClientAssociationOptions::async_operations_window`]: super::ClientAssociationOptions::async_operations_window
+use std::collections::HashMap;
+use std::io::Read;
+
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+
+use super::{client, ClientAssociation, Transport};
+use crate::dimse::{Command, DimseMessage, StatusType};
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// failed to send request
+    Send {
+        #[snafu(backtrace)]
+        source: client::Error,
+    },
+
+    /// failed to receive response
+    Receive {
+        #[snafu(backtrace)]
+        source: client::Error,
+    },
+
+    /// request command has no message ID
+    MissingMessageId { backtrace: Backtrace },
+
+    #[snafu(display("message ID {} is already outstanding", message_id))]
+    DuplicateMessageId {
+        message_id: u16,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("response to unknown message ID {:?}", message_id))]
+    UnknownResponse {
+        message_id: Option<u16>,
+        response: Box<DimseMessage>,
+        backtrace: Backtrace,
+    },
+
+    /// no requests are outstanding
+    NothingOutstanding { backtrace: Backtrace },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A DIMSE request pipeline over an established association,
+/// keeping up to the negotiated number of requests outstanding.
+///
+/// Each request is sent alongside a value of type `T`
+/// identifying it to the caller,
+/// which is yielded back with the matching response.
+/// See the [module-level documentation](self) for an example.
+#[derive(Debug)]
+pub struct Pipeline<'a, S, T>
+where
+    S: Transport,
+{
+    association: &'a mut ClientAssociation<S>,
+    window: usize,
+    outstanding: HashMap<u16, T>,
+}
+
+impl<'a, S, T> Pipeline<'a, S, T>
+where
+    S: Transport,
+{
+    /// Create a pipeline over the given association,
+    /// with a window of as many requests
+    /// as accorded by the acceptor.
+    pub fn new(association: &'a mut ClientAssociation<S>) -> Self {
+        let window = match association.max_operations_invoked() {
+            0 => usize::MAX,
+            n => usize::from(n),
+        };
+        Pipeline {
+            association,
+            window,
+            outstanding: HashMap::new(),
+        }
+    }
+
+    /// The maximum number of requests which may be outstanding at once.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// The number of requests still waiting for a response.
+    pub fn outstanding(&self) -> usize {
+        self.outstanding.len()
+    }
+
+    /// Whether the window is full,
+    /// so that a response must be received
+    /// before sending another request.
+    pub fn is_full(&self) -> bool {
+        self.outstanding.len() >= self.window
+    }
+
+    /// Send a request through the pipeline.
+    ///
+    /// If the window is full,
+    /// this first waits for the response to an earlier request,
+    /// which is then returned alongside its identifying value.
+    pub fn send(&mut self, msg: &DimseMessage, value: T) -> Result<Option<(T, DimseMessage)>> {
+        let message_id = msg.command.message_id().context(MissingMessageIdSnafu)?;
+        ensure!(
+            !self.outstanding.contains_key(&message_id),
+            DuplicateMessageIdSnafu { message_id }
+        );
+        let completed = if self.is_full() {
+            Some(self.receive()?)
+        } else {
+            None
+        };
+
+        self.association.send_dimse(msg).context(SendSnafu)?;
+        self.outstanding.insert(message_id, value);
+        Ok(completed)
+    }
+
+    /// Send a request through the pipeline,
+    /// with its data set read from the given source as it is sent
+    /// (see [`ClientAssociation::send_dimse_from`]).
+    ///
+    /// If the window is full,
+    /// this first waits for the response to an earlier request,
+    /// which is then returned alongside its identifying value.
+    pub fn send_from<R>(
+        &mut self,
+        presentation_context_id: u8,
+        command: &Command,
+        data: R,
+        value: T,
+    ) -> Result<Option<(T, DimseMessage)>>
+    where
+        R: Read,
+    {
+        let message_id = command.message_id().context(MissingMessageIdSnafu)?;
+        ensure!(
+            !self.outstanding.contains_key(&message_id),
+            DuplicateMessageIdSnafu { message_id }
+        );
+        let completed = if self.is_full() {
+            Some(self.receive()?)
+        } else {
+            None
+        };
+
+        self.association
+            .send_dimse_from(presentation_context_id, command, data)
+            .context(SendSnafu)?;
+        self.outstanding.insert(message_id, value);
+        Ok(completed)
+    }
+
+    /// Wait for the next final response to an outstanding request,
+    /// returning it alongside the value given when sending the request.
+    pub fn receive(&mut self) -> Result<(T, DimseMessage)> {
+        ensure!(!self.outstanding.is_empty(), NothingOutstandingSnafu);
+        loop {
+            let rsp = self.association.receive_dimse().context(ReceiveSnafu)?;
+            let message_id = rsp.command.message_id_being_responded_to();
+            match message_id {
+                Some(id) if self.outstanding.contains_key(&id) => {
+                    if rsp.command.status().map(|status| status.status_type())
+                        == Some(StatusType::Pending)
+                    {
+                        tracing::debug!("Skipping pending response to message {}", id);
+                        continue;
+                    }
+                    let value = self
+                        .outstanding
+                        .remove(&id)
+                        .expect("message ID should be outstanding");
+                    return Ok((value, rsp));
+                }
+                _ => {
+                    return UnknownResponseSnafu {
+                        message_id,
+                        response: Box::new(rsp),
+                    }
+                    .fail()
+                }
+            }
+        }
+    }
+
+    /// Wait for the responses to all outstanding requests.
+    pub fn finish(mut self) -> Result<Vec<(T, DimseMessage)>> {
+        let mut responses = Vec::with_capacity(self.outstanding.len());
+        while !self.outstanding.is_empty() {
+            responses.push(self.receive()?);
+        }
+        Ok(responses)
+    }
+}
@@ -0,0 +1,471 @@
+//! Pooling of client associations.
+//!
+//! Negotiating an association takes a few round trips,
+//! which can dominate the time spent sending many small objects.
+//! An [`AssociationPool`] keeps associations open after use,
+//! so that later operations with the same peer can reuse them.
+//!
+//! Associations are checked out of the pool with [`AssociationPool::get`],
+//! stating the peer and the presentation contexts needed.
+//! An idle association with that peer is reused
+//! if it was negotiated with all of those presentation contexts.
+//! Otherwise, an idle association with that peer is released
+//! and renegotiated with the presentation contexts needed
+//! in addition to the ones it had,
+//! or a new association is established if none is idle.
+//! The [`PooledAssociation`] obtained returns to the pool when dropped.
+//! Associations left idle for longer than the idle timeout
+//! are released on the next use of the pool,
+//! or with [`AssociationPool::release_idle`].
+//!
+//! The peer may close an idle association at any time,
+//! in which case the next operation through it fails.
+//! Associations which can no longer be used
+//! should be [discarded](PooledAssociation::discard)
+//! rather than returned to the pool.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_ul::association::{pool::AssociationPool, ClientAssociationOptions};
+//! # #[cfg(feature = "dimse")]
+//! # use dicom_ul::dimse::{composite::CEchoRq, DimseMessage};
+//! # #[cfg(feature = "dimse")]
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let pool = AssociationPool::new(ClientAssociationOptions::new().calling_ae_title("GATEWAY"));
+//! let verification = [("1.2.840.10008.1.1", vec!["1.2.840.10008.1.2"])];
+//! for message_id in 1..=16 {
+//!     // only the first iteration negotiates an association
+//!     let mut association = pool.get("ARCHIVE@10.0.0.5:104", verification.iter().cloned())?;
+//!     let pc_id = association.presentation_contexts()[0].id;
+//!     association.send_dimse(&DimseMessage::new(pc_id, CEchoRq::new(message_id)))?;
+//!     let rsp = association.receive_dimse()?;
+//!     println!("#{}: {:?}", message_id, rsp.command.status());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::borrow::Cow;
+use std::fmt;
+use std::net::TcpStream;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use super::client::{ClientAssociation, ClientAssociationOptions, Result};
+use super::uid::trim_uid;
+use super::Transport;
+
+/// There can be no more than 128 presentation contexts in an association.
+const MAX_PRESENTATION_CONTEXTS: usize = 128;
+
+/// A list of presentation contexts,
+/// as pairs of abstract syntax and transfer syntaxes.
+type PresentationContexts = Vec<(String, Vec<String>)>;
+
+/// The means of establishing a new association with a peer.
+type Connect<S> =
+    dyn Fn(ClientAssociationOptions<'static>, &str) -> Result<ClientAssociation<S>> + Send + Sync;
+
+/// An association kept by the pool.
+struct Pooled<S>
+where
+    S: Transport,
+{
+    /// the address of the peer, as given when checking out the association
+    peer: String,
+    /// the presentation contexts proposed when negotiating the association
+    presentation_contexts: PresentationContexts,
+    association: ClientAssociation<S>,
+    /// when the association was returned to the pool
+    since: Instant,
+}
+
+impl<S> Pooled<S>
+where
+    S: Transport,
+{
+    /// Whether the association acceptor accepted
+    /// each of the given presentation contexts,
+    /// in one of their transfer syntaxes.
+    fn covers(&self, presentation_contexts: &[(String, Vec<String>)]) -> bool {
+        let accepted = self.association.presentation_contexts();
+        presentation_contexts
+            .iter()
+            .all(|(abstract_syntax, transfer_syntaxes)| {
+                accepted.iter().any(|pc| {
+                    self.association.abstract_syntax(pc.id) == Some(abstract_syntax.as_str())
+                        && transfer_syntaxes.iter().any(|ts| {
+                            trim_uid(Cow::from(pc.transfer_syntax.as_str())) == ts.as_str()
+                        })
+                })
+            })
+    }
+}
+
+/// Whether one of the proposed presentation contexts
+/// has the abstract syntax and all transfer syntaxes of the given one.
+fn covers(
+    proposed: &[(String, Vec<String>)],
+    (abstract_syntax, transfer_syntaxes): &(String, Vec<String>),
+) -> bool {
+    proposed
+        .iter()
+        .any(|(a, t)| a == abstract_syntax && transfer_syntaxes.iter().all(|ts| t.contains(ts)))
+}
+
+/// A pool of associations with any number of peers,
+/// kept open for reuse once established.
+///
+/// The pool is shared by reference,
+/// so that associations may be checked out from several threads.
+/// See the [module-level documentation](self) for more details.
+pub struct AssociationPool<S = TcpStream>
+where
+    S: Transport,
+{
+    /// the options of every association established,
+    /// to which the presentation contexts needed are added
+    options: ClientAssociationOptions<'static>,
+    /// the presentation contexts already in the options
+    base_presentation_contexts: PresentationContexts,
+    connect: Box<Connect<S>>,
+    max_idle: usize,
+    idle_timeout: Duration,
+    idle: Mutex<Vec<Pooled<S>>>,
+}
+
+impl<S> fmt::Debug for AssociationPool<S>
+where
+    S: Transport,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssociationPool")
+            .field("options", &self.options)
+            .field("max_idle", &self.max_idle)
+            .field("idle_timeout", &self.idle_timeout)
+            .field("idle", &self.idle())
+            .finish()
+    }
+}
+
+impl AssociationPool<TcpStream> {
+    /// Create a pool of associations over TCP,
+    /// each established with the given options
+    /// and the presentation contexts needed at the time.
+    ///
+    /// Any presentation contexts in the options
+    /// are proposed in every association.
+    pub fn new(options: ClientAssociationOptions<'static>) -> Self {
+        Self::with_connector(options, |options, peer| options.establish_with(peer))
+    }
+}
+
+impl<S> AssociationPool<S>
+where
+    S: Transport,
+{
+    /// Create a pool of associations
+    /// established by the given function,
+    /// which receives the options of the association
+    /// and the address of the peer.
+    ///
+    /// This enables pools of associations secured with TLS
+    /// or over other transports.
+    pub fn with_connector<F>(options: ClientAssociationOptions<'static>, connect: F) -> Self
+    where
+        F: Fn(ClientAssociationOptions<'static>, &str) -> Result<ClientAssociation<S>>,
+        F: Send + Sync + 'static,
+    {
+        AssociationPool {
+            base_presentation_contexts: options.proposed_presentation_contexts(),
+            options,
+            connect: Box::new(connect),
+            max_idle: 4,
+            idle_timeout: Duration::from_secs(60),
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Override the maximum number of idle associations
+    /// kept open with each peer.
+    /// The default is 4.
+    ///
+    /// With 0, associations are released as soon as they are returned.
+    pub fn max_idle(mut self, max_idle: usize) -> Self {
+        self.max_idle = max_idle;
+        self
+    }
+
+    /// Override how long an association may be left idle
+    /// before it is released.
+    /// The default is 60 seconds.
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Check out an association with the given peer
+    /// negotiated with the given presentation contexts,
+    /// as pairs of abstract syntax and transfer syntaxes.
+    ///
+    /// An idle association is reused
+    /// if all of these presentation contexts were proposed in it,
+    /// regardless of whether they were accepted.
+    /// Otherwise,
+    /// an idle association with the peer is renegotiated
+    /// with these presentation contexts in addition to its own,
+    /// or a new association is established.
+    pub fn get<I, A, T>(
+        &self,
+        peer: &str,
+        presentation_contexts: I,
+    ) -> Result<PooledAssociation<'_, S>>
+    where
+        I: IntoIterator<Item = (A, Vec<T>)>,
+        A: Into<Cow<'static, str>>,
+        T: Into<Cow<'static, str>>,
+    {
+        let needed: PresentationContexts = presentation_contexts
+            .into_iter()
+            .map(|(abstract_syntax, transfer_syntaxes)| {
+                (
+                    trim_uid(abstract_syntax.into()).into_owned(),
+                    transfer_syntaxes
+                        .into_iter()
+                        .map(|ts| trim_uid(ts.into()).into_owned())
+                        .collect(),
+                )
+            })
+            .collect();
+
+        let (reusable, renegotiated, expired) = {
+            let mut idle = self.lock_idle();
+            let expired = self.take_expired(&mut idle);
+            // prefer the most recently used associations
+            let reusable = idle
+                .iter()
+                .rposition(|pooled| pooled.peer == peer && pooled.covers(&needed))
+                .map(|i| idle.remove(i));
+            let renegotiated = if reusable.is_none() {
+                idle.iter()
+                    .rposition(|pooled| pooled.peer == peer)
+                    .map(|i| idle.remove(i))
+            } else {
+                None
+            };
+            (reusable, renegotiated, expired)
+        };
+        release_all(expired);
+
+        if let Some(pooled) = reusable {
+            tracing::debug!("Reusing association with {}", peer);
+            return Ok(PooledAssociation {
+                pool: self,
+                pooled: Some(pooled),
+            });
+        }
+
+        // propose the presentation contexts needed first,
+        // followed by those of the association being renegotiated
+        let mut proposed: PresentationContexts = Vec::new();
+        let previous = renegotiated
+            .as_ref()
+            .map(|pooled| pooled.presentation_contexts.as_slice())
+            .unwrap_or_default();
+        for pc in needed.iter().chain(previous) {
+            if proposed.len() + self.base_presentation_contexts.len() >= MAX_PRESENTATION_CONTEXTS {
+                break;
+            }
+            if !covers(&self.base_presentation_contexts, pc) && !covers(&proposed, pc) {
+                proposed.push(pc.clone());
+            }
+        }
+        if let Some(pooled) = renegotiated {
+            tracing::debug!("Renegotiating association with {}", peer);
+            let _ = pooled.association.release();
+        }
+
+        let mut options = self.options.clone();
+        for (abstract_syntax, transfer_syntaxes) in &proposed {
+            options = options.with_presentation_context(
+                Cow::from(abstract_syntax.clone()),
+                transfer_syntaxes.iter().cloned().map(Cow::from).collect(),
+            );
+        }
+        let association = (self.connect)(options, peer)?;
+
+        let mut presentation_contexts = self.base_presentation_contexts.clone();
+        presentation_contexts.extend(proposed);
+        Ok(PooledAssociation {
+            pool: self,
+            pooled: Some(Pooled {
+                peer: peer.to_string(),
+                presentation_contexts,
+                association,
+                since: Instant::now(),
+            }),
+        })
+    }
+
+    /// The number of idle associations in the pool.
+    pub fn idle(&self) -> usize {
+        self.lock_idle().len()
+    }
+
+    /// Release the associations
+    /// which were left idle for longer than the idle timeout.
+    pub fn release_idle(&self) {
+        let expired = self.take_expired(&mut self.lock_idle());
+        release_all(expired);
+    }
+
+    /// Release all idle associations.
+    pub fn clear(&self) {
+        let idle = std::mem::take(&mut *self.lock_idle());
+        release_all(idle);
+    }
+
+    /// Return an association to the pool,
+    /// releasing the oldest idle association with the same peer
+    /// if there are too many.
+    fn put_back(&self, mut pooled: Pooled<S>) {
+        pooled.since = Instant::now();
+        let excess = {
+            let mut idle = self.lock_idle();
+            let mut excess = self.take_expired(&mut idle);
+            let peer = pooled.peer.clone();
+            idle.push(pooled);
+            if idle.iter().filter(|pooled| pooled.peer == peer).count() > self.max_idle {
+                let oldest = idle
+                    .iter()
+                    .position(|pooled| pooled.peer == peer)
+                    .expect("there should be an idle association with the peer");
+                excess.push(idle.remove(oldest));
+            }
+            excess
+        };
+        release_all(excess);
+    }
+
+    fn take_expired(&self, idle: &mut Vec<Pooled<S>>) -> Vec<Pooled<S>> {
+        let (expired, kept) = std::mem::take(idle)
+            .into_iter()
+            .partition(|pooled| pooled.since.elapsed() >= self.idle_timeout);
+        *idle = kept;
+        expired
+    }
+
+    fn lock_idle(&self) -> MutexGuard<'_, Vec<Pooled<S>>> {
+        // the list of idle associations remains consistent
+        // even if another thread panicked
+        self.idle
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Gracefully release the given associations,
+/// ignoring any failures.
+fn release_all<S>(pooled: Vec<Pooled<S>>)
+where
+    S: Transport,
+{
+    for pooled in pooled {
+        tracing::debug!("Releasing idle association with {}", pooled.peer);
+        let _ = pooled.association.release();
+    }
+}
+
+/// An association checked out of an [`AssociationPool`],
+/// which is returned to the pool when dropped.
+///
+/// This dereferences to the underlying [`ClientAssociation`].
+pub struct PooledAssociation<'a, S = TcpStream>
+where
+    S: Transport,
+{
+    pool: &'a AssociationPool<S>,
+    pooled: Option<Pooled<S>>,
+}
+
+impl<S> fmt::Debug for PooledAssociation<'_, S>
+where
+    S: Transport,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PooledAssociation")
+            .field("peer", &self.peer())
+            .field("presentation_contexts", &self.presentation_contexts())
+            .finish()
+    }
+}
+
+impl<S> PooledAssociation<'_, S>
+where
+    S: Transport,
+{
+    /// The address of the peer,
+    /// as given when checking out the association.
+    pub fn peer(&self) -> &str {
+        &self.pooled().peer
+    }
+
+    /// Abort the association instead of returning it to the pool,
+    /// such as after a failure which left it unusable.
+    pub fn discard(mut self) {
+        if let Some(pooled) = self.pooled.take() {
+            let _ = pooled.association.abort();
+        }
+    }
+
+    /// Gracefully release the association
+    /// instead of returning it to the pool.
+    pub fn release(mut self) -> Result<()> {
+        match self.pooled.take() {
+            Some(pooled) => pooled.association.release(),
+            None => Ok(()),
+        }
+    }
+
+    fn pooled(&self) -> &Pooled<S> {
+        self.pooled
+            .as_ref()
+            .expect("pooled association should be present until dropped")
+    }
+}
+
+impl<S> Deref for PooledAssociation<'_, S>
+where
+    S: Transport,
+{
+    type Target = ClientAssociation<S>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pooled().association
+    }
+}
+
+impl<S> DerefMut for PooledAssociation<'_, S>
+where
+    S: Transport,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self
+            .pooled
+            .as_mut()
+            .expect("pooled association should be present until dropped")
+            .association
+    }
+}
+
+impl<S> Drop for PooledAssociation<'_, S>
+where
+    S: Transport,
+{
+    fn drop(&mut self) {
+        if let Some(pooled) = self.pooled.take() {
+            self.pool.put_back(pooled);
+        }
+    }
+}
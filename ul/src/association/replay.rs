@@ -0,0 +1,684 @@
+//! Recording and replaying of associations for testing.
+//!
+//! A [`Recorder`] wraps the [`Transport`] of one end of an association
+//! and keeps every PDU sent and received through it in a [`Recording`].
+//! Recordings can be saved to a file and loaded back,
+//! so that they can be kept alongside the tests which use them.
+//!
+//! A [`Replay`] then plays one side of a recording as a mock peer:
+//! the PDUs recorded from that side are sent in order,
+//! and each PDU received from the other side
+//! is checked against the one recorded in its place.
+//! This enables deterministic tests of service class users and providers
+//! without running a real DICOM node.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_ul::association::{duplex::duplex, ClientAssociationOptions};
+//! # use dicom_ul::association::replay::{Recording, Replay, Side};
+//! # type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+//! # fn main() -> Result<()> {
+//! // a recording of an association between an SCU and an SCP
+//! let recording = Recording::read_from(std::fs::File::open("tests/echo.pdus")?)?;
+//!
+//! // play the SCP side against the SCU under test
+//! let (scu_stream, scp_stream) = duplex(64 * 1024);
+//! let scp = Replay::new(recording, Side::Acceptor).spawn(scp_stream);
+//!
+//! let association = ClientAssociationOptions::new()
+//!     .with_abstract_syntax("1.2.840.10008.1.1")
+//!     .establish_over(scu_stream)?;
+//! association.release()?;
+//!
+//! // fails if the SCU did not send the same PDUs as recorded
+//! scp.join().unwrap()?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    convert::TryFrom,
+    fmt,
+    io::{Cursor, ErrorKind, Read, Write},
+    sync::{Arc, Mutex, MutexGuard},
+    thread::JoinHandle,
+};
+
+use snafu::{ensure, Backtrace, ResultExt, Snafu};
+use tracing::warn;
+
+use super::{client::CloseSocket, Transport};
+use crate::pdu::{self, read_pdu, write_pdu, Pdu, UserVariableItem, MAXIMUM_PDU_SIZE};
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// failed to read recording
+    ReadRecording {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    /// failed to write recording
+    WriteRecording {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("invalid side {:02X}H in recording", value))]
+    InvalidSide { value: u8, backtrace: Backtrace },
+
+    /// failed to decode PDU
+    DecodePdu {
+        #[snafu(backtrace)]
+        source: pdu::ReadError,
+    },
+
+    /// failed to encode PDU
+    EncodePdu {
+        #[snafu(backtrace)]
+        source: pdu::WriteError,
+    },
+
+    #[snafu(display("recording ended in the middle of a PDU"))]
+    TruncatedRecording { backtrace: Backtrace },
+
+    /// failed to send PDU to the peer
+    Send {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    /// failed to receive PDU from the peer
+    Receive {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "connection closed before PDU #{} was received (expected {})",
+        index,
+        expected.short_description()
+    ))]
+    MissingPdu {
+        index: usize,
+        expected: Box<Pdu>,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "PDU #{} does not match the recording: expected {:?}, got {:?}",
+        index,
+        expected,
+        actual
+    ))]
+    UnexpectedPdu {
+        index: usize,
+        expected: Box<Pdu>,
+        actual: Box<Pdu>,
+        backtrace: Backtrace,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// One of the two ends of an association.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Side {
+    /// The association requestor, usually the service class user
+    Requestor,
+    /// The association acceptor, usually the service class provider
+    Acceptor,
+}
+
+impl Side {
+    /// The end on the other side of the association.
+    pub fn peer(self) -> Self {
+        match self {
+            Side::Requestor => Side::Acceptor,
+            Side::Acceptor => Side::Requestor,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Side::Requestor => 0,
+            Side::Acceptor => 1,
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Side::Requestor => "requestor",
+            Side::Acceptor => "acceptor",
+        })
+    }
+}
+
+impl From<Side> for u8 {
+    fn from(side: Side) -> u8 {
+        match side {
+            Side::Requestor => 0x01,
+            Side::Acceptor => 0x02,
+        }
+    }
+}
+
+impl TryFrom<u8> for Side {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0x01 => Ok(Side::Requestor),
+            0x02 => Ok(Side::Acceptor),
+            _ => InvalidSideSnafu { value }.fail(),
+        }
+    }
+}
+
+/// A PDU in a recording, along with the side which sent it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedPdu {
+    /// the side of the association which sent the PDU
+    pub from: Side,
+    /// the PDU
+    pub pdu: Pdu,
+}
+
+/// The sequence of PDUs exchanged through an association.
+///
+/// Recordings are usually obtained with a [`Recorder`],
+/// but can also be written by hand
+/// (see [`with_pdu`](Recording::with_pdu)).
+///
+/// In the file representation of a recording,
+/// each PDU is preceded by a single byte
+/// identifying the side which sent it
+/// (01H for the requestor, 02H for the acceptor),
+/// and is otherwise encoded as in the upper layer protocol.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Recording {
+    pdus: Vec<RecordedPdu>,
+}
+
+impl Recording {
+    /// Create an empty recording.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a PDU sent by the given side to the end of the recording.
+    pub fn push(&mut self, from: Side, pdu: Pdu) {
+        self.pdus.push(RecordedPdu { from, pdu });
+    }
+
+    /// Add a PDU sent by the given side to the end of the recording,
+    /// returning the recording.
+    pub fn with_pdu(mut self, from: Side, pdu: impl Into<Pdu>) -> Self {
+        self.push(from, pdu.into());
+        self
+    }
+
+    /// Retrieve all PDUs in the recording, in order.
+    pub fn pdus(&self) -> &[RecordedPdu] {
+        &self.pdus
+    }
+
+    /// Iterate over the PDUs sent by the given side, in order.
+    pub fn sent_by(&self, side: Side) -> impl Iterator<Item = &Pdu> {
+        self.pdus
+            .iter()
+            .filter(move |recorded| recorded.from == side)
+            .map(|recorded| &recorded.pdu)
+    }
+
+    /// The number of PDUs in the recording.
+    pub fn len(&self) -> usize {
+        self.pdus.len()
+    }
+
+    /// Whether the recording has no PDUs.
+    pub fn is_empty(&self) -> bool {
+        self.pdus.is_empty()
+    }
+
+    /// Read a recording from its file representation.
+    pub fn read_from(mut reader: impl Read) -> Result<Self> {
+        let mut recording = Recording::new();
+        loop {
+            let mut side = [0; 1];
+            match reader.read_exact(&mut side) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e).context(ReadRecordingSnafu),
+            }
+            let from = Side::try_from(side[0])?;
+
+            let mut data = vec![0; 6];
+            read_exact_or_truncated(&mut reader, &mut data)?;
+            let length = u32::from_be_bytes([data[2], data[3], data[4], data[5]]) as usize;
+            data.resize(6 + length, 0);
+            read_exact_or_truncated(&mut reader, &mut data[6..])?;
+
+            let pdu = read_pdu(&data[..], MAXIMUM_PDU_SIZE, false)
+                .context(DecodePduSnafu)?
+                .ok_or_else(|| TruncatedRecordingSnafu.build())?;
+            recording.push(from, pdu);
+        }
+        Ok(recording)
+    }
+
+    /// Write the recording in its file representation.
+    pub fn write_to(&self, mut writer: impl Write) -> Result<()> {
+        let mut data = Vec::new();
+        for recorded in &self.pdus {
+            data.clear();
+            data.push(u8::from(recorded.from));
+            write_pdu(&mut data, &recorded.pdu).context(EncodePduSnafu)?;
+            writer.write_all(&data).context(WriteRecordingSnafu)?;
+        }
+        writer.flush().context(WriteRecordingSnafu)
+    }
+}
+
+fn read_exact_or_truncated(reader: &mut impl Read, buf: &mut [u8]) -> Result<()> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => TruncatedRecordingSnafu.fail(),
+        Err(e) => Err(e).context(ReadRecordingSnafu),
+    }
+}
+
+/// A recorder of the PDUs exchanged through an association.
+///
+/// The recorder is attached to one end of an association
+/// by wrapping its transport with [`wrap`](Recorder::wrap).
+/// The recorder can be cloned,
+/// so that the recording can be retrieved
+/// after the transport was passed to the association.
+///
+/// # Example
+///
+/// ```no_run
+/// # use dicom_ul::association::ClientAssociationOptions;
+/// # use dicom_ul::association::replay::{Recorder, Side};
+/// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let recorder = Recorder::new();
+/// let stream = std::net::TcpStream::connect("127.0.0.1:104")?;
+/// let association = ClientAssociationOptions::new()
+///     .with_abstract_syntax("1.2.840.10008.1.1")
+///     .establish_over(recorder.wrap(stream, Side::Requestor))?;
+/// association.release()?;
+///
+/// let file = std::fs::File::create("echo.pdus")?;
+/// recorder.recording().write_to(file)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Recorder {
+    state: Arc<Mutex<RecorderState>>,
+}
+
+#[derive(Debug, Default)]
+struct RecorderState {
+    recording: Recording,
+    /// the bytes of incomplete PDUs sent by each side
+    pending: [Vec<u8>; 2],
+}
+
+impl Recorder {
+    /// Create a new recorder with an empty recording.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wrap the transport of one end of an association,
+    /// so that all PDUs sent and received through it are recorded.
+    ///
+    /// `side` is the side of the association at this end.
+    pub fn wrap<S: Transport>(&self, stream: S, side: Side) -> RecordingStream<S> {
+        RecordingStream {
+            inner: stream,
+            side,
+            recorder: self.clone(),
+        }
+    }
+
+    /// Retrieve a copy of all PDUs recorded so far.
+    pub fn recording(&self) -> Recording {
+        self.lock().recording.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RecorderState> {
+        // the state is always consistent, even if a holder panicked
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn feed(&self, from: Side, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let mut state = self.lock();
+        let RecorderState { recording, pending } = &mut *state;
+        let pending = &mut pending[from.index()];
+        pending.extend_from_slice(data);
+        while pending.len() >= 6 {
+            let length =
+                u32::from_be_bytes([pending[2], pending[3], pending[4], pending[5]]) as usize;
+            if pending.len() < 6 + length {
+                break;
+            }
+            let bytes: Vec<u8> = pending.drain(..6 + length).collect();
+            match read_pdu(&bytes[..], MAXIMUM_PDU_SIZE, false) {
+                Ok(Some(pdu)) => recording.push(from, pdu),
+                Ok(None) => {}
+                Err(e) => warn!(
+                    "Could not record PDU sent by {}: {}",
+                    from,
+                    snafu::Report::from_error(e)
+                ),
+            }
+        }
+    }
+}
+
+/// A transport which records the PDUs passing through it,
+/// created with [`Recorder::wrap`].
+#[derive(Debug)]
+pub struct RecordingStream<S> {
+    inner: S,
+    side: Side,
+    recorder: Recorder,
+}
+
+impl<S> RecordingStream<S> {
+    /// Retrieve the underlying transport.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Read> Read for RecordingStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.recorder.feed(self.side.peer(), &buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<S: Write> Write for RecordingStream<S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.recorder.feed(self.side, &buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: CloseSocket> CloseSocket for RecordingStream<S> {
+    fn close(&mut self) -> std::io::Result<()> {
+        self.inner.close()
+    }
+}
+
+impl<S: Transport> Transport for RecordingStream<S> {
+    fn has_pending_input(&mut self) -> std::io::Result<bool> {
+        self.inner.has_pending_input()
+    }
+}
+
+/// Check whether a PDU received matches the one in a recording.
+///
+/// PDUs are compared for equality,
+/// except for the implementation class UID
+/// and implementation version name of association PDUs,
+/// which usually change between versions of the same software.
+pub fn pdus_match(expected: &Pdu, actual: &Pdu) -> bool {
+    fn is_identification(item: &UserVariableItem) -> bool {
+        matches!(
+            item,
+            UserVariableItem::ImplementationClassUID(_)
+                | UserVariableItem::ImplementationVersionName(_)
+        )
+    }
+
+    fn without_identification(pdu: &Pdu) -> Pdu {
+        let mut pdu = pdu.clone();
+        match &mut pdu {
+            Pdu::AssociationRQ(rq) => rq.user_variables.retain(|item| !is_identification(item)),
+            Pdu::AssociationAC(ac) => ac.user_variables.retain(|item| !is_identification(item)),
+            _ => {}
+        }
+        pdu
+    }
+
+    match (expected, actual) {
+        (Pdu::AssociationRQ(_), Pdu::AssociationRQ(_))
+        | (Pdu::AssociationAC(_), Pdu::AssociationAC(_)) => {
+            without_identification(expected) == without_identification(actual)
+        }
+        _ => expected == actual,
+    }
+}
+
+/// A mock peer which plays one side of a recorded association.
+///
+/// The PDUs recorded from the played side are sent in order,
+/// each one once all PDUs recorded before it
+/// were received from the other side.
+/// The PDUs received are checked against the recording
+/// with [`pdus_match`],
+/// unless another criterion is given with
+/// [`match_with`](Replay::match_with).
+/// The replay fails on the first PDU which does not match.
+pub struct Replay {
+    recording: Recording,
+    side: Side,
+    matcher: Box<Matcher>,
+}
+
+/// A criterion for a PDU received to match the one recorded.
+type Matcher = dyn FnMut(&Pdu, &Pdu) -> bool + Send;
+
+impl fmt::Debug for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Replay")
+            .field("recording", &self.recording)
+            .field("side", &self.side)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Replay {
+    /// Prepare to play the given side of a recorded association.
+    pub fn new(recording: Recording, side: Side) -> Self {
+        Replay {
+            recording,
+            side,
+            matcher: Box::new(pdus_match),
+        }
+    }
+
+    /// Check the PDUs received from the other side with the given function,
+    /// which is called with the recorded PDU and the PDU received
+    /// and returns whether they match.
+    pub fn match_with<F>(mut self, matcher: F) -> Self
+    where
+        F: FnMut(&Pdu, &Pdu) -> bool + Send + 'static,
+    {
+        self.matcher = Box::new(matcher);
+        self
+    }
+
+    /// Play the recorded side through the given transport
+    /// until the end of the recording.
+    ///
+    /// Returns all PDUs exchanged,
+    /// including the ones actually received from the other side.
+    pub fn run<S: Transport>(mut self, mut stream: S) -> Result<Recording> {
+        let mut exchanged = Recording::new();
+        let mut read_buffer = Vec::new();
+        let mut write_buffer = Vec::new();
+
+        for (index, recorded) in self.recording.pdus.iter().enumerate() {
+            if recorded.from == self.side {
+                write_buffer.clear();
+                write_pdu(&mut write_buffer, &recorded.pdu).context(EncodePduSnafu)?;
+                stream.write_all(&write_buffer).context(SendSnafu)?;
+                stream.flush().context(SendSnafu)?;
+                exchanged.push(recorded.from, recorded.pdu.clone());
+            } else {
+                let pdu = receive_pdu(&mut stream, &mut read_buffer)?.ok_or_else(|| {
+                    MissingPduSnafu {
+                        index,
+                        expected: Box::new(recorded.pdu.clone()),
+                    }
+                    .build()
+                })?;
+                ensure!(
+                    (self.matcher)(&recorded.pdu, &pdu),
+                    UnexpectedPduSnafu {
+                        index,
+                        expected: Box::new(recorded.pdu.clone()),
+                        actual: Box::new(pdu),
+                    }
+                );
+                exchanged.push(recorded.from, pdu);
+            }
+        }
+        let _ = stream.close();
+        Ok(exchanged)
+    }
+
+    /// Play the recorded side through the given transport
+    /// in a new thread.
+    pub fn spawn<S>(self, stream: S) -> JoinHandle<Result<Recording>>
+    where
+        S: Transport + Send + 'static,
+    {
+        std::thread::spawn(move || self.run(stream))
+    }
+}
+
+/// Receive the next PDU from the stream,
+/// or `None` if the connection was closed.
+fn receive_pdu(stream: &mut impl Read, read_buffer: &mut Vec<u8>) -> Result<Option<Pdu>> {
+    let mut buf = [0; 8192];
+    loop {
+        let mut cursor = Cursor::new(&read_buffer[..]);
+        if let Some(pdu) = read_pdu(&mut cursor, MAXIMUM_PDU_SIZE, false).context(DecodePduSnafu)? {
+            let position = cursor.position() as usize;
+            read_buffer.drain(..position);
+            return Ok(Some(pdu));
+        }
+        let n = match stream.read(&mut buf) {
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e).context(ReceiveSnafu),
+        };
+        if n == 0 {
+            return Ok(None);
+        }
+        read_buffer.extend_from_slice(&buf[..n]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::association::duplex::duplex;
+    use crate::pdu::{PDataValue, PDataValueType};
+
+    fn pdata(value_type: PDataValueType, byte: u8) -> Pdu {
+        Pdu::PData {
+            data: vec![PDataValue {
+                presentation_context_id: 1,
+                value_type,
+                is_last: true,
+                data: vec![byte; 32],
+            }],
+        }
+    }
+
+    fn sample() -> Recording {
+        Recording::new()
+            .with_pdu(Side::Requestor, pdata(PDataValueType::Command, 0x55))
+            .with_pdu(Side::Acceptor, pdata(PDataValueType::Command, 0xAA))
+            .with_pdu(Side::Requestor, Pdu::ReleaseRQ)
+            .with_pdu(Side::Acceptor, Pdu::ReleaseRP)
+    }
+
+    #[test]
+    fn recording_round_trip() {
+        let recording = sample();
+        let mut data = Vec::new();
+        recording.write_to(&mut data).unwrap();
+        assert_eq!(data[0], 0x01);
+        assert_eq!(Recording::read_from(&data[..]).unwrap(), recording);
+
+        // a recording cut short is reported
+        assert!(matches!(
+            Recording::read_from(&data[..data.len() - 2]),
+            Err(Error::TruncatedRecording { .. })
+        ));
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let recording = sample();
+        let (scu_stream, scp_stream) = duplex(1024);
+        let scp = Replay::new(recording.clone(), Side::Acceptor).spawn(scp_stream);
+
+        // play the requestor by hand, through a recorder
+        let recorder = Recorder::new();
+        let mut stream = recorder.wrap(scu_stream, Side::Requestor);
+        let mut read_buffer = Vec::new();
+        write_pdu(&mut stream, &pdata(PDataValueType::Command, 0x55)).unwrap();
+        let pdu = receive_pdu(&mut stream, &mut read_buffer).unwrap();
+        assert_eq!(pdu, Some(pdata(PDataValueType::Command, 0xAA)));
+        write_pdu(&mut stream, &Pdu::ReleaseRQ).unwrap();
+        let pdu = receive_pdu(&mut stream, &mut read_buffer).unwrap();
+        assert_eq!(pdu, Some(Pdu::ReleaseRP));
+
+        assert_eq!(scp.join().unwrap().unwrap(), recording);
+        assert_eq!(recorder.recording(), recording);
+    }
+
+    #[test]
+    fn replay_reports_mismatch() {
+        let (mut scu_stream, scp_stream) = duplex(1024);
+        let scp = Replay::new(sample(), Side::Acceptor).spawn(scp_stream);
+
+        write_pdu(&mut scu_stream, &pdata(PDataValueType::Data, 0x55)).unwrap();
+
+        match scp.join().unwrap() {
+            Err(Error::UnexpectedPdu {
+                index,
+                expected,
+                actual,
+                ..
+            }) => {
+                assert_eq!(index, 0);
+                assert_eq!(*expected, pdata(PDataValueType::Command, 0x55));
+                assert_eq!(*actual, pdata(PDataValueType::Data, 0x55));
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+
+        // the connection is closed on failure
+        let (mut scu_stream, scp_stream) = duplex(1024);
+        let scp = Replay::new(sample(), Side::Acceptor).spawn(scp_stream);
+        write_pdu(&mut scu_stream, &pdata(PDataValueType::Command, 0x55)).unwrap();
+        drop(scu_stream);
+        assert!(matches!(
+            scp.join().unwrap(),
+            Err(Error::MissingPdu { index: 2, .. }) | Err(Error::Send { .. })
+        ));
+    }
+}
@@ -5,15 +5,24 @@
 //! See [`ServerAssociationOptions`]
 //! for details and examples on how to create an association.
 use bytes::{Buf, BytesMut};
+#[cfg(feature = "dimse")]
+use std::io::Read;
 use std::io::{BufRead, BufReader};
+use std::net::TcpStream;
+#[cfg(feature = "sync-tls")]
+use std::sync::Arc;
 use std::time::Duration;
 use std::{borrow::Cow, io::Cursor};
-use std::{io::Write, net::TcpStream};
 
 use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
 use snafu::{ensure, Backtrace, ResultExt, Snafu};
 
+#[cfg(feature = "dimse")]
+use crate::{
+    dimse::{DimseMessage, MessageAssembler},
+    pdu::PDataValueType,
+};
 use crate::{
     pdu::{
         read_pdu, write_pdu, AbortRQServiceProviderReason, AbortRQSource, AssociationAC,
@@ -27,6 +36,12 @@ use crate::{
 use super::{
     pdata::{PDataReader, PDataWriter},
     uid::trim_uid,
+    Transport,
+};
+#[cfg(feature = "sync-tls")]
+use crate::tls::{
+    rustls::{ServerConfig, ServerConnection, StreamOwned},
+    TlsServerStream,
 };
 
 #[derive(Debug, Snafu)]
@@ -83,6 +98,16 @@ pub enum Error {
         pdu: Box<Pdu>,
     },
 
+    #[snafu(display(
+        "unexpected P-Data value in presentation context {} while receiving a data set",
+        presentation_context_id
+    ))]
+    #[cfg(feature = "dimse")]
+    UnexpectedPDataValue {
+        presentation_context_id: u8,
+        backtrace: Backtrace,
+    },
+
     /// association rejected
     Rejected { backtrace: Backtrace },
 
@@ -98,6 +123,13 @@ pub enum Error {
     #[snafu(display("Connection closed by peer"))]
     ConnectionClosed,
 
+    /// failed to process DIMSE message
+    #[cfg(feature = "dimse")]
+    Dimse {
+        #[snafu(backtrace)]
+        source: crate::dimse::Error,
+    },
+
     /// Could not set tcp read timeout
     SetReadTimeout {
         source: std::io::Error,
@@ -109,6 +141,24 @@ pub enum Error {
         source: std::io::Error,
         backtrace: Backtrace,
     },
+
+    /// missing TLS configuration to establish a secure association
+    #[cfg(feature = "sync-tls")]
+    MissingTlsConfig { backtrace: Backtrace },
+
+    /// could not initiate TLS connection
+    #[cfg(feature = "sync-tls")]
+    TlsConnection {
+        source: crate::tls::rustls::Error,
+        backtrace: Backtrace,
+    },
+
+    /// TLS handshake failed
+    #[cfg(feature = "sync-tls")]
+    TlsHandshake {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -116,6 +166,8 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 /// Common interface for application entity access control policies.
 ///
 /// Existing implementations include [`AcceptAny`] and [`AcceptCalledAeTitle`],
+/// as well as [`RequireAuthentication`](crate::auth::RequireAuthentication)
+/// for verifying the user identity of the requester,
 /// but users are free to implement their own.
 pub trait AccessControl {
     /// Obtain the decision of whether to accept an incoming association request
@@ -130,6 +182,80 @@ pub trait AccessControl {
         called_ae_title: &str,
         user_identity: Option<&UserIdentity>,
     ) -> Result<(), AssociationRJServiceUserReason>;
+
+    /// Obtain the decision of whether to accept an incoming association request,
+    /// along with the server response to the requester's user identity.
+    ///
+    /// Returns `Ok(Some(response))` if the user identity was verified,
+    /// in which case the response is sent back in the association acknowledgement
+    /// when the requester asked for a positive response
+    /// (the response is empty for the username identity types).
+    /// The default implementation defers to [`check_access`](Self::check_access)
+    /// and does not produce a response.
+    fn grant_access(
+        &self,
+        this_ae_title: &str,
+        calling_ae_title: &str,
+        called_ae_title: &str,
+        user_identity: Option<&UserIdentity>,
+    ) -> Result<Option<Vec<u8>>, AssociationRJServiceUserReason> {
+        self.check_access(
+            this_ae_title,
+            calling_ae_title,
+            called_ae_title,
+            user_identity,
+        )
+        .map(|_| None)
+    }
+
+    /// Obtain the decision of whether the requesting node
+    /// may use the given abstract syntax in this association.
+    ///
+    /// Presentation contexts proposing an abstract syntax
+    /// which is not allowed are rejected as not supported.
+    /// The default implementation allows all abstract syntaxes.
+    fn check_abstract_syntax(&self, _calling_ae_title: &str, _abstract_syntax_uid: &str) -> bool {
+        true
+    }
+}
+
+impl<T> AccessControl for Box<T>
+where
+    T: AccessControl + ?Sized,
+{
+    fn check_access(
+        &self,
+        this_ae_title: &str,
+        calling_ae_title: &str,
+        called_ae_title: &str,
+        user_identity: Option<&UserIdentity>,
+    ) -> Result<(), AssociationRJServiceUserReason> {
+        (**self).check_access(
+            this_ae_title,
+            calling_ae_title,
+            called_ae_title,
+            user_identity,
+        )
+    }
+
+    fn grant_access(
+        &self,
+        this_ae_title: &str,
+        calling_ae_title: &str,
+        called_ae_title: &str,
+        user_identity: Option<&UserIdentity>,
+    ) -> Result<Option<Vec<u8>>, AssociationRJServiceUserReason> {
+        (**self).grant_access(
+            this_ae_title,
+            calling_ae_title,
+            called_ae_title,
+            user_identity,
+        )
+    }
+
+    fn check_abstract_syntax(&self, calling_ae_title: &str, abstract_syntax_uid: &str) -> bool {
+        (**self).check_abstract_syntax(calling_ae_title, abstract_syntax_uid)
+    }
 }
 
 /// An access control rule that accepts any incoming association request.
@@ -254,6 +380,10 @@ impl AccessControl for AcceptCalledAeTitle {
 /// supported by the main [transfer syntax registry][1],
 /// unless one or more transfer syntaxes are explicitly indicated
 /// through calls to [`with_transfer_syntax`][2].
+/// The transfer syntaxes accepted for a specific abstract syntax,
+/// and the order in which they are preferred,
+/// can also be defined through
+/// [`with_transfer_syntax_preference`][3].
 ///
 /// Access control logic is also available,
 /// enabling application entities to decide on
@@ -274,6 +404,7 @@ impl AccessControl for AcceptCalledAeTitle {
 ///
 /// [1]: dicom_transfer_syntax_registry
 /// [2]: ServerAssociationOptions::with_transfer_syntax
+/// [3]: ServerAssociationOptions::with_transfer_syntax_preference
 #[derive(Debug, Clone)]
 pub struct ServerAssociationOptions<'a, A> {
     /// the application entity access control policy
@@ -286,6 +417,9 @@ pub struct ServerAssociationOptions<'a, A> {
     abstract_syntax_uids: Vec<Cow<'a, str>>,
     /// the list of requested transfer syntaxes
     transfer_syntax_uids: Vec<Cow<'a, str>>,
+    /// the transfer syntaxes accepted for specific abstract syntaxes,
+    /// by order of preference
+    transfer_syntax_preferences: Vec<(Cow<'a, str>, Vec<Cow<'a, str>>)>,
     /// the expected protocol version
     protocol_version: u16,
     /// the maximum PDU length
@@ -296,6 +430,11 @@ pub struct ServerAssociationOptions<'a, A> {
     promiscuous: bool,
     /// Timeout for individual send/receive operations
     timeout: Option<std::time::Duration>,
+    /// the maximum numbers of asynchronous operations invoked and performed
+    async_operations_window: Option<(u16, u16)>,
+    /// TLS configuration for secure associations
+    #[cfg(feature = "sync-tls")]
+    tls_config: Option<Arc<ServerConfig>>,
 }
 
 impl Default for ServerAssociationOptions<'_, AcceptAny> {
@@ -306,11 +445,15 @@ impl Default for ServerAssociationOptions<'_, AcceptAny> {
             application_context_name: "1.2.840.10008.3.1.1.1".into(),
             abstract_syntax_uids: Vec::new(),
             transfer_syntax_uids: Vec::new(),
+            transfer_syntax_preferences: Vec::new(),
             protocol_version: 1,
             max_pdu_length: DEFAULT_MAX_PDU,
             strict: true,
             promiscuous: false,
             timeout: None,
+            async_operations_window: None,
+            #[cfg(feature = "sync-tls")]
+            tls_config: None,
         }
     }
 }
@@ -356,12 +499,16 @@ where
             application_context_name,
             abstract_syntax_uids,
             transfer_syntax_uids,
+            transfer_syntax_preferences,
             protocol_version,
             max_pdu_length,
             strict,
             promiscuous,
             ae_access_control: _,
             timeout,
+            async_operations_window,
+            #[cfg(feature = "sync-tls")]
+            tls_config,
         } = self;
 
         ServerAssociationOptions {
@@ -370,11 +517,15 @@ where
             application_context_name,
             abstract_syntax_uids,
             transfer_syntax_uids,
+            transfer_syntax_preferences,
             protocol_version,
             max_pdu_length,
             strict,
             promiscuous,
             timeout,
+            async_operations_window,
+            #[cfg(feature = "sync-tls")]
+            tls_config,
         }
     }
 
@@ -410,6 +561,53 @@ where
         self
     }
 
+    /// Define the transfer syntaxes accepted for this abstract syntax,
+    /// by order of preference.
+    ///
+    /// For presentation contexts with this abstract syntax,
+    /// the first of these transfer syntaxes
+    /// which is also proposed by the requester
+    /// and supported by the main transfer syntax registry
+    /// is chosen,
+    /// regardless of the order proposed by the requester.
+    /// Any other transfer syntax is rejected,
+    /// even if included through [`with_transfer_syntax`](Self::with_transfer_syntax).
+    /// Calling this method again for the same abstract syntax
+    /// replaces its preferences.
+    ///
+    /// ```
+    /// # use dicom_ul::association::server::ServerAssociationOptions;
+    /// let scp_options = ServerAssociationOptions::new()
+    ///     // CT Image Storage:
+    ///     // prefer JPEG 2000 (lossless only), then uncompressed
+    ///     .with_abstract_syntax("1.2.840.10008.5.1.4.1.1.2")
+    ///     .with_transfer_syntax_preference(
+    ///         "1.2.840.10008.5.1.4.1.1.2",
+    ///         ["1.2.840.10008.1.2.4.90", "1.2.840.10008.1.2.1", "1.2.840.10008.1.2"],
+    ///     );
+    /// ```
+    pub fn with_transfer_syntax_preference<T, I, U>(
+        mut self,
+        abstract_syntax_uid: T,
+        transfer_syntax_uids: I,
+    ) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+        I: IntoIterator<Item = U>,
+        U: Into<Cow<'a, str>>,
+    {
+        let abstract_syntax_uid = trim_uid(abstract_syntax_uid.into());
+        let transfer_syntax_uids = transfer_syntax_uids
+            .into_iter()
+            .map(|uid| trim_uid(uid.into()))
+            .collect();
+        self.transfer_syntax_preferences
+            .retain(|(uid, _)| *uid != abstract_syntax_uid);
+        self.transfer_syntax_preferences
+            .push((abstract_syntax_uid, transfer_syntax_uids));
+        self
+    }
+
     /// Override the maximum expected PDU length.
     pub fn max_pdu_length(mut self, value: u32) -> Self {
         self.max_pdu_length = value;
@@ -431,6 +629,20 @@ where
         self
     }
 
+    /// Support the negotiation of an asynchronous operations window,
+    /// with the maximum number of outstanding operations
+    /// which this node may invoke and perform on the association.
+    /// A value of 0 means unlimited.
+    ///
+    /// When requested by the association requester,
+    /// the window accepted is the lesser of both parties.
+    /// By default, no window is negotiated,
+    /// so that only one operation may be outstanding at a time.
+    pub fn async_operations_window(mut self, max_invoked: u16, max_performed: u16) -> Self {
+        self.async_operations_window = Some((max_invoked, max_performed));
+        self
+    }
+
     /// Set the timeout for the underlying TCP socket
     pub fn timeout(self, timeout: Duration) -> Self {
         Self {
@@ -439,20 +651,71 @@ where
         }
     }
 
+    /// Define the TLS configuration
+    /// for establishing secure associations
+    /// with [`establish_tls`](Self::establish_tls).
+    ///
+    /// See the [`tls`](crate::tls) module
+    /// for a convenient way to build one.
+    #[cfg(feature = "sync-tls")]
+    pub fn tls_config(mut self, config: Arc<ServerConfig>) -> Self {
+        self.tls_config = Some(config);
+        self
+    }
+
     /// Negotiate an association with the given TCP stream.
-    pub fn establish(&self, mut socket: TcpStream) -> Result<ServerAssociation<TcpStream>> {
-        ensure!(
-            !self.abstract_syntax_uids.is_empty() || self.promiscuous,
-            MissingAbstractSyntaxSnafu
-        );
+    pub fn establish(&self, socket: TcpStream) -> Result<ServerAssociation<TcpStream>> {
+        self.set_timeouts(&socket)?;
+        self.establish_over(socket)
+    }
 
-        let max_pdu_length = self.max_pdu_length;
+    /// Secure the given TCP stream with TLS
+    /// and negotiate an association through it.
+    ///
+    /// A TLS configuration must have been defined via
+    /// [`tls_config`](Self::tls_config).
+    #[cfg(feature = "sync-tls")]
+    pub fn establish_tls(&self, socket: TcpStream) -> Result<ServerAssociation<TlsServerStream>> {
+        let config = self
+            .tls_config
+            .clone()
+            .ok_or_else(|| MissingTlsConfigSnafu.build())?;
+        self.set_timeouts(&socket)?;
+        let conn = ServerConnection::new(config).context(TlsConnectionSnafu)?;
+        let mut stream = StreamOwned::new(conn, socket);
+        while stream.conn.is_handshaking() {
+            stream
+                .conn
+                .complete_io(&mut stream.sock)
+                .context(TlsHandshakeSnafu)?;
+        }
+        self.establish_over(stream)
+    }
+
+    /// Apply the configured timeout to the TCP stream.
+    fn set_timeouts(&self, socket: &TcpStream) -> Result<()> {
         socket
             .set_read_timeout(self.timeout)
             .context(SetReadTimeoutSnafu)?;
         socket
             .set_write_timeout(self.timeout)
             .context(SetWriteTimeoutSnafu)?;
+        Ok(())
+    }
+
+    /// Negotiate an association over an already connected transport.
+    ///
+    /// This allows associations to run on streams other than TCP,
+    /// such as Unix domain sockets
+    /// or an in-memory [`duplex`](crate::association::duplex) pipe.
+    /// The configured timeout is not applied to the stream.
+    pub fn establish_over<S: Transport>(&self, mut socket: S) -> Result<ServerAssociation<S>> {
+        ensure!(
+            !self.abstract_syntax_uids.is_empty() || self.promiscuous,
+            MissingAbstractSyntaxSnafu
+        );
+
+        let max_pdu_length = self.max_pdu_length;
 
         let mut read_buffer = BytesMut::with_capacity(MAXIMUM_PDU_SIZE as usize);
         let mut reader = BufReader::new(&mut socket);
@@ -519,19 +782,22 @@ where
                     return RejectedSnafu.fail();
                 }
 
-                self.ae_access_control
-                    .check_access(
+                let user_identity =
+                    user_variables
+                        .iter()
+                        .find_map(|user_variable| match user_variable {
+                            UserVariableItem::UserIdentityItem(user_identity) => {
+                                Some(user_identity)
+                            }
+                            _ => None,
+                        });
+                let user_identity_response = self
+                    .ae_access_control
+                    .grant_access(
                         &self.ae_title,
                         &calling_ae_title,
                         &called_ae_title,
-                        user_variables
-                            .iter()
-                            .find_map(|user_variable| match user_variable {
-                                UserVariableItem::UserIdentityItem(user_identity) => {
-                                    Some(user_identity)
-                                }
-                                _ => None,
-                            }),
+                        user_identity,
                     )
                     .map(Ok)
                     .unwrap_or_else(|reason| {
@@ -563,13 +829,25 @@ where
                     requestor_max_pdu_length
                 };
 
+                let abstract_syntaxes: Vec<_> = presentation_contexts
+                    .iter()
+                    .map(|pc| {
+                        (
+                            pc.id,
+                            trim_uid(Cow::from(pc.abstract_syntax.as_str())).into_owned(),
+                        )
+                    })
+                    .collect();
+
                 let presentation_contexts: Vec<_> = presentation_contexts
                     .into_iter()
                     .map(|pc| {
-                        if !self
-                            .abstract_syntax_uids
-                            .contains(&trim_uid(Cow::from(pc.abstract_syntax)))
-                            && !self.promiscuous
+                        let abstract_syntax = trim_uid(Cow::from(pc.abstract_syntax));
+                        if (!self.abstract_syntax_uids.contains(&abstract_syntax)
+                            && !self.promiscuous)
+                            || !self
+                                .ae_access_control
+                                .check_abstract_syntax(&calling_ae_title, &abstract_syntax)
                         {
                             return PresentationContextResult {
                                 id: pc.id,
@@ -579,7 +857,7 @@ where
                         }
 
                         let (transfer_syntax, reason) = self
-                            .choose_ts(pc.transfer_syntaxes)
+                            .choose_ts(&abstract_syntax, pc.transfer_syntaxes)
                             .map(|ts| (ts, PresentationContextResultReason::Acceptance))
                             .unwrap_or_else(|| {
                                 (
@@ -603,7 +881,7 @@ where
                         application_context_name,
                         presentation_contexts: presentation_contexts.clone(),
                         calling_ae_title: calling_ae_title.clone(),
-                        called_ae_title,
+                        called_ae_title: called_ae_title.clone(),
                         user_variables: vec![
                             UserVariableItem::MaxLength(max_pdu_length),
                             UserVariableItem::ImplementationClassUID(
@@ -612,7 +890,26 @@ where
                             UserVariableItem::ImplementationVersionName(
                                 IMPLEMENTATION_VERSION_NAME.to_string(),
                             ),
-                        ],
+                        ]
+                        .into_iter()
+                        .chain(async_operations_window_response(
+                            &user_variables,
+                            self.async_operations_window,
+                        ))
+                        .chain(role_selection_response(
+                            &user_variables,
+                            &abstract_syntaxes,
+                            &presentation_contexts,
+                        ))
+                        .chain(
+                            user_identity_response
+                                .filter(|_| {
+                                    user_identity
+                                        .is_some_and(UserIdentity::positive_response_requested)
+                                })
+                                .map(UserVariableItem::UserIdentityResponse),
+                        )
+                        .collect(),
                     }),
                 )
                 .context(SendResponseSnafu)?;
@@ -620,14 +917,18 @@ where
 
                 Ok(ServerAssociation {
                     presentation_contexts,
+                    abstract_syntaxes,
                     requestor_max_pdu_length,
                     acceptor_max_pdu_length: max_pdu_length,
                     socket,
                     client_ae_title: calling_ae_title,
+                    called_ae_title,
                     buffer,
                     strict: self.strict,
                     read_buffer: BytesMut::with_capacity(MAXIMUM_PDU_SIZE as usize),
                     timeout: self.timeout,
+                    #[cfg(feature = "dimse")]
+                    dimse: MessageAssembler::default(),
                 })
             }
             Pdu::ReleaseRQ => {
@@ -644,18 +945,39 @@ where
         }
     }
 
-    /// From a sequence of transfer syntaxes,
+    /// From a sequence of transfer syntaxes
+    /// proposed for the given abstract syntax,
     /// choose the first transfer syntax to
     /// - be on the options' list of transfer syntaxes, and
     /// - be supported by the main transfer syntax registry.
     ///
     /// If the options' list is empty,
     /// accept the first transfer syntax supported.
-    fn choose_ts<I, T>(&self, it: I) -> Option<T>
+    /// If the abstract syntax has its own preferences,
+    /// choose the most preferred transfer syntax which is supported instead.
+    fn choose_ts<I, T>(&self, abstract_syntax_uid: &str, it: I) -> Option<T>
     where
         I: IntoIterator<Item = T>,
         T: AsRef<str>,
     {
+        if let Some((_, preferences)) = self
+            .transfer_syntax_preferences
+            .iter()
+            .find(|(uid, _)| uid == abstract_syntax_uid)
+        {
+            return it
+                .into_iter()
+                .filter_map(|ts| {
+                    let rank = preferences
+                        .iter()
+                        .position(|uid| *uid == trim_uid(ts.as_ref().into()))?;
+                    Some((rank, ts))
+                })
+                .filter(|(_, ts)| is_supported(ts.as_ref()))
+                .min_by_key(|(rank, _)| *rank)
+                .map(|(_, ts)| ts);
+        }
+
         if self.transfer_syntax_uids.is_empty() {
             return choose_supported(it);
         }
@@ -686,6 +1008,8 @@ where
 pub struct ServerAssociation<S> {
     /// The accorded presentation contexts
     presentation_contexts: Vec<PresentationContextResult>,
+    /// The abstract syntaxes proposed for each presentation context
+    abstract_syntaxes: Vec<(u8, String)>,
     /// The maximum PDU length that the remote application entity accepts
     requestor_max_pdu_length: u32,
     /// The maximum PDU length that this application entity is expecting to receive
@@ -694,6 +1018,8 @@ pub struct ServerAssociation<S> {
     socket: S,
     /// The application entity title of the other DICOM node
     client_ae_title: String,
+    /// The application entity title called by the other DICOM node
+    called_ae_title: String,
     /// write buffer to send fully assembled PDUs on wire
     buffer: Vec<u8>,
     /// whether to receive PDUs in strict mode
@@ -702,6 +1028,9 @@ pub struct ServerAssociation<S> {
     read_buffer: bytes::BytesMut,
     /// Timeout for individual send/receive operations
     timeout: Option<std::time::Duration>,
+    /// Reassembler of incoming DIMSE messages
+    #[cfg(feature = "dimse")]
+    dimse: MessageAssembler,
 }
 
 impl<S> ServerAssociation<S> {
@@ -710,13 +1039,28 @@ impl<S> ServerAssociation<S> {
         &self.presentation_contexts
     }
 
+    /// Obtain the abstract syntax proposed for the presentation context
+    /// with the given identifier.
+    pub fn abstract_syntax(&self, presentation_context_id: u8) -> Option<&str> {
+        self.abstract_syntaxes
+            .iter()
+            .find(|(id, _)| *id == presentation_context_id)
+            .map(|(_, uid)| uid.as_str())
+    }
+
     /// Obtain the remote DICOM node's application entity title.
     pub fn client_ae_title(&self) -> &str {
         &self.client_ae_title
     }
+
+    /// Obtain the application entity title
+    /// which the remote DICOM node called.
+    pub fn called_ae_title(&self) -> &str {
+        &self.called_ae_title
+    }
 }
 
-impl ServerAssociation<TcpStream> {
+impl<S: Transport> ServerAssociation<S> {
     /// Send a PDU message to the other intervenient.
     pub fn send(&mut self, msg: &Pdu) -> Result<()> {
         self.buffer.clear();
@@ -772,7 +1116,7 @@ impl ServerAssociation<TcpStream> {
             ),
         };
         let out = self.send(&pdu);
-        let _ = self.socket.shutdown(std::net::Shutdown::Both);
+        let _ = self.socket.close();
         out
     }
 
@@ -781,7 +1125,7 @@ impl ServerAssociation<TcpStream> {
     ///
     /// Returns a writer which automatically
     /// splits the inner data into separate PDUs if necessary.
-    pub fn send_pdata(&mut self, presentation_context_id: u8) -> PDataWriter<&mut TcpStream> {
+    pub fn send_pdata(&mut self, presentation_context_id: u8) -> PDataWriter<&mut S> {
         PDataWriter::new(
             &mut self.socket,
             presentation_context_id,
@@ -794,7 +1138,7 @@ impl ServerAssociation<TcpStream> {
     ///
     /// Returns a reader which automatically
     /// receives more data PDUs once the bytes collected are consumed.
-    pub fn receive_pdata(&mut self) -> PDataReader<&mut TcpStream> {
+    pub fn receive_pdata(&mut self) -> PDataReader<&mut S> {
         PDataReader::new(
             &mut self.socket,
             self.acceptor_max_pdu_length,
@@ -802,8 +1146,107 @@ impl ServerAssociation<TcpStream> {
         )
     }
 
-    /// Obtain access to the inner TCP stream
-    /// connected to the association acceptor.
+    /// Send a full DIMSE message to the association requester,
+    /// split into as many P-Data PDUs as necessary.
+    #[cfg(feature = "dimse")]
+    pub fn send_dimse(&mut self, msg: &DimseMessage) -> Result<()> {
+        for pdu in msg
+            .to_pdus(self.requestor_max_pdu_length)
+            .context(DimseSnafu)?
+        {
+            self.send(&pdu)?;
+        }
+        Ok(())
+    }
+
+    /// Receive a full DIMSE message from the association requester.
+    ///
+    /// Fails with [`Error::UnexpectedRequest`]
+    /// if a PDU other than P-Data is received,
+    /// such as a release request or an abort.
+    #[cfg(feature = "dimse")]
+    pub fn receive_dimse(&mut self) -> Result<DimseMessage> {
+        loop {
+            if let Some(msg) = self.dimse.pop_message() {
+                return Ok(msg);
+            }
+            match self.receive()? {
+                Pdu::PData { data } => self.dimse.push_values(data).context(DimseSnafu)?,
+                pdu => return UnexpectedRequestSnafu { pdu }.fail(),
+            }
+        }
+    }
+
+    /// Receive a full DIMSE message from the association requester
+    /// if one is already arriving,
+    /// without waiting for one otherwise.
+    ///
+    /// This is useful for noticing requests such as C-CANCEL
+    /// while an operation is in progress.
+    /// Whether a message is arriving is decided through
+    /// [`Transport::has_pending_input`],
+    /// so transports which cannot tell never yield a message here.
+    #[cfg(feature = "dimse")]
+    pub fn try_receive_dimse(&mut self) -> Result<Option<DimseMessage>> {
+        if let Some(msg) = self.dimse.pop_message() {
+            return Ok(Some(msg));
+        }
+        if self.read_buffer.is_empty()
+            && !self
+                .socket
+                .has_pending_input()
+                .context(ReadPduSnafu)
+                .context(ReceiveSnafu)?
+        {
+            return Ok(None);
+        }
+        self.receive_dimse().map(Some)
+    }
+
+    /// Receive the next DIMSE message from the association requester,
+    /// without collecting its data set in memory.
+    ///
+    /// If the data set of the message is still being received,
+    /// the message is returned without data,
+    /// alongside a reader of the data set as it arrives.
+    /// Otherwise, the data set, if any, is already in the message.
+    ///
+    /// Fails with [`Error::UnexpectedRequest`]
+    /// if a PDU other than P-Data is received,
+    /// such as a release request or an abort.
+    #[cfg(feature = "dimse")]
+    pub fn receive_dimse_streaming(
+        &mut self,
+    ) -> Result<(DimseMessage, Option<DataSetReader<'_, S>>)> {
+        loop {
+            if let Some(msg) = self.dimse.pop_message() {
+                return Ok((msg, None));
+            }
+            if let Some((presentation_context_id, command, data)) =
+                self.dimse.take_pending_command()
+            {
+                let reader = DataSetReader {
+                    association: self,
+                    presentation_context_id,
+                    buffer: data,
+                    position: 0,
+                    finished: false,
+                    error: None,
+                };
+                return Ok((
+                    DimseMessage::new(presentation_context_id, command),
+                    Some(reader),
+                ));
+            }
+            match self.receive()? {
+                Pdu::PData { data } => self.dimse.push_values(data).context(DimseSnafu)?,
+                pdu => return UnexpectedRequestSnafu { pdu }.fail(),
+            }
+        }
+    }
+
+    /// Obtain access to the inner stream
+    /// connected to the association requester.
     ///
     /// This can be used to send the PDU in semantic fragments of the message,
     /// thus using less memory.
@@ -811,11 +1254,166 @@ impl ServerAssociation<TcpStream> {
     /// **Note:** reading and writing should be done with care
     /// to avoid inconsistencies in the association state.
     /// Do not call `send` and `receive` while not in a PDU boundary.
-    pub fn inner_stream(&mut self) -> &mut TcpStream {
+    pub fn inner_stream(&mut self) -> &mut S {
         &mut self.socket
     }
 }
 
+/// A reader of a data set as it is received from the association requester,
+/// obtained through [`ServerAssociation::receive_dimse_streaming`].
+///
+/// The data set is read in the transfer syntax of the presentation context,
+/// receiving more P-Data PDUs as needed
+/// until its last fragment.
+/// Once done reading,
+/// call [`finish`](Self::finish)
+/// to discard the rest of the data set
+/// and check that it was fully received.
+/// Dropping the reader also discards the rest of the data set.
+#[cfg(feature = "dimse")]
+#[must_use]
+pub struct DataSetReader<'a, S>
+where
+    S: Transport,
+{
+    association: &'a mut ServerAssociation<S>,
+    presentation_context_id: u8,
+    buffer: Vec<u8>,
+    position: usize,
+    finished: bool,
+    error: Option<Error>,
+}
+
+#[cfg(feature = "dimse")]
+impl<S> std::fmt::Debug for DataSetReader<'_, S>
+where
+    S: Transport,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataSetReader")
+            .field("presentation_context_id", &self.presentation_context_id)
+            .field("buffered", &(self.buffer.len() - self.position))
+            .field("finished", &self.finished)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+#[cfg(feature = "dimse")]
+impl<S> DataSetReader<'_, S>
+where
+    S: Transport,
+{
+    /// The association over which the data set is received.
+    pub fn association(&self) -> &ServerAssociation<S> {
+        self.association
+    }
+
+    /// The identifier of the presentation context of the data set.
+    pub fn presentation_context_id(&self) -> u8 {
+        self.presentation_context_id
+    }
+
+    /// Discard the rest of the data set,
+    /// failing if it could not be fully received.
+    pub fn finish(mut self) -> Result<()> {
+        self.discard();
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn discard(&mut self) {
+        self.position = self.buffer.len();
+        while !self.finished && self.error.is_none() {
+            if let Err(e) = self.fill() {
+                self.error = Some(e);
+            }
+        }
+        self.position = self.buffer.len();
+    }
+
+    /// Receive the next fragments of the data set.
+    ///
+    /// Any P-Data values after the last fragment
+    /// belong to the next message,
+    /// and are handed back to the association.
+    fn fill(&mut self) -> Result<()> {
+        self.buffer.clear();
+        self.position = 0;
+        match self.association.receive()? {
+            Pdu::PData { data } => {
+                let mut values = data.into_iter();
+                for value in values.by_ref() {
+                    ensure!(
+                        value.presentation_context_id == self.presentation_context_id
+                            && value.value_type == PDataValueType::Data,
+                        UnexpectedPDataValueSnafu {
+                            presentation_context_id: value.presentation_context_id,
+                        }
+                    );
+                    self.buffer.extend(value.data);
+                    if value.is_last {
+                        self.finished = true;
+                        break;
+                    }
+                }
+                let rest: Vec<_> = values.collect();
+                if !rest.is_empty() {
+                    self.association
+                        .dimse
+                        .push_values(rest)
+                        .context(DimseSnafu)?;
+                }
+                Ok(())
+            }
+            pdu => UnexpectedRequestSnafu { pdu }.fail(),
+        }
+    }
+}
+
+#[cfg(feature = "dimse")]
+impl<S> Read for DataSetReader<'_, S>
+where
+    S: Transport,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.position == self.buffer.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            if let Some(e) = &self.error {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    e.to_string(),
+                ));
+            }
+            if let Err(e) = self.fill() {
+                let io_error = std::io::Error::new(std::io::ErrorKind::Other, e.to_string());
+                self.error = Some(e);
+                return Err(io_error);
+            }
+        }
+        let n = buf.len().min(self.buffer.len() - self.position);
+        buf[..n].copy_from_slice(&self.buffer[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+/// Discard the rest of the data set,
+/// so that the association can carry on with the next message.
+#[cfg(feature = "dimse")]
+impl<S> Drop for DataSetReader<'_, S>
+where
+    S: Transport,
+{
+    fn drop(&mut self) {
+        self.discard();
+    }
+}
+
 /// Check that a transfer syntax repository
 /// supports the given transfer syntax,
 /// meaning that it can parse and decode DICOM data sets.
@@ -873,6 +1471,77 @@ where
     it.into_iter().find(|ts| is_supported(ts.as_ref()))
 }
 
+/// Build the asynchronous operations window sub-item
+/// of the association response,
+/// if requested by the requestor and supported by this node.
+///
+/// Each party may invoke at most as many operations
+/// as the other party can perform,
+/// with 0 standing for an unlimited number.
+fn async_operations_window_response(
+    user_variables: &[UserVariableItem],
+    supported: Option<(u16, u16)>,
+) -> Option<UserVariableItem> {
+    let (max_invoked, max_performed) = supported?;
+    let (requested_invoked, requested_performed) =
+        user_variables.iter().find_map(|item| match item {
+            UserVariableItem::AsyncOperationsWindow(invoked, performed) => {
+                Some((*invoked, *performed))
+            }
+            _ => None,
+        })?;
+
+    fn lesser(a: u16, b: u16) -> u16 {
+        match (a, b) {
+            (0, b) => b,
+            (a, 0) => a,
+            (a, b) => a.min(b),
+        }
+    }
+
+    Some(UserVariableItem::AsyncOperationsWindow(
+        lesser(max_invoked, requested_performed),
+        lesser(max_performed, requested_invoked),
+    ))
+}
+
+/// Build the SCP/SCU role selection sub-items of the association response,
+/// accepting the roles proposed by the requestor
+/// for each SOP class with at least one accepted presentation context.
+///
+/// No sub-item is produced for SOP classes which were not proposed
+/// in a role selection sub-item,
+/// so that the default roles apply.
+fn role_selection_response(
+    user_variables: &[UserVariableItem],
+    abstract_syntaxes: &[(u8, String)],
+    presentation_contexts: &[PresentationContextResult],
+) -> Vec<UserVariableItem> {
+    user_variables
+        .iter()
+        .filter_map(|item| match item {
+            UserVariableItem::RoleSelectionSubItem(sop_class_uid, scu_role, scp_role) => {
+                let accepted = presentation_contexts.iter().any(|pc| {
+                    pc.reason == PresentationContextResultReason::Acceptance
+                        && abstract_syntaxes
+                            .iter()
+                            .any(|(id, uid)| *id == pc.id && uid == sop_class_uid)
+                });
+                if accepted {
+                    Some(UserVariableItem::RoleSelectionSubItem(
+                        sop_class_uid.clone(),
+                        *scu_role,
+                        *scp_role,
+                    ))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
 #[cfg(feature = "async")]
 pub mod non_blocking {
     use std::{borrow::Cow, io::Cursor};
@@ -880,13 +1549,18 @@ pub mod non_blocking {
     use bytes::{Buf, BytesMut};
     use snafu::{ensure, ResultExt};
     use tokio::{
-        io::{AsyncReadExt, AsyncWriteExt},
+        io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
         net::TcpStream,
     };
 
     use super::{
-        AccessControl, Result, SendSnafu, SendTooLongPduSnafu, ServerAssociation,
-        ServerAssociationOptions, WireSendSnafu,
+        async_operations_window_response, role_selection_response, AccessControl, Result,
+        SendSnafu, SendTooLongPduSnafu, ServerAssociation, ServerAssociationOptions, WireSendSnafu,
+    };
+    #[cfg(feature = "async-tls")]
+    use crate::{
+        association::server::{MissingTlsConfigSnafu, TlsHandshakeSnafu},
+        tls::AsyncTlsServerStream,
     };
     use crate::{
         association::{
@@ -901,10 +1575,15 @@ pub mod non_blocking {
             AbortRQServiceProviderReason, AbortRQSource, AssociationAC, AssociationRJ,
             AssociationRJResult, AssociationRJServiceUserReason, AssociationRJSource,
             AssociationRQ, PresentationContextResult, PresentationContextResultReason,
-            ReadPduSnafu, UserVariableItem, DEFAULT_MAX_PDU, MAXIMUM_PDU_SIZE,
+            ReadPduSnafu, UserIdentity, UserVariableItem, DEFAULT_MAX_PDU, MAXIMUM_PDU_SIZE,
         },
         read_pdu, write_pdu, Pdu, IMPLEMENTATION_CLASS_UID, IMPLEMENTATION_VERSION_NAME,
     };
+    #[cfg(feature = "dimse")]
+    use {
+        super::DimseSnafu,
+        crate::dimse::{DimseMessage, MessageAssembler},
+    };
 
     impl<A> ServerAssociationOptions<'_, A>
     where
@@ -913,8 +1592,45 @@ pub mod non_blocking {
         /// Negotiate an association with the given TCP stream.
         pub async fn establish_async(
             &self,
-            mut socket: TcpStream,
+            socket: TcpStream,
         ) -> Result<ServerAssociation<TcpStream>> {
+            self.establish_over_async(socket).await
+        }
+
+        /// Secure the given TCP stream with TLS
+        /// and negotiate an association through it.
+        ///
+        /// A TLS configuration must have been defined via
+        /// [`tls_config`](Self::tls_config).
+        #[cfg(feature = "async-tls")]
+        pub async fn establish_tls_async(
+            &self,
+            socket: TcpStream,
+        ) -> Result<ServerAssociation<AsyncTlsServerStream>> {
+            let config = self
+                .tls_config
+                .clone()
+                .ok_or_else(|| MissingTlsConfigSnafu.build())?;
+            let accept = tokio_rustls::TlsAcceptor::from(config).accept(socket);
+            let socket = match self.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, accept)
+                    .await
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::TimedOut, err))
+                    .and_then(|result| result),
+                None => accept.await,
+            }
+            .context(TlsHandshakeSnafu)?;
+            self.establish_over_async(socket).await
+        }
+
+        /// Negotiate an association
+        /// over an already connected asynchronous transport,
+        /// such as a Unix domain socket
+        /// or an in-memory [`DuplexStream`](tokio::io::DuplexStream).
+        pub async fn establish_over_async<S>(&self, mut socket: S) -> Result<ServerAssociation<S>>
+        where
+            S: AsyncRead + AsyncWrite + Unpin,
+        {
             ensure!(
                 !self.abstract_syntax_uids.is_empty() || self.promiscuous,
                 MissingAbstractSyntaxSnafu
@@ -986,10 +1702,7 @@ pub mod non_blocking {
                             return RejectedSnafu.fail();
                         }
 
-                        match self.ae_access_control.check_access(
-                            &self.ae_title,
-                            &calling_ae_title,
-                            &called_ae_title,
+                        let user_identity =
                             user_variables
                                 .iter()
                                 .find_map(|user_variable| match user_variable {
@@ -997,9 +1710,14 @@ pub mod non_blocking {
                                         Some(user_identity)
                                     }
                                     _ => None,
-                                }),
+                                });
+                        let user_identity_response = match self.ae_access_control.grant_access(
+                            &self.ae_title,
+                            &calling_ae_title,
+                            &called_ae_title,
+                            user_identity,
                         ) {
-                            Ok(()) => {}
+                            Ok(response) => response,
                             Err(reason) => {
                                 write_pdu(
                                     &mut buffer,
@@ -1012,7 +1730,7 @@ pub mod non_blocking {
                                 socket.write_all(&buffer).await.context(WireSendSnafu)?;
                                 return Err(RejectedSnafu.build());
                             }
-                        }
+                        };
 
                         // fetch requested maximum PDU length
                         let requestor_max_pdu_length = user_variables
@@ -1030,13 +1748,24 @@ pub mod non_blocking {
                             requestor_max_pdu_length
                         };
 
+                        let abstract_syntaxes: Vec<_> = presentation_contexts
+                            .iter()
+                            .map(|pc| {
+                                (
+                                    pc.id,
+                                    trim_uid(Cow::from(pc.abstract_syntax.as_str())).into_owned(),
+                                )
+                            })
+                            .collect();
+
                         let presentation_contexts: Vec<_> = presentation_contexts
                             .into_iter()
                             .map(|pc| {
-                                if !self
-                                    .abstract_syntax_uids
-                                    .contains(&trim_uid(Cow::from(pc.abstract_syntax)))
-                                    && !self.promiscuous
+                                let abstract_syntax = trim_uid(Cow::from(pc.abstract_syntax));
+                                if (!self.abstract_syntax_uids.contains(&abstract_syntax) && !self.promiscuous)
+                                    || !self
+                                        .ae_access_control
+                                        .check_abstract_syntax(&calling_ae_title, &abstract_syntax)
                                 {
                                     return PresentationContextResult {
                                         id: pc.id,
@@ -1046,7 +1775,7 @@ pub mod non_blocking {
                                 }
 
                                 let (transfer_syntax, reason) = self
-                                    .choose_ts(pc.transfer_syntaxes)
+                                    .choose_ts(&abstract_syntax, pc.transfer_syntaxes)
                                     .map(|ts| (ts, PresentationContextResultReason::Acceptance))
                                     .unwrap_or_else(|| {
                                         (
@@ -1070,7 +1799,7 @@ pub mod non_blocking {
                                 application_context_name,
                                 presentation_contexts: presentation_contexts.clone(),
                                 calling_ae_title: calling_ae_title.clone(),
-                                called_ae_title,
+                                called_ae_title: called_ae_title.clone(),
                                 user_variables: vec![
                                     UserVariableItem::MaxLength(max_pdu_length),
                                     UserVariableItem::ImplementationClassUID(
@@ -1079,7 +1808,27 @@ pub mod non_blocking {
                                     UserVariableItem::ImplementationVersionName(
                                         IMPLEMENTATION_VERSION_NAME.to_string(),
                                     ),
-                                ],
+                                ]
+                                .into_iter()
+                                .chain(async_operations_window_response(
+                                    &user_variables,
+                                    self.async_operations_window,
+                                ))
+                                .chain(role_selection_response(
+                                    &user_variables,
+                                    &abstract_syntaxes,
+                                    &presentation_contexts,
+                                ))
+                                .chain(
+                                    user_identity_response
+                                        .filter(|_| {
+                                            user_identity.is_some_and(
+                                                UserIdentity::positive_response_requested,
+                                            )
+                                        })
+                                        .map(UserVariableItem::UserIdentityResponse),
+                                )
+                                .collect(),
                             }),
                         )
                         .context(SendResponseSnafu)?;
@@ -1087,14 +1836,18 @@ pub mod non_blocking {
 
                         Ok(ServerAssociation {
                             presentation_contexts,
+                            abstract_syntaxes,
                             requestor_max_pdu_length,
                             acceptor_max_pdu_length: max_pdu_length,
                             socket,
                             client_ae_title: calling_ae_title,
+                            called_ae_title,
                             buffer,
                             strict: self.strict,
                             read_buffer: BytesMut::with_capacity(MAXIMUM_PDU_SIZE as usize),
                             timeout,
+                            #[cfg(feature = "dimse")]
+                            dimse: MessageAssembler::default(),
                         })
                     }
                     Pdu::ReleaseRQ => {
@@ -1121,106 +1874,154 @@ pub mod non_blocking {
         }
     }
 
-    impl ServerAssociation<TcpStream> {
-        /// Send a PDU message to the other intervenient.
-        pub async fn send(&mut self, msg: &Pdu) -> Result<()> {
-            let timeout = self.timeout;
-            let task = async {
-                self.buffer.clear();
-                write_pdu(&mut self.buffer, msg).context(SendSnafu)?;
-                if self.buffer.len() > self.requestor_max_pdu_length as usize {
-                    return SendTooLongPduSnafu {
-                        length: self.buffer.len(),
+    /// Implement the asynchronous operations of an association acceptor
+    /// over the given stream type.
+    macro_rules! impl_async_server_association {
+        ($stream: ty) => {
+            impl ServerAssociation<$stream> {
+                /// Send a PDU message to the other intervenient.
+                pub async fn send(&mut self, msg: &Pdu) -> Result<()> {
+                    let timeout = self.timeout;
+                    let task = async {
+                        self.buffer.clear();
+                        write_pdu(&mut self.buffer, msg).context(SendSnafu)?;
+                        if self.buffer.len() > self.requestor_max_pdu_length as usize {
+                            return SendTooLongPduSnafu {
+                                length: self.buffer.len(),
+                            }
+                            .fail();
+                        }
+                        self.socket
+                            .write_all(&self.buffer)
+                            .await
+                            .context(WireSendSnafu)
+                    };
+                    if let Some(timeout) = timeout {
+                        tokio::time::timeout(timeout, task)
+                            .await
+                            .map_err(|err| std::io::Error::new(std::io::ErrorKind::TimedOut, err))
+                            .context(WireSendSnafu)?
+                    } else {
+                        task.await
                     }
-                    .fail();
                 }
-                self.socket
-                    .write_all(&self.buffer)
-                    .await
-                    .context(WireSendSnafu)
-            };
-            if let Some(timeout) = timeout {
-                tokio::time::timeout(timeout, task)
-                    .await
-                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::TimedOut, err))
-                    .context(WireSendSnafu)?
-            } else {
-                task.await
-            }
-        }
 
-        /// Read a PDU message from the other intervenient.
-        pub async fn receive(&mut self) -> Result<Pdu> {
-            let timeout = self.timeout;
-            let task = async {
-                loop {
-                    let mut buf = Cursor::new(&self.read_buffer[..]);
-                    match read_pdu(&mut buf, self.requestor_max_pdu_length, self.strict)
-                        .context(ReceiveRequestSnafu)?
+                /// Read a PDU message from the other intervenient.
+                pub async fn receive(&mut self) -> Result<Pdu> {
+                    let timeout = self.timeout;
+                    let task = async {
+                        loop {
+                            let mut buf = Cursor::new(&self.read_buffer[..]);
+                            match read_pdu(&mut buf, self.requestor_max_pdu_length, self.strict)
+                                .context(ReceiveRequestSnafu)?
+                            {
+                                Some(pdu) => {
+                                    self.read_buffer.advance(buf.position() as usize);
+                                    return Ok(pdu);
+                                }
+                                None => {
+                                    // Reset position
+                                    buf.set_position(0)
+                                }
+                            }
+                            let recv = self
+                                .socket
+                                .read_buf(&mut self.read_buffer)
+                                .await
+                                .context(ReadPduSnafu)
+                                .context(ReceiveSnafu)?;
+                            ensure!(recv > 0, ConnectionClosedSnafu);
+                        }
+                    };
+                    if let Some(timeout) = timeout {
+                        tokio::time::timeout(timeout, task)
+                            .await
+                            .map_err(|err| std::io::Error::new(std::io::ErrorKind::TimedOut, err))
+                            .context(ReadPduSnafu)
+                            .context(ReceiveSnafu)?
+                    } else {
+                        task.await
+                    }
+                }
+
+                /// Send a provider initiated abort message
+                /// and shut down the TCP connection,
+                /// terminating the association.
+                pub async fn abort(mut self) -> Result<()> {
+                    let timeout = self.timeout;
+                    let task = async {
+                        let pdu = Pdu::AbortRQ {
+                            source: AbortRQSource::ServiceProvider(
+                                AbortRQServiceProviderReason::ReasonNotSpecified,
+                            ),
+                        };
+                        let out = self.send(&pdu).await;
+                        let _ = self.socket.shutdown().await;
+                        out
+                    };
+                    if let Some(timeout) = timeout {
+                        tokio::time::timeout(timeout, task)
+                            .await
+                            .map_err(|err| std::io::Error::new(std::io::ErrorKind::TimedOut, err))
+                            .context(WireSendSnafu)?
+                    } else {
+                        task.await
+                    }
+                }
+
+                /// Send a full DIMSE message to the association requester,
+                /// split into as many P-Data PDUs as necessary.
+                #[cfg(feature = "dimse")]
+                pub async fn send_dimse(&mut self, msg: &DimseMessage) -> Result<()> {
+                    for pdu in msg
+                        .to_pdus(self.requestor_max_pdu_length)
+                        .context(DimseSnafu)?
                     {
-                        Some(pdu) => {
-                            self.read_buffer.advance(buf.position() as usize);
-                            return Ok(pdu);
+                        self.send(&pdu).await?;
+                    }
+                    Ok(())
+                }
+
+                /// Receive a full DIMSE message from the association requester.
+                ///
+                /// Fails with [`Error::UnexpectedRequest`](super::Error::UnexpectedRequest)
+                /// if a PDU other than P-Data is received,
+                /// such as a release request or an abort.
+                #[cfg(feature = "dimse")]
+                pub async fn receive_dimse(&mut self) -> Result<DimseMessage> {
+                    loop {
+                        if let Some(msg) = self.dimse.pop_message() {
+                            return Ok(msg);
                         }
-                        None => {
-                            // Reset position
-                            buf.set_position(0)
+                        match self.receive().await? {
+                            Pdu::PData { data } => {
+                                self.dimse.push_values(data).context(DimseSnafu)?
+                            }
+                            pdu => return UnexpectedRequestSnafu { pdu }.fail(),
                         }
                     }
-                    let recv = self
-                        .socket
-                        .read_buf(&mut self.read_buffer)
-                        .await
-                        .context(ReadPduSnafu)
-                        .context(ReceiveSnafu)?;
-                    ensure!(recv > 0, ConnectionClosedSnafu);
                 }
-            };
-            if let Some(timeout) = timeout {
-                tokio::time::timeout(timeout, task)
-                    .await
-                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::TimedOut, err))
-                    .context(ReadPduSnafu)
-                    .context(ReceiveSnafu)?
-            } else {
-                task.await
-            }
-        }
 
-        /// Send a provider initiated abort message
-        /// and shut down the TCP connection,
-        /// terminating the association.
-        pub async fn abort(mut self) -> Result<()> {
-            let timeout = self.timeout;
-            let task = async {
-                let pdu = Pdu::AbortRQ {
-                    source: AbortRQSource::ServiceProvider(
-                        AbortRQServiceProviderReason::ReasonNotSpecified,
-                    ),
-                };
-                let out = self.send(&pdu).await;
-                let _ = self.socket.shutdown().await;
-                out
-            };
-            if let Some(timeout) = timeout {
-                tokio::time::timeout(timeout, task)
-                    .await
-                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::TimedOut, err))
-                    .context(WireSendSnafu)?
-            } else {
-                task.await
+                /// Obtain access to the inner stream
+                /// connected to the association requester.
+                pub fn inner_stream(&mut self) -> &mut $stream {
+                    &mut self.socket
+                }
             }
-        }
-
-        pub fn inner_stream(&mut self) -> &mut TcpStream {
-            &mut self.socket
-        }
+        };
     }
+
+    impl_async_server_association!(TcpStream);
+    impl_async_server_association!(tokio::io::DuplexStream);
+    #[cfg(unix)]
+    impl_async_server_association!(tokio::net::UnixStream);
+    #[cfg(feature = "async-tls")]
+    impl_async_server_association!(AsyncTlsServerStream);
 }
 
 #[cfg(test)]
 mod tests {
-    use super::choose_supported;
+    use super::{choose_supported, ServerAssociationOptions};
 
     #[test]
     fn test_choose_supported() {
@@ -1241,4 +2042,60 @@ mod tests {
             Some("1.2.840.10008.1.2.1".to_string()),
         );
     }
+
+    #[test]
+    fn test_choose_ts_with_preferences() {
+        const CT_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.2";
+        const MG_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.1.2";
+        const SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.11";
+
+        let options = ServerAssociationOptions::new()
+            .with_transfer_syntax("1.2.840.10008.1.2")
+            .with_transfer_syntax("1.2.840.10008.1.2.1")
+            .with_transfer_syntax("1.2.840.10008.1.2.4.50")
+            .with_transfer_syntax_preference(
+                CT_IMAGE_STORAGE,
+                ["1.2.840.10008.1.2.4.90", "1.2.840.10008.1.2.1\0"],
+            )
+            .with_transfer_syntax_preference(MG_IMAGE_STORAGE, ["1.2.840.10008.1.2.1"])
+            .with_transfer_syntax_preference(
+                MG_IMAGE_STORAGE,
+                ["1.2.840.10008.1.2.4.90", "1.2.840.10008.1.2.1"],
+            );
+
+        // the preferred transfer syntax wins over the requester's order
+        assert_eq!(
+            options.choose_ts(
+                CT_IMAGE_STORAGE,
+                ["1.2.840.10008.1.2.1", "1.2.840.10008.1.2.4.90"]
+            ),
+            Some("1.2.840.10008.1.2.4.90"),
+        );
+        assert_eq!(
+            options.choose_ts(
+                CT_IMAGE_STORAGE,
+                ["1.2.840.10008.1.2", "1.2.840.10008.1.2.1\0"]
+            ),
+            Some("1.2.840.10008.1.2.1\0"),
+        );
+        // lossy transfer syntaxes are rejected
+        // even though they are accepted for other abstract syntaxes
+        assert_eq!(
+            options.choose_ts(MG_IMAGE_STORAGE, ["1.2.840.10008.1.2.4.50"]),
+            None,
+        );
+        // the last preferences for an abstract syntax apply
+        assert_eq!(
+            options.choose_ts(
+                MG_IMAGE_STORAGE,
+                ["1.2.840.10008.1.2.1", "1.2.840.10008.1.2.4.90"]
+            ),
+            Some("1.2.840.10008.1.2.4.90"),
+        );
+        // other abstract syntaxes follow the options' list
+        assert_eq!(
+            options.choose_ts(SR_STORAGE, ["1.2.840.10008.1.2.4.90", "1.2.840.10008.1.2"]),
+            Some("1.2.840.10008.1.2"),
+        );
+    }
 }
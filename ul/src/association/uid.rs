@@ -1,7 +1,23 @@
-//! Private utility module for working with UIDs
+//! Utility module for working with UIDs and other padded values
 
 use std::borrow::Cow;
 
+/// Remove the trailing padding of a value received from another DICOM node,
+/// such as the null character ending a UID
+/// or the space ending an AE title.
+///
+/// # Example
+///
+/// ```
+/// # use dicom_ul::association::trim_padding;
+/// assert_eq!(trim_padding("1.2.840.10008.1.1\0"), "1.2.840.10008.1.1");
+/// assert_eq!(trim_padding("STORE-SCP "), "STORE-SCP");
+/// assert_eq!(trim_padding(" STORE-SCP"), " STORE-SCP");
+/// ```
+pub fn trim_padding(value: &str) -> &str {
+    value.trim_end_matches(['\0', ' '])
+}
+
 pub(crate) fn trim_uid(uid: Cow<str>) -> Cow<str> {
     if uid.ends_with('\0') {
         Cow::Owned(
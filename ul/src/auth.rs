@@ -0,0 +1,1063 @@
+//! Verification of the user identity of association requesters.
+//!
+//! An association requester may present a user identity
+//! (a username, optionally with a password,
+//! a Kerberos service ticket, a SAML assertion, or a JSON Web Token)
+//! in the user identity negotiation sub-item of the association request,
+//! as described in PS3.7, Annex D.3.3.7.
+//! An [`Authenticator`] verifies these identities,
+//! and [`RequireAuthentication`] turns an authenticator
+//! into an [access control policy](crate::association::server::AccessControl)
+//! which rejects association requests without a valid user identity.
+//! When the requester asks for a positive response,
+//! the server response produced by the authenticator
+//! is sent back in the association acknowledgement.
+//!
+//! The following authenticators are provided:
+//!
+//! - [`PasswordAuthenticator`] checks usernames and passwords
+//!   against a table of known users;
+//! - `JwtAuthenticator` (requires the `jwt` feature)
+//!   verifies JSON Web Tokens against a set of keys;
+//! - `SamlAuthenticator` (requires the `saml` feature)
+//!   checks the conditions of SAML 2.0 assertions.
+//!
+//! Authenticators can be combined with [`Authenticator::or`],
+//! so that each kind of identity is handled by the respective authenticator.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_ul::auth::{Authenticator, PasswordAuthenticator, RequireAuthentication};
+//! # #[cfg(feature = "dimse")]
+//! # use dicom_ul::scp::{AcceptEcho, ServiceClassProvider};
+//! # #[cfg(feature = "dimse")]
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let passwords = PasswordAuthenticator::new()
+//!     .with_user("modality", "s3cr3t")
+//!     .with_user("viewer", "hunter2");
+//!
+//! let scp = ServiceClassProvider::new()
+//!     .ae_title("STORE-SCP")
+//!     .with_echo_handler(AcceptEcho)
+//!     .ae_access_control(RequireAuthentication::new(passwords));
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use snafu::{ResultExt, Snafu};
+use tracing::{debug, warn};
+
+use crate::association::server::{AcceptAny, AccessControl};
+use crate::pdu::{AssociationRJServiceUserReason, UserIdentity, UserIdentityType};
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[snafu(display("User identity type {:?} is not supported", identity_type))]
+    UnsupportedIdentityType { identity_type: UserIdentityType },
+    #[snafu(display("User identity is not valid UTF-8"))]
+    InvalidText { source: std::str::Utf8Error },
+    #[snafu(display("Unknown user {:?}", username))]
+    UnknownUser { username: String },
+    #[snafu(display("No password was given for user {:?}", username))]
+    MissingPassword { username: String },
+    #[snafu(display("Wrong password for user {:?}", username))]
+    WrongPassword { username: String },
+    #[cfg(feature = "jwt")]
+    #[snafu(display("Could not read JWT verification key"))]
+    ReadKey { source: jsonwebtoken::errors::Error },
+    #[cfg(feature = "jwt")]
+    #[snafu(display("Invalid JSON Web Token"))]
+    InvalidToken { source: jsonwebtoken::errors::Error },
+    #[cfg(feature = "jwt")]
+    #[snafu(display("No key to verify JSON Web Token signed with {:?}", algorithm))]
+    NoMatchingKey { algorithm: jsonwebtoken::Algorithm },
+    #[cfg(feature = "jwt")]
+    #[snafu(display("JSON Web Token has no subject"))]
+    MissingSubject,
+    #[cfg(feature = "saml")]
+    #[snafu(display("Could not parse SAML assertion"))]
+    ParseAssertion { source: roxmltree::Error },
+    #[cfg(feature = "saml")]
+    #[snafu(display("Invalid SAML assertion: {}", reason))]
+    InvalidAssertion { reason: &'static str },
+    #[cfg(feature = "saml")]
+    #[snafu(display("Signature of SAML assertion could not be verified"))]
+    InvalidSignature,
+    #[cfg(feature = "saml")]
+    #[snafu(display("SAML assertion issuer {:?} is not trusted", issuer))]
+    UntrustedIssuer { issuer: String },
+    #[cfg(feature = "saml")]
+    #[snafu(display("SAML assertion is not meant for this node"))]
+    AudienceMismatch,
+    #[cfg(feature = "saml")]
+    #[snafu(display("SAML assertion is not valid at this time"))]
+    AssertionNotValidNow,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The outcome of a successful authentication.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Authenticated {
+    /// The name of the authenticated user.
+    pub user: String,
+    /// The server response to send back to the requester,
+    /// if it asked for a positive response.
+    pub server_response: Vec<u8>,
+}
+
+impl Authenticated {
+    /// Create the outcome of authenticating the given user,
+    /// with an empty server response.
+    pub fn new(user: impl Into<String>) -> Self {
+        Authenticated {
+            user: user.into(),
+            server_response: Vec::new(),
+        }
+    }
+
+    /// Set the server response to send back to the requester.
+    pub fn with_server_response(mut self, server_response: impl Into<Vec<u8>>) -> Self {
+        self.server_response = server_response.into();
+        self
+    }
+}
+
+/// Common interface for verifying the user identity
+/// presented by an association requester.
+pub trait Authenticator {
+    /// Verify the user identity presented by the node
+    /// with the given calling AE title.
+    ///
+    /// Implementations should fail with [`Error::UnsupportedIdentityType`]
+    /// for the kinds of identity which they do not handle,
+    /// so that they can be combined with [`or`](Self::or).
+    fn authenticate(
+        &self,
+        calling_ae_title: &str,
+        user_identity: &UserIdentity,
+    ) -> Result<Authenticated>;
+
+    /// Combine this authenticator with another one,
+    /// which is used for the user identity types
+    /// not supported by this one.
+    fn or<B>(self, other: B) -> Or<Self, B>
+    where
+        Self: Sized,
+        B: Authenticator,
+    {
+        Or(self, other)
+    }
+}
+
+impl<T> Authenticator for Box<T>
+where
+    T: Authenticator + ?Sized,
+{
+    fn authenticate(
+        &self,
+        calling_ae_title: &str,
+        user_identity: &UserIdentity,
+    ) -> Result<Authenticated> {
+        (**self).authenticate(calling_ae_title, user_identity)
+    }
+}
+
+impl<T> Authenticator for Arc<T>
+where
+    T: Authenticator + ?Sized,
+{
+    fn authenticate(
+        &self,
+        calling_ae_title: &str,
+        user_identity: &UserIdentity,
+    ) -> Result<Authenticated> {
+        (**self).authenticate(calling_ae_title, user_identity)
+    }
+}
+
+/// A combination of two authenticators,
+/// created with [`Authenticator::or`].
+#[derive(Debug, Clone)]
+pub struct Or<A, B>(A, B);
+
+impl<A, B> Authenticator for Or<A, B>
+where
+    A: Authenticator,
+    B: Authenticator,
+{
+    fn authenticate(
+        &self,
+        calling_ae_title: &str,
+        user_identity: &UserIdentity,
+    ) -> Result<Authenticated> {
+        match self.0.authenticate(calling_ae_title, user_identity) {
+            Err(Error::UnsupportedIdentityType { .. }) => {
+                self.1.authenticate(calling_ae_title, user_identity)
+            }
+            outcome => outcome,
+        }
+    }
+}
+
+/// An authenticator of usernames and passwords
+/// against a table of known users.
+///
+/// Identities with a username only are rejected,
+/// unless [`accept_username_only`](Self::accept_username_only) is set.
+#[derive(Clone, Default)]
+pub struct PasswordAuthenticator {
+    users: HashMap<String, String>,
+    accept_username_only: bool,
+}
+
+impl fmt::Debug for PasswordAuthenticator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // do not reveal the passwords
+        f.debug_struct("PasswordAuthenticator")
+            .field("users", &self.users.keys().collect::<Vec<_>>())
+            .field("accept_username_only", &self.accept_username_only)
+            .finish()
+    }
+}
+
+impl PasswordAuthenticator {
+    /// Create an authenticator without any known users.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a known user with the given password.
+    pub fn with_user(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.users.insert(username.into(), password.into());
+        self
+    }
+
+    /// Whether to accept known users
+    /// which present their username without a password.
+    ///
+    /// This only identifies the user,
+    /// and should not be relied upon for security.
+    pub fn accept_username_only(mut self, accept: bool) -> Self {
+        self.accept_username_only = accept;
+        self
+    }
+}
+
+impl Authenticator for PasswordAuthenticator {
+    fn authenticate(
+        &self,
+        _calling_ae_title: &str,
+        user_identity: &UserIdentity,
+    ) -> Result<Authenticated> {
+        let identity_type = user_identity.identity_type();
+        let primary_field = user_identity.primary_field();
+        let username = std::str::from_utf8(&primary_field).context(InvalidTextSnafu)?;
+        let known_password = self.users.get(username).ok_or_else(|| Error::UnknownUser {
+            username: username.to_string(),
+        });
+
+        match identity_type {
+            UserIdentityType::Username => {
+                known_password?;
+                snafu::ensure!(self.accept_username_only, MissingPasswordSnafu { username });
+            }
+            UserIdentityType::UsernamePassword => {
+                let known_password = known_password?;
+                snafu::ensure!(
+                    constant_time_eq(known_password.as_bytes(), &user_identity.secondary_field()),
+                    WrongPasswordSnafu { username }
+                );
+            }
+            identity_type => return UnsupportedIdentityTypeSnafu { identity_type }.fail(),
+        }
+        Ok(Authenticated::new(username))
+    }
+}
+
+/// Compare two secrets in time independent of their contents.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// An access control policy which only accepts association requests
+/// with a user identity verified by the given authenticator.
+///
+/// The request is also subject to another access control policy,
+/// which accepts any node by default.
+/// Requests are rejected with no reason given
+/// when the user identity is missing or invalid,
+/// and the reason is logged.
+#[derive(Debug, Clone)]
+pub struct RequireAuthentication<Au, A = AcceptAny> {
+    authenticator: Au,
+    access_control: A,
+}
+
+impl<Au> RequireAuthentication<Au>
+where
+    Au: Authenticator,
+{
+    /// Require the user identity to be verified by the given authenticator.
+    pub fn new(authenticator: Au) -> Self {
+        RequireAuthentication {
+            authenticator,
+            access_control: AcceptAny,
+        }
+    }
+}
+
+impl<Au, A> RequireAuthentication<Au, A> {
+    /// Also subject association requests to the given access control policy.
+    pub fn with_access_control<P>(self, access_control: P) -> RequireAuthentication<Au, P>
+    where
+        P: AccessControl,
+    {
+        RequireAuthentication {
+            authenticator: self.authenticator,
+            access_control,
+        }
+    }
+}
+
+impl<Au, A> AccessControl for RequireAuthentication<Au, A>
+where
+    Au: Authenticator,
+    A: AccessControl,
+{
+    fn check_access(
+        &self,
+        this_ae_title: &str,
+        calling_ae_title: &str,
+        called_ae_title: &str,
+        user_identity: Option<&UserIdentity>,
+    ) -> Result<(), AssociationRJServiceUserReason> {
+        self.grant_access(
+            this_ae_title,
+            calling_ae_title,
+            called_ae_title,
+            user_identity,
+        )
+        .map(|_| ())
+    }
+
+    fn grant_access(
+        &self,
+        this_ae_title: &str,
+        calling_ae_title: &str,
+        called_ae_title: &str,
+        user_identity: Option<&UserIdentity>,
+    ) -> Result<Option<Vec<u8>>, AssociationRJServiceUserReason> {
+        self.access_control.check_access(
+            this_ae_title,
+            calling_ae_title,
+            called_ae_title,
+            user_identity,
+        )?;
+
+        let user_identity = user_identity.ok_or_else(|| {
+            warn!(
+                "Rejecting association from {}: no user identity",
+                calling_ae_title
+            );
+            AssociationRJServiceUserReason::NoReasonGiven
+        })?;
+        match self
+            .authenticator
+            .authenticate(calling_ae_title, user_identity)
+        {
+            Ok(authenticated) => {
+                debug!(
+                    "Authenticated {} as user {:?}",
+                    calling_ae_title, authenticated.user
+                );
+                Ok(Some(authenticated.server_response))
+            }
+            Err(e) => {
+                warn!(
+                    "Rejecting association from {}: {}",
+                    calling_ae_title,
+                    snafu::Report::from_error(e)
+                );
+                Err(AssociationRJServiceUserReason::NoReasonGiven)
+            }
+        }
+    }
+
+    fn check_abstract_syntax(&self, calling_ae_title: &str, abstract_syntax_uid: &str) -> bool {
+        self.access_control
+            .check_abstract_syntax(calling_ae_title, abstract_syntax_uid)
+    }
+}
+
+#[cfg(feature = "jwt")]
+pub use self::jwt::JwtAuthenticator;
+
+#[cfg(feature = "jwt")]
+mod jwt {
+    use std::fmt;
+
+    use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+    use snafu::{OptionExt, ResultExt};
+
+    use super::{
+        Authenticated, Authenticator, InvalidTextSnafu, InvalidTokenSnafu, MissingSubjectSnafu,
+        NoMatchingKeySnafu, ReadKeySnafu, Result, UnsupportedIdentityTypeSnafu,
+    };
+    use crate::pdu::{UserIdentity, UserIdentityType};
+
+    const HMAC: &[Algorithm] = &[Algorithm::HS256, Algorithm::HS384, Algorithm::HS512];
+    const RSA: &[Algorithm] = &[
+        Algorithm::RS256,
+        Algorithm::RS384,
+        Algorithm::RS512,
+        Algorithm::PS256,
+        Algorithm::PS384,
+        Algorithm::PS512,
+    ];
+    const EC: &[Algorithm] = &[Algorithm::ES256, Algorithm::ES384];
+    const ED: &[Algorithm] = &[Algorithm::EdDSA];
+
+    #[derive(Clone)]
+    struct Key {
+        id: Option<String>,
+        key: DecodingKey,
+        algorithms: &'static [Algorithm],
+    }
+
+    /// An authenticator of JSON Web Tokens,
+    /// verified locally against a set of keys.
+    ///
+    /// The token's signature, expiration time and "not before" time
+    /// are always checked,
+    /// and so are its issuer and audience if configured.
+    /// The authenticated user is the subject (`sub`) of the token.
+    #[derive(Clone, Default)]
+    pub struct JwtAuthenticator {
+        keys: Vec<Key>,
+        issuers: Vec<String>,
+        audiences: Vec<String>,
+        leeway: u64,
+    }
+
+    impl fmt::Debug for JwtAuthenticator {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("JwtAuthenticator")
+                .field(
+                    "keys",
+                    &self.keys.iter().map(|key| &key.id).collect::<Vec<_>>(),
+                )
+                .field("issuers", &self.issuers)
+                .field("audiences", &self.audiences)
+                .field("leeway", &self.leeway)
+                .finish()
+        }
+    }
+
+    impl JwtAuthenticator {
+        /// Create an authenticator without any keys.
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Accept tokens signed with HMAC using the given shared secret.
+        pub fn with_hmac_secret(mut self, secret: &[u8]) -> Self {
+            self.keys.push(Key {
+                id: None,
+                key: DecodingKey::from_secret(secret),
+                algorithms: HMAC,
+            });
+            self
+        }
+
+        /// Accept tokens signed with the private key
+        /// matching the given public key in PEM format,
+        /// which may be an RSA, elliptic curve, or Ed25519 key.
+        pub fn with_public_key_pem(mut self, pem: &[u8]) -> Result<Self> {
+            let key = DecodingKey::from_rsa_pem(pem)
+                .map(|key| (key, RSA))
+                .or_else(|_| DecodingKey::from_ec_pem(pem).map(|key| (key, EC)))
+                .or_else(|_| DecodingKey::from_ed_pem(pem).map(|key| (key, ED)))
+                .context(ReadKeySnafu)?;
+            self.keys.push(Key {
+                id: None,
+                key: key.0,
+                algorithms: key.1,
+            });
+            Ok(self)
+        }
+
+        /// Identify the key added last with the given key ID,
+        /// so that tokens naming a key ID (`kid`)
+        /// are only verified against the key with that ID.
+        pub fn key_id(mut self, id: impl Into<String>) -> Self {
+            if let Some(key) = self.keys.last_mut() {
+                key.id = Some(id.into());
+            }
+            self
+        }
+
+        /// Only accept tokens from the given issuer (`iss`).
+        ///
+        /// Can be called more than once to accept several issuers.
+        pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+            self.issuers.push(issuer.into());
+            self
+        }
+
+        /// Only accept tokens meant for the given audience (`aud`).
+        ///
+        /// Can be called more than once to accept several audiences.
+        pub fn audience(mut self, audience: impl Into<String>) -> Self {
+            self.audiences.push(audience.into());
+            self
+        }
+
+        /// Set the clock skew tolerated when checking the token's time claims,
+        /// in seconds.
+        pub fn leeway(mut self, seconds: u64) -> Self {
+            self.leeway = seconds;
+            self
+        }
+
+        fn validation(&self, algorithm: Algorithm) -> Validation {
+            let mut validation = Validation::new(algorithm);
+            validation.leeway = self.leeway;
+            validation.validate_nbf = true;
+            if !self.issuers.is_empty() {
+                validation.set_issuer(&self.issuers);
+            }
+            if self.audiences.is_empty() {
+                validation.validate_aud = false;
+            } else {
+                validation.set_audience(&self.audiences);
+            }
+            validation
+        }
+    }
+
+    impl Authenticator for JwtAuthenticator {
+        fn authenticate(
+            &self,
+            _calling_ae_title: &str,
+            user_identity: &UserIdentity,
+        ) -> Result<Authenticated> {
+            let identity_type = user_identity.identity_type();
+            if identity_type != UserIdentityType::Jwt {
+                return UnsupportedIdentityTypeSnafu { identity_type }.fail();
+            }
+            let primary_field = user_identity.primary_field();
+            let token = std::str::from_utf8(&primary_field).context(InvalidTextSnafu)?;
+            let header = jsonwebtoken::decode_header(token).context(InvalidTokenSnafu)?;
+            let algorithm = header.alg;
+
+            let mut outcome = None;
+            for key in self.keys.iter().filter(|key| {
+                key.algorithms.contains(&algorithm)
+                    && (header.kid.is_none() || key.id.is_none() || key.id == header.kid)
+            }) {
+                let result = jsonwebtoken::decode::<serde_json::Map<String, serde_json::Value>>(
+                    token,
+                    &key.key,
+                    &self.validation(algorithm),
+                );
+                match result {
+                    Ok(data) => {
+                        outcome = Some(Ok(data.claims));
+                        break;
+                    }
+                    Err(e) => outcome = Some(Err(e)),
+                }
+            }
+            let claims = outcome
+                .context(NoMatchingKeySnafu { algorithm })?
+                .context(InvalidTokenSnafu)?;
+
+            let subject = claims
+                .get("sub")
+                .and_then(|sub| sub.as_str())
+                .context(MissingSubjectSnafu)?;
+            Ok(Authenticated::new(subject))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::auth::Error;
+        use jsonwebtoken::{encode, EncodingKey, Header};
+        use serde_json::json;
+
+        fn jwt_identity(token: &str) -> UserIdentity {
+            UserIdentity::new(
+                true,
+                UserIdentityType::Jwt,
+                token.as_bytes().to_vec(),
+                Vec::new(),
+            )
+        }
+
+        fn token(claims: serde_json::Value, secret: &[u8]) -> String {
+            encode(
+                &Header::default(),
+                &claims,
+                &EncodingKey::from_secret(secret),
+            )
+            .unwrap()
+        }
+
+        fn now() -> u64 {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        }
+
+        #[test]
+        fn verify_tokens() {
+            let authenticator = JwtAuthenticator::new()
+                .with_hmac_secret(b"shared secret")
+                .issuer("https://idp.example.org")
+                .audience("STORE-SCP");
+            let claims = json!({
+                "sub": "modality",
+                "iss": "https://idp.example.org",
+                "aud": "STORE-SCP",
+                "exp": now() + 600,
+            });
+
+            let authenticated = authenticator
+                .authenticate(
+                    "CT",
+                    &jwt_identity(&token(claims.clone(), b"shared secret")),
+                )
+                .unwrap();
+            assert_eq!(authenticated, Authenticated::new("modality"));
+
+            // signed with another key
+            assert!(matches!(
+                authenticator.authenticate("CT", &jwt_identity(&token(claims.clone(), b"guess"))),
+                Err(Error::InvalidToken { .. })
+            ));
+
+            // expired
+            let mut expired = claims.clone();
+            expired["exp"] = json!(now() - 600);
+            assert!(matches!(
+                authenticator.authenticate("CT", &jwt_identity(&token(expired, b"shared secret"))),
+                Err(Error::InvalidToken { .. })
+            ));
+
+            // meant for another node
+            let mut elsewhere = claims;
+            elsewhere["aud"] = json!("OTHER-SCP");
+            assert!(matches!(
+                authenticator
+                    .authenticate("CT", &jwt_identity(&token(elsewhere, b"shared secret"))),
+                Err(Error::InvalidToken { .. })
+            ));
+
+            // not a token
+            assert!(matches!(
+                authenticator.authenticate("CT", &jwt_identity("not.a.token")),
+                Err(Error::InvalidToken { .. })
+            ));
+        }
+    }
+}
+
+#[cfg(feature = "saml")]
+pub use self::saml::SamlAuthenticator;
+
+#[cfg(feature = "saml")]
+mod saml {
+    use std::fmt;
+    use std::sync::Arc;
+
+    use chrono::{DateTime, Duration, Utc};
+    use roxmltree::{Document, Node};
+    use snafu::{ensure, OptionExt, ResultExt};
+
+    use super::{
+        AssertionNotValidNowSnafu, AudienceMismatchSnafu, Authenticated, Authenticator,
+        InvalidAssertionSnafu, InvalidSignatureSnafu, InvalidTextSnafu, ParseAssertionSnafu,
+        Result, UnsupportedIdentityTypeSnafu, UntrustedIssuerSnafu,
+    };
+    use crate::pdu::{UserIdentity, UserIdentityType};
+
+    const SAML_NS: &str = "urn:oasis:names:tc:SAML:2.0:assertion";
+
+    type SignatureVerifier = dyn Fn(&str) -> bool + Send + Sync;
+
+    /// An authenticator of SAML 2.0 assertions.
+    ///
+    /// XML signatures are not verified by this crate:
+    /// the authenticator is created with a function
+    /// which verifies the signature of the whole assertion document.
+    /// The authenticator then checks the assertion's
+    /// issuer, audience, and validity period,
+    /// and the authenticated user is the name ID of its subject.
+    #[derive(Clone)]
+    pub struct SamlAuthenticator {
+        verify_signature: Arc<SignatureVerifier>,
+        issuers: Vec<String>,
+        audiences: Vec<String>,
+        clock_skew: Duration,
+    }
+
+    impl fmt::Debug for SamlAuthenticator {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("SamlAuthenticator")
+                .field("issuers", &self.issuers)
+                .field("audiences", &self.audiences)
+                .field("clock_skew", &self.clock_skew)
+                .finish()
+        }
+    }
+
+    impl SamlAuthenticator {
+        /// Create an authenticator which accepts the assertions
+        /// for which the given function verifies the signature.
+        pub fn new<F>(verify_signature: F) -> Self
+        where
+            F: Fn(&str) -> bool + Send + Sync + 'static,
+        {
+            SamlAuthenticator {
+                verify_signature: Arc::new(verify_signature),
+                issuers: Vec::new(),
+                audiences: Vec::new(),
+                clock_skew: Duration::zero(),
+            }
+        }
+
+        /// Only accept assertions from the given issuer.
+        ///
+        /// Can be called more than once to accept several issuers.
+        pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+            self.issuers.push(issuer.into());
+            self
+        }
+
+        /// Only accept assertions restricted to the given audience.
+        ///
+        /// Can be called more than once to accept several audiences.
+        pub fn audience(mut self, audience: impl Into<String>) -> Self {
+            self.audiences.push(audience.into());
+            self
+        }
+
+        /// Set the clock skew tolerated when checking
+        /// the assertion's validity period, in seconds.
+        pub fn clock_skew(mut self, seconds: u32) -> Self {
+            self.clock_skew = Duration::seconds(seconds.into());
+            self
+        }
+
+        fn check_conditions(&self, conditions: Node) -> Result<()> {
+            let now = Utc::now();
+            if let Some(not_before) = conditions.attribute("NotBefore") {
+                ensure!(
+                    parse_time(not_before)? - self.clock_skew <= now,
+                    AssertionNotValidNowSnafu
+                );
+            }
+            if let Some(not_on_or_after) = conditions.attribute("NotOnOrAfter") {
+                ensure!(
+                    now < parse_time(not_on_or_after)? + self.clock_skew,
+                    AssertionNotValidNowSnafu
+                );
+            }
+            if !self.audiences.is_empty() {
+                // every audience restriction must name one of the audiences
+                for restriction in children(conditions, "AudienceRestriction") {
+                    ensure!(
+                        children(restriction, "Audience").any(|audience| audience
+                            .text()
+                            .is_some_and(|text| self.audiences.iter().any(|a| a == text.trim()))),
+                        AudienceMismatchSnafu
+                    );
+                }
+                ensure!(
+                    children(conditions, "AudienceRestriction").next().is_some(),
+                    AudienceMismatchSnafu
+                );
+            }
+            Ok(())
+        }
+    }
+
+    fn children<'a, 'input>(
+        node: Node<'a, 'input>,
+        name: &'static str,
+    ) -> impl Iterator<Item = Node<'a, 'input>> {
+        node.children()
+            .filter(move |child| child.has_tag_name((SAML_NS, name)))
+    }
+
+    fn parse_time(text: &str) -> Result<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(text.trim())
+            .map(|time| time.with_timezone(&Utc))
+            .ok()
+            .context(InvalidAssertionSnafu {
+                reason: "invalid time in conditions",
+            })
+    }
+
+    impl Authenticator for SamlAuthenticator {
+        fn authenticate(
+            &self,
+            _calling_ae_title: &str,
+            user_identity: &UserIdentity,
+        ) -> Result<Authenticated> {
+            let identity_type = user_identity.identity_type();
+            if identity_type != UserIdentityType::SamlAssertion {
+                return UnsupportedIdentityTypeSnafu { identity_type }.fail();
+            }
+            let primary_field = user_identity.primary_field();
+            let text = std::str::from_utf8(&primary_field).context(InvalidTextSnafu)?;
+            let document = Document::parse(text).context(ParseAssertionSnafu)?;
+            let assertion = document
+                .descendants()
+                .find(|node| node.has_tag_name((SAML_NS, "Assertion")))
+                .context(InvalidAssertionSnafu {
+                    reason: "no assertion",
+                })?;
+
+            ensure!((self.verify_signature)(text), InvalidSignatureSnafu);
+
+            let issuer = children(assertion, "Issuer")
+                .next()
+                .and_then(|issuer| issuer.text())
+                .map(str::trim)
+                .context(InvalidAssertionSnafu {
+                    reason: "no issuer",
+                })?;
+            ensure!(
+                self.issuers.is_empty() || self.issuers.iter().any(|i| i == issuer),
+                UntrustedIssuerSnafu { issuer }
+            );
+
+            match children(assertion, "Conditions").next() {
+                Some(conditions) => self.check_conditions(conditions)?,
+                None => ensure!(self.audiences.is_empty(), AudienceMismatchSnafu),
+            }
+
+            let name_id = children(assertion, "Subject")
+                .flat_map(|subject| children(subject, "NameID"))
+                .next()
+                .and_then(|name_id| name_id.text())
+                .map(str::trim)
+                .context(InvalidAssertionSnafu {
+                    reason: "no subject name ID",
+                })?;
+            Ok(Authenticated::new(name_id))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::auth::Error;
+
+        fn assertion(issuer: &str, audience: &str, not_on_or_after: DateTime<Utc>) -> String {
+            format!(
+                r#"<saml:Assertion xmlns:saml="urn:oasis:names:tc:SAML:2.0:assertion" ID="a1" Version="2.0" IssueInstant="2024-01-01T00:00:00Z">
+  <saml:Issuer>{}</saml:Issuer>
+  <saml:Subject>
+    <saml:NameID>radiologist</saml:NameID>
+  </saml:Subject>
+  <saml:Conditions NotBefore="2024-01-01T00:00:00Z" NotOnOrAfter="{}">
+    <saml:AudienceRestriction>
+      <saml:Audience>{}</saml:Audience>
+    </saml:AudienceRestriction>
+  </saml:Conditions>
+</saml:Assertion>"#,
+                issuer,
+                not_on_or_after.to_rfc3339(),
+                audience
+            )
+        }
+
+        fn saml_identity(assertion: &str) -> UserIdentity {
+            UserIdentity::new(
+                false,
+                UserIdentityType::SamlAssertion,
+                assertion.as_bytes().to_vec(),
+                Vec::new(),
+            )
+        }
+
+        #[test]
+        fn check_assertions() {
+            let authenticator = SamlAuthenticator::new(|_| true)
+                .issuer("https://idp.example.org")
+                .audience("STORE-SCP");
+            let later = Utc::now() + Duration::hours(1);
+
+            let valid = assertion("https://idp.example.org", "STORE-SCP", later);
+            let authenticated = authenticator
+                .authenticate("CT", &saml_identity(&valid))
+                .unwrap();
+            assert_eq!(authenticated, Authenticated::new("radiologist"));
+
+            assert!(matches!(
+                authenticator.authenticate(
+                    "CT",
+                    &saml_identity(&assertion("https://evil.example.org", "STORE-SCP", later))
+                ),
+                Err(Error::UntrustedIssuer { .. })
+            ));
+            assert!(matches!(
+                authenticator.authenticate(
+                    "CT",
+                    &saml_identity(&assertion("https://idp.example.org", "OTHER-SCP", later))
+                ),
+                Err(Error::AudienceMismatch)
+            ));
+            assert!(matches!(
+                authenticator.authenticate(
+                    "CT",
+                    &saml_identity(&assertion(
+                        "https://idp.example.org",
+                        "STORE-SCP",
+                        Utc::now() - Duration::hours(1)
+                    ))
+                ),
+                Err(Error::AssertionNotValidNow)
+            ));
+
+            let unsigned = SamlAuthenticator::new(|_| false);
+            assert!(matches!(
+                unsigned.authenticate("CT", &saml_identity(&valid)),
+                Err(Error::InvalidSignature)
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn password_identity(username: &str, password: &str) -> UserIdentity {
+        UserIdentity::new(
+            true,
+            UserIdentityType::UsernamePassword,
+            username.as_bytes().to_vec(),
+            password.as_bytes().to_vec(),
+        )
+    }
+
+    #[test]
+    fn check_passwords() {
+        let authenticator = PasswordAuthenticator::new().with_user("modality", "s3cr3t");
+
+        let authenticated = authenticator
+            .authenticate("CT", &password_identity("modality", "s3cr3t"))
+            .unwrap();
+        assert_eq!(authenticated, Authenticated::new("modality"));
+
+        assert!(matches!(
+            authenticator.authenticate("CT", &password_identity("modality", "guess")),
+            Err(Error::WrongPassword { .. })
+        ));
+        assert!(matches!(
+            authenticator.authenticate("CT", &password_identity("intruder", "s3cr3t")),
+            Err(Error::UnknownUser { .. })
+        ));
+
+        let username_only = UserIdentity::new(
+            false,
+            UserIdentityType::Username,
+            b"modality".to_vec(),
+            Vec::new(),
+        );
+        assert!(matches!(
+            authenticator.authenticate("CT", &username_only),
+            Err(Error::MissingPassword { .. })
+        ));
+        assert!(authenticator
+            .accept_username_only(true)
+            .authenticate("CT", &username_only)
+            .is_ok());
+    }
+
+    /// An authenticator of Kerberos service tickets for testing,
+    /// which accepts any ticket and responds with a fixed server ticket.
+    struct AnyTicket;
+
+    impl Authenticator for AnyTicket {
+        fn authenticate(
+            &self,
+            _calling_ae_title: &str,
+            user_identity: &UserIdentity,
+        ) -> Result<Authenticated> {
+            let identity_type = user_identity.identity_type();
+            if identity_type != UserIdentityType::KerberosServiceTicket {
+                return UnsupportedIdentityTypeSnafu { identity_type }.fail();
+            }
+            Ok(Authenticated::new("kerberos-user").with_server_response(b"server ticket".to_vec()))
+        }
+    }
+
+    #[test]
+    fn require_authentication() {
+        let access_control = RequireAuthentication::new(
+            PasswordAuthenticator::new()
+                .with_user("modality", "s3cr3t")
+                .or(AnyTicket),
+        );
+
+        assert_eq!(
+            access_control.grant_access(
+                "STORE-SCP",
+                "CT",
+                "STORE-SCP",
+                Some(&password_identity("modality", "s3cr3t"))
+            ),
+            Ok(Some(Vec::new()))
+        );
+        assert_eq!(
+            access_control.grant_access(
+                "STORE-SCP",
+                "CT",
+                "STORE-SCP",
+                Some(&password_identity("modality", "guess"))
+            ),
+            Err(AssociationRJServiceUserReason::NoReasonGiven)
+        );
+        assert_eq!(
+            access_control.grant_access("STORE-SCP", "CT", "STORE-SCP", None),
+            Err(AssociationRJServiceUserReason::NoReasonGiven)
+        );
+
+        let ticket = UserIdentity::new(
+            true,
+            UserIdentityType::KerberosServiceTicket,
+            b"ticket".to_vec(),
+            Vec::new(),
+        );
+        assert_eq!(
+            access_control.grant_access("STORE-SCP", "CT", "STORE-SCP", Some(&ticket)),
+            Ok(Some(b"server ticket".to_vec()))
+        );
+
+        // the other access control policy still applies
+        let access_control =
+            access_control.with_access_control(crate::association::server::AcceptCalledAeTitle);
+        assert_eq!(
+            access_control.check_access(
+                "STORE-SCP",
+                "CT",
+                "ANY-SCP",
+                Some(&password_identity("modality", "s3cr3t"))
+            ),
+            Err(AssociationRJServiceUserReason::CalledAETitleNotRecognized)
+        );
+    }
+}
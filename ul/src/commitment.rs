@@ -0,0 +1,596 @@
+//! Storage Commitment Push Model
+//!
+//! This module supports both ends of the Storage Commitment Push Model
+//! (see the standard, part 4, annex J).
+//!
+//! The service class user asks the provider
+//! to commit to the safekeeping of a list of instances
+//! with an N-ACTION request ([`request_commitment`]),
+//! identified by a transaction UID.
+//! Once the provider has verified the instances,
+//! it reports which of them were committed and which failed
+//! with an N-EVENT-REPORT request,
+//! either on the same association ([`receive_result`])
+//! or on a new association started by the provider
+//! (see [`ResultCollector`]).
+//!
+//! On the provider side,
+//! a [`CommitmentHandler`](crate::scp::CommitmentHandler)
+//! can be registered in a [`ServiceClassProvider`](crate::scp::ServiceClassProvider),
+//! which takes care of the message exchange.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_ul::association::ClientAssociationOptions;
+//! use dicom_ul::commitment::{
+//!     receive_result, request_commitment, CommitmentRequest, InstanceReference,
+//!     STORAGE_COMMITMENT_PUSH_MODEL,
+//! };
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut association = ClientAssociationOptions::new()
+//!     .with_abstract_syntax(STORAGE_COMMITMENT_PUSH_MODEL)
+//!     .establish_with("ARCHIVE@127.0.0.1:104")?;
+//!
+//! let request = CommitmentRequest::new(
+//!     "2.25.314159265358979323846264338327950288",
+//!     vec![InstanceReference::new(
+//!         "1.2.840.10008.5.1.4.1.1.7",
+//!         "2.25.60156688944589400766024286894543900794",
+//!     )],
+//! );
+//! request_commitment(&mut association, 1, &request)?;
+//! let result = receive_result(&mut association)?;
+//! println!(
+//!     "{} committed, {} failed",
+//!     result.committed.len(),
+//!     result.failed.len()
+//! );
+//! # Ok(())
+//! # }
+//! ```
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+
+use dicom_core::{dicom_value, value::DataSetSequence, DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_encoding::transfer_syntax::{TransferSyntax, TransferSyntaxIndex};
+use dicom_object::InMemDicomObject;
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    association::{client, ClientAssociation, Transport},
+    dimse::{
+        normalized::{NActionRq, NActionRsp, NEventReportRq, NEventReportRsp},
+        Command, CommandField, DimseMessage, Status,
+    },
+    scp::{NormalizedHandler, ServiceContext},
+};
+
+/// The Storage Commitment Push Model SOP class UID.
+pub const STORAGE_COMMITMENT_PUSH_MODEL: &str = uids::STORAGE_COMMITMENT_PUSH_MODEL;
+
+/// The well-known SOP instance UID of the Storage Commitment Push Model.
+pub const STORAGE_COMMITMENT_PUSH_MODEL_INSTANCE: &str =
+    uids::STORAGE_COMMITMENT_PUSH_MODEL_INSTANCE;
+
+/// The action type ID of a storage commitment request.
+pub const REQUEST_STORAGE_COMMITMENT: u16 = 1;
+
+/// The event type ID of a report in which all instances were committed.
+pub const STORAGE_COMMITMENT_SUCCESSFUL: u16 = 1;
+
+/// The event type ID of a report in which some instances failed.
+pub const STORAGE_COMMITMENT_FAILURES_EXIST: u16 = 2;
+
+/// Values of the _Failure Reason_ (0008,1197) attribute
+/// of an instance which could not be committed.
+pub mod failure_reason {
+    /// A general failure in processing the operation was encountered.
+    pub const PROCESSING_FAILURE: u16 = 0x0110;
+    /// The instance is not known to the provider.
+    pub const NO_SUCH_OBJECT_INSTANCE: u16 = 0x0112;
+    /// The provider does not currently have enough resources
+    /// to commit to the instance.
+    pub const RESOURCE_LIMITATION: u16 = 0x0213;
+    /// The provider does not commit to instances of this SOP class.
+    pub const REFERENCED_SOP_CLASS_NOT_SUPPORTED: u16 = 0x0122;
+    /// The SOP class of the instance known to the provider
+    /// differs from the one in the request.
+    pub const CLASS_INSTANCE_CONFLICT: u16 = 0x0119;
+    /// The transaction UID is already in use.
+    pub const DUPLICATE_TRANSACTION_UID: u16 = 0x0131;
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[snafu(display("missing attribute {}", tag))]
+    MissingAttribute { tag: Tag, backtrace: Backtrace },
+
+    /// no presentation context was accepted for storage commitment
+    NoPresentationContext { backtrace: Backtrace },
+
+    #[snafu(display("unsupported transfer syntax {}", uid))]
+    UnsupportedTransferSyntax { uid: String, backtrace: Backtrace },
+
+    /// failed to prepare or read message
+    Dimse {
+        #[snafu(backtrace)]
+        source: crate::dimse::Error,
+    },
+
+    /// failed to send message
+    Send {
+        #[snafu(backtrace)]
+        source: client::Error,
+    },
+
+    /// failed to receive message
+    Receive {
+        #[snafu(backtrace)]
+        source: client::Error,
+    },
+
+    #[snafu(display("storage commitment request refused with status {}", status))]
+    Refused {
+        status: Status,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("unexpected {} message", command_field))]
+    UnexpectedMessage {
+        command_field: CommandField,
+        backtrace: Backtrace,
+    },
+
+    /// storage commitment report without event information
+    MissingEventInformation { backtrace: Backtrace },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A reference to a composite SOP instance.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InstanceReference {
+    pub sop_class_uid: String,
+    pub sop_instance_uid: String,
+}
+
+impl InstanceReference {
+    /// Create a reference to the given instance.
+    pub fn new(sop_class_uid: impl Into<String>, sop_instance_uid: impl Into<String>) -> Self {
+        InstanceReference {
+            sop_class_uid: sop_class_uid.into(),
+            sop_instance_uid: sop_instance_uid.into(),
+        }
+    }
+
+    fn to_item(&self) -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            uid_element(tags::REFERENCED_SOP_CLASS_UID, &self.sop_class_uid),
+            uid_element(tags::REFERENCED_SOP_INSTANCE_UID, &self.sop_instance_uid),
+        ])
+    }
+
+    fn from_item(item: &InMemDicomObject) -> Result<Self> {
+        Ok(InstanceReference {
+            sop_class_uid: read_str(item, tags::REFERENCED_SOP_CLASS_UID)?,
+            sop_instance_uid: read_str(item, tags::REFERENCED_SOP_INSTANCE_UID)?,
+        })
+    }
+}
+
+/// An instance which could not be committed,
+/// with the reason for the failure
+/// (see [`failure_reason`] for the values defined by the standard).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FailedInstance {
+    pub instance: InstanceReference,
+    pub failure_reason: u16,
+}
+
+impl FailedInstance {
+    /// Declare the failure to commit to the given instance.
+    pub fn new(instance: InstanceReference, failure_reason: u16) -> Self {
+        FailedInstance {
+            instance,
+            failure_reason,
+        }
+    }
+}
+
+/// The action information of a storage commitment request.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CommitmentRequest {
+    /// the UID identifying this storage commitment transaction
+    pub transaction_uid: String,
+    /// the instances to commit to
+    pub instances: Vec<InstanceReference>,
+}
+
+impl CommitmentRequest {
+    /// Create a storage commitment request for the given instances.
+    pub fn new(transaction_uid: impl Into<String>, instances: Vec<InstanceReference>) -> Self {
+        CommitmentRequest {
+            transaction_uid: transaction_uid.into(),
+            instances,
+        }
+    }
+
+    /// Build the data set of the N-ACTION request.
+    pub fn to_dataset(&self) -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            uid_element(tags::TRANSACTION_UID, &self.transaction_uid),
+            sequence_element(
+                tags::REFERENCED_SOP_SEQUENCE,
+                self.instances.iter().map(InstanceReference::to_item),
+            ),
+        ])
+    }
+
+    /// Read a storage commitment request
+    /// from the data set of an N-ACTION request.
+    pub fn from_dataset(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(CommitmentRequest {
+            transaction_uid: read_str(obj, tags::TRANSACTION_UID)?,
+            instances: items(obj, tags::REFERENCED_SOP_SEQUENCE)
+                .context(MissingAttributeSnafu {
+                    tag: tags::REFERENCED_SOP_SEQUENCE,
+                })?
+                .iter()
+                .map(InstanceReference::from_item)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// The outcome of a storage commitment request,
+/// as reported by the provider.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CommitmentResult {
+    /// the UID of the storage commitment transaction
+    pub transaction_uid: String,
+    /// the instances committed to
+    pub committed: Vec<InstanceReference>,
+    /// the instances which could not be committed
+    pub failed: Vec<FailedInstance>,
+}
+
+impl CommitmentResult {
+    /// Create an empty result for the given transaction.
+    pub fn new(transaction_uid: impl Into<String>) -> Self {
+        CommitmentResult {
+            transaction_uid: transaction_uid.into(),
+            committed: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+
+    /// Whether all instances were committed.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// The event type ID of the N-EVENT-REPORT request
+    /// conveying this result.
+    pub fn event_type_id(&self) -> u16 {
+        if self.is_success() {
+            STORAGE_COMMITMENT_SUCCESSFUL
+        } else {
+            STORAGE_COMMITMENT_FAILURES_EXIST
+        }
+    }
+
+    /// Build the data set of the N-EVENT-REPORT request.
+    pub fn to_dataset(&self) -> InMemDicomObject {
+        let mut obj = InMemDicomObject::from_element_iter([uid_element(
+            tags::TRANSACTION_UID,
+            &self.transaction_uid,
+        )]);
+        if !self.committed.is_empty() {
+            obj.put(sequence_element(
+                tags::REFERENCED_SOP_SEQUENCE,
+                self.committed.iter().map(InstanceReference::to_item),
+            ));
+        }
+        if !self.failed.is_empty() {
+            obj.put(sequence_element(
+                tags::FAILED_SOP_SEQUENCE,
+                self.failed.iter().map(|failed| {
+                    let mut item = failed.instance.to_item();
+                    item.put(DataElement::new(
+                        tags::FAILURE_REASON,
+                        VR::US,
+                        dicom_value!(U16, [failed.failure_reason]),
+                    ));
+                    item
+                }),
+            ));
+        }
+        obj
+    }
+
+    /// Read a storage commitment result
+    /// from the data set of an N-EVENT-REPORT request.
+    pub fn from_dataset(obj: &InMemDicomObject) -> Result<Self> {
+        let committed = items(obj, tags::REFERENCED_SOP_SEQUENCE)
+            .unwrap_or_default()
+            .iter()
+            .map(InstanceReference::from_item)
+            .collect::<Result<_>>()?;
+        let failed = items(obj, tags::FAILED_SOP_SEQUENCE)
+            .unwrap_or_default()
+            .iter()
+            .map(|item| {
+                let failure_reason = item
+                    .get(tags::FAILURE_REASON)
+                    .and_then(|e| e.to_int::<u16>().ok())
+                    .context(MissingAttributeSnafu {
+                        tag: tags::FAILURE_REASON,
+                    })?;
+                Ok(FailedInstance::new(
+                    InstanceReference::from_item(item)?,
+                    failure_reason,
+                ))
+            })
+            .collect::<Result<_>>()?;
+        Ok(CommitmentResult {
+            transaction_uid: read_str(obj, tags::TRANSACTION_UID)?,
+            committed,
+            failed,
+        })
+    }
+}
+
+/// Ask the provider at the other end of the association
+/// to commit to the instances in the request,
+/// waiting for the N-ACTION response.
+///
+/// The association must have an accepted presentation context
+/// for the [Storage Commitment Push Model](STORAGE_COMMITMENT_PUSH_MODEL).
+/// A response with a status other than _Success_
+/// results in an [`Error::Refused`].
+pub fn request_commitment<S: Transport>(
+    association: &mut ClientAssociation<S>,
+    message_id: u16,
+    request: &CommitmentRequest,
+) -> Result<()> {
+    let pc = association
+        .presentation_contexts()
+        .iter()
+        .find(|pc| association.abstract_syntax(pc.id) == Some(STORAGE_COMMITMENT_PUSH_MODEL))
+        .context(NoPresentationContextSnafu)?;
+    let ts = transfer_syntax(&pc.transfer_syntax)?;
+    let msg = DimseMessage::new(
+        pc.id,
+        NActionRq::new(
+            message_id,
+            STORAGE_COMMITMENT_PUSH_MODEL,
+            STORAGE_COMMITMENT_PUSH_MODEL_INSTANCE,
+            REQUEST_STORAGE_COMMITMENT,
+        ),
+    )
+    .with_dataset(&request.to_dataset(), ts)
+    .context(DimseSnafu)?;
+    association.send_dimse(&msg).context(SendSnafu)?;
+
+    let rsp = association.receive_dimse().context(ReceiveSnafu)?;
+    let command_field = rsp.command.command_field();
+    let rsp = NActionRsp::try_from(rsp.command)
+        .ok()
+        .context(UnexpectedMessageSnafu { command_field })?;
+    ensure!(rsp.status.is_success(), RefusedSnafu { status: rsp.status });
+    Ok(())
+}
+
+/// Wait for the provider to report the outcome of a storage commitment request
+/// on the same association,
+/// acknowledging the report.
+pub fn receive_result<S: Transport>(
+    association: &mut ClientAssociation<S>,
+) -> Result<CommitmentResult> {
+    let msg = association.receive_dimse().context(ReceiveSnafu)?;
+    let command_field = msg.command.command_field();
+    let rq = match &msg.command {
+        Command::NEventReportRq(rq)
+            if rq.affected_sop_class_uid == STORAGE_COMMITMENT_PUSH_MODEL =>
+        {
+            rq.clone()
+        }
+        _ => return UnexpectedMessageSnafu { command_field }.fail(),
+    };
+    let pc = association
+        .presentation_contexts()
+        .iter()
+        .find(|pc| pc.id == msg.presentation_context_id)
+        .context(NoPresentationContextSnafu)?;
+    let ts = transfer_syntax(&pc.transfer_syntax)?;
+    let result = read_report(&msg, ts)?;
+
+    association
+        .send_dimse(&DimseMessage::new(
+            msg.presentation_context_id,
+            NEventReportRsp::new(&rq, Status::SUCCESS),
+        ))
+        .context(SendSnafu)?;
+    Ok(result)
+}
+
+/// A handler of storage commitment reports
+/// sent by the provider on a new association,
+/// to be registered in a [`ServiceClassProvider`]
+/// listening on behalf of the service class user.
+///
+/// The results received are kept until taken
+/// with [`take`](Self::take).
+/// Clones of the collector share the same results.
+///
+/// ```no_run
+/// # use dicom_ul::commitment::{ResultCollector, STORAGE_COMMITMENT_PUSH_MODEL};
+/// # use dicom_ul::scp::ServiceClassProvider;
+/// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let collector = ResultCollector::new();
+/// let scp = ServiceClassProvider::new()
+///     .ae_title("STORE-SCU")
+///     .with_normalized_handler([STORAGE_COMMITMENT_PUSH_MODEL], collector.clone());
+/// let listener = std::net::TcpListener::bind("0.0.0.0:11113")?;
+/// let (stream, _) = listener.accept()?;
+/// scp.handle(stream)?;
+/// for result in collector.take() {
+///     println!("transaction {}: {} failed", result.transaction_uid, result.failed.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`ServiceClassProvider`]: crate::scp::ServiceClassProvider
+#[derive(Debug, Default, Clone)]
+pub struct ResultCollector {
+    results: Arc<Mutex<Vec<CommitmentResult>>>,
+}
+
+impl ResultCollector {
+    /// Create a collector without any results.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take all results received so far.
+    pub fn take(&self) -> Vec<CommitmentResult> {
+        std::mem::take(&mut *self.results.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl NormalizedHandler for ResultCollector {
+    fn n_event_report(
+        &self,
+        _ctx: &ServiceContext,
+        rq: &NEventReportRq,
+        dataset: Option<InMemDicomObject>,
+    ) -> (NEventReportRsp, Option<InMemDicomObject>) {
+        let status = match dataset.as_ref().map(CommitmentResult::from_dataset) {
+            Some(Ok(result)) => {
+                self.results
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(result);
+                Status::SUCCESS
+            }
+            Some(Err(e)) => {
+                tracing::warn!(
+                    "Invalid storage commitment report: {}",
+                    snafu::Report::from_error(e)
+                );
+                Status::INVALID_ARGUMENT_VALUE
+            }
+            None => Status::MISSING_ATTRIBUTE,
+        };
+        (NEventReportRsp::new(rq, status), None)
+    }
+}
+
+/// Read the storage commitment result in an N-EVENT-REPORT message.
+fn read_report(msg: &DimseMessage, ts: &TransferSyntax) -> Result<CommitmentResult> {
+    let obj = msg
+        .dataset(ts)
+        .context(DimseSnafu)?
+        .context(MissingEventInformationSnafu)?;
+    CommitmentResult::from_dataset(&obj)
+}
+
+fn transfer_syntax(uid: &str) -> Result<&'static TransferSyntax> {
+    TransferSyntaxRegistry
+        .get(uid)
+        .context(UnsupportedTransferSyntaxSnafu { uid })
+}
+
+fn uid_element(tag: Tag, uid: &str) -> DataElement<InMemDicomObject> {
+    DataElement::new(tag, VR::UI, PrimitiveValue::from(uid))
+}
+
+fn sequence_element(
+    tag: Tag,
+    items: impl Iterator<Item = InMemDicomObject>,
+) -> DataElement<InMemDicomObject> {
+    DataElement::new(
+        tag,
+        VR::SQ,
+        DataSetSequence::from(items.collect::<Vec<_>>()),
+    )
+}
+
+fn items(obj: &InMemDicomObject, tag: Tag) -> Option<&[InMemDicomObject]> {
+    obj.get(tag).and_then(|e| e.items())
+}
+
+fn read_str(obj: &InMemDicomObject, tag: Tag) -> Result<String> {
+    obj.get(tag)
+        .and_then(|e| e.to_str().ok())
+        .map(|value| {
+            value
+                .trim_end_matches(|c: char| c.is_whitespace() || c == '\0')
+                .to_string()
+        })
+        .filter(|value| !value.is_empty())
+        .context(MissingAttributeSnafu { tag })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_dataset_roundtrip() {
+        let request = CommitmentRequest::new(
+            "2.25.1",
+            vec![
+                InstanceReference::new(uids::SECONDARY_CAPTURE_IMAGE_STORAGE, "2.25.11"),
+                InstanceReference::new(uids::CT_IMAGE_STORAGE, "2.25.12"),
+            ],
+        );
+        let obj = request.to_dataset();
+        assert_eq!(
+            obj.get(tags::REFERENCED_SOP_SEQUENCE)
+                .unwrap()
+                .items()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(CommitmentRequest::from_dataset(&obj).unwrap(), request);
+    }
+
+    #[test]
+    fn result_dataset_roundtrip() {
+        let mut result = CommitmentResult::new("2.25.1");
+        assert!(result.is_success());
+        result.committed.push(InstanceReference::new(
+            uids::SECONDARY_CAPTURE_IMAGE_STORAGE,
+            "2.25.11",
+        ));
+        let obj = result.to_dataset();
+        assert!(obj.get(tags::FAILED_SOP_SEQUENCE).is_none());
+        assert_eq!(CommitmentResult::from_dataset(&obj).unwrap(), result);
+        assert_eq!(result.event_type_id(), STORAGE_COMMITMENT_SUCCESSFUL);
+
+        result.failed.push(FailedInstance::new(
+            InstanceReference::new(uids::CT_IMAGE_STORAGE, "2.25.12"),
+            failure_reason::NO_SUCH_OBJECT_INSTANCE,
+        ));
+        let obj = result.to_dataset();
+        assert_eq!(CommitmentResult::from_dataset(&obj).unwrap(), result);
+        assert_eq!(result.event_type_id(), STORAGE_COMMITMENT_FAILURES_EXIST);
+    }
+
+    #[test]
+    fn request_without_references_is_invalid() {
+        let obj =
+            InMemDicomObject::from_element_iter([uid_element(tags::TRANSACTION_UID, "2.25.1")]);
+        assert!(matches!(
+            CommitmentRequest::from_dataset(&obj),
+            Err(Error::MissingAttribute { tag, .. }) if tag == tags::REFERENCED_SOP_SEQUENCE
+        ));
+    }
+}
@@ -0,0 +1,651 @@
+//! DIMSE-C messages
+//!
+//! See the standard, part 7, section 9.3.
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::{mem::InMemElement, InMemDicomObject};
+
+use super::{
+    push_error_comment, push_opt_uid, push_opt_us, read_opt_str, read_opt_u16, read_priority,
+    read_status, read_str, read_u16, uid_element, us_element, CommandField, DimseCommand, Priority,
+    Result, Status,
+};
+
+/// C-ECHO request
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CEchoRq {
+    pub message_id: u16,
+    pub affected_sop_class_uid: String,
+}
+
+impl CEchoRq {
+    /// Create a C-ECHO request for the _Verification_ SOP class.
+    pub fn new(message_id: u16) -> Self {
+        CEchoRq {
+            message_id,
+            affected_sop_class_uid: uids::VERIFICATION.to_string(),
+        }
+    }
+}
+
+impl DimseCommand for CEchoRq {
+    const COMMAND_FIELD: CommandField = CommandField::CEchoRq;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        vec![
+            uid_element(tags::AFFECTED_SOP_CLASS_UID, &self.affected_sop_class_uid),
+            us_element(tags::MESSAGE_ID, self.message_id),
+        ]
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(CEchoRq {
+            message_id: read_u16(obj, tags::MESSAGE_ID)?,
+            affected_sop_class_uid: read_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+        })
+    }
+}
+
+/// C-ECHO response
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CEchoRsp {
+    pub message_id_being_responded_to: u16,
+    pub affected_sop_class_uid: Option<String>,
+    pub status: Status,
+    pub error_comment: Option<String>,
+}
+
+impl CEchoRsp {
+    /// Create a response to the given C-ECHO request.
+    pub fn new(rq: &CEchoRq, status: Status) -> Self {
+        CEchoRsp {
+            message_id_being_responded_to: rq.message_id,
+            affected_sop_class_uid: Some(rq.affected_sop_class_uid.clone()),
+            status,
+            error_comment: None,
+        }
+    }
+}
+
+impl DimseCommand for CEchoRsp {
+    const COMMAND_FIELD: CommandField = CommandField::CEchoRsp;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        let mut elements = vec![
+            us_element(
+                tags::MESSAGE_ID_BEING_RESPONDED_TO,
+                self.message_id_being_responded_to,
+            ),
+            us_element(tags::STATUS, self.status.0),
+        ];
+        push_opt_uid(
+            &mut elements,
+            tags::AFFECTED_SOP_CLASS_UID,
+            self.affected_sop_class_uid.as_deref(),
+        );
+        push_error_comment(&mut elements, self.error_comment.as_deref());
+        elements
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(CEchoRsp {
+            message_id_being_responded_to: read_u16(obj, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+            affected_sop_class_uid: read_opt_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            status: read_status(obj)?,
+            error_comment: read_opt_str(obj, tags::ERROR_COMMENT)?,
+        })
+    }
+}
+
+/// C-STORE request
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CStoreRq {
+    pub message_id: u16,
+    pub affected_sop_class_uid: String,
+    pub affected_sop_instance_uid: String,
+    pub priority: Priority,
+    /// the AE title of the node which invoked the C-MOVE operation
+    /// that originated this sub-operation
+    pub move_originator_ae_title: Option<String>,
+    /// the message ID of the C-MOVE request
+    /// that originated this sub-operation
+    pub move_originator_message_id: Option<u16>,
+}
+
+impl CStoreRq {
+    /// Create a C-STORE request with medium priority.
+    pub fn new(
+        message_id: u16,
+        affected_sop_class_uid: impl Into<String>,
+        affected_sop_instance_uid: impl Into<String>,
+    ) -> Self {
+        CStoreRq {
+            message_id,
+            affected_sop_class_uid: affected_sop_class_uid.into(),
+            affected_sop_instance_uid: affected_sop_instance_uid.into(),
+            priority: Priority::Medium,
+            move_originator_ae_title: None,
+            move_originator_message_id: None,
+        }
+    }
+
+    /// Declare this request as a sub-operation
+    /// of the given C-MOVE request from the given AE title.
+    pub fn with_move_originator(mut self, ae_title: impl Into<String>, message_id: u16) -> Self {
+        self.move_originator_ae_title = Some(ae_title.into());
+        self.move_originator_message_id = Some(message_id);
+        self
+    }
+}
+
+impl DimseCommand for CStoreRq {
+    const COMMAND_FIELD: CommandField = CommandField::CStoreRq;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        let mut elements = vec![
+            uid_element(tags::AFFECTED_SOP_CLASS_UID, &self.affected_sop_class_uid),
+            us_element(tags::MESSAGE_ID, self.message_id),
+            us_element(tags::PRIORITY, self.priority.code()),
+            uid_element(
+                tags::AFFECTED_SOP_INSTANCE_UID,
+                &self.affected_sop_instance_uid,
+            ),
+        ];
+        if let Some(ae_title) = &self.move_originator_ae_title {
+            elements.push(DataElement::new(
+                tags::MOVE_ORIGINATOR_APPLICATION_ENTITY_TITLE,
+                VR::AE,
+                PrimitiveValue::from(ae_title.as_str()),
+            ));
+        }
+        push_opt_us(
+            &mut elements,
+            tags::MOVE_ORIGINATOR_MESSAGE_ID,
+            self.move_originator_message_id,
+        );
+        elements
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(CStoreRq {
+            message_id: read_u16(obj, tags::MESSAGE_ID)?,
+            affected_sop_class_uid: read_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            affected_sop_instance_uid: read_str(obj, tags::AFFECTED_SOP_INSTANCE_UID)?,
+            priority: read_priority(obj)?,
+            move_originator_ae_title: read_opt_str(
+                obj,
+                tags::MOVE_ORIGINATOR_APPLICATION_ENTITY_TITLE,
+            )?,
+            move_originator_message_id: read_opt_u16(obj, tags::MOVE_ORIGINATOR_MESSAGE_ID)?,
+        })
+    }
+}
+
+/// C-STORE response
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CStoreRsp {
+    pub message_id_being_responded_to: u16,
+    pub affected_sop_class_uid: Option<String>,
+    pub affected_sop_instance_uid: Option<String>,
+    pub status: Status,
+    pub error_comment: Option<String>,
+}
+
+impl CStoreRsp {
+    /// Create a response to the given C-STORE request.
+    pub fn new(rq: &CStoreRq, status: Status) -> Self {
+        CStoreRsp {
+            message_id_being_responded_to: rq.message_id,
+            affected_sop_class_uid: Some(rq.affected_sop_class_uid.clone()),
+            affected_sop_instance_uid: Some(rq.affected_sop_instance_uid.clone()),
+            status,
+            error_comment: None,
+        }
+    }
+}
+
+impl DimseCommand for CStoreRsp {
+    const COMMAND_FIELD: CommandField = CommandField::CStoreRsp;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        let mut elements = vec![
+            us_element(
+                tags::MESSAGE_ID_BEING_RESPONDED_TO,
+                self.message_id_being_responded_to,
+            ),
+            us_element(tags::STATUS, self.status.0),
+        ];
+        push_opt_uid(
+            &mut elements,
+            tags::AFFECTED_SOP_CLASS_UID,
+            self.affected_sop_class_uid.as_deref(),
+        );
+        push_opt_uid(
+            &mut elements,
+            tags::AFFECTED_SOP_INSTANCE_UID,
+            self.affected_sop_instance_uid.as_deref(),
+        );
+        push_error_comment(&mut elements, self.error_comment.as_deref());
+        elements
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(CStoreRsp {
+            message_id_being_responded_to: read_u16(obj, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+            affected_sop_class_uid: read_opt_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            affected_sop_instance_uid: read_opt_str(obj, tags::AFFECTED_SOP_INSTANCE_UID)?,
+            status: read_status(obj)?,
+            error_comment: read_opt_str(obj, tags::ERROR_COMMENT)?,
+        })
+    }
+}
+
+/// C-FIND request
+///
+/// The query identifier is sent as the message's data set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CFindRq {
+    pub message_id: u16,
+    pub affected_sop_class_uid: String,
+    pub priority: Priority,
+}
+
+impl CFindRq {
+    /// Create a C-FIND request with medium priority.
+    pub fn new(message_id: u16, affected_sop_class_uid: impl Into<String>) -> Self {
+        CFindRq {
+            message_id,
+            affected_sop_class_uid: affected_sop_class_uid.into(),
+            priority: Priority::Medium,
+        }
+    }
+}
+
+impl DimseCommand for CFindRq {
+    const COMMAND_FIELD: CommandField = CommandField::CFindRq;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        vec![
+            uid_element(tags::AFFECTED_SOP_CLASS_UID, &self.affected_sop_class_uid),
+            us_element(tags::MESSAGE_ID, self.message_id),
+            us_element(tags::PRIORITY, self.priority.code()),
+        ]
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(CFindRq {
+            message_id: read_u16(obj, tags::MESSAGE_ID)?,
+            affected_sop_class_uid: read_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            priority: read_priority(obj)?,
+        })
+    }
+}
+
+/// C-FIND response
+///
+/// Pending responses carry a matching identifier as the message's data set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CFindRsp {
+    pub message_id_being_responded_to: u16,
+    pub affected_sop_class_uid: Option<String>,
+    pub status: Status,
+    pub error_comment: Option<String>,
+}
+
+impl CFindRsp {
+    /// Create a response to the given C-FIND request.
+    pub fn new(rq: &CFindRq, status: Status) -> Self {
+        CFindRsp {
+            message_id_being_responded_to: rq.message_id,
+            affected_sop_class_uid: Some(rq.affected_sop_class_uid.clone()),
+            status,
+            error_comment: None,
+        }
+    }
+}
+
+impl DimseCommand for CFindRsp {
+    const COMMAND_FIELD: CommandField = CommandField::CFindRsp;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        let mut elements = vec![
+            us_element(
+                tags::MESSAGE_ID_BEING_RESPONDED_TO,
+                self.message_id_being_responded_to,
+            ),
+            us_element(tags::STATUS, self.status.0),
+        ];
+        push_opt_uid(
+            &mut elements,
+            tags::AFFECTED_SOP_CLASS_UID,
+            self.affected_sop_class_uid.as_deref(),
+        );
+        push_error_comment(&mut elements, self.error_comment.as_deref());
+        elements
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(CFindRsp {
+            message_id_being_responded_to: read_u16(obj, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+            affected_sop_class_uid: read_opt_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            status: read_status(obj)?,
+            error_comment: read_opt_str(obj, tags::ERROR_COMMENT)?,
+        })
+    }
+}
+
+/// The numbers of sub-operations reported in C-GET and C-MOVE responses.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SubOperations {
+    /// number of remaining sub-operations
+    pub remaining: Option<u16>,
+    /// number of completed sub-operations
+    pub completed: Option<u16>,
+    /// number of failed sub-operations
+    pub failed: Option<u16>,
+    /// number of sub-operations completed with a warning
+    pub warning: Option<u16>,
+}
+
+impl SubOperations {
+    fn push_elements(&self, elements: &mut Vec<InMemElement>) {
+        push_opt_us(
+            elements,
+            tags::NUMBER_OF_REMAINING_SUBOPERATIONS,
+            self.remaining,
+        );
+        push_opt_us(
+            elements,
+            tags::NUMBER_OF_COMPLETED_SUBOPERATIONS,
+            self.completed,
+        );
+        push_opt_us(elements, tags::NUMBER_OF_FAILED_SUBOPERATIONS, self.failed);
+        push_opt_us(
+            elements,
+            tags::NUMBER_OF_WARNING_SUBOPERATIONS,
+            self.warning,
+        );
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(SubOperations {
+            remaining: read_opt_u16(obj, tags::NUMBER_OF_REMAINING_SUBOPERATIONS)?,
+            completed: read_opt_u16(obj, tags::NUMBER_OF_COMPLETED_SUBOPERATIONS)?,
+            failed: read_opt_u16(obj, tags::NUMBER_OF_FAILED_SUBOPERATIONS)?,
+            warning: read_opt_u16(obj, tags::NUMBER_OF_WARNING_SUBOPERATIONS)?,
+        })
+    }
+}
+
+/// C-GET request
+///
+/// The retrieve identifier is sent as the message's data set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CGetRq {
+    pub message_id: u16,
+    pub affected_sop_class_uid: String,
+    pub priority: Priority,
+}
+
+impl CGetRq {
+    /// Create a C-GET request with medium priority.
+    pub fn new(message_id: u16, affected_sop_class_uid: impl Into<String>) -> Self {
+        CGetRq {
+            message_id,
+            affected_sop_class_uid: affected_sop_class_uid.into(),
+            priority: Priority::Medium,
+        }
+    }
+}
+
+impl DimseCommand for CGetRq {
+    const COMMAND_FIELD: CommandField = CommandField::CGetRq;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        vec![
+            uid_element(tags::AFFECTED_SOP_CLASS_UID, &self.affected_sop_class_uid),
+            us_element(tags::MESSAGE_ID, self.message_id),
+            us_element(tags::PRIORITY, self.priority.code()),
+        ]
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(CGetRq {
+            message_id: read_u16(obj, tags::MESSAGE_ID)?,
+            affected_sop_class_uid: read_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            priority: read_priority(obj)?,
+        })
+    }
+}
+
+/// C-GET response
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CGetRsp {
+    pub message_id_being_responded_to: u16,
+    pub affected_sop_class_uid: Option<String>,
+    pub status: Status,
+    pub sub_operations: SubOperations,
+    pub error_comment: Option<String>,
+}
+
+impl CGetRsp {
+    /// Create a response to the given C-GET request.
+    pub fn new(rq: &CGetRq, status: Status) -> Self {
+        CGetRsp {
+            message_id_being_responded_to: rq.message_id,
+            affected_sop_class_uid: Some(rq.affected_sop_class_uid.clone()),
+            status,
+            sub_operations: SubOperations::default(),
+            error_comment: None,
+        }
+    }
+
+    /// Set the numbers of remaining, completed, failed and warning sub-operations.
+    pub fn with_sub_operations(
+        mut self,
+        remaining: u16,
+        completed: u16,
+        failed: u16,
+        warning: u16,
+    ) -> Self {
+        self.sub_operations = SubOperations {
+            remaining: Some(remaining),
+            completed: Some(completed),
+            failed: Some(failed),
+            warning: Some(warning),
+        };
+        self
+    }
+}
+
+impl DimseCommand for CGetRsp {
+    const COMMAND_FIELD: CommandField = CommandField::CGetRsp;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        let mut elements = vec![
+            us_element(
+                tags::MESSAGE_ID_BEING_RESPONDED_TO,
+                self.message_id_being_responded_to,
+            ),
+            us_element(tags::STATUS, self.status.0),
+        ];
+        push_opt_uid(
+            &mut elements,
+            tags::AFFECTED_SOP_CLASS_UID,
+            self.affected_sop_class_uid.as_deref(),
+        );
+        self.sub_operations.push_elements(&mut elements);
+        push_error_comment(&mut elements, self.error_comment.as_deref());
+        elements
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(CGetRsp {
+            message_id_being_responded_to: read_u16(obj, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+            affected_sop_class_uid: read_opt_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            status: read_status(obj)?,
+            sub_operations: SubOperations::from_command_set(obj)?,
+            error_comment: read_opt_str(obj, tags::ERROR_COMMENT)?,
+        })
+    }
+}
+
+/// C-MOVE request
+///
+/// The retrieve identifier is sent as the message's data set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CMoveRq {
+    pub message_id: u16,
+    pub affected_sop_class_uid: String,
+    pub priority: Priority,
+    /// the AE title of the destination of the C-STORE sub-operations
+    pub move_destination: String,
+}
+
+impl CMoveRq {
+    /// Create a C-MOVE request with medium priority.
+    pub fn new(
+        message_id: u16,
+        affected_sop_class_uid: impl Into<String>,
+        move_destination: impl Into<String>,
+    ) -> Self {
+        CMoveRq {
+            message_id,
+            affected_sop_class_uid: affected_sop_class_uid.into(),
+            priority: Priority::Medium,
+            move_destination: move_destination.into(),
+        }
+    }
+}
+
+impl DimseCommand for CMoveRq {
+    const COMMAND_FIELD: CommandField = CommandField::CMoveRq;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        vec![
+            uid_element(tags::AFFECTED_SOP_CLASS_UID, &self.affected_sop_class_uid),
+            us_element(tags::MESSAGE_ID, self.message_id),
+            us_element(tags::PRIORITY, self.priority.code()),
+            DataElement::new(
+                tags::MOVE_DESTINATION,
+                VR::AE,
+                PrimitiveValue::from(self.move_destination.as_str()),
+            ),
+        ]
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(CMoveRq {
+            message_id: read_u16(obj, tags::MESSAGE_ID)?,
+            affected_sop_class_uid: read_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            priority: read_priority(obj)?,
+            move_destination: read_str(obj, tags::MOVE_DESTINATION)?,
+        })
+    }
+}
+
+/// C-MOVE response
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CMoveRsp {
+    pub message_id_being_responded_to: u16,
+    pub affected_sop_class_uid: Option<String>,
+    pub status: Status,
+    pub sub_operations: SubOperations,
+    pub error_comment: Option<String>,
+}
+
+impl CMoveRsp {
+    /// Create a response to the given C-MOVE request.
+    pub fn new(rq: &CMoveRq, status: Status) -> Self {
+        CMoveRsp {
+            message_id_being_responded_to: rq.message_id,
+            affected_sop_class_uid: Some(rq.affected_sop_class_uid.clone()),
+            status,
+            sub_operations: SubOperations::default(),
+            error_comment: None,
+        }
+    }
+
+    /// Set the numbers of remaining, completed, failed and warning sub-operations.
+    pub fn with_sub_operations(
+        mut self,
+        remaining: u16,
+        completed: u16,
+        failed: u16,
+        warning: u16,
+    ) -> Self {
+        self.sub_operations = SubOperations {
+            remaining: Some(remaining),
+            completed: Some(completed),
+            failed: Some(failed),
+            warning: Some(warning),
+        };
+        self
+    }
+}
+
+impl DimseCommand for CMoveRsp {
+    const COMMAND_FIELD: CommandField = CommandField::CMoveRsp;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        let mut elements = vec![
+            us_element(
+                tags::MESSAGE_ID_BEING_RESPONDED_TO,
+                self.message_id_being_responded_to,
+            ),
+            us_element(tags::STATUS, self.status.0),
+        ];
+        push_opt_uid(
+            &mut elements,
+            tags::AFFECTED_SOP_CLASS_UID,
+            self.affected_sop_class_uid.as_deref(),
+        );
+        self.sub_operations.push_elements(&mut elements);
+        push_error_comment(&mut elements, self.error_comment.as_deref());
+        elements
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(CMoveRsp {
+            message_id_being_responded_to: read_u16(obj, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+            affected_sop_class_uid: read_opt_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            status: read_status(obj)?,
+            sub_operations: SubOperations::from_command_set(obj)?,
+            error_comment: read_opt_str(obj, tags::ERROR_COMMENT)?,
+        })
+    }
+}
+
+/// C-CANCEL request,
+/// to cancel an ongoing C-FIND, C-GET or C-MOVE operation
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CCancelRq {
+    /// the message ID of the operation to cancel
+    pub message_id_being_responded_to: u16,
+}
+
+impl CCancelRq {
+    /// Create a request to cancel the operation with the given message ID.
+    pub fn new(message_id_being_responded_to: u16) -> Self {
+        CCancelRq {
+            message_id_being_responded_to,
+        }
+    }
+}
+
+impl DimseCommand for CCancelRq {
+    const COMMAND_FIELD: CommandField = CommandField::CCancelRq;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        vec![us_element(
+            tags::MESSAGE_ID_BEING_RESPONDED_TO,
+            self.message_id_being_responded_to,
+        )]
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(CCancelRq {
+            message_id_being_responded_to: read_u16(obj, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+        })
+    }
+}
@@ -0,0 +1,348 @@
+//! Full DIMSE messages and their conversion to and from P-Data PDUs.
+use std::collections::VecDeque;
+
+use dicom_encoding::TransferSyntax;
+use dicom_object::InMemDicomObject;
+use snafu::{ensure, ResultExt};
+
+use crate::pdu::{PDataValue, PDataValueType, Pdu, PDU_HEADER_SIZE};
+
+use super::{
+    decode_command, Command, DecodeDataSetSnafu, EncodeDataSetSnafu,
+    PresentationContextMismatchSnafu, Result, UnexpectedDataSetSnafu,
+};
+
+/// The size of a P-Data value item header
+/// (item length, presentation context ID and message control header).
+const PDV_HEADER_SIZE: u32 = 6;
+
+/// A complete DIMSE message:
+/// a command and its optional data set,
+/// bound to a presentation context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DimseMessage {
+    /// the presentation context in which the message is sent
+    pub presentation_context_id: u8,
+    /// the command
+    pub command: Command,
+    /// the encoded data set, if any,
+    /// in the transfer syntax of the presentation context
+    pub data: Option<Vec<u8>>,
+}
+
+impl DimseMessage {
+    /// Create a new message without a data set.
+    pub fn new(presentation_context_id: u8, command: impl Into<Command>) -> Self {
+        DimseMessage {
+            presentation_context_id,
+            command: command.into(),
+            data: None,
+        }
+    }
+
+    /// Attach an already encoded data set to the message.
+    pub fn with_data(mut self, data: Vec<u8>) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Encode and attach a data set to the message,
+    /// using the transfer syntax negotiated for the presentation context.
+    pub fn with_dataset(mut self, dataset: &InMemDicomObject, ts: &TransferSyntax) -> Result<Self> {
+        let mut data = Vec::new();
+        dataset
+            .write_dataset_with_ts(&mut data, ts)
+            .context(EncodeDataSetSnafu)?;
+        self.data = Some(data);
+        Ok(self)
+    }
+
+    /// Whether the message has a data set.
+    pub fn has_data_set(&self) -> bool {
+        self.data.is_some()
+    }
+
+    /// Decode the message's data set, if any,
+    /// in the given transfer syntax.
+    pub fn dataset(&self, ts: &TransferSyntax) -> Result<Option<InMemDicomObject>> {
+        self.data
+            .as_deref()
+            .map(|data| {
+                InMemDicomObject::read_dataset_with_ts(data, ts).context(DecodeDataSetSnafu)
+            })
+            .transpose()
+    }
+
+    /// Split the message into P-Data PDUs
+    /// which do not exceed the given maximum PDU length.
+    ///
+    /// The command fragments are always sent before the data set fragments,
+    /// and fragments are packed into as few PDUs as possible.
+    pub fn to_pdus(&self, max_pdu_length: u32) -> Result<Vec<Pdu>> {
+        let command = self.command.encode(self.data.is_some())?;
+        // the full PDU, including its header, must fit in the maximum length
+        let max_data_len = max_pdu_length
+            .saturating_sub(PDU_HEADER_SIZE + PDV_HEADER_SIZE)
+            .max(1) as usize;
+
+        let mut fragments = split_values(
+            self.presentation_context_id,
+            PDataValueType::Command,
+            &command,
+            max_data_len,
+        );
+        if let Some(data) = &self.data {
+            fragments.extend(split_values(
+                self.presentation_context_id,
+                PDataValueType::Data,
+                data,
+                max_data_len,
+            ));
+        }
+
+        Ok(pack_values(fragments, max_pdu_length))
+    }
+}
+
+/// Split the command of a message into P-Data PDUs
+/// which do not exceed the given maximum PDU length,
+/// announcing a data set which is sent separately afterwards.
+pub(crate) fn command_to_pdus(
+    presentation_context_id: u8,
+    command: &Command,
+    max_pdu_length: u32,
+) -> Result<Vec<Pdu>> {
+    let command = command.encode(true)?;
+    let max_data_len = max_pdu_length
+        .saturating_sub(PDU_HEADER_SIZE + PDV_HEADER_SIZE)
+        .max(1) as usize;
+    let fragments = split_values(
+        presentation_context_id,
+        PDataValueType::Command,
+        &command,
+        max_data_len,
+    );
+    Ok(pack_values(fragments, max_pdu_length))
+}
+
+/// Pack P-Data values into as few PDUs as possible
+/// without exceeding the given maximum PDU length.
+fn pack_values(fragments: Vec<PDataValue>, max_pdu_length: u32) -> Vec<Pdu> {
+    let max_values_len = max_pdu_length.saturating_sub(PDU_HEADER_SIZE) as usize;
+    let mut pdus = Vec::new();
+    let mut values: Vec<PDataValue> = Vec::new();
+    let mut values_len = 0;
+    for fragment in fragments {
+        let len = fragment.data.len() + PDV_HEADER_SIZE as usize;
+        if !values.is_empty() && values_len + len > max_values_len {
+            pdus.push(Pdu::PData {
+                data: std::mem::take(&mut values),
+            });
+            values_len = 0;
+        }
+        values_len += len;
+        values.push(fragment);
+    }
+    if !values.is_empty() {
+        pdus.push(Pdu::PData { data: values });
+    }
+    pdus
+}
+
+/// Split a command or data set into P-Data values of bounded size.
+fn split_values(
+    presentation_context_id: u8,
+    value_type: PDataValueType,
+    data: &[u8],
+    max_data_len: usize,
+) -> Vec<PDataValue> {
+    if data.is_empty() {
+        return vec![PDataValue {
+            presentation_context_id,
+            value_type,
+            is_last: true,
+            data: Vec::new(),
+        }];
+    }
+    let count = (data.len() + max_data_len - 1) / max_data_len;
+    data.chunks(max_data_len)
+        .enumerate()
+        .map(|(i, chunk)| PDataValue {
+            presentation_context_id,
+            value_type: value_type.clone(),
+            is_last: i + 1 == count,
+            data: chunk.to_vec(),
+        })
+        .collect()
+}
+
+/// A reassembler of DIMSE messages from incoming P-Data values.
+///
+/// P-Data values are fed via [`push_values`](MessageAssembler::push_values)
+/// in the order in which they are received,
+/// and complete messages are retrieved via
+/// [`pop_message`](MessageAssembler::pop_message).
+/// A single PDU may contain the end of a message
+/// and the beginning of another.
+#[derive(Debug, Default)]
+pub struct MessageAssembler {
+    presentation_context_id: Option<u8>,
+    command_data: Vec<u8>,
+    command: Option<Command>,
+    data: Vec<u8>,
+    ready: VecDeque<DimseMessage>,
+}
+
+impl MessageAssembler {
+    /// Create a new message assembler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the P-Data values of a received PDU to the assembler.
+    pub fn push_values(&mut self, values: Vec<PDataValue>) -> Result<()> {
+        for value in values {
+            self.push_value(value)?;
+        }
+        Ok(())
+    }
+
+    fn push_value(&mut self, value: PDataValue) -> Result<()> {
+        match self.presentation_context_id {
+            Some(id) => ensure!(
+                id == value.presentation_context_id,
+                PresentationContextMismatchSnafu {
+                    expected: id,
+                    got: value.presentation_context_id,
+                }
+            ),
+            None => self.presentation_context_id = Some(value.presentation_context_id),
+        }
+
+        match value.value_type {
+            PDataValueType::Command => {
+                self.command_data.extend(value.data);
+                if value.is_last {
+                    let (command, has_data_set) = decode_command(&self.command_data)?;
+                    self.command_data.clear();
+                    if has_data_set {
+                        self.command = Some(command);
+                    } else {
+                        self.complete(command, None);
+                    }
+                }
+            }
+            PDataValueType::Data => {
+                ensure!(self.command.is_some(), UnexpectedDataSetSnafu);
+                self.data.extend(value.data);
+                if value.is_last {
+                    if let Some(command) = self.command.take() {
+                        let data = std::mem::take(&mut self.data);
+                        self.complete(command, Some(data));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn complete(&mut self, command: Command, data: Option<Vec<u8>>) {
+        let presentation_context_id = self.presentation_context_id.take().unwrap_or_default();
+        self.ready.push_back(DimseMessage {
+            presentation_context_id,
+            command,
+            data,
+        });
+    }
+
+    /// Retrieve the next fully received message, if any.
+    pub fn pop_message(&mut self) -> Option<DimseMessage> {
+        self.ready.pop_front()
+    }
+
+    /// Whether a message is only partially received.
+    pub fn is_partial(&self) -> bool {
+        self.presentation_context_id.is_some()
+    }
+
+    /// Take out the message being assembled
+    /// if its command is complete but its data set is not,
+    /// along with the data set bytes received so far.
+    ///
+    /// This allows the rest of the data set to be received separately,
+    /// without collecting it in memory.
+    /// The assembler then expects the beginning of a new message.
+    pub fn take_pending_command(&mut self) -> Option<(u8, Command, Vec<u8>)> {
+        let command = self.command.take()?;
+        let presentation_context_id = self.presentation_context_id.take().unwrap_or_default();
+        Some((presentation_context_id, command, std::mem::take(&mut self.data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dimse::composite::{CEchoRq, CStoreRq};
+    use crate::pdu::write_pdu;
+
+    #[test]
+    fn split_and_reassemble() {
+        let msg = DimseMessage::new(3, CStoreRq::new(5, "1.2.3", "1.2.3.4"))
+            .with_data((0..10_000).map(|i| i as u8).collect());
+
+        let pdus = msg.to_pdus(4_096).unwrap();
+        assert!(pdus.len() > 2);
+
+        let mut assembler = MessageAssembler::new();
+        for pdu in pdus {
+            // every PDU must fit in the maximum PDU length
+            let mut buf = Vec::new();
+            write_pdu(&mut buf, &pdu).unwrap();
+            assert!(buf.len() <= 4_096);
+
+            match pdu {
+                Pdu::PData { data } => assembler.push_values(data).unwrap(),
+                pdu => panic!("unexpected PDU {:?}", pdu),
+            }
+        }
+        assert!(!assembler.is_partial());
+        assert_eq!(assembler.pop_message(), Some(msg));
+        assert_eq!(assembler.pop_message(), None);
+    }
+
+    #[test]
+    fn several_messages_in_one_pdu() {
+        let msg1 = DimseMessage::new(1, CEchoRq::new(1));
+        let msg2 = DimseMessage::new(1, CEchoRq::new(2));
+
+        let mut values = Vec::new();
+        for msg in [&msg1, &msg2] {
+            for pdu in msg.to_pdus(16_384).unwrap() {
+                if let Pdu::PData { data } = pdu {
+                    values.extend(data);
+                }
+            }
+        }
+
+        let mut assembler = MessageAssembler::new();
+        assembler.push_values(values).unwrap();
+        assert_eq!(assembler.pop_message(), Some(msg1));
+        assert_eq!(assembler.pop_message(), Some(msg2));
+        assert_eq!(assembler.pop_message(), None);
+    }
+
+    #[test]
+    fn data_before_command() {
+        let mut assembler = MessageAssembler::new();
+        let out = assembler.push_values(vec![PDataValue {
+            presentation_context_id: 1,
+            value_type: PDataValueType::Data,
+            is_last: true,
+            data: vec![0; 4],
+        }]);
+        assert!(matches!(
+            out,
+            Err(crate::dimse::Error::UnexpectedDataSet { .. })
+        ));
+    }
+}
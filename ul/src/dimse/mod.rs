@@ -0,0 +1,725 @@
+//! DICOM Message Service Element (DIMSE) module
+//!
+//! This module provides typed representations of the DIMSE messages
+//! defined in part 7 of the standard,
+//! so that service class users and providers
+//! do not need to build command sets by hand.
+//!
+//! - The [`composite`] module contains the DIMSE-C services
+//!   (C-ECHO, C-STORE, C-FIND, C-GET, C-MOVE and C-CANCEL).
+//! - The [`normalized`] module contains the DIMSE-N services
+//!   (N-EVENT-REPORT, N-GET, N-SET, N-ACTION, N-CREATE and N-DELETE).
+//! - [`DimseMessage`] puts together a command and its optional data set,
+//!   and can be sent through an established association
+//!   via `send_dimse` and received via `receive_dimse`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_ul::association::ClientAssociationOptions;
+//! use dicom_ul::dimse::{composite::CEchoRq, Command, DimseMessage};
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut association = ClientAssociationOptions::new()
+//!     .with_abstract_syntax("1.2.840.10008.1.1")
+//!     .establish_with("ECHO-SCP@127.0.0.1:104")?;
+//! let pc_id = association.presentation_contexts()[0].id;
+//!
+//! association.send_dimse(&DimseMessage::new(pc_id, CEchoRq::new(1)))?;
+//! let rsp = association.receive_dimse()?;
+//! match rsp.command {
+//!     Command::CEchoRsp(rsp) => println!("C-ECHO status: {}", rsp.status),
+//!     _ => eprintln!("unexpected response"),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::convert::TryFrom;
+
+use dicom_core::{dicom_value, DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::{mem::InMemElement, InMemDicomObject};
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+
+pub mod composite;
+pub mod message;
+pub mod normalized;
+pub mod status;
+
+pub use message::{DimseMessage, MessageAssembler};
+pub use status::{Status, StatusType};
+
+use composite::*;
+use normalized::*;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// failed to encode command set
+    EncodeCommand {
+        #[snafu(source(from(dicom_object::WriteError, Box::new)))]
+        source: Box<dicom_object::WriteError>,
+    },
+
+    /// failed to decode command set
+    DecodeCommand {
+        #[snafu(source(from(dicom_object::ReadError, Box::new)))]
+        source: Box<dicom_object::ReadError>,
+    },
+
+    /// failed to encode data set
+    EncodeDataSet {
+        #[snafu(source(from(dicom_object::WriteError, Box::new)))]
+        source: Box<dicom_object::WriteError>,
+    },
+
+    /// failed to decode data set
+    DecodeDataSet {
+        #[snafu(source(from(dicom_object::ReadError, Box::new)))]
+        source: Box<dicom_object::ReadError>,
+    },
+
+    #[snafu(display("missing command element {}", tag))]
+    MissingCommandElement { tag: Tag, backtrace: Backtrace },
+
+    #[snafu(display("invalid value in command element {}", tag))]
+    InvalidCommandElement {
+        tag: Tag,
+        source: dicom_core::value::ConvertValueError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("unsupported command field {:04X}H", command_field))]
+    UnsupportedCommandField {
+        command_field: u16,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "expected {} message, got {:?}",
+        expected,
+        got.command_field()
+    ))]
+    UnexpectedCommand {
+        expected: CommandField,
+        got: Box<Command>,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "received P-Data value for presentation context {} while assembling a message for presentation context {}",
+        got,
+        expected
+    ))]
+    PresentationContextMismatch {
+        expected: u8,
+        got: u8,
+        backtrace: Backtrace,
+    },
+
+    /// received data set fragment before the command set
+    UnexpectedDataSet { backtrace: Backtrace },
+
+    #[snafu(display("unknown presentation context {}", id))]
+    UnknownPresentationContext { id: u8, backtrace: Backtrace },
+
+    #[snafu(display("unsupported transfer syntax {}", uid))]
+    UnsupportedTransferSyntax { uid: String, backtrace: Backtrace },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The value of the _Command Field_ (0000,0100) command element,
+/// identifying the DIMSE operation.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq, Ord, PartialOrd)]
+#[repr(u16)]
+pub enum CommandField {
+    CStoreRq = 0x0001,
+    CStoreRsp = 0x8001,
+    CGetRq = 0x0010,
+    CGetRsp = 0x8010,
+    CFindRq = 0x0020,
+    CFindRsp = 0x8020,
+    CMoveRq = 0x0021,
+    CMoveRsp = 0x8021,
+    CEchoRq = 0x0030,
+    CEchoRsp = 0x8030,
+    NEventReportRq = 0x0100,
+    NEventReportRsp = 0x8100,
+    NGetRq = 0x0110,
+    NGetRsp = 0x8110,
+    NSetRq = 0x0120,
+    NSetRsp = 0x8120,
+    NActionRq = 0x0130,
+    NActionRsp = 0x8130,
+    NCreateRq = 0x0140,
+    NCreateRsp = 0x8140,
+    NDeleteRq = 0x0150,
+    NDeleteRsp = 0x8150,
+    CCancelRq = 0x0FFF,
+}
+
+impl CommandField {
+    /// Obtain the command field from its code,
+    /// returning `None` if the code is not recognized.
+    pub fn from_code(code: u16) -> Option<Self> {
+        use CommandField::*;
+        let out = match code {
+            0x0001 => CStoreRq,
+            0x8001 => CStoreRsp,
+            0x0010 => CGetRq,
+            0x8010 => CGetRsp,
+            0x0020 => CFindRq,
+            0x8020 => CFindRsp,
+            0x0021 => CMoveRq,
+            0x8021 => CMoveRsp,
+            0x0030 => CEchoRq,
+            0x8030 => CEchoRsp,
+            0x0100 => NEventReportRq,
+            0x8100 => NEventReportRsp,
+            0x0110 => NGetRq,
+            0x8110 => NGetRsp,
+            0x0120 => NSetRq,
+            0x8120 => NSetRsp,
+            0x0130 => NActionRq,
+            0x8130 => NActionRsp,
+            0x0140 => NCreateRq,
+            0x8140 => NCreateRsp,
+            0x0150 => NDeleteRq,
+            0x8150 => NDeleteRsp,
+            0x0FFF => CCancelRq,
+            _ => return None,
+        };
+        Some(out)
+    }
+
+    /// Obtain the code of this command field.
+    #[inline]
+    pub fn code(self) -> u16 {
+        self as u16
+    }
+
+    /// Whether this command field refers to a response message.
+    #[inline]
+    pub fn is_response(self) -> bool {
+        self.code() & 0x8000 != 0
+    }
+}
+
+impl std::fmt::Display for CommandField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use CommandField::*;
+        let name = match self {
+            CStoreRq => "C-STORE-RQ",
+            CStoreRsp => "C-STORE-RSP",
+            CGetRq => "C-GET-RQ",
+            CGetRsp => "C-GET-RSP",
+            CFindRq => "C-FIND-RQ",
+            CFindRsp => "C-FIND-RSP",
+            CMoveRq => "C-MOVE-RQ",
+            CMoveRsp => "C-MOVE-RSP",
+            CEchoRq => "C-ECHO-RQ",
+            CEchoRsp => "C-ECHO-RSP",
+            NEventReportRq => "N-EVENT-REPORT-RQ",
+            NEventReportRsp => "N-EVENT-REPORT-RSP",
+            NGetRq => "N-GET-RQ",
+            NGetRsp => "N-GET-RSP",
+            NSetRq => "N-SET-RQ",
+            NSetRsp => "N-SET-RSP",
+            NActionRq => "N-ACTION-RQ",
+            NActionRsp => "N-ACTION-RSP",
+            NCreateRq => "N-CREATE-RQ",
+            NCreateRsp => "N-CREATE-RSP",
+            NDeleteRq => "N-DELETE-RQ",
+            NDeleteRsp => "N-DELETE-RSP",
+            CCancelRq => "C-CANCEL-RQ",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The priority of a DIMSE-C request,
+/// as in the _Priority_ (0000,0700) command element.
+#[derive(Debug, Default, Copy, Clone, Eq, Hash, PartialEq)]
+pub enum Priority {
+    #[default]
+    Medium,
+    High,
+    Low,
+}
+
+impl Priority {
+    /// Obtain the priority from its code,
+    /// returning `None` if the code is not valid.
+    pub fn from_code(code: u16) -> Option<Self> {
+        match code {
+            0x0000 => Some(Priority::Medium),
+            0x0001 => Some(Priority::High),
+            0x0002 => Some(Priority::Low),
+            _ => None,
+        }
+    }
+
+    /// Obtain the code of this priority.
+    pub fn code(self) -> u16 {
+        match self {
+            Priority::Medium => 0x0000,
+            Priority::High => 0x0001,
+            Priority::Low => 0x0002,
+        }
+    }
+}
+
+/// The value of _Command Data Set Type_ (0000,0800)
+/// which indicates that no data set is present.
+pub const NO_DATA_SET: u16 = 0x0101;
+
+/// The value of _Command Data Set Type_ (0000,0800)
+/// used in this implementation
+/// to indicate that a data set is present.
+pub const DATA_SET_PRESENT: u16 = 0x0000;
+
+/// Common interface for all typed DIMSE commands.
+pub trait DimseCommand: Sized {
+    /// The command field identifying this kind of message.
+    const COMMAND_FIELD: CommandField;
+
+    /// Collect the command elements specific to this message,
+    /// excluding _Command Group Length_, _Command Field_
+    /// and _Command Data Set Type_.
+    fn command_elements(&self) -> Vec<InMemElement>;
+
+    /// Build a command from a decoded command set.
+    ///
+    /// The command field is not checked.
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self>;
+
+    /// Build the full command set of this message,
+    /// ready to be encoded in _Implicit VR Little Endian_.
+    fn to_command_set(&self, has_data_set: bool) -> InMemDicomObject {
+        let mut elements = self.command_elements();
+        elements.push(DataElement::new(
+            tags::COMMAND_FIELD,
+            VR::US,
+            dicom_value!(U16, [Self::COMMAND_FIELD.code()]),
+        ));
+        elements.push(DataElement::new(
+            tags::COMMAND_DATA_SET_TYPE,
+            VR::US,
+            dicom_value!(
+                U16,
+                [if has_data_set {
+                    DATA_SET_PRESENT
+                } else {
+                    NO_DATA_SET
+                }]
+            ),
+        ));
+        InMemDicomObject::command_from_element_iter(elements)
+    }
+}
+
+macro_rules! impl_command_enum {
+    ($($name: ident),* $(,)?) => {
+        /// A DIMSE command of any kind.
+        #[derive(Debug, Clone, PartialEq)]
+        #[non_exhaustive]
+        pub enum Command {
+            $(
+                #[doc = concat!("A ", stringify!($name), " message")]
+                $name($name),
+            )*
+        }
+
+        impl Command {
+            /// Obtain the command field of this command.
+            pub fn command_field(&self) -> CommandField {
+                match self {
+                    $(Command::$name(_) => CommandField::$name,)*
+                }
+            }
+
+            /// Build the full command set of this message,
+            /// ready to be encoded in _Implicit VR Little Endian_.
+            pub fn to_command_set(&self, has_data_set: bool) -> InMemDicomObject {
+                match self {
+                    $(Command::$name(c) => c.to_command_set(has_data_set),)*
+                }
+            }
+
+            /// Build a typed command from a decoded command set,
+            /// according to its command field.
+            pub fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+                let code = read_u16(obj, tags::COMMAND_FIELD)?;
+                let command_field = CommandField::from_code(code)
+                    .context(UnsupportedCommandFieldSnafu { command_field: code })?;
+                match command_field {
+                    $(CommandField::$name => $name::from_command_set(obj).map(Command::$name),)*
+                }
+            }
+        }
+
+        $(
+            impl From<$name> for Command {
+                fn from(value: $name) -> Self {
+                    Command::$name(value)
+                }
+            }
+
+            impl TryFrom<Command> for $name {
+                type Error = Error;
+
+                fn try_from(value: Command) -> Result<Self> {
+                    match value {
+                        Command::$name(c) => Ok(c),
+                        got => UnexpectedCommandSnafu {
+                            expected: CommandField::$name,
+                            got: Box::new(got),
+                        }
+                        .fail(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_command_enum! {
+    CStoreRq,
+    CStoreRsp,
+    CGetRq,
+    CGetRsp,
+    CFindRq,
+    CFindRsp,
+    CMoveRq,
+    CMoveRsp,
+    CEchoRq,
+    CEchoRsp,
+    CCancelRq,
+    NEventReportRq,
+    NEventReportRsp,
+    NGetRq,
+    NGetRsp,
+    NSetRq,
+    NSetRsp,
+    NActionRq,
+    NActionRsp,
+    NCreateRq,
+    NCreateRsp,
+    NDeleteRq,
+    NDeleteRsp,
+}
+
+impl Command {
+    /// Decode a command from the bytes of a command set,
+    /// which are always in _Implicit VR Little Endian_.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        decode_command(data).map(|(command, _)| command)
+    }
+
+    /// Encode this command into the bytes of a command set,
+    /// in _Implicit VR Little Endian_.
+    pub fn encode(&self, has_data_set: bool) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(128);
+        self.to_command_set(has_data_set)
+            .write_dataset_with_ts(
+                &mut data,
+                &dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased(),
+            )
+            .context(EncodeCommandSnafu)?;
+        Ok(data)
+    }
+
+    /// Whether this command is a response message.
+    pub fn is_response(&self) -> bool {
+        self.command_field().is_response()
+    }
+
+    /// Obtain the message ID of this command,
+    /// if it is a request other than C-CANCEL-RQ.
+    pub fn message_id(&self) -> Option<u16> {
+        match self {
+            Command::CStoreRq(c) => Some(c.message_id),
+            Command::CGetRq(c) => Some(c.message_id),
+            Command::CFindRq(c) => Some(c.message_id),
+            Command::CMoveRq(c) => Some(c.message_id),
+            Command::CEchoRq(c) => Some(c.message_id),
+            Command::NEventReportRq(c) => Some(c.message_id),
+            Command::NGetRq(c) => Some(c.message_id),
+            Command::NSetRq(c) => Some(c.message_id),
+            Command::NActionRq(c) => Some(c.message_id),
+            Command::NCreateRq(c) => Some(c.message_id),
+            Command::NDeleteRq(c) => Some(c.message_id),
+            _ => None,
+        }
+    }
+
+    /// Obtain the ID of the message being responded to,
+    /// if this is a response or a C-CANCEL-RQ.
+    pub fn message_id_being_responded_to(&self) -> Option<u16> {
+        match self {
+            Command::CStoreRsp(c) => Some(c.message_id_being_responded_to),
+            Command::CGetRsp(c) => Some(c.message_id_being_responded_to),
+            Command::CFindRsp(c) => Some(c.message_id_being_responded_to),
+            Command::CMoveRsp(c) => Some(c.message_id_being_responded_to),
+            Command::CEchoRsp(c) => Some(c.message_id_being_responded_to),
+            Command::CCancelRq(c) => Some(c.message_id_being_responded_to),
+            Command::NEventReportRsp(c) => Some(c.message_id_being_responded_to),
+            Command::NGetRsp(c) => Some(c.message_id_being_responded_to),
+            Command::NSetRsp(c) => Some(c.message_id_being_responded_to),
+            Command::NActionRsp(c) => Some(c.message_id_being_responded_to),
+            Command::NCreateRsp(c) => Some(c.message_id_being_responded_to),
+            Command::NDeleteRsp(c) => Some(c.message_id_being_responded_to),
+            _ => None,
+        }
+    }
+
+    /// Obtain the status of this command, if it is a response.
+    pub fn status(&self) -> Option<Status> {
+        match self {
+            Command::CStoreRsp(c) => Some(c.status),
+            Command::CGetRsp(c) => Some(c.status),
+            Command::CFindRsp(c) => Some(c.status),
+            Command::CMoveRsp(c) => Some(c.status),
+            Command::CEchoRsp(c) => Some(c.status),
+            Command::NEventReportRsp(c) => Some(c.status),
+            Command::NGetRsp(c) => Some(c.status),
+            Command::NSetRsp(c) => Some(c.status),
+            Command::NActionRsp(c) => Some(c.status),
+            Command::NCreateRsp(c) => Some(c.status),
+            Command::NDeleteRsp(c) => Some(c.status),
+            _ => None,
+        }
+    }
+
+    /// Obtain the SOP class UID which this command refers to,
+    /// either affected or requested.
+    pub fn sop_class_uid(&self) -> Option<&str> {
+        match self {
+            Command::CStoreRq(c) => Some(&c.affected_sop_class_uid),
+            Command::CGetRq(c) => Some(&c.affected_sop_class_uid),
+            Command::CFindRq(c) => Some(&c.affected_sop_class_uid),
+            Command::CMoveRq(c) => Some(&c.affected_sop_class_uid),
+            Command::CEchoRq(c) => Some(&c.affected_sop_class_uid),
+            Command::NEventReportRq(c) => Some(&c.affected_sop_class_uid),
+            Command::NGetRq(c) => Some(&c.requested_sop_class_uid),
+            Command::NSetRq(c) => Some(&c.requested_sop_class_uid),
+            Command::NActionRq(c) => Some(&c.requested_sop_class_uid),
+            Command::NCreateRq(c) => Some(&c.affected_sop_class_uid),
+            Command::NDeleteRq(c) => Some(&c.requested_sop_class_uid),
+            Command::CStoreRsp(c) => c.affected_sop_class_uid.as_deref(),
+            Command::CGetRsp(c) => c.affected_sop_class_uid.as_deref(),
+            Command::CFindRsp(c) => c.affected_sop_class_uid.as_deref(),
+            Command::CMoveRsp(c) => c.affected_sop_class_uid.as_deref(),
+            Command::CEchoRsp(c) => c.affected_sop_class_uid.as_deref(),
+            Command::NEventReportRsp(c) => c.affected_sop_class_uid.as_deref(),
+            Command::NGetRsp(c) => c.affected_sop_class_uid.as_deref(),
+            Command::NSetRsp(c) => c.affected_sop_class_uid.as_deref(),
+            Command::NActionRsp(c) => c.affected_sop_class_uid.as_deref(),
+            Command::NCreateRsp(c) => c.affected_sop_class_uid.as_deref(),
+            Command::NDeleteRsp(c) => c.affected_sop_class_uid.as_deref(),
+            Command::CCancelRq(_) => None,
+        }
+    }
+
+    /// Obtain the SOP instance UID which this command refers to,
+    /// either affected or requested, if any.
+    pub fn sop_instance_uid(&self) -> Option<&str> {
+        match self {
+            Command::CStoreRq(c) => Some(&c.affected_sop_instance_uid),
+            Command::NEventReportRq(c) => Some(&c.affected_sop_instance_uid),
+            Command::NGetRq(c) => Some(&c.requested_sop_instance_uid),
+            Command::NSetRq(c) => Some(&c.requested_sop_instance_uid),
+            Command::NActionRq(c) => Some(&c.requested_sop_instance_uid),
+            Command::NCreateRq(c) => c.affected_sop_instance_uid.as_deref(),
+            Command::NDeleteRq(c) => Some(&c.requested_sop_instance_uid),
+            Command::CStoreRsp(c) => c.affected_sop_instance_uid.as_deref(),
+            Command::NEventReportRsp(c) => c.affected_sop_instance_uid.as_deref(),
+            Command::NGetRsp(c) => c.affected_sop_instance_uid.as_deref(),
+            Command::NSetRsp(c) => c.affected_sop_instance_uid.as_deref(),
+            Command::NActionRsp(c) => c.affected_sop_instance_uid.as_deref(),
+            Command::NCreateRsp(c) => c.affected_sop_instance_uid.as_deref(),
+            Command::NDeleteRsp(c) => c.affected_sop_instance_uid.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Decode a command set,
+/// also returning whether it announces a data set.
+fn decode_command(data: &[u8]) -> Result<(Command, bool)> {
+    let obj = InMemDicomObject::read_dataset_with_ts(
+        data,
+        &dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased(),
+    )
+    .context(DecodeCommandSnafu)?;
+    let has_data_set = read_opt_u16(&obj, tags::COMMAND_DATA_SET_TYPE)?
+        .map(|v| v != NO_DATA_SET)
+        .unwrap_or(false);
+    Ok((Command::from_command_set(&obj)?, has_data_set))
+}
+
+// --- command set element helpers ---
+
+/// Trim the padding of a text value in a command set.
+fn trim_value(s: &str) -> &str {
+    s.trim_end_matches(|c: char| c.is_whitespace() || c == '\0')
+}
+
+fn read_u16(obj: &InMemDicomObject, tag: Tag) -> Result<u16> {
+    obj.get(tag)
+        .context(MissingCommandElementSnafu { tag })?
+        .to_int::<u16>()
+        .context(InvalidCommandElementSnafu { tag })
+}
+
+fn read_opt_u16(obj: &InMemDicomObject, tag: Tag) -> Result<Option<u16>> {
+    match obj.get(tag) {
+        Some(e) if e.value().multiplicity() > 0 => e
+            .to_int::<u16>()
+            .map(Some)
+            .context(InvalidCommandElementSnafu { tag }),
+        _ => Ok(None),
+    }
+}
+
+fn read_str(obj: &InMemDicomObject, tag: Tag) -> Result<String> {
+    read_opt_str(obj, tag)?.context(MissingCommandElementSnafu { tag })
+}
+
+fn read_opt_str(obj: &InMemDicomObject, tag: Tag) -> Result<Option<String>> {
+    match obj.get(tag) {
+        Some(e) => {
+            let value = e.to_str().context(InvalidCommandElementSnafu { tag })?;
+            Ok(Some(trim_value(&value).to_string()))
+        }
+        None => Ok(None),
+    }
+}
+
+fn read_status(obj: &InMemDicomObject) -> Result<Status> {
+    read_u16(obj, tags::STATUS).map(Status)
+}
+
+fn read_priority(obj: &InMemDicomObject) -> Result<Priority> {
+    // be lenient on priority, which is of no consequence to most providers
+    Ok(read_opt_u16(obj, tags::PRIORITY)?
+        .and_then(Priority::from_code)
+        .unwrap_or_default())
+}
+
+fn read_tags(obj: &InMemDicomObject, tag: Tag) -> Vec<Tag> {
+    match obj.get(tag).and_then(|e| e.value().primitive()) {
+        Some(PrimitiveValue::Tags(tags)) => tags.to_vec(),
+        _ => Vec::new(),
+    }
+}
+
+fn uid_element(tag: Tag, uid: &str) -> InMemElement {
+    DataElement::new(tag, VR::UI, PrimitiveValue::from(uid))
+}
+
+fn us_element(tag: Tag, value: u16) -> InMemElement {
+    DataElement::new(tag, VR::US, dicom_value!(U16, [value]))
+}
+
+fn push_opt_uid(elements: &mut Vec<InMemElement>, tag: Tag, uid: Option<&str>) {
+    if let Some(uid) = uid {
+        elements.push(uid_element(tag, uid));
+    }
+}
+
+fn push_opt_us(elements: &mut Vec<InMemElement>, tag: Tag, value: Option<u16>) {
+    if let Some(value) = value {
+        elements.push(us_element(tag, value));
+    }
+}
+
+fn push_error_comment(elements: &mut Vec<InMemElement>, error_comment: Option<&str>) {
+    if let Some(comment) = error_comment {
+        elements.push(DataElement::new(
+            tags::ERROR_COMMENT,
+            VR::LO,
+            PrimitiveValue::from(comment),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_field_codes() {
+        for code in 0..=0xFFFF_u16 {
+            if let Some(field) = CommandField::from_code(code) {
+                assert_eq!(field.code(), code);
+                assert_eq!(field.is_response(), code & 0x8000 != 0);
+            }
+        }
+        assert_eq!(CommandField::CFindRq.to_string(), "C-FIND-RQ");
+    }
+
+    #[test]
+    fn echo_roundtrip() {
+        let cmd = Command::from(CEchoRq::new(7));
+        let data = cmd.encode(false).unwrap();
+        let obj = InMemDicomObject::read_dataset_with_ts(
+            &data[..],
+            &dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased(),
+        )
+        .unwrap();
+        assert_eq!(
+            obj.get(tags::COMMAND_FIELD)
+                .unwrap()
+                .to_int::<u16>()
+                .unwrap(),
+            0x0030
+        );
+        assert_eq!(
+            obj.get(tags::COMMAND_DATA_SET_TYPE)
+                .unwrap()
+                .to_int::<u16>()
+                .unwrap(),
+            NO_DATA_SET
+        );
+        let decoded = Command::decode(&data).unwrap();
+        assert_eq!(decoded, cmd);
+        assert_eq!(decoded.message_id(), Some(7));
+        assert_eq!(decoded.sop_class_uid(), Some("1.2.840.10008.1.1"));
+    }
+
+    #[test]
+    fn move_response_roundtrip() {
+        let rq = CMoveRq::new(3, "1.2.840.10008.5.1.4.1.2.2.2", "STORE-SCP");
+        let rsp = CMoveRsp::new(&rq, Status::PENDING).with_sub_operations(5, 2, 1, 0);
+        let cmd = Command::from(rsp.clone());
+        let data = cmd.encode(false).unwrap();
+        let decoded = Command::decode(&data).unwrap();
+        assert_eq!(decoded.status(), Some(Status::PENDING));
+        assert_eq!(decoded.message_id_being_responded_to(), Some(3));
+        let decoded = CMoveRsp::try_from(decoded).unwrap();
+        assert_eq!(decoded, rsp);
+    }
+
+    #[test]
+    fn store_sop_instance_uid() {
+        let rq = CStoreRq::new(1, "1.2.840.10008.5.1.4.1.1.2", "2.25.1");
+        let rsp = CStoreRsp::new(&rq, Status::SUCCESS);
+        assert_eq!(Command::from(rq).sop_instance_uid(), Some("2.25.1"));
+        assert_eq!(Command::from(rsp).sop_instance_uid(), Some("2.25.1"));
+        assert_eq!(Command::from(CEchoRq::new(2)).sop_instance_uid(), None);
+    }
+
+    #[test]
+    fn unsupported_command_field() {
+        let obj =
+            InMemDicomObject::command_from_element_iter([us_element(tags::COMMAND_FIELD, 0x0042)]);
+        assert!(matches!(
+            Command::from_command_set(&obj),
+            Err(Error::UnsupportedCommandField {
+                command_field: 0x0042,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,654 @@
+//! DIMSE-N messages
+//!
+//! See the standard, part 7, section 10.3.
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::{mem::InMemElement, InMemDicomObject};
+
+use super::{
+    push_error_comment, push_opt_uid, push_opt_us, read_opt_str, read_opt_u16, read_status,
+    read_str, read_tags, read_u16, uid_element, us_element, CommandField, DimseCommand, Result,
+    Status,
+};
+
+/// Collect the command elements shared by all DIMSE-N responses.
+fn response_elements(
+    message_id_being_responded_to: u16,
+    affected_sop_class_uid: Option<&str>,
+    affected_sop_instance_uid: Option<&str>,
+    status: Status,
+    error_comment: Option<&str>,
+) -> Vec<InMemElement> {
+    let mut elements = vec![
+        us_element(
+            tags::MESSAGE_ID_BEING_RESPONDED_TO,
+            message_id_being_responded_to,
+        ),
+        us_element(tags::STATUS, status.0),
+    ];
+    push_opt_uid(
+        &mut elements,
+        tags::AFFECTED_SOP_CLASS_UID,
+        affected_sop_class_uid,
+    );
+    push_opt_uid(
+        &mut elements,
+        tags::AFFECTED_SOP_INSTANCE_UID,
+        affected_sop_instance_uid,
+    );
+    push_error_comment(&mut elements, error_comment);
+    elements
+}
+
+/// N-EVENT-REPORT request
+///
+/// The event information is sent as the message's optional data set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NEventReportRq {
+    pub message_id: u16,
+    pub affected_sop_class_uid: String,
+    pub affected_sop_instance_uid: String,
+    pub event_type_id: u16,
+}
+
+impl NEventReportRq {
+    /// Create an N-EVENT-REPORT request.
+    pub fn new(
+        message_id: u16,
+        affected_sop_class_uid: impl Into<String>,
+        affected_sop_instance_uid: impl Into<String>,
+        event_type_id: u16,
+    ) -> Self {
+        NEventReportRq {
+            message_id,
+            affected_sop_class_uid: affected_sop_class_uid.into(),
+            affected_sop_instance_uid: affected_sop_instance_uid.into(),
+            event_type_id,
+        }
+    }
+}
+
+impl DimseCommand for NEventReportRq {
+    const COMMAND_FIELD: CommandField = CommandField::NEventReportRq;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        vec![
+            uid_element(tags::AFFECTED_SOP_CLASS_UID, &self.affected_sop_class_uid),
+            us_element(tags::MESSAGE_ID, self.message_id),
+            uid_element(
+                tags::AFFECTED_SOP_INSTANCE_UID,
+                &self.affected_sop_instance_uid,
+            ),
+            us_element(tags::EVENT_TYPE_ID, self.event_type_id),
+        ]
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(NEventReportRq {
+            message_id: read_u16(obj, tags::MESSAGE_ID)?,
+            affected_sop_class_uid: read_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            affected_sop_instance_uid: read_str(obj, tags::AFFECTED_SOP_INSTANCE_UID)?,
+            event_type_id: read_u16(obj, tags::EVENT_TYPE_ID)?,
+        })
+    }
+}
+
+/// N-EVENT-REPORT response
+///
+/// The event reply is sent as the message's optional data set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NEventReportRsp {
+    pub message_id_being_responded_to: u16,
+    pub affected_sop_class_uid: Option<String>,
+    pub affected_sop_instance_uid: Option<String>,
+    pub event_type_id: Option<u16>,
+    pub status: Status,
+    pub error_comment: Option<String>,
+}
+
+impl NEventReportRsp {
+    /// Create a response to the given N-EVENT-REPORT request.
+    pub fn new(rq: &NEventReportRq, status: Status) -> Self {
+        NEventReportRsp {
+            message_id_being_responded_to: rq.message_id,
+            affected_sop_class_uid: Some(rq.affected_sop_class_uid.clone()),
+            affected_sop_instance_uid: Some(rq.affected_sop_instance_uid.clone()),
+            event_type_id: Some(rq.event_type_id),
+            status,
+            error_comment: None,
+        }
+    }
+}
+
+impl DimseCommand for NEventReportRsp {
+    const COMMAND_FIELD: CommandField = CommandField::NEventReportRsp;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        let mut elements = response_elements(
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            self.affected_sop_instance_uid.as_deref(),
+            self.status,
+            self.error_comment.as_deref(),
+        );
+        push_opt_us(&mut elements, tags::EVENT_TYPE_ID, self.event_type_id);
+        elements
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(NEventReportRsp {
+            message_id_being_responded_to: read_u16(obj, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+            affected_sop_class_uid: read_opt_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            affected_sop_instance_uid: read_opt_str(obj, tags::AFFECTED_SOP_INSTANCE_UID)?,
+            event_type_id: read_opt_u16(obj, tags::EVENT_TYPE_ID)?,
+            status: read_status(obj)?,
+            error_comment: read_opt_str(obj, tags::ERROR_COMMENT)?,
+        })
+    }
+}
+
+/// N-GET request
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NGetRq {
+    pub message_id: u16,
+    pub requested_sop_class_uid: String,
+    pub requested_sop_instance_uid: String,
+    /// the attributes to retrieve,
+    /// all attributes are requested if empty
+    pub attribute_identifier_list: Vec<Tag>,
+}
+
+impl NGetRq {
+    /// Create an N-GET request for all attributes of the given instance.
+    pub fn new(
+        message_id: u16,
+        requested_sop_class_uid: impl Into<String>,
+        requested_sop_instance_uid: impl Into<String>,
+    ) -> Self {
+        NGetRq {
+            message_id,
+            requested_sop_class_uid: requested_sop_class_uid.into(),
+            requested_sop_instance_uid: requested_sop_instance_uid.into(),
+            attribute_identifier_list: Vec::new(),
+        }
+    }
+}
+
+impl DimseCommand for NGetRq {
+    const COMMAND_FIELD: CommandField = CommandField::NGetRq;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        let mut elements = vec![
+            uid_element(tags::REQUESTED_SOP_CLASS_UID, &self.requested_sop_class_uid),
+            us_element(tags::MESSAGE_ID, self.message_id),
+            uid_element(
+                tags::REQUESTED_SOP_INSTANCE_UID,
+                &self.requested_sop_instance_uid,
+            ),
+        ];
+        if !self.attribute_identifier_list.is_empty() {
+            elements.push(DataElement::new(
+                tags::ATTRIBUTE_IDENTIFIER_LIST,
+                VR::AT,
+                PrimitiveValue::Tags(self.attribute_identifier_list.iter().copied().collect()),
+            ));
+        }
+        elements
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(NGetRq {
+            message_id: read_u16(obj, tags::MESSAGE_ID)?,
+            requested_sop_class_uid: read_str(obj, tags::REQUESTED_SOP_CLASS_UID)?,
+            requested_sop_instance_uid: read_str(obj, tags::REQUESTED_SOP_INSTANCE_UID)?,
+            attribute_identifier_list: read_tags(obj, tags::ATTRIBUTE_IDENTIFIER_LIST),
+        })
+    }
+}
+
+/// N-GET response
+///
+/// The attribute list is sent as the message's data set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NGetRsp {
+    pub message_id_being_responded_to: u16,
+    pub affected_sop_class_uid: Option<String>,
+    pub affected_sop_instance_uid: Option<String>,
+    pub status: Status,
+    pub error_comment: Option<String>,
+}
+
+impl NGetRsp {
+    /// Create a response to the given N-GET request.
+    pub fn new(rq: &NGetRq, status: Status) -> Self {
+        NGetRsp {
+            message_id_being_responded_to: rq.message_id,
+            affected_sop_class_uid: Some(rq.requested_sop_class_uid.clone()),
+            affected_sop_instance_uid: Some(rq.requested_sop_instance_uid.clone()),
+            status,
+            error_comment: None,
+        }
+    }
+}
+
+impl DimseCommand for NGetRsp {
+    const COMMAND_FIELD: CommandField = CommandField::NGetRsp;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        response_elements(
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            self.affected_sop_instance_uid.as_deref(),
+            self.status,
+            self.error_comment.as_deref(),
+        )
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(NGetRsp {
+            message_id_being_responded_to: read_u16(obj, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+            affected_sop_class_uid: read_opt_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            affected_sop_instance_uid: read_opt_str(obj, tags::AFFECTED_SOP_INSTANCE_UID)?,
+            status: read_status(obj)?,
+            error_comment: read_opt_str(obj, tags::ERROR_COMMENT)?,
+        })
+    }
+}
+
+/// N-SET request
+///
+/// The modification list is sent as the message's data set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NSetRq {
+    pub message_id: u16,
+    pub requested_sop_class_uid: String,
+    pub requested_sop_instance_uid: String,
+}
+
+impl NSetRq {
+    /// Create an N-SET request.
+    pub fn new(
+        message_id: u16,
+        requested_sop_class_uid: impl Into<String>,
+        requested_sop_instance_uid: impl Into<String>,
+    ) -> Self {
+        NSetRq {
+            message_id,
+            requested_sop_class_uid: requested_sop_class_uid.into(),
+            requested_sop_instance_uid: requested_sop_instance_uid.into(),
+        }
+    }
+}
+
+impl DimseCommand for NSetRq {
+    const COMMAND_FIELD: CommandField = CommandField::NSetRq;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        vec![
+            uid_element(tags::REQUESTED_SOP_CLASS_UID, &self.requested_sop_class_uid),
+            us_element(tags::MESSAGE_ID, self.message_id),
+            uid_element(
+                tags::REQUESTED_SOP_INSTANCE_UID,
+                &self.requested_sop_instance_uid,
+            ),
+        ]
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(NSetRq {
+            message_id: read_u16(obj, tags::MESSAGE_ID)?,
+            requested_sop_class_uid: read_str(obj, tags::REQUESTED_SOP_CLASS_UID)?,
+            requested_sop_instance_uid: read_str(obj, tags::REQUESTED_SOP_INSTANCE_UID)?,
+        })
+    }
+}
+
+/// N-SET response
+///
+/// The resulting attribute list may be sent as the message's data set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NSetRsp {
+    pub message_id_being_responded_to: u16,
+    pub affected_sop_class_uid: Option<String>,
+    pub affected_sop_instance_uid: Option<String>,
+    pub status: Status,
+    pub error_comment: Option<String>,
+}
+
+impl NSetRsp {
+    /// Create a response to the given N-SET request.
+    pub fn new(rq: &NSetRq, status: Status) -> Self {
+        NSetRsp {
+            message_id_being_responded_to: rq.message_id,
+            affected_sop_class_uid: Some(rq.requested_sop_class_uid.clone()),
+            affected_sop_instance_uid: Some(rq.requested_sop_instance_uid.clone()),
+            status,
+            error_comment: None,
+        }
+    }
+}
+
+impl DimseCommand for NSetRsp {
+    const COMMAND_FIELD: CommandField = CommandField::NSetRsp;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        response_elements(
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            self.affected_sop_instance_uid.as_deref(),
+            self.status,
+            self.error_comment.as_deref(),
+        )
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(NSetRsp {
+            message_id_being_responded_to: read_u16(obj, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+            affected_sop_class_uid: read_opt_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            affected_sop_instance_uid: read_opt_str(obj, tags::AFFECTED_SOP_INSTANCE_UID)?,
+            status: read_status(obj)?,
+            error_comment: read_opt_str(obj, tags::ERROR_COMMENT)?,
+        })
+    }
+}
+
+/// N-ACTION request
+///
+/// The action information is sent as the message's optional data set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NActionRq {
+    pub message_id: u16,
+    pub requested_sop_class_uid: String,
+    pub requested_sop_instance_uid: String,
+    pub action_type_id: u16,
+}
+
+impl NActionRq {
+    /// Create an N-ACTION request.
+    pub fn new(
+        message_id: u16,
+        requested_sop_class_uid: impl Into<String>,
+        requested_sop_instance_uid: impl Into<String>,
+        action_type_id: u16,
+    ) -> Self {
+        NActionRq {
+            message_id,
+            requested_sop_class_uid: requested_sop_class_uid.into(),
+            requested_sop_instance_uid: requested_sop_instance_uid.into(),
+            action_type_id,
+        }
+    }
+}
+
+impl DimseCommand for NActionRq {
+    const COMMAND_FIELD: CommandField = CommandField::NActionRq;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        vec![
+            uid_element(tags::REQUESTED_SOP_CLASS_UID, &self.requested_sop_class_uid),
+            us_element(tags::MESSAGE_ID, self.message_id),
+            uid_element(
+                tags::REQUESTED_SOP_INSTANCE_UID,
+                &self.requested_sop_instance_uid,
+            ),
+            us_element(tags::ACTION_TYPE_ID, self.action_type_id),
+        ]
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(NActionRq {
+            message_id: read_u16(obj, tags::MESSAGE_ID)?,
+            requested_sop_class_uid: read_str(obj, tags::REQUESTED_SOP_CLASS_UID)?,
+            requested_sop_instance_uid: read_str(obj, tags::REQUESTED_SOP_INSTANCE_UID)?,
+            action_type_id: read_u16(obj, tags::ACTION_TYPE_ID)?,
+        })
+    }
+}
+
+/// N-ACTION response
+///
+/// The action reply may be sent as the message's data set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NActionRsp {
+    pub message_id_being_responded_to: u16,
+    pub affected_sop_class_uid: Option<String>,
+    pub affected_sop_instance_uid: Option<String>,
+    pub action_type_id: Option<u16>,
+    pub status: Status,
+    pub error_comment: Option<String>,
+}
+
+impl NActionRsp {
+    /// Create a response to the given N-ACTION request.
+    pub fn new(rq: &NActionRq, status: Status) -> Self {
+        NActionRsp {
+            message_id_being_responded_to: rq.message_id,
+            affected_sop_class_uid: Some(rq.requested_sop_class_uid.clone()),
+            affected_sop_instance_uid: Some(rq.requested_sop_instance_uid.clone()),
+            action_type_id: Some(rq.action_type_id),
+            status,
+            error_comment: None,
+        }
+    }
+}
+
+impl DimseCommand for NActionRsp {
+    const COMMAND_FIELD: CommandField = CommandField::NActionRsp;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        let mut elements = response_elements(
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            self.affected_sop_instance_uid.as_deref(),
+            self.status,
+            self.error_comment.as_deref(),
+        );
+        push_opt_us(&mut elements, tags::ACTION_TYPE_ID, self.action_type_id);
+        elements
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(NActionRsp {
+            message_id_being_responded_to: read_u16(obj, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+            affected_sop_class_uid: read_opt_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            affected_sop_instance_uid: read_opt_str(obj, tags::AFFECTED_SOP_INSTANCE_UID)?,
+            action_type_id: read_opt_u16(obj, tags::ACTION_TYPE_ID)?,
+            status: read_status(obj)?,
+            error_comment: read_opt_str(obj, tags::ERROR_COMMENT)?,
+        })
+    }
+}
+
+/// N-CREATE request
+///
+/// The initial attribute values are sent as the message's optional data set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NCreateRq {
+    pub message_id: u16,
+    pub affected_sop_class_uid: String,
+    /// the UID of the instance to create,
+    /// to be assigned by the performing node if absent
+    pub affected_sop_instance_uid: Option<String>,
+}
+
+impl NCreateRq {
+    /// Create an N-CREATE request.
+    pub fn new(
+        message_id: u16,
+        affected_sop_class_uid: impl Into<String>,
+        affected_sop_instance_uid: Option<String>,
+    ) -> Self {
+        NCreateRq {
+            message_id,
+            affected_sop_class_uid: affected_sop_class_uid.into(),
+            affected_sop_instance_uid,
+        }
+    }
+}
+
+impl DimseCommand for NCreateRq {
+    const COMMAND_FIELD: CommandField = CommandField::NCreateRq;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        let mut elements = vec![
+            uid_element(tags::AFFECTED_SOP_CLASS_UID, &self.affected_sop_class_uid),
+            us_element(tags::MESSAGE_ID, self.message_id),
+        ];
+        push_opt_uid(
+            &mut elements,
+            tags::AFFECTED_SOP_INSTANCE_UID,
+            self.affected_sop_instance_uid.as_deref(),
+        );
+        elements
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(NCreateRq {
+            message_id: read_u16(obj, tags::MESSAGE_ID)?,
+            affected_sop_class_uid: read_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            affected_sop_instance_uid: read_opt_str(obj, tags::AFFECTED_SOP_INSTANCE_UID)?,
+        })
+    }
+}
+
+/// N-CREATE response
+///
+/// The resulting attribute list may be sent as the message's data set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NCreateRsp {
+    pub message_id_being_responded_to: u16,
+    pub affected_sop_class_uid: Option<String>,
+    pub affected_sop_instance_uid: Option<String>,
+    pub status: Status,
+    pub error_comment: Option<String>,
+}
+
+impl NCreateRsp {
+    /// Create a response to the given N-CREATE request.
+    pub fn new(rq: &NCreateRq, status: Status) -> Self {
+        NCreateRsp {
+            message_id_being_responded_to: rq.message_id,
+            affected_sop_class_uid: Some(rq.affected_sop_class_uid.clone()),
+            affected_sop_instance_uid: rq.affected_sop_instance_uid.clone(),
+            status,
+            error_comment: None,
+        }
+    }
+}
+
+impl DimseCommand for NCreateRsp {
+    const COMMAND_FIELD: CommandField = CommandField::NCreateRsp;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        response_elements(
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            self.affected_sop_instance_uid.as_deref(),
+            self.status,
+            self.error_comment.as_deref(),
+        )
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(NCreateRsp {
+            message_id_being_responded_to: read_u16(obj, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+            affected_sop_class_uid: read_opt_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            affected_sop_instance_uid: read_opt_str(obj, tags::AFFECTED_SOP_INSTANCE_UID)?,
+            status: read_status(obj)?,
+            error_comment: read_opt_str(obj, tags::ERROR_COMMENT)?,
+        })
+    }
+}
+
+/// N-DELETE request
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NDeleteRq {
+    pub message_id: u16,
+    pub requested_sop_class_uid: String,
+    pub requested_sop_instance_uid: String,
+}
+
+impl NDeleteRq {
+    /// Create an N-DELETE request.
+    pub fn new(
+        message_id: u16,
+        requested_sop_class_uid: impl Into<String>,
+        requested_sop_instance_uid: impl Into<String>,
+    ) -> Self {
+        NDeleteRq {
+            message_id,
+            requested_sop_class_uid: requested_sop_class_uid.into(),
+            requested_sop_instance_uid: requested_sop_instance_uid.into(),
+        }
+    }
+}
+
+impl DimseCommand for NDeleteRq {
+    const COMMAND_FIELD: CommandField = CommandField::NDeleteRq;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        vec![
+            uid_element(tags::REQUESTED_SOP_CLASS_UID, &self.requested_sop_class_uid),
+            us_element(tags::MESSAGE_ID, self.message_id),
+            uid_element(
+                tags::REQUESTED_SOP_INSTANCE_UID,
+                &self.requested_sop_instance_uid,
+            ),
+        ]
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(NDeleteRq {
+            message_id: read_u16(obj, tags::MESSAGE_ID)?,
+            requested_sop_class_uid: read_str(obj, tags::REQUESTED_SOP_CLASS_UID)?,
+            requested_sop_instance_uid: read_str(obj, tags::REQUESTED_SOP_INSTANCE_UID)?,
+        })
+    }
+}
+
+/// N-DELETE response
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NDeleteRsp {
+    pub message_id_being_responded_to: u16,
+    pub affected_sop_class_uid: Option<String>,
+    pub affected_sop_instance_uid: Option<String>,
+    pub status: Status,
+    pub error_comment: Option<String>,
+}
+
+impl NDeleteRsp {
+    /// Create a response to the given N-DELETE request.
+    pub fn new(rq: &NDeleteRq, status: Status) -> Self {
+        NDeleteRsp {
+            message_id_being_responded_to: rq.message_id,
+            affected_sop_class_uid: Some(rq.requested_sop_class_uid.clone()),
+            affected_sop_instance_uid: Some(rq.requested_sop_instance_uid.clone()),
+            status,
+            error_comment: None,
+        }
+    }
+}
+
+impl DimseCommand for NDeleteRsp {
+    const COMMAND_FIELD: CommandField = CommandField::NDeleteRsp;
+
+    fn command_elements(&self) -> Vec<InMemElement> {
+        response_elements(
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            self.affected_sop_instance_uid.as_deref(),
+            self.status,
+            self.error_comment.as_deref(),
+        )
+    }
+
+    fn from_command_set(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(NDeleteRsp {
+            message_id_being_responded_to: read_u16(obj, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+            affected_sop_class_uid: read_opt_str(obj, tags::AFFECTED_SOP_CLASS_UID)?,
+            affected_sop_instance_uid: read_opt_str(obj, tags::AFFECTED_SOP_INSTANCE_UID)?,
+            status: read_status(obj)?,
+            error_comment: read_opt_str(obj, tags::ERROR_COMMENT)?,
+        })
+    }
+}
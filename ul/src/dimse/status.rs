@@ -0,0 +1,208 @@
+//! DIMSE status codes
+//!
+//! See the standard, part 7, annex C.
+
+use std::fmt;
+
+/// A DIMSE status code,
+/// as found in the _Status_ (0000,0900) command element
+/// of every DIMSE response.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq, Ord, PartialOrd)]
+pub struct Status(pub u16);
+
+impl Status {
+    /// Success
+    pub const SUCCESS: Status = Status(0x0000);
+    /// Attribute list error (warning)
+    pub const ATTRIBUTE_LIST_ERROR: Status = Status(0x0107);
+    /// Attribute value out of range (warning)
+    pub const ATTRIBUTE_VALUE_OUT_OF_RANGE: Status = Status(0x0116);
+    /// Sub-operations complete, one or more failures or warnings (warning)
+    pub const SUB_OPERATIONS_COMPLETE_WITH_FAILURES: Status = Status(0xB000);
+    /// Coercion of data elements (warning)
+    pub const COERCION_OF_DATA_ELEMENTS: Status = Status(0xB000);
+    /// Refused: SOP class not supported
+    pub const SOP_CLASS_NOT_SUPPORTED: Status = Status(0x0122);
+    /// Class-instance conflict
+    pub const CLASS_INSTANCE_CONFLICT: Status = Status(0x0119);
+    /// Duplicate SOP instance
+    pub const DUPLICATE_SOP_INSTANCE: Status = Status(0x0111);
+    /// Duplicate invocation
+    pub const DUPLICATE_INVOCATION: Status = Status(0x0210);
+    /// Invalid argument value
+    pub const INVALID_ARGUMENT_VALUE: Status = Status(0x0115);
+    /// Invalid attribute value
+    pub const INVALID_ATTRIBUTE_VALUE: Status = Status(0x0106);
+    /// Invalid object instance
+    pub const INVALID_OBJECT_INSTANCE: Status = Status(0x0117);
+    /// Missing attribute
+    pub const MISSING_ATTRIBUTE: Status = Status(0x0120);
+    /// Missing attribute value
+    pub const MISSING_ATTRIBUTE_VALUE: Status = Status(0x0121);
+    /// Mistyped argument
+    pub const MISTYPED_ARGUMENT: Status = Status(0x0212);
+    /// No such action type
+    pub const NO_SUCH_ACTION_TYPE: Status = Status(0x0123);
+    /// No such argument
+    pub const NO_SUCH_ARGUMENT: Status = Status(0x0114);
+    /// No such attribute
+    pub const NO_SUCH_ATTRIBUTE: Status = Status(0x0105);
+    /// No such event type
+    pub const NO_SUCH_EVENT_TYPE: Status = Status(0x0113);
+    /// No such SOP instance
+    pub const NO_SUCH_SOP_INSTANCE: Status = Status(0x0112);
+    /// No such SOP class
+    pub const NO_SUCH_SOP_CLASS: Status = Status(0x0118);
+    /// Not authorized
+    pub const NOT_AUTHORIZED: Status = Status(0x0124);
+    /// Processing failure
+    pub const PROCESSING_FAILURE: Status = Status(0x0110);
+    /// Resource limitation
+    pub const RESOURCE_LIMITATION: Status = Status(0x0213);
+    /// Unrecognized operation
+    pub const UNRECOGNIZED_OPERATION: Status = Status(0x0211);
+    /// Refused: out of resources
+    pub const OUT_OF_RESOURCES: Status = Status(0xA700);
+    /// Refused: out of resources, unable to calculate number of matches
+    pub const OUT_OF_RESOURCES_MATCHES: Status = Status(0xA701);
+    /// Refused: out of resources, unable to perform sub-operations
+    pub const OUT_OF_RESOURCES_SUB_OPERATIONS: Status = Status(0xA702);
+    /// Refused: move destination unknown
+    pub const MOVE_DESTINATION_UNKNOWN: Status = Status(0xA801);
+    /// Error: data set does not match SOP class
+    pub const DATA_SET_DOES_NOT_MATCH_SOP_CLASS: Status = Status(0xA900);
+    /// Failed: identifier does not match SOP class
+    pub const IDENTIFIER_DOES_NOT_MATCH_SOP_CLASS: Status = Status(0xA900);
+    /// Error: cannot understand / unable to process
+    pub const UNABLE_TO_PROCESS: Status = Status(0xC000);
+    /// Cancel: the operation was terminated due to a cancel request
+    pub const CANCEL: Status = Status(0xFE00);
+    /// Pending: matches are continuing
+    pub const PENDING: Status = Status(0xFF00);
+    /// Pending: matches are continuing,
+    /// but one or more optional keys were not supported
+    pub const PENDING_WARNING: Status = Status(0xFF01);
+
+    /// Obtain the raw status code.
+    #[inline]
+    pub fn code(self) -> u16 {
+        self.0
+    }
+
+    /// Classify this status code into one of the status types
+    /// defined in the standard.
+    pub fn status_type(self) -> StatusType {
+        match self.0 {
+            0x0000 => StatusType::Success,
+            0x0001 | 0x0107 | 0x0116 | 0xB000..=0xBFFF => StatusType::Warning,
+            0xFE00 => StatusType::Cancel,
+            0xFF00 | 0xFF01 => StatusType::Pending,
+            _ => StatusType::Failure,
+        }
+    }
+
+    /// Whether the status is of type success.
+    #[inline]
+    pub fn is_success(self) -> bool {
+        self.status_type() == StatusType::Success
+    }
+
+    /// Whether the status is of type warning.
+    #[inline]
+    pub fn is_warning(self) -> bool {
+        self.status_type() == StatusType::Warning
+    }
+
+    /// Whether the status is of type failure.
+    #[inline]
+    pub fn is_failure(self) -> bool {
+        self.status_type() == StatusType::Failure
+    }
+
+    /// Whether the status indicates that the operation was cancelled.
+    #[inline]
+    pub fn is_cancel(self) -> bool {
+        self.status_type() == StatusType::Cancel
+    }
+
+    /// Whether the status indicates that more responses are to follow.
+    #[inline]
+    pub fn is_pending(self) -> bool {
+        self.status_type() == StatusType::Pending
+    }
+
+    /// Whether the status is final,
+    /// meaning that no more responses are expected
+    /// for the same request.
+    #[inline]
+    pub fn is_final(self) -> bool {
+        !self.is_pending()
+    }
+}
+
+impl From<u16> for Status {
+    fn from(code: u16) -> Self {
+        Status(code)
+    }
+}
+
+impl From<Status> for u16 {
+    fn from(status: Status) -> Self {
+        status.0
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04X}H ({})", self.0, self.status_type())
+    }
+}
+
+/// The category of a DIMSE status code.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub enum StatusType {
+    /// The operation was successful
+    Success,
+    /// The operation was performed with some issues
+    Warning,
+    /// The operation failed
+    Failure,
+    /// The operation was cancelled
+    Cancel,
+    /// The operation is in progress,
+    /// further responses will follow
+    Pending,
+}
+
+impl fmt::Display for StatusType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            StatusType::Success => "success",
+            StatusType::Warning => "warning",
+            StatusType::Failure => "failure",
+            StatusType::Cancel => "cancel",
+            StatusType::Pending => "pending",
+        };
+        f.write_str(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Status, StatusType};
+
+    #[test]
+    fn status_types() {
+        assert_eq!(Status::SUCCESS.status_type(), StatusType::Success);
+        assert_eq!(Status(0x0001).status_type(), StatusType::Warning);
+        assert_eq!(Status(0xB007).status_type(), StatusType::Warning);
+        assert_eq!(Status::PENDING.status_type(), StatusType::Pending);
+        assert_eq!(Status::PENDING_WARNING.status_type(), StatusType::Pending);
+        assert_eq!(Status::CANCEL.status_type(), StatusType::Cancel);
+        assert_eq!(Status(0xA700).status_type(), StatusType::Failure);
+        assert_eq!(Status(0xC123).status_type(), StatusType::Failure);
+        assert!(Status::CANCEL.is_final());
+        assert!(!Status::PENDING.is_final());
+        assert_eq!(Status(0xA801).to_string(), "A801H (failure)");
+    }
+}
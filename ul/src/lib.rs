@@ -16,14 +16,53 @@
 //!   comprises abstractions for establishing and negotiating associations
 //!   between application entities,
 //!   via the upper layer protocol by TCP.
+//! - The `dimse` module (requires the `dimse` feature)
+//!   provides typed DIMSE messages
+//!   which can be sent and received through an association.
+//! - The `scp` module (requires the `dimse` feature)
+//!   provides a framework for writing service class providers
+//!   out of pluggable service handlers.
+//! - The `commitment` module (requires the `dimse` feature)
+//!   supports both ends of the Storage Commitment Push Model.
+//! - The `mpps` module (requires the `dimse` feature)
+//!   supports both ends of the Modality Performed Procedure Step SOP class.
+//! - The `tls` module (requires the `sync-tls` feature)
+//!   provides the means to secure associations with TLS.
+//! - The [`auth`] module provides the means to verify
+//!   the user identity of association requesters.
+//! - The `registry` module (requires the `config` feature)
+//!   describes the known application entities,
+//!   as loaded from a configuration file.
 //!
 //! ## Features
+//! * `dimse` (enabled by default): Enables typed DIMSE messages
+//!   and the service modules built on top of them,
+//!   which depend on `dicom-object` and `dicom-dictionary-std`.
 //! * `async`: Enables a fully async implementation of the upper layer protocol.
 //!   See [`ClientAssociationOptions`] and [`ServerAssociationOptions`] for details
+//! * `sync-tls`: Enables TLS secured associations through [rustls](https://crates.io/crates/rustls).
+//! * `async-tls`: Enables TLS secured associations in the async implementation.
+//! * `jwt`: Enables verifying JSON Web Tokens presented as user identities.
+//! * `saml`: Enables checking SAML assertions presented as user identities.
+//! * `config`: Enables loading registries of known application entities
+//!   from TOML or JSON files.
 
 pub mod address;
 pub mod association;
+pub mod auth;
+#[cfg(feature = "dimse")]
+pub mod commitment;
+#[cfg(feature = "dimse")]
+pub mod dimse;
+#[cfg(feature = "dimse")]
+pub mod mpps;
 pub mod pdu;
+#[cfg(feature = "config")]
+pub mod registry;
+#[cfg(feature = "dimse")]
+pub mod scp;
+#[cfg(feature = "sync-tls")]
+pub mod tls;
 
 /// The current implementation class UID generically referring to DICOM-rs.
 ///
@@ -0,0 +1,545 @@
+//! Modality Performed Procedure Step
+//!
+//! This module supports both ends of the
+//! Modality Performed Procedure Step SOP class
+//! (see the standard, part 4, annex F.7).
+//!
+//! The modality reports that it started performing a procedure step
+//! by creating the step with an N-CREATE request
+//! ([`create_procedure_step`]) in the status _IN PROGRESS_,
+//! and later reports its progress and outcome
+//! with N-SET requests ([`set_procedure_step`]),
+//! until the step is either _COMPLETED_ or _DISCONTINUED_.
+//! A procedure step in one of these final states
+//! may no longer be updated.
+//!
+//! On the provider side,
+//! [`validate_create`] and [`validate_set`]
+//! check requests against these state-transition rules,
+//! telling the status to respond with on violation.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_core::{DataElement, PrimitiveValue, VR};
+//! # use dicom_dictionary_std::tags;
+//! # use dicom_object::InMemDicomObject;
+//! # use dicom_ul::association::ClientAssociationOptions;
+//! use dicom_ul::mpps::{
+//!     create_procedure_step, set_procedure_step, StepStatus,
+//!     MODALITY_PERFORMED_PROCEDURE_STEP,
+//! };
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut association = ClientAssociationOptions::new()
+//!     .with_abstract_syntax(MODALITY_PERFORMED_PROCEDURE_STEP)
+//!     .establish_with("RIS@127.0.0.1:104")?;
+//!
+//! let uid = "2.25.153898301476421404722286434429325327427";
+//! let mut attributes = InMemDicomObject::from_element_iter([
+//!     DataElement::new(tags::PATIENT_ID, VR::LO, PrimitiveValue::from("12345")),
+//!     DataElement::new(tags::MODALITY, VR::CS, PrimitiveValue::from("CT")),
+//! ]);
+//! attributes.put(StepStatus::InProgress.to_element());
+//! create_procedure_step(&mut association, 1, uid, &attributes)?;
+//!
+//! let modifications = InMemDicomObject::from_element_iter([
+//!     StepStatus::Completed.to_element(),
+//!     DataElement::new(
+//!         tags::PERFORMED_PROCEDURE_STEP_END_DATE,
+//!         VR::DA,
+//!         PrimitiveValue::from("20240101"),
+//!     ),
+//!     DataElement::new(
+//!         tags::PERFORMED_PROCEDURE_STEP_END_TIME,
+//!         VR::TM,
+//!         PrimitiveValue::from("120000"),
+//!     ),
+//! ]);
+//! set_procedure_step(&mut association, 2, uid, &modifications)?;
+//! association.release()?;
+//! # Ok(())
+//! # }
+//! ```
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_encoding::transfer_syntax::{TransferSyntax, TransferSyntaxIndex};
+use dicom_object::InMemDicomObject;
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    association::{client, ClientAssociation, Transport},
+    dimse::{
+        normalized::{NCreateRq, NCreateRsp, NSetRq, NSetRsp},
+        Command, CommandField, DimseMessage, Status, StatusType,
+    },
+};
+
+/// The Modality Performed Procedure Step SOP class UID.
+pub const MODALITY_PERFORMED_PROCEDURE_STEP: &str = uids::MODALITY_PERFORMED_PROCEDURE_STEP;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[snafu(display("invalid performed procedure step status {:?}", value))]
+    InvalidStatus { value: String, backtrace: Backtrace },
+
+    /// no presentation context was accepted for performed procedure steps
+    NoPresentationContext { backtrace: Backtrace },
+
+    #[snafu(display("unsupported transfer syntax {}", uid))]
+    UnsupportedTransferSyntax { uid: String, backtrace: Backtrace },
+
+    /// failed to prepare or read message
+    Dimse {
+        #[snafu(backtrace)]
+        source: crate::dimse::Error,
+    },
+
+    /// failed to send message
+    Send {
+        #[snafu(backtrace)]
+        source: client::Error,
+    },
+
+    /// failed to receive message
+    Receive {
+        #[snafu(backtrace)]
+        source: client::Error,
+    },
+
+    #[snafu(display(
+        "procedure step request refused with status {}{}",
+        status,
+        error_comment.as_ref().map(|c| format!(": {}", c)).unwrap_or_default()
+    ))]
+    Refused {
+        status: Status,
+        error_comment: Option<String>,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("unexpected {} message", command_field))]
+    UnexpectedMessage {
+        command_field: CommandField,
+        backtrace: Backtrace,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The status of a performed procedure step,
+/// in the _Performed Procedure Step Status_ (0040,0252) attribute.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum StepStatus {
+    /// the procedure step is being performed
+    InProgress,
+    /// the procedure step was performed to its end
+    Completed,
+    /// the procedure step was interrupted
+    Discontinued,
+}
+
+impl StepStatus {
+    /// The defined term of this status.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StepStatus::InProgress => "IN PROGRESS",
+            StepStatus::Completed => "COMPLETED",
+            StepStatus::Discontinued => "DISCONTINUED",
+        }
+    }
+
+    /// Whether the procedure step may no longer be updated
+    /// once in this status.
+    pub fn is_final(self) -> bool {
+        self != StepStatus::InProgress
+    }
+
+    /// Build the _Performed Procedure Step Status_ element
+    /// with this status.
+    pub fn to_element(self) -> DataElement<InMemDicomObject> {
+        DataElement::new(
+            tags::PERFORMED_PROCEDURE_STEP_STATUS,
+            VR::CS,
+            PrimitiveValue::from(self.as_str()),
+        )
+    }
+
+    /// Read the status of a performed procedure step
+    /// from its attributes or from a modification list,
+    /// returning `None` if the attribute is absent or empty.
+    pub fn from_dataset(obj: &InMemDicomObject) -> Option<Result<Self>> {
+        status_value(obj).map(|value| value.parse())
+    }
+}
+
+/// Read the trimmed value of the _Performed Procedure Step Status_,
+/// if not empty.
+fn status_value(obj: &InMemDicomObject) -> Option<String> {
+    let value = obj
+        .get(tags::PERFORMED_PROCEDURE_STEP_STATUS)
+        .and_then(|e| e.to_str().ok())?;
+    let value = value.trim_end_matches(|c: char| c.is_whitespace() || c == '\0');
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+impl FromStr for StepStatus {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "IN PROGRESS" => Ok(StepStatus::InProgress),
+            "COMPLETED" => Ok(StepStatus::Completed),
+            "DISCONTINUED" => Ok(StepStatus::Discontinued),
+            _ => InvalidStatusSnafu { value }.fail(),
+        }
+    }
+}
+
+impl fmt::Display for StepStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A violation of the rules of the performed procedure step state model
+/// by an N-CREATE or N-SET request.
+#[derive(Debug, Clone, PartialEq, Eq, Snafu)]
+#[non_exhaustive]
+pub enum Violation {
+    /// the performed procedure step status is missing
+    MissingStatus,
+
+    #[snafu(display("invalid performed procedure step status {:?}", value))]
+    UnknownStatus { value: String },
+
+    #[snafu(display(
+        "performed procedure step created as {} instead of IN PROGRESS",
+        status
+    ))]
+    NotCreatedInProgress { status: StepStatus },
+
+    #[snafu(display("performed procedure step is {} and may no longer be updated", status))]
+    FinalStatus { status: StepStatus },
+
+    #[snafu(display("attribute {} is required to set the step as {}", tag, status))]
+    MissingFinalAttribute { tag: Tag, status: StepStatus },
+}
+
+impl Violation {
+    /// The status of the response to the request
+    /// which violated the state model.
+    pub fn status(&self) -> Status {
+        match self {
+            Violation::MissingStatus => Status::MISSING_ATTRIBUTE,
+            Violation::UnknownStatus { .. } | Violation::NotCreatedInProgress { .. } => {
+                Status::INVALID_ATTRIBUTE_VALUE
+            }
+            Violation::FinalStatus { .. } => Status::PROCESSING_FAILURE,
+            Violation::MissingFinalAttribute { .. } => Status::MISSING_ATTRIBUTE_VALUE,
+        }
+    }
+}
+
+/// Attributes which must have a value
+/// by the time the procedure step reaches a final status.
+const FINAL_ATTRIBUTES: [Tag; 2] = [
+    tags::PERFORMED_PROCEDURE_STEP_END_DATE,
+    tags::PERFORMED_PROCEDURE_STEP_END_TIME,
+];
+
+fn read_status(obj: &InMemDicomObject) -> Option<std::result::Result<StepStatus, Violation>> {
+    status_value(obj).map(|value| {
+        value
+            .parse()
+            .map_err(|_| Violation::UnknownStatus { value })
+    })
+}
+
+fn has_value(obj: &InMemDicomObject, tag: Tag) -> bool {
+    obj.get(tag)
+        .and_then(|e| e.to_str().ok())
+        .map(|value| {
+            !value
+                .trim_end_matches(|c: char| c.is_whitespace() || c == '\0')
+                .is_empty()
+        })
+        .unwrap_or(false)
+}
+
+/// Check the attributes of an N-CREATE request
+/// against the performed procedure step state model:
+/// a procedure step can only be created _IN PROGRESS_.
+pub fn validate_create(attributes: &InMemDicomObject) -> Result<(), Violation> {
+    let status = read_status(attributes).unwrap_or(Err(Violation::MissingStatus))?;
+    ensure!(
+        status == StepStatus::InProgress,
+        NotCreatedInProgressSnafu { status }
+    );
+    Ok(())
+}
+
+/// Check the modification list of an N-SET request
+/// against the current attributes of the performed procedure step,
+/// returning the status of the step after the modification.
+///
+/// A procedure step may only be updated while _IN PROGRESS_,
+/// and can only be set as _COMPLETED_ or _DISCONTINUED_
+/// with an end date and time.
+pub fn validate_set(
+    current: &InMemDicomObject,
+    modifications: &InMemDicomObject,
+) -> Result<StepStatus, Violation> {
+    let status = read_status(current).unwrap_or(Err(Violation::MissingStatus))?;
+    ensure!(!status.is_final(), FinalStatusSnafu { status });
+
+    let new_status = match read_status(modifications) {
+        Some(new_status) => new_status?,
+        None => status,
+    };
+    if new_status.is_final() {
+        for tag in FINAL_ATTRIBUTES {
+            let value_set = if modifications.get(tag).is_some() {
+                has_value(modifications, tag)
+            } else {
+                has_value(current, tag)
+            };
+            ensure!(
+                value_set,
+                MissingFinalAttributeSnafu {
+                    tag,
+                    status: new_status
+                }
+            );
+        }
+    }
+    Ok(new_status)
+}
+
+/// Apply the modification list of an N-SET request
+/// to the attributes of a performed procedure step,
+/// replacing the attributes present in the list.
+pub fn apply_modifications(current: &mut InMemDicomObject, modifications: InMemDicomObject) {
+    for element in modifications {
+        current.put(element);
+    }
+}
+
+/// Create a performed procedure step
+/// at the provider on the other end of the association,
+/// waiting for the N-CREATE response.
+///
+/// The association must have an accepted presentation context
+/// for the [Modality Performed Procedure Step](MODALITY_PERFORMED_PROCEDURE_STEP) SOP class.
+/// A response with a failure status results in an [`Error::Refused`].
+pub fn create_procedure_step<S: Transport>(
+    association: &mut ClientAssociation<S>,
+    message_id: u16,
+    sop_instance_uid: &str,
+    attributes: &InMemDicomObject,
+) -> Result<NCreateRsp> {
+    let rq = NCreateRq::new(
+        message_id,
+        MODALITY_PERFORMED_PROCEDURE_STEP,
+        Some(sop_instance_uid.to_string()),
+    );
+    let rsp: NCreateRsp = request(association, rq, attributes)?;
+    check_status(rsp.status, &rsp.error_comment)?;
+    Ok(rsp)
+}
+
+/// Update a performed procedure step
+/// at the provider on the other end of the association
+/// with the given modification list,
+/// waiting for the N-SET response.
+///
+/// A response with a failure status results in an [`Error::Refused`].
+pub fn set_procedure_step<S: Transport>(
+    association: &mut ClientAssociation<S>,
+    message_id: u16,
+    sop_instance_uid: &str,
+    modifications: &InMemDicomObject,
+) -> Result<NSetRsp> {
+    let rq = NSetRq::new(
+        message_id,
+        MODALITY_PERFORMED_PROCEDURE_STEP,
+        sop_instance_uid,
+    );
+    let rsp: NSetRsp = request(association, rq, modifications)?;
+    check_status(rsp.status, &rsp.error_comment)?;
+    Ok(rsp)
+}
+
+/// Send a request with the given data set
+/// and wait for its response.
+fn request<S, Rq, Rsp>(
+    association: &mut ClientAssociation<S>,
+    rq: Rq,
+    dataset: &InMemDicomObject,
+) -> Result<Rsp>
+where
+    S: Transport,
+    Rq: Into<Command>,
+    Rsp: TryFrom<Command>,
+{
+    let pc = association
+        .presentation_contexts()
+        .iter()
+        .find(|pc| association.abstract_syntax(pc.id) == Some(MODALITY_PERFORMED_PROCEDURE_STEP))
+        .context(NoPresentationContextSnafu)?;
+    let ts = transfer_syntax(&pc.transfer_syntax)?;
+    let msg = DimseMessage::new(pc.id, rq)
+        .with_dataset(dataset, ts)
+        .context(DimseSnafu)?;
+    association.send_dimse(&msg).context(SendSnafu)?;
+
+    let rsp = association.receive_dimse().context(ReceiveSnafu)?;
+    let command_field = rsp.command.command_field();
+    Rsp::try_from(rsp.command)
+        .ok()
+        .context(UnexpectedMessageSnafu { command_field })
+}
+
+fn check_status(status: Status, error_comment: &Option<String>) -> Result<()> {
+    ensure!(
+        matches!(
+            status.status_type(),
+            StatusType::Success | StatusType::Warning
+        ),
+        RefusedSnafu {
+            status,
+            error_comment: error_comment.clone(),
+        }
+    );
+    Ok(())
+}
+
+fn transfer_syntax(uid: &str) -> Result<&'static TransferSyntax> {
+    TransferSyntaxRegistry
+        .get(uid)
+        .context(UnsupportedTransferSyntaxSnafu { uid })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(status: StepStatus) -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::PERFORMED_PROCEDURE_STEP_ID,
+                VR::SH,
+                PrimitiveValue::from("PPS1"),
+            ),
+            status.to_element(),
+        ])
+    }
+
+    fn end_date_time() -> [DataElement<InMemDicomObject>; 2] {
+        [
+            DataElement::new(
+                tags::PERFORMED_PROCEDURE_STEP_END_DATE,
+                VR::DA,
+                PrimitiveValue::from("20240101"),
+            ),
+            DataElement::new(
+                tags::PERFORMED_PROCEDURE_STEP_END_TIME,
+                VR::TM,
+                PrimitiveValue::from("120000"),
+            ),
+        ]
+    }
+
+    #[test]
+    fn status_roundtrip() {
+        for status in [
+            StepStatus::InProgress,
+            StepStatus::Completed,
+            StepStatus::Discontinued,
+        ] {
+            let obj = InMemDicomObject::from_element_iter([status.to_element()]);
+            assert_eq!(StepStatus::from_dataset(&obj).unwrap().unwrap(), status);
+        }
+        assert!(StepStatus::from_dataset(&InMemDicomObject::new_empty()).is_none());
+        assert!("SCHEDULED".parse::<StepStatus>().is_err());
+    }
+
+    #[test]
+    fn steps_are_created_in_progress() {
+        assert_eq!(validate_create(&step(StepStatus::InProgress)), Ok(()));
+        assert_eq!(
+            validate_create(&step(StepStatus::Completed)),
+            Err(Violation::NotCreatedInProgress {
+                status: StepStatus::Completed
+            })
+        );
+        let violation = validate_create(&InMemDicomObject::new_empty()).unwrap_err();
+        assert_eq!(violation, Violation::MissingStatus);
+        assert_eq!(violation.status(), Status::MISSING_ATTRIBUTE);
+    }
+
+    #[test]
+    fn final_steps_require_end_date_time() {
+        let current = step(StepStatus::InProgress);
+
+        // updating attributes keeps the step in progress
+        let modifications = InMemDicomObject::from_element_iter(end_date_time());
+        assert_eq!(
+            validate_set(&current, &modifications),
+            Ok(StepStatus::InProgress)
+        );
+
+        let modifications =
+            InMemDicomObject::from_element_iter([StepStatus::Completed.to_element()]);
+        let violation = validate_set(&current, &modifications).unwrap_err();
+        assert_eq!(
+            violation,
+            Violation::MissingFinalAttribute {
+                tag: tags::PERFORMED_PROCEDURE_STEP_END_DATE,
+                status: StepStatus::Completed,
+            }
+        );
+        assert_eq!(violation.status(), Status::MISSING_ATTRIBUTE_VALUE);
+
+        let mut modifications = InMemDicomObject::from_element_iter(end_date_time());
+        modifications.put(StepStatus::Discontinued.to_element());
+        assert_eq!(
+            validate_set(&current, &modifications),
+            Ok(StepStatus::Discontinued)
+        );
+
+        let mut updated = current.clone();
+        apply_modifications(&mut updated, modifications);
+        assert_eq!(
+            StepStatus::from_dataset(&updated).unwrap().unwrap(),
+            StepStatus::Discontinued
+        );
+    }
+
+    #[test]
+    fn final_steps_cannot_be_updated() {
+        let mut current = step(StepStatus::Completed);
+        for element in end_date_time() {
+            current.put(element);
+        }
+        let modifications =
+            InMemDicomObject::from_element_iter([StepStatus::InProgress.to_element()]);
+        let violation = validate_set(&current, &modifications).unwrap_err();
+        assert_eq!(
+            violation,
+            Violation::FinalStatus {
+                status: StepStatus::Completed
+            }
+        );
+        assert_eq!(violation.status(), Status::PROCESSING_FAILURE);
+    }
+}
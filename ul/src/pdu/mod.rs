@@ -462,7 +462,26 @@ pub enum UserVariableItem {
     ImplementationClassUID(String),
     ImplementationVersionName(String),
     SopClassExtendedNegotiationSubItem(String, Vec<u8>),
+    /// SCP/SCU Role Selection Sub-Item:
+    /// the SOP class UID,
+    /// followed by whether the SCU role and the SCP role
+    /// are proposed (in A-ASSOCIATE-RQ) or accepted (in A-ASSOCIATE-AC)
+    RoleSelectionSubItem(String, bool, bool),
+    /// Asynchronous Operations Window Sub-Item:
+    /// the maximum number of operations invoked
+    /// and the maximum number of operations performed,
+    /// where 0 means unlimited
+    AsyncOperationsWindow(u16, u16),
+    /// SOP Class Common Extended Negotiation Sub-Item:
+    /// the SOP class UID, the service class UID,
+    /// and the UIDs of the related general SOP classes
+    SopClassCommonExtendedNegotiationSubItem(String, String, Vec<String>),
     UserIdentityItem(UserIdentity),
+    /// User Identity Negotiation Sub-Item of the acceptor:
+    /// the server response to a user identity
+    /// for which a positive response was requested,
+    /// empty for the username identity types
+    UserIdentityResponse(Vec<u8>),
 }
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Hash, Debug)]
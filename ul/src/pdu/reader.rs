@@ -805,24 +805,128 @@ fn read_pdu_variable(mut buf: impl Buf, codec: &dyn TextCodec) -> Result<Option<
                             .trim()
                             .to_string();
 
-                        if bytes.remaining() < 2 {
-                            return Ok(None);
-                        }
-                        let data_length = bytes.get_u16();
-
                         // xxx-xxx - Service-class-application-information -This field shall contain
                         // the application information specific to the Service Class specification
                         // identified by the SOP-class-uid. The semantics and value of this field
                         // is defined in the identified Service Class specification.
-                        if bytes.remaining() < data_length as usize {
+                        // It takes the remainder of the sub-item.
+                        let data_length = (item_length as usize)
+                            .saturating_sub(2 + sop_class_uid_length as usize);
+                        if bytes.remaining() < data_length {
                             return Ok(None);
                         }
-                        let data = bytes.copy_to_bytes(data_length as usize);
+                        let data = bytes.copy_to_bytes(data_length);
                         user_variables.push(UserVariableItem::SopClassExtendedNegotiationSubItem(
                             sop_class_uid,
                             data.to_vec(),
                         ));
                     }
+                    0x53 => {
+                        // Asynchronous Operations Window Sub-Item
+
+                        // 5-6 - Maximum-number-operations-invoked
+                        // 7-8 - Maximum-number-operations-performed
+                        if bytes.remaining() < 4 {
+                            return Ok(None);
+                        }
+                        let max_operations_invoked = bytes.get_u16();
+                        let max_operations_performed = bytes.get_u16();
+                        user_variables.push(UserVariableItem::AsyncOperationsWindow(
+                            max_operations_invoked,
+                            max_operations_performed,
+                        ));
+                    }
+                    0x54 => {
+                        // SCP/SCU Role Selection Sub-Item
+
+                        // 5-6 - UID-length
+                        if bytes.remaining() < 2 {
+                            return Ok(None);
+                        }
+                        let sop_class_uid_length = bytes.get_u16();
+
+                        // 7-xxx - SOP-class-uid - The SOP Class or Meta SOP Class identifier
+                        // encoded as a UID as defined in Section 9 “Unique Identifiers (UIDs)” in PS3.5.
+                        if bytes.remaining() < sop_class_uid_length as usize + 2 {
+                            return Ok(None);
+                        }
+                        let sop_class_uid = codec
+                            .decode(bytes.copy_to_bytes(sop_class_uid_length as usize).as_ref())
+                            .context(DecodeTextSnafu {
+                                field: "SOP-class-uid",
+                            })?
+                            .trim()
+                            .to_string();
+
+                        // xxx - SCU-role - 0 for non-support (or rejection) of the SCU role,
+                        // 1 for support (or acceptance) of the SCU role
+                        let scu_role = bytes.get_u8();
+
+                        // xxx - SCP-role - 0 for non-support (or rejection) of the SCP role,
+                        // 1 for support (or acceptance) of the SCP role
+                        let scp_role = bytes.get_u8();
+
+                        user_variables.push(UserVariableItem::RoleSelectionSubItem(
+                            sop_class_uid,
+                            scu_role == 1,
+                            scp_role == 1,
+                        ));
+                    }
+                    0x57 => {
+                        // SOP Class Common Extended Negotiation Sub-Item
+                        if bytes.remaining() < item_length as usize {
+                            return Ok(None);
+                        }
+                        let mut item = bytes.copy_to_bytes(item_length as usize);
+
+                        // 5-6 - SOP-class-uid-length
+                        // 7-xxx - SOP-class-uid
+                        let sop_class_uid = match read_uid_field(&mut item, codec, "SOP-class-uid")?
+                        {
+                            Some(uid) => uid,
+                            None => return Ok(None),
+                        };
+
+                        // xxx-xxx - Service-class-uid-length
+                        // xxx-xxx - Service-class-uid
+                        let service_class_uid =
+                            match read_uid_field(&mut item, codec, "Service-class-uid")? {
+                                Some(uid) => uid,
+                                None => return Ok(None),
+                            };
+
+                        // xxx-xxx - Related-general-sop-class-identification-length
+                        if item.remaining() < 2 {
+                            return Ok(None);
+                        }
+                        let related_length = item.get_u16() as usize;
+                        if item.remaining() < related_length {
+                            return Ok(None);
+                        }
+
+                        // xxx-xxx - Related-general-sop-class-identification:
+                        // a sequence of UID length and UID pairs
+                        let mut related = item.copy_to_bytes(related_length);
+                        let mut related_general_sop_class_uids = Vec::new();
+                        while related.has_remaining() {
+                            match read_uid_field(
+                                &mut related,
+                                codec,
+                                "Related-general-sop-class-uid",
+                            )? {
+                                Some(uid) => related_general_sop_class_uids.push(uid),
+                                None => return Ok(None),
+                            }
+                        }
+
+                        user_variables.push(
+                            UserVariableItem::SopClassCommonExtendedNegotiationSubItem(
+                                sop_class_uid,
+                                service_class_uid,
+                                related_general_sop_class_uids,
+                            ),
+                        );
+                    }
                     0x58 => {
                         // User Identity Negotiation
 
@@ -878,6 +982,24 @@ fn read_pdu_variable(mut buf: impl Buf, codec: &dyn TextCodec) -> Result<Option<
                             }
                         }
                     }
+                    0x59 => {
+                        // User Identity Negotiation (A-ASSOCIATE-AC)
+
+                        // 5-6 - Server-response-length
+                        if bytes.remaining() < 2 {
+                            return Ok(None);
+                        }
+                        let server_response_length = bytes.get_u16();
+
+                        // 7-n - Server-response
+                        if bytes.remaining() < server_response_length as usize {
+                            return Ok(None);
+                        }
+                        let server_response = bytes.copy_to_bytes(server_response_length as usize);
+                        user_variables.push(UserVariableItem::UserIdentityResponse(
+                            server_response.to_vec(),
+                        ));
+                    }
                     _ => {
                         if bytes.remaining() < item_length as usize {
                             return Ok(None);
@@ -895,3 +1017,25 @@ fn read_pdu_variable(mut buf: impl Buf, codec: &dyn TextCodec) -> Result<Option<
         _ => Ok(Some(PduVariableItem::Unknown(item_type))),
     }
 }
+
+/// Read a UID prefixed by its length as a 16-bit unsigned integer,
+/// as found in some of the user information sub-items.
+fn read_uid_field(
+    buf: &mut impl Buf,
+    codec: &dyn TextCodec,
+    field: &'static str,
+) -> Result<Option<String>> {
+    if buf.remaining() < 2 {
+        return Ok(None);
+    }
+    let length = buf.get_u16() as usize;
+    if buf.remaining() < length {
+        return Ok(None);
+    }
+    let uid = codec
+        .decode(buf.copy_to_bytes(length).as_ref())
+        .context(DecodeTextSnafu { field })?
+        .trim()
+        .to_string();
+    Ok(Some(uid))
+}
@@ -980,17 +980,163 @@ fn write_pdu_variable_user_variables(
                             name: "SOP-class-uid",
                         })?;
 
-                        write_chunk_u16(writer, |writer| {
-                            // xxx-xxx Service-class-application-information - This field shall contain
-                            // the application information specific to the Service Class specification
-                            // identified by the SOP-class-uid. The semantics and value of this field is
-                            // defined in the identified Service Class specification.
-                            writer.write_all(data).context(WriteFieldSnafu {
-                                field: "Service-class-application-information",
+                        // xxx-xxx Service-class-application-information - This field shall contain
+                        // the application information specific to the Service Class specification
+                        // identified by the SOP-class-uid. The semantics and value of this field is
+                        // defined in the identified Service Class specification.
+                        writer.write_all(data).context(WriteFieldSnafu {
+                            field: "Service-class-application-information",
+                        })
+                    })
+                    .context(WriteChunkSnafu { name: "Sub-item" })?;
+                }
+                UserVariableItem::AsyncOperationsWindow(
+                    max_operations_invoked,
+                    max_operations_performed,
+                ) => {
+                    // 1 - Item-type - 53H
+                    writer
+                        .write_u8(0x53)
+                        .context(WriteFieldSnafu { field: "Item-type" })?;
+                    // 2 - Reserved - This reserved field shall be sent with a value 00H but not
+                    // tested to this value when received.
+                    writer
+                        .write_u8(0x00)
+                        .context(WriteReservedSnafu { bytes: 1_u32 })?;
+
+                    // 3-4 - Item-length
+                    write_chunk_u16(writer, |writer| {
+                        // 5-6 - Maximum-number-operations-invoked
+                        writer
+                            .write_u16::<BigEndian>(*max_operations_invoked)
+                            .context(WriteFieldSnafu {
+                                field: "Maximum-number-operations-invoked",
+                            })?;
+                        // 7-8 - Maximum-number-operations-performed
+                        writer
+                            .write_u16::<BigEndian>(*max_operations_performed)
+                            .context(WriteFieldSnafu {
+                                field: "Maximum-number-operations-performed",
                             })
+                    })
+                    .context(WriteChunkSnafu { name: "Sub-item" })?;
+                }
+                UserVariableItem::RoleSelectionSubItem(sop_class_uid, scu_role, scp_role) => {
+                    // 1 - Item-type - 54H
+                    writer
+                        .write_u8(0x54)
+                        .context(WriteFieldSnafu { field: "Item-type" })?;
+                    // 2 - Reserved - This reserved field shall be sent with a value 00H but not
+                    // tested to this value when received.
+                    writer
+                        .write_u8(0x00)
+                        .context(WriteReservedSnafu { bytes: 1_u32 })?;
+
+                    // 3-4 - Item-length
+                    write_chunk_u16(writer, |writer| {
+                        // 5-6 - UID-length
+                        // 7-xxx - SOP-class-uid - The SOP Class or Meta SOP Class identifier
+                        // encoded as a UID as defined in Section 9 “Unique Identifiers (UIDs)” in PS3.5.
+                        write_chunk_u16(writer, |writer| {
+                            writer
+                                .write_all(&codec.encode(sop_class_uid).context(
+                                    EncodeFieldSnafu {
+                                        field: "SOP-class-uid",
+                                    },
+                                )?)
+                                .context(WriteFieldSnafu {
+                                    field: "SOP-class-uid",
+                                })
+                        })
+                        .context(WriteChunkSnafu {
+                            name: "SOP-class-uid",
+                        })?;
+
+                        // xxx - SCU-role
+                        writer
+                            .write_u8(u8::from(*scu_role))
+                            .context(WriteFieldSnafu { field: "SCU-role" })?;
+                        // xxx - SCP-role
+                        writer
+                            .write_u8(u8::from(*scp_role))
+                            .context(WriteFieldSnafu { field: "SCP-role" })
+                    })
+                    .context(WriteChunkSnafu { name: "Sub-item" })?;
+                }
+                UserVariableItem::SopClassCommonExtendedNegotiationSubItem(
+                    sop_class_uid,
+                    service_class_uid,
+                    related_general_sop_class_uids,
+                ) => {
+                    // 1 - Item-type - 57H
+                    writer
+                        .write_u8(0x57)
+                        .context(WriteFieldSnafu { field: "Item-type" })?;
+                    // 2 - Reserved - This reserved field shall be sent with a value 00H but not
+                    // tested to this value when received.
+                    writer
+                        .write_u8(0x00)
+                        .context(WriteReservedSnafu { bytes: 1_u32 })?;
+
+                    // 3-4 - Item-length
+                    write_chunk_u16(writer, |writer| {
+                        // 5-6 - SOP-class-uid-length
+                        // 7-xxx - SOP-class-uid
+                        write_chunk_u16(writer, |writer| {
+                            writer
+                                .write_all(&codec.encode(sop_class_uid).context(
+                                    EncodeFieldSnafu {
+                                        field: "SOP-class-uid",
+                                    },
+                                )?)
+                                .context(WriteFieldSnafu {
+                                    field: "SOP-class-uid",
+                                })
+                        })
+                        .context(WriteChunkSnafu {
+                            name: "SOP-class-uid",
+                        })?;
+
+                        // xxx-xxx - Service-class-uid-length
+                        // xxx-xxx - Service-class-uid
+                        write_chunk_u16(writer, |writer| {
+                            writer
+                                .write_all(&codec.encode(service_class_uid).context(
+                                    EncodeFieldSnafu {
+                                        field: "Service-class-uid",
+                                    },
+                                )?)
+                                .context(WriteFieldSnafu {
+                                    field: "Service-class-uid",
+                                })
+                        })
+                        .context(WriteChunkSnafu {
+                            name: "Service-class-uid",
+                        })?;
+
+                        // xxx-xxx - Related-general-sop-class-identification-length
+                        // xxx-xxx - Related-general-sop-class-identification
+                        write_chunk_u16(writer, |writer| {
+                            for uid in related_general_sop_class_uids {
+                                write_chunk_u16(writer, |writer| {
+                                    writer
+                                        .write_all(&codec.encode(uid).context(
+                                            EncodeFieldSnafu {
+                                                field: "Related-general-sop-class-uid",
+                                            },
+                                        )?)
+                                        .context(WriteFieldSnafu {
+                                            field: "Related-general-sop-class-uid",
+                                        })
+                                })
+                                .context(WriteChunkSnafu {
+                                    name: "Related-general-sop-class-uid",
+                                })?;
+                            }
+                            Ok(())
                         })
                         .context(WriteChunkSnafu {
-                            name: "Service-class-application-information",
+                            name: "Related-general-sop-class-identification",
                         })
                     })
                     .context(WriteChunkSnafu { name: "Sub-item" })?;
@@ -1059,6 +1205,37 @@ fn write_pdu_variable_user_variables(
                         name: "Item-length",
                     })?;
                 }
+                UserVariableItem::UserIdentityResponse(server_response) => {
+                    // 1 - Item-type - 59H
+                    writer
+                        .write_u8(0x59)
+                        .context(WriteFieldSnafu { field: "Item-type" })?;
+
+                    // 2 - Reserved - This reserved field shall be sent with a value 00H but not
+                    // tested to this value when received.
+                    writer
+                        .write_u8(0x00)
+                        .context(WriteReservedSnafu { bytes: 1_u32 })?;
+
+                    // 3-4 - Item-length
+                    write_chunk_u16(writer, |writer| {
+                        // 5-6 - Server-response-length
+                        write_chunk_u16(writer, |writer| {
+                            // 7-n - Server-response
+                            writer
+                                .write_all(server_response)
+                                .context(WriteFieldSnafu {
+                                    field: "Server-response",
+                                })
+                        })
+                        .context(WriteChunkSnafu {
+                            name: "Server-response",
+                        })
+                    })
+                    .context(WriteChunkSnafu {
+                        name: "Item-length",
+                    })?;
+                }
                 UserVariableItem::Unknown(item_type, data) => {
                     writer
                         .write_u8(*item_type)
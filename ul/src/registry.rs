@@ -0,0 +1,665 @@
+//! Registry of known application entities.
+//!
+//! An [`AeRegistry`] describes the DICOM nodes known to an application,
+//! by name:
+//! their AE title, network address,
+//! the SOP classes which they may use,
+//! and how to secure and authenticate associations with them.
+//! Registries are usually loaded from a TOML or JSON file:
+//!
+//! ```toml
+//! [ae.pacs]
+//! ae-title = "PACS"
+//! host = "10.0.0.5"
+//! port = 104
+//!
+//! [ae.pacs.tls]
+//! ca = "certs/ca.pem"
+//! server-name = "pacs.example.org"
+//!
+//! [ae.pacs.credentials]
+//! username = "scu"
+//! password = "secret"
+//!
+//! # a modality which only stores CT images
+//! [ae.ct]
+//! ae-title = "CT-SCANNER"
+//! sop-classes = ["1.2.840.10008.5.1.4.1.1.2"]
+//! ```
+//!
+//! As an association requester,
+//! a node is looked up by name with [`AeRegistry::get`],
+//! and the association options are completed
+//! with [`AeEntry::configure`].
+//!
+//! As an association acceptor,
+//! the registry works as an [`AccessControl`] policy
+//! which only accepts associations from known AE titles,
+//! presenting the expected credentials,
+//! and only for the SOP classes which they may use.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use snafu::{ensure, ResultExt, Snafu};
+
+use crate::association::client::ClientAssociationOptions;
+use crate::association::server::AccessControl;
+use crate::auth::constant_time_eq;
+use crate::pdu::{AssociationRJServiceUserReason, UserIdentity, UserIdentityType};
+
+/// The environment variable naming the AE configuration file
+/// to use when none is given explicitly.
+pub const AE_CONFIG_ENV: &str = "DICOM_AE_CONFIG";
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[snafu(display("Could not read AE configuration file {}", path.display()))]
+    ReadConfig {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Invalid AE configuration in TOML"))]
+    ParseToml { source: toml::de::Error },
+    #[snafu(display("Invalid AE configuration in JSON"))]
+    ParseJson { source: serde_json::Error },
+    #[snafu(display("Invalid AE title {:?} of node {:?}", ae_title, name))]
+    InvalidAeTitle { name: String, ae_title: String },
+    #[snafu(display("Node {:?} has a password but no username", name))]
+    MissingUsername { name: String },
+    #[snafu(display("Node {:?} has more than one kind of credentials", name))]
+    ConflictingCredentials { name: String },
+    #[snafu(display("Unknown TLS profile {:?} of node {:?}", profile, name))]
+    UnknownTlsProfile { name: String, profile: String },
+    #[snafu(display(
+        "Node {:?} has a TLS certificate chain without a private key, or vice versa",
+        name
+    ))]
+    IncompleteTlsIdentity { name: String },
+    #[cfg(feature = "sync-tls")]
+    #[snafu(display("Could not set up TLS for node {:?}", name))]
+    Tls {
+        name: String,
+        source: crate::tls::Error,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A registry of known application entities, by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct AeRegistry {
+    /// The known application entities, by name
+    #[serde(default, rename = "ae")]
+    pub entities: BTreeMap<String, AeEntry>,
+}
+
+/// A known application entity.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct AeEntry {
+    /// The AE title of the node
+    pub ae_title: String,
+    /// The host name or IP address of the node,
+    /// if it accepts associations
+    #[serde(default)]
+    pub host: Option<String>,
+    /// The TCP port on which the node accepts associations
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// The SOP classes which the node may use
+    /// in associations with this application
+    /// (any SOP class if empty)
+    #[serde(default)]
+    pub sop_classes: Vec<String>,
+    /// How to secure associations with the node
+    #[serde(default)]
+    pub tls: Option<TlsEntry>,
+    /// The user identity presented in associations
+    /// requested to or by the node
+    #[serde(default)]
+    pub credentials: Option<Credentials>,
+}
+
+fn default_port() -> u16 {
+    104
+}
+
+/// The TLS settings for associations with a known node.
+///
+/// Relative paths are resolved
+/// against the directory of the configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct TlsEntry {
+    /// PEM file with the CA certificates trusted to verify the node
+    #[serde(default)]
+    pub ca: Option<PathBuf>,
+    /// PEM file with the certificate chain
+    /// presented by this application to the node
+    #[serde(default)]
+    pub cert: Option<PathBuf>,
+    /// PEM file with the private key of this application
+    #[serde(default)]
+    pub key: Option<PathBuf>,
+    /// The TLS secure transport connection profile
+    /// (`bcp195`, `non-downgrading`, or `extended`) [default: `bcp195`]
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// The name to verify the node's certificate against
+    /// [default: the host of the node]
+    #[serde(default)]
+    pub server_name: Option<String>,
+}
+
+/// The user identity of associations with a known node.
+///
+/// Only one kind of credentials may be given:
+/// a username with an optional password,
+/// a Kerberos service ticket, a SAML assertion, or a JSON web token.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Credentials {
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub kerberos_service_ticket: Option<String>,
+    #[serde(default)]
+    pub saml_assertion: Option<String>,
+    #[serde(default)]
+    pub jwt: Option<String>,
+}
+
+impl AeRegistry {
+    /// Read a registry from a file,
+    /// in JSON if the file name ends with `.json`,
+    /// or in TOML otherwise.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).context(ReadConfigSnafu { path })?;
+        let mut registry: AeRegistry = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&text).context(ParseJsonSnafu)?
+        } else {
+            toml::from_str(&text).context(ParseTomlSnafu)?
+        };
+        if let Some(dir) = path.parent() {
+            registry.resolve_paths(dir);
+        }
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Read the registry in the given file,
+    /// or in the file named by the `DICOM_AE_CONFIG` environment variable
+    /// if no file is given.
+    ///
+    /// Returns `None` if neither is present.
+    pub fn locate(path: Option<&Path>) -> Result<Option<Self>> {
+        match path {
+            Some(path) => Self::from_file(path).map(Some),
+            None => match std::env::var_os(AE_CONFIG_ENV) {
+                Some(path) if !path.is_empty() => Self::from_file(path).map(Some),
+                _ => Ok(None),
+            },
+        }
+    }
+
+    /// Parse a registry in TOML.
+    pub fn from_toml_str(text: &str) -> Result<Self> {
+        let registry: AeRegistry = toml::from_str(text).context(ParseTomlSnafu)?;
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Parse a registry in JSON.
+    pub fn from_json_str(text: &str) -> Result<Self> {
+        let registry: AeRegistry = serde_json::from_str(text).context(ParseJsonSnafu)?;
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Resolve the relative paths in the registry against a base directory.
+    fn resolve_paths(&mut self, base: &Path) {
+        for tls in self.entities.values_mut().filter_map(|e| e.tls.as_mut()) {
+            let paths = [&mut tls.ca, &mut tls.cert, &mut tls.key];
+            for path in IntoIterator::into_iter(paths).flatten() {
+                if path.is_relative() {
+                    *path = base.join(&*path);
+                }
+            }
+        }
+    }
+
+    /// Check that all entries are consistent.
+    pub fn validate(&self) -> Result<()> {
+        for (name, entry) in &self.entities {
+            entry.validate(name)?;
+        }
+        Ok(())
+    }
+
+    /// Look up a node by name.
+    pub fn get(&self, name: &str) -> Option<&AeEntry> {
+        self.entities.get(name)
+    }
+
+    /// Look up a node by AE title.
+    pub fn by_ae_title(&self, ae_title: &str) -> Option<&AeEntry> {
+        let ae_title = ae_title.trim();
+        self.entities
+            .values()
+            .find(|entry| entry.ae_title == ae_title)
+    }
+
+    /// Add a node to the registry, replacing any node with the same name.
+    pub fn insert(&mut self, name: impl Into<String>, entry: AeEntry) {
+        self.entities.insert(name.into(), entry);
+    }
+}
+
+impl AeEntry {
+    /// A node with the given AE title,
+    /// without a network address.
+    pub fn new(ae_title: impl Into<String>) -> Self {
+        AeEntry {
+            ae_title: ae_title.into(),
+            host: None,
+            port: default_port(),
+            sop_classes: Vec::new(),
+            tls: None,
+            credentials: None,
+        }
+    }
+
+    /// Set the network address of the node.
+    pub fn with_address(mut self, host: impl Into<String>, port: u16) -> Self {
+        self.host = Some(host.into());
+        self.port = port;
+        self
+    }
+
+    /// Add a SOP class which the node may use.
+    pub fn with_sop_class(mut self, uid: impl Into<String>) -> Self {
+        self.sop_classes.push(uid.into());
+        self
+    }
+
+    /// Set the user identity of associations with the node.
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    fn validate(&self, name: &str) -> Result<()> {
+        ensure!(
+            !self.ae_title.trim().is_empty() && self.ae_title.len() <= 16,
+            InvalidAeTitleSnafu {
+                name,
+                ae_title: &self.ae_title,
+            }
+        );
+        if let Some(credentials) = &self.credentials {
+            credentials.validate(name)?;
+        }
+        if let Some(tls) = &self.tls {
+            if let Some(profile) = &tls.profile {
+                ensure!(
+                    ["bcp195", "non-downgrading", "extended"].contains(&profile.as_str()),
+                    UnknownTlsProfileSnafu { name, profile }
+                );
+            }
+            ensure!(
+                tls.cert.is_some() == tls.key.is_some(),
+                IncompleteTlsIdentitySnafu { name }
+            );
+        }
+        Ok(())
+    }
+
+    /// The address of the node with its AE title,
+    /// as accepted by
+    /// [`establish_with`](ClientAssociationOptions::establish_with),
+    /// if the node accepts associations.
+    pub fn address(&self) -> Option<String> {
+        let host = self.host.as_ref()?;
+        if host.contains(':') {
+            // IPv6 address
+            Some(format!("{}@[{}]:{}", self.ae_title, host, self.port))
+        } else {
+            Some(format!("{}@{}:{}", self.ae_title, host, self.port))
+        }
+    }
+
+    /// Whether the node may use the given SOP class.
+    pub fn allows_sop_class(&self, uid: &str) -> bool {
+        let uid = uid.trim_end_matches(['\0', ' ']);
+        self.sop_classes.is_empty() || self.sop_classes.iter().any(|sop| sop == uid)
+    }
+
+    /// Whether associations with the node are secured with TLS.
+    pub fn uses_tls(&self) -> bool {
+        self.tls.is_some()
+    }
+
+    /// Complete the options for requesting an association with the node
+    /// with its credentials and TLS settings.
+    ///
+    /// The called AE title is part of the node's [address](Self::address).
+    pub fn configure<'a>(
+        &self,
+        options: ClientAssociationOptions<'a>,
+    ) -> Result<ClientAssociationOptions<'a>> {
+        let mut options = options;
+        if let Some(credentials) = &self.credentials {
+            options = credentials.configure(options);
+        }
+        #[cfg(feature = "sync-tls")]
+        if let Some(tls) = &self.tls {
+            let mut tls_options = crate::tls::TlsOptions::new();
+            if let Some(profile) = &tls.profile {
+                let profile = profile.parse().map_err(|_| {
+                    UnknownTlsProfileSnafu {
+                        name: &self.ae_title,
+                        profile,
+                    }
+                    .build()
+                })?;
+                tls_options = tls_options.profile(profile);
+            }
+            if let Some(ca) = &tls.ca {
+                tls_options = tls_options.ca_certificates(ca);
+            }
+            if let (Some(cert), Some(key)) = (&tls.cert, &tls.key) {
+                tls_options = tls_options.certificate_chain(cert).private_key(key);
+            }
+            let config = tls_options.client_config().context(TlsSnafu {
+                name: &self.ae_title,
+            })?;
+            options = options.tls_config(config);
+            if let Some(server_name) = &tls.server_name {
+                options = options.server_name(server_name.clone());
+            }
+        }
+        Ok(options)
+    }
+}
+
+impl Credentials {
+    /// Credentials with a username and an optional password.
+    pub fn username(username: impl Into<String>, password: Option<String>) -> Self {
+        Credentials {
+            username: Some(username.into()),
+            password,
+            ..Default::default()
+        }
+    }
+
+    fn validate(&self, name: &str) -> Result<()> {
+        ensure!(
+            self.password.is_none() || self.username.is_some(),
+            MissingUsernameSnafu { name }
+        );
+        let kinds = [
+            self.username.is_some(),
+            self.kerberos_service_ticket.is_some(),
+            self.saml_assertion.is_some(),
+            self.jwt.is_some(),
+        ];
+        ensure!(
+            kinds.iter().filter(|kind| **kind).count() <= 1,
+            ConflictingCredentialsSnafu { name }
+        );
+        Ok(())
+    }
+
+    fn configure<'a>(&self, options: ClientAssociationOptions<'a>) -> ClientAssociationOptions<'a> {
+        match self {
+            Credentials {
+                username: Some(username),
+                password: Some(password),
+                ..
+            } => options.username_password(username.clone(), password.clone()),
+            Credentials {
+                username: Some(username),
+                ..
+            } => options.username(username.clone()),
+            Credentials {
+                kerberos_service_ticket: Some(ticket),
+                ..
+            } => options.kerberos_service_ticket(ticket.clone()),
+            Credentials {
+                saml_assertion: Some(assertion),
+                ..
+            } => options.saml_assertion(assertion.clone()),
+            Credentials { jwt: Some(jwt), .. } => options.jwt(jwt.clone()),
+            _ => options,
+        }
+    }
+
+    /// Whether the user identity presented by a requester
+    /// matches these credentials.
+    ///
+    /// A username with a password only matches
+    /// credentials with that same password,
+    /// and a username alone only matches credentials without a password.
+    pub fn matches(&self, user_identity: &UserIdentity) -> bool {
+        let primary = user_identity.primary_field();
+        let secondary = user_identity.secondary_field();
+        let is = |expected: &Option<String>, field: &[u8]| {
+            expected
+                .as_deref()
+                .is_some_and(|expected| constant_time_eq(expected.as_bytes(), field))
+        };
+        match user_identity.identity_type() {
+            UserIdentityType::Username => self.password.is_none() && is(&self.username, &primary),
+            UserIdentityType::UsernamePassword => {
+                is(&self.username, &primary) && is(&self.password, &secondary)
+            }
+            UserIdentityType::KerberosServiceTicket => is(&self.kerberos_service_ticket, &primary),
+            UserIdentityType::SamlAssertion => is(&self.saml_assertion, &primary),
+            UserIdentityType::Jwt => is(&self.jwt, &primary),
+        }
+    }
+}
+
+/// Accept associations from the known AE titles only,
+/// with the credentials expected from them, if any,
+/// and only for the SOP classes which they may use.
+///
+/// The called AE title is not checked.
+/// A positive response is given to the user identities
+/// which match the expected credentials.
+impl AccessControl for AeRegistry {
+    fn check_access(
+        &self,
+        this_ae_title: &str,
+        calling_ae_title: &str,
+        called_ae_title: &str,
+        user_identity: Option<&UserIdentity>,
+    ) -> Result<(), AssociationRJServiceUserReason> {
+        self.grant_access(
+            this_ae_title,
+            calling_ae_title,
+            called_ae_title,
+            user_identity,
+        )
+        .map(|_| ())
+    }
+
+    fn grant_access(
+        &self,
+        _this_ae_title: &str,
+        calling_ae_title: &str,
+        _called_ae_title: &str,
+        user_identity: Option<&UserIdentity>,
+    ) -> Result<Option<Vec<u8>>, AssociationRJServiceUserReason> {
+        let entry = self
+            .by_ae_title(calling_ae_title)
+            .ok_or(AssociationRJServiceUserReason::CallingAETitleNotRecognized)?;
+        match (&entry.credentials, user_identity) {
+            (None, _) => Ok(None),
+            (Some(credentials), Some(user_identity)) if credentials.matches(user_identity) => {
+                Ok(Some(Vec::new()))
+            }
+            (Some(_), _) => Err(AssociationRJServiceUserReason::NoReasonGiven),
+        }
+    }
+
+    fn check_abstract_syntax(&self, calling_ae_title: &str, abstract_syntax_uid: &str) -> bool {
+        self.by_ae_title(calling_ae_title)
+            .is_some_and(|entry| entry.allows_sop_class(abstract_syntax_uid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONFIG: &str = r#"
+[ae.pacs]
+ae-title = "PACS"
+host = "10.0.0.5"
+port = 11112
+
+[ae.pacs.credentials]
+username = "scu"
+password = "secret"
+
+[ae.ct]
+ae-title = "CT-SCANNER"
+sop-classes = ["1.2.840.10008.5.1.4.1.1.2"]
+"#;
+
+    #[test]
+    fn parse_toml_and_json() {
+        let registry = AeRegistry::from_toml_str(CONFIG).unwrap();
+        let pacs = registry.get("pacs").unwrap();
+        assert_eq!(pacs.address().as_deref(), Some("PACS@10.0.0.5:11112"));
+        assert_eq!(
+            pacs.credentials,
+            Some(Credentials::username("scu", Some("secret".to_string())))
+        );
+        let ct = registry.by_ae_title("CT-SCANNER ").unwrap();
+        assert_eq!(ct.address(), None);
+        assert_eq!(ct.port, 104);
+        assert!(ct.allows_sop_class("1.2.840.10008.5.1.4.1.1.2\0"));
+        assert!(!ct.allows_sop_class("1.2.840.10008.5.1.4.1.1.4"));
+
+        let json = r#"{
+            "ae": {
+                "pacs": {
+                    "ae-title": "PACS",
+                    "host": "10.0.0.5",
+                    "port": 11112,
+                    "credentials": { "username": "scu", "password": "secret" }
+                },
+                "ct": {
+                    "ae-title": "CT-SCANNER",
+                    "sop-classes": ["1.2.840.10008.5.1.4.1.1.2"]
+                }
+            }
+        }"#;
+        assert_eq!(AeRegistry::from_json_str(json).unwrap(), registry);
+    }
+
+    #[test]
+    fn reject_inconsistent_entries() {
+        let config = r#"
+[ae.pacs]
+ae-title = "PACS"
+credentials = { password = "secret" }
+"#;
+        assert!(matches!(
+            AeRegistry::from_toml_str(config),
+            Err(Error::MissingUsername { .. })
+        ));
+
+        let config = r#"
+[ae.pacs]
+ae-title = "PACS"
+credentials = { username = "scu", jwt = "token" }
+"#;
+        assert!(matches!(
+            AeRegistry::from_toml_str(config),
+            Err(Error::ConflictingCredentials { .. })
+        ));
+
+        let config = r#"
+[ae.pacs]
+ae-title = "THIS-AE-TITLE-IS-TOO-LONG"
+"#;
+        assert!(matches!(
+            AeRegistry::from_toml_str(config),
+            Err(Error::InvalidAeTitle { .. })
+        ));
+    }
+
+    #[test]
+    fn access_control() {
+        let registry = AeRegistry::from_toml_str(CONFIG).unwrap();
+        let user = |kind, primary: &str, secondary: &str| {
+            UserIdentity::new(
+                false,
+                kind,
+                primary.as_bytes().to_vec(),
+                secondary.as_bytes().to_vec(),
+            )
+        };
+
+        // unknown calling AE title
+        assert_eq!(
+            registry.check_access("STORE-SCP", "UNKNOWN", "STORE-SCP", None),
+            Err(AssociationRJServiceUserReason::CallingAETitleNotRecognized)
+        );
+        // known node without credentials
+        assert_eq!(
+            registry.check_access("STORE-SCP", "CT-SCANNER", "STORE-SCP", None),
+            Ok(())
+        );
+        // known node with credentials
+        assert_eq!(
+            registry.check_access(
+                "STORE-SCP",
+                "PACS",
+                "STORE-SCP",
+                Some(&user(UserIdentityType::UsernamePassword, "scu", "secret"))
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            registry.check_access(
+                "STORE-SCP",
+                "PACS",
+                "STORE-SCP",
+                Some(&user(UserIdentityType::UsernamePassword, "scu", "wrong"))
+            ),
+            Err(AssociationRJServiceUserReason::NoReasonGiven)
+        );
+        assert_eq!(
+            registry.check_access("STORE-SCP", "PACS", "STORE-SCP", None),
+            Err(AssociationRJServiceUserReason::NoReasonGiven)
+        );
+
+        assert!(registry.check_abstract_syntax("CT-SCANNER", "1.2.840.10008.5.1.4.1.1.2"));
+        assert!(!registry.check_abstract_syntax("CT-SCANNER", "1.2.840.10008.5.1.4.1.1.4"));
+        assert!(registry.check_abstract_syntax("PACS", "1.2.840.10008.5.1.4.1.1.4"));
+        assert!(!registry.check_abstract_syntax("UNKNOWN", "1.2.840.10008.1.1"));
+    }
+
+    #[test]
+    fn username_without_password() {
+        let credentials = Credentials::username("scu", None);
+        let user = |kind, secondary: &str| {
+            UserIdentity::new(false, kind, b"scu".to_vec(), secondary.as_bytes().to_vec())
+        };
+
+        assert!(credentials.matches(&user(UserIdentityType::Username, "")));
+        // a password is never accepted when none is expected
+        assert!(!credentials.matches(&user(UserIdentityType::UsernamePassword, "anything")));
+        assert!(!credentials.matches(&user(UserIdentityType::UsernamePassword, "")));
+    }
+}
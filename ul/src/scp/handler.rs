@@ -0,0 +1,320 @@
+//! Service handler traits.
+//!
+//! Each trait covers one DIMSE service
+//! and is invoked by the [`ServiceClassProvider`](super::ServiceClassProvider)
+//! whenever a request for one of the SOP classes
+//! the handler was registered for arrives.
+//! Handlers are shared between associations,
+//! so they must be thread safe.
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use dicom_core::Tag;
+use dicom_object::{DefaultDicomObject, InMemDicomObject, OpenFileOptions, ReadError};
+
+use crate::association::trim_padding;
+use crate::commitment::{CommitmentRequest, CommitmentResult};
+use crate::dimse::{
+    composite::{CEchoRq, CFindRq, CGetRq, CMoveRq, CStoreRq},
+    normalized::{
+        NActionRq, NActionRsp, NCreateRq, NCreateRsp, NDeleteRq, NDeleteRsp, NEventReportRq,
+        NEventReportRsp, NGetRq, NGetRsp, NSetRq, NSetRsp,
+    },
+    Status,
+};
+
+use super::ServiceContext;
+
+/// Handler of C-ECHO requests (Verification Service Class).
+pub trait EchoHandler: Send + Sync {
+    /// Respond to a verification request.
+    ///
+    /// The default implementation always succeeds.
+    fn echo(&self, _ctx: &ServiceContext, _rq: &CEchoRq) -> Status {
+        Status::SUCCESS
+    }
+}
+
+/// A handler which accepts every C-ECHO request.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct AcceptEcho;
+
+impl EchoHandler for AcceptEcho {}
+
+/// Handler of C-STORE requests (Storage Service Class).
+pub trait StoreHandler: Send + Sync {
+    /// Process a received composite instance,
+    /// returning the status of the operation.
+    fn store(&self, ctx: &ServiceContext, rq: &CStoreRq, dataset: InMemDicomObject) -> Status;
+
+    /// Process a composite instance as it is received,
+    /// reading its encoded data set
+    /// in the transfer syntax of the presentation context,
+    /// returning the status of the operation.
+    ///
+    /// The default implementation decodes the whole data set in memory
+    /// and passes it on to [`store`](Self::store).
+    /// Handlers may override this to save large instances
+    /// without holding them in memory,
+    /// such as by writing the data set bytes straight to a file.
+    fn store_from(&self, ctx: &ServiceContext, rq: &CStoreRq, data: &mut dyn Read) -> Status {
+        match InMemDicomObject::read_dataset_with_ts(data, ctx.transfer_syntax()) {
+            Ok(dataset) => self.store(ctx, rq, dataset),
+            Err(e) => {
+                tracing::warn!(
+                    "Could not decode data set: {}",
+                    snafu::Report::from_error(e)
+                );
+                Status::UNABLE_TO_PROCESS
+            }
+        }
+    }
+}
+
+impl<F> StoreHandler for F
+where
+    F: Fn(&ServiceContext, &CStoreRq, InMemDicomObject) -> Status + Send + Sync,
+{
+    fn store(&self, ctx: &ServiceContext, rq: &CStoreRq, dataset: InMemDicomObject) -> Status {
+        self(ctx, rq, dataset)
+    }
+}
+
+/// The outcome of a query:
+/// either an iterator over the matching identifiers,
+/// or the failure status to respond with.
+pub type FindResult<'a> = Result<Box<dyn Iterator<Item = InMemDicomObject> + 'a>, Status>;
+
+/// Handler of C-FIND requests (Query/Retrieve and Worklist Service Classes).
+pub trait FindHandler: Send + Sync {
+    /// Look for the records matching the given identifier.
+    ///
+    /// Each item produced is sent back in a pending response,
+    /// followed by a final response with the status _Success_.
+    fn find(
+        &self,
+        ctx: &ServiceContext,
+        rq: &CFindRq,
+        identifier: &InMemDicomObject,
+    ) -> FindResult<'_>;
+}
+
+/// The outcome of a retrieve request:
+/// either the list of instances to send,
+/// or the failure status to respond with.
+pub type RetrieveResult = Result<Vec<RetrieveItem>, Status>;
+
+/// Handler of C-MOVE requests (Query/Retrieve Service Class).
+///
+/// The service class provider takes care of
+/// sending the resolved instances to the move destination
+/// through C-STORE sub-operations on a separate association
+/// and of reporting the progress back to the requester.
+pub trait MoveHandler: Send + Sync {
+    /// Resolve the instances matching the given retrieve identifier.
+    fn retrieve(
+        &self,
+        ctx: &ServiceContext,
+        rq: &CMoveRq,
+        identifier: &InMemDicomObject,
+    ) -> RetrieveResult;
+
+    /// Resolve the network address (`host:port`)
+    /// of the move destination with the given AE title,
+    /// or return `None` if the destination is unknown.
+    fn destination(&self, ae_title: &str) -> Option<String>;
+}
+
+/// Handler of C-GET requests (Query/Retrieve Service Class).
+///
+/// The service class provider takes care of
+/// sending the resolved instances through C-STORE sub-operations
+/// on the same association
+/// and of reporting the progress back to the requester.
+pub trait GetHandler: Send + Sync {
+    /// Resolve the instances matching the given retrieve identifier.
+    fn retrieve(
+        &self,
+        ctx: &ServiceContext,
+        rq: &CGetRq,
+        identifier: &InMemDicomObject,
+    ) -> RetrieveResult;
+}
+
+/// Handler of DIMSE-N requests for a normalized SOP class.
+///
+/// Each method receives the request and its data set, if any,
+/// and returns the response to send along with its optional data set.
+/// The default implementations respond with
+/// the status _Unrecognized Operation_.
+pub trait NormalizedHandler: Send + Sync {
+    /// Handle an N-EVENT-REPORT request.
+    fn n_event_report(
+        &self,
+        _ctx: &ServiceContext,
+        rq: &NEventReportRq,
+        _dataset: Option<InMemDicomObject>,
+    ) -> (NEventReportRsp, Option<InMemDicomObject>) {
+        (
+            NEventReportRsp::new(rq, Status::UNRECOGNIZED_OPERATION),
+            None,
+        )
+    }
+
+    /// Handle an N-GET request.
+    fn n_get(&self, _ctx: &ServiceContext, rq: &NGetRq) -> (NGetRsp, Option<InMemDicomObject>) {
+        (NGetRsp::new(rq, Status::UNRECOGNIZED_OPERATION), None)
+    }
+
+    /// Handle an N-SET request.
+    fn n_set(
+        &self,
+        _ctx: &ServiceContext,
+        rq: &NSetRq,
+        _dataset: Option<InMemDicomObject>,
+    ) -> (NSetRsp, Option<InMemDicomObject>) {
+        (NSetRsp::new(rq, Status::UNRECOGNIZED_OPERATION), None)
+    }
+
+    /// Handle an N-ACTION request.
+    fn n_action(
+        &self,
+        _ctx: &ServiceContext,
+        rq: &NActionRq,
+        _dataset: Option<InMemDicomObject>,
+    ) -> (NActionRsp, Option<InMemDicomObject>) {
+        (NActionRsp::new(rq, Status::UNRECOGNIZED_OPERATION), None)
+    }
+
+    /// Handle an N-CREATE request.
+    fn n_create(
+        &self,
+        _ctx: &ServiceContext,
+        rq: &NCreateRq,
+        _dataset: Option<InMemDicomObject>,
+    ) -> (NCreateRsp, Option<InMemDicomObject>) {
+        (NCreateRsp::new(rq, Status::UNRECOGNIZED_OPERATION), None)
+    }
+
+    /// Handle an N-DELETE request.
+    fn n_delete(
+        &self,
+        _ctx: &ServiceContext,
+        rq: &NDeleteRq,
+    ) -> (NDeleteRsp, Option<InMemDicomObject>) {
+        (NDeleteRsp::new(rq, Status::UNRECOGNIZED_OPERATION), None)
+    }
+}
+
+/// Handler of storage commitment requests
+/// (Storage Commitment Push Model).
+///
+/// The service class provider takes care of
+/// responding to the N-ACTION request
+/// and of reporting the result back to the requester
+/// in an N-EVENT-REPORT request.
+pub trait CommitmentHandler: Send + Sync {
+    /// Verify the instances referenced in the request,
+    /// telling which of them are committed to
+    /// and which could not be.
+    fn commit(&self, ctx: &ServiceContext, request: &CommitmentRequest) -> CommitmentResult;
+
+    /// Resolve the network address (`host:port`)
+    /// of the requester with the given AE title,
+    /// so as to report the result on a new association
+    /// once the requesting association is over.
+    ///
+    /// The default implementation returns `None`,
+    /// so that the result is reported on the same association
+    /// right after responding to the request.
+    fn report_destination(&self, _ae_title: &str) -> Option<String> {
+        None
+    }
+}
+
+/// A composite instance to be sent in a C-STORE sub-operation.
+///
+/// Only the instance's identification is kept in memory
+/// until the instance is about to be sent,
+/// so that large retrievals do not need to hold every object at once.
+#[derive(Debug, Clone)]
+pub struct RetrieveItem {
+    sop_class_uid: String,
+    sop_instance_uid: String,
+    transfer_syntax: String,
+    source: ItemSource,
+}
+
+#[derive(Debug, Clone)]
+enum ItemSource {
+    Path(PathBuf),
+    Object(Box<DefaultDicomObject>),
+}
+
+impl RetrieveItem {
+    /// Create a retrieve item for a DICOM file
+    /// of which the identification is already known.
+    pub fn new(
+        sop_class_uid: impl Into<String>,
+        sop_instance_uid: impl Into<String>,
+        transfer_syntax: impl Into<String>,
+        path: impl Into<PathBuf>,
+    ) -> Self {
+        RetrieveItem {
+            sop_class_uid: sop_class_uid.into(),
+            sop_instance_uid: sop_instance_uid.into(),
+            transfer_syntax: transfer_syntax.into(),
+            source: ItemSource::Path(path.into()),
+        }
+    }
+
+    /// Create a retrieve item for a DICOM file,
+    /// reading only its file meta group to identify it.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, ReadError> {
+        let path = path.as_ref();
+        let obj = OpenFileOptions::new()
+            .read_until(Tag(0x0001, 0x0000))
+            .open_file(path)?;
+        let meta = obj.meta();
+        Ok(RetrieveItem {
+            sop_class_uid: trim_padding(&meta.media_storage_sop_class_uid).to_string(),
+            sop_instance_uid: trim_padding(&meta.media_storage_sop_instance_uid).to_string(),
+            transfer_syntax: trim_padding(&meta.transfer_syntax).to_string(),
+            source: ItemSource::Path(path.to_owned()),
+        })
+    }
+
+    /// Create a retrieve item for a DICOM object already in memory.
+    pub fn from_object(obj: DefaultDicomObject) -> Self {
+        let meta = obj.meta();
+        RetrieveItem {
+            sop_class_uid: trim_padding(&meta.media_storage_sop_class_uid).to_string(),
+            sop_instance_uid: trim_padding(&meta.media_storage_sop_instance_uid).to_string(),
+            transfer_syntax: trim_padding(&meta.transfer_syntax).to_string(),
+            source: ItemSource::Object(Box::new(obj)),
+        }
+    }
+
+    /// The SOP class UID of the instance.
+    pub fn sop_class_uid(&self) -> &str {
+        &self.sop_class_uid
+    }
+
+    /// The SOP instance UID of the instance.
+    pub fn sop_instance_uid(&self) -> &str {
+        &self.sop_instance_uid
+    }
+
+    /// The UID of the transfer syntax in which the instance is encoded.
+    pub fn transfer_syntax(&self) -> &str {
+        &self.transfer_syntax
+    }
+
+    /// Fetch the full DICOM object.
+    pub fn load(self) -> Result<DefaultDicomObject, ReadError> {
+        match self.source {
+            ItemSource::Path(path) => dicom_object::open_file(path),
+            ItemSource::Object(obj) => Ok(*obj),
+        }
+    }
+}
@@ -0,0 +1,497 @@
+//! Attribute matching for query services.
+//!
+//! This module implements the matching rules of C-FIND
+//! (see PS3.4 section C.2.2.2),
+//! so that [`FindHandler`](super::FindHandler) implementations
+//! can check records against the identifier of a request
+//! and build the identifiers to send back:
+//!
+//! - an empty key (or a single `*`) is a _universal match_;
+//! - string keys with `*` or `?` are _wildcard matches_;
+//! - UI keys with several values are _UID list matches_;
+//! - DA, TM and DT keys holding a `-` are _range matches_,
+//!   other values of these types match
+//!   any value within their precision
+//!   (`2024` matches every date in 2024);
+//! - SQ keys are _sequence matches_,
+//!   where a record matches if any of its sequence items
+//!   matches all keys in the first item of the key;
+//! - keys with any other value are _single value matches_.
+//!
+//! When a key holds several values,
+//! the record matches if any of them matches.
+//! The Query/Retrieve Level and Specific Character Set
+//! are not matching keys and are ignored.
+//!
+//! # Example
+//!
+//! ```
+//! # use dicom_core::{DataElement, PrimitiveValue, VR};
+//! # use dicom_dictionary_std::tags;
+//! # use dicom_object::InMemDicomObject;
+//! use dicom_ul::scp::matching::{matches, response};
+//!
+//! let record = InMemDicomObject::from_element_iter([
+//!     DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::from("Doe^John")),
+//!     DataElement::new(tags::STUDY_DATE, VR::DA, PrimitiveValue::from("20240315")),
+//!     DataElement::new(tags::STUDY_DESCRIPTION, VR::LO, PrimitiveValue::from("Chest")),
+//! ]);
+//! let identifier = InMemDicomObject::from_element_iter([
+//!     DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::from("Doe^*")),
+//!     DataElement::new(tags::STUDY_DATE, VR::DA, PrimitiveValue::from("20240101-20240630")),
+//!     DataElement::new(tags::STUDY_DESCRIPTION, VR::LO, PrimitiveValue::Empty),
+//! ]);
+//!
+//! assert!(matches(&identifier, &record));
+//! let rsp = response(&identifier, &record);
+//! assert_eq!(rsp.get(tags::STUDY_DESCRIPTION).unwrap().to_str().unwrap(), "Chest");
+//! ```
+use dicom_core::{
+    header::Header,
+    value::{AsRange, DataSetSequence, PrimitiveValue},
+    DataElement, Tag, VR,
+};
+use dicom_dictionary_std::tags;
+use dicom_object::{mem::InMemElement, InMemDicomObject};
+
+use crate::association::trim_padding;
+
+/// Check whether a record matches all matching keys in the identifier.
+pub fn matches(identifier: &InMemDicomObject, record: &InMemDicomObject) -> bool {
+    identifier.iter().all(|key| matches_key(key, record))
+}
+
+/// Check whether a record matches a single key of an identifier.
+pub fn matches_key(key: &InMemElement, record: &InMemDicomObject) -> bool {
+    let tag = key.tag();
+    if !is_matching_key(tag) {
+        return true;
+    }
+    if key.vr() == VR::SQ {
+        return matches_sequence(key, record.get(tag));
+    }
+
+    let query = match key.to_str() {
+        Ok(query) => query,
+        // keys which are not textual cannot be matched
+        Err(_) => return true,
+    };
+    let query = trim(&query);
+    if query.is_empty() || query == "*" {
+        return true;
+    }
+
+    let values = match record.get(tag).and_then(|e| e.to_multi_str().ok()) {
+        Some(values) => values,
+        None => return false,
+    };
+    let values: Vec<&str> = values.iter().map(|v| trim(v)).collect();
+
+    match key.vr() {
+        VR::DA => values.iter().any(|v| matches_date(query, v)),
+        VR::TM => values.iter().any(|v| matches_time(query, v)),
+        VR::DT => values.iter().any(|v| matches_datetime(query, v)),
+        // these are never multi-valued, so backslashes are part of the value
+        VR::LT | VR::ST | VR::UT | VR::UR => values.iter().any(|v| matches_string(query, v)),
+        _ => query
+            .split('\\')
+            .map(trim)
+            .any(|query| values.iter().any(|v| matches_string(query, v))),
+    }
+}
+
+/// Build the identifier to respond with for a matching record.
+///
+/// The response holds the record's value for each key of the identifier,
+/// or an empty value if the record does not have it.
+/// Sequence keys with items only keep the record's items
+/// which match the key's first item,
+/// each one projected to its keys.
+/// The Query/Retrieve Level is copied from the identifier,
+/// and the record's Specific Character Set is always included.
+pub fn response(identifier: &InMemDicomObject, record: &InMemDicomObject) -> InMemDicomObject {
+    let mut rsp = InMemDicomObject::new_empty();
+    for key in identifier.iter() {
+        let tag = key.tag();
+        let element = if tag == tags::QUERY_RETRIEVE_LEVEL {
+            key.clone()
+        } else if key.vr() == VR::SQ {
+            response_sequence(key, record.get(tag))
+        } else {
+            match record.get(tag) {
+                Some(element) => element.clone(),
+                None => DataElement::empty(tag, key.vr()),
+            }
+        };
+        rsp.put(element);
+    }
+    if let Some(charset) = record.get(tags::SPECIFIC_CHARACTER_SET) {
+        rsp.put(charset.clone());
+    }
+    rsp
+}
+
+fn is_matching_key(tag: Tag) -> bool {
+    tag != tags::QUERY_RETRIEVE_LEVEL && tag != tags::SPECIFIC_CHARACTER_SET && tag.element() != 0
+}
+
+fn matches_sequence(key: &InMemElement, element: Option<&InMemElement>) -> bool {
+    let query = match key.items().and_then(|items| items.first()) {
+        Some(query) => query,
+        None => return true,
+    };
+    // a query item of universal keys matches anything
+    if matches(query, &InMemDicomObject::new_empty()) {
+        return true;
+    }
+    element
+        .and_then(|e| e.items())
+        .map(|items| items.iter().any(|item| matches(query, item)))
+        .unwrap_or(false)
+}
+
+fn response_sequence(key: &InMemElement, element: Option<&InMemElement>) -> InMemElement {
+    let tag = key.tag();
+    let items = match element.and_then(|e| e.items()) {
+        Some(items) => items,
+        None => return DataElement::empty(tag, VR::SQ),
+    };
+    let query = match key.items().and_then(|items| items.first()) {
+        Some(query) if query.iter().next().is_some() => query,
+        // return the full sequence
+        _ => {
+            return element
+                .cloned()
+                .unwrap_or_else(|| DataElement::empty(tag, VR::SQ))
+        }
+    };
+    let items: Vec<_> = items
+        .iter()
+        .filter(|item| matches(query, item))
+        .map(|item| response(query, item))
+        .collect();
+    DataElement::new(tag, VR::SQ, DataSetSequence::from(items))
+}
+
+fn matches_string(query: &str, value: &str) -> bool {
+    if query.contains(['*', '?'].as_ref()) {
+        matches_wildcard(query.as_bytes(), value.as_bytes())
+    } else {
+        query == value
+    }
+}
+
+/// Match a value against a pattern where
+/// `*` stands for any sequence of characters
+/// and `?` for any single character.
+fn matches_wildcard(pattern: &[u8], value: &[u8]) -> bool {
+    let (mut p, mut v) = (0, 0);
+    // position of the last `*` in the pattern,
+    // and of the value when it was found
+    let mut backtrack = None;
+    while v < value.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, v));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                // let the last `*` absorb one more character
+                Some((star, at)) => {
+                    p = star + 1;
+                    v = at + 1;
+                    backtrack = Some((star, at + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+fn matches_date(query: &str, value: &str) -> bool {
+    let date = match PrimitiveValue::from(value)
+        .to_date()
+        .ok()
+        .and_then(|d| d.earliest().ok())
+    {
+        Some(date) => date,
+        None => return false,
+    };
+    let range = if query.contains('-') {
+        PrimitiveValue::from(query).to_date_range().ok()
+    } else {
+        PrimitiveValue::from(query)
+            .to_date()
+            .ok()
+            .and_then(|d| d.range().ok())
+    };
+    match range {
+        Some(range) => {
+            range.start().map_or(true, |start| &date >= start)
+                && range.end().map_or(true, |end| &date <= end)
+        }
+        None => false,
+    }
+}
+
+fn matches_time(query: &str, value: &str) -> bool {
+    let time = match PrimitiveValue::from(value)
+        .to_time()
+        .ok()
+        .and_then(|t| t.earliest().ok())
+    {
+        Some(time) => time,
+        None => return false,
+    };
+    let range = if query.contains('-') {
+        PrimitiveValue::from(query).to_time_range().ok()
+    } else {
+        PrimitiveValue::from(query)
+            .to_time()
+            .ok()
+            .and_then(|t| t.range().ok())
+    };
+    match range {
+        Some(range) => {
+            range.start().map_or(true, |start| &time >= start)
+                && range.end().map_or(true, |end| &time <= end)
+        }
+        None => false,
+    }
+}
+
+fn matches_datetime(query: &str, value: &str) -> bool {
+    let datetime = match PrimitiveValue::from(value)
+        .to_datetime()
+        .ok()
+        .and_then(|dt| dt.earliest().ok())
+    {
+        Some(datetime) => datetime,
+        None => return false,
+    };
+    let range = if query.contains('-') {
+        PrimitiveValue::from(query).to_datetime_range().ok()
+    } else {
+        PrimitiveValue::from(query)
+            .to_datetime()
+            .ok()
+            .and_then(|dt| dt.range().ok())
+    };
+    match range {
+        Some(range) => {
+            range.start().map_or(true, |start| datetime >= start)
+                && range.end().map_or(true, |end| datetime <= end)
+        }
+        None => false,
+    }
+}
+
+/// Remove the padding of a value,
+/// as well as any leading spaces, which are not significant in matching.
+fn trim(value: &str) -> &str {
+    trim_padding(value).trim_start_matches(' ')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::header::HasLength;
+
+    fn record() -> InMemDicomObject {
+        InMemDicomObject::from_element_iter(vec![
+            DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::from("Doe^John")),
+            DataElement::new(tags::PATIENT_ID, VR::LO, PrimitiveValue::from("P001 ")),
+            DataElement::new(tags::STUDY_DATE, VR::DA, PrimitiveValue::from("20240315")),
+            DataElement::new(tags::STUDY_TIME, VR::TM, PrimitiveValue::from("101500")),
+            DataElement::new(
+                tags::MODALITIES_IN_STUDY,
+                VR::CS,
+                PrimitiveValue::Strs(vec!["CT".to_string(), "SR".to_string()].into()),
+            ),
+            DataElement::new(
+                tags::STUDY_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from("1.2.3.4\0"),
+            ),
+        ])
+    }
+
+    fn key(tag: Tag, vr: VR, value: &str) -> InMemElement {
+        DataElement::new(tag, vr, PrimitiveValue::from(value))
+    }
+
+    #[test]
+    fn universal_and_single_value_matching() {
+        let record = record();
+        assert!(matches_key(&key(tags::PATIENT_ID, VR::LO, ""), &record));
+        assert!(matches_key(&key(tags::PATIENT_ID, VR::LO, "*"), &record));
+        assert!(matches_key(&key(tags::PATIENT_ID, VR::LO, "P001"), &record));
+        assert!(!matches_key(&key(tags::PATIENT_ID, VR::LO, "P00"), &record));
+        // the record does not have the attribute
+        assert!(!matches_key(
+            &key(tags::ACCESSION_NUMBER, VR::SH, "A1"),
+            &record
+        ));
+        assert!(matches_key(
+            &key(tags::ACCESSION_NUMBER, VR::SH, ""),
+            &record
+        ));
+    }
+
+    #[test]
+    fn wildcard_matching() {
+        assert!(matches_wildcard(b"Doe^*", b"Doe^John"));
+        assert!(matches_wildcard(b"*^J?hn", b"Doe^John"));
+        assert!(matches_wildcard(b"*o*o*", b"Doe^John"));
+        assert!(matches_wildcard(b"D**", b"D"));
+        assert!(!matches_wildcard(b"Doe^?", b"Doe^John"));
+        assert!(!matches_wildcard(b"*x*", b"Doe^John"));
+    }
+
+    #[test]
+    fn multiple_values_and_uid_lists() {
+        let record = record();
+        assert!(matches_key(
+            &key(tags::MODALITIES_IN_STUDY, VR::CS, "SR"),
+            &record
+        ));
+        assert!(matches_key(
+            &key(tags::MODALITIES_IN_STUDY, VR::CS, "MR\\CT"),
+            &record
+        ));
+        assert!(!matches_key(
+            &key(tags::MODALITIES_IN_STUDY, VR::CS, "MR"),
+            &record
+        ));
+        assert!(matches_key(
+            &key(tags::STUDY_INSTANCE_UID, VR::UI, "1.2.3.5\\1.2.3.4"),
+            &record
+        ));
+        assert!(!matches_key(
+            &key(tags::STUDY_INSTANCE_UID, VR::UI, "1.2.3"),
+            &record
+        ));
+    }
+
+    #[test]
+    fn date_and_time_ranges() {
+        let record = record();
+        for (query, expected) in [
+            ("20240315", true),
+            ("20240316", false),
+            ("202403", true),
+            ("20240101-20240630", true),
+            ("20240316-", false),
+            ("-20240315", true),
+            ("2023-", true),
+        ] {
+            assert_eq!(
+                matches_key(&key(tags::STUDY_DATE, VR::DA, query), &record),
+                expected,
+                "date query {}",
+                query
+            );
+        }
+        for (query, expected) in [("10-11", true), ("1016-", false), ("10", true)] {
+            assert_eq!(
+                matches_key(&key(tags::STUDY_TIME, VR::TM, query), &record),
+                expected,
+                "time query {}",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn datetime_ranges() {
+        let record = InMemDicomObject::from_element_iter(vec![key(
+            tags::ACQUISITION_DATE_TIME,
+            VR::DT,
+            "20240315101500",
+        )]);
+        for (query, expected) in [
+            ("2024", true),
+            ("20240315-20240316", true),
+            ("202403151016-", false),
+        ] {
+            assert_eq!(
+                matches_key(&key(tags::ACQUISITION_DATE_TIME, VR::DT, query), &record),
+                expected,
+                "date-time query {}",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn sequence_matching_and_response() {
+        let step = |modality: &str, station: &str| {
+            InMemDicomObject::from_element_iter(vec![
+                key(tags::MODALITY, VR::CS, modality),
+                key(tags::SCHEDULED_STATION_AE_TITLE, VR::AE, station),
+            ])
+        };
+        let record = InMemDicomObject::from_element_iter(vec![DataElement::new(
+            tags::SCHEDULED_PROCEDURE_STEP_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![step("CT", "CT1"), step("MR", "MR1")]),
+        )]);
+        let identifier = |modality: &str| {
+            InMemDicomObject::from_element_iter(vec![DataElement::new(
+                tags::SCHEDULED_PROCEDURE_STEP_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter(vec![
+                    key(tags::MODALITY, VR::CS, modality),
+                    key(tags::SCHEDULED_STATION_AE_TITLE, VR::AE, ""),
+                ])]),
+            )])
+        };
+
+        assert!(matches(&identifier("MR"), &record));
+        assert!(!matches(&identifier("US"), &record));
+
+        let rsp = response(&identifier("MR"), &record);
+        let items = rsp
+            .get(tags::SCHEDULED_PROCEDURE_STEP_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0]
+                .get(tags::SCHEDULED_STATION_AE_TITLE)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "MR1"
+        );
+    }
+
+    #[test]
+    fn response_holds_requested_keys() {
+        let identifier = InMemDicomObject::from_element_iter(vec![
+            key(tags::QUERY_RETRIEVE_LEVEL, VR::CS, "STUDY"),
+            key(tags::PATIENT_NAME, VR::PN, ""),
+            key(tags::ACCESSION_NUMBER, VR::SH, ""),
+        ]);
+        let rsp = response(&identifier, &record());
+        assert_eq!(
+            rsp.get(tags::QUERY_RETRIEVE_LEVEL)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "STUDY"
+        );
+        assert_eq!(
+            rsp.get(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "Doe^John"
+        );
+        assert!(rsp.get(tags::ACCESSION_NUMBER).unwrap().is_empty());
+        assert!(rsp.get(tags::PATIENT_ID).is_none());
+    }
+}
@@ -0,0 +1,1640 @@
+//! Service class provider module
+//!
+//! This module provides a framework for writing DICOM service class providers
+//! (SCPs) out of pluggable handlers,
+//! one for each DIMSE service to support.
+//! The [`ServiceClassProvider`] takes care of
+//! negotiating associations,
+//! dispatching each request to the handler registered for its SOP class,
+//! sending the responses back,
+//! and handling association release and abort.
+//!
+//! The handler traits are defined in the [`handler`] module:
+//!
+//! - [`EchoHandler`] for C-ECHO;
+//! - [`StoreHandler`] for C-STORE;
+//! - [`FindHandler`] for C-FIND;
+//! - [`MoveHandler`] and [`GetHandler`] for C-MOVE and C-GET,
+//!   where the provider performs the C-STORE sub-operations
+//!   and reports their progress;
+//! - [`NormalizedHandler`] for the DIMSE-N services;
+//! - [`CommitmentHandler`] for storage commitment requests,
+//!   where the provider reports the result back to the requester.
+//!
+//! Requests for SOP classes without a handler are answered
+//! with the status _SOP Class Not Supported_.
+//! Query handlers may rely on the [`matching`] module
+//! to apply the standard attribute matching rules.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_ul::scp::{AcceptEcho, ServiceClassProvider, ServiceContext};
+//! # use dicom_ul::dimse::{composite::CStoreRq, Status};
+//! # use dicom_object::InMemDicomObject;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let scp = ServiceClassProvider::new()
+//!     .ae_title("STORE-SCP")
+//!     .with_echo_handler(AcceptEcho)
+//!     .with_store_handler(
+//!         ["1.2.840.10008.5.1.4.1.1.7"],
+//!         |_ctx: &ServiceContext, rq: &CStoreRq, _obj: InMemDicomObject| {
+//!             println!("received {}", rq.affected_sop_instance_uid);
+//!             Status::SUCCESS
+//!         },
+//!     );
+//!
+//! let listener = std::net::TcpListener::bind("0.0.0.0:11111")?;
+//! scp.serve(listener);
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::io::Read;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::tags;
+use dicom_encoding::transfer_syntax::{TransferSyntax, TransferSyntaxIndex};
+use dicom_object::InMemDicomObject;
+use dicom_transfer_syntax_registry::{entries, TransferSyntaxRegistry};
+use snafu::{OptionExt, ResultExt, Snafu};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    association::{
+        client::ClientAssociationOptions,
+        server::{self, AcceptAny, AcceptCalledAeTitle, AccessControl, DataSetReader},
+        trim_padding, ClientAssociation, ServerAssociation, ServerAssociationOptions, Transport,
+    },
+    commitment::{
+        CommitmentRequest, CommitmentResult, REQUEST_STORAGE_COMMITMENT,
+        STORAGE_COMMITMENT_PUSH_MODEL, STORAGE_COMMITMENT_PUSH_MODEL_INSTANCE,
+    },
+    dimse::{
+        composite::{
+            CEchoRsp, CFindRq, CFindRsp, CGetRq, CGetRsp, CMoveRq, CMoveRsp, CStoreRq, CStoreRsp,
+            SubOperations,
+        },
+        normalized::{NActionRq, NActionRsp, NEventReportRq, NEventReportRsp},
+        Command, DimseMessage, Status,
+    },
+    pdu::{Pdu, PresentationContextResultReason},
+};
+
+pub mod handler;
+pub mod matching;
+
+pub use handler::{
+    AcceptEcho, CommitmentHandler, EchoHandler, FindHandler, FindResult, GetHandler, MoveHandler,
+    NormalizedHandler, RetrieveItem, RetrieveResult, StoreHandler,
+};
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// failed to establish association
+    Establish {
+        #[snafu(backtrace)]
+        source: server::Error,
+    },
+
+    /// failed to receive message
+    Receive {
+        #[snafu(backtrace)]
+        source: server::Error,
+    },
+
+    /// failed to send message
+    Send {
+        #[snafu(backtrace)]
+        source: server::Error,
+    },
+
+    /// failed to prepare message
+    Dimse {
+        #[snafu(backtrace)]
+        source: crate::dimse::Error,
+    },
+
+    #[snafu(display("message for unknown presentation context {}", id))]
+    UnknownPresentationContext { id: u8, backtrace: snafu::Backtrace },
+
+    #[snafu(display("unsupported transfer syntax {}", uid))]
+    UnsupportedTransferSyntax {
+        uid: String,
+        backtrace: snafu::Backtrace,
+    },
+
+    /// failed to prepare TCP stream
+    PrepareStream {
+        source: std::io::Error,
+        backtrace: snafu::Backtrace,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The circumstances of a request passed to a service handler.
+#[derive(Clone)]
+pub struct ServiceContext {
+    calling_ae_title: String,
+    called_ae_title: String,
+    presentation_context_id: u8,
+    transfer_syntax: &'static TransferSyntax,
+}
+
+impl std::fmt::Debug for ServiceContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceContext")
+            .field("calling_ae_title", &self.calling_ae_title)
+            .field("called_ae_title", &self.called_ae_title)
+            .field("presentation_context_id", &self.presentation_context_id)
+            .field("transfer_syntax", &self.transfer_syntax.uid())
+            .finish()
+    }
+}
+
+impl ServiceContext {
+    /// The application entity title of the requesting node.
+    pub fn calling_ae_title(&self) -> &str {
+        &self.calling_ae_title
+    }
+
+    /// The application entity title which the requesting node called.
+    pub fn called_ae_title(&self) -> &str {
+        &self.called_ae_title
+    }
+
+    /// The identifier of the presentation context
+    /// in which the request was received.
+    pub fn presentation_context_id(&self) -> u8 {
+        self.presentation_context_id
+    }
+
+    /// The transfer syntax negotiated for the presentation context.
+    pub fn transfer_syntax(&self) -> &'static TransferSyntax {
+        self.transfer_syntax
+    }
+}
+
+/// A DICOM service class provider built out of service handlers.
+///
+/// Registering a handler for a SOP class
+/// also makes the provider accept presentation contexts
+/// with that abstract syntax.
+/// See the [module-level documentation](self) for an example.
+pub struct ServiceClassProvider<A = AcceptAny> {
+    options: ServerAssociationOptions<'static, A>,
+    ae_title: String,
+    max_associations: usize,
+    echo: Option<Arc<dyn EchoHandler>>,
+    store: HashMap<String, Arc<dyn StoreHandler>>,
+    default_store: Option<Arc<dyn StoreHandler>>,
+    find: HashMap<String, Arc<dyn FindHandler>>,
+    move_: HashMap<String, Arc<dyn MoveHandler>>,
+    get: HashMap<String, Arc<dyn GetHandler>>,
+    normalized: HashMap<String, Arc<dyn NormalizedHandler>>,
+    commitment: Option<Arc<dyn CommitmentHandler>>,
+    /// whether to secure incoming connections with TLS
+    #[cfg(feature = "sync-tls")]
+    tls: bool,
+}
+
+impl<A: std::fmt::Debug> std::fmt::Debug for ServiceClassProvider<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceClassProvider")
+            .field("options", &self.options)
+            .field("max_associations", &self.max_associations)
+            .field("echo", &self.echo.is_some())
+            .field("store", &self.store.keys())
+            .field("find", &self.find.keys())
+            .field("move", &self.move_.keys())
+            .field("get", &self.get.keys())
+            .field("normalized", &self.normalized.keys())
+            .field("commitment", &self.commitment.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for ServiceClassProvider<AcceptAny> {
+    fn default() -> Self {
+        ServiceClassProvider {
+            options: ServerAssociationOptions::new(),
+            ae_title: "THIS-SCP".to_string(),
+            max_associations: 16,
+            echo: None,
+            store: HashMap::new(),
+            default_store: None,
+            find: HashMap::new(),
+            move_: HashMap::new(),
+            get: HashMap::new(),
+            normalized: HashMap::new(),
+            commitment: None,
+            #[cfg(feature = "sync-tls")]
+            tls: false,
+        }
+    }
+}
+
+impl ServiceClassProvider<AcceptAny> {
+    /// Create a new service class provider without any handlers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<A> ServiceClassProvider<A>
+where
+    A: AccessControl,
+{
+    /// Define the application entity title referring to this DICOM node.
+    ///
+    /// The default is `THIS-SCP`.
+    pub fn ae_title(mut self, ae_title: impl Into<String>) -> Self {
+        self.ae_title = ae_title.into();
+        self.options = self.options.ae_title(self.ae_title.clone());
+        self
+    }
+
+    /// Change the access control policy to accept an association
+    /// if the called AE title matches this node's AE title.
+    pub fn accept_called_ae_title(self) -> ServiceClassProvider<AcceptCalledAeTitle> {
+        self.ae_access_control(AcceptCalledAeTitle)
+    }
+
+    /// Change the access control policy.
+    ///
+    /// The default is to accept any requesting node.
+    pub fn ae_access_control<P>(self, access_control: P) -> ServiceClassProvider<P>
+    where
+        P: AccessControl,
+    {
+        ServiceClassProvider {
+            options: self.options.ae_access_control(access_control),
+            ae_title: self.ae_title,
+            max_associations: self.max_associations,
+            echo: self.echo,
+            store: self.store,
+            default_store: self.default_store,
+            find: self.find,
+            move_: self.move_,
+            get: self.get,
+            normalized: self.normalized,
+            commitment: self.commitment,
+            #[cfg(feature = "sync-tls")]
+            tls: self.tls,
+        }
+    }
+
+    /// Restrict the accepted transfer syntaxes to the ones given.
+    ///
+    /// By default, any transfer syntax in the main registry is accepted.
+    pub fn with_transfer_syntax(mut self, transfer_syntax_uid: impl Into<String>) -> Self {
+        self.options = self
+            .options
+            .with_transfer_syntax(transfer_syntax_uid.into());
+        self
+    }
+
+    /// Accept only the given transfer syntaxes for this abstract syntax,
+    /// preferring them in the order given.
+    ///
+    /// See [`ServerAssociationOptions::with_transfer_syntax_preference`].
+    pub fn with_transfer_syntax_preference<I, T>(
+        mut self,
+        abstract_syntax_uid: impl Into<String>,
+        transfer_syntax_uids: I,
+    ) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.options = self.options.with_transfer_syntax_preference(
+            abstract_syntax_uid.into(),
+            transfer_syntax_uids
+                .into_iter()
+                .map(Into::into)
+                .collect::<Vec<String>>(),
+        );
+        self
+    }
+
+    /// Override the maximum expected PDU length.
+    pub fn max_pdu_length(mut self, value: u32) -> Self {
+        self.options = self.options.max_pdu_length(value);
+        self
+    }
+
+    /// Override strict mode:
+    /// whether receiving PDUs must not
+    /// surpass the negotiated maximum PDU length.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options = self.options.strict(strict);
+        self
+    }
+
+    /// Override promiscuous mode:
+    /// whether to accept unknown abstract syntaxes.
+    ///
+    /// Storage requests for SOP classes without a dedicated handler
+    /// are passed to the default store handler, if any.
+    pub fn promiscuous(mut self, promiscuous: bool) -> Self {
+        self.options = self.options.promiscuous(promiscuous);
+        self
+    }
+
+    /// Support the negotiation of an asynchronous operations window,
+    /// so that requesters may have several requests outstanding.
+    ///
+    /// Requests are still served one at a time, in order of arrival.
+    /// See [`ServerAssociationOptions::async_operations_window`].
+    pub fn async_operations_window(mut self, max_invoked: u16, max_performed: u16) -> Self {
+        self.options = self
+            .options
+            .async_operations_window(max_invoked, max_performed);
+        self
+    }
+
+    /// Set the timeout for the underlying TCP sockets.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options = self.options.timeout(timeout);
+        self
+    }
+
+    /// Secure all incoming connections with TLS,
+    /// using the given configuration.
+    ///
+    /// See the [`tls`](crate::tls) module
+    /// for a convenient way to build one.
+    #[cfg(feature = "sync-tls")]
+    pub fn tls_config(mut self, config: Arc<crate::tls::rustls::ServerConfig>) -> Self {
+        self.options = self.options.tls_config(config);
+        self.tls = true;
+        self
+    }
+
+    /// Set the maximum number of associations served at the same time
+    /// by [`serve`](Self::serve).
+    ///
+    /// The default is 16.
+    pub fn max_associations(mut self, max_associations: usize) -> Self {
+        self.max_associations = max_associations.max(1);
+        self
+    }
+
+    fn with_abstract_syntaxes<I, T>(mut self, sop_class_uids: I) -> (Self, Vec<String>)
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let uids: Vec<String> = sop_class_uids
+            .into_iter()
+            .map(|uid| trim_padding(&uid.into()).to_string())
+            .collect();
+        for uid in &uids {
+            self.options = self.options.with_abstract_syntax(uid.clone());
+        }
+        (self, uids)
+    }
+
+    /// Register the handler of verification requests.
+    pub fn with_echo_handler(self, handler: impl EchoHandler + 'static) -> Self {
+        let (mut this, _) = self.with_abstract_syntaxes([uids::VERIFICATION]);
+        this.echo = Some(Arc::new(handler));
+        this
+    }
+
+    /// Register a storage handler for the given SOP classes.
+    pub fn with_store_handler<I, T>(
+        self,
+        sop_class_uids: I,
+        handler: impl StoreHandler + 'static,
+    ) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let (mut this, uids) = self.with_abstract_syntaxes(sop_class_uids);
+        let handler: Arc<dyn StoreHandler> = Arc::new(handler);
+        for uid in uids {
+            this.store.insert(uid, Arc::clone(&handler));
+        }
+        this
+    }
+
+    /// Register the storage handler for SOP classes
+    /// without a dedicated store handler.
+    ///
+    /// This is only useful in promiscuous mode,
+    /// as no abstract syntax is added to the accepted ones.
+    pub fn with_default_store_handler(mut self, handler: impl StoreHandler + 'static) -> Self {
+        self.default_store = Some(Arc::new(handler));
+        self
+    }
+
+    /// Register a query handler for the given information models.
+    pub fn with_find_handler<I, T>(
+        self,
+        sop_class_uids: I,
+        handler: impl FindHandler + 'static,
+    ) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let (mut this, uids) = self.with_abstract_syntaxes(sop_class_uids);
+        let handler: Arc<dyn FindHandler> = Arc::new(handler);
+        for uid in uids {
+            this.find.insert(uid, Arc::clone(&handler));
+        }
+        this
+    }
+
+    /// Register a C-MOVE handler for the given information models.
+    pub fn with_move_handler<I, T>(
+        self,
+        sop_class_uids: I,
+        handler: impl MoveHandler + 'static,
+    ) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let (mut this, uids) = self.with_abstract_syntaxes(sop_class_uids);
+        let handler: Arc<dyn MoveHandler> = Arc::new(handler);
+        for uid in uids {
+            this.move_.insert(uid, Arc::clone(&handler));
+        }
+        this
+    }
+
+    /// Register a C-GET handler for the given information models.
+    ///
+    /// The storage SOP classes of the retrieved instances
+    /// must also be negotiated by the requester,
+    /// so they should be accepted as well,
+    /// either by registering them with [`with_abstract_syntax`](Self::with_abstract_syntax)
+    /// or through promiscuous mode.
+    pub fn with_get_handler<I, T>(
+        self,
+        sop_class_uids: I,
+        handler: impl GetHandler + 'static,
+    ) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let (mut this, uids) = self.with_abstract_syntaxes(sop_class_uids);
+        let handler: Arc<dyn GetHandler> = Arc::new(handler);
+        for uid in uids {
+            this.get.insert(uid, Arc::clone(&handler));
+        }
+        this
+    }
+
+    /// Register a handler of DIMSE-N services for the given SOP classes.
+    pub fn with_normalized_handler<I, T>(
+        self,
+        sop_class_uids: I,
+        handler: impl NormalizedHandler + 'static,
+    ) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let (mut this, uids) = self.with_abstract_syntaxes(sop_class_uids);
+        let handler: Arc<dyn NormalizedHandler> = Arc::new(handler);
+        for uid in uids {
+            this.normalized.insert(uid, Arc::clone(&handler));
+        }
+        this
+    }
+
+    /// Register the handler of storage commitment requests
+    /// (Storage Commitment Push Model).
+    pub fn with_commitment_handler(self, handler: impl CommitmentHandler + 'static) -> Self {
+        let (mut this, _) = self.with_abstract_syntaxes([STORAGE_COMMITMENT_PUSH_MODEL]);
+        this.commitment = Some(Arc::new(handler));
+        this
+    }
+
+    /// Accept presentation contexts with this abstract syntax
+    /// without registering a handler for it.
+    pub fn with_abstract_syntax(mut self, abstract_syntax_uid: impl Into<String>) -> Self {
+        self.options = self
+            .options
+            .with_abstract_syntax(trim_padding(&abstract_syntax_uid.into()).to_string());
+        self
+    }
+
+    /// Serve a single association over the given TCP stream,
+    /// until it is released or aborted.
+    ///
+    /// The connection is secured with TLS first
+    /// if a TLS configuration was given.
+    pub fn handle(&self, stream: TcpStream) -> Result<()> {
+        #[cfg(feature = "sync-tls")]
+        if self.tls {
+            let association = self.options.establish_tls(stream).context(EstablishSnafu)?;
+            return self.serve_association(association);
+        }
+        let association = self.options.establish(stream).context(EstablishSnafu)?;
+        self.serve_association(association)
+    }
+
+    /// Serve a single association over an already connected transport,
+    /// such as a Unix domain socket
+    /// or an in-memory [`duplex`](crate::association::duplex) pipe,
+    /// until it is released or aborted.
+    pub fn handle_over<S: Transport>(&self, stream: S) -> Result<()> {
+        let association = self
+            .options
+            .establish_over(stream)
+            .context(EstablishSnafu)?;
+        self.serve_association(association)
+    }
+
+    /// Serve an established association until it is released or aborted.
+    fn serve_association<S: Transport>(&self, mut association: ServerAssociation<S>) -> Result<()> {
+        info!("New association from {}", association.client_ae_title());
+
+        let mut state = AssociationState::default();
+        let outcome = loop {
+            // serve what arrived during the last operation first
+            let received = match state.deferred.pop_front() {
+                Some(received) => received.map(|msg| (msg, None)),
+                None => association
+                    .receive_dimse_streaming()
+                    .and_then(|(msg, data)| match data {
+                        Some(data) => self.receive_data_set(msg, data),
+                        None => Ok((msg, None)),
+                    }),
+            };
+            match received {
+                Ok((msg, stored)) => {
+                    if let Err(e) = self.dispatch(&mut association, &mut state, msg, stored) {
+                        break Err(e);
+                    }
+                }
+                Err(server::Error::UnexpectedRequest { pdu, .. }) => match *pdu {
+                    Pdu::ReleaseRQ => {
+                        let sent = association.send(&Pdu::ReleaseRP).context(SendSnafu);
+                        info!(
+                            "Released association with {}",
+                            association.client_ae_title()
+                        );
+                        break sent;
+                    }
+                    Pdu::AbortRQ { source } => {
+                        warn!("Aborted connection from: {:?}", source);
+                        break Ok(());
+                    }
+                    pdu => {
+                        warn!("Unexpected PDU {:?}, aborting", pdu);
+                        break association.abort().context(SendSnafu);
+                    }
+                },
+                Err(e) => break Err(e).context(ReceiveSnafu),
+            }
+        };
+
+        for report in state.deferred_reports {
+            self.send_report(report);
+        }
+        outcome
+    }
+
+    /// Accept and serve associations from the given TCP listener,
+    /// each in its own thread,
+    /// for as long as the listener produces connections.
+    ///
+    /// No more than the configured maximum number of associations
+    /// are served at the same time.
+    pub fn serve(self, listener: TcpListener)
+    where
+        A: Send + Sync + 'static,
+    {
+        let scp = Arc::new(self);
+        let slots = Arc::new(Slots::new(scp.max_associations));
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    error!("Failed to accept connection: {}", e);
+                    continue;
+                }
+            };
+            let slot = Slots::acquire(&slots);
+            let scp = Arc::clone(&scp);
+            std::thread::spawn(move || {
+                let _slot = slot;
+                if let Err(e) = scp.handle(stream) {
+                    error!("{}", snafu::Report::from_error(e));
+                }
+            });
+        }
+    }
+
+    /// Receive the rest of the data set of a message.
+    ///
+    /// The data set of a C-STORE request is passed on
+    /// to its storage handler as it arrives,
+    /// yielding the status of the operation.
+    /// Any other data set is collected into the message.
+    fn receive_data_set<S: Transport>(
+        &self,
+        mut msg: DimseMessage,
+        mut data: DataSetReader<'_, S>,
+    ) -> Result<(DimseMessage, Option<Status>), server::Error> {
+        if let Command::CStoreRq(rq) = &msg.command {
+            let handler = self
+                .store
+                .get(&rq.affected_sop_class_uid)
+                .or(self.default_store.as_ref());
+            let ctx = self
+                .context(data.association(), msg.presentation_context_id)
+                .ok();
+            if let (Some(handler), Some(ctx)) = (handler, ctx) {
+                let status = handler.store_from(&ctx, rq, &mut data);
+                data.finish()?;
+                return Ok((msg, Some(status)));
+            }
+        }
+
+        let mut buffer = Vec::new();
+        // a failure to receive the data set is reported when finishing
+        let _ = data.read_to_end(&mut buffer);
+        data.finish()?;
+        msg.data = Some(buffer);
+        Ok((msg, None))
+    }
+
+    /// Dispatch a request to the appropriate handler and respond to it.
+    ///
+    /// `stored` is the status of a C-STORE request
+    /// already handled while receiving its data set.
+    fn dispatch<S: Transport>(
+        &self,
+        association: &mut ServerAssociation<S>,
+        state: &mut AssociationState,
+        msg: DimseMessage,
+        stored: Option<Status>,
+    ) -> Result<()> {
+        let ctx = self.context(association, msg.presentation_context_id)?;
+        let pc_id = msg.presentation_context_id;
+
+        if let (Command::NActionRq(rq), Some(handler)) = (&msg.command, &self.commitment) {
+            if rq.requested_sop_class_uid == STORAGE_COMMITMENT_PUSH_MODEL {
+                return self.commit(association, state, &ctx, handler.as_ref(), rq, &msg);
+            }
+        }
+
+        match &msg.command {
+            Command::CEchoRq(rq) => {
+                let status = match &self.echo {
+                    Some(handler) => handler.echo(&ctx, rq),
+                    None => Status::SOP_CLASS_NOT_SUPPORTED,
+                };
+                respond(
+                    association,
+                    DimseMessage::new(pc_id, CEchoRsp::new(rq, status)),
+                )
+            }
+            Command::CStoreRq(rq) => {
+                let handler = self
+                    .store
+                    .get(&rq.affected_sop_class_uid)
+                    .or(self.default_store.as_ref());
+                let status = match (stored, handler, &msg.data) {
+                    (Some(status), _, _) => status,
+                    (None, Some(handler), Some(data)) => {
+                        handler.store_from(&ctx, rq, &mut data.as_slice())
+                    }
+                    (None, Some(_), None) => {
+                        warn!("Missing data set in C-STORE request");
+                        Status::UNABLE_TO_PROCESS
+                    }
+                    (None, None, _) => Status::SOP_CLASS_NOT_SUPPORTED,
+                };
+                respond(
+                    association,
+                    DimseMessage::new(pc_id, CStoreRsp::new(rq, status)),
+                )
+            }
+            Command::CFindRq(rq) => self.find(association, &ctx, rq, &msg),
+            Command::CMoveRq(rq) => self.move_(association, state, &ctx, rq, &msg),
+            Command::CGetRq(rq) => self.get(association, state, &ctx, rq, &msg),
+            Command::CCancelRq(rq) => {
+                debug!(
+                    "Ignoring C-CANCEL for message {} with no operation in progress",
+                    rq.message_id_being_responded_to
+                );
+                Ok(())
+            }
+            Command::NEventReportRq(rq) => {
+                let (rsp, data) = match self.normalized.get(&rq.affected_sop_class_uid) {
+                    Some(handler) => handler.n_event_report(&ctx, rq, dataset(&msg, &ctx)),
+                    None => (
+                        crate::dimse::normalized::NEventReportRsp::new(
+                            rq,
+                            Status::NO_SUCH_SOP_CLASS,
+                        ),
+                        None,
+                    ),
+                };
+                respond_with(association, &ctx, rsp, data)
+            }
+            Command::NGetRq(rq) => {
+                let (rsp, data) = match self.normalized.get(&rq.requested_sop_class_uid) {
+                    Some(handler) => handler.n_get(&ctx, rq),
+                    None => (
+                        crate::dimse::normalized::NGetRsp::new(rq, Status::NO_SUCH_SOP_CLASS),
+                        None,
+                    ),
+                };
+                respond_with(association, &ctx, rsp, data)
+            }
+            Command::NSetRq(rq) => {
+                let (rsp, data) = match self.normalized.get(&rq.requested_sop_class_uid) {
+                    Some(handler) => handler.n_set(&ctx, rq, dataset(&msg, &ctx)),
+                    None => (
+                        crate::dimse::normalized::NSetRsp::new(rq, Status::NO_SUCH_SOP_CLASS),
+                        None,
+                    ),
+                };
+                respond_with(association, &ctx, rsp, data)
+            }
+            Command::NActionRq(rq) => {
+                let (rsp, data) = match self.normalized.get(&rq.requested_sop_class_uid) {
+                    Some(handler) => handler.n_action(&ctx, rq, dataset(&msg, &ctx)),
+                    None => (
+                        crate::dimse::normalized::NActionRsp::new(rq, Status::NO_SUCH_SOP_CLASS),
+                        None,
+                    ),
+                };
+                respond_with(association, &ctx, rsp, data)
+            }
+            Command::NCreateRq(rq) => {
+                let (rsp, data) = match self.normalized.get(&rq.affected_sop_class_uid) {
+                    Some(handler) => handler.n_create(&ctx, rq, dataset(&msg, &ctx)),
+                    None => (
+                        crate::dimse::normalized::NCreateRsp::new(rq, Status::NO_SUCH_SOP_CLASS),
+                        None,
+                    ),
+                };
+                respond_with(association, &ctx, rsp, data)
+            }
+            Command::NDeleteRq(rq) => {
+                let (rsp, data) = match self.normalized.get(&rq.requested_sop_class_uid) {
+                    Some(handler) => handler.n_delete(&ctx, rq),
+                    None => (
+                        crate::dimse::normalized::NDeleteRsp::new(rq, Status::NO_SUCH_SOP_CLASS),
+                        None,
+                    ),
+                };
+                respond_with(association, &ctx, rsp, data)
+            }
+            Command::NEventReportRsp(rsp) => {
+                if rsp.status.is_success() {
+                    debug!(
+                        "Event report {} acknowledged",
+                        rsp.message_id_being_responded_to
+                    );
+                } else {
+                    warn!(
+                        "Event report {} not acknowledged: {}",
+                        rsp.message_id_being_responded_to, rsp.status
+                    );
+                }
+                Ok(())
+            }
+            command => {
+                warn!("Ignoring unexpected {:?} message", command.command_field());
+                Ok(())
+            }
+        }
+    }
+
+    /// Respond to a storage commitment request,
+    /// then report its result
+    /// either on the same association
+    /// or on a new one once this association is over.
+    fn commit<S: Transport>(
+        &self,
+        association: &mut ServerAssociation<S>,
+        state: &mut AssociationState,
+        ctx: &ServiceContext,
+        handler: &dyn CommitmentHandler,
+        rq: &NActionRq,
+        msg: &DimseMessage,
+    ) -> Result<()> {
+        if rq.action_type_id != REQUEST_STORAGE_COMMITMENT {
+            let rsp = NActionRsp::new(rq, Status::NO_SUCH_ACTION_TYPE);
+            return respond_with(association, ctx, rsp, None);
+        }
+        let request = match dataset(msg, ctx).map(|obj| CommitmentRequest::from_dataset(&obj)) {
+            Some(Ok(request)) => request,
+            Some(Err(e)) => {
+                warn!(
+                    "Invalid storage commitment request: {}",
+                    snafu::Report::from_error(e)
+                );
+                let rsp = NActionRsp::new(rq, Status::INVALID_ARGUMENT_VALUE);
+                return respond_with(association, ctx, rsp, None);
+            }
+            None => {
+                let rsp = NActionRsp::new(rq, Status::INVALID_ARGUMENT_VALUE);
+                return respond_with(association, ctx, rsp, None);
+            }
+        };
+        respond_with(association, ctx, NActionRsp::new(rq, Status::SUCCESS), None)?;
+
+        let result = handler.commit(ctx, &request);
+        info!(
+            "Storage commitment {}: {} committed, {} failed",
+            result.transaction_uid,
+            result.committed.len(),
+            result.failed.len()
+        );
+        match handler.report_destination(&ctx.calling_ae_title) {
+            Some(address) => {
+                state.deferred_reports.push(DeferredReport {
+                    ae_title: ctx.calling_ae_title.clone(),
+                    address,
+                    result,
+                });
+                Ok(())
+            }
+            None => {
+                let message_id = state.next_message_id();
+                let msg = report_message(
+                    ctx.presentation_context_id,
+                    message_id,
+                    &result,
+                    ctx.transfer_syntax,
+                )
+                .context(DimseSnafu)?;
+                respond(association, msg)
+            }
+        }
+    }
+
+    /// Report a storage commitment result on a new association,
+    /// in which the requester takes the SCP role.
+    fn send_report(&self, report: DeferredReport) {
+        let DeferredReport {
+            ae_title,
+            address,
+            result,
+        } = report;
+        let options = ClientAssociationOptions::new()
+            .calling_ae_title(self.ae_title.clone())
+            .called_ae_title(ae_title.clone())
+            .with_presentation_context(
+                STORAGE_COMMITMENT_PUSH_MODEL,
+                vec![
+                    entries::EXPLICIT_VR_LITTLE_ENDIAN.uid(),
+                    entries::IMPLICIT_VR_LITTLE_ENDIAN.uid(),
+                ],
+            )
+            .with_role_selection(STORAGE_COMMITMENT_PUSH_MODEL, false, true);
+        let mut association = match options.establish(address.as_str()) {
+            Ok(association) => association,
+            Err(e) => {
+                warn!(
+                    "Could not connect to {} to report storage commitment {}: {}",
+                    ae_title,
+                    result.transaction_uid,
+                    snafu::Report::from_error(e)
+                );
+                return;
+            }
+        };
+
+        let pc = association.presentation_contexts()[0].clone();
+        let outcome = TransferSyntaxRegistry
+            .get(&pc.transfer_syntax)
+            .ok_or_else(|| format!("unsupported transfer syntax {}", pc.transfer_syntax))
+            .and_then(|ts| {
+                report_message(pc.id, 1, &result, ts)
+                    .map_err(|e| snafu::Report::from_error(e).to_string())
+            })
+            .and_then(|msg| {
+                association
+                    .send_dimse(&msg)
+                    .and_then(|_| association.receive_dimse())
+                    .map_err(|e| snafu::Report::from_error(e).to_string())
+            })
+            .and_then(|rsp| {
+                NEventReportRsp::try_from(rsp.command)
+                    .map_err(|e| snafu::Report::from_error(e).to_string())
+            });
+        match outcome {
+            Ok(rsp) if rsp.status.is_success() => {
+                info!(
+                    "Reported storage commitment {} to {}",
+                    result.transaction_uid, ae_title
+                );
+                let _ = association.release();
+            }
+            Ok(rsp) => {
+                warn!(
+                    "Storage commitment report {} not acknowledged by {}: {}",
+                    result.transaction_uid, ae_title, rsp.status
+                );
+                let _ = association.release();
+            }
+            Err(e) => {
+                warn!(
+                    "Could not report storage commitment {} to {}: {}",
+                    result.transaction_uid, ae_title, e
+                );
+                let _ = association.abort();
+            }
+        }
+    }
+
+    fn context<S: Transport>(
+        &self,
+        association: &ServerAssociation<S>,
+        id: u8,
+    ) -> Result<ServiceContext> {
+        let pc = association
+            .presentation_contexts()
+            .iter()
+            .find(|pc| pc.id == id && pc.reason == PresentationContextResultReason::Acceptance)
+            .context(UnknownPresentationContextSnafu { id })?;
+        let transfer_syntax = TransferSyntaxRegistry.get(&pc.transfer_syntax).context(
+            UnsupportedTransferSyntaxSnafu {
+                uid: pc.transfer_syntax.clone(),
+            },
+        )?;
+        Ok(ServiceContext {
+            calling_ae_title: association.client_ae_title().to_string(),
+            called_ae_title: association.called_ae_title().to_string(),
+            presentation_context_id: id,
+            transfer_syntax,
+        })
+    }
+
+    fn find<S: Transport>(
+        &self,
+        association: &mut ServerAssociation<S>,
+        ctx: &ServiceContext,
+        rq: &CFindRq,
+        msg: &DimseMessage,
+    ) -> Result<()> {
+        let pc_id = ctx.presentation_context_id;
+        let status = match (self.find.get(&rq.affected_sop_class_uid), dataset(msg, ctx)) {
+            (None, _) => Status::SOP_CLASS_NOT_SUPPORTED,
+            (Some(_), None) => Status::UNABLE_TO_PROCESS,
+            (Some(handler), Some(identifier)) => match handler.find(ctx, rq, &identifier) {
+                Ok(matches) => {
+                    for obj in matches {
+                        let rsp = DimseMessage::new(pc_id, CFindRsp::new(rq, Status::PENDING))
+                            .with_dataset(&obj, ctx.transfer_syntax)
+                            .context(DimseSnafu)?;
+                        respond(association, rsp)?;
+                    }
+                    Status::SUCCESS
+                }
+                Err(status) => status,
+            },
+        };
+        respond(
+            association,
+            DimseMessage::new(pc_id, CFindRsp::new(rq, status)),
+        )
+    }
+
+    fn move_<S: Transport>(
+        &self,
+        association: &mut ServerAssociation<S>,
+        state: &mut AssociationState,
+        ctx: &ServiceContext,
+        rq: &CMoveRq,
+        msg: &DimseMessage,
+    ) -> Result<()> {
+        let pc_id = ctx.presentation_context_id;
+        let fail = |association: &mut ServerAssociation<S>, status| {
+            respond(
+                association,
+                DimseMessage::new(pc_id, CMoveRsp::new(rq, status)),
+            )
+        };
+
+        let handler = match self.move_.get(&rq.affected_sop_class_uid) {
+            Some(handler) => handler,
+            None => return fail(association, Status::SOP_CLASS_NOT_SUPPORTED),
+        };
+        let identifier = match dataset(msg, ctx) {
+            Some(identifier) => identifier,
+            None => return fail(association, Status::UNABLE_TO_PROCESS),
+        };
+        let address = match handler.destination(&rq.move_destination) {
+            Some(address) => address,
+            None => {
+                warn!("Unknown move destination {}", rq.move_destination);
+                return fail(association, Status::MOVE_DESTINATION_UNKNOWN);
+            }
+        };
+        let items = match handler.retrieve(ctx, rq, &identifier) {
+            Ok(items) => items,
+            Err(status) => return fail(association, status),
+        };
+
+        let mut progress = Progress::new(items.len());
+        let mut interruption = Interruption::Continue;
+        if !items.is_empty() {
+            let (mut store, contexts) =
+                match self.connect_destination(&rq.move_destination, &address, &items) {
+                    Ok(destination) => destination,
+                    Err(e) => {
+                        warn!(
+                            "Could not connect to move destination {}: {}",
+                            rq.move_destination,
+                            snafu::Report::from_error(e)
+                        );
+                        return fail(association, Status::OUT_OF_RESOURCES_SUB_OPERATIONS);
+                    }
+                };
+
+            for (i, item) in items.into_iter().enumerate() {
+                let sop_instance_uid = item.sop_instance_uid().to_string();
+                let originator = Some((ctx.calling_ae_title(), rq.message_id));
+                let status = prepare_store(&contexts, item, i as u16 + 1, originator)
+                    .and_then(|store_rq| send_store(&mut store, &store_rq));
+                progress.record(status, sop_instance_uid);
+
+                // take note of anything sent in the meantime
+                while let Some(received) = association.try_receive_dimse().transpose() {
+                    interruption = state.interrupt(rq.message_id, received)?;
+                    if interruption != Interruption::Continue {
+                        break;
+                    }
+                }
+
+                if interruption != Interruption::Continue {
+                    break;
+                }
+                if progress.remaining > 0 {
+                    let mut rsp = CMoveRsp::new(rq, Status::PENDING);
+                    rsp.sub_operations = progress.sub_operations(true);
+                    respond(association, DimseMessage::new(pc_id, rsp))?;
+                }
+            }
+
+            if let Err(e) = store.release() {
+                warn!("Failed to release association with move destination: {}", e);
+            }
+        }
+
+        let status = match interruption {
+            Interruption::Continue => progress.final_status(),
+            Interruption::Cancel | Interruption::Release => Status::CANCEL,
+            // there is no one left to respond to
+            Interruption::Abort => return Ok(()),
+        };
+        let mut rsp = CMoveRsp::new(rq, status);
+        rsp.sub_operations = progress.sub_operations(interruption != Interruption::Continue);
+        respond_with(association, ctx, rsp, progress.failed_list())
+    }
+
+    fn get<S: Transport>(
+        &self,
+        association: &mut ServerAssociation<S>,
+        state: &mut AssociationState,
+        ctx: &ServiceContext,
+        rq: &CGetRq,
+        msg: &DimseMessage,
+    ) -> Result<()> {
+        let pc_id = ctx.presentation_context_id;
+        let fail = |association: &mut ServerAssociation<S>, status| {
+            respond(
+                association,
+                DimseMessage::new(pc_id, CGetRsp::new(rq, status)),
+            )
+        };
+
+        let handler = match self.get.get(&rq.affected_sop_class_uid) {
+            Some(handler) => handler,
+            None => return fail(association, Status::SOP_CLASS_NOT_SUPPORTED),
+        };
+        let identifier = match dataset(msg, ctx) {
+            Some(identifier) => identifier,
+            None => return fail(association, Status::UNABLE_TO_PROCESS),
+        };
+        let items = match handler.retrieve(ctx, rq, &identifier) {
+            Ok(items) => items,
+            Err(status) => return fail(association, status),
+        };
+
+        let contexts: Vec<_> = association
+            .presentation_contexts()
+            .iter()
+            .filter(|pc| pc.reason == PresentationContextResultReason::Acceptance)
+            .filter_map(|pc| {
+                association
+                    .abstract_syntax(pc.id)
+                    .map(|uid| (pc.id, uid.to_string(), pc.transfer_syntax.clone()))
+            })
+            .collect();
+
+        let mut progress = Progress::new(items.len());
+        let mut interruption = Interruption::Continue;
+        for (i, item) in items.into_iter().enumerate() {
+            let sop_instance_uid = item.sop_instance_uid().to_string();
+            let store_rq = match prepare_store(&contexts, item, i as u16 + 1, None) {
+                Ok(store_rq) => store_rq,
+                Err(()) => {
+                    progress.record(Err(()), sop_instance_uid);
+                    continue;
+                }
+            };
+            respond(association, store_rq)?;
+
+            // wait for the C-STORE response,
+            // taking note of anything else sent in the meantime
+            let status = loop {
+                match association.receive_dimse() {
+                    Ok(DimseMessage {
+                        command: Command::CStoreRsp(rsp),
+                        ..
+                    }) => break Ok(rsp.status),
+                    received => match state.interrupt(rq.message_id, received)? {
+                        Interruption::Continue => {}
+                        // the sub-operation in progress still completes
+                        Interruption::Cancel => interruption = Interruption::Cancel,
+                        ending => {
+                            interruption = ending;
+                            break Err(());
+                        }
+                    },
+                }
+            };
+            progress.record(status, sop_instance_uid);
+
+            if interruption != Interruption::Continue {
+                break;
+            }
+            if progress.remaining > 0 {
+                let mut rsp = CGetRsp::new(rq, Status::PENDING);
+                rsp.sub_operations = progress.sub_operations(true);
+                respond(association, DimseMessage::new(pc_id, rsp))?;
+            }
+        }
+
+        let status = match interruption {
+            Interruption::Continue => progress.final_status(),
+            Interruption::Cancel | Interruption::Release => Status::CANCEL,
+            // there is no one left to respond to
+            Interruption::Abort => return Ok(()),
+        };
+        let mut rsp = CGetRsp::new(rq, status);
+        rsp.sub_operations = progress.sub_operations(interruption != Interruption::Continue);
+        respond_with(association, ctx, rsp, progress.failed_list())
+    }
+
+    /// Open an association with a move destination,
+    /// proposing presentation contexts for all instances to send.
+    fn connect_destination(
+        &self,
+        ae_title: &str,
+        address: &str,
+        items: &[RetrieveItem],
+    ) -> Result<
+        (ClientAssociation<TcpStream>, Vec<AcceptedContext>),
+        crate::association::client::Error,
+    > {
+        let mut proposed: Vec<(String, Vec<String>)> = Vec::new();
+        for item in items {
+            let mut transfer_syntaxes = vec![item.transfer_syntax().to_string()];
+            if is_native(item.transfer_syntax()) {
+                for uid in [
+                    entries::EXPLICIT_VR_LITTLE_ENDIAN.uid(),
+                    entries::IMPLICIT_VR_LITTLE_ENDIAN.uid(),
+                ] {
+                    if !transfer_syntaxes.iter().any(|ts| ts == uid) {
+                        transfer_syntaxes.push(uid.to_string());
+                    }
+                }
+            }
+            let proposal = (item.sop_class_uid().to_string(), transfer_syntaxes);
+            if !proposed.contains(&proposal) {
+                proposed.push(proposal);
+            }
+        }
+        // there can be no more than 128 presentation contexts
+        proposed.truncate(128);
+
+        let mut options = ClientAssociationOptions::new()
+            .calling_ae_title(self.ae_title.clone())
+            .called_ae_title(ae_title.to_string());
+        for (abstract_syntax, transfer_syntaxes) in &proposed {
+            options = options
+                .with_presentation_context(abstract_syntax.clone(), transfer_syntaxes.clone());
+        }
+        let association = options.establish(address)?;
+
+        // proposed presentation contexts are identified by odd numbers in order
+        let contexts = association
+            .presentation_contexts()
+            .iter()
+            .filter_map(|pc| {
+                proposed
+                    .get(usize::from(pc.id / 2))
+                    .map(|(abstract_syntax, _)| {
+                        (pc.id, abstract_syntax.clone(), pc.transfer_syntax.clone())
+                    })
+            })
+            .collect();
+        Ok((association, contexts))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<A> ServiceClassProvider<A>
+where
+    A: AccessControl + Send + Sync + 'static,
+{
+    /// Accept associations from the given asynchronous TCP listener,
+    /// serving each of them in a blocking task.
+    ///
+    /// No more than the configured maximum number of associations
+    /// are served at the same time.
+    pub async fn serve_async(self, listener: tokio::net::TcpListener) {
+        let scp = Arc::new(self);
+        let slots = Arc::new(tokio::sync::Semaphore::new(scp.max_associations));
+
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _addr)) => stream,
+                Err(e) => {
+                    error!("Failed to accept connection: {}", e);
+                    continue;
+                }
+            };
+            let slot = match Arc::clone(&slots).acquire_owned().await {
+                Ok(slot) => slot,
+                // the semaphore is never closed
+                Err(_) => return,
+            };
+            let scp = Arc::clone(&scp);
+            tokio::task::spawn_blocking(move || {
+                let _slot = slot;
+                let result = stream
+                    .into_std()
+                    .and_then(|stream| {
+                        stream.set_nonblocking(false)?;
+                        Ok(stream)
+                    })
+                    .context(PrepareStreamSnafu)
+                    .and_then(|stream| scp.handle(stream));
+                if let Err(e) = result {
+                    error!("{}", snafu::Report::from_error(e));
+                }
+            });
+        }
+    }
+}
+
+/// Well-known SOP class UIDs used by the framework.
+mod uids {
+    pub const VERIFICATION: &str = "1.2.840.10008.1.1";
+}
+
+/// A counting limit on the number of associations served at once.
+#[derive(Debug)]
+struct Slots {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+/// A taken association slot, given back when dropped.
+#[derive(Debug)]
+struct Slot(Arc<Slots>);
+
+impl Slots {
+    fn new(count: usize) -> Self {
+        Slots {
+            available: Mutex::new(count),
+            released: Condvar::new(),
+        }
+    }
+
+    fn acquire(this: &Arc<Self>) -> Slot {
+        let mut available = this.available.lock().unwrap_or_else(|e| e.into_inner());
+        while *available == 0 {
+            available = this
+                .released
+                .wait(available)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        *available -= 1;
+        Slot(Arc::clone(this))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut available = self.0.available.lock().unwrap_or_else(|e| e.into_inner());
+        *available += 1;
+        self.0.released.notify_one();
+    }
+}
+
+/// The state kept while serving an association.
+#[derive(Debug, Default)]
+struct AssociationState {
+    /// the message ID of the last request sent by this node
+    last_message_id: u16,
+    /// storage commitment results to report on a new association
+    /// once this one is over
+    deferred_reports: Vec<DeferredReport>,
+    /// what the requester sent while an operation was in progress,
+    /// to be served once it is over
+    deferred: VecDeque<std::result::Result<DimseMessage, server::Error>>,
+}
+
+impl AssociationState {
+    /// Obtain the message ID for a new request sent by this node.
+    fn next_message_id(&mut self) -> u16 {
+        self.last_message_id = self.last_message_id.wrapping_add(1);
+        self.last_message_id
+    }
+
+    /// Take note of what the requester sent
+    /// while the operation with the given message ID is in progress.
+    ///
+    /// A C-CANCEL of the operation is reported right away.
+    /// Anything else is deferred until the operation is over,
+    /// including a release or abort,
+    /// which also ask the operation to stop.
+    fn interrupt(
+        &mut self,
+        message_id: u16,
+        received: std::result::Result<DimseMessage, server::Error>,
+    ) -> Result<Interruption> {
+        let interruption = match &received {
+            Ok(DimseMessage {
+                command: Command::CCancelRq(cancel),
+                ..
+            }) if cancel.message_id_being_responded_to == message_id => {
+                return Ok(Interruption::Cancel)
+            }
+            Ok(msg) => {
+                debug!(
+                    "Deferring {:?} message until message {} is complete",
+                    msg.command.command_field(),
+                    message_id
+                );
+                Interruption::Continue
+            }
+            Err(server::Error::UnexpectedRequest { pdu, .. }) => match **pdu {
+                Pdu::ReleaseRQ => Interruption::Release,
+                _ => {
+                    // nothing else will be answered
+                    self.deferred.clear();
+                    Interruption::Abort
+                }
+            },
+            Err(_) => {
+                return received
+                    .map(|_| Interruption::Continue)
+                    .context(ReceiveSnafu)
+            }
+        };
+        self.deferred.push_back(received);
+        Ok(interruption)
+    }
+}
+
+/// What the requester asked for
+/// while an operation was in progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Interruption {
+    /// nothing which affects the operation
+    Continue,
+    /// cancel the operation
+    Cancel,
+    /// release the association
+    Release,
+    /// abort the association,
+    /// or anything else which ends it abruptly
+    Abort,
+}
+
+/// A storage commitment result to report on a new association.
+#[derive(Debug)]
+struct DeferredReport {
+    ae_title: String,
+    address: String,
+    result: CommitmentResult,
+}
+
+/// The bookkeeping of C-STORE sub-operations.
+#[derive(Debug)]
+struct Progress {
+    remaining: u16,
+    completed: u16,
+    failed: u16,
+    warning: u16,
+    failed_uids: Vec<String>,
+}
+
+impl Progress {
+    fn new(count: usize) -> Self {
+        Progress {
+            remaining: u16::try_from(count).unwrap_or(u16::MAX),
+            completed: 0,
+            failed: 0,
+            warning: 0,
+            failed_uids: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, status: Result<Status, ()>, sop_instance_uid: String) {
+        self.remaining = self.remaining.saturating_sub(1);
+        match status {
+            Ok(status) if status.is_success() => self.completed += 1,
+            Ok(status) if status.is_warning() => self.warning += 1,
+            _ => {
+                self.failed += 1;
+                self.failed_uids.push(sop_instance_uid);
+            }
+        }
+    }
+
+    fn sub_operations(&self, with_remaining: bool) -> SubOperations {
+        SubOperations {
+            remaining: Some(self.remaining).filter(|_| with_remaining),
+            completed: Some(self.completed),
+            failed: Some(self.failed),
+            warning: Some(self.warning),
+        }
+    }
+
+    fn final_status(&self) -> Status {
+        if self.failed > 0 && self.completed == 0 && self.warning == 0 {
+            // none of the sub-operations could be performed
+            Status::OUT_OF_RESOURCES_SUB_OPERATIONS
+        } else if self.failed > 0 || self.warning > 0 {
+            Status::SUB_OPERATIONS_COMPLETE_WITH_FAILURES
+        } else {
+            Status::SUCCESS
+        }
+    }
+
+    /// The identifier listing the instances which could not be sent, if any.
+    fn failed_list(&self) -> Option<InMemDicomObject> {
+        if self.failed_uids.is_empty() {
+            return None;
+        }
+        Some(InMemDicomObject::from_element_iter([DataElement::new(
+            tags::FAILED_SOP_INSTANCE_UID_LIST,
+            VR::UI,
+            PrimitiveValue::Strs(self.failed_uids.iter().cloned().collect()),
+        )]))
+    }
+}
+
+/// An accepted presentation context:
+/// its identifier, abstract syntax and transfer syntax.
+type AcceptedContext = (u8, String, String);
+
+/// Build the C-STORE request of a sub-operation,
+/// in a presentation context which can convey the instance.
+fn prepare_store(
+    contexts: &[AcceptedContext],
+    item: RetrieveItem,
+    message_id: u16,
+    move_originator: Option<(&str, u16)>,
+) -> Result<DimseMessage, ()> {
+    let (pc_id, ts) = contexts
+        .iter()
+        .filter(|(_, abstract_syntax, _)| abstract_syntax == item.sop_class_uid())
+        .find(|(_, _, ts)| ts == item.transfer_syntax())
+        .or_else(|| {
+            contexts.iter().find(|(_, abstract_syntax, ts)| {
+                abstract_syntax == item.sop_class_uid()
+                    && is_native(ts)
+                    && is_native(item.transfer_syntax())
+            })
+        })
+        .and_then(|(id, _, ts)| TransferSyntaxRegistry.get(ts).map(|ts| (*id, ts)))
+        .ok_or_else(|| {
+            warn!(
+                "No presentation context for {} in {}",
+                item.sop_class_uid(),
+                item.transfer_syntax()
+            );
+        })?;
+
+    let mut rq = CStoreRq::new(message_id, item.sop_class_uid(), item.sop_instance_uid());
+    if let Some((ae_title, message_id)) = move_originator {
+        rq = rq.with_move_originator(ae_title, message_id);
+    }
+    let obj = item
+        .load()
+        .map_err(|e| warn!("Could not load instance: {}", snafu::Report::from_error(e)))?;
+    DimseMessage::new(pc_id, rq)
+        .with_dataset(&obj, ts)
+        .map_err(|e| {
+            warn!(
+                "Could not encode instance: {}",
+                snafu::Report::from_error(e)
+            )
+        })
+}
+
+/// Perform a C-STORE sub-operation on a separate association.
+fn send_store(
+    association: &mut ClientAssociation<TcpStream>,
+    msg: &DimseMessage,
+) -> Result<Status, ()> {
+    association
+        .send_dimse(msg)
+        .map_err(|e| warn!("Failed to send C-STORE request: {}", e))?;
+    let rsp = association
+        .receive_dimse()
+        .map_err(|e| warn!("Failed to receive C-STORE response: {}", e))?;
+    CStoreRsp::try_from(rsp.command)
+        .map(|rsp| rsp.status)
+        .map_err(|e| warn!("Unexpected C-STORE response: {}", e))
+}
+
+/// Whether the transfer syntax can be converted to other native encodings
+/// without transcoding pixel data.
+fn is_native(uid: &str) -> bool {
+    TransferSyntaxRegistry
+        .get(uid)
+        .map(|ts| ts.is_codec_free())
+        .unwrap_or(false)
+}
+
+/// Decode the data set of a request,
+/// logging the reason if it is missing or invalid.
+fn dataset(msg: &DimseMessage, ctx: &ServiceContext) -> Option<InMemDicomObject> {
+    match msg.dataset(ctx.transfer_syntax) {
+        Ok(Some(obj)) => Some(obj),
+        Ok(None) => {
+            warn!(
+                "Missing data set in {:?} request",
+                msg.command.command_field()
+            );
+            None
+        }
+        Err(e) => {
+            warn!(
+                "Could not decode data set: {}",
+                snafu::Report::from_error(e)
+            );
+            None
+        }
+    }
+}
+
+/// Build the N-EVENT-REPORT request conveying a storage commitment result.
+fn report_message(
+    pc_id: u8,
+    message_id: u16,
+    result: &CommitmentResult,
+    ts: &TransferSyntax,
+) -> Result<DimseMessage, crate::dimse::Error> {
+    DimseMessage::new(
+        pc_id,
+        NEventReportRq::new(
+            message_id,
+            STORAGE_COMMITMENT_PUSH_MODEL,
+            STORAGE_COMMITMENT_PUSH_MODEL_INSTANCE,
+            result.event_type_id(),
+        ),
+    )
+    .with_dataset(&result.to_dataset(), ts)
+}
+
+fn respond<S: Transport>(association: &mut ServerAssociation<S>, msg: DimseMessage) -> Result<()> {
+    association.send_dimse(&msg).context(SendSnafu)
+}
+
+/// Send a response with an optional data set
+/// in the presentation context of the request.
+fn respond_with<S: Transport>(
+    association: &mut ServerAssociation<S>,
+    ctx: &ServiceContext,
+    rsp: impl Into<Command>,
+    data: Option<InMemDicomObject>,
+) -> Result<()> {
+    let mut msg = DimseMessage::new(ctx.presentation_context_id, rsp);
+    if let Some(data) = data {
+        msg = msg
+            .with_dataset(&data, ctx.transfer_syntax)
+            .context(DimseSnafu)?;
+    }
+    respond(association, msg)
+}
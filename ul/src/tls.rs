@@ -0,0 +1,443 @@
+//! Secure transport connections with TLS.
+//!
+//! This module provides the building blocks for
+//! establishing DICOM associations over TLS,
+//! as described in the secure transport connection profiles
+//! of the DICOM standard (PS3.15, Annex B).
+//! TLS is implemented with [`rustls`],
+//! which is re-exported here for convenience.
+//!
+//! [`TlsOptions`] helps creating client and server configurations
+//! from certificates and private keys in PEM files.
+//! Then, the resulting configuration can be passed to
+//! [`ClientAssociationOptions::tls_config`](crate::ClientAssociationOptions::tls_config)
+//! or [`ServerAssociationOptions::tls_config`](crate::ServerAssociationOptions::tls_config),
+//! and the association is established through
+//! [`establish_tls`](crate::ClientAssociationOptions::establish_tls)
+//! (or [`establish_tls`](crate::ServerAssociationOptions::establish_tls)
+//! on the acceptor side).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_ul::association::client::ClientAssociationOptions;
+//! # use dicom_ul::tls::TlsOptions;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let tls_config = TlsOptions::new()
+//!     .ca_certificates("ca.pem")
+//!     .certificate_chain("client.pem")
+//!     .private_key("client.key")
+//!     .client_config()?;
+//!
+//! let association = ClientAssociationOptions::new()
+//!     .with_abstract_syntax("1.2.840.10008.1.1")
+//!     .tls_config(tls_config)
+//!     .establish_with_tls("STORE-SCP@pacs.example.com:2762")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Features
+//!
+//! This module is only available with the `sync-tls` feature.
+//! Enable `async-tls` to also establish TLS associations asynchronously.
+use std::{
+    net::TcpStream,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+pub use rustls;
+use rustls::{
+    crypto::{ring, CryptoProvider},
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    ClientConfig, ClientConnection, ConnectionCommon, RootCertStore, ServerConfig,
+    ServerConnection, StreamOwned, SupportedCipherSuite, SupportedProtocolVersion,
+};
+use snafu::{ResultExt, Snafu};
+
+use crate::association::{client::CloseSocket, Transport};
+
+/// A synchronous TLS stream from the perspective of the association requester.
+pub type TlsClientStream<T = TcpStream> = StreamOwned<ClientConnection, T>;
+
+/// A synchronous TLS stream from the perspective of the association acceptor.
+pub type TlsServerStream<T = TcpStream> = StreamOwned<ServerConnection, T>;
+
+/// An asynchronous TLS stream from the perspective of the association requester.
+#[cfg(feature = "async-tls")]
+pub type AsyncTlsClientStream = tokio_rustls::client::TlsStream<tokio::net::TcpStream>;
+
+/// An asynchronous TLS stream from the perspective of the association acceptor.
+#[cfg(feature = "async-tls")]
+pub type AsyncTlsServerStream = tokio_rustls::server::TlsStream<tokio::net::TcpStream>;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// Could not read certificates from {}
+    #[snafu(display("Could not read certificates from {}", path.display()))]
+    ReadCertificates {
+        path: PathBuf,
+        source: rustls::pki_types::pem::Error,
+    },
+
+    /// Could not read private key
+    #[snafu(display("Could not read private key from {}", path.display()))]
+    ReadPrivateKey {
+        path: PathBuf,
+        source: rustls::pki_types::pem::Error,
+    },
+
+    /// No certificates found
+    #[snafu(display("No certificates found in {}", path.display()))]
+    NoCertificates { path: PathBuf },
+
+    /// Missing certificate chain for this node
+    MissingCertificateChain,
+
+    /// Missing private key for this node
+    MissingPrivateKey,
+
+    /// Missing CA certificates for verifying the remote node
+    MissingCaCertificates,
+
+    /// Invalid CA certificate
+    AddCaCertificate { source: rustls::Error },
+
+    /// Could not set up the TLS protocol versions
+    ProtocolVersions { source: rustls::Error },
+
+    /// Could not set up the certificate of this node
+    Certificate { source: rustls::Error },
+
+    /// Could not set up client certificate verification
+    ClientVerifier {
+        source: rustls::server::VerifierBuilderError,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A policy on TLS protocol versions and cipher suites,
+/// after the secure transport connection profiles in PS3.15.
+///
+/// Only forward-secret AEAD cipher suites are ever enabled,
+/// in compliance with the BCP 195 recommendations.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TlsProfile {
+    /// BCP 195 TLS Secure Transport Connection Profile:
+    /// TLS 1.2 and TLS 1.3 with every cipher suite available.
+    #[default]
+    Bcp195,
+    /// Non-downgrading BCP 195 TLS Secure Transport Connection Profile:
+    /// TLS 1.2 restricted to ECDHE with AES-GCM, and TLS 1.3.
+    NonDowngradingBcp195,
+    /// Extended BCP 195 TLS Secure Transport Connection Profile:
+    /// TLS 1.2 restricted to ECDHE with AES-256-GCM or ChaCha20-Poly1305,
+    /// and TLS 1.3.
+    ExtendedBcp195,
+}
+
+impl TlsProfile {
+    /// Obtain the TLS protocol versions enabled by this profile.
+    ///
+    /// All profiles admit TLS 1.2 and TLS 1.3.
+    pub fn protocol_versions(self) -> &'static [&'static SupportedProtocolVersion] {
+        rustls::ALL_VERSIONS
+    }
+
+    /// Obtain the cipher suites enabled by this profile,
+    /// in order of preference.
+    pub fn cipher_suites(self) -> Vec<SupportedCipherSuite> {
+        use ring::cipher_suite::*;
+        match self {
+            TlsProfile::Bcp195 => ring::DEFAULT_CIPHER_SUITES.to_vec(),
+            TlsProfile::NonDowngradingBcp195 => vec![
+                TLS13_AES_256_GCM_SHA384,
+                TLS13_AES_128_GCM_SHA256,
+                TLS13_CHACHA20_POLY1305_SHA256,
+                TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+                TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+                TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+                TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            ],
+            TlsProfile::ExtendedBcp195 => vec![
+                TLS13_AES_256_GCM_SHA384,
+                TLS13_AES_128_GCM_SHA256,
+                TLS13_CHACHA20_POLY1305_SHA256,
+                TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+                TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+                TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+                TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+            ],
+        }
+    }
+
+    /// Build a cryptographic provider restricted to this profile's cipher suites.
+    fn crypto_provider(self) -> Arc<CryptoProvider> {
+        Arc::new(CryptoProvider {
+            cipher_suites: self.cipher_suites(),
+            ..ring::default_provider()
+        })
+    }
+}
+
+impl std::str::FromStr for TlsProfile {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bcp195" => Ok(TlsProfile::Bcp195),
+            "non-downgrading" => Ok(TlsProfile::NonDowngradingBcp195),
+            "extended" => Ok(TlsProfile::ExtendedBcp195),
+            _ => Err("unknown TLS profile (expected bcp195, non-downgrading, or extended)"),
+        }
+    }
+}
+
+/// Options for building TLS configurations
+/// from certificates and keys in PEM files.
+///
+/// The same options can describe either side of the connection:
+/// use [`client_config`](Self::client_config)
+/// for the association requester
+/// and [`server_config`](Self::server_config)
+/// for the association acceptor.
+#[derive(Debug, Default, Clone)]
+pub struct TlsOptions {
+    /// the PEM file with the certificate chain of this node
+    certificate_chain: Option<PathBuf>,
+    /// the PEM file with the private key of this node
+    private_key: Option<PathBuf>,
+    /// the PEM file with the CA certificates trusted to verify the remote node
+    ca_certificates: Option<PathBuf>,
+    /// whether the acceptor requires a certificate from the requester
+    require_client_auth: bool,
+    /// the protocol versions and cipher suites admitted
+    profile: TlsProfile,
+}
+
+impl TlsOptions {
+    /// Create a new set of TLS options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define the PEM file containing the certificate chain of this node,
+    /// starting with the end-entity certificate.
+    ///
+    /// This is required for the association acceptor,
+    /// and for the requester when client authentication is expected.
+    pub fn certificate_chain(mut self, path: impl Into<PathBuf>) -> Self {
+        self.certificate_chain = Some(path.into());
+        self
+    }
+
+    /// Define the PEM file containing the private key of this node.
+    pub fn private_key(mut self, path: impl Into<PathBuf>) -> Self {
+        self.private_key = Some(path.into());
+        self
+    }
+
+    /// Define the PEM file containing the CA certificates
+    /// trusted for verifying the certificate of the remote node.
+    ///
+    /// This is required for the association requester,
+    /// and for the acceptor when verifying client certificates.
+    pub fn ca_certificates(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_certificates = Some(path.into());
+        self
+    }
+
+    /// Define whether the association acceptor
+    /// requires association requesters to present a valid certificate.
+    ///
+    /// The default is `false`,
+    /// in which case a client certificate is still verified if presented,
+    /// as long as CA certificates were provided.
+    pub fn require_client_auth(mut self, require_client_auth: bool) -> Self {
+        self.require_client_auth = require_client_auth;
+        self
+    }
+
+    /// Define the secure transport connection profile,
+    /// which determines the protocol versions and cipher suites admitted.
+    ///
+    /// The default is [`TlsProfile::Bcp195`].
+    pub fn profile(mut self, profile: TlsProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Build a TLS configuration for association requesters.
+    pub fn client_config(&self) -> Result<Arc<ClientConfig>> {
+        let ca_certificates = self
+            .ca_certificates
+            .as_ref()
+            .ok_or(Error::MissingCaCertificates)?;
+        let roots = load_root_store(ca_certificates)?;
+
+        let builder = ClientConfig::builder_with_provider(self.profile.crypto_provider())
+            .with_protocol_versions(self.profile.protocol_versions())
+            .context(ProtocolVersionsSnafu)?
+            .with_root_certificates(roots);
+
+        let config = match (&self.certificate_chain, &self.private_key) {
+            (Some(certificate_chain), Some(private_key)) => builder
+                .with_client_auth_cert(
+                    load_certificates(certificate_chain)?,
+                    load_private_key(private_key)?,
+                )
+                .context(CertificateSnafu)?,
+            (Some(_), None) => return MissingPrivateKeySnafu.fail(),
+            (None, Some(_)) => return MissingCertificateChainSnafu.fail(),
+            (None, None) => builder.with_no_client_auth(),
+        };
+        Ok(Arc::new(config))
+    }
+
+    /// Build a TLS configuration for association acceptors.
+    pub fn server_config(&self) -> Result<Arc<ServerConfig>> {
+        let certificate_chain = self
+            .certificate_chain
+            .as_ref()
+            .ok_or(Error::MissingCertificateChain)?;
+        let private_key = self.private_key.as_ref().ok_or(Error::MissingPrivateKey)?;
+
+        let provider = self.profile.crypto_provider();
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(self.profile.protocol_versions())
+            .context(ProtocolVersionsSnafu)?;
+
+        let builder = match &self.ca_certificates {
+            Some(ca_certificates) => {
+                let roots = Arc::new(load_root_store(ca_certificates)?);
+                let verifier = WebPkiClientVerifier::builder_with_provider(roots, provider);
+                let verifier = if self.require_client_auth {
+                    verifier
+                } else {
+                    verifier.allow_unauthenticated()
+                };
+                builder.with_client_cert_verifier(verifier.build().context(ClientVerifierSnafu)?)
+            }
+            None if self.require_client_auth => return MissingCaCertificatesSnafu.fail(),
+            None => builder.with_no_client_auth(),
+        };
+
+        let config = builder
+            .with_single_cert(
+                load_certificates(certificate_chain)?,
+                load_private_key(private_key)?,
+            )
+            .context(CertificateSnafu)?;
+        Ok(Arc::new(config))
+    }
+}
+
+/// Read all certificates in a PEM file.
+fn load_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certificates = CertificateDer::pem_file_iter(path)
+        .and_then(|it| it.collect::<Result<Vec<_>, _>>())
+        .context(ReadCertificatesSnafu { path })?;
+    snafu::ensure!(!certificates.is_empty(), NoCertificatesSnafu { path });
+    Ok(certificates)
+}
+
+/// Read the first private key in a PEM file.
+fn load_private_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_file(path).context(ReadPrivateKeySnafu { path })
+}
+
+/// Read the trusted CA certificates in a PEM file.
+fn load_root_store(path: &Path) -> Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    for certificate in load_certificates(path)? {
+        roots.add(certificate).context(AddCaCertificateSnafu)?;
+    }
+    Ok(roots)
+}
+
+impl<T> CloseSocket for StreamOwned<ClientConnection, T>
+where
+    T: Transport,
+{
+    fn close(&mut self) -> std::io::Result<()> {
+        self.conn.send_close_notify();
+        let _ = self.conn.complete_io(&mut self.sock);
+        self.sock.close()
+    }
+}
+
+impl<T> Transport for StreamOwned<ClientConnection, T>
+where
+    T: Transport,
+{
+    fn has_pending_input(&mut self) -> std::io::Result<bool> {
+        has_pending_input(&mut self.conn, &mut self.sock)
+    }
+}
+
+impl<T> CloseSocket for StreamOwned<ServerConnection, T>
+where
+    T: Transport,
+{
+    fn close(&mut self) -> std::io::Result<()> {
+        self.conn.send_close_notify();
+        let _ = self.conn.complete_io(&mut self.sock);
+        self.sock.close()
+    }
+}
+
+impl<T> Transport for StreamOwned<ServerConnection, T>
+where
+    T: Transport,
+{
+    fn has_pending_input(&mut self) -> std::io::Result<bool> {
+        has_pending_input(&mut self.conn, &mut self.sock)
+    }
+}
+
+/// Check whether a TLS connection has input ready to be read,
+/// be it plaintext already decrypted by rustls
+/// or new records arriving through the underlying transport.
+///
+/// The transport is only looked at when rustls wants more records,
+/// since it may be holding everything there was to read already.
+fn has_pending_input<D, T>(conn: &mut ConnectionCommon<D>, sock: &mut T) -> std::io::Result<bool>
+where
+    T: Transport,
+{
+    let state = conn
+        .process_new_packets()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    if state.plaintext_bytes_to_read() > 0 || state.peer_has_closed() {
+        return Ok(true);
+    }
+    if !conn.wants_read() {
+        return Ok(false);
+    }
+    sock.has_pending_input()
+}
+
+#[cfg(feature = "async-tls")]
+impl CloseSocket for AsyncTlsClientStream {
+    fn close(&mut self) -> std::io::Result<()> {
+        use tokio::io::AsyncWriteExt;
+        tokio::task::block_in_place(move || {
+            tokio::runtime::Handle::current().block_on(async move { self.shutdown().await })
+        })
+    }
+}
+
+#[cfg(feature = "async-tls")]
+impl CloseSocket for AsyncTlsServerStream {
+    fn close(&mut self) -> std::io::Result<()> {
+        use tokio::io::AsyncWriteExt;
+        tokio::task::block_in_place(move || {
+            tokio::runtime::Handle::current().block_on(async move { self.shutdown().await })
+        })
+    }
+}
@@ -0,0 +1,156 @@
+use dicom_ul::{
+    association::{client::ClientAssociationOptions, duplex::duplex},
+    commitment::{
+        failure_reason, receive_result, request_commitment, CommitmentRequest, CommitmentResult,
+        FailedInstance, InstanceReference, ResultCollector, STORAGE_COMMITMENT_PUSH_MODEL,
+    },
+    scp::{CommitmentHandler, ServiceClassProvider, ServiceContext},
+};
+use std::net::{SocketAddr, TcpListener};
+
+static SCU_AE_TITLE: &str = "COMMIT-SCU";
+static SCP_AE_TITLE: &str = "COMMIT-SCP";
+
+static IMPLICIT_VR_LE: &str = "1.2.840.10008.1.2";
+static SECONDARY_CAPTURE: &str = "1.2.840.10008.5.1.4.1.1.7";
+static CT_IMAGE: &str = "1.2.840.10008.5.1.4.1.1.2";
+
+/// A provider which knows of a single secondary capture instance.
+struct Archive {
+    report_to: Option<SocketAddr>,
+}
+
+impl CommitmentHandler for Archive {
+    fn commit(&self, _ctx: &ServiceContext, request: &CommitmentRequest) -> CommitmentResult {
+        let mut result = CommitmentResult::new(request.transaction_uid.clone());
+        for instance in &request.instances {
+            match (
+                instance.sop_instance_uid.as_str(),
+                instance.sop_class_uid.as_str(),
+            ) {
+                ("2.25.1", class) if class == SECONDARY_CAPTURE => {
+                    result.committed.push(instance.clone())
+                }
+                ("2.25.1", _) => result.failed.push(FailedInstance::new(
+                    instance.clone(),
+                    failure_reason::CLASS_INSTANCE_CONFLICT,
+                )),
+                _ => result.failed.push(FailedInstance::new(
+                    instance.clone(),
+                    failure_reason::NO_SUCH_OBJECT_INSTANCE,
+                )),
+            }
+        }
+        result
+    }
+
+    fn report_destination(&self, ae_title: &str) -> Option<String> {
+        assert_eq!(ae_title, SCU_AE_TITLE);
+        self.report_to.map(|addr| addr.to_string())
+    }
+}
+
+fn request() -> CommitmentRequest {
+    CommitmentRequest::new(
+        "2.25.100",
+        vec![
+            InstanceReference::new(SECONDARY_CAPTURE, "2.25.1"),
+            InstanceReference::new(SECONDARY_CAPTURE, "2.25.2"),
+            InstanceReference::new(CT_IMAGE, "2.25.1"),
+        ],
+    )
+}
+
+fn check_result(result: &CommitmentResult) {
+    assert_eq!(result.transaction_uid, "2.25.100");
+    assert_eq!(
+        result.committed,
+        vec![InstanceReference::new(SECONDARY_CAPTURE, "2.25.1")]
+    );
+    assert_eq!(
+        result.failed,
+        vec![
+            FailedInstance::new(
+                InstanceReference::new(SECONDARY_CAPTURE, "2.25.2"),
+                failure_reason::NO_SUCH_OBJECT_INSTANCE,
+            ),
+            FailedInstance::new(
+                InstanceReference::new(CT_IMAGE, "2.25.1"),
+                failure_reason::CLASS_INSTANCE_CONFLICT,
+            ),
+        ]
+    );
+}
+
+fn scu_options() -> ClientAssociationOptions<'static> {
+    ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_presentation_context(STORAGE_COMMITMENT_PUSH_MODEL, vec![IMPLICIT_VR_LE])
+}
+
+/// The result is reported on the same association
+/// right after the N-ACTION response.
+#[test]
+fn commitment_reported_on_same_association() {
+    let scp = ServiceClassProvider::new()
+        .ae_title(SCP_AE_TITLE)
+        .with_commitment_handler(Archive { report_to: None });
+    let (scu_stream, scp_stream) = duplex(64 * 1024);
+    let scp_handle = std::thread::spawn(move || scp.handle_over(scp_stream));
+
+    let mut association = scu_options().establish_over(scu_stream).unwrap();
+    request_commitment(&mut association, 1, &request()).unwrap();
+    let result = receive_result(&mut association).unwrap();
+    check_result(&result);
+
+    association
+        .release()
+        .expect("did not have a peaceful release");
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+}
+
+/// The result is reported on a new association
+/// once the requesting association is released.
+#[test]
+fn commitment_reported_on_new_association() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let report_to = listener.local_addr().unwrap();
+    let collector = ResultCollector::new();
+    let receiver = ServiceClassProvider::new()
+        .ae_title(SCU_AE_TITLE)
+        .with_normalized_handler([STORAGE_COMMITMENT_PUSH_MODEL], collector.clone());
+    let receiver_handle = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        receiver.handle(stream)
+    });
+
+    let scp = ServiceClassProvider::new()
+        .ae_title(SCP_AE_TITLE)
+        .with_commitment_handler(Archive {
+            report_to: Some(report_to),
+        });
+    let (scu_stream, scp_stream) = duplex(64 * 1024);
+    let scp_handle = std::thread::spawn(move || scp.handle_over(scp_stream));
+
+    let mut association = scu_options().establish_over(scu_stream).unwrap();
+    request_commitment(&mut association, 1, &request()).unwrap();
+    association
+        .release()
+        .expect("did not have a peaceful release");
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+
+    receiver_handle
+        .join()
+        .expect("report receiver panicked")
+        .expect("Error at the report receiver");
+    let results = collector.take();
+    assert_eq!(results.len(), 1);
+    check_result(&results[0]);
+}
@@ -0,0 +1,232 @@
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN;
+use dicom_ul::{
+    association::{client::ClientAssociationOptions, server::ServerAssociationOptions},
+    dimse::{
+        composite::{CEchoRq, CEchoRsp, CStoreRq, CStoreRsp},
+        Command, DimseMessage, Status,
+    },
+    pdu::Pdu,
+};
+use std::convert::TryFrom;
+use std::io::Read;
+use std::net::SocketAddr;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+static SCU_AE_TITLE: &str = "DIMSE-SCU";
+static SCP_AE_TITLE: &str = "DIMSE-SCP";
+
+static IMPLICIT_VR_LE: &str = "1.2.840.10008.1.2";
+static VERIFICATION_SOP_CLASS: &str = "1.2.840.10008.1.1";
+static SECONDARY_CAPTURE_SOP_CLASS: &str = "1.2.840.10008.5.1.4.1.1.7";
+static SOP_INSTANCE_UID: &str = "2.25.123456789";
+
+/// A data set large enough to be split across several PDUs.
+fn large_dataset() -> InMemDicomObject {
+    InMemDicomObject::from_element_iter([
+        DataElement::new(
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            PrimitiveValue::from(SECONDARY_CAPTURE_SOP_CLASS),
+        ),
+        DataElement::new(
+            tags::SOP_INSTANCE_UID,
+            VR::UI,
+            PrimitiveValue::from(SOP_INSTANCE_UID),
+        ),
+        DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::from(vec![0x55_u8; 40_000]),
+        ),
+    ])
+}
+
+fn spawn_scp() -> Result<(std::thread::JoinHandle<Result<()>>, SocketAddr)> {
+    let listener = std::net::TcpListener::bind("localhost:0")?;
+    let addr = listener.local_addr()?;
+    let scp = ServerAssociationOptions::new()
+        .accept_called_ae_title()
+        .ae_title(SCP_AE_TITLE)
+        .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+        .with_abstract_syntax(SECONDARY_CAPTURE_SOP_CLASS);
+
+    let h = std::thread::spawn(move || -> Result<()> {
+        let (stream, _addr) = listener.accept()?;
+        let mut association = scp.establish(stream)?;
+
+        // C-ECHO
+        let msg = association.receive_dimse()?;
+        let rq = CEchoRq::try_from(msg.command)?;
+        assert_eq!(rq.message_id, 1);
+        association.send_dimse(&DimseMessage::new(
+            msg.presentation_context_id,
+            CEchoRsp::new(&rq, Status::SUCCESS),
+        ))?;
+
+        // C-STORE
+        let msg = association.receive_dimse()?;
+        let obj = msg
+            .dataset(&IMPLICIT_VR_LITTLE_ENDIAN.erased())?
+            .expect("C-STORE request should have a data set");
+        assert_eq!(
+            obj.get(tags::SOP_INSTANCE_UID).unwrap().to_str()?,
+            SOP_INSTANCE_UID
+        );
+        assert_eq!(obj.get(tags::PIXEL_DATA).unwrap().to_bytes()?.len(), 40_000);
+        let rq = CStoreRq::try_from(msg.command)?;
+        assert_eq!(rq.affected_sop_instance_uid, SOP_INSTANCE_UID);
+        association.send_dimse(&DimseMessage::new(
+            msg.presentation_context_id,
+            CStoreRsp::new(&rq, Status::SUCCESS),
+        ))?;
+
+        // release
+        match association.receive_dimse() {
+            Err(dicom_ul::association::server::Error::UnexpectedRequest { pdu, .. }) => {
+                assert_eq!(*pdu, Pdu::ReleaseRQ);
+            }
+            other => panic!("expected release request, got {:?}", other),
+        }
+        association.send(&Pdu::ReleaseRP)?;
+
+        Ok(())
+    });
+    Ok((h, addr))
+}
+
+/// Exchange C-ECHO and C-STORE messages through the DIMSE API.
+#[test]
+fn scu_scp_dimse_test() {
+    let (scp_handle, scp_addr) = spawn_scp().unwrap();
+
+    let mut association = ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_presentation_context(VERIFICATION_SOP_CLASS, vec![IMPLICIT_VR_LE])
+        .with_presentation_context(SECONDARY_CAPTURE_SOP_CLASS, vec![IMPLICIT_VR_LE])
+        .max_pdu_length(16_384)
+        .establish(scp_addr)
+        .unwrap();
+
+    let echo_pc = association.presentation_contexts()[0].id;
+    let store_pc = association.presentation_contexts()[1].id;
+
+    association
+        .send_dimse(&DimseMessage::new(echo_pc, CEchoRq::new(1)))
+        .unwrap();
+    let rsp = association.receive_dimse().unwrap();
+    assert_eq!(rsp.presentation_context_id, echo_pc);
+    match rsp.command {
+        Command::CEchoRsp(rsp) => {
+            assert_eq!(rsp.message_id_being_responded_to, 1);
+            assert_eq!(rsp.status, Status::SUCCESS);
+        }
+        other => panic!("unexpected response {:?}", other),
+    }
+
+    let msg = DimseMessage::new(
+        store_pc,
+        CStoreRq::new(2, SECONDARY_CAPTURE_SOP_CLASS, SOP_INSTANCE_UID),
+    )
+    .with_dataset(&large_dataset(), &IMPLICIT_VR_LITTLE_ENDIAN.erased())
+    .unwrap();
+    association.send_dimse(&msg).unwrap();
+    let rsp = association.receive_dimse().unwrap();
+    let rsp = CStoreRsp::try_from(rsp.command).unwrap();
+    assert_eq!(rsp.message_id_being_responded_to, 2);
+    assert!(rsp.status.is_success());
+
+    association
+        .release()
+        .expect("did not have a peaceful release");
+
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+}
+
+fn spawn_streaming_scp() -> Result<(std::thread::JoinHandle<Result<Vec<u8>>>, SocketAddr)> {
+    let listener = std::net::TcpListener::bind("localhost:0")?;
+    let addr = listener.local_addr()?;
+    let scp = ServerAssociationOptions::new()
+        .accept_called_ae_title()
+        .ae_title(SCP_AE_TITLE)
+        .with_abstract_syntax(SECONDARY_CAPTURE_SOP_CLASS)
+        .max_pdu_length(4_096);
+
+    let h = std::thread::spawn(move || -> Result<Vec<u8>> {
+        let (stream, _addr) = listener.accept()?;
+        let mut association = scp.establish(stream)?;
+
+        let (msg, data) = association.receive_dimse_streaming()?;
+        let mut data = data.expect("data set should still be arriving");
+        assert_eq!(data.presentation_context_id(), msg.presentation_context_id);
+        let mut bytes = Vec::new();
+        data.read_to_end(&mut bytes)?;
+        data.finish()?;
+
+        let rq = CStoreRq::try_from(msg.command)?;
+        association.send_dimse(&DimseMessage::new(
+            msg.presentation_context_id,
+            CStoreRsp::new(&rq, Status::SUCCESS),
+        ))?;
+
+        match association.receive_dimse() {
+            Err(dicom_ul::association::server::Error::UnexpectedRequest { pdu, .. }) => {
+                assert_eq!(*pdu, Pdu::ReleaseRQ);
+            }
+            other => panic!("expected release request, got {:?}", other),
+        }
+        association.send(&Pdu::ReleaseRP)?;
+
+        Ok(bytes)
+    });
+    Ok((h, addr))
+}
+
+/// Send a C-STORE data set from a reader
+/// and receive it as a stream on the other end.
+#[test]
+fn scu_scp_dimse_streaming_test() {
+    let (scp_handle, scp_addr) = spawn_streaming_scp().unwrap();
+
+    let mut association = ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_presentation_context(SECONDARY_CAPTURE_SOP_CLASS, vec![IMPLICIT_VR_LE])
+        .establish(scp_addr)
+        .unwrap();
+    let store_pc = association.presentation_contexts()[0].id;
+
+    let mut dataset = Vec::new();
+    large_dataset()
+        .write_dataset_with_ts(&mut dataset, &IMPLICIT_VR_LITTLE_ENDIAN.erased())
+        .unwrap();
+
+    let cmd = Command::from(CStoreRq::new(
+        1,
+        SECONDARY_CAPTURE_SOP_CLASS,
+        SOP_INSTANCE_UID,
+    ));
+    association
+        .send_dimse_from(store_pc, &cmd, dataset.as_slice())
+        .unwrap();
+    let rsp = association.receive_dimse().unwrap();
+    let rsp = CStoreRsp::try_from(rsp.command).unwrap();
+    assert!(rsp.status.is_success());
+
+    association
+        .release()
+        .expect("did not have a peaceful release");
+
+    let received = scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    assert_eq!(received, dataset);
+}
@@ -0,0 +1,212 @@
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN;
+use dicom_ul::{
+    association::{
+        client::ClientAssociationOptions,
+        duplex::duplex,
+        pipeline::{Error as PipelineError, Pipeline},
+    },
+    dimse::{
+        composite::{CStoreRq, CStoreRsp},
+        DimseMessage, Status,
+    },
+    scp::{AcceptEcho, ServiceClassProvider, ServiceContext},
+};
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+
+static SCU_AE_TITLE: &str = "PIPELINE-SCU";
+static SCP_AE_TITLE: &str = "PIPELINE-SCP";
+
+static IMPLICIT_VR_LE: &str = "1.2.840.10008.1.2";
+static VERIFICATION_SOP_CLASS: &str = "1.2.840.10008.1.1";
+static SECONDARY_CAPTURE_SOP_CLASS: &str = "1.2.840.10008.5.1.4.1.1.7";
+
+fn instance(sop_instance_uid: &str) -> InMemDicomObject {
+    InMemDicomObject::from_element_iter([
+        DataElement::new(
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            PrimitiveValue::from(SECONDARY_CAPTURE_SOP_CLASS),
+        ),
+        DataElement::new(
+            tags::SOP_INSTANCE_UID,
+            VR::UI,
+            PrimitiveValue::from(sop_instance_uid),
+        ),
+    ])
+}
+
+/// A service class provider which records the stored instances in order.
+fn service_class_provider(stored: Arc<Mutex<Vec<String>>>) -> ServiceClassProvider {
+    ServiceClassProvider::new()
+        .ae_title(SCP_AE_TITLE)
+        .with_echo_handler(AcceptEcho)
+        .with_store_handler(
+            [SECONDARY_CAPTURE_SOP_CLASS],
+            move |_ctx: &ServiceContext, rq: &CStoreRq, _obj: InMemDicomObject| {
+                stored
+                    .lock()
+                    .unwrap()
+                    .push(rq.affected_sop_instance_uid.clone());
+                Status::SUCCESS
+            },
+        )
+}
+
+fn scu_options() -> ClientAssociationOptions<'static> {
+    ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_presentation_context(SECONDARY_CAPTURE_SOP_CLASS, vec![IMPLICIT_VR_LE])
+}
+
+/// Send several C-STORE requests before receiving their responses,
+/// matching each response to its request.
+#[test]
+fn pipelined_stores_are_matched_to_responses() {
+    let (scu_stream, scp_stream) = duplex(64 * 1024);
+    let stored = Arc::new(Mutex::new(Vec::new()));
+    let scp = service_class_provider(Arc::clone(&stored)).async_operations_window(1, 4);
+    let scp_handle = std::thread::spawn(move || scp.handle_over(scp_stream));
+
+    let mut association = scu_options()
+        .async_operations_window(8, 1)
+        .establish_over(scu_stream)
+        .unwrap();
+    // the acceptor performs at most 4 operations at once
+    assert_eq!(association.max_operations_invoked(), 4);
+    let pc_id = association.presentation_contexts()[0].id;
+
+    let uids: Vec<String> = (1..=10).map(|i| format!("2.25.{}", i)).collect();
+    let mut pipeline = Pipeline::new(&mut association);
+    assert_eq!(pipeline.window(), 4);
+
+    let mut responses = Vec::new();
+    for (i, uid) in uids.iter().enumerate() {
+        let message_id = i as u16 + 1;
+        let msg = DimseMessage::new(
+            pc_id,
+            CStoreRq::new(message_id, SECONDARY_CAPTURE_SOP_CLASS, uid.as_str()),
+        )
+        .with_dataset(&instance(uid), &IMPLICIT_VR_LITTLE_ENDIAN.erased())
+        .unwrap();
+        responses.extend(pipeline.send(&msg, uid.clone()).unwrap());
+        assert!(pipeline.outstanding() <= 4);
+    }
+    assert_eq!(pipeline.outstanding(), 4);
+    responses.extend(pipeline.finish().unwrap());
+
+    assert_eq!(responses.len(), uids.len());
+    for (uid, rsp) in responses {
+        let rsp = CStoreRsp::try_from(rsp.command).unwrap();
+        assert_eq!(rsp.affected_sop_instance_uid, Some(uid));
+        assert!(rsp.status.is_success());
+    }
+
+    association
+        .release()
+        .expect("did not have a peaceful release");
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    assert_eq!(*stored.lock().unwrap(), uids);
+}
+
+/// Only one operation may be outstanding
+/// if the acceptor does not support an operations window.
+#[test]
+fn window_is_one_without_acceptor_support() {
+    let (scu_stream, scp_stream) = duplex(64 * 1024);
+    let scp = service_class_provider(Arc::default());
+    let scp_handle = std::thread::spawn(move || scp.handle_over(scp_stream));
+
+    let mut association = scu_options()
+        .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+        .async_operations_window(8, 1)
+        .establish_over(scu_stream)
+        .unwrap();
+    assert_eq!(association.max_operations_invoked(), 1);
+    assert_eq!(Pipeline::<_, ()>::new(&mut association).window(), 1);
+
+    association
+        .release()
+        .expect("did not have a peaceful release");
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+}
+
+/// Reusing the message ID of an outstanding request is refused
+/// without consuming the response to that request.
+#[test]
+fn duplicate_message_id_keeps_outstanding_response() {
+    let (scu_stream, scp_stream) = duplex(64 * 1024);
+    let stored = Arc::new(Mutex::new(Vec::new()));
+    let scp = service_class_provider(Arc::clone(&stored));
+    let scp_handle = std::thread::spawn(move || scp.handle_over(scp_stream));
+
+    let mut association = scu_options().establish_over(scu_stream).unwrap();
+    let pc_id = association.presentation_contexts()[0].id;
+    let mut pipeline = Pipeline::new(&mut association);
+    assert_eq!(pipeline.window(), 1);
+
+    let store = |uid: &str| {
+        DimseMessage::new(pc_id, CStoreRq::new(1, SECONDARY_CAPTURE_SOP_CLASS, uid))
+            .with_dataset(&instance(uid), &IMPLICIT_VR_LITTLE_ENDIAN.erased())
+            .unwrap()
+    };
+    assert!(pipeline.send(&store("2.25.1"), "2.25.1").unwrap().is_none());
+    let e = pipeline.send(&store("2.25.2"), "2.25.2").unwrap_err();
+    assert!(matches!(
+        e,
+        PipelineError::DuplicateMessageId { message_id: 1, .. }
+    ));
+    assert_eq!(pipeline.outstanding(), 1);
+
+    let responses = pipeline.finish().unwrap();
+    assert_eq!(responses.len(), 1);
+    let (uid, rsp) = &responses[0];
+    assert_eq!(*uid, "2.25.1");
+    let rsp = CStoreRsp::try_from(rsp.command.clone()).unwrap();
+    assert_eq!(rsp.affected_sop_instance_uid.as_deref(), Some("2.25.1"));
+
+    association
+        .release()
+        .expect("did not have a peaceful release");
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    assert_eq!(*stored.lock().unwrap(), vec!["2.25.1"]);
+}
+
+/// An unlimited window on both sides is accepted as such.
+#[test]
+fn unlimited_window() {
+    let (scu_stream, scp_stream) = duplex(64 * 1024);
+    let scp = service_class_provider(Arc::default()).async_operations_window(0, 0);
+    let scp_handle = std::thread::spawn(move || scp.handle_over(scp_stream));
+
+    let mut association = scu_options()
+        .async_operations_window(0, 0)
+        .establish_over(scu_stream)
+        .unwrap();
+    assert_eq!(association.max_operations_invoked(), 0);
+    assert_eq!(
+        Pipeline::<_, ()>::new(&mut association).window(),
+        usize::MAX
+    );
+
+    association
+        .release()
+        .expect("did not have a peaceful release");
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+}
@@ -0,0 +1,193 @@
+use dicom_ul::{
+    association::{
+        client::ClientAssociationOptions, pool::AssociationPool, server::ServerAssociationOptions,
+    },
+    dimse::{
+        composite::{CEchoRq, CEchoRsp},
+        Command, DimseMessage, Status,
+    },
+    pdu::Pdu,
+};
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+static SCU_AE_TITLE: &str = "POOL-SCU";
+static SCP_AE_TITLE: &str = "POOL-SCP";
+
+static IMPLICIT_VR_LE: &str = "1.2.840.10008.1.2";
+static VERIFICATION_SOP_CLASS: &str = "1.2.840.10008.1.1";
+static SECONDARY_CAPTURE_SOP_CLASS: &str = "1.2.840.10008.5.1.4.1.1.7";
+static CT_IMAGE_SOP_CLASS: &str = "1.2.840.10008.5.1.4.1.1.2";
+
+/// Spawn an SCP answering C-ECHO requests on every association,
+/// returning its address
+/// and the number of associations established with it so far.
+fn spawn_scp() -> (String, Arc<AtomicUsize>) {
+    let listener = std::net::TcpListener::bind("localhost:0").unwrap();
+    let addr = format!("{}@{}", SCP_AE_TITLE, listener.local_addr().unwrap());
+    let established = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&established);
+    let scp = ServerAssociationOptions::new()
+        .accept_called_ae_title()
+        .ae_title(SCP_AE_TITLE)
+        .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+        .with_abstract_syntax(SECONDARY_CAPTURE_SOP_CLASS);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            // counted before the requester learns of the association
+            counter.fetch_add(1, Ordering::SeqCst);
+            let mut association = match scp.establish(stream) {
+                Ok(association) => association,
+                Err(_) => continue,
+            };
+            std::thread::spawn(move || loop {
+                match association.receive_dimse() {
+                    Ok(msg) => {
+                        let rq = CEchoRq::try_from(msg.command).unwrap();
+                        association
+                            .send_dimse(&DimseMessage::new(
+                                msg.presentation_context_id,
+                                CEchoRsp::new(&rq, Status::SUCCESS),
+                            ))
+                            .unwrap();
+                    }
+                    Err(dicom_ul::association::server::Error::UnexpectedRequest {
+                        pdu, ..
+                    }) if *pdu == Pdu::ReleaseRQ => {
+                        let _ = association.send(&Pdu::ReleaseRP);
+                        break;
+                    }
+                    Err(_) => break,
+                }
+            });
+        }
+    });
+    (addr, established)
+}
+
+fn pool() -> AssociationPool {
+    AssociationPool::new(ClientAssociationOptions::new().calling_ae_title(SCU_AE_TITLE))
+}
+
+fn context(abstract_syntax: &'static str) -> Vec<(&'static str, Vec<&'static str>)> {
+    vec![(abstract_syntax, vec![IMPLICIT_VR_LE])]
+}
+
+/// Send a C-ECHO request through the verification presentation context.
+fn echo(association: &mut dicom_ul::ClientAssociation<std::net::TcpStream>, message_id: u16) {
+    let pc_id = association
+        .presentation_contexts()
+        .iter()
+        .find(|pc| association.abstract_syntax(pc.id) == Some(VERIFICATION_SOP_CLASS))
+        .expect("verification should be accepted")
+        .id;
+    association
+        .send_dimse(&DimseMessage::new(pc_id, CEchoRq::new(message_id)))
+        .unwrap();
+    match association.receive_dimse().unwrap().command {
+        Command::CEchoRsp(rsp) => assert_eq!(rsp.message_id_being_responded_to, message_id),
+        other => panic!("unexpected response {:?}", other),
+    }
+}
+
+#[test]
+fn reuse_idle_association() {
+    let (addr, established) = spawn_scp();
+    let pool = pool();
+
+    for message_id in 1..=3 {
+        let mut association = pool.get(&addr, context(VERIFICATION_SOP_CLASS)).unwrap();
+        echo(&mut association, message_id);
+    }
+    assert_eq!(established.load(Ordering::SeqCst), 1);
+    assert_eq!(pool.idle(), 1);
+
+    pool.clear();
+    assert_eq!(pool.idle(), 0);
+}
+
+#[test]
+fn renegotiate_for_missing_abstract_syntax() {
+    let (addr, established) = spawn_scp();
+    let pool = pool();
+
+    drop(pool.get(&addr, context(VERIFICATION_SOP_CLASS)).unwrap());
+    let association = pool
+        .get(&addr, context(SECONDARY_CAPTURE_SOP_CLASS))
+        .unwrap();
+    assert_eq!(established.load(Ordering::SeqCst), 2);
+    // the renegotiated association keeps the earlier presentation context
+    assert_eq!(association.presentation_contexts().len(), 2);
+    drop(association);
+    assert_eq!(pool.idle(), 1);
+
+    let mut association = pool.get(&addr, context(VERIFICATION_SOP_CLASS)).unwrap();
+    echo(&mut association, 1);
+    assert_eq!(established.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn renegotiate_for_rejected_presentation_context() {
+    let (addr, established) = spawn_scp();
+    let pool = pool();
+    // the SCP does not accept CT images
+    let mut needed = context(VERIFICATION_SOP_CLASS);
+    needed.extend(context(CT_IMAGE_SOP_CLASS));
+
+    let association = pool.get(&addr, needed.clone()).unwrap();
+    assert_eq!(association.presentation_contexts().len(), 1);
+    drop(association);
+    assert_eq!(established.load(Ordering::SeqCst), 1);
+
+    // proposed before, but never accepted
+    drop(pool.get(&addr, needed).unwrap());
+    assert_eq!(established.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn keep_at_most_max_idle_associations() {
+    let (addr, established) = spawn_scp();
+    let pool = pool().max_idle(1);
+
+    let mut first = pool.get(&addr, context(VERIFICATION_SOP_CLASS)).unwrap();
+    let mut second = pool.get(&addr, context(VERIFICATION_SOP_CLASS)).unwrap();
+    echo(&mut first, 1);
+    echo(&mut second, 2);
+    assert_eq!(established.load(Ordering::SeqCst), 2);
+    drop(first);
+    drop(second);
+    assert_eq!(pool.idle(), 1);
+}
+
+#[test]
+fn release_expired_associations() {
+    let (addr, _established) = spawn_scp();
+    let pool = pool().idle_timeout(Duration::from_millis(10));
+
+    drop(pool.get(&addr, context(VERIFICATION_SOP_CLASS)).unwrap());
+    assert_eq!(pool.idle(), 1);
+    std::thread::sleep(Duration::from_millis(20));
+    pool.release_idle();
+    assert_eq!(pool.idle(), 0);
+}
+
+#[test]
+fn discard_broken_association() {
+    let (addr, established) = spawn_scp();
+    let pool = pool();
+
+    pool.get(&addr, context(VERIFICATION_SOP_CLASS))
+        .unwrap()
+        .discard();
+    assert_eq!(pool.idle(), 0);
+    let mut association = pool.get(&addr, context(VERIFICATION_SOP_CLASS)).unwrap();
+    echo(&mut association, 1);
+    assert_eq!(established.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,133 @@
+use dicom_ul::{
+    association::{
+        client::ClientAssociationOptions,
+        duplex::duplex,
+        replay::{Error, Recorder, Recording, Replay, Side},
+        ClientAssociation, Transport,
+    },
+    dimse::{composite::CEchoRq, Command, DimseMessage, Status},
+    pdu::Pdu,
+    scp::{AcceptEcho, ServiceClassProvider},
+};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+static SCU_AE_TITLE: &str = "REPLAY-SCU";
+static SCP_AE_TITLE: &str = "REPLAY-SCP";
+
+static IMPLICIT_VR_LE: &str = "1.2.840.10008.1.2";
+static VERIFICATION_SOP_CLASS: &str = "1.2.840.10008.1.1";
+
+fn scu_options() -> ClientAssociationOptions<'static> {
+    ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_presentation_context(VERIFICATION_SOP_CLASS, vec![IMPLICIT_VR_LE])
+}
+
+/// Run a C-ECHO with the given message ID through the association,
+/// then release it.
+fn echo<S: Transport>(mut association: ClientAssociation<S>, message_id: u16) -> Result<()> {
+    let pc_id = association.presentation_contexts()[0].id;
+    association.send_dimse(&DimseMessage::new(pc_id, CEchoRq::new(message_id)))?;
+    match association.receive_dimse()?.command {
+        Command::CEchoRsp(rsp) => assert_eq!(rsp.status, Status::SUCCESS),
+        other => panic!("unexpected response {:?}", other),
+    }
+    association.release()?;
+    Ok(())
+}
+
+/// Record a C-ECHO between a real SCU and SCP,
+/// through a round trip to the file representation.
+fn record_echo() -> Recording {
+    let (scu_stream, scp_stream) = duplex(1024);
+    let scp = ServiceClassProvider::new()
+        .ae_title(SCP_AE_TITLE)
+        .with_echo_handler(AcceptEcho);
+    let scp_handle = std::thread::spawn(move || scp.handle_over(scp_stream));
+
+    let recorder = Recorder::new();
+    let association = scu_options()
+        .establish_over(recorder.wrap(scu_stream, Side::Requestor))
+        .unwrap();
+    echo(association, 1).unwrap();
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+
+    let mut data = Vec::new();
+    recorder.recording().write_to(&mut data).unwrap();
+    Recording::read_from(&data[..]).unwrap()
+}
+
+#[test]
+fn record_echo_association() {
+    let recording = record_echo();
+    let sent_by_scu: Vec<_> = recording.sent_by(Side::Requestor).collect();
+    let sent_by_scp: Vec<_> = recording.sent_by(Side::Acceptor).collect();
+    assert_eq!(sent_by_scu.len(), 3);
+    assert_eq!(sent_by_scp.len(), 3);
+    assert!(matches!(sent_by_scu[0], Pdu::AssociationRQ(_)));
+    assert!(matches!(sent_by_scp[0], Pdu::AssociationAC(_)));
+    assert_eq!(sent_by_scu[2], &Pdu::ReleaseRQ);
+    assert_eq!(sent_by_scp[2], &Pdu::ReleaseRP);
+}
+
+/// Test an SCU against a recorded SCP.
+#[test]
+fn replay_scp() {
+    let recording = record_echo();
+    let (scu_stream, scp_stream) = duplex(1024);
+    let scp = Replay::new(recording.clone(), Side::Acceptor).spawn(scp_stream);
+
+    let association = scu_options().establish_over(scu_stream).unwrap();
+    echo(association, 1).unwrap();
+
+    let exchanged = scp
+        .join()
+        .expect("replay panicked")
+        .expect("SCU did not behave as recorded");
+    assert_eq!(exchanged, recording);
+}
+
+/// Test an SCP against a recorded SCU.
+#[test]
+fn replay_scu() {
+    let recording = record_echo();
+    let (scu_stream, scp_stream) = duplex(1024);
+    let scu = Replay::new(recording, Side::Requestor).spawn(scu_stream);
+
+    ServiceClassProvider::new()
+        .ae_title(SCP_AE_TITLE)
+        .with_echo_handler(AcceptEcho)
+        .handle_over(scp_stream)
+        .unwrap();
+
+    scu.join()
+        .expect("replay panicked")
+        .expect("SCP did not behave as recorded");
+}
+
+/// An SCU departing from the recording is caught by the replay.
+#[test]
+fn replay_catches_regression() {
+    let recording = record_echo();
+    let (scu_stream, scp_stream) = duplex(1024);
+    let scp = Replay::new(recording, Side::Acceptor).spawn(scp_stream);
+
+    let association = scu_options().establish_over(scu_stream).unwrap();
+    // a different message ID than the one recorded
+    assert!(echo(association, 2).is_err());
+
+    match scp.join().expect("replay panicked") {
+        Err(Error::UnexpectedPdu {
+            index, expected, ..
+        }) => {
+            assert_eq!(index, 2);
+            assert!(matches!(*expected, Pdu::PData { .. }));
+        }
+        other => panic!("unexpected outcome {:?}", other),
+    }
+}
@@ -0,0 +1,176 @@
+#![cfg(feature = "sync-tls")]
+use dicom_ul::{
+    association::{client::ClientAssociationOptions, server::ServerAssociationOptions},
+    pdu::{Pdu, PresentationContextResult, PresentationContextResultReason},
+    tls::TlsOptions,
+};
+
+use std::net::SocketAddr;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+static SCU_AE_TITLE: &str = "ECHO-SCU";
+static SCP_AE_TITLE: &str = "ECHO-SCP";
+
+static IMPLICIT_VR_LE: &str = "1.2.840.10008.1.2";
+static EXPLICIT_VR_LE: &str = "1.2.840.10008.1.2.1";
+static VERIFICATION_SOP_CLASS: &str = "1.2.840.10008.1.1";
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn server_tls_options() -> TlsOptions {
+    TlsOptions::new()
+        .certificate_chain(fixture("server.pem"))
+        .private_key(fixture("server.key"))
+        .ca_certificates(fixture("ca.pem"))
+        .require_client_auth(true)
+}
+
+fn client_tls_options() -> TlsOptions {
+    TlsOptions::new()
+        .certificate_chain(fixture("client.pem"))
+        .private_key(fixture("client.key"))
+        .ca_certificates(fixture("ca.pem"))
+}
+
+fn spawn_scp() -> Result<(std::thread::JoinHandle<Result<()>>, SocketAddr)> {
+    let listener = std::net::TcpListener::bind("localhost:0")?;
+    let addr = listener.local_addr()?;
+    let scp = ServerAssociationOptions::new()
+        .accept_called_ae_title()
+        .ae_title(SCP_AE_TITLE)
+        .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+        .tls_config(server_tls_options().server_config()?);
+
+    let h = std::thread::spawn(move || -> Result<()> {
+        let (stream, _addr) = listener.accept()?;
+        let mut association = scp.establish_tls(stream)?;
+
+        assert_eq!(
+            association.presentation_contexts(),
+            &[PresentationContextResult {
+                id: 1,
+                reason: PresentationContextResultReason::Acceptance,
+                transfer_syntax: IMPLICIT_VR_LE.to_string(),
+            }],
+        );
+
+        // handle one release request
+        let pdu = association.receive()?;
+        assert_eq!(pdu, Pdu::ReleaseRQ);
+        association.send(&Pdu::ReleaseRP)?;
+
+        Ok(())
+    });
+    Ok((h, addr))
+}
+
+/// Negotiate and release an association over TLS with mutual authentication.
+#[test]
+fn scu_scp_association_over_tls() {
+    let (scp_handle, scp_addr) = spawn_scp().unwrap();
+
+    let association = ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .with_presentation_context(VERIFICATION_SOP_CLASS, vec![IMPLICIT_VR_LE, EXPLICIT_VR_LE])
+        .tls_config(client_tls_options().client_config().unwrap())
+        // server name is taken from the host in the address
+        .establish_with_tls(&format!("{}@localhost:{}", SCP_AE_TITLE, scp_addr.port()))
+        .unwrap();
+
+    association
+        .release()
+        .expect("did not have a peaceful release");
+
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+}
+
+/// An acceptor requiring client authentication
+/// refuses requesters without a certificate.
+#[test]
+fn scp_rejects_scu_without_certificate() {
+    let (scp_handle, scp_addr) = spawn_scp().unwrap();
+
+    let tls_config = TlsOptions::new()
+        .ca_certificates(fixture("ca.pem"))
+        .client_config()
+        .unwrap();
+    let result = ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_presentation_context(VERIFICATION_SOP_CLASS, vec![IMPLICIT_VR_LE])
+        .tls_config(tls_config)
+        .server_name("localhost")
+        .establish_tls(scp_addr);
+    assert!(result.is_err());
+
+    let scp_result = scp_handle.join().expect("SCP panicked");
+    assert!(scp_result.is_err());
+}
+
+/// A TLS configuration is required to establish secure associations.
+#[test]
+fn establish_tls_requires_config() {
+    let result = ClientAssociationOptions::new()
+        .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+        .establish_tls("localhost:104");
+    assert!(matches!(
+        result,
+        Err(dicom_ul::association::client::Error::MissingTlsConfig { .. })
+    ));
+}
+
+#[cfg(feature = "async-tls")]
+async fn spawn_scp_async() -> Result<(tokio::task::JoinHandle<Result<()>>, SocketAddr)> {
+    let listener = tokio::net::TcpListener::bind("localhost:0").await?;
+    let addr = listener.local_addr()?;
+    let scp = ServerAssociationOptions::new()
+        .accept_called_ae_title()
+        .ae_title(SCP_AE_TITLE)
+        .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+        .tls_config(server_tls_options().server_config()?);
+
+    let h = tokio::spawn(async move {
+        let (stream, _addr) = listener.accept().await?;
+        let mut association = scp.establish_tls_async(stream).await?;
+
+        // handle one release request
+        let pdu = association.receive().await?;
+        assert_eq!(pdu, Pdu::ReleaseRQ);
+        association.send(&Pdu::ReleaseRP).await?;
+
+        Ok(())
+    });
+    Ok((h, addr))
+}
+
+#[cfg(feature = "async-tls")]
+#[tokio::test(flavor = "multi_thread")]
+async fn scu_scp_association_over_tls_async() {
+    let (scp_handle, scp_addr) = spawn_scp_async().await.unwrap();
+
+    let association = ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_presentation_context(VERIFICATION_SOP_CLASS, vec![IMPLICIT_VR_LE, EXPLICIT_VR_LE])
+        .tls_config(client_tls_options().client_config().unwrap())
+        .server_name("localhost")
+        .establish_tls_async(scp_addr)
+        .await
+        .unwrap();
+
+    association
+        .release()
+        .await
+        .expect("did not have a peaceful release");
+
+    scp_handle
+        .await
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+}
@@ -0,0 +1,196 @@
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN;
+use dicom_ul::{
+    association::{
+        client::ClientAssociationOptions, duplex::duplex, server::ServerAssociationOptions,
+        ClientAssociation, Transport,
+    },
+    dimse::{
+        composite::{CEchoRq, CStoreRq, CStoreRsp},
+        Command, DimseMessage, Status,
+    },
+    scp::{AcceptEcho, ServiceClassProvider, ServiceContext},
+};
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+static SCU_AE_TITLE: &str = "TRANSPORT-SCU";
+static SCP_AE_TITLE: &str = "TRANSPORT-SCP";
+
+static IMPLICIT_VR_LE: &str = "1.2.840.10008.1.2";
+static VERIFICATION_SOP_CLASS: &str = "1.2.840.10008.1.1";
+static SECONDARY_CAPTURE_SOP_CLASS: &str = "1.2.840.10008.5.1.4.1.1.7";
+static SOP_INSTANCE_UID: &str = "2.25.987654321";
+
+/// A data set large enough to be split across several PDUs.
+fn large_dataset() -> InMemDicomObject {
+    InMemDicomObject::from_element_iter([
+        DataElement::new(
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            PrimitiveValue::from(SECONDARY_CAPTURE_SOP_CLASS),
+        ),
+        DataElement::new(
+            tags::SOP_INSTANCE_UID,
+            VR::UI,
+            PrimitiveValue::from(SOP_INSTANCE_UID),
+        ),
+        DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::from(vec![0x55_u8; 40_000]),
+        ),
+    ])
+}
+
+fn scu_options() -> ClientAssociationOptions<'static> {
+    ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_presentation_context(VERIFICATION_SOP_CLASS, vec![IMPLICIT_VR_LE])
+        .with_presentation_context(SECONDARY_CAPTURE_SOP_CLASS, vec![IMPLICIT_VR_LE])
+        .max_pdu_length(16_384)
+}
+
+/// Run a C-ECHO and a C-STORE through the association, then release it.
+fn echo_and_store<S: Transport>(mut association: ClientAssociation<S>) -> Result<()> {
+    let echo_pc = association.presentation_contexts()[0].id;
+    let store_pc = association.presentation_contexts()[1].id;
+
+    association.send_dimse(&DimseMessage::new(echo_pc, CEchoRq::new(1)))?;
+    match association.receive_dimse()?.command {
+        Command::CEchoRsp(rsp) => {
+            assert_eq!(rsp.message_id_being_responded_to, 1);
+            assert_eq!(rsp.status, Status::SUCCESS);
+        }
+        other => panic!("unexpected response {:?}", other),
+    }
+
+    let msg = DimseMessage::new(
+        store_pc,
+        CStoreRq::new(2, SECONDARY_CAPTURE_SOP_CLASS, SOP_INSTANCE_UID),
+    )
+    .with_dataset(&large_dataset(), &IMPLICIT_VR_LITTLE_ENDIAN.erased())?;
+    association.send_dimse(&msg)?;
+    let rsp = CStoreRsp::try_from(association.receive_dimse()?.command)?;
+    assert_eq!(rsp.message_id_being_responded_to, 2);
+    assert!(rsp.status.is_success());
+
+    association.release()?;
+    Ok(())
+}
+
+/// A service class provider which records the stored instances.
+fn service_class_provider(stored: Arc<Mutex<Vec<String>>>) -> ServiceClassProvider {
+    ServiceClassProvider::new()
+        .ae_title(SCP_AE_TITLE)
+        .with_echo_handler(AcceptEcho)
+        .with_store_handler(
+            [SECONDARY_CAPTURE_SOP_CLASS],
+            move |_ctx: &ServiceContext, rq: &CStoreRq, obj: InMemDicomObject| {
+                assert_eq!(
+                    obj.get(tags::PIXEL_DATA).unwrap().to_bytes().unwrap().len(),
+                    40_000
+                );
+                stored
+                    .lock()
+                    .unwrap()
+                    .push(rq.affected_sop_instance_uid.clone());
+                Status::SUCCESS
+            },
+        )
+}
+
+/// Serve a full SCU/SCP exchange over an in-memory pipe,
+/// without opening any ports.
+#[test]
+fn scu_scp_over_duplex() {
+    // a small buffer makes both sides wait on each other
+    let (scu_stream, scp_stream) = duplex(1024);
+    let stored = Arc::new(Mutex::new(Vec::new()));
+    let scp = service_class_provider(Arc::clone(&stored));
+    let scp_handle = std::thread::spawn(move || scp.handle_over(scp_stream));
+
+    let association = scu_options().establish_over(scu_stream).unwrap();
+    echo_and_store(association).unwrap();
+
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    assert_eq!(*stored.lock().unwrap(), vec![SOP_INSTANCE_UID.to_string()]);
+}
+
+/// Serve a full SCU/SCP exchange over a pair of Unix domain sockets.
+#[cfg(unix)]
+#[test]
+fn scu_scp_over_unix_socket() {
+    let (scu_stream, scp_stream) = std::os::unix::net::UnixStream::pair().unwrap();
+    let stored = Arc::new(Mutex::new(Vec::new()));
+    let scp = service_class_provider(Arc::clone(&stored));
+    let scp_handle = std::thread::spawn(move || scp.handle_over(scp_stream));
+
+    let association = scu_options().establish_over(scu_stream).unwrap();
+    echo_and_store(association).unwrap();
+
+    scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    assert_eq!(*stored.lock().unwrap(), vec![SOP_INSTANCE_UID.to_string()]);
+}
+
+/// The acceptor sees the requester going away
+/// when its end of the pipe is dropped.
+#[test]
+fn scp_sees_closed_duplex() {
+    let (scu_stream, scp_stream) = duplex(1024);
+    drop(scu_stream);
+    let result = ServerAssociationOptions::new()
+        .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+        .establish_over(scp_stream);
+    assert!(result.is_err());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "multi_thread")]
+async fn scu_scp_over_duplex_async() {
+    use dicom_ul::pdu::Pdu;
+
+    let (scu_stream, scp_stream) = tokio::io::duplex(1024);
+    let scp = ServerAssociationOptions::new()
+        .accept_called_ae_title()
+        .ae_title(SCP_AE_TITLE)
+        .with_abstract_syntax(VERIFICATION_SOP_CLASS);
+    let scp_handle = tokio::spawn(async move {
+        let mut association = scp.establish_over_async(scp_stream).await?;
+        assert_eq!(association.client_ae_title(), SCU_AE_TITLE);
+
+        // handle one release request
+        let pdu = association.receive().await?;
+        assert_eq!(pdu, Pdu::ReleaseRQ);
+        association.send(&Pdu::ReleaseRP).await?;
+        Result::Ok(())
+    });
+
+    let association = ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_presentation_context(VERIFICATION_SOP_CLASS, vec![IMPLICIT_VR_LE])
+        .establish_over_async(scu_stream)
+        .await
+        .unwrap();
+    association
+        .release()
+        .await
+        .expect("did not have a peaceful release");
+
+    scp_handle
+        .await
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+}
@@ -0,0 +1,86 @@
+use dicom_ul::{
+    association::{
+        client::{ClientAssociationOptions, Error},
+        duplex::duplex,
+        server::ServerAssociationOptions,
+    },
+    auth::{PasswordAuthenticator, RequireAuthentication},
+    pdu::{AssociationRJServiceUserReason, AssociationRJSource},
+};
+
+static SCU_AE_TITLE: &str = "IDENTITY-SCU";
+static SCP_AE_TITLE: &str = "IDENTITY-SCP";
+
+static IMPLICIT_VR_LE: &str = "1.2.840.10008.1.2";
+static VERIFICATION_SOP_CLASS: &str = "1.2.840.10008.1.1";
+
+fn scu_options() -> ClientAssociationOptions<'static> {
+    ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_presentation_context(VERIFICATION_SOP_CLASS, vec![IMPLICIT_VR_LE])
+}
+
+/// Request an association with an acceptor requiring a password,
+/// returning the user identity response obtained by the requester.
+fn negotiate(options: ClientAssociationOptions<'static>) -> Result<Option<Vec<u8>>, Error> {
+    let (scu_stream, scp_stream) = duplex(1024);
+    let scp = ServerAssociationOptions::new()
+        .ae_title(SCP_AE_TITLE)
+        .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+        .ae_access_control(RequireAuthentication::new(
+            PasswordAuthenticator::new().with_user("modality", "s3cr3t"),
+        ));
+    let scp_handle = std::thread::spawn(move || {
+        let association = scp.establish_over(scp_stream)?;
+        association.abort()
+    });
+
+    let outcome = options
+        .establish_over(scu_stream)
+        .map(|association| association.user_identity_response().map(|r| r.to_vec()));
+    let _ = scp_handle.join().expect("SCP panicked");
+    outcome
+}
+
+#[test]
+fn positive_response_to_user_identity() {
+    let response = negotiate(
+        scu_options()
+            .username_password("modality", "s3cr3t")
+            .request_user_identity_response(true),
+    )
+    .unwrap();
+    // the server response is empty for passwords
+    assert_eq!(response, Some(Vec::new()));
+}
+
+#[test]
+fn no_response_unless_requested() {
+    let response = negotiate(scu_options().username_password("modality", "s3cr3t")).unwrap();
+    assert_eq!(response, None);
+}
+
+#[test]
+fn reject_wrong_password() {
+    let outcome = negotiate(
+        scu_options()
+            .username_password("modality", "guess")
+            .request_user_identity_response(true),
+    );
+    match outcome {
+        Err(Error::Rejected { association_rj, .. }) => assert_eq!(
+            association_rj.source,
+            AssociationRJSource::ServiceUser(AssociationRJServiceUserReason::NoReasonGiven)
+        ),
+        other => panic!("unexpected outcome {:?}", other),
+    }
+}
+
+#[test]
+fn reject_missing_user_identity() {
+    assert!(matches!(
+        negotiate(scu_options()),
+        Err(Error::Rejected { .. })
+    ));
+}
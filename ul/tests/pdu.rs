@@ -1,8 +1,9 @@
 use dicom_ul::pdu::reader::read_pdu;
 use dicom_ul::pdu::writer::write_pdu;
 use dicom_ul::pdu::{
-    AssociationRQ, PDataValue, PDataValueType, Pdu, PresentationContextProposed, UserIdentity,
-    UserIdentityType, UserVariableItem, DEFAULT_MAX_PDU,
+    AssociationAC, AssociationRQ, PDataValue, PDataValueType, Pdu, PresentationContextProposed,
+    PresentationContextResult, PresentationContextResultReason, UserIdentity, UserIdentityType,
+    UserVariableItem, DEFAULT_MAX_PDU,
 };
 use matches::matches;
 use std::io::Cursor;
@@ -176,7 +177,7 @@ fn can_read_write_primary_field_only_user_identity() -> Result<(), Box<dyn std::
             if !user_identity.positive_response_requested() &&
             user_identity.identity_type() == UserIdentityType::Username &&
             user_identity.primary_field() == [77,121,85,115,101,114,110,97,109,101] &&
-            user_identity.secondary_field() == []
+            user_identity.secondary_field().is_empty()
         ));
     } else {
         panic!("invalid pdu type");
@@ -213,3 +214,121 @@ fn can_read_write_pdata() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn can_read_write_extended_negotiation_items() -> Result<(), Box<dyn std::error::Error>> {
+    let user_variables = vec![
+        UserVariableItem::MaxLength(16384),
+        UserVariableItem::AsyncOperationsWindow(8, 0),
+        UserVariableItem::RoleSelectionSubItem(
+            "1.2.840.10008.5.1.4.1.1.7".to_string(),
+            false,
+            true,
+        ),
+        UserVariableItem::SopClassExtendedNegotiationSubItem(
+            "1.2.840.10008.5.1.4.1.2.2.3".to_string(),
+            vec![1, 0, 1],
+        ),
+        UserVariableItem::SopClassCommonExtendedNegotiationSubItem(
+            "1.2.840.10008.5.1.4.1.1.88.22".to_string(),
+            "1.2.840.10008.4.2".to_string(),
+            vec![
+                "1.2.840.10008.5.1.4.1.1.88.11".to_string(),
+                "1.2.840.10008.5.1.4.1.1.88.33".to_string(),
+            ],
+        ),
+        UserVariableItem::SopClassCommonExtendedNegotiationSubItem(
+            "1.2.840.10008.5.1.4.1.1.2".to_string(),
+            "1.2.840.10008.4.2".to_string(),
+            vec![],
+        ),
+    ];
+    let association_ac = AssociationAC {
+        protocol_version: 1,
+        calling_ae_title: "calling ae".to_string(),
+        called_ae_title: "called ae".to_string(),
+        application_context_name: "1.2.840.10008.3.1.1.1".to_string(),
+        presentation_contexts: vec![PresentationContextResult {
+            id: 1,
+            reason: PresentationContextResultReason::Acceptance,
+            transfer_syntax: "1.2.840.10008.1.2".to_string(),
+        }],
+        user_variables: user_variables.clone(),
+    };
+
+    let mut bytes = Vec::new();
+    write_pdu(&mut bytes, &association_ac.into())?;
+
+    // Asynchronous Operations Window Sub-Item: invoked, performed
+    let window = [0x53, 0x00, 0x00, 4, 0x00, 8, 0x00, 0x00];
+    assert!(bytes.windows(window.len()).any(|w| w == window));
+
+    // SCP/SCU Role Selection Sub-Item: UID length, UID, SCU role, SCP role
+    let uid = b"1.2.840.10008.5.1.4.1.1.7";
+    let mut role_selection = vec![0x54, 0x00, 0x00, 29, 0x00, 25];
+    role_selection.extend_from_slice(uid);
+    role_selection.extend_from_slice(&[0, 1]);
+    assert!(bytes
+        .windows(role_selection.len())
+        .any(|w| w == role_selection.as_slice()));
+
+    // SOP Class Extended Negotiation Sub-Item:
+    // the application information takes the rest of the item
+    let uid = b"1.2.840.10008.5.1.4.1.2.2.3";
+    let mut extended = vec![0x56, 0x00, 0x00, 32, 0x00, 27];
+    extended.extend_from_slice(uid);
+    extended.extend_from_slice(&[1, 0, 1]);
+    assert!(bytes
+        .windows(extended.len())
+        .any(|w| w == extended.as_slice()));
+
+    let result = read_pdu(&mut Cursor::new(&bytes), DEFAULT_MAX_PDU, true)?.unwrap();
+
+    if let Pdu::AssociationAC(association_ac) = result {
+        assert_eq!(association_ac.user_variables, user_variables);
+    } else {
+        panic!("invalid pdu type");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn can_read_write_user_identity_response() -> Result<(), Box<dyn std::error::Error>> {
+    let user_variables = vec![
+        UserVariableItem::MaxLength(16384),
+        UserVariableItem::UserIdentityResponse(b"server-token".to_vec()),
+    ];
+    let association_ac = AssociationAC {
+        protocol_version: 1,
+        calling_ae_title: "calling ae".to_string(),
+        called_ae_title: "called ae".to_string(),
+        application_context_name: "1.2.840.10008.3.1.1.1".to_string(),
+        presentation_contexts: vec![PresentationContextResult {
+            id: 1,
+            reason: PresentationContextResultReason::Acceptance,
+            transfer_syntax: "1.2.840.10008.1.2".to_string(),
+        }],
+        user_variables: user_variables.clone(),
+    };
+
+    let mut bytes = Vec::new();
+    write_pdu(&mut bytes, &association_ac.into())?;
+
+    // User Identity Sub-Item: server response length, server response
+    let mut response = vec![0x59, 0x00, 0x00, 14, 0x00, 12];
+    response.extend_from_slice(b"server-token");
+    assert!(bytes
+        .windows(response.len())
+        .any(|w| w == response.as_slice()));
+
+    let result = read_pdu(&mut Cursor::new(&bytes), DEFAULT_MAX_PDU, true)?.unwrap();
+
+    if let Pdu::AssociationAC(association_ac) = result {
+        assert_eq!(association_ac.user_variables, user_variables);
+    } else {
+        panic!("invalid pdu type");
+    }
+
+    Ok(())
+}
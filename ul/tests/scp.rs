@@ -0,0 +1,515 @@
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
+use dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN;
+use dicom_ul::{
+    association::client::{ClientAssociation, ClientAssociationOptions},
+    dimse::{
+        composite::{
+            CCancelRq, CEchoRq, CEchoRsp, CFindRq, CFindRsp, CGetRq, CMoveRq, CMoveRsp, CStoreRq,
+            CStoreRsp,
+        },
+        Command, DimseMessage, Status,
+    },
+    pdu::{Pdu, UserVariableItem},
+    scp::{
+        AcceptEcho, FindHandler, FindResult, GetHandler, MoveHandler, RetrieveItem, RetrieveResult,
+        ServiceClassProvider, ServiceContext,
+    },
+};
+use std::convert::TryFrom;
+use std::net::SocketAddr;
+use std::sync::{Arc, Barrier, Mutex};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+type ScpHandle = std::thread::JoinHandle<Result<()>>;
+type Received = Arc<Mutex<Vec<String>>>;
+
+static SCU_AE_TITLE: &str = "SCP-TEST-SCU";
+static SCP_AE_TITLE: &str = "SCP-TEST-SCP";
+static DESTINATION_AE_TITLE: &str = "SCP-TEST-DEST";
+
+static IMPLICIT_VR_LE: &str = "1.2.840.10008.1.2";
+static VERIFICATION: &str = "1.2.840.10008.1.1";
+static SECONDARY_CAPTURE: &str = "1.2.840.10008.5.1.4.1.1.7";
+static PATIENT_ROOT_FIND: &str = "1.2.840.10008.5.1.4.1.2.1.1";
+static PATIENT_ROOT_MOVE: &str = "1.2.840.10008.5.1.4.1.2.1.2";
+static PATIENT_ROOT_GET: &str = "1.2.840.10008.5.1.4.1.2.1.3";
+static STUDY_ROOT_FIND: &str = "1.2.840.10008.5.1.4.1.2.2.1";
+
+fn instance(sop_instance_uid: &str) -> InMemDicomObject {
+    InMemDicomObject::from_element_iter([
+        DataElement::new(
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            PrimitiveValue::from(SECONDARY_CAPTURE),
+        ),
+        DataElement::new(
+            tags::SOP_INSTANCE_UID,
+            VR::UI,
+            PrimitiveValue::from(sop_instance_uid),
+        ),
+        DataElement::new(tags::PATIENT_ID, VR::LO, PrimitiveValue::from("P1")),
+    ])
+}
+
+fn patient(id: &str) -> InMemDicomObject {
+    InMemDicomObject::from_element_iter([
+        DataElement::new(tags::QUERY_RETRIEVE_LEVEL, VR::CS, "PATIENT"),
+        DataElement::new(tags::PATIENT_ID, VR::LO, PrimitiveValue::from(id)),
+    ])
+}
+
+struct Patients;
+
+impl FindHandler for Patients {
+    fn find(
+        &self,
+        _ctx: &ServiceContext,
+        _rq: &CFindRq,
+        _identifier: &InMemDicomObject,
+    ) -> FindResult<'_> {
+        Ok(Box::new(vec![patient("P1"), patient("P2")].into_iter()))
+    }
+}
+
+struct Retriever {
+    destination: SocketAddr,
+}
+
+impl Retriever {
+    fn items() -> RetrieveResult {
+        ["2.25.1", "2.25.2"]
+            .iter()
+            .map(|uid| {
+                let obj = instance(uid)
+                    .with_meta(
+                        FileMetaTableBuilder::new()
+                            .transfer_syntax(IMPLICIT_VR_LE)
+                            .media_storage_sop_class_uid(SECONDARY_CAPTURE)
+                            .media_storage_sop_instance_uid(*uid),
+                    )
+                    .map_err(|_| Status::UNABLE_TO_PROCESS)?;
+                Ok(RetrieveItem::from_object(obj))
+            })
+            .collect()
+    }
+}
+
+impl MoveHandler for Retriever {
+    fn retrieve(
+        &self,
+        _ctx: &ServiceContext,
+        _rq: &CMoveRq,
+        _identifier: &InMemDicomObject,
+    ) -> RetrieveResult {
+        Self::items()
+    }
+
+    fn destination(&self, ae_title: &str) -> Option<String> {
+        if ae_title == DESTINATION_AE_TITLE {
+            Some(self.destination.to_string())
+        } else {
+            None
+        }
+    }
+}
+
+impl GetHandler for Retriever {
+    fn retrieve(
+        &self,
+        _ctx: &ServiceContext,
+        _rq: &CGetRq,
+        _identifier: &InMemDicomObject,
+    ) -> RetrieveResult {
+        Self::items()
+    }
+}
+
+/// Spawn a storage SCP which records the received SOP instance UIDs
+/// in a single association,
+/// responding to each of them with the given status.
+///
+/// If a gate is given,
+/// the response to the first instance waits for it.
+fn spawn_destination(
+    status: Status,
+    gate: Option<Arc<Barrier>>,
+) -> Result<(ScpHandle, SocketAddr, Received)> {
+    let listener = std::net::TcpListener::bind("localhost:0")?;
+    let addr = listener.local_addr()?;
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let scp = ServiceClassProvider::new()
+        .ae_title(DESTINATION_AE_TITLE)
+        .with_store_handler([SECONDARY_CAPTURE], {
+            let received = Arc::clone(&received);
+            move |_ctx: &ServiceContext, rq: &CStoreRq, _obj: InMemDicomObject| {
+                let mut received = received.lock().unwrap();
+                if let (Some(gate), true) = (&gate, received.is_empty()) {
+                    gate.wait();
+                }
+                received.push(rq.affected_sop_instance_uid.clone());
+                status
+            }
+        });
+
+    let h = std::thread::spawn(move || -> Result<()> {
+        let (stream, _addr) = listener.accept()?;
+        scp.handle(stream)?;
+        Ok(())
+    });
+    Ok((h, addr, received))
+}
+
+fn spawn_scp(destination: SocketAddr) -> Result<(ScpHandle, SocketAddr)> {
+    let listener = std::net::TcpListener::bind("localhost:0")?;
+    let addr = listener.local_addr()?;
+    let scp = ServiceClassProvider::new()
+        .ae_title(SCP_AE_TITLE)
+        .with_echo_handler(AcceptEcho)
+        .with_store_handler(
+            [SECONDARY_CAPTURE],
+            |_ctx: &ServiceContext, _rq: &CStoreRq, obj: InMemDicomObject| {
+                if obj.get(tags::PATIENT_ID).is_some() {
+                    Status::SUCCESS
+                } else {
+                    Status::MISSING_ATTRIBUTE
+                }
+            },
+        )
+        .with_find_handler([PATIENT_ROOT_FIND], Patients)
+        .with_move_handler([PATIENT_ROOT_MOVE], Retriever { destination })
+        .with_get_handler([PATIENT_ROOT_GET], Retriever { destination })
+        // accepted, but without a handler
+        .with_abstract_syntax(STUDY_ROOT_FIND);
+
+    let h = std::thread::spawn(move || -> Result<()> {
+        let (stream, _addr) = listener.accept()?;
+        scp.handle(stream)?;
+        Ok(())
+    });
+    Ok((h, addr))
+}
+
+/// Exercise C-ECHO, C-STORE and C-FIND against the SCP framework.
+#[test]
+fn scp_echo_store_find() {
+    // no retrieval is made, so the move destination is never contacted
+    let (scp_handle, scp_addr) = spawn_scp(([127, 0, 0, 1], 1).into()).unwrap();
+
+    let mut association = ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_presentation_context(VERIFICATION, vec![IMPLICIT_VR_LE])
+        .with_presentation_context(SECONDARY_CAPTURE, vec![IMPLICIT_VR_LE])
+        .with_presentation_context(PATIENT_ROOT_FIND, vec![IMPLICIT_VR_LE])
+        .with_presentation_context(STUDY_ROOT_FIND, vec![IMPLICIT_VR_LE])
+        .establish(scp_addr)
+        .unwrap();
+    let ts = IMPLICIT_VR_LITTLE_ENDIAN.erased();
+
+    // C-ECHO
+    association
+        .send_dimse(&DimseMessage::new(1, CEchoRq::new(1)))
+        .unwrap();
+    let rsp = CEchoRsp::try_from(association.receive_dimse().unwrap().command).unwrap();
+    assert_eq!(rsp.status, Status::SUCCESS);
+
+    // C-STORE
+    let msg = DimseMessage::new(3, CStoreRq::new(2, SECONDARY_CAPTURE, "2.25.9"))
+        .with_dataset(&instance("2.25.9"), &ts)
+        .unwrap();
+    association.send_dimse(&msg).unwrap();
+    let rsp = CStoreRsp::try_from(association.receive_dimse().unwrap().command).unwrap();
+    assert_eq!(rsp.message_id_being_responded_to, 2);
+    assert_eq!(rsp.status, Status::SUCCESS);
+
+    // C-FIND
+    let msg = DimseMessage::new(5, CFindRq::new(3, PATIENT_ROOT_FIND))
+        .with_dataset(&patient(""), &ts)
+        .unwrap();
+    association.send_dimse(&msg).unwrap();
+    let mut patient_ids = Vec::new();
+    loop {
+        let msg = association.receive_dimse().unwrap();
+        let rsp = CFindRsp::try_from(msg.command.clone()).unwrap();
+        if !rsp.status.is_pending() {
+            assert_eq!(rsp.status, Status::SUCCESS);
+            break;
+        }
+        let obj = msg.dataset(&ts).unwrap().unwrap();
+        patient_ids.push(
+            obj.get(tags::PATIENT_ID)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string(),
+        );
+    }
+    assert_eq!(patient_ids, vec!["P1", "P2"]);
+
+    // C-FIND on a SOP class without a handler
+    let msg = DimseMessage::new(7, CFindRq::new(4, STUDY_ROOT_FIND))
+        .with_dataset(&patient(""), &ts)
+        .unwrap();
+    association.send_dimse(&msg).unwrap();
+    let rsp = CFindRsp::try_from(association.receive_dimse().unwrap().command).unwrap();
+    assert_eq!(rsp.status, Status::SOP_CLASS_NOT_SUPPORTED);
+
+    association.release().unwrap();
+    scp_handle.join().unwrap().unwrap();
+}
+
+/// Retrieve instances with C-MOVE and C-GET through the SCP framework.
+#[test]
+fn scp_move_and_get() {
+    let (destination_handle, destination_addr, received) =
+        spawn_destination(Status::SUCCESS, None).unwrap();
+    let (scp_handle, scp_addr) = spawn_scp(destination_addr).unwrap();
+
+    let mut association = ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_presentation_context(PATIENT_ROOT_MOVE, vec![IMPLICIT_VR_LE])
+        .with_presentation_context(PATIENT_ROOT_GET, vec![IMPLICIT_VR_LE])
+        .with_presentation_context(SECONDARY_CAPTURE, vec![IMPLICIT_VR_LE])
+        .with_role_selection(SECONDARY_CAPTURE, false, true)
+        // not proposed in any presentation context, so it is left out
+        .with_role_selection(VERIFICATION, false, true)
+        .establish(scp_addr)
+        .unwrap();
+    let ts = IMPLICIT_VR_LITTLE_ENDIAN.erased();
+
+    let role_selections: Vec<_> = association
+        .user_variables()
+        .iter()
+        .filter(|item| matches!(item, UserVariableItem::RoleSelectionSubItem(..)))
+        .collect();
+    assert_eq!(
+        role_selections,
+        vec![&UserVariableItem::RoleSelectionSubItem(
+            SECONDARY_CAPTURE.to_string(),
+            false,
+            true
+        )]
+    );
+
+    // C-MOVE to an unknown destination
+    let msg = DimseMessage::new(1, CMoveRq::new(1, PATIENT_ROOT_MOVE, "NOWHERE"))
+        .with_dataset(&patient("P1"), &ts)
+        .unwrap();
+    association.send_dimse(&msg).unwrap();
+    let rsp = CMoveRsp::try_from(association.receive_dimse().unwrap().command).unwrap();
+    assert_eq!(rsp.status, Status::MOVE_DESTINATION_UNKNOWN);
+
+    // C-MOVE to the destination SCP
+    let msg = DimseMessage::new(1, CMoveRq::new(2, PATIENT_ROOT_MOVE, DESTINATION_AE_TITLE))
+        .with_dataset(&patient("P1"), &ts)
+        .unwrap();
+    association.send_dimse(&msg).unwrap();
+    let mut pending = 0;
+    let rsp = loop {
+        let rsp = CMoveRsp::try_from(association.receive_dimse().unwrap().command).unwrap();
+        if !rsp.status.is_pending() {
+            break rsp;
+        }
+        pending += 1;
+        assert_eq!(rsp.sub_operations.remaining, Some(1));
+    };
+    assert_eq!(pending, 1);
+    assert_eq!(rsp.status, Status::SUCCESS);
+    assert_eq!(rsp.sub_operations.completed, Some(2));
+    assert_eq!(rsp.sub_operations.failed, Some(0));
+    destination_handle.join().unwrap().unwrap();
+    assert_eq!(*received.lock().unwrap(), vec!["2.25.1", "2.25.2"]);
+
+    // C-GET, with the instances sent back over the same association
+    let msg = DimseMessage::new(3, CGetRq::new(3, PATIENT_ROOT_GET))
+        .with_dataset(&patient("P1"), &ts)
+        .unwrap();
+    association.send_dimse(&msg).unwrap();
+    let mut stored = Vec::new();
+    let rsp = loop {
+        let msg = association.receive_dimse().unwrap();
+        match msg.command {
+            Command::CStoreRq(ref rq) => {
+                assert_eq!(msg.presentation_context_id, 5);
+                let obj = msg.dataset(&ts).unwrap().unwrap();
+                assert_eq!(obj.get(tags::PATIENT_ID).unwrap().to_str().unwrap(), "P1");
+                stored.push(rq.affected_sop_instance_uid.clone());
+                association
+                    .send_dimse(&DimseMessage::new(5, CStoreRsp::new(rq, Status::SUCCESS)))
+                    .unwrap();
+            }
+            Command::CGetRsp(rsp) if rsp.status.is_pending() => {}
+            Command::CGetRsp(rsp) => break rsp,
+            command => panic!("unexpected command {:?}", command),
+        }
+    };
+    assert_eq!(rsp.status, Status::SUCCESS);
+    assert_eq!(rsp.sub_operations.completed, Some(2));
+    assert_eq!(stored, vec!["2.25.1", "2.25.2"]);
+
+    association.release().unwrap();
+    scp_handle.join().unwrap().unwrap();
+}
+
+/// Establish an association for C-MOVE with the SCP under test.
+fn establish_move(scp_addr: SocketAddr) -> ClientAssociation<std::net::TcpStream> {
+    let mut association = ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_presentation_context(PATIENT_ROOT_MOVE, vec![IMPLICIT_VR_LE])
+        .with_presentation_context(VERIFICATION, vec![IMPLICIT_VR_LE])
+        .establish(scp_addr)
+        .unwrap();
+    // do not hold back short requests such as C-CANCEL
+    association.inner_stream().set_nodelay(true).unwrap();
+    association
+}
+
+/// A C-MOVE in which every sub-operation fails
+/// is refused as unable to perform sub-operations.
+#[test]
+fn scp_move_all_sub_operations_failed() {
+    let (destination_handle, destination_addr, received) =
+        spawn_destination(Status::OUT_OF_RESOURCES, None).unwrap();
+    let (scp_handle, scp_addr) = spawn_scp(destination_addr).unwrap();
+    let mut association = establish_move(scp_addr);
+    let ts = IMPLICIT_VR_LITTLE_ENDIAN.erased();
+
+    let msg = DimseMessage::new(1, CMoveRq::new(1, PATIENT_ROOT_MOVE, DESTINATION_AE_TITLE))
+        .with_dataset(&patient("P1"), &ts)
+        .unwrap();
+    association.send_dimse(&msg).unwrap();
+    let msg = loop {
+        let msg = association.receive_dimse().unwrap();
+        if !CMoveRsp::try_from(msg.command.clone())
+            .unwrap()
+            .status
+            .is_pending()
+        {
+            break msg;
+        }
+    };
+    let rsp = CMoveRsp::try_from(msg.command.clone()).unwrap();
+    assert_eq!(rsp.status, Status::OUT_OF_RESOURCES_SUB_OPERATIONS);
+    assert_eq!(rsp.sub_operations.completed, Some(0));
+    assert_eq!(rsp.sub_operations.failed, Some(2));
+    let failed = msg.dataset(&ts).unwrap().unwrap();
+    assert_eq!(
+        failed
+            .get(tags::FAILED_SOP_INSTANCE_UID_LIST)
+            .unwrap()
+            .to_multi_str()
+            .unwrap()
+            .as_ref(),
+        ["2.25.1", "2.25.2"]
+    );
+    destination_handle.join().unwrap().unwrap();
+    assert_eq!(*received.lock().unwrap(), vec!["2.25.1", "2.25.2"]);
+
+    association.release().unwrap();
+    scp_handle.join().unwrap().unwrap();
+}
+
+/// A C-MOVE is interrupted by a C-CANCEL between sub-operations.
+#[test]
+fn scp_move_cancel() {
+    let gate = Arc::new(Barrier::new(2));
+    let (destination_handle, destination_addr, received) =
+        spawn_destination(Status::SUCCESS, Some(Arc::clone(&gate))).unwrap();
+    let (scp_handle, scp_addr) = spawn_scp(destination_addr).unwrap();
+    let mut association = establish_move(scp_addr);
+    let ts = IMPLICIT_VR_LITTLE_ENDIAN.erased();
+
+    let msg = DimseMessage::new(1, CMoveRq::new(1, PATIENT_ROOT_MOVE, DESTINATION_AE_TITLE))
+        .with_dataset(&patient("P1"), &ts)
+        .unwrap();
+    association.send_dimse(&msg).unwrap();
+    // cancel while the first instance is being stored
+    association
+        .send_dimse(&DimseMessage::new(1, CCancelRq::new(1)))
+        .unwrap();
+    gate.wait();
+
+    let rsp = CMoveRsp::try_from(association.receive_dimse().unwrap().command).unwrap();
+    assert_eq!(rsp.status, Status::CANCEL);
+    assert_eq!(rsp.sub_operations.remaining, Some(1));
+    assert_eq!(rsp.sub_operations.completed, Some(1));
+    assert_eq!(rsp.sub_operations.failed, Some(0));
+    destination_handle.join().unwrap().unwrap();
+    assert_eq!(*received.lock().unwrap(), vec!["2.25.1"]);
+
+    association.release().unwrap();
+    scp_handle.join().unwrap().unwrap();
+}
+
+/// A C-MOVE is stopped by a release request between sub-operations,
+/// still getting a final response before the association is released.
+#[test]
+fn scp_move_release() {
+    let gate = Arc::new(Barrier::new(2));
+    let (destination_handle, destination_addr, received) =
+        spawn_destination(Status::SUCCESS, Some(Arc::clone(&gate))).unwrap();
+    let (scp_handle, scp_addr) = spawn_scp(destination_addr).unwrap();
+    let mut association = establish_move(scp_addr);
+    let ts = IMPLICIT_VR_LITTLE_ENDIAN.erased();
+
+    let msg = DimseMessage::new(1, CMoveRq::new(1, PATIENT_ROOT_MOVE, DESTINATION_AE_TITLE))
+        .with_dataset(&patient("P1"), &ts)
+        .unwrap();
+    association.send_dimse(&msg).unwrap();
+    // release while the first instance is being stored
+    association.send(&Pdu::ReleaseRQ).unwrap();
+    gate.wait();
+
+    let rsp = CMoveRsp::try_from(association.receive_dimse().unwrap().command).unwrap();
+    assert_eq!(rsp.status, Status::CANCEL);
+    assert_eq!(rsp.sub_operations.remaining, Some(1));
+    assert_eq!(rsp.sub_operations.completed, Some(1));
+    assert_eq!(association.receive().unwrap(), Pdu::ReleaseRP);
+    destination_handle.join().unwrap().unwrap();
+    assert_eq!(*received.lock().unwrap(), vec!["2.25.1"]);
+    scp_handle.join().unwrap().unwrap();
+}
+
+/// Requests sent while a C-MOVE is in progress
+/// are served once it is complete.
+#[test]
+fn scp_move_defers_other_requests() {
+    let gate = Arc::new(Barrier::new(2));
+    let (destination_handle, destination_addr, _received) =
+        spawn_destination(Status::SUCCESS, Some(Arc::clone(&gate))).unwrap();
+    let (scp_handle, scp_addr) = spawn_scp(destination_addr).unwrap();
+    let mut association = establish_move(scp_addr);
+    let ts = IMPLICIT_VR_LITTLE_ENDIAN.erased();
+
+    let msg = DimseMessage::new(1, CMoveRq::new(1, PATIENT_ROOT_MOVE, DESTINATION_AE_TITLE))
+        .with_dataset(&patient("P1"), &ts)
+        .unwrap();
+    association.send_dimse(&msg).unwrap();
+    association
+        .send_dimse(&DimseMessage::new(3, CEchoRq::new(2)))
+        .unwrap();
+    gate.wait();
+
+    let mut statuses = Vec::new();
+    loop {
+        let msg = association.receive_dimse().unwrap();
+        match msg.command {
+            Command::CMoveRsp(rsp) => statuses.push(rsp.status),
+            Command::CEchoRsp(rsp) => {
+                assert_eq!(rsp.message_id_being_responded_to, 2);
+                assert_eq!(rsp.status, Status::SUCCESS);
+                break;
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+    assert_eq!(statuses, vec![Status::PENDING, Status::SUCCESS]);
+    destination_handle.join().unwrap().unwrap();
+
+    association.release().unwrap();
+    scp_handle.join().unwrap().unwrap();
+}
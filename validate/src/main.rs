@@ -0,0 +1,94 @@
+//! A CLI tool for checking the attributes of DICOM files
+//! against the information object definitions (IODs)
+//! of their SOP classes.
+//!
+//! Only the few IODs in the standard dictionary are known.
+use std::path::PathBuf;
+
+use clap::Parser;
+use dicom_object::open_file;
+use dicom_object::validate::{validate, Severity};
+use snafu::Report;
+
+/// Exit code for when some files have errors against their IOD
+const ERROR_INVALID: i32 = 1;
+/// Exit code for when some files could not be read or validated
+const ERROR_READ: i32 = -2;
+
+/// Check DICOM files against the IOD of their SOP class
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// The DICOM files to validate
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Only report errors, not warnings
+    #[arg(short = 'q', long = "quiet")]
+    quiet: bool,
+
+    /// Also fail if warnings are found
+    #[arg(long = "strict", conflicts_with = "quiet")]
+    strict: bool,
+}
+
+fn main() {
+    let App {
+        files,
+        quiet,
+        strict,
+    } = App::parse();
+
+    let mut exit_code = 0;
+    for path in &files {
+        let obj = match open_file(path) {
+            Ok(obj) => obj,
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), Report::from_error(e));
+                exit_code = ERROR_READ;
+                continue;
+            }
+        };
+        let report = match validate(&obj) {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), Report::from_error(e));
+                exit_code = ERROR_READ;
+                continue;
+            }
+        };
+
+        let errors = report.errors().count();
+        let warnings = report.warnings().count();
+        println!(
+            "{}: {} IOD, {} error(s), {} warning(s)",
+            path.display(),
+            report.iod().name,
+            errors,
+            warnings,
+        );
+        for issue in report.issues() {
+            if quiet && issue.severity == Severity::Warning {
+                continue;
+            }
+            println!("  {}", issue);
+        }
+
+        if (errors > 0 || (strict && warnings > 0)) && exit_code == 0 {
+            exit_code = ERROR_INVALID;
+        }
+    }
+
+    std::process::exit(exit_code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}